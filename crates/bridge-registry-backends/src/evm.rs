@@ -0,0 +1,92 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use ethereum_types::Address;
+use webb::evm::ethers::abi::parse_abi;
+use webb::evm::ethers::contract::Contract;
+use webb_proposals::ResourceId;
+use webb_relayer_types::EthersClient;
+
+/// The subset of the Registry contract ABI used by the relayer.
+///
+/// Bridge indices start from `1`, an index of `0` means that the resource
+/// is not registered in any bridge.
+const REGISTRY_ABI: &[&str] = &[
+    "function nextBridgeIndex() external view returns (uint32)",
+    "function resourceToBridgeIndex(bytes32 resourceId) external view returns (uint32)",
+    "function getBridgeResources(uint32 bridgeIndex) external view returns (bytes32[])",
+];
+
+/// A BridgeRegistryBackend that reads the bridges from a Registry contract
+/// deployed on an EVM chain.
+#[derive(Clone, Debug)]
+pub struct EvmBridgeRegistryBackend {
+    contract: Contract<EthersClient>,
+}
+
+impl EvmBridgeRegistryBackend {
+    /// Creates a new backend for the Registry contract at `address`.
+    pub fn new(address: Address, client: Arc<EthersClient>) -> Self {
+        let abi = parse_abi(REGISTRY_ABI).expect("registry abi is valid");
+        Self {
+            contract: Contract::new(address, abi, client),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl super::BridgeRegistryBackend for EvmBridgeRegistryBackend {
+    async fn next_bridge_index(&self) -> webb_relayer_utils::Result<u32> {
+        let index = self
+            .contract
+            .method::<_, u32>("nextBridgeIndex", ())?
+            .call()
+            .await?;
+        Ok(index)
+    }
+
+    async fn resource_to_bridge_index(
+        &self,
+        resource_id: &ResourceId,
+    ) -> webb_relayer_utils::Result<Option<u32>> {
+        let index = self
+            .contract
+            .method::<_, u32>("resourceToBridgeIndex", resource_id.to_bytes())?
+            .call()
+            .await?;
+        Ok((index != 0).then_some(index))
+    }
+
+    async fn bridges(
+        &self,
+        index: u32,
+    ) -> webb_relayer_utils::Result<Option<Vec<ResourceId>>> {
+        let resources = self
+            .contract
+            .method::<_, Vec<[u8; 32]>>("getBridgeResources", index)?
+            .call()
+            .await?;
+        if resources.is_empty() {
+            return Ok(None);
+        }
+        tracing::trace!(
+            bridge_index = %index,
+            resources = resources.len(),
+            "Found bridge in the registry contract",
+        );
+        Ok(Some(resources.into_iter().map(ResourceId::from).collect()))
+    }
+}
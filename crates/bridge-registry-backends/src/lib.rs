@@ -0,0 +1,100 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![deny(unsafe_code)]
+#![warn(missing_docs)]
+//! # Relayer Bridge Registry Backends 🕸️
+//!
+//! ## Overview
+//! This crate contains the bridge registry backends for the relayer.
+//! A bridge registry is used to find out which anchors are linked together
+//! as part of the same bridge. There are two types of bridge registry backends:
+//! - `MockedBridgeRegistryBackend`: Only uses the linked anchors from the relayer config.
+//! - `EvmBridgeRegistryBackend`: Reads the bridge topology from a Registry contract
+//!   deployed on an EVM chain, so it can be managed on-chain.
+
+use webb_proposals::ResourceId;
+use webb_relayer_config::anchor::LinkedAnchorConfig;
+use webb_relayer_utils::Error;
+
+#[doc(hidden)]
+pub mod evm;
+
+#[doc(hidden)]
+pub mod mocked;
+
+/// A module that Implements the EVM Registry contract backend.
+pub use evm::*;
+/// A module that Implements the Mocked Bridge Registry Backend.
+pub use mocked::*;
+
+/// A Bridge Registry Backend is responsible for resolving the set of anchors
+/// that are linked to a given anchor, identified by its [`ResourceId`].
+///
+/// As of now, we have two implementations of this trait:
+///
+/// - `MockedBridgeRegistryBackend`: Only the linked anchors from the config are used.
+/// - `EvmBridgeRegistryBackend`: This is using a Registry contract on an EVM chain.
+#[async_trait::async_trait]
+pub trait BridgeRegistryBackend: Send + Sync {
+    /// Returns the index that will be assigned to the next registered bridge.
+    async fn next_bridge_index(&self) -> webb_relayer_utils::Result<u32>;
+    /// Returns the index of the bridge the given resource id is registered in,
+    /// or `None` if it is not part of any bridge.
+    async fn resource_to_bridge_index(
+        &self,
+        resource_id: &ResourceId,
+    ) -> webb_relayer_utils::Result<Option<u32>>;
+    /// Returns all the resource ids registered in the bridge with the given index,
+    /// or `None` if there is no such bridge.
+    async fn bridges(
+        &self,
+        index: u32,
+    ) -> webb_relayer_utils::Result<Option<Vec<ResourceId>>>;
+
+    /// Returns the resource ids of the anchors linked to `resource_id`.
+    ///
+    /// The linked anchors from the config take precedence, if they are not
+    /// configured, the registry is queried instead.
+    async fn config_or_registry_bridges(
+        &self,
+        linked_anchors: &Option<Vec<LinkedAnchorConfig>>,
+        resource_id: &ResourceId,
+    ) -> webb_relayer_utils::Result<Vec<ResourceId>> {
+        match linked_anchors {
+            Some(linked_anchors) => {
+                let resource_ids = linked_anchors
+                    .iter()
+                    .map(LinkedAnchorConfig::to_resource_id)
+                    .collect();
+                Ok(resource_ids)
+            }
+            None => {
+                let index = self
+                    .resource_to_bridge_index(resource_id)
+                    .await?
+                    .ok_or(Error::BridgeNotRegistered(*resource_id))?;
+                let resource_ids = self
+                    .bridges(index)
+                    .await?
+                    .ok_or(Error::BridgeNotRegistered(*resource_id))?;
+                // the bridge also contains the anchor itself, skip it.
+                Ok(resource_ids
+                    .into_iter()
+                    .filter(|r| r != resource_id)
+                    .collect())
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use webb_proposals::ResourceId;
+
+/// A BridgeRegistryBackend that does not know about any bridge, hence
+/// only the linked anchors from the config are used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockedBridgeRegistryBackend;
+
+#[async_trait::async_trait]
+impl super::BridgeRegistryBackend for MockedBridgeRegistryBackend {
+    async fn next_bridge_index(&self) -> webb_relayer_utils::Result<u32> {
+        Ok(0)
+    }
+
+    async fn resource_to_bridge_index(
+        &self,
+        _resource_id: &ResourceId,
+    ) -> webb_relayer_utils::Result<Option<u32>> {
+        Ok(None)
+    }
+
+    async fn bridges(
+        &self,
+        _index: u32,
+    ) -> webb_relayer_utils::Result<Option<Vec<ResourceId>>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BridgeRegistryBackend;
+    use ethereum_types::H256;
+    use webb_relayer_config::anchor::{LinkedAnchorConfig, RawResourceId};
+
+    #[tokio::test]
+    async fn should_use_linked_anchors_from_config() {
+        let backend = MockedBridgeRegistryBackend;
+        let src = ResourceId::from([1u8; 32]);
+        let target = ResourceId::from([2u8; 32]);
+        let linked_anchors =
+            Some(vec![LinkedAnchorConfig::Raw(RawResourceId {
+                resource_id: H256::from(target.to_bytes()),
+            })]);
+        let resolved = backend
+            .config_or_registry_bridges(&linked_anchors, &src)
+            .await
+            .unwrap();
+        assert_eq!(resolved, vec![target]);
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_bridge_is_not_registered() {
+        let backend = MockedBridgeRegistryBackend;
+        let src = ResourceId::from([1u8; 32]);
+        let result = backend.config_or_registry_bridges(&None, &src).await;
+        assert!(matches!(
+            result,
+            Err(webb_relayer_utils::Error::BridgeNotRegistered(_))
+        ));
+    }
+}
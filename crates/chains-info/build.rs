@@ -42,6 +42,10 @@ struct ChainOverride {
     native_currency_symbol: Option<String>,
     native_currency_decimals: Option<u8>,
     coingecko_coin_id: Option<String>,
+    /// The rollup stack of the chain, if it is an L2.
+    rollup: Option<String>,
+    /// The average block time of the chain, in milliseconds.
+    block_time: Option<u64>,
 }
 
 fn generate_chains_info(
@@ -52,11 +56,18 @@ fn generate_chains_info(
         .into_iter()
         .filter(|chain| supported_chains.ids.contains(&chain.chain_id))
         .map(|chain| {
-            supported_chains
-                .overrides
-                .get(&chain.chain_id.to_string())
+            let overrides =
+                supported_chains.overrides.get(&chain.chain_id.to_string());
+            let rollup = overrides.and_then(|v| v.rollup.clone());
+            let block_time = overrides.and_then(|v| v.block_time);
+            let chain = overrides
                 .cloned()
-                .map_or(chain.clone(), |v| chain.overrides_with(v))
+                .map_or(chain.clone(), |v| chain.overrides_with(v));
+            chains::Chain {
+                rollup,
+                block_time,
+                ..chain
+            }
         })
         .collect::<Vec<_>>();
     // sort the chains by the chain identifier
@@ -88,6 +99,21 @@ fn generate_chains_info(
             pub short_name: &'static str,
             /// Chain Native Currency Information.
             pub native_currency: CurrencyInfo,
+            /// The rollup stack of the chain, `None` if it is not an L2.
+            pub rollup: Option<Rollup>,
+            /// The average block time of the chain, in milliseconds, `None`
+            /// if it is not known.
+            pub block_time: Option<u64>,
+        }
+
+        /// The rollup stack of an L2 chain, which decides how its L1 data
+        /// fee is priced.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Rollup {
+            /// OP Stack (Optimism, Base), priced by the `GasPriceOracle` predeploy.
+            Optimism,
+            /// Arbitrum Nitro, priced by the `ArbGasInfo` precompile.
+            Arbitrum,
         }
 
         /// The Currency Information.
@@ -151,6 +177,18 @@ fn bake_simple_chains_info(
         } else {
             quote::quote! { None }
         };
+        let rollup = match chain.rollup.as_deref() {
+            None => quote::quote! { None },
+            Some("optimism") => quote::quote! { Some(Rollup::Optimism) },
+            Some("arbitrum") => quote::quote! { Some(Rollup::Arbitrum) },
+            Some(other) => {
+                panic!("unknown rollup `{other}` of chain {chain_id}")
+            }
+        };
+        let block_time = match chain.block_time {
+            Some(block_time) => quote::quote! { Some(#block_time) },
+            None => quote::quote! { None },
+        };
         let token = quote::quote! {
             (
                 #chain_id,
@@ -164,6 +202,8 @@ fn bake_simple_chains_info(
                         decimals: #native_currency_decimals,
                         coingecko_coin_id: #coingecko_coin_id,
                     },
+                    rollup: #rollup,
+                    block_time: #block_time,
                 }
             ),
         };
@@ -199,6 +239,12 @@ mod chains {
         /// Used internally to override the chain's coingecko coin id.
         #[serde(skip)]
         pub coingecko_coin_id: Option<String>,
+        /// Used internally to set the chain's rollup stack.
+        #[serde(skip)]
+        pub rollup: Option<String>,
+        /// Used internally to set the chain's block time.
+        #[serde(skip)]
+        pub block_time: Option<u64>,
     }
     impl Chain {
         pub(crate) fn overrides_with(
@@ -6,6 +6,8 @@
 
 include!(concat!(env!("OUT_DIR"), "/chains.rs"));
 
+pub use chains::Rollup;
+
 /// Get the chains information.
 #[must_use]
 #[inline]
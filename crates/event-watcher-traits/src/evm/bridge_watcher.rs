@@ -15,6 +15,7 @@
 use super::{event_watcher::EventWatcher, *};
 use tokio::sync::Mutex;
 use webb_relayer_types::EthersTimeLagClient;
+use webb_relayer_utils::leader::Leadership;
 
 /// A Bridge Watcher is a trait for Bridge contracts that not specific for watching events from that contract,
 /// instead it watches for commands sent from other event watchers or services, it helps decouple the event watchers
@@ -39,6 +40,8 @@ where
 
     /// Returns a task that should be running in the background
     /// that will watch for all commands
+    ///
+    /// Commands are only handled while this relayer is the leader.
     #[tracing::instrument(
         skip_all,
         fields(
@@ -52,6 +55,7 @@ where
         store: Arc<Self::Store>,
         contract: Self::Contract,
         metrics: Arc<Mutex<metric::Metrics>>,
+        leadership: Leadership,
     ) -> webb_relayer_utils::Result<()> {
         let backoff = backoff::backoff::Constant::new(Duration::from_secs(1));
         let task = || async {
@@ -65,6 +69,12 @@ where
             let bridge_key = BridgeKey::new(typed_chain_id);
             let key = SledQueueKey::from_bridge_key(bridge_key);
             loop {
+                webb_relayer_utils::heartbeat::beat();
+                if !leadership.is_leader() {
+                    // followers leave the commands to the leader.
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
                 let result = match store.dequeue_item(key)? {
                     Some(item) => {
                         self.handle_cmd(store.clone(), &contract, item.inner())
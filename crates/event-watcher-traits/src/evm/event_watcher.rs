@@ -15,7 +15,18 @@
 use super::*;
 use tokio::sync::Mutex;
 use webb_relayer_types::EthersTimeLagClient;
-use webb_relayer_utils::retry;
+use webb_relayer_utils::{heartbeat, retry};
+
+/// How many blocks back the processed event hashes are kept for deduplication.
+pub const EVENT_DEDUP_WINDOW_BLOCKS: u64 = 100_000;
+/// How many blocks the chain should advance before pruning the event hashes again.
+const EVENT_PRUNE_INTERVAL_BLOCKS: u64 = 1_000;
+/// How many handlers go through the events of a contract at the same time.
+const MAX_CONCURRENT_HANDLERS: usize = 8;
+/// The shortest polling interval of an adaptive watcher.
+const MIN_POLLING_INTERVAL: Duration = Duration::from_millis(250);
+/// The longest polling interval of an adaptive watcher.
+const MAX_POLLING_INTERVAL: Duration = Duration::from_secs(60);
 
 /// A watchable contract is a contract used in the [EventWatcher]
 pub trait WatchableContract: Send + Sync {
@@ -25,6 +36,13 @@ pub trait WatchableContract: Send + Sync {
     /// How often this contract should be polled for events.
     fn polling_interval(&self) -> Duration;
 
+    /// Whether the polling interval should be adapted to the block time of
+    /// the chain, the [`Self::polling_interval`] is then used until it is
+    /// known.
+    fn adaptive_polling(&self) -> bool {
+        false
+    }
+
     /// How many events to fetch at one request.
     fn max_blocks_per_step(&self) -> types::U64;
 
@@ -73,8 +91,9 @@ pub trait EventWatcher {
         ctx: &RelayerContext,
     ) -> webb_relayer_utils::Result<()> {
         let backoff = backoff::backoff::Constant::new(Duration::from_secs(1));
+        let handler_ids = handler_ids::<Self>(&handlers);
         let task = || async {
-            let step = contract.max_blocks_per_step().as_u64();
+            let max_step = contract.max_blocks_per_step().as_u64().max(1);
             let metrics = &ctx.metrics;
             let chain_id: u32 = client
                 .inner()
@@ -91,9 +110,19 @@ pub trait EventWatcher {
             let src_typed_chain_id = TypedChainId::Evm(chain_id);
             let history_store_key =
                 ResourceId::new(src_target_system, src_typed_chain_id);
+            let circuit_breaker = ctx.circuit_breaker(chain_id);
+            let sync_lock = ctx.sync_lock(history_store_key).await;
+            // the block range of each query, it shrinks when the node refuses
+            // it and slowly grows back to the configured one.
+            let mut step = store
+                .get_blocks_per_step(history_store_key)?
+                .unwrap_or(max_step)
+                .clamp(1, max_step);
 
             // saves the last time we printed sync progress.
             let mut instant = std::time::Instant::now();
+            // the block number at which we last pruned the event hashes.
+            let mut last_pruned_at = 0u64;
             // we only query this once, at the start of the events watcher.
             // then we will update it later once we fully synced.
 
@@ -110,8 +139,27 @@ pub trait EventWatcher {
                 history_store_key,
                 target_block_number,
             )?;
+            let mut polling_interval =
+                PollingInterval::new(&contract, chain_id);
+            polling_interval.observe(target_block_number);
 
             loop {
+                heartbeat::beat();
+                // Pause the watcher while the chain is disabled at runtime.
+                if ctx.is_chain_disabled(chain_id) {
+                    heartbeat::sleep(polling_interval.get()).await;
+                    continue;
+                }
+                // Pause the watcher while the chain's circuit breaker is open.
+                if let Some(cb) = &circuit_breaker {
+                    if !cb.is_request_allowed() {
+                        heartbeat::sleep(polling_interval.get()).await;
+                        continue;
+                    }
+                }
+                // held until the step moved the block pointer, so a resync
+                // does not race with it.
+                let sync_guard = sync_lock.lock().await;
                 let block = store.get_last_block_number(
                     history_store_key,
                     contract.deployed_at().as_u64(),
@@ -123,76 +171,125 @@ pub trait EventWatcher {
                     .event_with_filter::<Self::Events>(Default::default())
                     .from_block(block + 1)
                     .to_block(dest_block);
-                let found_events = events_filter
-                    .query_with_meta()
-                    .map_err(Into::into)
-                    .map_err(backoff::Error::transient)
-                    .await?;
+                let found_events = match events_filter.query_with_meta().await {
+                    Ok(found_events) => {
+                        if let Some(cb) = &circuit_breaker {
+                            cb.record_success();
+                        }
+                        if step < max_step {
+                            step = (step + (step / 10).max(1)).min(max_step);
+                            store
+                                .set_blocks_per_step(history_store_key, step)?;
+                        }
+                        found_events
+                    }
+                    Err(e) if step > 1 && is_query_too_large(&e) => {
+                        step = (step / 2).max(1);
+                        store.set_blocks_per_step(history_store_key, step)?;
+                        tracing::warn!(
+                            %chain_id,
+                            step,
+                            error = %e,
+                            "Events query too large, shrinking the block range",
+                        );
+                        continue;
+                    }
+                    Err(e) => {
+                        if let Some(cb) = &circuit_breaker {
+                            cb.record_failure();
+                        }
+                        return Err(backoff::Error::transient(e.into()));
+                    }
+                };
 
                 let number_of_events = found_events.len();
                 tracing::trace!("Found #{number_of_events} events");
-                for (event, log) in found_events {
-                    // wraps each handler future in a retry logic, that will retry the handler
-                    // if it fails, up to `MAX_RETRY_COUNT`, after this it will ignore that event for
-                    // that specific handler.
-                    const MAX_RETRY_COUNT: usize = 5;
-                    let tasks = handlers.iter().map(|handler| {
-                        // a constant backoff with maximum retry count is used here.
-                        let backoff = retry::ConstantWithMaxRetryCount::new(
-                            Duration::from_millis(100),
-                            MAX_RETRY_COUNT,
-                        );
-                        handler.handle_event_with_retry(
-                            store.clone(),
+                // every handler goes through the events in order, so each of
+                // them sees the events of the contract in order, while a slow
+                // handler (e.g. signing proposals) does not hold back the
+                // others (e.g. caching leaves). a handler stops at the first
+                // event it fails to handle, `stopped_at` keeps its index.
+                let mut stopped_at: Vec<Option<usize>> =
+                    vec![None; handlers.len()];
+                let mut resume: Vec<_> =
+                    (0..handlers.len()).map(|h| (h, 0)).collect();
+                loop {
+                    let pipelines = resume.iter().map(|&(h, from)| {
+                        handle_events_in_order(
+                            &handlers[h],
+                            &handler_ids[h],
+                            &store,
+                            history_store_key,
                             &contract,
-                            (event.clone(), log.clone()),
-                            backoff,
-                            metrics.clone(),
+                            &found_events,
+                            from,
+                            metrics,
                         )
+                        .map(move |result| (h, result))
                     });
-                    let result = futures::future::join_all(tasks).await;
-                    // this event will be marked as handled if at least one handler succeeded.
-                    // this because, for the failed events, we arleady tried to handle them
-                    // many times (at this point), and there is no point in trying again.
-                    let mark_as_handled = result.iter().any(Result::is_ok);
-                    // also, for all the failed event handlers, we should print what went
-                    // wrong.
-                    result.iter().for_each(|r| {
-                            if let Err(e) = r {
-                                tracing::error!(?e, %chain_id, "Error while handling the event");
-                            }
+                    let results: Vec<_> = futures::stream::iter(pipelines)
+                        .buffered(MAX_CONCURRENT_HANDLERS)
+                        .collect()
+                        .await;
+                    for (h, result) in results {
+                        let failed =
+                            result.map_err(backoff::Error::transient)?;
+                        stopped_at[h] = failed.map(|(i, e)| {
+                            tracing::error!(?e, %chain_id, "Error while handling the event");
+                            i
                         });
-                    if mark_as_handled {
+                    }
+                    // the earliest event a handler stopped at.
+                    let Some(i) = stopped_at.iter().flatten().min().copied()
+                    else {
+                        break;
+                    };
+                    let log = &found_events[i].1;
+                    if stopped_at.iter().all(|at| *at == Some(i)) {
+                        tracing::error!(%chain_id, "Error while handling event, all handlers failed.");
+                        // none of the handlers went past this event, and
+                        // they saved how far they got, so the watcher
+                        // restarts from its block without replaying the
+                        // events they already went through.
                         store.set_last_block_number(
                             history_store_key,
-                            log.block_number.as_u64(),
+                            log.block_number.as_u64().saturating_sub(1),
                         )?;
-                        tracing::trace!(
-                            %chain_id,
-                            %log.block_number,
-                            "event handled successfully",
-                        );
-                    } else {
-                        tracing::error!(%chain_id, "Error while handling event, all handlers failed.");
                         tracing::warn!(%chain_id, "Restarting event watcher ...");
                         // this a transient error, so we will retry again.
                         return Err(backoff::Error::transient(
                             webb_relayer_utils::Error::ForceRestart,
                         ));
                     }
+                    // this event is marked as handled since other handlers
+                    // succeeded, the failed ones already tried to handle it
+                    // many times (at this point), and there is no point in
+                    // trying again. they resume after it.
+                    tracing::trace!(
+                        %chain_id,
+                        %log.block_number,
+                        "event handled successfully",
+                    );
+                    resume = stopped_at
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, at)| **at == Some(i))
+                        .map(|(h, _)| (h, i + 1))
+                        .collect();
                 }
 
                 // move the block pointer to the destination block
                 store.set_last_block_number(history_store_key, dest_block)?;
+                drop(sync_guard);
                 // if we fully synced, we can update the target block number
                 let should_cooldown = dest_block == target_block_number;
                 if should_cooldown {
-                    let duration = contract.polling_interval();
+                    let duration = polling_interval.get();
                     tracing::trace!(
                         "Cooldown a bit for {}ms",
                         duration.as_millis()
                     );
-                    tokio::time::sleep(duration).await;
+                    heartbeat::sleep(duration).await;
                     // update the latest block number
                     target_block_number = client
                         .get_block_number()
@@ -200,10 +297,22 @@ pub trait EventWatcher {
                         .map_err(backoff::Error::transient)
                         .await?
                         .as_u64();
+                    polling_interval.observe(target_block_number);
                     store.set_target_block_number(
                         history_store_key,
                         target_block_number,
                     )?;
+                    // drop the event hashes that fell out of the dedup window.
+                    if target_block_number.saturating_sub(last_pruned_at)
+                        >= EVENT_PRUNE_INTERVAL_BLOCKS
+                    {
+                        store.prune_events(
+                            history_store_key,
+                            target_block_number
+                                .saturating_sub(EVENT_DEDUP_WINDOW_BLOCKS),
+                        )?;
+                        last_pruned_at = target_block_number;
+                    }
                 }
 
                 if contract.print_progress_interval()
@@ -243,6 +352,172 @@ pub trait EventWatcher {
         Ok(())
     }
 }
+
+/// Whether the events query failed because its block range is too large for
+/// the node, either by returning too many logs or by timing out.
+fn is_query_too_large(error: &impl std::fmt::Display) -> bool {
+    const PATTERNS: &[&str] = &[
+        "more than",
+        "too many",
+        "limit exceeded",
+        "size exceeded",
+        "block range",
+        "timeout",
+        "timed out",
+    ];
+    let message = error.to_string().to_lowercase();
+    PATTERNS.iter().any(|pattern| message.contains(pattern))
+}
+
+/// The polling interval of a watcher, adapted to the block time of its
+/// chain if enabled.
+///
+/// The block time starts from the one known for the chain, if any, and then
+/// follows the time measured between the new blocks seen by the watcher.
+struct PollingInterval {
+    configured: Duration,
+    adaptive: bool,
+    block_time: Option<Duration>,
+    /// The last new block seen by the watcher, and when.
+    last_block: Option<(u64, std::time::Instant)>,
+}
+
+impl PollingInterval {
+    fn new(contract: &impl WatchableContract, chain_id: u32) -> Self {
+        let block_time =
+            webb_chains_info::chain_info_by_chain_id(u64::from(chain_id))
+                .and_then(|info| info.block_time)
+                .map(Duration::from_millis);
+        Self {
+            configured: contract.polling_interval(),
+            adaptive: contract.adaptive_polling(),
+            block_time,
+            last_block: None,
+        }
+    }
+
+    /// Returns how long to wait before polling again.
+    fn get(&self) -> Duration {
+        match self.block_time {
+            Some(block_time) if self.adaptive => {
+                block_time.clamp(MIN_POLLING_INTERVAL, MAX_POLLING_INTERVAL)
+            }
+            _ => self.configured,
+        }
+    }
+
+    /// Records the latest block number of the chain.
+    fn observe(&mut self, block_number: u64) {
+        let now = std::time::Instant::now();
+        match self.last_block {
+            Some((last, at)) if block_number > last => {
+                let blocks =
+                    u32::try_from(block_number - last).unwrap_or(u32::MAX);
+                let sample = now.duration_since(at) / blocks;
+                // smooth the samples, so a single slow block does not slow
+                // the watcher down.
+                self.block_time = Some(match self.block_time {
+                    Some(block_time) => (block_time * 4 + sample) / 5,
+                    None => sample,
+                });
+                self.last_block = Some((block_number, now));
+            }
+            Some(_) => {}
+            None => self.last_block = Some((block_number, now)),
+        }
+    }
+}
+
+/// Handles the events, in order, with the given handler, from the event at
+/// `from`, until it fails to handle one of them.
+///
+/// Each event is retried up to `MAX_RETRY_COUNT` times, after this the
+/// handler stops and the index of the event is returned with its error. The
+/// handler is flushed once it stops, and the position of the last event it
+/// went through is saved, so it skips the events it already went through if
+/// the watcher restarts before them.
+#[allow(clippy::too_many_arguments)]
+async fn handle_events_in_order<W: EventWatcher + ?Sized>(
+    handler: &EventHandlerFor<W>,
+    handler_id: &str,
+    store: &Arc<W::Store>,
+    history_store_key: ResourceId,
+    contract: &W::Contract,
+    events: &[(W::Events, contract::LogMeta)],
+    from: usize,
+    metrics: &Arc<Mutex<metric::Metrics>>,
+) -> webb_relayer_utils::Result<Option<(usize, webb_relayer_utils::Error)>> {
+    const MAX_RETRY_COUNT: usize = 5;
+    let checkpoint =
+        store.get_handler_checkpoint(history_store_key, handler_id)?;
+    // the events before `from` were gone through already.
+    let mut last_position = from
+        .checked_sub(1)
+        .and_then(|i| events.get(i))
+        .map(|(_, log)| event_position(log));
+    let mut failed = None;
+    for (i, (event, log)) in events.iter().enumerate().skip(from) {
+        let position = event_position(log);
+        if checkpoint.map_or(false, |checkpoint| position <= checkpoint) {
+            continue;
+        }
+        // a constant backoff with maximum retry count is used here.
+        let backoff = retry::ConstantWithMaxRetryCount::new(
+            Duration::from_millis(100),
+            MAX_RETRY_COUNT,
+        );
+        let result = handler
+            .handle_event_with_retry(
+                store.clone(),
+                contract,
+                (event.clone(), log.clone()),
+                backoff,
+                metrics.clone(),
+            )
+            .await;
+        if let Err(e) = result {
+            failed = Some((i, e));
+            break;
+        }
+        last_position = Some(position);
+    }
+    // the handlers may buffer their writes, so they are flushed before the
+    // checkpoint moves past their events.
+    handler.flush(store.clone()).await?;
+    if let Some(position) = last_position {
+        if checkpoint.map_or(true, |checkpoint| position > checkpoint) {
+            store.set_handler_checkpoint(
+                history_store_key,
+                handler_id,
+                position,
+            )?;
+        }
+    }
+    Ok(failed)
+}
+
+/// The position of an event in the chain: its block number and log index.
+fn event_position(log: &contract::LogMeta) -> (u64, u64) {
+    (log.block_number.as_u64(), log.log_index.as_u64())
+}
+
+/// The ids the checkpoints of the handlers are saved under: their names,
+/// numbered if several handlers have the same name.
+fn handler_ids<W: EventWatcher + ?Sized>(
+    handlers: &[EventHandlerFor<W>],
+) -> Vec<String> {
+    let mut ids = Vec::with_capacity(handlers.len());
+    for (i, handler) in handlers.iter().enumerate() {
+        let name = handler.name();
+        let same_name = handlers[..i].iter().filter(|h| h.name() == name);
+        match same_name.count() {
+            0 => ids.push(name.to_owned()),
+            n => ids.push(format!("{name}#{n}")),
+        }
+    }
+    ids
+}
+
 /// A trait that defines a handler for a specific set of event types.
 ///
 /// The handlers are implemented separately from the watchers, so that we can have
@@ -280,6 +555,25 @@ pub trait EventHandler {
         (event, log): (Self::Events, contract::LogMeta),
         wrapper: &Self::Contract,
     ) -> webb_relayer_utils::Result<bool>;
+
+    /// The name the progress of the handler through the events is saved
+    /// under, the type name of the handler by default.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Writes what the handler buffered while handling the events to the
+    /// store.
+    ///
+    /// The event watcher calls it before saving the progress of the
+    /// handler, so the writes of handlers that batch them are not lost on a
+    /// restart. Does nothing by default.
+    async fn flush(
+        &self,
+        _store: Arc<Self::Store>,
+    ) -> webb_relayer_utils::Result<()> {
+        Ok(())
+    }
 }
 
 /// An Auxiliary trait to handle events with retry logic.
@@ -20,6 +20,9 @@ use webb_relayer_utils::{metric, retry};
 
 use super::*;
 
+/// How long to wait for a new finalized block before polling anyway.
+const FINALIZED_HEAD_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// A type alias to extract the event handler type from the event watcher.
 pub type EventHandlerFor<W, RuntimeConfig> = Box<
     dyn EventHandler<
@@ -168,10 +171,16 @@ where
                     return Err(backoff::Error::transient(err));
                 }
             };
-            let client = Arc::new(client);
+            let mut client = Arc::new(client);
+            // we follow the finalized blocks, so we know when to poll again.
+            let mut finalized_heads = client
+                .rpc()
+                .subscribe_finalized_block_headers()
+                .map_err(Into::into)
+                .map_err(backoff::Error::transient)
+                .await?;
             let mut instant = std::time::Instant::now();
             let step = 1u64;
-            let rpc = client.rpc();
             // get pallet index
             let pallet_index = {
                 let metadata = client.metadata();
@@ -193,15 +202,23 @@ where
                 ResourceId::new(src_target_system, src_typed_chain_id);
 
             loop {
+                webb_relayer_utils::heartbeat::beat();
+                // Pause the watcher while the chain is disabled at runtime.
+                if ctx.is_chain_disabled(chain_id) {
+                    tokio::time::sleep(Duration::from_secs(6)).await;
+                    continue;
+                }
                 // now we start polling for new events.
                 // get the current latest block number.
-                let latest_head = rpc
+                let latest_head = client
+                    .rpc()
                     .finalized_head()
                     .map_err(Into::into)
                     .map_err(backoff::Error::transient)
                     .await?;
 
-                let maybe_latest_header = rpc
+                let maybe_latest_header = client
+                    .rpc()
                     .header(Some(latest_head))
                     .map_err(Into::into)
                     .map_err(backoff::Error::transient)
@@ -242,7 +259,8 @@ where
                     // we need to query the node for the events that happened in the
                     // range [block, dest_block].
                     // so first we get the hash of the block we want to start from.
-                    let maybe_from = rpc
+                    let maybe_from = client
+                        .rpc()
                         .block_hash(Some(dest_block.into()))
                         .map_err(Into::into)
                         .map_err(backoff::Error::transient)
@@ -311,12 +329,49 @@ where
                 }
                 tracing::trace!("Polled from #{} to #{}", block, dest_block);
                 if should_cooldown {
-                    let duration = Duration::from_secs(6);
-                    tracing::trace!(
-                        "Cooldown a bit for {}ms",
-                        duration.as_millis()
-                    );
-                    tokio::time::sleep(duration).await;
+                    // wait for the next finalized block, falling back to
+                    // polling if none arrives in time.
+                    let next_head = tokio::time::timeout(
+                        FINALIZED_HEAD_TIMEOUT,
+                        finalized_heads.next(),
+                    )
+                    .await;
+                    match next_head {
+                        Ok(Some(Ok(_))) => {}
+                        Err(_) => {
+                            tracing::trace!(
+                                "No finalized block in {}ms, polling",
+                                FINALIZED_HEAD_TIMEOUT.as_millis()
+                            );
+                        }
+                        Ok(Some(Err(_))) | Ok(None) => {
+                            // the connection dropped, reconnect and resubscribe.
+                            // the missed blocks are backfilled from the last
+                            // stored block number in the next iterations.
+                            tracing::warn!(
+                                %chain_id,
+                                "Finalized blocks subscription dropped, reconnecting ...",
+                            );
+                            client = ctx
+                                .substrate_provider::<RuntimeConfig, _>(
+                                    chain_id,
+                                )
+                                .await
+                                .map(Arc::new)
+                                .map_err(backoff::Error::transient)?;
+                            finalized_heads = client
+                                .rpc()
+                                .subscribe_finalized_block_headers()
+                                .map_err(Into::into)
+                                .map_err(backoff::Error::transient)
+                                .await?;
+                            tracing::info!(
+                                %chain_id,
+                                from = dest_block,
+                                "Resubscribed to finalized blocks, backfilling missed blocks",
+                            );
+                        }
+                    }
                 }
 
                 let print_progress_interval = Duration::from_millis(
@@ -63,7 +63,9 @@ impl super::ProposalSigningBackend for DkgProposalSigningRulesBackend {
         );
 
         let typed_tx: TypedTransaction = call.tx;
-        let item = QueueItem::new(typed_tx.clone());
+        let mut item = QueueItem::new(typed_tx.clone());
+        // governance goes on once the daily gas budget is spent.
+        item.set_budget_exempt(true);
         let tx_key = SledQueueKey::from_evm_with_custom_key(
             self.src_chain_id,
             typed_tx.item_key(),
@@ -87,7 +87,11 @@ where
             signature = %hex::encode(&signature_bytes),
         );
         // Proposal signed metric
-        metrics.lock().await.proposals_signed.inc();
+        {
+            let mut metrics = metrics.lock().await;
+            metrics.proposals_signed.inc();
+            metrics.record_proposal_signed(hash);
+        }
         // now all we have to do is to send the data and the signature to the signature bridge.
         let item =
             QueueItem::new(BridgeCommand::ExecuteProposalWithSignature {
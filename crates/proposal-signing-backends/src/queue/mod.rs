@@ -3,7 +3,9 @@ use std::sync::{atomic, Arc};
 use tokio::sync::Mutex;
 use webb::evm::ethers;
 use webb_proposals::ProposalTrait;
+use webb_relayer_utils::leader::Leadership;
 use webb_relayer_utils::metric;
+use webb_relayer_utils::pause::PauseSwitch;
 
 /// A module for in-memory Proposals Queue.
 pub mod mem;
@@ -291,18 +293,34 @@ impl ProposalMetadata for QueuedAnchorUpdateProposal {
 ///
 /// This function will loop forever and should be run in a separate task.
 /// it will never end unless the task is cancelled.
+///
+/// Proposals are only dequeued while this relayer is the leader, followers
+/// keep them queued, ready to take over. They are kept queued as well while
+/// the relayer is paused.
 #[tracing::instrument(skip_all)]
 pub async fn run<Queue, Policy, PSB>(
     queue: Queue,
     dequeue_policy: Policy,
     proposal_signing_backend: PSB,
     metrics: Arc<Mutex<metric::Metrics>>,
+    leadership: Leadership,
+    pause: PauseSwitch,
 ) where
     Queue: ProposalsQueue,
     Policy: policy::ProposalPolicy + Clone,
     PSB: super::ProposalSigningBackend,
 {
     loop {
+        if !leadership.is_leader() {
+            tracing::trace!("Not the leader, skipping the proposals queue");
+            tokio::time::sleep(core::time::Duration::from_millis(1100)).await;
+            continue;
+        }
+        if pause.is_paused() {
+            tracing::trace!("Relayer paused, skipping the proposals queue");
+            tokio::time::sleep(core::time::Duration::from_millis(1100)).await;
+            continue;
+        }
         let proposal = match queue.dequeue(dequeue_policy.clone()) {
             Ok(Some(proposal)) => proposal,
             Ok(None) => {
@@ -469,6 +487,8 @@ mod tests {
             dequeue_policy,
             signing_backend.clone(),
             metrics,
+            Leadership::always(),
+            PauseSwitch::default(),
         ));
 
         let target_system = mock_target_system(ethers::types::Address::zero());
@@ -525,6 +545,8 @@ mod tests {
             dequeue_policy,
             signing_backend.clone(),
             metrics,
+            Leadership::always(),
+            PauseSwitch::default(),
         ));
 
         let target_system = mock_target_system(ethers::types::Address::zero());
@@ -594,6 +616,8 @@ mod tests {
             dequeue_policy,
             signing_backend.clone(),
             metrics,
+            Leadership::always(),
+            PauseSwitch::default(),
         ));
 
         let target_system = mock_target_system(ethers::types::Address::zero());
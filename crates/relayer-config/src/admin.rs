@@ -0,0 +1,37 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// AdminConfig controls the admin API of the relayer, used by operators to
+/// manage the relayer at runtime (for example, disabling a chain during an
+/// incident).
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct AdminConfig {
+    /// Whether the admin API is enabled or not.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Bearer token required in the `Authorization` header of admin requests.
+    ///
+    /// Required when the admin API is enabled.
+    #[serde(skip_serializing, default)]
+    pub api_token: Option<String>,
+    /// Whether the relayer starts paused, rejecting new relay requests and
+    /// holding the proposals until it is resumed.
+    ///
+    /// Applies even with the admin API disabled.
+    #[serde(skip_serializing, default)]
+    pub paused: bool,
+}
@@ -1,31 +1,39 @@
 use ethereum_types::H256;
 
 use crate::evm::EvmLinkedAnchorConfig;
+use crate::substrate::SubstrateLinkedAnchorConfig;
 
 use super::*;
 
 /// Linked anchor config for Evm based target system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct RawResourceId {
     /// Raw resource Id
+    #[schemars(with = "String")]
     pub resource_id: H256,
 }
 
 /// LinkedAnchorConfig is configuration for the linked anchors. Linked anchor can be added in multiple ways
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum LinkedAnchorConfig {
     /// Linked anchor configuration for raw resource Id   
     Raw(RawResourceId),
     /// Linked anchor configuration for evm based chains
     Evm(EvmLinkedAnchorConfig),
+    /// Linked anchor configuration for substrate based chains
+    Substrate(SubstrateLinkedAnchorConfig),
 }
 
 impl LinkedAnchorConfig {
-    /// Convert linked anchor to Raw resource Id format
-    pub fn into_raw_resource_id(self) -> LinkedAnchorConfig {
+    /// Resolves the linked anchor into its [`webb_proposals::ResourceId`].
+    pub fn to_resource_id(&self) -> webb_proposals::ResourceId {
         match self {
+            LinkedAnchorConfig::Raw(target) => {
+                let bytes: [u8; 32] = target.resource_id.into();
+                webb_proposals::ResourceId::from(bytes)
+            }
             LinkedAnchorConfig::Evm(config) => {
                 let target_system =
                     webb_proposals::TargetSystem::new_contract_address(
@@ -33,10 +41,28 @@ impl LinkedAnchorConfig {
                     );
                 let typed_chain_id =
                     webb_proposals::TypedChainId::Evm(config.chain_id);
-                let resource_id = webb_proposals::ResourceId::new(
-                    target_system,
-                    typed_chain_id,
-                );
+                webb_proposals::ResourceId::new(target_system, typed_chain_id)
+            }
+            LinkedAnchorConfig::Substrate(config) => {
+                let target = webb_proposals::SubstrateTargetSystem::builder()
+                    .pallet_index(config.pallet)
+                    .tree_id(config.tree_id)
+                    .build();
+                let target_system =
+                    webb_proposals::TargetSystem::Substrate(target);
+                let typed_chain_id =
+                    webb_proposals::TypedChainId::Substrate(config.chain_id);
+                webb_proposals::ResourceId::new(target_system, typed_chain_id)
+            }
+        }
+    }
+
+    /// Convert linked anchor to Raw resource Id format
+    pub fn into_raw_resource_id(self) -> LinkedAnchorConfig {
+        match self {
+            LinkedAnchorConfig::Raw(_) => self,
+            _ => {
+                let resource_id = self.to_resource_id();
                 let raw_resource_id = RawResourceId {
                     resource_id: H256::from_slice(
                         resource_id.to_bytes().as_slice(),
@@ -44,7 +70,54 @@ impl LinkedAnchorConfig {
                 };
                 LinkedAnchorConfig::Raw(raw_resource_id)
             }
-            _ => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_resolve_all_linked_anchor_variants() {
+        let evm = LinkedAnchorConfig::Evm(EvmLinkedAnchorConfig {
+            chain_id: 5,
+            address: Address::repeat_byte(0x11),
+        });
+        let substrate =
+            LinkedAnchorConfig::Substrate(SubstrateLinkedAnchorConfig {
+                chain_id: 1080,
+                pallet: 44,
+                tree_id: 9,
+            });
+        for anchor in [evm, substrate] {
+            let resource_id = anchor.to_resource_id();
+            let raw = anchor.into_raw_resource_id();
+            assert!(matches!(raw, LinkedAnchorConfig::Raw(_)));
+            // converting to raw must not change the resolved resource id.
+            assert_eq!(raw.to_resource_id(), resource_id);
+        }
+    }
+
+    #[test]
+    fn should_resolve_substrate_linked_anchor() {
+        let anchor =
+            LinkedAnchorConfig::Substrate(SubstrateLinkedAnchorConfig {
+                chain_id: 1080,
+                pallet: 44,
+                tree_id: 9,
+            });
+        let resource_id = anchor.to_resource_id();
+        assert_eq!(
+            resource_id.typed_chain_id(),
+            webb_proposals::TypedChainId::Substrate(1080)
+        );
+        match resource_id.target_system() {
+            webb_proposals::TargetSystem::Substrate(system) => {
+                assert_eq!(system.pallet_index, 44);
+                assert_eq!(system.tree_id, 9);
+            }
+            _ => panic!("expected a substrate target system"),
         }
     }
 }
@@ -0,0 +1,39 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// Bridge registry backend configuration.
+///
+/// It is used to find the linked anchors of an anchor when they are not
+/// configured explicitly.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum BridgeRegistryBackendConfig {
+    /// Only uses the linked anchors from the config.
+    Mocked,
+    /// Reads the bridges from a Registry contract deployed on an EVM chain.
+    Evm(EvmBridgeRegistryBackendConfig),
+}
+
+/// The Registry contract the bridges of the relayer are read from.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct EvmBridgeRegistryBackendConfig {
+    /// The chain id of the EVM chain the Registry contract is deployed on.
+    pub chain_id: u32,
+    /// The address of the Registry contract.
+    #[schemars(with = "String")]
+    pub address: Address,
+}
@@ -0,0 +1,54 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// CircuitBreakerConfig controls the per-chain circuit breaker.
+///
+/// The relayer tracks the rolling error rate (RPC failures, reverted
+/// transactions) of a chain, and once it exceeds `error-threshold` the
+/// watchers and the transaction queue of that chain are paused. After
+/// `cooldown` a probe is made, resuming the chain if it succeeds.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct CircuitBreakerConfig {
+    /// Whether the circuit breaker is enabled or not.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Error rate (between 0 and 1) at which the breaker opens.
+    #[serde(default = "defaults::circuit_breaker_error_threshold")]
+    pub error_threshold: f64,
+    /// Minimum number of calls within the window before the error rate
+    /// is evaluated.
+    #[serde(default = "defaults::circuit_breaker_min_samples")]
+    pub min_samples: usize,
+    /// The rolling window (in milliseconds) over which errors are counted.
+    #[serde(default = "defaults::circuit_breaker_window")]
+    pub window: u64,
+    /// How long (in milliseconds) the breaker stays open before probing.
+    #[serde(default = "defaults::circuit_breaker_cooldown")]
+    pub cooldown: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            error_threshold: defaults::circuit_breaker_error_threshold(),
+            min_samples: defaults::circuit_breaker_min_samples(),
+            window: defaults::circuit_breaker_window(),
+            cooldown: defaults::circuit_breaker_cooldown(),
+        }
+    }
+}
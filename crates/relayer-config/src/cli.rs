@@ -1,8 +1,11 @@
+use crate::store_encryption::StoreEncryptionConfig;
 use crate::WebbRelayerConfig;
 use anyhow::Context;
 use directories_next::ProjectDirs;
 use std::path::{Path, PathBuf};
 use structopt::StructOpt;
+use webb_relayer_utils::log_filter::LogFilterHandle;
+use webb_relayer_utils::probe::{self, ProbeBus, ProbeEvent};
 
 /// Package identifier, where the default configuration & database are defined.
 /// If the user does not start the relayer with the `--config-dir`
@@ -14,6 +17,14 @@ pub const PACKAGE_ID: [&str; 3] = ["tools", "webb", "webb-relayer"];
 /// Start the relayer from a config file:
 ///
 /// $ webb-relayer -vvv -c <CONFIG_FILE_PATH>
+///
+/// Inspect the database of a stopped relayer:
+///
+/// $ webb-relayer -c <CONFIG_FILE_PATH> db queues
+///
+/// Generate the keys for a new relayer:
+///
+/// $ webb-relayer keygen --keystore-dir <KEYSTORE_DIR>
 #[derive(StructOpt)]
 #[structopt(name = "Webb Relayer")]
 pub struct Opts {
@@ -33,6 +44,100 @@ pub struct Opts {
     /// and will be deleted when the process exits.
     #[structopt(long)]
     pub tmp: bool,
+    /// Check the configuration against the configured chains and exit,
+    /// without starting the relayer.
+    #[structopt(long)]
+    pub dry_run: bool,
+    /// Run a command instead of starting the relayer.
+    #[structopt(subcommand)]
+    pub cmd: Option<Command>,
+}
+
+/// Commands that can be run instead of starting the relayer.
+#[derive(StructOpt)]
+pub enum Command {
+    /// Inspect the contents of the relayer database.
+    Db(DbCommand),
+    /// Generate the EVM and sr25519 keys for a new relayer.
+    Keygen(KeygenOpts),
+    /// Manage the items of the transaction queues.
+    Queue(QueueCommand),
+    /// Print the JSON Schema of the configuration files.
+    Schema,
+}
+
+/// Subcommands of `webb-relayer db`.
+///
+/// The database can only be opened by one process at a time,
+/// so the relayer must be stopped before running any of these.
+#[derive(StructOpt)]
+pub enum DbCommand {
+    /// Print the items of all the transaction and bridge command queues.
+    Queues,
+    /// Print the cached leaves of an anchor.
+    Leaves {
+        /// The resource id of the anchor, as a hex string.
+        #[structopt(long, value_name = "RESOURCE_ID")]
+        resource_id: ethereum_types::H256,
+    },
+    /// Print the queued bridge commands, i.e. the signed proposals
+    /// waiting to be executed on the signature bridges.
+    Proposals,
+}
+
+/// Subcommands of `webb-relayer queue`.
+///
+/// Like the `db` subcommands, these require the relayer to be stopped.
+#[derive(StructOpt)]
+pub enum QueueCommand {
+    /// Remove the items of a chain's transaction queue.
+    Purge {
+        /// The chain id of the queue.
+        #[structopt(long)]
+        chain_id: u32,
+        /// Purge the Substrate transaction queue instead of the EVM one.
+        #[structopt(long)]
+        substrate: bool,
+        /// Only remove the items in this state.
+        #[structopt(
+            long,
+            possible_values = &[
+                "pending",
+                "processing",
+                "waiting-for-gas-price",
+                "validated",
+                "broadcast",
+                "in-block",
+                "expired",
+                "failed",
+                "processed",
+            ],
+            case_insensitive = true
+        )]
+        state: Option<String>,
+    },
+    /// Reset an item back to pending and move it to the end of its queue.
+    Requeue {
+        /// The key of the item, as a hex string.
+        #[structopt(long, value_name = "ITEM_KEY")]
+        item_key: ethereum_types::H512,
+    },
+}
+
+/// Options of `webb-relayer keygen`.
+#[derive(StructOpt)]
+pub struct KeygenOpts {
+    /// Derive both keys from a newly generated 12 words mnemonic.
+    #[structopt(long)]
+    pub mnemonic: bool,
+    /// Write the keys as encrypted keystore files into this directory,
+    /// instead of printing them.
+    #[structopt(long, value_name = "PATH", parse(from_os_str))]
+    pub keystore_dir: Option<PathBuf>,
+    /// Name of the environment variable holding the keystore passphrase.
+    /// If not set, the passphrase is prompted for.
+    #[structopt(long, value_name = "VAR", requires = "keystore-dir")]
+    pub passphrase_env: Option<String>,
 }
 
 /// Loads the configuration from the given directory.
@@ -75,16 +180,35 @@ where
     Ok(v)
 }
 
+/// Handles to the logger, shared with the rest of the relayer.
+#[derive(Debug, Clone)]
+pub struct LoggerHandles {
+    /// The bus the probe events are published on.
+    pub probe_bus: ProbeBus,
+    /// The filter of the logs, tunable at runtime.
+    pub log_filter: LogFilterHandle,
+}
+
 /// Sets up the logger for the relayer, based on the verbosity level passed in.
 ///
-/// Returns `Ok(())` on success, or `Err(anyhow::Error)` on failure.
+/// Returns `Ok(LoggerHandles)` on success, or `Err(anyhow::Error)` on failure.
 ///
 /// # Arguments
 ///
 /// * `verbosity` - An i32 integer representing the verbosity level.
 /// * `filter` -  An &str representing filtering directive for EnvFilter
-pub fn setup_logger(verbosity: i32, filter: &str) -> anyhow::Result<()> {
+///
+/// The probe events are published on the returned bus whatever the
+/// verbosity, so they can be exported, and the filter of the logs can be
+/// replaced at runtime through the returned handle.
+pub fn setup_logger(
+    verbosity: i32,
+    filter: &str,
+) -> anyhow::Result<LoggerHandles> {
     use tracing::Level;
+    use tracing_subscriber::filter::Targets;
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::{reload, EnvFilter};
     let log_level = match verbosity {
         0 => Level::ERROR,
         1 => Level::WARN,
@@ -98,13 +222,20 @@ pub fn setup_logger(verbosity: i32, filter: &str) -> anyhow::Result<()> {
     let directive_2 = format!("webb_={log_level}")
         .parse()
         .expect("valid log level");
-    let env_filter = tracing_subscriber::EnvFilter::from_default_env()
+    let env_filter = EnvFilter::from_default_env()
         .add_directive(directive_1)
         .add_directive(directive_2);
-    let logger = tracing_subscriber::fmt()
-        .with_target(true)
-        .with_max_level(log_level)
-        .with_env_filter(env_filter);
+    let directives = env_filter.to_string();
+    // the filter is reloadable, so it can be changed through the admin API.
+    let (env_filter, reload_handle) = reload::Layer::new(env_filter);
+    let log_filter = LogFilterHandle::new(directives, move |directives| {
+        let env_filter = EnvFilter::try_new(directives)
+            .map_err(|e| format!("Invalid log filter: {e}"))?;
+        reload_handle
+            .reload(env_filter)
+            .map_err(|e| format!("Failed to reload the log filter: {e}"))
+    });
+    let logger = tracing_subscriber::fmt::layer().with_target(true);
     // if we are not compiling for integration tests, we should use pretty logs
     #[cfg(not(feature = "integration-tests"))]
     let logger = logger.pretty();
@@ -112,8 +243,85 @@ pub fn setup_logger(verbosity: i32, filter: &str) -> anyhow::Result<()> {
     #[cfg(feature = "integration-tests")]
     let logger = logger.json().flatten_event(true).with_current_span(false);
 
-    logger.init();
-    Ok(())
+    let probe_bus = ProbeBus::default();
+    let probes = ProbeLayer {
+        bus: probe_bus.clone(),
+    }
+    .with_filter(Targets::new().with_target(probe::TARGET, Level::TRACE));
+    tracing_subscriber::registry()
+        .with(logger.with_filter(env_filter))
+        .with(probes)
+        .init();
+    Ok(LoggerHandles {
+        probe_bus,
+        log_filter,
+    })
+}
+
+/// A tracing layer publishing the events of the [`probe::TARGET`] on a
+/// [`ProbeBus`], with their fields as JSON values.
+struct ProbeLayer {
+    bus: ProbeBus,
+}
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for ProbeLayer {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let mut visitor = ProbeFieldsVisitor::default();
+        event.record(&mut visitor);
+        let mut fields = visitor.0;
+        let kind = match fields.remove("kind") {
+            Some(serde_json::Value::String(kind)) => Some(kind),
+            _ => None,
+        };
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.bus.publish(ProbeEvent {
+            kind,
+            timestamp,
+            fields,
+        });
+    }
+}
+
+/// Collects the fields of a tracing event as JSON values.
+#[derive(Default)]
+struct ProbeFieldsVisitor(serde_json::Map<String, serde_json::Value>);
+
+impl tracing::field::Visit for ProbeFieldsVisitor {
+    fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(
+        &mut self,
+        field: &tracing::field::Field,
+        value: &dyn std::fmt::Debug,
+    ) {
+        self.0
+            .insert(field.name().into(), format!("{value:?}").into());
+    }
 }
 
 /// Creates a database store for the relayer based on the configuration passed in.
@@ -123,26 +331,34 @@ pub fn setup_logger(verbosity: i32, filter: &str) -> anyhow::Result<()> {
 /// # Arguments
 ///
 /// * `opts` - The configuration options for the database store.
+/// * `encryption` - The encryption at rest of the store values.
 pub async fn create_store(
     opts: &Opts,
+    encryption: &StoreEncryptionConfig,
 ) -> anyhow::Result<webb_relayer_store::SledStore> {
     // check if we shall use the temp dir.
-    if opts.tmp {
+    let store = if opts.tmp {
         tracing::debug!("Using temp dir for store");
-        let store = webb_relayer_store::SledStore::temporary()?;
-        return Ok(store);
-    }
-    let dirs = ProjectDirs::from(PACKAGE_ID[0], PACKAGE_ID[1], PACKAGE_ID[2])
-        .context("failed to get config")?;
-    let p = match opts.config_dir.as_ref() {
-        Some(p) => p.clone(),
-        None => dirs.data_local_dir().to_path_buf(),
+        webb_relayer_store::SledStore::temporary()?
+    } else {
+        let dirs =
+            ProjectDirs::from(PACKAGE_ID[0], PACKAGE_ID[1], PACKAGE_ID[2])
+                .context("failed to get config")?;
+        let p = match opts.config_dir.as_ref() {
+            Some(p) => p.clone(),
+            None => dirs.data_local_dir().to_path_buf(),
+        };
+        let db_path = match opts.config_dir.as_ref().zip(p.parent()) {
+            Some((_, parent)) => parent.join("store"),
+            None => p.join("store"),
+        };
+        webb_relayer_store::SledStore::open(db_path)?
     };
-    let db_path = match opts.config_dir.as_ref().zip(p.parent()) {
-        Some((_, parent)) => parent.join("store"),
-        None => p.join("store"),
-    };
-
-    let store = webb_relayer_store::SledStore::open(db_path)?;
-    Ok(store)
+    match &encryption.key {
+        Some(key) => {
+            tracing::debug!("Encrypting the store values at rest");
+            Ok(store.with_encryption_key(key.to_fixed_bytes()))
+        }
+        None => Ok(store),
+    }
 }
@@ -0,0 +1,48 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// CompressionConfig controls the compression of the responses of the data
+/// query endpoints, which routinely reach megabytes.
+///
+/// The encoding is negotiated with the client through the `Accept-Encoding`
+/// header, clients that do not ask for it get uncompressed responses.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct CompressionConfig {
+    /// Compress the responses of the leaves endpoint.
+    #[serde(default = "defaults::compression_enabled")]
+    pub leaves: bool,
+    /// Compress the responses of the encrypted outputs endpoint.
+    #[serde(default = "defaults::compression_enabled")]
+    pub encrypted_outputs: bool,
+    /// Allow the `gzip` encoding.
+    #[serde(default = "defaults::compression_enabled")]
+    pub gzip: bool,
+    /// Allow the `br` (brotli) encoding.
+    #[serde(default = "defaults::compression_enabled")]
+    pub brotli: bool,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            leaves: defaults::compression_enabled(),
+            encrypted_outputs: defaults::compression_enabled(),
+            gzip: defaults::compression_enabled(),
+            brotli: defaults::compression_enabled(),
+        }
+    }
+}
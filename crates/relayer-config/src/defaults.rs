@@ -20,6 +20,109 @@ pub const fn max_blocks_per_step() -> u64 {
 pub const fn print_progress_interval() -> u64 {
     7_000
 }
+/// The anchor discovery polling interval is set to `60_000` by default.
+pub const fn anchor_discovery_polling_interval() -> u64 {
+    60_000
+}
+/// The HD wallet derivation path is set to `m/44'/60'/0'/0` by default.
+pub fn hd_wallet_derivation_path() -> String {
+    String::from("m/44'/60'/0'/0")
+}
+/// The circuit breaker error threshold is set to `0.5` by default.
+pub const fn circuit_breaker_error_threshold() -> f64 {
+    0.5
+}
+/// The circuit breaker minimum samples is set to `10` by default.
+pub const fn circuit_breaker_min_samples() -> usize {
+    10
+}
+/// The circuit breaker rolling window is set to `60_000` by default.
+pub const fn circuit_breaker_window() -> u64 {
+    60_000
+}
+/// The circuit breaker cooldown is set to `30_000` by default.
+pub const fn circuit_breaker_cooldown() -> u64 {
+    30_000
+}
+/// The RPC connect timeout is set to `10_000` by default.
+pub const fn rpc_connect_timeout() -> u64 {
+    10_000
+}
+/// The RPC request timeout is set to `30_000` by default.
+pub const fn rpc_request_timeout() -> u64 {
+    30_000
+}
+/// The RPC initial retry backoff is set to `1_000` by default.
+pub const fn rpc_initial_backoff() -> u64 {
+    1_000
+}
+/// RPC metrics are set to `true` by default.
+pub const fn rpc_metrics() -> bool {
+    true
+}
+/// Response compression is set to `true` by default.
+pub const fn compression_enabled() -> bool {
+    true
+}
+/// The leader lease key is set to `webb-relayer:leader` by default.
+pub fn leader_lease_key() -> String {
+    String::from("webb-relayer:leader")
+}
+/// The probe export subject is set to `webb.relayer.probe` by default.
+pub fn probe_export_subject() -> String {
+    String::from("webb.relayer.probe")
+}
+/// The EIP-712 domain name of the trusted forwarders is set to
+/// `MinimalForwarder` by default, the one of the OpenZeppelin forwarder.
+pub fn forwarder_domain_name() -> String {
+    String::from("MinimalForwarder")
+}
+/// The EIP-712 domain version of the trusted forwarders is set to `0.0.1` by
+/// default, the one of the OpenZeppelin forwarder.
+pub fn forwarder_domain_version() -> String {
+    String::from("0.0.1")
+}
+/// The leader lease TTL is set to `15_000` by default.
+pub const fn leader_lease_ttl() -> u64 {
+    15_000
+}
+/// The peer request timeout is set to `30_000` by default.
+pub const fn peer_request_timeout() -> u64 {
+    30_000
+}
+/// The refund cap window is set to `86_400_000` (a day) by default.
+pub const fn refund_cap_window() -> u64 {
+    86_400_000
+}
+/// The fee harvesting interval is set to `3_600_000` (an hour) by default.
+pub const fn fee_harvesting_interval() -> u64 {
+    3_600_000
+}
+/// The treasury sweep interval is set to `3_600_000` (an hour) by default.
+pub const fn treasury_sweep_interval() -> u64 {
+    3_600_000
+}
+
+/// The leaves flush batch size is set to `1000` leaves by default.
+pub const fn leaves_flush_max_batch_size() -> usize {
+    1000
+}
+
+/// The second leaf of every transaction is propagated to the linked anchors
+/// by default.
+pub const fn propagation_every_nth_leaf() -> u32 {
+    2
+}
+
+/// Stuck transactions are replaced with a `20%` higher gas price by default.
+pub const fn stuck_tx_gas_price_bump_percent() -> u64 {
+    20
+}
+
+/// Stuck transactions are replaced at most `3` times by default.
+pub const fn stuck_tx_max_replacements() -> u32 {
+    3
+}
 
 /// The default unlisted assets.
 pub fn unlisted_assets() -> HashMap<String, crate::UnlistedAssetConfig> {
@@ -51,3 +154,24 @@ pub fn unlisted_assets() -> HashMap<String, crate::UnlistedAssetConfig> {
         ),
     ])
 }
+/// The registry heartbeat interval is set to `3_600_000` (an hour) by default.
+pub const fn registry_heartbeat_interval() -> u64 {
+    3_600_000
+}
+/// The shutdown drain timeout is set to `30_000` by default.
+pub const fn shutdown_drain_timeout() -> u64 {
+    30_000
+}
+/// The gas estimates are used as is (`1.0`) by default.
+pub const fn gas_limit_multiplier() -> f64 {
+    1.0
+}
+/// The expired history is purged every hour (`3_600_000`) by default.
+pub const fn retention_purge_interval() -> u64 {
+    3_600_000
+}
+/// The signed envelopes of the relay commands are valid for 5 minutes
+/// (`300`) by default.
+pub const fn replay_protection_max_age() -> u64 {
+    300
+}
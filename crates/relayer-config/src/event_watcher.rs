@@ -1,7 +1,7 @@
 use super::*;
 
 /// EventsWatchConfig is the configuration for the events watch.
-#[derive(Debug, Clone, Serialize, Deserialize, Default, Copy)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Copy, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct EventsWatcherConfig {
     /// A flag for enabling API endpoints for querying data from the relayer.
@@ -12,6 +12,10 @@ pub struct EventsWatcherConfig {
     pub enabled: bool,
     /// Polling interval in milliseconds
     pub polling_interval: u64,
+    /// Whether the polling interval is adapted to the block time of the
+    /// chain, using the configured one until it is known.
+    #[serde(default)]
+    pub adaptive_polling: bool,
     /// The maximum number of events to fetch in one request.
     #[serde(default = "defaults::max_blocks_per_step")]
     pub max_blocks_per_step: u64,
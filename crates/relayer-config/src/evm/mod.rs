@@ -1,18 +1,21 @@
 use core::fmt;
 
-use ethereum_types::Address;
+use ethereum_types::{Address, U256};
 use url::Url;
-use webb_relayer_types::{private_key::PrivateKey, rpc_url::RpcUrl};
+use webb_relayer_types::{
+    mnemonic::Mnemonic, private_key::PrivateKey, rpc_url::RpcUrl,
+};
 
 use crate::{
     anchor::LinkedAnchorConfig, block_poller::BlockPollerConfig,
-    event_watcher::EventsWatcherConfig,
+    circuit_breaker::CircuitBreakerConfig, event_watcher::EventsWatcherConfig,
+    keystore::KeystoreConfig,
 };
 
 use super::*;
 
 /// EvmChainConfig is the configuration for the EVM based networks.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct EvmChainConfig {
     /// String that groups configuration for this chain on a human-readable name.
@@ -20,12 +23,24 @@ pub struct EvmChainConfig {
     /// Boolean indicating EVM based networks are enabled or not.
     #[serde(default)]
     pub enabled: bool,
+    /// Whether the relayer sends transactions to this chain, or only watches
+    /// it to serve its data.
+    #[serde(default)]
+    pub mode: ChainMode,
     /// Http(s) Endpoint for quick Req/Res
     #[serde(skip_serializing)]
     pub http_endpoint: HttpEndpoint,
     /// Websocket Endpoint for long living connections
     #[serde(skip_serializing)]
+    #[schemars(with = "String")]
     pub ws_endpoint: RpcUrl,
+    /// Additional transports to reach the chain with, with priorities.
+    ///
+    /// Calls use the `http` and `ipc` transports, subscriptions use the `ws`
+    /// ones. When none of a kind is listed, the `http-endpoint` or the
+    /// `ws-endpoint` is used instead.
+    #[serde(skip_serializing, default)]
+    pub transports: Vec<TransportConfig>,
     /// Block confirmations
     #[serde(skip_serializing, default)]
     pub block_confirmations: u8,
@@ -60,9 +75,21 @@ pub struct EvmChainConfig {
     /// 5. if it doesn't contains special characters and has 12 or 24 words in it
     ///   then we should process it as a mnemonic string: 'word two three four ...'
     #[serde(skip_serializing)]
+    #[schemars(with = "Option<String>")]
     pub private_key: Option<PrivateKey>,
+    /// HD wallet configuration, used to derive the account of this network
+    /// from a BIP-39 mnemonic when no `private_key` is configured.
+    ///
+    /// This allows a single seed to back the accounts on all networks.
+    #[serde(skip_serializing, default)]
+    pub hd_wallet: Option<HdWalletConfig>,
+    /// Encrypted keystore holding the private key of this account, used
+    /// when no `private_key` is configured.
+    #[serde(skip_serializing, default)]
+    pub keystore: Option<KeystoreConfig>,
     /// Optionally, a user can specify an account to receive rewards for relaying
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
     pub beneficiary: Option<Address>,
     /// Supported contracts over this chain.
     #[serde(default)]
@@ -76,16 +103,256 @@ pub struct EvmChainConfig {
     /// Block poller/listening configuration
     #[serde(skip_serializing, default)]
     pub block_poller: Option<BlockPollerConfig>,
+    /// Circuit breaker configuration, pausing the watchers and the
+    /// transaction queue of this chain on elevated error rates.
+    #[serde(skip_serializing, default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// RPC client configuration (timeouts, retries, throttling and metrics).
+    #[serde(skip_serializing, default)]
+    pub rpc: RpcClientConfig,
+    /// Daily gas budget, pausing the relayed transactions of this chain
+    /// once it is spent.
+    #[serde(skip_serializing, default)]
+    pub gas_budget: Option<GasBudgetConfig>,
+    /// Periodic harvesting of the relayer's accumulated wrapped-token fees.
+    #[serde(skip_serializing, default)]
+    pub fee_harvesting: FeeHarvestingConfig,
+    /// Periodic sweeping of the hot wallet's balances to a cold address.
+    #[serde(skip_serializing, default)]
+    pub treasury_sweep: TreasurySweepConfig,
+    /// The tokens the relayer is willing to relay transactions for, any
+    /// token is accepted if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<Vec<String>>")]
+    pub allowed_tokens: Option<Vec<Address>>,
+}
+
+impl EvmChainConfig {
+    /// Adds the gas limit headroom of the given contract to a gas estimate
+    /// of a transaction sent to it.
+    ///
+    /// The estimates of the transactions sent to other addresses are left
+    /// as is.
+    pub fn gas_limit_with_headroom(&self, to: Address, gas: U256) -> U256 {
+        let multiplier = self
+            .contracts
+            .iter()
+            .map(Contract::common)
+            .find(|common| common.address == to)
+            .map_or(1.0, |common| common.gas_limit_multiplier);
+        if multiplier <= 1.0 {
+            return gas;
+        }
+        // in thousandths, to keep the multiplication in integers.
+        let permille = (multiplier * 1000.0).round() as u64;
+        gas.saturating_mul(U256::from(permille)) / 1000
+    }
+}
+
+/// TreasurySweepConfig configures the periodic transfer of the relayer's
+/// balances above their high-water marks from the hot wallet to a cold
+/// address.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct TreasurySweepConfig {
+    /// Whether the treasury sweep is enabled.
+    #[serde(default)]
+    pub enabled: bool,
+    /// How often (in milliseconds) the balances are checked.
+    #[serde(default = "defaults::treasury_sweep_interval")]
+    pub interval: u64,
+    /// The cold address the balances are swept to.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub cold_address: Option<Address>,
+    /// The native balance (in whole tokens) kept in the hot wallet, the
+    /// native token is not swept if not set.
+    #[serde(default)]
+    pub native_high_water_mark: Option<f64>,
+    /// The ERC-20 tokens swept.
+    #[serde(default)]
+    pub tokens: Vec<TokenSweepConfig>,
+}
+
+impl Default for TreasurySweepConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: defaults::treasury_sweep_interval(),
+            cold_address: None,
+            native_high_water_mark: None,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+/// TokenSweepConfig configures the sweeping of an ERC-20 token.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct TokenSweepConfig {
+    /// The address of the token.
+    #[schemars(with = "String")]
+    pub address: Address,
+    /// The balance (in whole tokens) kept in the hot wallet.
+    pub high_water_mark: f64,
+}
+
+/// FeeHarvestingConfig configures the periodic sweeping of the wrapped
+/// tokens the relayer collected as fees on a chain.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct FeeHarvestingConfig {
+    /// Whether the fee harvesting is enabled.
+    #[serde(default)]
+    pub enabled: bool,
+    /// How often (in milliseconds) the fee balances are checked.
+    #[serde(default = "defaults::fee_harvesting_interval")]
+    pub interval: u64,
+    /// The balance of a wrapped token (in whole tokens) above which its fees
+    /// are harvested.
+    #[serde(default)]
+    pub threshold: f64,
+    /// The account (e.g. the operator's treasury) the fees are sent to.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub recipient: Option<Address>,
+    /// Whether the fees are unwrapped into the underlying asset through the
+    /// FungibleTokenWrapper contract instead of sent as wrapped tokens.
+    #[serde(default)]
+    pub unwrap: bool,
+    /// The underlying token the fees are unwrapped into, the native token if
+    /// not set.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub unwrap_into: Option<Address>,
+}
+
+impl Default for FeeHarvestingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: defaults::fee_harvesting_interval(),
+            threshold: 0.,
+            recipient: None,
+            unwrap: false,
+            unwrap_into: None,
+        }
+    }
+}
+
+/// GasBudgetConfig limits how much the relayer spends on gas on a chain per
+/// (UTC) day.
+///
+/// Once the budget is spent, the transaction queue of the chain pauses its
+/// transactions until the next day, only the governance transactions are
+/// never paused.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct GasBudgetConfig {
+    /// Maximum gas spend per day, in the native token (e.g. `0.5` ETH).
+    pub max_native: Option<f64>,
+    /// Maximum gas spend per day, in USD, converted at the current price of
+    /// the native token.
+    pub max_usd: Option<f64>,
+}
+
+/// RpcClientConfig configures the provider middleware stack of a chain.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct RpcClientConfig {
+    /// Timeout (in milliseconds) for connecting to the RPC endpoint.
+    #[serde(default = "defaults::rpc_connect_timeout")]
+    pub connect_timeout: u64,
+    /// Timeout (in milliseconds) for a single RPC request.
+    #[serde(default = "defaults::rpc_request_timeout")]
+    pub request_timeout: u64,
+    /// Maximum number of retries for rate limited or timed out requests.
+    ///
+    /// If not set, requests are retried until they succeed.
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Initial backoff (in milliseconds) between retries.
+    #[serde(default = "defaults::rpc_initial_backoff")]
+    pub initial_backoff: u64,
+    /// Maximum number of requests per second sent to the chain, across
+    /// all of its endpoints.
+    ///
+    /// If not set, requests are not throttled.
+    #[serde(default)]
+    pub max_requests_per_second: Option<u32>,
+    /// Whether to record the RPC requests metrics of this chain.
+    #[serde(default = "defaults::rpc_metrics")]
+    pub metrics: bool,
+    /// The number of endpoints that must agree on the reads used in safety
+    /// checks (roots, nullifiers and balances) before they are trusted.
+    ///
+    /// If not set, these reads are sent to a single endpoint like any other.
+    #[serde(default)]
+    pub quorum: Option<usize>,
+}
+
+impl Default for RpcClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: defaults::rpc_connect_timeout(),
+            request_timeout: defaults::rpc_request_timeout(),
+            max_retries: None,
+            initial_backoff: defaults::rpc_initial_backoff(),
+            max_requests_per_second: None,
+            metrics: defaults::rpc_metrics(),
+            quorum: None,
+        }
+    }
+}
+
+/// HD wallet configuration for deriving an account from a BIP-39 mnemonic.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct HdWalletConfig {
+    /// The BIP-39 mnemonic, either the word list itself or an
+    /// env var (starting with `$`) containing it.
+    #[schemars(with = "String")]
+    pub mnemonic: Mnemonic,
+    /// The derivation path, without the account index.
+    ///
+    /// default to `m/44'/60'/0'/0`
+    #[serde(default = "defaults::hd_wallet_derivation_path")]
+    pub derivation_path: String,
+    /// The index of the account to derive for this network.
+    #[serde(default)]
+    pub account_index: u32,
+}
+
+impl HdWalletConfig {
+    /// Returns the full derivation path of the account, including the account index.
+    pub fn account_derivation_path(&self) -> String {
+        format!(
+            "{}/{}",
+            self.derivation_path.trim_end_matches('/'),
+            self.account_index
+        )
+    }
 }
 
 /// Transaction withdraw fee configuration.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct RelayerFeeConfig {
     /// Relayer profit percent per transaction fee for relaying
     pub relayer_profit_percent: f64,
     /// Maximum refund amount per transaction relaying
     pub max_refund_amount: f64,
+    /// Maximum cumulative refund amount (in USD) relayed to a single
+    /// recipient within the refund cap window, unlimited if not set.
+    #[serde(default)]
+    pub max_refund_per_recipient: Option<f64>,
+    /// The rolling window (in milliseconds) over which the refunds relayed
+    /// to a recipient are capped.
+    #[serde(default = "defaults::refund_cap_window")]
+    pub refund_cap_window: u64,
+    /// Relaying withdrawals without fees, disabled if not set.
+    #[serde(default)]
+    pub subsidy: Option<SubsidyConfig>,
 }
 
 impl Default for RelayerFeeConfig {
@@ -93,18 +360,50 @@ impl Default for RelayerFeeConfig {
         Self {
             relayer_profit_percent: 5.,
             max_refund_amount: 5.,
+            max_refund_per_recipient: None,
+            refund_cap_window: defaults::refund_cap_window(),
+            subsidy: None,
         }
     }
 }
 
+/// Subsidized (zero-fee) relaying, for testnets and promotional campaigns.
+///
+/// The fee of a subsidized withdrawal is not checked, the relayer pays for
+/// its gas.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct SubsidyConfig {
+    /// Subsidize the withdrawals to any recipient.
+    #[serde(default)]
+    pub all_recipients: bool,
+    /// The recipients whose withdrawals are subsidized.
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    pub recipients: Vec<Address>,
+    /// The maximum number of withdrawals subsidized per (UTC) day on the
+    /// chain, unlimited if not set.
+    ///
+    /// Once reached, withdrawals pay their fee again until the next day.
+    #[serde(default)]
+    pub max_daily_txs: Option<u64>,
+}
+
+impl SubsidyConfig {
+    /// Whether the withdrawals to the given recipient are subsidized.
+    pub fn covers(&self, recipient: Address) -> bool {
+        self.all_recipients || self.recipients.contains(&recipient)
+    }
+}
+
 /// configuration for adding http endpoints.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum HttpEndpoint {
     /// Single http endpoint
-    Single(RpcUrl),
+    Single(#[schemars(with = "String")] RpcUrl),
     /// Multiple http endpoints
-    Multiple(Vec<RpcUrl>),
+    Multiple(#[schemars(with = "Vec<String>")] Vec<RpcUrl>),
 }
 
 impl fmt::Display for HttpEndpoint {
@@ -126,18 +425,85 @@ impl From<Url> for HttpEndpoint {
     }
 }
 
+/// The kind of a transport to an EVM node.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    /// JSON-RPC over HTTP(s), used for calls.
+    Http,
+    /// JSON-RPC over a websocket, used for subscriptions.
+    Ws,
+    /// JSON-RPC over a local IPC socket, used for calls.
+    Ipc,
+}
+
+/// TransportConfig is a transport to an EVM node.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct TransportConfig {
+    /// The kind of the transport.
+    pub kind: TransportKind,
+    /// The endpoint of the node, required by `http` and `ws` transports.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub url: Option<RpcUrl>,
+    /// The path of the IPC socket of the node, required by `ipc` transports.
+    #[serde(default)]
+    pub path: Option<std::path::PathBuf>,
+    /// The priority of the transport, lower values are preferred.
+    ///
+    /// Calls are spread over all the transports of the best priority that
+    /// could be opened, subscriptions use the first websocket that connects.
+    #[serde(default)]
+    pub priority: u32,
+}
+
+/// ChainMode is what the relayer does with a chain.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChainMode {
+    /// The relayer watches the chain and sends transactions to it.
+    #[default]
+    Full,
+    /// The events watchers run and the data of the chain is served, but the
+    /// transaction queue is not started and relay requests are rejected,
+    /// for chains where the relayer holds no funds.
+    WatchOnly,
+}
+
+impl ChainMode {
+    /// Returns `true` if the relayer does not send transactions to the
+    /// chain.
+    pub fn is_watch_only(&self) -> bool {
+        matches!(self, ChainMode::WatchOnly)
+    }
+}
+
 /// Linked anchor config for Evm based target system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct EvmLinkedAnchorConfig {
     /// The chain Id
     pub chain_id: u32,
     /// The V-anchor Contract Address.
+    #[schemars(with = "String")]
     pub address: Address,
 }
 
 /// Enumerates the supported contract configurations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "contract")]
 pub enum Contract {
     /// The VAnchor contract configuration.
@@ -149,19 +515,39 @@ pub enum Contract {
 }
 
 /// CommonContractConfig represents the common configuration for contracts.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct CommonContractConfig {
     /// The address of this contract on this chain.
+    #[schemars(with = "String")]
     pub address: Address,
     /// the block number where this contract got deployed at.
+    ///
+    /// If not set, it is detected on chain at startup and persisted.
+    #[serde(default)]
     pub deployed_at: u64,
+    /// The multiplier applied to the gas estimates of the transactions sent
+    /// to this contract (e.g. `1.2`), as headroom for a gas usage that
+    /// varies with the state of the contract.
+    #[serde(default = "defaults::gas_limit_multiplier", skip_serializing)]
+    pub gas_limit_multiplier: f64,
+}
+
+impl Contract {
+    /// The common configuration of the contract.
+    pub fn common(&self) -> &CommonContractConfig {
+        match self {
+            Contract::VAnchor(c) => &c.common,
+            Contract::SignatureBridge(c) => &c.common,
+            Contract::MaspVanchor(c) => &c.common,
+        }
+    }
 }
 
 /// Smart Anchor Updates applies polices to the AnchorUpdate Proposals
 /// which helps to reduce the number of updates, hence the number of
 /// transactions and gas fees.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct SmartAnchorUpdatesConfig {
     /// Enables smart anchor updates
@@ -195,7 +581,7 @@ impl Default for SmartAnchorUpdatesConfig {
 }
 
 /// VAnchorContractConfig represents the configuration for the VAnchor contract.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct VAnchorContractConfig {
     /// Common contract configuration.
@@ -209,10 +595,126 @@ pub struct VAnchorContractConfig {
     /// For configuring the smart anchor updates
     #[serde(default)]
     pub smart_anchor_updates: SmartAnchorUpdatesConfig,
+    /// Whether the roots of the AnchorUpdate proposals are read at the
+    /// `block-confirmations` depth of the chain instead of its latest block,
+    /// so they never reference a root that gets reorged out.
+    #[serde(default)]
+    pub confirmed_roots: bool,
+    /// The minimum amounts accepted for withdrawals relayed to this contract
+    #[serde(default)]
+    pub withdrawal_policy: WithdrawalPolicyConfig,
+    /// For configuring how the cached leaves are written to the store
+    #[serde(default)]
+    pub leaves_flush_policy: LeavesFlushPolicyConfig,
+    /// For configuring which new leaves are propagated to the linked anchors
+    #[serde(default)]
+    pub propagation_policy: PropagationPolicyConfig,
+    /// The trusted forwarder the withdrawals are relayed through, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forwarder: Option<ForwarderConfig>,
+}
+
+/// Trusted forwarder (ERC-2771) configuration. The relayed transactions are
+/// executed through the forwarder on behalf of a sponsor account, which signs
+/// them, while the account of the relayer only pays for their gas.
+///
+/// The forwarder is expected to expose the interface of the OpenZeppelin
+/// `MinimalForwarder`: `getNonce(address)` and `execute(ForwardRequest,
+/// bytes)`, with EIP-712 signed requests.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct ForwarderConfig {
+    /// The address of the trusted forwarder.
+    #[schemars(with = "String")]
+    pub address: Address,
+    /// The private key of the sponsor account, which signs the requests
+    /// executed by the forwarder.
+    #[serde(skip_serializing)]
+    #[schemars(with = "String")]
+    pub sponsor_private_key: PrivateKey,
+    /// The name of the EIP-712 domain of the forwarder.
+    #[serde(default = "defaults::forwarder_domain_name")]
+    pub domain_name: String,
+    /// The version of the EIP-712 domain of the forwarder.
+    #[serde(default = "defaults::forwarder_domain_version")]
+    pub domain_version: String,
+}
+
+/// Propagation policy controls which new leaves of the anchor are propagated
+/// to its linked anchors as AnchorUpdate proposals, trading the freshness of
+/// the linked anchors for the cost of updating them.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct PropagationPolicyConfig {
+    /// Propagate every `every-nth-leaf` leaf, that is the leaves whose
+    /// `leaf_index + 1` is a multiple of it.
+    ///
+    /// Defaults to `2`, the second leaf of every transaction, as every
+    /// `transact` call inserts two leaves.
+    #[serde(default = "defaults::propagation_every_nth_leaf")]
+    pub every_nth_leaf: u32,
+    /// Minimum interval, in seconds, between the blocks of two propagated
+    /// leaves. The leaves within the interval are skipped, their root is
+    /// propagated along with the next propagated leaf, or once the interval
+    /// passed if there is none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_interval: Option<u64>,
+}
+
+impl PropagationPolicyConfig {
+    /// Returns `true` if the leaf at `leaf_index` is to be propagated, not
+    /// accounting for the `min_interval`.
+    pub fn propagates_leaf(&self, leaf_index: u32) -> bool {
+        self.every_nth_leaf != 0
+            && (u64::from(leaf_index) + 1) % u64::from(self.every_nth_leaf) == 0
+    }
+}
+
+impl Default for PropagationPolicyConfig {
+    fn default() -> Self {
+        Self {
+            every_nth_leaf: defaults::propagation_every_nth_leaf(),
+            min_interval: None,
+        }
+    }
+}
+
+/// Leaves flush policy controls how many leaves are buffered before being
+/// written to the store at once, which speeds up the initial sync of large
+/// anchors. The buffered leaves are also written at the end of every step of
+/// the events watcher.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct LeavesFlushPolicyConfig {
+    /// Maximum number of leaves buffered before they are written to the store
+    #[serde(default = "defaults::leaves_flush_max_batch_size")]
+    pub max_batch_size: usize,
+}
+
+impl Default for LeavesFlushPolicyConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: defaults::leaves_flush_max_batch_size(),
+        }
+    }
+}
+
+/// Withdrawal policy rejects dust withdrawals, which would cost the relayer
+/// more gas than the fee they pay.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct WithdrawalPolicyConfig {
+    /// Minimum amount withdrawn (in `wrappedToken`), unlimited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_withdrawal_amount: Option<f64>,
+    /// Minimum fee (in `wrappedToken`) paid to the relayer, unlimited if not
+    /// set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_fee: Option<f64>,
 }
 
 /// Signature Bridge contract configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct SignatureBridgeContractConfig {
     /// Common contract configuration.
@@ -220,10 +722,36 @@ pub struct SignatureBridgeContractConfig {
     pub common: CommonContractConfig,
     /// Controls the events watcher
     pub events_watcher: EventsWatcherConfig,
+    /// Controls the discovery of anchors registered on this bridge.
+    #[serde(default)]
+    pub anchor_discovery: AnchorDiscoveryConfig,
+}
+
+/// Anchor discovery reads the resource mappings of the Signature Bridge
+/// and starts the services for the newly registered anchors at runtime.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct AnchorDiscoveryConfig {
+    /// Enables anchor discovery for this bridge
+    #[serde(default)]
+    pub enabled: bool,
+    /// Polling interval in milliseconds
+    #[serde(default = "defaults::anchor_discovery_polling_interval")]
+    pub polling_interval: u64,
+}
+
+impl Default for AnchorDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            // Disabled by default
+            enabled: false,
+            polling_interval: defaults::anchor_discovery_polling_interval(),
+        }
+    }
 }
 
 /// MaspContractConfig represents the configuration for the Masp contract.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct MaspContractConfig {
     /// Common contract configuration.
@@ -234,4 +762,7 @@ pub struct MaspContractConfig {
     /// A List of linked Anchor Contracts (on other chains) to this contract.
     #[serde(default)]
     pub linked_anchors: Option<Vec<LinkedAnchorConfig>>,
+    /// The minimum amounts accepted for withdrawals relayed to this contract
+    #[serde(default)]
+    pub withdrawal_policy: WithdrawalPolicyConfig,
 }
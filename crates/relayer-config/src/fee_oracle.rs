@@ -0,0 +1,45 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// Enumerates the supported sources of the token prices and gas prices
+/// used to compute the relayer fees.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum FeeOracleConfig {
+    /// Fetches the token prices from CoinGecko, and the gas prices from the
+    /// chains and Etherscan.
+    #[default]
+    Live,
+    /// Uses the fixed token prices and gas prices of the configuration.
+    Static(StaticFeeOracleConfig),
+}
+
+/// StaticFeeOracleConfig represents the configuration for the static fee
+/// oracle, used by integration tests and airgapped deployments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct StaticFeeOracleConfig {
+    /// The price of the tokens in USD, by token symbol.
+    ///
+    /// the prices of the unlisted assets are used for the missing tokens.
+    #[serde(default)]
+    pub prices: HashMap<String, f64>,
+    /// The gas price in wei, by chain id.
+    ///
+    /// the chains without a gas price use the gas price of their node.
+    #[serde(default)]
+    pub gas_prices: HashMap<String, u64>,
+}
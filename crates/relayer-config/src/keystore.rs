@@ -0,0 +1,34 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+
+use super::*;
+
+/// KeystoreConfig references an encrypted keystore file that holds the
+/// account secret, so no plaintext key has to be kept in the config.
+///
+/// For EVM chains this is an Ethereum V3 keystore JSON file, for Substrate
+/// nodes the same format is used to encrypt the 32 bytes sr25519 seed.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct KeystoreConfig {
+    /// Path to the encrypted keystore file.
+    pub path: PathBuf,
+    /// Name of the environment variable that holds the passphrase.
+    ///
+    /// If it is not set, the relayer will prompt for the passphrase at startup.
+    #[serde(default)]
+    pub passphrase_env: Option<String>,
+}
@@ -0,0 +1,55 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// LeaderElectionConfig controls the leader election between several
+/// relayers that watch the same anchors.
+///
+/// The replicas compete for a lease in a shared Redis instance, only the
+/// holder of the lease signs and submits proposals, while the others keep
+/// watching the chains (so their caches stay warm) and take over once the
+/// lease expires.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct LeaderElectionConfig {
+    /// Whether the leader election is enabled or not.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The URL of the Redis instance that holds the lease.
+    #[serde(skip_serializing)]
+    pub redis_url: Option<url::Url>,
+    /// The key of the lease, replicas using the same key compete with
+    /// each other.
+    #[serde(default = "defaults::leader_lease_key")]
+    pub lease_key: String,
+    /// How long (in milliseconds) the lease is held without being renewed.
+    #[serde(default = "defaults::leader_lease_ttl")]
+    pub lease_ttl: u64,
+    /// The identifier of this replica, a random one is used if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+}
+
+impl Default for LeaderElectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            redis_url: None,
+            lease_key: defaults::leader_lease_key(),
+            lease_ttl: defaults::leader_lease_ttl(),
+            node_id: None,
+        }
+    }
+}
@@ -27,40 +27,87 @@
 //! for an example.
 //! * `substrate`: Substrate based networks and the configuration. See [config/local-substrate](./config/local-substrate) for an example.
 
+/// Admin API configuration
+pub mod admin;
 /// Generic anchor configuration
 pub mod anchor;
 /// Block poller configuration
 pub mod block_poller;
+/// Bridge registry backend configuration
+pub mod bridge_registry_backend;
+/// Circuit breaker configuration
+pub mod circuit_breaker;
 /// CLI configuration
 #[cfg(feature = "cli")]
 pub mod cli;
+/// HTTP response compression configuration
+pub mod compression;
 /// Module for all the default values.
 pub mod defaults;
 /// Event watcher configuration
 pub mod event_watcher;
 /// EVM configuration
 pub mod evm;
+/// Fee oracle configuration
+pub mod fee_oracle;
+/// Encrypted keystore configuration
+pub mod keystore;
+/// Leader election configuration
+pub mod leader_election;
+/// Transaction queue maintenance windows configuration
+pub mod maintenance;
+/// Peer relayers configuration
+pub mod peers;
+/// Probe events export configuration
+pub mod probe_export;
+/// On-chain relayer registry configuration
+pub mod registry;
+/// Relay commands replay protection configuration
+pub mod replay_protection;
+/// Data retention configuration
+pub mod retention;
+/// Sharding configuration
+pub mod shard;
+/// Shutdown configuration
+pub mod shutdown;
 /// Signing backend configuration
 pub mod signing_backend;
+/// Store encryption configuration
+pub mod store_encryption;
 /// Substrate configuration
 pub mod substrate;
 /// Utils for processing configuration
 pub mod utils;
 
+use admin::AdminConfig;
+use bridge_registry_backend::BridgeRegistryBackendConfig;
+use compression::CompressionConfig;
 use ethereum_types::Address;
 use evm::EvmChainConfig;
+use fee_oracle::FeeOracleConfig;
+use leader_election::LeaderElectionConfig;
+use maintenance::MaintenanceWindowConfig;
+use peers::PeersConfig;
+use probe_export::ProbeExportConfig;
+use registry::RelayerRegistryConfig;
+use replay_protection::ReplayProtectionConfig;
+use retention::RetentionConfig;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use shard::ShardConfig;
+use shutdown::ShutdownConfig;
 use signing_backend::ProposalSigningBackendConfig;
 use std::collections::{HashMap, HashSet};
+use store_encryption::StoreEncryptionConfig;
 use substrate::SubstrateConfig;
 use webb::evm::ethers::types::Chain;
 use webb_relayer_types::etherscan_api::EtherscanApiKey;
 
 /// WebbRelayerConfig is the configuration for the webb relayer.
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct WebbRelayerConfig {
-    /// WebSocket Server Port number
+    /// HTTP Server Port number
     ///
     /// default to 9955
     #[serde(default = "defaults::relayer_port", skip_serializing)]
@@ -72,11 +119,13 @@ pub struct WebbRelayerConfig {
     pub evm: HashMap<String, EvmChainConfig>,
     /// Etherscan API key configuration for evm based chains.
     #[serde(default, skip_serializing)]
+    #[schemars(with = "HashMap<String, EtherscanApiConfig>")]
     pub evm_etherscan: HashMap<Chain, EtherscanApiConfig>,
     /// ETH2 based networks and the configuration
     ///
     /// a map between chain name and its configuration
     #[cfg(feature = "eth2")]
+    #[schemars(skip)]
     #[serde(default)]
     pub eth2: HashMap<String, eth2_to_substrate_relay::config::Config>,
     /// Substrate based networks and the configuration.
@@ -101,6 +150,70 @@ pub struct WebbRelayerConfig {
     /// The type of the optional signing backend used for signing proposals. It can be None for pure Tx relayers
     #[serde(skip_serializing_if = "Option::is_none")]
     pub proposal_signing_backend: Option<ProposalSigningBackendConfig>,
+    /// The type of the optional bridge registry backend used for finding linked anchors
+    /// that are not configured explicitly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bridge_registry_backend: Option<BridgeRegistryBackendConfig>,
+    /// Admin API configuration
+    ///
+    /// disabled by default.
+    #[serde(default, skip_serializing)]
+    pub admin: AdminConfig,
+    /// Compression of the data query responses
+    ///
+    /// enabled by default.
+    #[serde(default, skip_serializing)]
+    pub compression: CompressionConfig,
+    /// Leader election between several relayer replicas
+    ///
+    /// disabled by default.
+    #[serde(default, skip_serializing)]
+    pub leader_election: LeaderElectionConfig,
+    /// Peer relayers to exchange data with
+    ///
+    /// disabled by default.
+    #[serde(default, skip_serializing)]
+    pub peers: PeersConfig,
+    /// Registration of the relayer in an on-chain registry
+    ///
+    /// disabled by default.
+    #[serde(default, skip_serializing)]
+    pub registry: RelayerRegistryConfig,
+    /// Source of the token prices and gas prices used for the fees
+    ///
+    /// CoinGecko and Etherscan by default.
+    #[serde(default, skip_serializing)]
+    pub fee_oracle: FeeOracleConfig,
+    /// Draining of the transaction queues on shutdown
+    ///
+    /// for 30 seconds by default.
+    #[serde(default, skip_serializing)]
+    pub shutdown: ShutdownConfig,
+    /// Assignment of the chains to several relayer instances
+    ///
+    /// disabled by default, all chains are served.
+    #[serde(default, skip_serializing)]
+    pub shard: ShardConfig,
+    /// Encryption at rest of the queued items and the leaves in the store
+    ///
+    /// disabled by default.
+    #[serde(default, skip_serializing)]
+    pub store_encryption: StoreEncryptionConfig,
+    /// How long the tx history, event hashes and proposals are kept
+    ///
+    /// forever by default.
+    #[serde(default, skip_serializing)]
+    pub retention: RetentionConfig,
+    /// Export of the probe events to a message broker
+    ///
+    /// disabled by default.
+    #[serde(default, skip_serializing)]
+    pub probe_export: ProbeExportConfig,
+    /// Signed envelopes protecting the relay commands from replays
+    ///
+    /// optional, valid for 5 minutes by default.
+    #[serde(default, skip_serializing)]
+    pub replay_protection: ReplayProtectionConfig,
 }
 
 impl WebbRelayerConfig {
@@ -117,17 +230,17 @@ impl WebbRelayerConfig {
         let check_features =
             self.features.governance_relay || self.features.private_tx_relay;
         let check_evm = check_features
-            && self
-                .evm
-                .iter()
-                .filter(|(_k, v)| v.enabled)
-                .all(|(_k, v)| v.private_key.is_some());
+            && self.evm.iter().filter(|(_k, v)| v.enabled).all(|(_k, v)| {
+                v.private_key.is_some()
+                    || v.hd_wallet.is_some()
+                    || v.keystore.is_some()
+            });
         let check_substrate = check_features
             && self
                 .substrate
                 .iter()
                 .filter(|(_k, v)| v.enabled)
-                .all(|(_k, v)| v.suri.is_some());
+                .all(|(_k, v)| v.suri.is_some() || v.keystore.is_some());
         (check_evm && check_substrate)
             .then_some(())
             .ok_or(webb_relayer_utils::Error::MissingSecrets)
@@ -135,7 +248,7 @@ impl WebbRelayerConfig {
 }
 
 /// FeaturesConfig is the configuration for running relayer with option.
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct FeaturesConfig {
     /// Enable data quering for leafs
@@ -144,6 +257,9 @@ pub struct FeaturesConfig {
     pub governance_relay: bool,
     /// Enable private tx relaying
     pub private_tx_relay: bool,
+    /// Enable the simulation of withdrawals, with `eth_call`
+    #[serde(default)]
+    pub simulation: bool,
 }
 
 impl Default for FeaturesConfig {
@@ -152,18 +268,20 @@ impl Default for FeaturesConfig {
             data_query: true,
             governance_relay: true,
             private_tx_relay: true,
+            simulation: false,
         }
     }
 }
 
 /// Configuration to add etherscan API key
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct EtherscanApiConfig {
     /// Chain Id
     pub chain_id: u32,
     /// A wrapper type around the `String` to allow reading it from the env.
     #[serde(skip_serializing)]
+    #[schemars(with = "String")]
     pub api_key: EtherscanApiKey,
     /// An optional URL to use for the Etherscan API instead of the default.
     ///
@@ -174,7 +292,7 @@ pub struct EtherscanApiConfig {
 }
 
 /// TxQueueConfig is the configuration for the TxQueue.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct TxQueueConfig {
     /// Maximum number of milliseconds to wait before dequeuing a transaction from
@@ -182,6 +300,33 @@ pub struct TxQueueConfig {
     pub max_sleep_interval: u64,
     /// Polling interval in milliseconds to wait before checking pending tx state on chain.
     pub polling_interval: u64,
+    /// Detection of broadcast transactions that are not getting mined.
+    ///
+    /// Only used by the EVM tx queue, disabled if not set.
+    #[serde(default)]
+    pub stuck_tx: Option<StuckTxConfig>,
+    /// How the nonces of the sent transactions are picked.
+    ///
+    /// Only used by the EVM tx queue.
+    #[serde(default)]
+    pub nonce_strategy: NonceStrategy,
+    /// Recurring windows during which the queue holds its items.
+    #[serde(default)]
+    pub maintenance_windows: Vec<MaintenanceWindowConfig>,
+    /// Maximum number of transactions of a single resource (contract) that
+    /// may wait or be processed in the queue at once, unlimited if not set.
+    ///
+    /// Only used by the EVM tx queue, for relayed transactions.
+    #[serde(default)]
+    pub max_in_flight_per_resource: Option<u32>,
+}
+
+impl TxQueueConfig {
+    /// Returns the end of the maintenance window the queue is in at `now`,
+    /// both in seconds since the unix epoch, if it is in one.
+    pub fn maintenance_until(&self, now: u64) -> Option<u64> {
+        maintenance::maintenance_until(&self.maintenance_windows, now)
+    }
 }
 
 impl Default for TxQueueConfig {
@@ -189,12 +334,64 @@ impl Default for TxQueueConfig {
         Self {
             max_sleep_interval: 10_000,
             polling_interval: 12_000,
+            stuck_tx: None,
+            nonce_strategy: NonceStrategy::default(),
+            maintenance_windows: Vec::new(),
+            max_in_flight_per_resource: None,
         }
     }
 }
 
+/// StuckTxConfig is the configuration for detecting broadcast transactions
+/// that are not getting mined, and replacing them with a higher gas price.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct StuckTxConfig {
+    /// Number of blocks after which a broadcast transaction that was not
+    /// mined yet is considered stuck.
+    pub after_blocks: u64,
+    /// By how many percent the gas price of a stuck transaction is raised
+    /// when it is replaced.
+    ///
+    /// Most nodes only accept replacements that raise it by at least 10%.
+    #[serde(default = "defaults::stuck_tx_gas_price_bump_percent")]
+    pub gas_price_bump_percent: u64,
+    /// Maximum number of times a stuck transaction is replaced, after which
+    /// the relayer keeps waiting for it.
+    #[serde(default = "defaults::stuck_tx_max_replacements")]
+    pub max_replacements: u32,
+}
+
+/// NonceStrategy is how the EVM tx queue picks the nonce of the
+/// transactions it sends.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum NonceStrategy {
+    /// The pending nonce of the relayer is fetched from the chain for every
+    /// transaction.
+    #[default]
+    Remote,
+    /// The nonce is fetched from the chain once, then tracked locally, and
+    /// only fetched again after a transaction failed to be sent or mined.
+    Local,
+    /// The nonce is tracked locally like with `Local`, and reconciled with
+    /// the pending nonce of the chain every minute, which wins when they
+    /// differ.
+    Hybrid,
+}
+
 /// UnlistedAssetConfig is the configuration for the assets that are not listed on any exchange.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct UnlistedAssetConfig {
     /// The Price of the asset in USD.
@@ -255,4 +452,15 @@ mod tests {
                 .expect("Failed to set current directory");
         }
     }
+
+    #[test]
+    fn config_schema_uses_the_config_files_names() {
+        let schema = serde_json::to_value(utils::config_schema()).unwrap();
+        let definitions = &schema["definitions"];
+        let evm = &definitions["EvmChainConfig"]["properties"];
+        assert!(evm.get("http-endpoint").is_some());
+        assert!(evm.get("httpEndpoint").is_none());
+        assert_eq!(evm["ws-endpoint"]["type"], "string");
+        assert_eq!(definitions["TimeOfDay"]["type"], "string");
+    }
 }
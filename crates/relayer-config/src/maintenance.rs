@@ -0,0 +1,218 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, StringValidation};
+
+use super::*;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// The longest a maintenance window can last, a week, in minutes.
+pub const MAX_MAINTENANCE_WINDOW_DURATION: u64 = 7 * 24 * 60;
+
+/// MaintenanceWindowConfig is a recurring window, in UTC, during which the
+/// transaction queue of a chain holds its items instead of sending them, so
+/// operators can do RPC or node maintenance without failed broadcasts.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct MaintenanceWindowConfig {
+    /// The days of the week the window starts on, every day if empty.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// The time of the day the window starts at, `HH:MM` in UTC.
+    pub start: TimeOfDay,
+    /// How long the window lasts, in minutes.
+    pub duration: u64,
+}
+
+impl MaintenanceWindowConfig {
+    /// Returns the end of the occurrence of the window that contains `now`,
+    /// both in seconds since the unix epoch, or `None` if `now` is not
+    /// within the window.
+    pub fn active_until(&self, now: u64) -> Option<u64> {
+        let today = now / SECONDS_PER_DAY;
+        let duration = self.duration.min(MAX_MAINTENANCE_WINDOW_DURATION) * 60;
+        // an occurrence lasts a week at most, so it started a week ago at
+        // the earliest.
+        (0..=7)
+            .filter_map(|days_ago| today.checked_sub(days_ago))
+            .filter(|day| self.starts_on(*day))
+            .map(|day| day * SECONDS_PER_DAY + self.start.seconds())
+            .filter(|start| *start <= now && now < start + duration)
+            .map(|start| start + duration)
+            .max()
+    }
+
+    fn starts_on(&self, day: u64) -> bool {
+        self.days.is_empty()
+            || self.days.contains(&Weekday::from_days_since_epoch(day))
+    }
+}
+
+/// Returns the end of the latest of the `windows` that contain `now`, both
+/// in seconds since the unix epoch, or `None` if `now` is not within any of
+/// them.
+pub fn maintenance_until(
+    windows: &[MaintenanceWindowConfig],
+    now: u64,
+) -> Option<u64> {
+    windows.iter().filter_map(|w| w.active_until(now)).max()
+}
+
+/// A day of the week.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    /// Monday
+    Mon,
+    /// Tuesday
+    Tue,
+    /// Wednesday
+    Wed,
+    /// Thursday
+    Thu,
+    /// Friday
+    Fri,
+    /// Saturday
+    Sat,
+    /// Sunday
+    Sun,
+}
+
+impl Weekday {
+    /// The day of the week of a day, counted in days since the unix epoch,
+    /// which was a Thursday.
+    pub fn from_days_since_epoch(days: u64) -> Self {
+        use Weekday::*;
+        [Thu, Fri, Sat, Sun, Mon, Tue, Wed][(days % 7) as usize]
+    }
+}
+
+/// A time of the day, parsed from and serialized to `HH:MM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+}
+
+impl TimeOfDay {
+    /// The number of seconds since midnight.
+    pub fn seconds(&self) -> u64 {
+        u64::from(self.hour) * 3600 + u64::from(self.minute) * 60
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("invalid time of day {value}, expected HH:MM");
+        let (hour, minute) = value.split_once(':').ok_or_else(invalid)?;
+        let hour: u8 = hour.parse().map_err(|_| invalid())?;
+        let minute: u8 = minute.parse().map_err(|_| invalid())?;
+        if hour > 23 || minute > 59 {
+            return Err(invalid());
+        }
+        Ok(Self { hour, minute })
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(value: TimeOfDay) -> Self {
+        format!("{:02}:{:02}", value.hour, value.minute)
+    }
+}
+
+impl JsonSchema for TimeOfDay {
+    fn schema_name() -> String {
+        String::from("TimeOfDay")
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(String::from(
+                    "^([01]?[0-9]|2[0-3]):[0-5]?[0-9]$",
+                )),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(
+        days: Vec<Weekday>,
+        start: &str,
+        duration: u64,
+    ) -> MaintenanceWindowConfig {
+        MaintenanceWindowConfig {
+            days,
+            start: TimeOfDay::try_from(String::from(start)).unwrap(),
+            duration,
+        }
+    }
+
+    #[test]
+    fn time_of_day_should_parse() {
+        let time = TimeOfDay::try_from(String::from("02:30")).unwrap();
+        assert_eq!(time.seconds(), 2 * 3600 + 30 * 60);
+        assert_eq!(String::from(time), "02:30");
+        assert!(TimeOfDay::try_from(String::from("24:00")).is_err());
+        assert!(TimeOfDay::try_from(String::from("2h30")).is_err());
+    }
+
+    #[test]
+    fn maintenance_windows_should_work() {
+        // 2023-05-20, a Saturday, at midnight UTC.
+        let saturday = 1_684_540_800;
+        assert_eq!(
+            Weekday::from_days_since_epoch(saturday / SECONDS_PER_DAY),
+            Weekday::Sat
+        );
+        let daily = window(vec![], "02:00", 60);
+        assert_eq!(daily.active_until(saturday), None);
+        assert_eq!(
+            daily.active_until(saturday + 2 * 3600),
+            Some(saturday + 3 * 3600)
+        );
+        assert_eq!(daily.active_until(saturday + 3 * 3600), None);
+
+        // from Sunday 23:00 to Monday 01:00.
+        let weekly = window(vec![Weekday::Sun], "23:00", 120);
+        let sunday = saturday + SECONDS_PER_DAY;
+        assert_eq!(weekly.active_until(saturday + 23 * 3600), None);
+        assert_eq!(
+            weekly.active_until(sunday + SECONDS_PER_DAY + 1800),
+            Some(sunday + SECONDS_PER_DAY + 3600)
+        );
+
+        let windows = [daily, weekly];
+        assert_eq!(
+            maintenance_until(&windows, saturday + 2 * 3600 + 60),
+            Some(saturday + 3 * 3600)
+        );
+        assert_eq!(maintenance_until(&windows, saturday + 12 * 3600), None);
+    }
+}
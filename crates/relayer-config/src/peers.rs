@@ -0,0 +1,49 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// PeersConfig lists the peer relayers this relayer exchanges data with.
+///
+/// Peers are other relayers watching the same anchors. The leaves they serve
+/// are only trusted once their merkle root is known by the anchor on chain.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct PeersConfig {
+    /// The base URLs of the peer relayers, e.g. `https://relayer.example.com`.
+    #[serde(default, skip_serializing)]
+    pub urls: Vec<url::Url>,
+    /// Whether the empty leaves caches are bootstrapped from the peers on
+    /// startup, instead of replaying the whole history of the chain.
+    #[serde(default)]
+    pub sync: bool,
+    /// Whether leaves requests for a range the local cache has not reached
+    /// yet are forwarded to the peers, e.g. while backfilling.
+    #[serde(default)]
+    pub fallback_query: bool,
+    /// How long (in milliseconds) to wait for a peer to respond.
+    #[serde(default = "defaults::peer_request_timeout")]
+    pub request_timeout: u64,
+}
+
+impl Default for PeersConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            sync: false,
+            fallback_query: false,
+            request_timeout: defaults::peer_request_timeout(),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// RelayerRegistryConfig controls the periodic registration of the relayer
+/// in an on-chain registry contract.
+///
+/// Each heartbeat publishes the public endpoint of the relayer, the
+/// resources it relays for and their fee parameters, so dApps can discover
+/// the live relayers on chain instead of relying on a centralized list.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct RelayerRegistryConfig {
+    /// Whether the heartbeats are published or not.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The chain id of the chain the registry contract is deployed on.
+    #[serde(default)]
+    pub chain_id: u32,
+    /// The address of the registry contract.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub address: Option<Address>,
+    /// The public URL dApps reach this relayer at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<url::Url>,
+    /// How often (in milliseconds) a heartbeat is published.
+    #[serde(default = "defaults::registry_heartbeat_interval")]
+    pub interval: u64,
+}
+
+impl Default for RelayerRegistryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            chain_id: 0,
+            address: None,
+            endpoint: None,
+            interval: defaults::registry_heartbeat_interval(),
+        }
+    }
+}
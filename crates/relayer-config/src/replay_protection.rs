@@ -0,0 +1,40 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// ReplayProtectionConfig controls the signed envelopes of the relay
+/// commands, which keep captured commands from being relayed again.
+///
+/// A command carrying an envelope is always verified, this only controls
+/// whether the envelope is required and how long it stays valid.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct ReplayProtectionConfig {
+    /// Whether the relay commands without a signed envelope are rejected.
+    #[serde(default)]
+    pub required: bool,
+    /// How long (in seconds) a signed envelope is valid after its timestamp.
+    #[serde(default = "defaults::replay_protection_max_age")]
+    pub max_age: u64,
+}
+
+impl Default for ReplayProtectionConfig {
+    fn default() -> Self {
+        Self {
+            required: false,
+            max_age: defaults::replay_protection_max_age(),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// RetentionConfig bounds how long the history kept by the relayer is
+/// stored, so long-running relayers keep no more data than they need.
+///
+/// Every window is in milliseconds, and the history is kept forever when
+/// its window is not set.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct RetentionConfig {
+    /// How long the relayed transactions, the relay jobs, and the processed,
+    /// failed or expired items of the transaction queues (with their tx
+    /// hashes and batches), are kept.
+    #[serde(default)]
+    pub tx_history: Option<u64>,
+    /// How long the hashes of the processed events are kept.
+    #[serde(default)]
+    pub event_hashes: Option<u64>,
+    /// How long the processed, failed or expired items of the proposal
+    /// queues, and the Tangle jobs, are kept.
+    #[serde(default)]
+    pub proposals: Option<u64>,
+    /// How often (in milliseconds) the expired history is purged.
+    #[serde(default = "defaults::retention_purge_interval")]
+    pub interval: u64,
+}
+
+impl RetentionConfig {
+    /// Whether any of the retention windows is set.
+    pub fn is_enabled(&self) -> bool {
+        self.tx_history.is_some()
+            || self.event_hashes.is_some()
+            || self.proposals.is_some()
+    }
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            tx_history: None,
+            event_hashes: None,
+            proposals: None,
+            interval: defaults::retention_purge_interval(),
+        }
+    }
+}
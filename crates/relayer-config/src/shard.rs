@@ -0,0 +1,79 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// ShardConfig splits the chains of a shared config file between several
+/// relayer instances.
+///
+/// Every instance loads the same config, with its own `instance` (usually
+/// set through the `WEBB_SHARD_INSTANCE` environment variable), and only
+/// serves the chains assigned to it.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct ShardConfig {
+    /// The identity of this relayer instance.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// The chain ids served by each instance, by instance identity.
+    #[serde(default)]
+    pub assignments: HashMap<String, Vec<u32>>,
+}
+
+impl ShardConfig {
+    /// Returns the chain ids assigned to this instance, or `None` if all
+    /// the chains are served by it.
+    ///
+    /// Fails if this instance is missing from the assignments.
+    pub fn assigned_chains(
+        &self,
+    ) -> webb_relayer_utils::Result<Option<&[u32]>> {
+        let Some(instance) = &self.instance else {
+            return Ok(None);
+        };
+        if self.assignments.is_empty() {
+            return Ok(None);
+        }
+        match self.assignments.get(instance) {
+            Some(chains) => Ok(Some(chains)),
+            None => Err(webb_relayer_utils::Error::Generic(
+                "the shard instance has no chains assigned to it",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assigned_chains_should_work() {
+        let mut config = ShardConfig::default();
+        assert_eq!(config.assigned_chains().unwrap(), None);
+
+        config.instance = Some(String::from("relayer-a"));
+        // without assignments, the instance serves all the chains.
+        assert_eq!(config.assigned_chains().unwrap(), None);
+
+        config.assignments = HashMap::from([
+            (String::from("relayer-a"), vec![1, 5]),
+            (String::from("relayer-b"), vec![1287]),
+        ]);
+        assert_eq!(config.assigned_chains().unwrap(), Some(&[1, 5][..]));
+
+        config.instance = Some(String::from("relayer-c"));
+        assert!(config.assigned_chains().is_err());
+    }
+}
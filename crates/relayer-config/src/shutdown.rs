@@ -0,0 +1,37 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// ShutdownConfig controls how the relayer shuts down on `SIGTERM` or
+/// `SIGINT`.
+///
+/// The relayer stops accepting new relay requests and keeps processing its
+/// transaction queues until they are empty, or the drain timeout passes.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct ShutdownConfig {
+    /// How long (in milliseconds) the transaction queues are drained for,
+    /// before the relayer exits anyway.
+    #[serde(default = "defaults::shutdown_drain_timeout")]
+    pub drain_timeout: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_timeout: defaults::shutdown_drain_timeout(),
+        }
+    }
+}
@@ -3,7 +3,7 @@ use webb_relayer_types::private_key::PrivateKey;
 use super::*;
 
 /// Enumerates the supported different signing backends configurations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum ProposalSigningBackendConfig {
     /// Uses signing rules contract to vote and submit proposals for signing.
@@ -13,20 +13,22 @@ pub enum ProposalSigningBackendConfig {
 }
 
 /// DkgProposalSigningBackendConfig represents the configuration for the DKG signing backend.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct DkgProposalSigningBackendConfig {
     /// The address of this contract on this chain.
+    #[schemars(with = "String")]
     pub address: Address,
     /// Phase1 Job Id
     pub phase1_job_id: [u8; 32],
 }
 
 /// MockedSigningBackendConfig represents the configuration for the Mocked signing backend.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct MockedProposalSigningBackendConfig {
     /// The private key of the current Governor.
     #[serde(skip_serializing)]
+    #[schemars(with = "String")]
     pub private_key: PrivateKey,
 }
@@ -0,0 +1,32 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use webb_relayer_types::private_key::PrivateKey;
+
+use super::*;
+
+/// StoreEncryptionConfig enables the encryption at rest of the store values
+/// that may contain sensitive payloads, i.e. the queued transactions, the
+/// queued proposals and the leaves.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct StoreEncryptionConfig {
+    /// The 256-bit key used to encrypt the store values.
+    ///
+    /// Like the private keys, it can be read from an environment variable,
+    /// a file, or the output of a command (i.e. a KMS client).
+    #[serde(skip_serializing)]
+    #[schemars(with = "Option<String>")]
+    pub key: Option<PrivateKey>,
+}
@@ -2,9 +2,10 @@ use super::*;
 use webb_relayer_types::{rpc_url::RpcUrl, suri::Suri};
 
 use crate::event_watcher::EventsWatcherConfig;
+use crate::keystore::KeystoreConfig;
 
 /// SubstrateConfig is the relayer configuration for the Substrate based networks.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct SubstrateConfig {
     /// String that groups configuration for this chain on a human-readable name.
@@ -14,10 +15,16 @@ pub struct SubstrateConfig {
     pub enabled: bool,
     /// Http(s) Endpoint for quick Req/Res
     #[serde(skip_serializing)]
+    #[schemars(with = "String")]
     pub http_endpoint: RpcUrl,
     /// Websocket Endpoint for long living connections
     #[serde(skip_serializing)]
+    #[schemars(with = "String")]
     pub ws_endpoint: RpcUrl,
+    /// Connects to the chain through an embedded light client instead of
+    /// the `ws-endpoint`, so no trusted node is needed.
+    #[serde(skip_serializing, default)]
+    pub light_client: Option<LightClientConfig>,
     /// Block Explorer for this Substrate node.
     ///
     /// Optional, and only used for printing a clickable links
@@ -56,7 +63,12 @@ pub struct SubstrateConfig {
     ///
     /// `None` is returned if no matches are found.
     #[serde(skip_serializing)]
+    #[schemars(with = "Option<String>")]
     pub suri: Option<Suri>,
+    /// Encrypted keystore holding the sr25519 seed of this account, used
+    /// when no `suri` is configured.
+    #[serde(skip_serializing, default)]
+    pub keystore: Option<KeystoreConfig>,
     /// Supported pallets over this substrate node.
     #[serde(default)]
     pub pallets: Vec<Pallet>,
@@ -65,8 +77,32 @@ pub struct SubstrateConfig {
     pub tx_queue: TxQueueConfig,
 }
 
+/// LightClientConfig is the configuration of the embedded smoldot light
+/// client of a Substrate chain.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct LightClientConfig {
+    /// Path to the JSON chain spec of the chain.
+    pub chain_spec: std::path::PathBuf,
+    /// Bootnodes to sync from, replacing the ones of the chain spec.
+    #[serde(default)]
+    pub bootnodes: Vec<String>,
+}
+
+/// Linked anchor config for Substrate based target system
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct SubstrateLinkedAnchorConfig {
+    /// The chain Id
+    pub chain_id: u32,
+    /// The index of the pallet that holds the merkle tree.
+    pub pallet: u8,
+    /// The Merkle tree Id.
+    pub tree_id: u32,
+}
+
 /// Enumerates the supported pallets configurations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "pallet")]
 pub enum Pallet {
     /// Jobs pallet
@@ -74,7 +110,7 @@ pub enum Pallet {
 }
 
 /// JobsPalletConfig represents the configuration for the Jobs pallet.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct JobsPalletConfig {
     /// Controls the events watcher
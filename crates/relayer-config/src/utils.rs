@@ -108,6 +108,15 @@ pub fn load<P: AsRef<Path>>(
     parse_from_files(&search_config_files(path)?)
 }
 
+/// The JSON Schema of the configuration files, generated from the
+/// [`WebbRelayerConfig`] types.
+///
+/// Like the files themselves, it uses the kebab-case names of the fields,
+/// so it can be used to validate them and by editors for completion.
+pub fn config_schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(WebbRelayerConfig)
+}
+
 /// The postloading_process exists to validate configuration and standardize
 /// the format of the configuration
 pub fn postloading_process(
@@ -115,6 +124,27 @@ pub fn postloading_process(
 ) -> webb_relayer_utils::Result<WebbRelayerConfig> {
     tracing::trace!("Checking configration sanity ...");
 
+    // disable the chains served by other instances of the shard.
+    if let Some(assigned) = config.shard.assigned_chains()? {
+        let assigned: HashSet<u32> = assigned.iter().copied().collect();
+        let chain_ids = config
+            .evm
+            .values_mut()
+            .map(|chain| (chain.chain_id, &mut chain.enabled))
+            .chain(
+                config
+                    .substrate
+                    .values_mut()
+                    .map(|node| (node.chain_id, &mut node.enabled)),
+            );
+        for (chain_id, enabled) in chain_ids {
+            if *enabled && !assigned.contains(&chain_id) {
+                tracing::debug!(chain_id, "Chain is served by another shard");
+                *enabled = false;
+            }
+        }
+    }
+
     // make all chain names lower case
     // 1. drain everything, and take enabled chains.
     let old_evm = config
@@ -137,7 +167,11 @@ pub fn postloading_process(
     }
 
     //Chain list is used to validate if linked anchor configuration is provided to the relayer.
-    let mut chain_list: HashSet<webb_proposals::TypedChainId> = HashSet::new();
+    let mut chain_list: HashSet<webb_proposals::TypedChainId> = config
+        .substrate
+        .values()
+        .map(|node| webb_proposals::TypedChainId::Substrate(node.chain_id))
+        .collect();
     // Convert linked anchor to Raw ResourceId type for evm chains
     for (_, network_chain) in config.evm.iter_mut() {
         let typed_chain_id =
@@ -214,23 +248,28 @@ pub fn postloading_process(
                             );
                         } else {
                             for linked_anchor in linked_anchors {
-                                match linked_anchor {
-                                    LinkedAnchorConfig::Raw(raw_resource) => {
-                                        let bytes: [u8; 32] = raw_resource.resource_id.into();
-                                        let resource_id = webb_proposals::ResourceId::from(bytes);
-                                        let typed_chain_id = resource_id.typed_chain_id();
-                                        if !chain_list.contains(&typed_chain_id)
-                                        {
-                                            tracing::warn!("!!WARNING!!: Type: Evm, chain with id {} is not defined in the config.
-                                                which is required by the Anchor Contract ({}) defined on {} chain.
-                                                Please, define it manually, to allow the relayer to work properly.",
-                                                typed_chain_id.chain_id(),
-                                                anchor.common.address,
-                                                chain_id
-                                            );
+                                let resource_id =
+                                    linked_anchor.to_resource_id();
+                                let typed_chain_id =
+                                    resource_id.typed_chain_id();
+                                if !chain_list.contains(&typed_chain_id) {
+                                    // use the well-known chain name, if any, to make the warning more helpful.
+                                    let chain_name = match typed_chain_id {
+                                        webb_proposals::TypedChainId::Evm(id) => {
+                                            webb_chains_info::chain_info_by_chain_id(u64::from(id))
+                                                .map(|info| info.name)
                                         }
-                                   }
-                                   _=> unreachable!("Convert all linked anchor to Raw ResourceId type")
+                                        _ => None,
+                                    }
+                                    .unwrap_or("Unknown");
+                                    tracing::warn!("!!WARNING!!: chain {:?} ({}) is not defined in the config.
+                                        which is required by the Anchor Contract ({}) defined on {} chain.
+                                        Please, define it manually, to allow the relayer to work properly.",
+                                        typed_chain_id,
+                                        chain_name,
+                                        anchor.common.address,
+                                        chain_id
+                                    );
                                 }
                             }
                         }
@@ -240,6 +279,106 @@ pub fn postloading_process(
         }
     }
 
+    // a multiplier below 1 would send transactions bound to run out of gas.
+    let invalid_multiplier = config
+        .evm
+        .values()
+        .flat_map(|chain| &chain.contracts)
+        .map(|c| c.common().gas_limit_multiplier)
+        .any(|multiplier| multiplier.is_nan() || multiplier < 1.0);
+    if invalid_multiplier {
+        return Err(webb_relayer_utils::Error::Generic(
+            "the gas-limit-multiplier of a contract must be at least 1",
+        ));
+    }
+
+    // an empty window would never hold the queue, and a longer one than a
+    // week would overlap its next occurrence.
+    let invalid_maintenance_window = config
+        .evm
+        .values()
+        .map(|chain| &chain.tx_queue)
+        .chain(config.substrate.values().map(|node| &node.tx_queue))
+        .flat_map(|tx_queue| &tx_queue.maintenance_windows)
+        .any(|window| {
+            window.duration == 0
+                || window.duration
+                    > crate::maintenance::MAX_MAINTENANCE_WINDOW_DURATION
+        });
+    if invalid_maintenance_window {
+        return Err(webb_relayer_utils::Error::Generic(
+            "a maintenance window must last between a minute and a week",
+        ));
+    }
+
+    // no leaf index satisfies an every-nth-leaf of zero.
+    let invalid_propagation = config
+        .evm
+        .values()
+        .flat_map(|chain| &chain.contracts)
+        .any(|c| {
+            matches!(c, Contract::VAnchor(cfg)
+                if cfg.propagation_policy.every_nth_leaf == 0)
+        });
+    if invalid_propagation {
+        return Err(webb_relayer_utils::Error::Generic(
+            "the every-nth-leaf of a propagation-policy must be at least 1",
+        ));
+    }
+
+    // a subsidy without recipients would never apply.
+    let empty_subsidy = config
+        .evm
+        .values()
+        .filter_map(|chain| chain.relayer_fee_config.subsidy.as_ref())
+        .any(|subsidy| {
+            !subsidy.all_recipients && subsidy.recipients.is_empty()
+        });
+    if empty_subsidy {
+        return Err(webb_relayer_utils::Error::Generic(
+            "a subsidy requires recipients, or all-recipients to be set",
+        ));
+    }
+
+    // the admin routes are served on the public API, never unauthenticated.
+    let missing_admin_token = config
+        .admin
+        .api_token
+        .as_ref()
+        .map_or(true, |token| token.is_empty());
+    if config.admin.enabled && missing_admin_token {
+        return Err(webb_relayer_utils::Error::Generic(
+            "the admin API requires the api-token to be configured",
+        ));
+    }
+
+    // the lease of the leader election lives in redis, so it is required.
+    if config.leader_election.enabled
+        && config.leader_election.redis_url.is_none()
+    {
+        return Err(webb_relayer_utils::Error::Generic(
+            "leader-election requires the redis-url to be configured",
+        ));
+    }
+
+    // the events are published with the plain text protocol of NATS.
+    let invalid_nats_url = matches!(
+        &config.probe_export.nats_url,
+        Some(url) if url.scheme() != "nats" || url.host_str().is_none()
+    );
+    if invalid_nats_url {
+        return Err(webb_relayer_utils::Error::Generic(
+            "the nats-url of the probe-export must be a nats:// url",
+        ));
+    }
+
+    // an envelope valid for no time at all would reject every command.
+    if config.replay_protection.max_age == 0 {
+        return Err(webb_relayer_utils::Error::Generic(
+            "the max-age of the replay-protection must be greater than zero",
+        ));
+    }
+
     tracing::trace!(
         "postloaded config: {}",
         serde_json::to_string_pretty(&config)?
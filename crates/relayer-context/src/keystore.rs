@@ -0,0 +1,124 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use webb::evm::ethers::types::H256;
+use webb_relayer_config::keystore::KeystoreConfig;
+use webb_relayer_config::WebbRelayerConfig;
+use webb_relayer_types::private_key::PrivateKey;
+use webb_relayer_types::suri::Suri;
+
+/// Decrypts all the keystores referenced in the config, and fills in the
+/// `private_key` (for EVM chains) and `suri` (for Substrate nodes) with the
+/// decrypted secrets, so that the rest of the relayer does not need to know
+/// where the secrets came from.
+///
+/// Explicitly configured secrets always take precedence over keystores.
+pub fn decrypt_keystores(
+    config: &mut WebbRelayerConfig,
+) -> webb_relayer_utils::Result<()> {
+    for chain in config.evm.values_mut() {
+        let Some(keystore) = chain.keystore.as_ref() else {
+            continue;
+        };
+        if chain.private_key.is_some() {
+            continue;
+        }
+        let secret = decrypt(keystore)?;
+        if secret.len() != 32 {
+            return Err(webb_relayer_utils::Error::KeystoreError(format!(
+                "expected a 32 bytes private key in {}",
+                keystore.path.display()
+            )));
+        }
+        chain.private_key = Some(PrivateKey::from(H256::from_slice(&secret)));
+        tracing::debug!(
+            chain_id = %chain.chain_id,
+            "Loaded the private key from the encrypted keystore",
+        );
+    }
+
+    for node in config.substrate.values_mut() {
+        let Some(keystore) = node.keystore.as_ref() else {
+            continue;
+        };
+        if node.suri.is_some() {
+            continue;
+        }
+        let secret = decrypt(keystore)?;
+        let seed: [u8; 32] = secret.as_slice().try_into().map_err(|_| {
+            webb_relayer_utils::Error::KeystoreError(format!(
+                "expected a 32 bytes sr25519 seed in {}",
+                keystore.path.display()
+            ))
+        })?;
+        let pair =
+            subxt_signer::sr25519::Keypair::from_seed(seed).map_err(|e| {
+                webb_relayer_utils::Error::KeystoreError(e.to_string())
+            })?;
+        node.suri = Some(Suri(pair));
+        tracing::debug!(
+            chain_id = %node.chain_id,
+            "Loaded the sr25519 seed from the encrypted keystore",
+        );
+    }
+    Ok(())
+}
+
+/// Reads the passphrase (from env, or by prompting the user) and
+/// decrypts the keystore file.
+fn decrypt(keystore: &KeystoreConfig) -> webb_relayer_utils::Result<Vec<u8>> {
+    let passphrase = match &keystore.passphrase_env {
+        Some(var) => std::env::var(var).map_err(|e| {
+            webb_relayer_utils::Error::KeystoreError(format!(
+                "error while loading this env {var}: {e}"
+            ))
+        })?,
+        None => rpassword::prompt_password(format!(
+            "Enter the passphrase for keystore {}: ",
+            keystore.path.display()
+        ))?,
+    };
+    eth_keystore::decrypt_key(&keystore.path, passphrase).map_err(|e| {
+        webb_relayer_utils::Error::KeystoreError(format!(
+            "failed to decrypt keystore {}: {e}",
+            keystore.path.display()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_decrypt_v3_keystore() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = [7u8; 32];
+        let name = eth_keystore::encrypt_key(
+            dir.path(),
+            &mut rand::rngs::OsRng,
+            secret,
+            "hunter2",
+            None,
+        )
+        .unwrap();
+        std::env::set_var("WEBB_TEST_KEYSTORE_PASSPHRASE", "hunter2");
+        let keystore = KeystoreConfig {
+            path: dir.path().join(name),
+            passphrase_env: Some(String::from("WEBB_TEST_KEYSTORE_PASSPHRASE")),
+        };
+        let decrypted = decrypt(&keystore).unwrap();
+        assert_eq!(decrypted, secret.to_vec());
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::time::MissedTickBehavior;
+use webb_relayer_utils::Error;
+
+use crate::RelayerContext;
+
+/// Holds (or renews) the lease if it is free or already ours, atomically.
+///
+/// Returns `1` if we hold the lease, `0` if another replica does.
+const HOLD_LEASE_SCRIPT: &str = r"
+local holder = redis.call('GET', KEYS[1])
+if holder == false then
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+    return 1
+elseif holder == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return 1
+end
+return 0
+";
+
+/// Starts the leader election, if it is enabled in the config.
+///
+/// The replicas compete for a lease in Redis, and the holder is the leader.
+/// The leader renews its lease three times per TTL, and steps down as soon
+/// as it fails to, so by the time the lease expires and another replica
+/// takes over, it has already stopped signing and submitting proposals.
+pub fn start(ctx: &RelayerContext) {
+    let config = &ctx.config.leader_election;
+    if !config.enabled {
+        return;
+    }
+    // the node id must survive restarts of the task, so that we can
+    // reclaim our own lease.
+    let node_id = config
+        .node_id
+        .clone()
+        .or_else(|| ctx.config.shard.instance.clone())
+        .unwrap_or_else(|| {
+            format!("relayer-{:016x}", rand::thread_rng().gen::<u64>())
+        });
+    tracing::info!(%node_id, "Starting the leader election");
+    let my_ctx = ctx.clone();
+    ctx.supervisor().spawn("leader-election", move || {
+        run(my_ctx.clone(), node_id.clone())
+    });
+}
+
+async fn run(
+    ctx: RelayerContext,
+    node_id: String,
+) -> webb_relayer_utils::Result<()> {
+    let leadership = ctx.leadership().clone();
+    let result = hold_lease(&ctx, &node_id).await;
+    // whatever went wrong, we can no longer be sure we hold the lease.
+    if leadership.set_leader(false) {
+        tracing::warn!(%node_id, "Stepped down from the leadership");
+    }
+    result
+}
+
+async fn hold_lease(
+    ctx: &RelayerContext,
+    node_id: &str,
+) -> webb_relayer_utils::Result<()> {
+    let config = &ctx.config.leader_election;
+    let redis_url = config.redis_url.as_ref().ok_or(Error::Generic(
+        "leader-election requires the redis-url to be configured",
+    ))?;
+    let ttl = config.lease_ttl.max(3);
+    let step = Duration::from_millis(ttl / 3);
+    let client = redis::Client::open(redis_url.as_str())
+        .map_err(|e| Error::LeaderElectionError(e.to_string()))?;
+    let mut conn = tokio::time::timeout(step, client.get_async_connection())
+        .await
+        .map_err(|_| {
+            Error::LeaderElectionError("connecting to redis timed out".into())
+        })?
+        .map_err(|e| Error::LeaderElectionError(e.to_string()))?;
+    let script = redis::Script::new(HOLD_LEASE_SCRIPT);
+    let leadership = ctx.leadership();
+    let mut interval = tokio::time::interval(step);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        webb_relayer_utils::heartbeat::beat();
+        let held: i32 = tokio::time::timeout(
+            step,
+            script
+                .key(&config.lease_key)
+                .arg(node_id)
+                .arg(ttl)
+                .invoke_async(&mut conn),
+        )
+        .await
+        .map_err(|_| {
+            Error::LeaderElectionError("renewing the lease timed out".into())
+        })?
+        .map_err(|e| Error::LeaderElectionError(e.to_string()))?;
+        let is_leader = held == 1;
+        if leadership.set_leader(is_leader) {
+            if is_leader {
+                tracing::info!(%node_id, "Became the leader");
+            } else {
+                tracing::warn!(%node_id, "Lost the leadership");
+            }
+        }
+    }
+}
@@ -16,12 +16,13 @@
 //! # Relayer Context Module 🕸️
 //!
 //! A module for managing the context of the relayer.
+use std::collections::HashSet;
+use std::sync::RwLock;
 use std::time::Duration;
 use std::{collections::HashMap, sync::Arc};
-use tokio::sync::{broadcast, Mutex};
-use webb::substrate::subxt::OnlineClient;
+use tokio::sync::{broadcast, Mutex, OwnedSemaphorePermit, Semaphore};
 use webb_relayer_tx_queue::evm::EvmTxQueueConfig;
-use webb_relayer_tx_queue::substrate::SubstrateTxQueueConfig;
+use webb_relayer_tx_queue::substrate::{SubstrateTxQueueConfig, SubxtTxClient};
 use webb_relayer_types::rpc_client::WebbRpcClient;
 
 use webb::evm::ethers;
@@ -29,6 +30,8 @@ use webb::evm::ethers;
 use webb::evm::ethers::core::k256::SecretKey;
 #[cfg(feature = "evm")]
 use webb::evm::ethers::prelude::*;
+#[cfg(feature = "evm")]
+use webb::evm::ethers::signers::{coins_bip39::English, MnemonicBuilder};
 
 #[cfg(feature = "substrate")]
 use subxt_signer::sr25519::Keypair as Sr25519Pair;
@@ -42,14 +45,42 @@ use webb::substrate::subxt;
 use webb_price_oracle_backends::{
     CachedPriceBackend, CoinGeckoBackend, DummyPriceBackend, PriceOracleMerger,
 };
+use webb_proposals::ResourceId;
+use webb_relayer_config::fee_oracle::FeeOracleConfig;
+use webb_relayer_config::{NonceStrategy, StuckTxConfig};
 use webb_relayer_store::SledStore;
+use webb_relayer_utils::circuit_breaker::CircuitBreaker;
+use webb_relayer_utils::leader::Leadership;
+use webb_relayer_utils::log_filter::LogFilterHandle;
 use webb_relayer_utils::metric::{self, Metrics};
+use webb_relayer_utils::pause::PauseSwitch;
 
 mod ethers_retry_policy;
+/// Encrypted keystore support.
+pub mod keystore;
+/// Leader election between several relayer replicas.
+pub mod leader_election;
+/// Lifecycle of the relayer.
+pub mod lifecycle;
+/// Gas oracle of the static fee oracle.
+mod static_gas_oracle;
+/// Supervision of background tasks.
+pub mod supervisor;
 use ethers_retry_policy::WebbHttpRetryPolicy;
+use lifecycle::{Lifecycle, LifecycleState};
+use static_gas_oracle::StaticGasOracle;
+use supervisor::TaskSupervisor;
 use webb_relayer_utils::multi_provider::MultiProvider;
+use webb_relayer_utils::quorum_provider::QuorumClient;
+use webb_relayer_utils::rpc_transport::{RpcTransport, Throttle};
+
+type EthersClient = Provider<RetryClient<MultiProvider<RpcTransport>>>;
+type EthersQuorumClient = Provider<
+    QuorumClient<RetryClient<MultiProvider<RpcTransport>>, RpcTransport>,
+>;
 
-type EthersClient = Provider<RetryClient<MultiProvider<Http>>>;
+/// The maximum number of relay jobs checked in the background at once.
+pub const MAX_RELAY_JOBS_IN_FLIGHT: usize = 256;
 
 /// RelayerContext contains Relayer's configuration and shutdown signal.
 #[derive(Clone)]
@@ -75,18 +106,73 @@ pub struct RelayerContext {
 
     /// Evm Providers Cache.
     evm_providers: Arc<HashMap<types::U256, Arc<EthersClient>>>,
+    /// Evm Providers for the reads used in safety checks.
+    evm_quorum_providers: Arc<HashMap<types::U256, Arc<EthersQuorumClient>>>,
+    /// Gas oracles cache.
+    gas_oracles: Arc<Mutex<HashMap<types::U256, Arc<GasOracleMedian>>>>,
+    /// Evm websocket providers cache, used for subscriptions.
+    evm_pubsub_providers: Arc<Mutex<HashMap<types::U256, Arc<Provider<Ws>>>>>,
     /// Substrate providers cache.
     substrate_providers: Arc<Mutex<HashMap<types::U256, Arc<WebbRpcClient>>>>,
+    /// Circuit breakers of the chains that have them enabled.
+    circuit_breakers: Arc<HashMap<types::U256, Arc<CircuitBreaker>>>,
+    /// Leaves served by peer relayers, ahead of the local leaves caches,
+    /// with the block they were queried at.
+    peer_leaves: Arc<RwLock<HashMap<ResourceId, (Vec<types::H256>, u64)>>>,
+    /// Locks of the refreshes of the peer leaves, one per contract.
+    peer_leaves_refresh: Arc<Mutex<HashMap<ResourceId, Arc<Mutex<()>>>>>,
+    /// Locks of the sync steps of the event watchers, one per contract.
+    sync_locks: Arc<Mutex<HashMap<ResourceId, Arc<Mutex<()>>>>>,
+    /// Chains disabled at runtime through the admin API.
+    disabled_chains: Arc<RwLock<HashSet<types::U256>>>,
+    /// Supervisor of the background tasks.
+    supervisor: TaskSupervisor,
+    /// Whether this relayer is the leader among its replicas.
+    leadership: Leadership,
+    /// Whether the relayer is paused through the admin API.
+    pause: PauseSwitch,
+    /// Whether the relayer is running or draining its queues.
+    lifecycle: Lifecycle,
+    /// The filter of the logs, tunable through the admin API.
+    log_filter: LogFilterHandle,
+    /// Permits of the relay jobs checked in the background.
+    relay_jobs: Arc<Semaphore>,
 }
 
 impl RelayerContext {
     /// Creates a new RelayerContext.
     pub async fn new(
-        config: webb_relayer_config::WebbRelayerConfig,
+        mut config: webb_relayer_config::WebbRelayerConfig,
         store: SledStore,
     ) -> webb_relayer_utils::Result<Self> {
+        // decrypt the keystores first, before anything needs the secrets.
+        keystore::decrypt_keystores(&mut config)?;
         let (notify_shutdown, _) = broadcast::channel(2);
-        let metrics = Arc::new(Mutex::new(Metrics::new()?));
+        let mut metrics = Metrics::new()?;
+
+        // Create the circuit breakers for the chains that enable them.
+        let mut circuit_breakers = HashMap::new();
+        for (_, chain_config) in config.evm.iter() {
+            let cb_config = &chain_config.circuit_breaker;
+            if !chain_config.enabled || !cb_config.enabled {
+                continue;
+            }
+            let gauge = metrics
+                .circuit_breaker_state_entry(webb_proposals::TypedChainId::Evm(
+                    chain_config.chain_id,
+                ))
+                .clone();
+            let breaker = CircuitBreaker::new(
+                cb_config.error_threshold,
+                cb_config.min_samples,
+                Duration::from_millis(cb_config.window),
+                Duration::from_millis(cb_config.cooldown),
+            )
+            .with_state_gauge(gauge);
+            circuit_breakers
+                .insert(chain_config.chain_id.into(), Arc::new(breaker));
+        }
+        let metrics = Arc::new(Mutex::new(metrics));
 
         let dummy_backend = {
             let price_map = config
@@ -96,18 +182,31 @@ impl RelayerContext {
                 .collect();
             DummyPriceBackend::new(price_map)
         };
-        // **chef's kiss** this is so beautiful
-        let cached_coingecko_backend = CachedPriceBackend::builder()
-            .backend(CoinGeckoBackend::builder().build())
-            .store(store.clone())
-            .use_cache_if_source_unavailable()
-            .even_if_expired()
-            .build();
         // merge all the price oracle backends
-        let price_oracle = PriceOracleMerger::builder()
-            .merge(Box::new(cached_coingecko_backend))
-            .merge(Box::new(dummy_backend))
-            .build();
+        let price_oracle = match &config.fee_oracle {
+            FeeOracleConfig::Live => {
+                // **chef's kiss** this is so beautiful
+                let cached_coingecko_backend = CachedPriceBackend::builder()
+                    .backend(CoinGeckoBackend::builder().build())
+                    .store(store.clone())
+                    .use_cache_if_source_unavailable()
+                    .even_if_expired()
+                    .build();
+                PriceOracleMerger::builder()
+                    .merge(Box::new(cached_coingecko_backend))
+                    .merge(Box::new(dummy_backend))
+                    .build()
+            }
+            // the static prices take precedence over the unlisted assets.
+            FeeOracleConfig::Static(static_config) => {
+                let static_backend =
+                    DummyPriceBackend::new(static_config.prices.clone());
+                PriceOracleMerger::builder()
+                    .merge(Box::new(dummy_backend))
+                    .merge(Box::new(static_backend))
+                    .build()
+            }
+        };
         let price_oracle = Arc::new(price_oracle);
         let mut etherscan_clients = HashMap::new();
         for (chain, etherscan_config) in &config.evm_etherscan {
@@ -125,46 +224,102 @@ impl RelayerContext {
 
         // Create a Map for all EVM Chains
         let mut evm_providers = HashMap::new();
+        let mut evm_quorum_providers = HashMap::new();
         for (_, chain_config) in config.evm.iter() {
+            let rpc_config = &chain_config.rpc;
+            // the throttle and metrics are shared by all the endpoints of the chain.
+            let throttle =
+                rpc_config
+                    .max_requests_per_second
+                    .map(|requests_per_second| {
+                        Arc::new(Throttle::new(requests_per_second))
+                    });
+            let rpc_metrics = if rpc_config.metrics {
+                let mut metrics = metrics.lock().await;
+                let typed_chain_id =
+                    webb_proposals::TypedChainId::Evm(chain_config.chain_id);
+                Some(metrics.rpc_metrics_entry(typed_chain_id).clone())
+            } else {
+                None
+            };
             let mut providers = Vec::new();
-            match chain_config.http_endpoint.clone() {
-                webb_relayer_config::evm::HttpEndpoint::Single(rpc_url) => {
-                    let provider = Http::new(rpc_url);
-                    providers.push(provider);
+            for mut provider in open_call_transports(chain_config).await? {
+                if let Some(throttle) = &throttle {
+                    provider = provider.with_throttle(throttle.clone());
                 }
-                webb_relayer_config::evm::HttpEndpoint::Multiple(rpc_urls) => {
-                    rpc_urls.iter().for_each(|rpc_url| {
-                        let provider = Http::new(rpc_url.clone());
-                        providers.push(provider);
-                    });
+                if let Some(rpc_metrics) = &rpc_metrics {
+                    provider = provider.with_metrics(rpc_metrics.clone());
                 }
+                providers.push(provider);
             }
 
+            let quorum = match rpc_config.quorum {
+                Some(quorum) if quorum == 0 || quorum > providers.len() => {
+                    tracing::error!(
+                        chain_id = %chain_config.chain_id,
+                        quorum,
+                        endpoints = providers.len(),
+                        "Invalid RPC quorum",
+                    );
+                    return Err(webb_relayer_utils::Error::Generic(
+                        "the rpc quorum must be between 1 and the number of \
+                         endpoints of the chain",
+                    ));
+                }
+                Some(quorum) => Some((quorum, providers.clone())),
+                None => None,
+            };
             let multi_provider = MultiProvider::new(Arc::new(providers));
             // Wrap the provider with a retry client.
+            let max_retries = rpc_config.max_retries.unwrap_or(u32::MAX);
             let retry_client = RetryClientBuilder::default()
-                .timeout_retries(u32::MAX)
-                .rate_limit_retries(u32::MAX)
+                .timeout_retries(max_retries)
+                .rate_limit_retries(max_retries)
+                .initial_backoff(Duration::from_millis(
+                    rpc_config.initial_backoff,
+                ))
                 .build(multi_provider, WebbHttpRetryPolicy::boxed());
 
-            let provider = Provider::new(retry_client).interval(
-                Duration::from_millis(chain_config.tx_queue.polling_interval),
+            let polling_interval =
+                Duration::from_millis(chain_config.tx_queue.polling_interval);
+            let provider = Arc::new(
+                Provider::new(retry_client).interval(polling_interval),
             );
-            evm_providers
-                .insert(chain_config.chain_id.into(), Arc::new(provider));
+            // the reads used in safety checks go through the quorum, if any.
+            let quorum_client = match quorum {
+                Some((quorum, providers)) => {
+                    QuorumClient::quorum(quorum, providers)
+                }
+                None => QuorumClient::Single(provider.clone()),
+            };
+            let quorum_provider =
+                Provider::new(quorum_client).interval(polling_interval);
+            evm_quorum_providers.insert(
+                chain_config.chain_id.into(),
+                Arc::new(quorum_provider),
+            );
+            evm_providers.insert(chain_config.chain_id.into(), provider);
         }
 
         // create hashmap for substrate providers
         let mut substrate_providers = HashMap::new();
         for (_, chain_config) in config.substrate.iter() {
-            let url = chain_config.ws_endpoint.to_string();
-            let webb_rpc_client = WebbRpcClient::new(url).await?;
+            let webb_rpc_client = connect_substrate_node(chain_config).await?;
             substrate_providers.insert(
                 chain_config.chain_id.into(),
                 Arc::new(webb_rpc_client),
             );
         }
         let substrate_providers = Arc::new(Mutex::new(substrate_providers));
+        let supervisor =
+            TaskSupervisor::new(notify_shutdown.clone(), metrics.clone());
+        // with the leader election enabled, we only lead once we win it.
+        let leadership = if config.leader_election.enabled {
+            Leadership::follower()
+        } else {
+            Leadership::always()
+        };
+        let pause = PauseSwitch::new(config.admin.paused);
 
         Ok(Self {
             config,
@@ -174,7 +329,21 @@ impl RelayerContext {
             price_oracle,
             etherscan_clients: Arc::new(etherscan_clients),
             evm_providers: Arc::new(evm_providers),
+            evm_quorum_providers: Arc::new(evm_quorum_providers),
+            evm_pubsub_providers: Default::default(),
+            gas_oracles: Default::default(),
             substrate_providers,
+            circuit_breakers: Arc::new(circuit_breakers),
+            peer_leaves: Default::default(),
+            peer_leaves_refresh: Default::default(),
+            sync_locks: Default::default(),
+            disabled_chains: Default::default(),
+            supervisor,
+            leadership,
+            pause,
+            lifecycle: Default::default(),
+            log_filter: Default::default(),
+            relay_jobs: Arc::new(Semaphore::new(MAX_RELAY_JOBS_IN_FLIGHT)),
         })
     }
     /// Returns a broadcast receiver handle for the shutdown signal.
@@ -185,7 +354,10 @@ impl RelayerContext {
     pub fn shutdown(&self) {
         let _ = self.notify_shutdown.send(());
     }
-    /// Returns a new `EthereumProvider` for the relayer.
+    /// Returns the `EthereumProvider` of the given chain.
+    ///
+    /// The providers are created once, when the context is, and shared by the
+    /// handlers, watchers and queues, so they all reuse the same connections.
     ///
     /// # Arguments
     ///
@@ -205,6 +377,94 @@ impl RelayerContext {
             })
         }
     }
+    /// Returns the provider of the given chain for the reads used in safety
+    /// checks (roots, nullifiers and balances).
+    ///
+    /// If an RPC quorum is configured for the chain, a read is only trusted
+    /// once enough of its endpoints agree on it. Otherwise, the reads go
+    /// through the regular provider of the chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_id` - A string representing the chain id.
+    #[cfg(feature = "evm")]
+    pub async fn evm_quorum_provider<I: Into<types::U256>>(
+        &self,
+        chain_id: I,
+    ) -> webb_relayer_utils::Result<Arc<EthersQuorumClient>> {
+        let chain_id: types::U256 = chain_id.into();
+        self.evm_quorum_providers
+            .get(&chain_id)
+            .cloned()
+            .ok_or_else(|| webb_relayer_utils::Error::ChainNotFound {
+                chain_id: chain_id.to_string(),
+            })
+    }
+    /// Returns the websocket provider of an EVM chain, used for
+    /// subscriptions.
+    ///
+    /// It connects to the first `ws` transport of the chain that is
+    /// reachable, by priority, or to its `ws-endpoint` when none is
+    /// configured, and is cached afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_id` - A string representing the chain id.
+    #[cfg(feature = "evm")]
+    pub async fn evm_pubsub_provider<I: Into<types::U256>>(
+        &self,
+        chain_id: I,
+    ) -> webb_relayer_utils::Result<Arc<Provider<Ws>>> {
+        use webb_relayer_config::evm::TransportKind;
+        let chain_id: types::U256 = chain_id.into();
+        let chain_name = chain_id.to_string();
+        let chain_config =
+            self.config.evm.get(&chain_name).ok_or_else(|| {
+                webb_relayer_utils::Error::ChainNotFound {
+                    chain_id: chain_name.clone(),
+                }
+            })?;
+        let mut pubsub_providers = self.evm_pubsub_providers.lock().await;
+        if let Some(provider) = pubsub_providers.get(&chain_id) {
+            return Ok(provider.clone());
+        }
+        let mut transports = chain_config
+            .transports
+            .iter()
+            .filter(|transport| transport.kind == TransportKind::Ws)
+            .collect::<Vec<_>>();
+        transports.sort_by_key(|transport| transport.priority);
+        let mut ws_urls = transports
+            .into_iter()
+            .filter_map(|transport| transport.url.clone())
+            .collect::<Vec<_>>();
+        if ws_urls.is_empty() {
+            ws_urls.push(chain_config.ws_endpoint.clone());
+        }
+        for ws_url in ws_urls {
+            match Ws::connect(ws_url.as_str()).await {
+                Ok(ws) => {
+                    let provider =
+                        Provider::new(ws).interval(Duration::from_millis(
+                            chain_config.tx_queue.polling_interval,
+                        ));
+                    let provider = Arc::new(provider);
+                    pubsub_providers.insert(chain_id, provider.clone());
+                    return Ok(provider);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        chain_id = %chain_name,
+                        error = %e,
+                        "Failed to connect to the websocket transport",
+                    );
+                }
+            }
+        }
+        Err(webb_relayer_utils::Error::NoTransportAvailable {
+            chain_id: chain_name,
+        })
+    }
     /// Sets up and returns an EVM wallet for the relayer.
     ///
     /// # Arguments
@@ -223,14 +483,39 @@ impl RelayerContext {
                     chain_id: chain_name.to_string(),
                 }
             })?;
-        let private_key = chain_config
-            .private_key
-            .as_ref()
-            .ok_or(webb_relayer_utils::Error::MissingSecrets)?;
-        let key = SecretKey::from_bytes(private_key.as_bytes().into())?;
         let chain_id = chain_config.chain_id;
-        let wallet = LocalWallet::from(key).with_chain_id(chain_id);
-        Ok(wallet)
+        // a raw private key always takes precedence over the HD wallet.
+        let wallet = match (&chain_config.private_key, &chain_config.hd_wallet)
+        {
+            (Some(private_key), _) => {
+                let key = SecretKey::from_bytes(private_key.as_bytes().into())?;
+                LocalWallet::from(key)
+            }
+            (None, Some(hd_wallet)) => MnemonicBuilder::<English>::default()
+                .phrase(hd_wallet.mnemonic.phrase())
+                .derivation_path(&hd_wallet.account_derivation_path())?
+                .build()?,
+            (None, None) => {
+                return Err(webb_relayer_utils::Error::MissingSecrets)
+            }
+        };
+        Ok(wallet.with_chain_id(chain_id))
+    }
+    /// Returns the wallet that identifies this relayer, that is the wallet
+    /// of the configured EVM chain with the lowest chain id that has a key.
+    ///
+    /// Returns `None` if no EVM chain has a key configured.
+    #[cfg(feature = "evm")]
+    pub async fn identity_wallet(&self) -> Option<LocalWallet> {
+        let mut chain_ids: Vec<u32> =
+            self.config.evm.values().map(|c| c.chain_id).collect();
+        chain_ids.sort_unstable();
+        for chain_id in chain_ids {
+            if let Ok(wallet) = self.evm_wallet(chain_id).await {
+                return Some(wallet);
+            }
+        }
+        None
     }
     /// Sets up and returns a Substrate client for the relayer.
     ///
@@ -253,14 +538,14 @@ impl RelayerContext {
         let mut substrate_providers = self.substrate_providers.lock().await;
         if let Some(webb_rpc_client) = substrate_providers.get(&chain_id) {
             // check if rpc is connected if not create a new connection and cache it
-            let substrate_client = if webb_rpc_client.0.is_connected() {
+            let substrate_client = if webb_rpc_client.is_connected() {
                 subxt::OnlineClient::<C>::from_rpc_client(
                     webb_rpc_client.clone(),
                 )
                 .await?
             } else {
-                let url = node_config.ws_endpoint.to_string();
-                let webb_rpc_client = Arc::new(WebbRpcClient::new(url).await?);
+                let webb_rpc_client =
+                    Arc::new(connect_substrate_node(node_config).await?);
                 substrate_providers.insert(chain_id, webb_rpc_client.clone());
                 subxt::OnlineClient::<C>::from_rpc_client(webb_rpc_client)
                     .await?
@@ -297,6 +582,225 @@ impl RelayerContext {
         Ok(suri_key.into())
     }
 
+    /// Returns the circuit breaker of the given chain, if it is enabled.
+    pub fn circuit_breaker<I: Into<types::U256>>(
+        &self,
+        chain_id: I,
+    ) -> Option<Arc<CircuitBreaker>> {
+        self.circuit_breakers.get(&chain_id.into()).cloned()
+    }
+
+    /// Returns the circuit breakers of all chains that have them enabled.
+    pub fn circuit_breakers(
+        &self,
+    ) -> impl Iterator<Item = (&types::U256, &Arc<CircuitBreaker>)> {
+        self.circuit_breakers.iter()
+    }
+
+    /// Disables the given chain at runtime, pausing its watchers and
+    /// transaction queue. Queued items are kept until it is enabled again.
+    ///
+    /// Returns `false` if the chain was already disabled.
+    pub fn disable_chain<I: Into<types::U256>>(&self, chain_id: I) -> bool {
+        let chain_id = chain_id.into();
+        tracing::warn!(%chain_id, "Chain disabled at runtime");
+        self.disabled_chains
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(chain_id)
+    }
+
+    /// Re-enables a chain that was disabled at runtime.
+    ///
+    /// Returns `false` if the chain was not disabled.
+    pub fn enable_chain<I: Into<types::U256>>(&self, chain_id: I) -> bool {
+        let chain_id = chain_id.into();
+        tracing::info!(%chain_id, "Chain re-enabled at runtime");
+        self.disabled_chains
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&chain_id)
+    }
+
+    /// Returns `true` if the given chain is disabled at runtime.
+    pub fn is_chain_disabled<I: Into<types::U256>>(&self, chain_id: I) -> bool {
+        self.disabled_chains
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&chain_id.into())
+    }
+
+    /// Returns the end of the maintenance window the tx queue of the given
+    /// chain is in, in seconds since the unix epoch, if it is in one.
+    pub fn maintenance_until<I: Into<types::U256>>(
+        &self,
+        chain_id: I,
+    ) -> Option<u64> {
+        let chain_name = chain_id.into().to_string();
+        let tx_queue = self
+            .config
+            .evm
+            .get(&chain_name)
+            .map(|chain| &chain.tx_queue)
+            .or_else(|| {
+                self.config.substrate.get(&chain_name).map(|n| &n.tx_queue)
+            })?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        tx_queue.maintenance_until(now)
+    }
+
+    /// Returns the verified leaves served by a peer for the given contract,
+    /// with the block they were queried at.
+    pub fn peer_leaves(
+        &self,
+        resource_id: ResourceId,
+    ) -> Option<(Vec<types::H256>, u64)> {
+        self.peer_leaves
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&resource_id)
+            .cloned()
+    }
+
+    /// Returns the lock held while the leaves of the given contract are
+    /// refreshed from the peers, so concurrent requests wait for the refresh
+    /// in flight instead of querying the peers each.
+    pub async fn peer_leaves_refresh_lock(
+        &self,
+        resource_id: ResourceId,
+    ) -> Arc<Mutex<()>> {
+        self.peer_leaves_refresh
+            .lock()
+            .await
+            .entry(resource_id)
+            .or_default()
+            .clone()
+    }
+
+    /// Returns the lock held by the event watcher of the given contract
+    /// during each of its sync steps, so the cache of the contract is not
+    /// cleared while a step is writing to it.
+    pub async fn sync_lock(&self, resource_id: ResourceId) -> Arc<Mutex<()>> {
+        self.sync_locks
+            .lock()
+            .await
+            .entry(resource_id)
+            .or_default()
+            .clone()
+    }
+
+    /// Caches the verified leaves served by a peer for the given contract,
+    /// unless more leaves are already cached.
+    pub fn cache_peer_leaves(
+        &self,
+        resource_id: ResourceId,
+        leaves: Vec<types::H256>,
+        last_queried_block: u64,
+    ) {
+        let mut peer_leaves =
+            self.peer_leaves.write().unwrap_or_else(|e| e.into_inner());
+        let cached = peer_leaves.get(&resource_id);
+        if cached.map_or(true, |(cached, _)| cached.len() < leaves.len()) {
+            peer_leaves.insert(resource_id, (leaves, last_queried_block));
+        }
+    }
+
+    /// Returns the supervisor of the relayer's background tasks.
+    pub fn supervisor(&self) -> &TaskSupervisor {
+        &self.supervisor
+    }
+
+    /// Returns the leadership of this relayer among its replicas.
+    pub fn leadership(&self) -> &Leadership {
+        &self.leadership
+    }
+
+    /// Returns the pause switch of the relayer.
+    pub fn pause_switch(&self) -> &PauseSwitch {
+        &self.pause
+    }
+
+    /// Takes a permit to check a relay job in the background, held until the
+    /// job is enqueued or failed.
+    ///
+    /// Returns `None` once [`MAX_RELAY_JOBS_IN_FLIGHT`] jobs are being
+    /// checked.
+    pub fn try_acquire_relay_job(&self) -> Option<OwnedSemaphorePermit> {
+        self.relay_jobs.clone().try_acquire_owned().ok()
+    }
+
+    /// Returns `true` while the relayer is paused, new relay requests are
+    /// rejected and proposals are not signed until it is resumed.
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
+    /// Pauses or resumes the relayer.
+    ///
+    /// Returns `false` if it already was in that state.
+    pub fn set_paused(&self, paused: bool) -> bool {
+        if paused {
+            tracing::warn!("Relayer paused");
+        } else {
+            tracing::info!("Relayer resumed");
+        }
+        self.pause.set_paused(paused)
+    }
+
+    /// Sets the handle to the filter of the logs, so it can be changed at
+    /// runtime through the admin API.
+    pub fn with_log_filter(mut self, log_filter: LogFilterHandle) -> Self {
+        self.log_filter = log_filter;
+        self
+    }
+
+    /// Returns the handle to the filter of the logs.
+    pub fn log_filter(&self) -> &LogFilterHandle {
+        &self.log_filter
+    }
+
+    /// Returns the lifecycle state of the relayer.
+    pub fn lifecycle(&self) -> LifecycleState {
+        self.lifecycle.state()
+    }
+
+    /// Returns `true` once the relayer started draining its transaction
+    /// queues on shutdown, new relay requests are rejected from then on.
+    pub fn is_draining(&self) -> bool {
+        self.lifecycle.is_draining()
+    }
+
+    /// Starts draining the transaction queues, for at most the configured
+    /// drain timeout.
+    ///
+    /// Returns `false` if the relayer was already draining.
+    pub fn start_draining(&self) -> bool {
+        let timeout = Duration::from_millis(self.config.shutdown.drain_timeout);
+        self.lifecycle.start_draining(timeout)
+    }
+
+    /// Returns the number of pending items in all the transaction queues.
+    pub fn pending_tx_queue_items(&self) -> webb_relayer_utils::Result<usize> {
+        let mut pending = 0;
+        for queue_name in self.store.queue_names() {
+            if !queue_name.starts_with("evm_tx_")
+                && !queue_name.starts_with("substrate_tx_")
+            {
+                continue;
+            }
+            pending += self
+                .store
+                .queue_items(&queue_name)?
+                .iter()
+                .filter(|item| item.state().is_pending())
+                .count();
+        }
+        Ok(pending)
+    }
+
     /// Returns [Sled](https://sled.rs)-based database store
     pub fn store(&self) -> &SledStore {
         &self.store
@@ -307,20 +811,41 @@ impl RelayerContext {
         self.price_oracle.clone()
     }
 
-    /// Returns a gas oracle for the given chain.
+    /// Returns the gas oracle of the given chain.
+    ///
+    /// The oracle is created on first use and shared afterwards, so its
+    /// Etherscan cache and HTTP connections are reused across requests.
     pub async fn gas_oracle<I: Into<types::U256>>(
         &self,
         chain_id: I,
-    ) -> webb_relayer_utils::Result<GasOracleMedian> {
+    ) -> webb_relayer_utils::Result<Arc<GasOracleMedian>> {
         let chain_id: types::U256 = chain_id.into();
+        let mut gas_oracles = self.gas_oracles.lock().await;
+        if let Some(gas_oracle) = gas_oracles.get(&chain_id) {
+            return Ok(gas_oracle.clone());
+        }
+        let mut gas_oracle = GasOracleMedian::new();
+        if let FeeOracleConfig::Static(static_config) = &self.config.fee_oracle
+        {
+            let static_gas_price =
+                static_config.gas_prices.get(&chain_id.to_string());
+            if let Some(gas_price) = static_gas_price {
+                gas_oracle.add(StaticGasOracle::new((*gas_price).into()));
+                let gas_oracle = Arc::new(gas_oracle);
+                gas_oracles.insert(chain_id, gas_oracle.clone());
+                return Ok(gas_oracle);
+            }
+        }
         let chain_provider = self.evm_provider(&chain_id).await?;
         let provider_gas_oracle = ProviderOracle::new(chain_provider);
-        let mut gas_oracle = GasOracleMedian::new();
         // Give only 10% of the weight to the provider gas oracle
         // since it is not very accurate.
         gas_oracle.add_weighted(0.1, provider_gas_oracle);
-        // Check if we have etherscan client for this chain
-        if let Some(_etherscan_client) = self.etherscan_clients.get(&chain_id) {
+        // Check if we have etherscan client for this chain, the static fee
+        // oracle never uses it.
+        let is_live = matches!(self.config.fee_oracle, FeeOracleConfig::Live);
+        let etherscan_client = self.etherscan_clients.get(&chain_id);
+        if let Some(_etherscan_client) = etherscan_client.filter(|_| is_live) {
             let etherscan_gas_oracle = EtherscanGasOracle::new();
             let cached = CachedGasOracle::new(
                 // Cache for 5 minutes to avoid hitting etherscan rate limit
@@ -333,14 +858,163 @@ impl RelayerContext {
         }
         // TODO: Add more gas oracles
 
+        let gas_oracle = Arc::new(gas_oracle);
+        gas_oracles.insert(chain_id, gas_oracle.clone());
         Ok(gas_oracle)
     }
+
+    /// Returns the daily gas budget of the given chain in wei, if it has one.
+    ///
+    /// A budget in USD is converted at the current price of the native
+    /// token; when both are configured, the lower one applies.
+    pub async fn daily_gas_budget<I: Into<types::U256>>(
+        &self,
+        chain_id: I,
+    ) -> webb_relayer_utils::Result<Option<types::U256>> {
+        let chain_id: types::U256 = chain_id.into();
+        let chain_config = self
+            .config
+            .evm
+            .get(&chain_id.as_u64().to_string())
+            .ok_or_else(|| webb_relayer_utils::Error::ChainNotFound {
+                chain_id: chain_id.to_string(),
+            })?;
+        let budget = match chain_config.gas_budget {
+            Some(budget) => budget,
+            None => return Ok(None),
+        };
+        let native_currency =
+            webb_chains_info::chain_info_by_chain_id(chain_id.as_u64())
+                .map(|info| &info.native_currency);
+        let (symbol, decimals) = match native_currency {
+            Some(currency) => (currency.symbol, currency.decimals),
+            // Typescript tests use randomly generated chain ids, so we
+            // assume ETH in debug mode to make them work.
+            None if cfg!(debug_assertions) => ("ETH", 18),
+            None => {
+                return Err(webb_relayer_utils::Error::ChainNotFound {
+                    chain_id: chain_id.to_string(),
+                })
+            }
+        };
+        let mut limit = budget.max_native;
+        if let Some(max_usd) = budget.max_usd {
+            let prices = self.price_oracle.get_prices(&[symbol]).await?;
+            let price = prices.get(symbol).copied().ok_or_else(|| {
+                webb_relayer_utils::Error::FetchTokenPriceError {
+                    token: symbol.into(),
+                }
+            })?;
+            let max_native = max_usd / price;
+            limit = Some(limit.map_or(max_native, |l| l.min(max_native)));
+        }
+        match limit {
+            Some(limit) => {
+                let wei =
+                    ethers::utils::parse_units(limit, u32::from(decimals))?;
+                Ok(Some(wei.into()))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Connects to a Substrate node, through its embedded light client if it
+/// has one configured, or its `ws-endpoint` otherwise.
+pub async fn connect_substrate_node(
+    node_config: &webb_relayer_config::substrate::SubstrateConfig,
+) -> webb_relayer_utils::Result<WebbRpcClient> {
+    match &node_config.light_client {
+        Some(light_client) => {
+            let chain_spec = std::fs::read_to_string(&light_client.chain_spec)?;
+            WebbRpcClient::light_client(&chain_spec, &light_client.bootnodes)
+        }
+        None => WebbRpcClient::new(node_config.ws_endpoint.to_string()).await,
+    }
+}
+
+/// Opens the transports used for calls to an EVM chain.
+///
+/// These are all the `http` and `ipc` transports of the best priority that
+/// could be opened, or the `http-endpoint` of the chain when none is
+/// configured.
+async fn open_call_transports(
+    chain_config: &webb_relayer_config::evm::EvmChainConfig,
+) -> webb_relayer_utils::Result<Vec<RpcTransport>> {
+    use webb_relayer_config::evm::{HttpEndpoint, TransportKind};
+    let rpc_config = &chain_config.rpc;
+    let connect_timeout = Duration::from_millis(rpc_config.connect_timeout);
+    let request_timeout = Duration::from_millis(rpc_config.request_timeout);
+    let mut transports = chain_config
+        .transports
+        .iter()
+        .filter(|transport| transport.kind != TransportKind::Ws)
+        .collect::<Vec<_>>();
+    if transports.is_empty() {
+        let rpc_urls = match chain_config.http_endpoint.clone() {
+            HttpEndpoint::Single(rpc_url) => vec![rpc_url],
+            HttpEndpoint::Multiple(rpc_urls) => rpc_urls,
+        };
+        return rpc_urls
+            .into_iter()
+            .map(|rpc_url| {
+                RpcTransport::new(
+                    rpc_url.into(),
+                    connect_timeout,
+                    request_timeout,
+                )
+            })
+            .collect();
+    }
+    transports.sort_by_key(|transport| transport.priority);
+    let mut opened = Vec::new();
+    let mut opened_priority = None;
+    for transport in transports {
+        // lower priorities are only used if none of the better ones opened.
+        if matches!(opened_priority, Some(p) if transport.priority > p) {
+            break;
+        }
+        let result = match (transport.kind, &transport.url, &transport.path) {
+            (TransportKind::Http, Some(url), _) => RpcTransport::new(
+                url.clone().into(),
+                connect_timeout,
+                request_timeout,
+            ),
+            (TransportKind::Ipc, _, Some(path)) => {
+                RpcTransport::connect_ipc(path, request_timeout).await
+            }
+            _ => Err(webb_relayer_utils::Error::Generic(
+                "http transports need a url, and ipc transports a path",
+            )),
+        };
+        match result {
+            Ok(rpc_transport) => {
+                opened.push(rpc_transport);
+                opened_priority = Some(transport.priority);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    chain_id = chain_config.chain_id,
+                    kind = ?transport.kind,
+                    priority = transport.priority,
+                    error = %e,
+                    "Failed to open the transport",
+                );
+            }
+        }
+    }
+    if opened.is_empty() {
+        return Err(webb_relayer_utils::Error::NoTransportAvailable {
+            chain_id: chain_config.chain_id.to_string(),
+        });
+    }
+    Ok(opened)
 }
 
 #[cfg(feature = "evm")]
 #[async_trait::async_trait]
 impl EvmTxQueueConfig for RelayerContext {
-    type EtherClient = EthersClient;
+    type TxClient = TimeLag<SignerMiddleware<Arc<EthersClient>, LocalWallet>>;
 
     fn max_sleep_interval(
         &self,
@@ -370,6 +1044,50 @@ impl EvmTxQueueConfig for RelayerContext {
         Ok(chain_config.block_confirmations)
     }
 
+    fn stuck_tx(
+        &self,
+        chain_id: &U256,
+    ) -> webb_relayer_utils::Result<Option<StuckTxConfig>> {
+        let chain_config = self
+            .config
+            .evm
+            .get(&chain_id.as_u64().to_string())
+            .ok_or_else(|| webb_relayer_utils::Error::ChainNotFound {
+                chain_id: chain_id.to_string(),
+            })?;
+        Ok(chain_config.tx_queue.stuck_tx)
+    }
+
+    fn nonce_strategy(
+        &self,
+        chain_id: &U256,
+    ) -> webb_relayer_utils::Result<NonceStrategy> {
+        let chain_config = self
+            .config
+            .evm
+            .get(&chain_id.as_u64().to_string())
+            .ok_or_else(|| webb_relayer_utils::Error::ChainNotFound {
+                chain_id: chain_id.to_string(),
+            })?;
+        Ok(chain_config.tx_queue.nonce_strategy)
+    }
+
+    fn gas_limit_with_headroom(
+        &self,
+        chain_id: &U256,
+        to: Address,
+        gas: U256,
+    ) -> webb_relayer_utils::Result<U256> {
+        let chain_config = self
+            .config
+            .evm
+            .get(&chain_id.as_u64().to_string())
+            .ok_or_else(|| webb_relayer_utils::Error::ChainNotFound {
+                chain_id: chain_id.to_string(),
+            })?;
+        Ok(chain_config.gas_limit_with_headroom(to, gas))
+    }
+
     fn explorer(
         &self,
         chain_id: &U256,
@@ -384,24 +1102,47 @@ impl EvmTxQueueConfig for RelayerContext {
         Ok(chain_config.explorer.clone())
     }
 
-    async fn get_evm_provider(
+    fn circuit_breaker(&self, chain_id: &U256) -> Option<Arc<CircuitBreaker>> {
+        RelayerContext::circuit_breaker(self, *chain_id)
+    }
+
+    fn is_chain_disabled(&self, chain_id: &U256) -> bool {
+        RelayerContext::is_chain_disabled(self, *chain_id)
+    }
+
+    fn maintenance_until(&self, chain_id: &U256) -> Option<u64> {
+        RelayerContext::maintenance_until(self, *chain_id)
+    }
+
+    fn metrics(&self) -> Arc<Mutex<Metrics>> {
+        self.metrics.clone()
+    }
+
+    async fn daily_gas_budget(
         &self,
         chain_id: &U256,
-    ) -> webb_relayer_utils::Result<Arc<Self::EtherClient>> {
-        self.evm_provider(chain_id).await
+    ) -> webb_relayer_utils::Result<Option<U256>> {
+        RelayerContext::daily_gas_budget(self, *chain_id).await
     }
 
-    async fn get_evm_wallet(
+    async fn evm_tx_client(
         &self,
         chain_id: &U256,
-    ) -> webb_relayer_utils::Result<LocalWallet> {
-        self.evm_wallet(chain_id).await
+    ) -> webb_relayer_utils::Result<Self::TxClient> {
+        let provider = self.evm_provider(chain_id).await?;
+        let wallet = self.evm_wallet(chain_id).await?;
+        let block_confirmations =
+            EvmTxQueueConfig::block_confirmations(self, chain_id)?;
+        let signer_client = SignerMiddleware::new(provider, wallet);
+        Ok(TimeLag::new(signer_client, block_confirmations))
     }
 }
 
 #[cfg(feature = "substrate")]
 #[async_trait::async_trait]
 impl SubstrateTxQueueConfig for RelayerContext {
+    type TxClient = SubxtTxClient;
+
     fn max_sleep_interval(
         &self,
         chain_id: u32,
@@ -415,18 +1156,25 @@ impl SubstrateTxQueueConfig for RelayerContext {
         Ok(chain_config.tx_queue.max_sleep_interval)
     }
 
-    async fn substrate_provider<C: subxt::Config>(
-        &self,
-        chain_id: u32,
-    ) -> webb_relayer_utils::Result<OnlineClient<C>> {
-        self.substrate_provider(chain_id).await
+    fn is_chain_disabled(&self, chain_id: u32) -> bool {
+        RelayerContext::is_chain_disabled(self, chain_id)
+    }
+
+    fn maintenance_until(&self, chain_id: u32) -> Option<u64> {
+        RelayerContext::maintenance_until(self, chain_id)
     }
 
-    async fn substrate_wallet(
+    fn metrics(&self) -> Arc<Mutex<Metrics>> {
+        self.metrics.clone()
+    }
+
+    async fn substrate_tx_client(
         &self,
         chain_id: u32,
-    ) -> webb_relayer_utils::Result<Sr25519Pair> {
-        self.substrate_wallet(chain_id).await
+    ) -> webb_relayer_utils::Result<Self::TxClient> {
+        let client = self.substrate_provider(chain_id).await?;
+        let pair = self.substrate_wallet(chain_id).await?;
+        Ok(SubxtTxClient::new(client, pair))
     }
 }
 
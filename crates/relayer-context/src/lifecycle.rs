@@ -0,0 +1,133 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lifecycle of the relayer, from running to draining its transaction
+//! queues on shutdown.
+//!
+//! ```text
+//! Running --(SIGTERM / SIGINT)--> Draining --(queues drained)--> exit 0
+//!                                     |
+//!                                     +--(drain timeout)--> exit 3
+//! ```
+//!
+//! While draining, new relay requests are rejected, and the transaction
+//! queues keep going until none of their items is pending anymore.
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+/// The lifecycle state of the relayer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum LifecycleState {
+    /// The relayer is running, and accepts relay requests.
+    Running,
+    /// The relayer is shutting down, and drains its transaction queues.
+    #[serde(rename_all = "camelCase")]
+    Draining {
+        /// Unix timestamp (in seconds) of when the draining started.
+        started_at: u64,
+        /// Unix timestamp (in seconds) of when the relayer exits, even if
+        /// the queues are not drained yet.
+        deadline: u64,
+    },
+}
+
+/// Shared lifecycle state of the relayer.
+#[derive(Debug, Clone)]
+pub struct Lifecycle {
+    state: Arc<RwLock<LifecycleState>>,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(RwLock::new(LifecycleState::Running)),
+        }
+    }
+}
+
+impl Lifecycle {
+    /// Returns the current lifecycle state.
+    pub fn state(&self) -> LifecycleState {
+        *self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns `true` once the relayer started draining.
+    pub fn is_draining(&self) -> bool {
+        matches!(self.state(), LifecycleState::Draining { .. })
+    }
+
+    /// Starts draining, for at most `timeout`.
+    ///
+    /// Returns `false` if the relayer was already draining.
+    pub fn start_draining(&self, timeout: Duration) -> bool {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        if matches!(*state, LifecycleState::Draining { .. }) {
+            return false;
+        }
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        *state = LifecycleState::Draining {
+            started_at,
+            deadline: started_at.saturating_add(timeout.as_secs()),
+        };
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_start_draining_once() {
+        let lifecycle = Lifecycle::default();
+        assert_eq!(lifecycle.state(), LifecycleState::Running);
+        assert!(!lifecycle.is_draining());
+
+        assert!(lifecycle.start_draining(Duration::from_secs(30)));
+        let LifecycleState::Draining {
+            started_at,
+            deadline,
+        } = lifecycle.state()
+        else {
+            panic!("expected the relayer to be draining");
+        };
+        assert_eq!(deadline, started_at + 30);
+        // a second signal does not push the deadline back.
+        assert!(!lifecycle.start_draining(Duration::from_secs(60)));
+        assert!(lifecycle.is_draining());
+    }
+
+    #[test]
+    fn should_serialize_state() {
+        let state = LifecycleState::Draining {
+            started_at: 1,
+            deadline: 31,
+        };
+        assert_eq!(
+            serde_json::to_value(state).unwrap(),
+            serde_json::json!({
+                "state": "draining",
+                "startedAt": 1,
+                "deadline": 31,
+            })
+        );
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use webb::evm::ethers::middleware::gas_oracle::{GasOracle, GasOracleError};
+use webb::evm::ethers::types::U256;
+
+/// A gas oracle that always returns the same gas price, used by the static
+/// fee oracle so the fees do not depend on any external service.
+#[derive(Debug, Clone, Copy)]
+pub struct StaticGasOracle {
+    gas_price: U256,
+}
+
+impl StaticGasOracle {
+    /// Creates a new gas oracle returning the given gas price, in wei.
+    pub fn new(gas_price: U256) -> Self {
+        Self { gas_price }
+    }
+}
+
+#[async_trait::async_trait]
+impl GasOracle for StaticGasOracle {
+    async fn fetch(&self) -> Result<U256, GasOracleError> {
+        Ok(self.gas_price)
+    }
+
+    async fn estimate_eip1559_fees(
+        &self,
+    ) -> Result<(U256, U256), GasOracleError> {
+        Ok((self.gas_price, self.gas_price))
+    }
+}
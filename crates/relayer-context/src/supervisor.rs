@@ -0,0 +1,263 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Supervision of the relayer's long-running background tasks.
+//!
+//! Every watcher and transaction queue is spawned through the
+//! [`TaskSupervisor`], which keeps track of its health and restarts it with
+//! a jittered exponential backoff whenever it stops, until the relayer shuts
+//! down.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use backoff::backoff::Backoff;
+use serde::Serialize;
+use tokio::sync::{broadcast, Mutex};
+use webb_relayer_utils::heartbeat::Heartbeat;
+use webb_relayer_utils::metric::Metrics;
+
+use crate::Shutdown;
+
+/// A task that kept running for this long is considered healthy again,
+/// so its restart backoff is reset.
+const HEALTHY_RUN_DURATION: Duration = Duration::from_secs(60);
+
+/// The status of a supervised task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskStatus {
+    /// The task is running.
+    Running,
+    /// The task stopped and is waiting to be restarted.
+    Restarting,
+    /// The task was stopped by the relayer shutdown.
+    Stopped,
+}
+
+/// Health information of a supervised task.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskHealth {
+    /// The current status of the task.
+    pub status: TaskStatus,
+    /// How many times the task was restarted.
+    pub restarts: u64,
+    /// The reason the task stopped the last time, if it ever did.
+    pub last_error: Option<String>,
+    /// Unix timestamp (in seconds) of the last time the task was started.
+    pub last_started_at: u64,
+    /// Unix timestamp (in seconds) of the last heartbeat of the task,
+    /// `None` for tasks that do not report heartbeats.
+    pub last_heartbeat_at: Option<u64>,
+}
+
+/// Spawns and supervises the long-running background tasks of the relayer.
+///
+/// A supervised task is expected to run until the relayer shuts down, so a
+/// task that returns, successfully or not, is considered crashed and gets
+/// restarted.
+#[derive(Clone)]
+pub struct TaskSupervisor {
+    tasks: Arc<RwLock<BTreeMap<String, TaskHealth>>>,
+    heartbeats: Arc<RwLock<BTreeMap<String, Heartbeat>>>,
+    notify_shutdown: broadcast::Sender<()>,
+    metrics: Arc<Mutex<Metrics>>,
+}
+
+impl TaskSupervisor {
+    /// Creates a new supervisor, stopping its tasks on `notify_shutdown`.
+    pub fn new(
+        notify_shutdown: broadcast::Sender<()>,
+        metrics: Arc<Mutex<Metrics>>,
+    ) -> Self {
+        Self {
+            tasks: Default::default(),
+            heartbeats: Default::default(),
+            notify_shutdown,
+            metrics,
+        }
+    }
+
+    /// Spawns a supervised task.
+    ///
+    /// `factory` is called to create the task future, once at the start and
+    /// again on every restart. The future runs in the scope of the task's
+    /// [`Heartbeat`], see [`webb_relayer_utils::heartbeat::beat`].
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A unique name of the task, used in logs and health reports.
+    /// * `factory` - A function that creates the task future.
+    pub fn spawn<F, Fut>(&self, name: impl Into<String>, factory: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = webb_relayer_utils::Result<()>> + Send + 'static,
+    {
+        let name = name.into();
+        let this = self.clone();
+        let mut shutdown = Shutdown::new(self.notify_shutdown.subscribe());
+        let heartbeat = Heartbeat::default();
+        self.heartbeats
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.clone(), heartbeat.clone());
+        let task = async move {
+            let mut backoff = backoff::ExponentialBackoff {
+                initial_interval: Duration::from_secs(1),
+                max_interval: Duration::from_secs(5 * 60),
+                max_elapsed_time: None,
+                ..Default::default()
+            };
+            loop {
+                this.set_running(&name).await;
+                // give a restarted task a fresh window to beat again.
+                if heartbeat.last_beat_at().is_some() {
+                    heartbeat.beat();
+                }
+                let started_at = Instant::now();
+                let outcome = tokio::select! {
+                    outcome = heartbeat.clone().scope(factory()) => outcome,
+                    _ = shutdown.recv() => {
+                        tracing::trace!(task = %name, "Stopping task");
+                        this.set_stopped(&name).await;
+                        break;
+                    }
+                };
+                let reason = match outcome {
+                    Ok(()) => String::from("task exited"),
+                    Err(e) => e.to_string(),
+                };
+                if started_at.elapsed() >= HEALTHY_RUN_DURATION {
+                    backoff.reset();
+                }
+                let delay =
+                    backoff.next_backoff().unwrap_or(backoff.max_interval);
+                tracing::warn!(
+                    task = %name,
+                    %reason,
+                    "Task stopped, restarting in {}ms",
+                    delay.as_millis(),
+                );
+                this.set_restarting(&name, reason).await;
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {},
+                    _ = shutdown.recv() => {
+                        this.set_stopped(&name).await;
+                        break;
+                    }
+                }
+            }
+        };
+        tokio::task::spawn(task);
+    }
+
+    /// Returns the health of all supervised tasks, by name.
+    pub fn health(&self) -> BTreeMap<String, TaskHealth> {
+        let mut tasks =
+            self.tasks.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let heartbeats =
+            self.heartbeats.read().unwrap_or_else(|e| e.into_inner());
+        for (name, task) in tasks.iter_mut() {
+            task.last_heartbeat_at =
+                heartbeats.get(name).and_then(Heartbeat::last_beat_at);
+        }
+        tasks
+    }
+
+    /// Returns the names of the running tasks that report heartbeats, but
+    /// did not beat for longer than `timeout`.
+    pub fn stalled_tasks(&self, timeout: Duration) -> Vec<String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.health()
+            .into_iter()
+            .filter(|(_, task)| task.status == TaskStatus::Running)
+            .filter(|(_, task)| {
+                task.last_heartbeat_at.map_or(false, |at| {
+                    now.saturating_sub(at) > timeout.as_secs()
+                })
+            })
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Returns `true` if all supervised tasks are running.
+    pub fn is_healthy(&self) -> bool {
+        self.tasks
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .all(|task| task.status != TaskStatus::Restarting)
+    }
+
+    async fn set_running(&self, name: &str) {
+        let last_started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        {
+            let mut tasks =
+                self.tasks.write().unwrap_or_else(|e| e.into_inner());
+            let task =
+                tasks.entry(name.to_owned()).or_insert_with(|| TaskHealth {
+                    status: TaskStatus::Running,
+                    restarts: 0,
+                    last_error: None,
+                    last_started_at,
+                    last_heartbeat_at: None,
+                });
+            task.status = TaskStatus::Running;
+            task.last_started_at = last_started_at;
+        }
+        self.metrics.lock().await.supervised_tasks_running.inc();
+    }
+
+    async fn set_restarting(&self, name: &str, reason: String) {
+        {
+            let mut tasks =
+                self.tasks.write().unwrap_or_else(|e| e.into_inner());
+            if let Some(task) = tasks.get_mut(name) {
+                task.status = TaskStatus::Restarting;
+                task.restarts += 1;
+                task.last_error = Some(reason);
+            }
+        }
+        let metrics = self.metrics.lock().await;
+        metrics.supervised_tasks_running.dec();
+        metrics.supervised_task_restarts.inc();
+    }
+
+    async fn set_stopped(&self, name: &str) {
+        let was_running = {
+            let mut tasks =
+                self.tasks.write().unwrap_or_else(|e| e.into_inner());
+            tasks
+                .get_mut(name)
+                .map(|task| {
+                    let was_running = task.status == TaskStatus::Running;
+                    task.status = TaskStatus::Stopped;
+                    was_running
+                })
+                .unwrap_or(false)
+        };
+        if was_running {
+            self.metrics.lock().await.supervised_tasks_running.dec();
+        }
+    }
+}
@@ -15,16 +15,21 @@
 #![allow(clippy::large_enum_variant)]
 #![allow(missing_docs)]
 
+use core::fmt;
+use core::ops::Deref;
+use core::str::FromStr;
+
 use serde::{Deserialize, Deserializer, Serialize};
 use webb::evm::ethers::abi::Address;
 use webb::evm::ethers::prelude::I256;
 
 use webb::evm::ethers::types::Bytes;
 use webb::evm::ethers::types::{H256, U256};
+use webb::evm::ethers::utils::to_checksum;
+
+use webb_relayer_tx_relay_utils::chain::{ChainTypes, Command};
 
-use webb_relayer_tx_relay_utils::{
-    MaspRelayTransaction, VAnchorRelayTransaction,
-};
+pub use webb_relayer_tx_relay_utils::WrapFunding;
 
 /// Representation for IP address response
 #[derive(Debug, Serialize)]
@@ -70,21 +75,91 @@ impl<'de> Deserialize<'de> for WebbI128 {
     }
 }
 
-/// The command type for EVM vanchor transactions
-pub type EvmVanchorCommand = EvmCommandType<
-    Bytes,    // Proof bytes
-    Bytes,    // Roots format
-    H256,     // Element type
-    Address,  // Account identifier
-    U256,     // Balance type
-    WebbI256, // Signed amount type
-    Address,  // Token Address
->;
-
-/// Enumerates the supported protocols for relaying transactions
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub enum EvmCommandType<P, R, E, I, B, A, T> {
-    VAnchor(VAnchorRelayTransaction<P, R, E, I, B, A, T>),
-    MaspVanchor(MaspRelayTransaction<P, R, E, I, B, A, T>),
+/// An EVM address, validated against its EIP-55 checksum when deserialized.
+///
+/// An address in mixed case must match its checksum, while one in a single
+/// case carries no checksum and is accepted as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(transparent)]
+pub struct EvmAddress(pub Address);
+
+impl<'de> Deserialize<'de> for EvmAddress {
+    fn deserialize<D>(deserializer: D) -> Result<EvmAddress, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        let hex = value.strip_prefix("0x").ok_or_else(|| {
+            serde::de::Error::custom(format!("{value} is not 0x-prefixed"))
+        })?;
+        if hex.len() != 40 {
+            return Err(serde::de::Error::invalid_length(
+                hex.len() / 2,
+                &"an address of 20 bytes",
+            ));
+        }
+        let address =
+            Address::from_str(hex).map_err(serde::de::Error::custom)?;
+        let mixed_case = hex.chars().any(|c| c.is_ascii_lowercase())
+            && hex.chars().any(|c| c.is_ascii_uppercase());
+        if mixed_case && to_checksum(&address, None) != value {
+            return Err(serde::de::Error::custom(format!(
+                "{value} does not match its checksum"
+            )));
+        }
+        Ok(EvmAddress(address))
+    }
+}
+
+impl Deref for EvmAddress {
+    type Target = Address;
+
+    fn deref(&self) -> &Address {
+        &self.0
+    }
 }
+
+impl From<Address> for EvmAddress {
+    fn from(address: Address) -> Self {
+        EvmAddress(address)
+    }
+}
+
+impl From<EvmAddress> for Address {
+    fn from(address: EvmAddress) -> Self {
+        address.0
+    }
+}
+
+impl PartialEq<Address> for EvmAddress {
+    fn eq(&self, other: &Address) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for EvmAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// The types of the commands relayed to EVM chains.
+#[derive(Debug, Clone, Copy)]
+pub struct Evm;
+
+impl ChainTypes for Evm {
+    type Proof = Bytes;
+    type Roots = Bytes;
+    type Element = H256;
+    type Identifier = EvmAddress;
+    type Balance = U256;
+    type SignedAmount = WebbI256;
+    type Token = EvmAddress;
+}
+
+/// Enumerates the supported protocols for relaying transactions to EVM
+/// chains.
+pub type EvmCommandType = Command<Evm>;
+
+/// The command type for EVM vanchor transactions
+pub type EvmVanchorCommand = EvmCommandType;
@@ -0,0 +1,91 @@
+#![allow(clippy::large_enum_variant)]
+#![warn(missing_docs)]
+use axum::extract::{Path, State};
+use axum::Json;
+use ethereum_types::{Address, U256};
+use std::sync::Arc;
+use webb_proposals::TypedChainId;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_tx_relay::evm::fees::{get_evm_fee_info, EvmFeeInfo};
+use webb_relayer_utils::HandlerError;
+
+/// Handler for fee estimation
+///
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::Json;
+use ethereum_types::{Address, H256};
+use serde::Serialize;
+use webb_proposals::{TargetSystem, TypedChainId};
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::{AccountingStore, ProfitAndLoss};
+use webb_relayer_tx_relay::evm::fees::get_evm_net_profit_usd;
+use webb_relayer_utils::HandlerError;
+
+/// The profit-and-loss ledger of a single resource
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceAccounting {
+    /// The chain id of the resource.
+    chain_id: u32,
+    /// The resource id (contract and chain).
+    resource_id: H256,
+    #[serde(flatten)]
+    ledger: ProfitAndLoss,
+    /// The net profit in USD, valued at the current token prices.
+    ///
+    /// `null` if the prices could not be fetched.
+    net_profit_usd: Option<f64>,
+}
+
+/// Accounting response struct
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountingResponse {
+    /// The ledger of every resource the relayer sent transactions to.
+    resources: Vec<ResourceAccounting>,
+}
+
+/// Handles the profit-and-loss accounting requests
+///
+/// Returns the fees collected and the gas and refunds paid for every
+/// resource, along with the net profit in USD.
+pub async fn handle_accounting(
+    State(ctx): State<Arc<RelayerContext>>,
+) -> Result<Json<AccountingResponse>, HandlerError> {
+    let ledgers = ctx.store().get_profit_and_loss()?;
+    let mut resources = Vec::with_capacity(ledgers.len());
+    for (resource_id, ledger) in ledgers {
+        let chain_id = resource_id.typed_chain_id();
+        let net_profit_usd = match (chain_id, resource_id.target_system()) {
+            (TypedChainId::Evm(_), TargetSystem::ContractAddress(address)) => {
+                let contract = Address::from(address);
+                net_profit_usd(&ctx, chain_id, contract, &ledger).await
+            }
+            _ => None,
+        };
+        resources.push(ResourceAccounting {
+            chain_id: chain_id.underlying_chain_id(),
+            resource_id: H256::from(resource_id.to_bytes()),
+            ledger,
+            net_profit_usd,
+        });
+    }
+    Ok(Json(AccountingResponse { resources }))
+}
+
+async fn net_profit_usd(
+    ctx: &RelayerContext,
+    chain_id: TypedChainId,
+    contract: Address,
+    ledger: &ProfitAndLoss,
+) -> Option<f64> {
+    match get_evm_net_profit_usd(chain_id, contract, ledger, ctx).await {
+        Ok(profit) => Some(profit),
+        Err(e) => {
+            tracing::warn!(?e, %contract, "Failed to value the ledger in USD");
+            None
+        }
+    }
+}
@@ -0,0 +1,359 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::Json;
+use ethereum_types::Address;
+use schemars::schema::RootSchema;
+use serde::{Deserialize, Serialize};
+use webb::evm::ethers::utils::keccak256;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::{
+    EncryptedOutputCacheStore, HistoryStore, LeafCacheStore,
+};
+use webb_relayer_utils::HandlerError;
+
+/// Runtime status of a configured chain
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainStatusResponse {
+    /// The chain id.
+    chain_id: u32,
+    /// The chain name.
+    name: String,
+    /// Whether the chain is enabled at runtime.
+    enabled: bool,
+}
+
+/// Whether the relayer is paused
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseStatusResponse {
+    /// Whether the relayer is paused.
+    paused: bool,
+}
+
+/// The filter of the relayer logs
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilterResponse {
+    /// The `EnvFilter` directives the logs are filtered with.
+    filter: String,
+}
+
+/// A new filter for the relayer logs
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilterRequest {
+    /// The `EnvFilter` directives, e.g. `webb_relayer=info,webb_probe=trace`.
+    filter: String,
+}
+
+/// Result of a leaf cache resync request
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResyncResponse {
+    /// The chain id.
+    chain_id: u32,
+    /// The contract address.
+    contract: Address,
+    /// The block number the watcher will start syncing from again.
+    from_block: u64,
+}
+
+/// Makes sure the admin API is enabled and the request carries the
+/// configured bearer token.
+pub fn authorize(
+    ctx: &RelayerContext,
+    headers: &HeaderMap,
+) -> Result<(), HandlerError> {
+    let admin = &ctx.config.admin;
+    if !admin.enabled {
+        return Err(HandlerError(
+            StatusCode::NOT_FOUND,
+            "Admin API is disabled".to_string(),
+        ));
+    }
+    // the config requires a token with the admin API enabled, never serve
+    // the admin routes without one.
+    let Some(expected) = &admin.api_token else {
+        return Err(HandlerError(
+            StatusCode::UNAUTHORIZED,
+            "Admin API token is not configured".to_string(),
+        ));
+    };
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // compare the hashes in constant time, to leak neither the token nor
+    // its length through timing.
+    let matches = keccak256(provided)
+        .iter()
+        .zip(keccak256(expected))
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0;
+    if matches {
+        Ok(())
+    } else {
+        Err(HandlerError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid admin API token".to_string(),
+        ))
+    }
+}
+
+/// Returns the name of the configured chain, if any.
+fn chain_name(ctx: &RelayerContext, chain_id: u32) -> Option<String> {
+    let key = chain_id.to_string();
+    ctx.config
+        .evm
+        .get(&key)
+        .map(|c| c.name.clone())
+        .or_else(|| ctx.config.substrate.get(&key).map(|c| c.name.clone()))
+}
+
+fn chain_status(
+    ctx: &RelayerContext,
+    chain_id: u32,
+) -> Result<ChainStatusResponse, HandlerError> {
+    let name = chain_name(ctx, chain_id).ok_or_else(|| {
+        HandlerError(
+            StatusCode::NOT_FOUND,
+            format!("Chain {chain_id} is not configured"),
+        )
+    })?;
+    Ok(ChainStatusResponse {
+        chain_id,
+        name,
+        enabled: !ctx.is_chain_disabled(chain_id),
+    })
+}
+
+/// Handles listing the runtime status of all configured chains
+///
+/// Returns a Result with the list of `ChainStatusResponse` on success
+pub async fn handle_list_chains(
+    State(ctx): State<Arc<RelayerContext>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ChainStatusResponse>>, HandlerError> {
+    authorize(&ctx, &headers)?;
+    let chain_ids = ctx
+        .config
+        .evm
+        .values()
+        .filter(|c| c.enabled)
+        .map(|c| c.chain_id)
+        .chain(
+            ctx.config
+                .substrate
+                .values()
+                .filter(|c| c.enabled)
+                .map(|c| c.chain_id),
+        );
+    let chains = chain_ids
+        .map(|chain_id| chain_status(&ctx, chain_id))
+        .collect::<Result<_, _>>()?;
+    Ok(Json(chains))
+}
+
+/// Handles disabling a chain at runtime
+///
+/// Stops the chain's watchers and pauses its transaction queue, without
+/// dropping the queued items.
+///
+/// # Arguments
+///
+/// * `chain_id` - The chain id of the chain to disable.
+pub async fn handle_disable_chain(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path(chain_id): Path<u32>,
+    headers: HeaderMap,
+) -> Result<Json<ChainStatusResponse>, HandlerError> {
+    authorize(&ctx, &headers)?;
+    // make sure the chain is configured before disabling it.
+    chain_status(&ctx, chain_id)?;
+    ctx.disable_chain(chain_id);
+    chain_status(&ctx, chain_id).map(Json)
+}
+
+/// Handles re-enabling a chain that was disabled at runtime
+///
+/// # Arguments
+///
+/// * `chain_id` - The chain id of the chain to enable.
+pub async fn handle_enable_chain(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path(chain_id): Path<u32>,
+    headers: HeaderMap,
+) -> Result<Json<ChainStatusResponse>, HandlerError> {
+    authorize(&ctx, &headers)?;
+    chain_status(&ctx, chain_id)?;
+    ctx.enable_chain(chain_id);
+    chain_status(&ctx, chain_id).map(Json)
+}
+
+/// Handles pausing the relayer on all chains
+///
+/// New relay requests are rejected and proposals stay queued until it is
+/// resumed, while the transactions already queued are still sent and the
+/// broadcast ones still finalized.
+pub async fn handle_pause(
+    State(ctx): State<Arc<RelayerContext>>,
+    headers: HeaderMap,
+) -> Result<Json<PauseStatusResponse>, HandlerError> {
+    authorize(&ctx, &headers)?;
+    ctx.set_paused(true);
+    Ok(Json(PauseStatusResponse {
+        paused: ctx.is_paused(),
+    }))
+}
+
+/// Handles resuming the relayer after it was paused
+pub async fn handle_resume(
+    State(ctx): State<Arc<RelayerContext>>,
+    headers: HeaderMap,
+) -> Result<Json<PauseStatusResponse>, HandlerError> {
+    authorize(&ctx, &headers)?;
+    ctx.set_paused(false);
+    Ok(Json(PauseStatusResponse {
+        paused: ctx.is_paused(),
+    }))
+}
+
+/// Handles getting the filter of the relayer logs
+pub async fn handle_log_filter(
+    State(ctx): State<Arc<RelayerContext>>,
+    headers: HeaderMap,
+) -> Result<Json<LogFilterResponse>, HandlerError> {
+    authorize(&ctx, &headers)?;
+    Ok(Json(LogFilterResponse {
+        filter: ctx.log_filter().directives(),
+    }))
+}
+
+/// Handles replacing the filter of the relayer logs
+///
+/// The new directives replace all the current ones, and apply right away
+/// without restarting the relayer, so its queues and watchers keep their
+/// state. Invalid directives are rejected and the current ones kept.
+///
+/// # Arguments
+///
+/// * `payload` - The new `EnvFilter` directives.
+pub async fn handle_set_log_filter(
+    State(ctx): State<Arc<RelayerContext>>,
+    headers: HeaderMap,
+    Json(payload): Json<LogFilterRequest>,
+) -> Result<Json<LogFilterResponse>, HandlerError> {
+    authorize(&ctx, &headers)?;
+    ctx.log_filter()
+        .set_directives(&payload.filter)
+        .map_err(|e| HandlerError(StatusCode::BAD_REQUEST, e))?;
+    tracing::warn!(filter = %payload.filter, "Log filter changed");
+    Ok(Json(LogFilterResponse {
+        filter: ctx.log_filter().directives(),
+    }))
+}
+
+/// Handles getting the JSON Schema of the configuration files
+///
+/// The schema is generated from the configuration types of the relayer, so
+/// it matches the configuration this version of the relayer accepts.
+pub async fn handle_config_schema(
+    State(ctx): State<Arc<RelayerContext>>,
+    headers: HeaderMap,
+) -> Result<Json<RootSchema>, HandlerError> {
+    authorize(&ctx, &headers)?;
+    Ok(Json(webb_relayer_config::utils::config_schema()))
+}
+
+/// Handles resyncing the leaf cache of a VAnchor contract
+///
+/// Clears the cached leaves and encrypted outputs of the contract and resets
+/// its sync checkpoint to the block it was deployed at, so the event watcher
+/// rebuilds the cache from scratch. The sync step of the watcher in flight,
+/// if any, finishes before the cache is cleared.
+///
+/// # Arguments
+///
+/// * `chain_id` - The chain id of the chain the contract is deployed on.
+/// * `contract` - The address of the VAnchor contract to resync.
+pub async fn handle_resync_leaves(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path((chain_id, contract)): Path<(u32, Address)>,
+    headers: HeaderMap,
+) -> Result<Json<ResyncResponse>, HandlerError> {
+    authorize(&ctx, &headers)?;
+    let chain = ctx.config.evm.get(&chain_id.to_string()).ok_or_else(|| {
+        HandlerError(
+            StatusCode::NOT_FOUND,
+            format!("Chain {chain_id} is not configured"),
+        )
+    })?;
+    let deployed_at = chain
+        .contracts
+        .iter()
+        .find_map(|c| match c {
+            webb_relayer_config::evm::Contract::VAnchor(c)
+                if c.common.address == contract =>
+            {
+                Some(c.common.deployed_at)
+            }
+            _ => None,
+        })
+        .ok_or_else(|| {
+            HandlerError(
+                StatusCode::NOT_FOUND,
+                format!(
+                    "VAnchor contract {contract} is not configured for chain {chain_id}"
+                ),
+            )
+        })?;
+    let history_store_key = ResourceId::new(
+        TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        TypedChainId::Evm(chain_id),
+    );
+    let store = ctx.store();
+    // fall back to the deployment block detected on chain, if not configured.
+    let deployed_at = match deployed_at {
+        0 => store
+            .get_deployment_block_number(history_store_key)?
+            .unwrap_or_default(),
+        deployed_at => deployed_at,
+    };
+    let sync_lock = ctx.sync_lock(history_store_key).await;
+    let _sync_guard = sync_lock.lock().await;
+    store.clear_leaves_cache(history_store_key)?;
+    store.clear_encrypted_output_cache(history_store_key)?;
+    // the handlers go through the events again, from the deployment block.
+    store.clear_handler_checkpoints(history_store_key)?;
+    store.set_last_block_number(history_store_key, deployed_at)?;
+    tracing::info!(
+        %chain_id,
+        %contract,
+        from_block = deployed_at,
+        "Leaf cache cleared, resyncing from the deployment block",
+    );
+    Ok(Json(ResyncResponse {
+        chain_id,
+        contract,
+        from_block: deployed_at,
+    }))
+}
@@ -17,13 +17,14 @@ use axum::http::StatusCode;
 use axum::Json;
 use ethereum_types::Address;
 use serde::Serialize;
+use std::ops::Range;
 use std::{collections::HashMap, sync::Arc};
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
 use webb_relayer_context::RelayerContext;
 use webb_relayer_store::EncryptedOutputCacheStore;
 use webb_relayer_utils::HandlerError;
 
-use super::OptionalRangeQuery;
+use super::{sign_data_query, OptionalRangeQuery};
 
 /// Response containing encrypted outputs.
 #[derive(Debug, Serialize)]
@@ -31,6 +32,10 @@ use super::OptionalRangeQuery;
 pub struct EncryptedOutputsCacheResponse {
     encrypted_outputs: Vec<String>,
     last_queried_block: u64,
+    /// The signature of the response by the relayer, if it has a key on
+    /// the chain.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
 }
 
 /// Handles encrypted outputs data requests for evm
@@ -111,18 +116,35 @@ pub async fn handle_encrypted_outputs_cache_evm(
     let src_typed_chain_id = TypedChainId::Evm(chain_id);
     let history_store_key =
         ResourceId::new(src_target_system, src_typed_chain_id);
-    let encrypted_output = ctx.store().get_encrypted_output_with_range(
-        history_store_key,
-        query_range.into(),
-    )?;
+    let range: Range<u32> = query_range.into();
+    let encrypted_output = ctx
+        .store()
+        .get_encrypted_output_with_range(history_store_key, range.clone())?;
     let last_queried_block = ctx
         .store()
         .get_last_deposit_block_number_for_encrypted_output(
             history_store_key,
         )?;
+    // the outputs are signed as bytes, not as their hex encoding.
+    let output_bytes = encrypted_output
+        .iter()
+        .map(|output| hex::decode(output.trim_start_matches("0x")))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            HandlerError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    let signature = sign_data_query(
+        &ctx,
+        history_store_key,
+        &range,
+        last_queried_block,
+        &output_bytes,
+    )
+    .await?;
 
     Ok(Json(EncryptedOutputsCacheResponse {
         encrypted_outputs: encrypted_output,
         last_queried_block,
+        signature,
     }))
 }
@@ -0,0 +1,146 @@
+//! Verification of the signed envelopes protecting the relay commands from
+//! replays.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ethereum_types::Address;
+use webb::evm::ethers::abi::{encode, Token};
+use webb::evm::ethers::types::Signature;
+use webb::evm::ethers::utils::keccak256;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_handler_utils::{
+    EvmCommandType, EvmVanchorCommand, WrapFunding,
+};
+use webb_relayer_store::RelayNonceStore;
+use webb_relayer_utils::TransactionRelayingError;
+
+/// How far (in seconds) the timestamp of an envelope may be ahead of the
+/// clock of the relayer.
+const MAX_CLOCK_SKEW: u64 = 30;
+
+/// The nonce of a verified envelope, released if its command is rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EnvelopeNonce {
+    signer: Address,
+    nonce: u64,
+}
+
+/// Verifies the signed envelope of a relay command, and uses its nonce. It
+/// is verified before any fee of the command is checked, so the nonce is
+/// used right away, and released if the command is rejected, see
+/// [`release_envelope_nonces`]: concurrent requests with the same envelope
+/// can't both pass the verification.
+///
+/// The envelope is signed (EIP-191) over
+/// `keccak256(abi.encode(chainId, contract, extDataHash, nonce, timestamp))`
+/// by the recipient of the command, or by the owner of the tokens for a
+/// wrap-and-deposit funded with a permit. A command without an envelope is
+/// accepted, unless the replay protection is required, and has no nonce.
+///
+/// # Arguments
+///
+/// * `ctx` - The relayer context
+/// * `chain_id` - The chain the command is relayed to
+/// * `contract` - The contract the command is relayed to
+/// * `cmd` - The command to verify
+pub(crate) fn verify_envelope(
+    ctx: &RelayerContext,
+    chain_id: u32,
+    contract: Address,
+    cmd: &EvmVanchorCommand,
+) -> Result<Option<EnvelopeNonce>, TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    let config = &ctx.config.replay_protection;
+    let (envelope, ext_data_hash, signers) = match cmd {
+        EvmCommandType::VAnchor(cmd) => (
+            &cmd.envelope,
+            cmd.proof_data.ext_data_hash,
+            vec![*cmd.ext_data.recipient],
+        ),
+        EvmCommandType::MaspVanchor(cmd) => (
+            &cmd.envelope,
+            cmd.proof_data.ext_data_hash,
+            vec![*cmd.ext_data.recipient],
+        ),
+        EvmCommandType::WrapAndDeposit(cmd) => {
+            let mut signers = vec![*cmd.ext_data.recipient];
+            if let WrapFunding::Permit { owner, .. } = &cmd.funding {
+                signers.push(**owner);
+            }
+            (&cmd.envelope, cmd.proof_data.ext_data_hash, signers)
+        }
+    };
+    let Some(envelope) = envelope else {
+        if config.required {
+            return Err(InvalidEnvelope(
+                "the command must carry a signed envelope".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if envelope.timestamp > now.saturating_add(MAX_CLOCK_SKEW) {
+        return Err(InvalidEnvelope(format!(
+            "the timestamp {} is in the future",
+            envelope.timestamp
+        )));
+    }
+    let expires_at = envelope.timestamp.saturating_add(config.max_age);
+    if expires_at <= now {
+        return Err(InvalidEnvelope(format!(
+            "the envelope expired at {expires_at}"
+        )));
+    }
+
+    let digest = keccak256(encode(&[
+        Token::Uint(chain_id.into()),
+        Token::Address(contract),
+        Token::FixedBytes(ext_data_hash.as_bytes().to_vec()),
+        Token::Uint(envelope.nonce.into()),
+        Token::Uint(envelope.timestamp.into()),
+    ]));
+    let signer = Signature::try_from(envelope.signature.as_ref())
+        .and_then(|signature| signature.recover(&digest[..]))
+        .map_err(|e| InvalidEnvelope(e.to_string()))?;
+    if !signers.contains(&signer) {
+        return Err(InvalidEnvelope(format!(
+            "signed by {signer:?}, not by the recipient of the command"
+        )));
+    }
+
+    let used = ctx
+        .store()
+        .use_relay_nonce(signer, envelope.nonce, now, expires_at)
+        .map_err(|e| ClientError(e.to_string()))?;
+    if !used {
+        return Err(ReplayedCommand(envelope.nonce));
+    }
+    Ok(Some(EnvelopeNonce {
+        signer,
+        nonce: envelope.nonce,
+    }))
+}
+
+/// Releases the nonces of the envelopes of a rejected command, or batch of
+/// commands, so the envelopes can be relayed again until they expire.
+pub(crate) fn release_envelope_nonces(
+    ctx: &RelayerContext,
+    nonces: impl IntoIterator<Item = Option<EnvelopeNonce>>,
+) {
+    for nonce in nonces.into_iter().flatten() {
+        let released =
+            ctx.store().release_relay_nonce(nonce.signer, nonce.nonce);
+        if let Err(e) = released {
+            tracing::warn!(
+                ?e,
+                signer = ?nonce.signer,
+                nonce = nonce.nonce,
+                "Failed to release the relay nonce",
+            );
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use super::*;
+use axum::extract::{Path, State};
+use std::sync::Arc;
+
+use axum::Json;
+use ethereum_types::{Address, U256};
+use webb_proposals::TypedChainId;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_handler_utils::EvmVanchorCommand;
+use webb_relayer_tx_relay::evm::fees::EvmFeeInfo;
+use webb_relayer_tx_relay::evm::vanchor::estimate_vanchor_relay_tx;
+use webb_relayer_utils::HandlerError;
+
+/// A prospective VAnchor command to estimate.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateTxRequest {
+    /// The address of the contract the transaction would be sent to.
+    contract: Address,
+    /// The command to estimate.
+    command: EvmVanchorCommand,
+}
+
+/// Estimate of a VAnchor command, as the relayer would relay it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateTxResponse {
+    /// Whether the relayer would accept the command.
+    accepted: bool,
+    /// The gas of the transaction, headroom included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_amount: Option<U256>,
+    /// The fee info the fee and refund of the command are checked against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fee_info: Option<EvmFeeInfo>,
+    /// The lowest fee accepted for the command, its refund included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_fee: Option<U256>,
+    /// The maximum refund per recipient, if the chain has a cap.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_refund_per_recipient: Option<U256>,
+    /// Why the relayer would reject the command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rejection: Option<WithdrawTxFailureResponse>,
+}
+
+/// Handles the estimation of a VAnchor command on an evm chain.
+///
+/// The command goes through all the validation and fee checks of a relay
+/// request, but is never enqueued.
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the chain.
+/// * `payload` - The contract and the command to estimate.
+pub async fn handle_estimate_evm(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path(chain_id): Path<u32>,
+    Json(payload): Json<EstimateTxRequest>,
+) -> Result<Json<EstimateTxResponse>, HandlerError> {
+    tracing::debug!(%chain_id, contract = %payload.contract, "Received estimate request");
+    let estimate = estimate_vanchor_relay_tx(
+        &ctx,
+        TypedChainId::Evm(chain_id),
+        payload.contract,
+        payload.command,
+    )
+    .await;
+    let quote = estimate.quote;
+    let rejection = estimate.result.err().map(|e| {
+        WithdrawTxFailureResponse::new("Transaction request would fail", &e)
+    });
+    Ok(Json(EstimateTxResponse {
+        accepted: rejection.is_none(),
+        gas_amount: quote.as_ref().map(|q| q.gas_amount),
+        min_fee: quote.as_ref().map(|q| q.min_fee),
+        max_refund_per_recipient: quote
+            .as_ref()
+            .and_then(|q| q.max_refund_per_recipient),
+        fee_info: quote.map(|q| q.fee_info),
+        rejection,
+    }))
+}
@@ -7,6 +7,8 @@ use std::sync::Arc;
 use webb_proposals::TypedChainId;
 use webb_relayer_context::RelayerContext;
 use webb_relayer_tx_relay::evm::fees::{get_evm_fee_info, EvmFeeInfo};
+use webb_relayer_tx_relay::evm::gas_simulation::get_simulated_transact_gas;
+use webb_relayer_tx_relay::evm::wrap_and_deposit::get_wrap_and_deposit_gas;
 use webb_relayer_utils::HandlerError;
 
 /// Handler for fee estimation
@@ -29,3 +31,49 @@ pub async fn handle_evm_fee_info(
             .map(Json)?,
     )
 }
+
+/// Handler for fee estimation, with the gas amount simulated by the relayer
+///
+/// The gas amount of a typical relayed transaction on the given VAnchor is
+/// simulated with a dummy proof, and cached per contract.
+///
+/// # Arguments
+///
+/// * `chain_id` - ID of the blockchain
+/// * `vanchor` - Address of the smart contract
+pub async fn handle_evm_simulated_fee_info(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path((chain_id, vanchor)): Path<(u32, Address)>,
+) -> Result<Json<EvmFeeInfo>, HandlerError> {
+    let chain_id = TypedChainId::Evm(chain_id);
+    let gas_amount =
+        get_simulated_transact_gas(chain_id, vanchor, ctx.as_ref()).await?;
+    Ok(
+        get_evm_fee_info(chain_id, vanchor, gas_amount, ctx.as_ref())
+            .await
+            .map(Json)?,
+    )
+}
+
+/// Handler for fee estimation of a wrap-and-deposit
+///
+/// The gas amount covers the simulated `transact` call wrapping the tokens,
+/// and the transactions funding the relayer with a permit.
+///
+/// # Arguments
+///
+/// * `chain_id` - ID of the blockchain
+/// * `vanchor` - Address of the smart contract
+pub async fn handle_evm_wrap_and_deposit_fee_info(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path((chain_id, vanchor)): Path<(u32, Address)>,
+) -> Result<Json<EvmFeeInfo>, HandlerError> {
+    let chain_id = TypedChainId::Evm(chain_id);
+    let gas_amount =
+        get_wrap_and_deposit_gas(chain_id, vanchor, ctx.as_ref()).await?;
+    Ok(
+        get_evm_fee_info(chain_id, vanchor, gas_amount, ctx.as_ref())
+            .await
+            .map(Json)?,
+    )
+}
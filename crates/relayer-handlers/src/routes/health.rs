@@ -0,0 +1,193 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use ethereum_types::U256;
+use serde::Serialize;
+use webb_proposals::TypedChainId;
+use webb_relayer_context::lifecycle::LifecycleState;
+use webb_relayer_context::supervisor::TaskHealth;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::AccountingStore;
+use webb_relayer_utils::circuit_breaker::CircuitState;
+
+/// Relayer health response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+    /// Whether all background tasks are running.
+    healthy: bool,
+    /// Whether this relayer is the leader among its replicas.
+    ///
+    /// always `true` if the leader election is disabled.
+    leader: bool,
+    /// Whether the relayer is running, or draining its transaction queues
+    /// on shutdown.
+    lifecycle: LifecycleState,
+    /// The health of each supervised background task, by name.
+    tasks: BTreeMap<String, TaskHealth>,
+}
+
+/// Handles relayer health requests
+///
+/// Returns `200 OK` if all background tasks are running, otherwise
+/// `503 Service Unavailable`, along with the health of each task. The
+/// relayer also responds with `503` while it drains its queues on shutdown.
+pub async fn handle_health(
+    State(ctx): State<Arc<RelayerContext>>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let supervisor = ctx.supervisor();
+    let healthy = supervisor.is_healthy();
+    let lifecycle = ctx.lifecycle();
+    let status = if healthy && lifecycle == LifecycleState::Running {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(HealthResponse {
+            healthy,
+            leader: ctx.leadership().is_leader(),
+            lifecycle,
+            tasks: supervisor.health(),
+        }),
+    )
+}
+
+/// Relayer readiness response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessResponse {
+    /// Whether the relayer is ready to serve all of its chains.
+    ///
+    /// `false` if any chain has its circuit breaker open.
+    ready: bool,
+    /// The circuit breaker state of each chain that has it enabled.
+    circuit_breakers: HashMap<String, CircuitState>,
+    /// Today's gas spend of each chain that has a daily gas budget.
+    gas_budgets: HashMap<String, GasBudgetResponse>,
+}
+
+/// The state of a chain's daily gas budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum GasBudgetState {
+    /// Relayed transactions are processed.
+    WithinBudget,
+    /// Relayed transactions are paused until the next day.
+    BudgetExceeded,
+}
+
+/// Today's gas spend of a chain against its daily budget.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasBudgetResponse {
+    /// Gas spent today, in wei.
+    spent: U256,
+    /// The daily budget, in wei.
+    ///
+    /// `None` if it could not be determined, in which case the budget is
+    /// considered exceeded.
+    limit: Option<U256>,
+    /// Whether relayed transactions are paused.
+    status: GasBudgetState,
+}
+
+/// Handles relayer readiness requests
+///
+/// Returns `200 OK` if all chains are operational, otherwise
+/// `503 Service Unavailable`, along with the circuit breaker
+/// state of each chain.
+pub async fn handle_health_ready(
+    State(ctx): State<Arc<RelayerContext>>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let circuit_breakers: HashMap<_, _> = ctx
+        .circuit_breakers()
+        .map(|(chain_id, cb)| (chain_id.to_string(), cb.state()))
+        .collect();
+    let ready = circuit_breakers
+        .values()
+        .all(|state| *state != CircuitState::Open);
+    let gas_budgets = gas_budgets(&ctx).await;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ReadinessResponse {
+            ready,
+            circuit_breakers,
+            gas_budgets,
+        }),
+    )
+}
+
+/// Today's gas spend of each enabled chain that has a daily gas budget.
+///
+/// An exceeded budget only pauses relayed transactions, so it does not
+/// affect the readiness of the relayer.
+async fn gas_budgets(
+    ctx: &RelayerContext,
+) -> HashMap<String, GasBudgetResponse> {
+    let today = webb_relayer_store::current_day();
+    let chains = ctx
+        .config
+        .evm
+        .values()
+        .filter(|chain| chain.enabled && chain.gas_budget.is_some());
+    let mut gas_budgets = HashMap::new();
+    for chain in chains {
+        let typed_chain_id = TypedChainId::Evm(chain.chain_id);
+        let spent = ctx
+            .store()
+            .get_daily_gas_spent(typed_chain_id, today)
+            .unwrap_or_else(|e| {
+                tracing::warn!(
+                    ?e,
+                    chain_id = chain.chain_id,
+                    "Failed to get the daily gas spend"
+                );
+                U256::zero()
+            });
+        let limit =
+            ctx.daily_gas_budget(chain.chain_id)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!(
+                        ?e,
+                        chain_id = chain.chain_id,
+                        "Failed to get the daily gas budget"
+                    );
+                    None
+                });
+        let status = match limit {
+            Some(limit) if spent < limit => GasBudgetState::WithinBudget,
+            _ => GasBudgetState::BudgetExceeded,
+        };
+        let response = GasBudgetResponse {
+            spent,
+            limit,
+            status,
+        };
+        gas_budgets.insert(chain.chain_id.to_string(), response);
+    }
+    gas_budgets
+}
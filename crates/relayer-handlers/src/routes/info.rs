@@ -1,14 +1,15 @@
 use axum::extract::State;
-use axum::Json;
+use axum::{Extension, Json};
 use axum_client_ip::InsecureClientIp;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use webb_relayer_handler_utils::IpInformationResponse;
 
 use serde::Serialize;
-use webb::evm::ethers::{
-    prelude::k256::SecretKey,
-    signers::{LocalWallet, Signer},
-};
+use webb::evm::ethers::signers::{LocalWallet, Signer};
+use webb::evm::ethers::types::Address;
+use webb::evm::ethers::utils::keccak256;
 use webb_relayer_context::RelayerContext;
 
 /// Build info data
@@ -31,6 +32,117 @@ pub struct RelayerConfig {
     pub config: webb_relayer_config::WebbRelayerConfig,
     /// Relayer build info
     pub build: BuildInfo,
+    /// The chains this relayer instance serves
+    pub shard: ShardInfo,
+    /// Whether the relayer is paused, and does not accept relay requests
+    pub paused: bool,
+    /// The signed identity of the relayer, if it has a key configured
+    pub identity: Option<RelayerIdentity>,
+    /// The chains under maintenance, on which the processing of relayed
+    /// transactions is delayed
+    pub maintenance: Vec<ChainMaintenance>,
+}
+
+/// A chain under maintenance
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainMaintenance {
+    /// The id of the chain
+    pub chain_id: u32,
+    /// The end of the maintenance window, in seconds since the unix epoch,
+    /// until which the transactions of the chain are held
+    pub until: u64,
+}
+
+/// The identity of a relayer, signed by its identity key.
+///
+/// The `id` is the address of the identity key, the same value as the
+/// `X-Relayer-Id` header of every response, so clients and registries can pin
+/// a relayer across endpoint changes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayerIdentity {
+    /// The id of the relayer, the address of its identity key
+    pub id: Address,
+    /// Version of the relayer
+    pub version: String,
+    /// Unix timestamp, in seconds, at which the identity was signed
+    pub timestamp: u64,
+    /// EIP-191 signature of `keccak256(id ‖ timestamp ‖ version)`, where
+    /// `timestamp` is a big-endian `u64`
+    pub signature: String,
+}
+
+impl RelayerIdentity {
+    /// Signs the identity of the relayer with its identity wallet.
+    pub async fn sign(
+        wallet: &LocalWallet,
+    ) -> Result<Self, webb::evm::ethers::signers::WalletError> {
+        let id = wallet.address();
+        let version = String::from(env!("CARGO_PKG_VERSION"));
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let mut message = Vec::new();
+        message.extend_from_slice(id.as_bytes());
+        message.extend_from_slice(&timestamp.to_be_bytes());
+        message.extend_from_slice(version.as_bytes());
+        let signature = wallet.sign_message(keccak256(message)).await?;
+        Ok(Self {
+            id,
+            version,
+            timestamp,
+            signature: format!("0x{signature}"),
+        })
+    }
+}
+
+/// The accounts of the relayer, derived from its keys once at startup, as
+/// deriving a wallet from a mnemonic is expensive.
+#[derive(Debug, Clone, Default)]
+pub struct RelayerAccounts {
+    /// The signed identity of the relayer, if it has a key configured.
+    identity: Option<RelayerIdentity>,
+    /// The address of the wallet of each EVM chain with a key configured.
+    wallets: HashMap<u32, Address>,
+}
+
+impl RelayerAccounts {
+    /// Derives the wallets of the relayer, and signs its identity.
+    pub async fn load(ctx: &RelayerContext) -> Self {
+        let mut wallets = HashMap::new();
+        for chain in ctx.config.evm.values() {
+            if let Ok(wallet) = ctx.evm_wallet(chain.chain_id).await {
+                wallets.insert(chain.chain_id, wallet.address());
+            }
+        }
+        let identity = match ctx.identity_wallet().await {
+            Some(wallet) => match RelayerIdentity::sign(&wallet).await {
+                Ok(identity) => Some(identity),
+                Err(e) => {
+                    tracing::warn!(
+                        error = %e,
+                        "Failed to sign the relayer identity",
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        Self { identity, wallets }
+    }
+}
+
+/// The chains served by a relayer instance
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardInfo {
+    /// The identity of this instance, if it is part of a shard.
+    pub instance: Option<String>,
+    /// The ids of the chains this instance serves, i.e. the chains assigned
+    /// to it that are not disabled at runtime.
+    pub chains: Vec<u32>,
 }
 
 /// Relayer configuration response
@@ -46,24 +158,14 @@ pub struct RelayerInformationResponse {
 /// Returns a Result with the `RelayerConfigurationResponse` on success
 pub async fn handle_relayer_info(
     State(ctx): State<Arc<RelayerContext>>,
+    Extension(accounts): Extension<Arc<RelayerAccounts>>,
 ) -> Json<RelayerInformationResponse> {
     // clone the original config, to update it with accounts.
     let mut config = ctx.config.clone();
 
-    let _ = config
-        .evm
-        .values_mut()
-        .filter(|v| v.beneficiary.is_none())
-        .try_for_each(|v| {
-            let key = v
-                .private_key
-                .as_ref()
-                .ok_or(webb_relayer_utils::Error::MissingSecrets)?;
-            let key = SecretKey::from_bytes(key.as_bytes().into())?;
-            let wallet = LocalWallet::from(key);
-            v.beneficiary = Some(wallet.address());
-            webb_relayer_utils::Result::Ok(())
-        });
+    for v in config.evm.values_mut().filter(|v| v.beneficiary.is_none()) {
+        v.beneficiary = accounts.wallets.get(&v.chain_id).copied();
+    }
 
     // Build info
     let build_info = BuildInfo {
@@ -71,9 +173,36 @@ pub async fn handle_relayer_info(
         commit: env!("GIT_COMMIT").into(),
         timestamp: env!("SOURCE_TIMESTAMP").into(),
     };
+    let mut chains: Vec<u32> = config
+        .evm
+        .values()
+        .map(|chain| chain.chain_id)
+        .chain(config.substrate.values().map(|node| node.chain_id))
+        .filter(|chain_id| !ctx.is_chain_disabled(*chain_id))
+        .collect();
+    chains.sort_unstable();
+    chains.dedup();
+    let maintenance = chains
+        .iter()
+        .filter_map(|chain_id| {
+            let until = ctx.maintenance_until(*chain_id)?;
+            Some(ChainMaintenance {
+                chain_id: *chain_id,
+                until,
+            })
+        })
+        .collect();
+    let shard = ShardInfo {
+        instance: config.shard.instance.clone(),
+        chains,
+    };
     let relayer_config = RelayerConfig {
         config,
         build: build_info,
+        shard,
+        paused: ctx.is_paused(),
+        identity: accounts.identity.clone(),
+        maintenance,
     };
 
     Json(RelayerInformationResponse { relayer_config })
@@ -0,0 +1,215 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use ethereum_types::{H256, H512, U256};
+use serde::Serialize;
+use webb::evm::ethers::types::transaction::eip2718::TypedTransaction;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::queue::{QueueItem, QueueItemState, QueueStore};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{
+    RelayJobStage, RelayJobStore, TangleJob, TangleJobStore,
+};
+use webb_relayer_utils::HandlerError;
+
+/// The lifecycle status of a relay job
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum RelayJobStatus {
+    /// The command passed validation and its transaction was built.
+    Validated,
+    /// Estimating the gas and checking the fee paid by the user.
+    CheckingFee,
+    /// The transaction is waiting in the tx queue.
+    Enqueued,
+    /// The transaction was being processed by an older relayer.
+    Processing {
+        /// A meaningful step for the current state.
+        step: String,
+        /// A meaningful progress percentage for the current state (0 to 1).
+        progress: Option<f32>,
+    },
+    /// The transaction passed its dry run, and is about to be broadcast.
+    DryRunPassed,
+    /// The transaction was broadcast, and is waiting to be mined.
+    Broadcast {
+        /// How many times it was broadcast, starting at 1.
+        attempt: u32,
+    },
+    /// The transaction was included in a block, not finalized yet.
+    InBlock {
+        /// The hash of the block.
+        #[serde(rename = "blockHash")]
+        block_hash: H256,
+    },
+    /// The transaction is waiting for the gas price to drop below the highest
+    /// one it may be sent with.
+    WaitingForGasPrice {
+        /// The highest gas price the transaction may be sent with.
+        #[serde(rename = "maxGasPrice")]
+        max_gas_price: U256,
+        /// The gas price when the transaction was last checked.
+        #[serde(rename = "gasPrice")]
+        gas_price: U256,
+    },
+    /// The transaction was finalized on chain.
+    Finalized {
+        /// Transaction hash.
+        #[serde(rename = "txHash")]
+        tx_hash: H256,
+        /// The block the transaction was included in.
+        #[serde(rename = "blockNumber")]
+        block_number: Option<u64>,
+        /// The gas used by the transaction.
+        #[serde(rename = "gasUsed")]
+        gas_used: Option<U256>,
+        /// The fee paid for the transaction, in wei.
+        #[serde(rename = "effectiveFee")]
+        effective_fee: Option<U256>,
+    },
+    /// The deadline of the transaction passed before it could be sent.
+    Expired,
+    /// The job failed.
+    Failed {
+        /// The error message.
+        reason: String,
+    },
+    /// The transaction is no longer in the tx queue, it expired or was removed.
+    Dropped,
+}
+
+/// Relay job response struct
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayJobResponse {
+    job_id: H256,
+    chain_id: u32,
+    item_key: H512,
+    created_at: u64,
+    updated_at: u64,
+    #[serde(flatten)]
+    status: RelayJobStatus,
+}
+
+/// Handles relay job status requests
+///
+/// Before the transaction is enqueued the status comes from the job itself,
+/// after that it follows the state of the item in the tx queue.
+///
+/// Returns a Result with the `RelayJobResponse` on success
+///
+/// # Arguments
+///
+/// * `job_id` - The 32 bytes id returned when the job was submitted.
+pub async fn handle_relay_job_status(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path(job_id): Path<H256>,
+) -> Result<Json<RelayJobResponse>, HandlerError> {
+    let store = ctx.store();
+    let job = store.get_relay_job(job_id)?.ok_or_else(|| {
+        HandlerError(
+            StatusCode::NOT_FOUND,
+            format!("Relay job {job_id:?} not found"),
+        )
+    })?;
+    let status = match job.stage {
+        RelayJobStage::Validated => RelayJobStatus::Validated,
+        RelayJobStage::CheckingFee => RelayJobStatus::CheckingFee,
+        RelayJobStage::Failed { reason } => RelayJobStatus::Failed { reason },
+        RelayJobStage::Enqueued => {
+            let maybe_item: Option<QueueItem<TypedTransaction>> = store
+                .get_item(SledQueueKey::from_evm_with_custom_key(
+                    job.chain_id,
+                    job.item_key.0,
+                ))
+                .unwrap_or(None);
+            match maybe_item.map(|item| item.state()) {
+                Some(QueueItemState::Pending) => RelayJobStatus::Enqueued,
+                Some(QueueItemState::Processing { step, progress }) => {
+                    RelayJobStatus::Processing { step, progress }
+                }
+                Some(QueueItemState::Validated) => RelayJobStatus::DryRunPassed,
+                Some(QueueItemState::Broadcast { attempt }) => {
+                    RelayJobStatus::Broadcast { attempt }
+                }
+                Some(QueueItemState::InBlock { block_hash }) => {
+                    RelayJobStatus::InBlock { block_hash }
+                }
+                Some(QueueItemState::WaitingForGasPrice {
+                    max_gas_price,
+                    gas_price,
+                }) => RelayJobStatus::WaitingForGasPrice {
+                    max_gas_price,
+                    gas_price,
+                },
+                Some(QueueItemState::Processed {
+                    tx_hash,
+                    block_number,
+                    gas_used,
+                    effective_fee,
+                }) => RelayJobStatus::Finalized {
+                    tx_hash,
+                    block_number,
+                    gas_used,
+                    effective_fee,
+                },
+                Some(QueueItemState::Expired) => RelayJobStatus::Expired,
+                Some(QueueItemState::Failed { reason }) => {
+                    RelayJobStatus::Failed { reason }
+                }
+                None => RelayJobStatus::Dropped,
+            }
+        }
+    };
+    Ok(Json(RelayJobResponse {
+        job_id: job.job_id,
+        chain_id: job.chain_id,
+        item_key: job.item_key,
+        created_at: job.created_at,
+        updated_at: job.updated_at,
+        status,
+    }))
+}
+
+/// Handles Tangle job status requests
+///
+/// Reports the stage of a job of the jobs pallet, as tracked by the jobs
+/// pallet watcher of the given Tangle node.
+///
+/// Returns a Result with the `TangleJob` on success
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the Tangle node
+/// * `job_id` - The id of the job on the jobs pallet
+pub async fn handle_tangle_job_status(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path((chain_id, job_id)): Path<(u32, u64)>,
+) -> Result<Json<TangleJob>, HandlerError> {
+    let job =
+        ctx.store()
+            .get_tangle_job(chain_id, job_id)?
+            .ok_or_else(|| {
+                HandlerError(
+                    StatusCode::NOT_FOUND,
+                    format!("Job {job_id} not found on chain {chain_id}"),
+                )
+            })?;
+    Ok(Json(job))
+}
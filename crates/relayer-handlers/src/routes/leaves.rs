@@ -15,17 +15,21 @@
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::Json;
+use std::ops::Range;
+use std::time::Duration;
 use std::{collections::HashMap, sync::Arc};
+use webb::evm::contract::protocol_solidity::variable_anchor::VAnchorContract;
 use webb::evm::ethers::types;
 
 use ethereum_types::Address;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use webb_ew_evm::vanchor::compute_merkle_root;
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
 use webb_relayer_context::RelayerContext;
 use webb_relayer_store::LeafCacheStore;
 use webb_relayer_utils::HandlerError;
 
-use super::OptionalRangeQuery;
+use super::{sign_data_query, OptionalRangeQuery};
 
 /// Leaves cache response
 #[derive(Debug, Serialize)]
@@ -33,6 +37,10 @@ use super::OptionalRangeQuery;
 pub struct LeavesCacheResponse {
     leaves: Vec<types::H256>,
     last_queried_block: u64,
+    /// The signature of the response by the relayer, if it has a key on
+    /// the chain.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
 }
 
 /// Handles leaf data requests for evm
@@ -112,16 +120,178 @@ pub async fn handle_leaves_cache_evm(
     let src_typed_chain_id = TypedChainId::Evm(chain_id);
     let history_store_key =
         ResourceId::new(src_target_system, src_typed_chain_id);
+    let range: Range<u32> = query_range.into();
     let leaves = ctx
         .store()
-        .get_leaves_with_range(history_store_key, query_range.into())
+        .get_leaves_with_range(history_store_key, range.clone())
         .map(|tree| tree.into_values().collect::<Vec<_>>())?;
     let last_queried_block = ctx
         .store()
         .get_last_deposit_block_number(history_store_key)?;
 
+    // an explicit range the local cache has not reached yet is served by
+    // the peers, if enabled.
+    let is_behind = range.end != u32::MAX
+        && leaves.len() < range.end.saturating_sub(range.start) as usize;
+    if config.peers.fallback_query && is_behind {
+        let peer_leaves = query_peer_leaves(
+            &ctx,
+            chain_id,
+            contract,
+            history_store_key,
+            range.end as usize,
+        )
+        .await;
+        match peer_leaves {
+            Ok((peer_leaves, peer_queried_block)) => {
+                let start = (range.start as usize).min(peer_leaves.len());
+                let end = (range.end as usize).min(peer_leaves.len());
+                // the peers may be behind as well.
+                if end - start > leaves.len() {
+                    let leaves = peer_leaves[start..end].to_vec();
+                    let signature = sign_data_query(
+                        &ctx,
+                        history_store_key,
+                        &range,
+                        peer_queried_block,
+                        &leaves,
+                    )
+                    .await?;
+                    return Ok(Json(LeavesCacheResponse {
+                        leaves,
+                        last_queried_block: peer_queried_block,
+                        signature,
+                    }));
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    %chain_id,
+                    %contract,
+                    error = %e,
+                    "Failed to query the leaves from the peers",
+                );
+            }
+        }
+    }
+
+    let signature = sign_data_query(
+        &ctx,
+        history_store_key,
+        &range,
+        last_queried_block,
+        &leaves,
+    )
+    .await?;
+
     Ok(Json(LeavesCacheResponse {
         leaves,
         last_queried_block,
+        signature,
     }))
 }
+
+/// The leaves of a contract, as served by a peer.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PeerLeavesResponse {
+    leaves: Vec<types::H256>,
+    last_queried_block: u64,
+}
+
+/// Returns the leaves of the contract from the peers, with the block they
+/// were queried at.
+///
+/// The cached leaves are reused if there are at least `min_len` of them, or
+/// as many as the contract has on chain. Otherwise they are taken from the
+/// first peer whose leaves match a root known by the contract on chain, and
+/// cached in memory, one refresh per contract at a time. They are never
+/// written to the leaves cache, which only holds leaves seen by the events
+/// watcher.
+async fn query_peer_leaves(
+    ctx: &RelayerContext,
+    chain_id: u32,
+    contract: Address,
+    resource_id: ResourceId,
+    min_len: usize,
+) -> webb_relayer_utils::Result<(Vec<types::H256>, u64)> {
+    match ctx.peer_leaves(resource_id) {
+        Some(cached) if cached.0.len() >= min_len => return Ok(cached),
+        _ => {}
+    }
+    let provider = ctx.evm_provider(chain_id).await?;
+    let anchor = VAnchorContract::new(contract, provider);
+    // the peers can not serve more leaves than the contract has.
+    let next_index = anchor.get_next_index().call().await?;
+    let min_len = min_len.min(next_index as usize);
+    // the requests waiting for the refresh in flight reuse its leaves.
+    let refresh = ctx.peer_leaves_refresh_lock(resource_id).await;
+    let _refresh = refresh.lock().await;
+    match ctx.peer_leaves(resource_id) {
+        Some(cached) if cached.0.len() >= min_len => return Ok(cached),
+        _ => {}
+    }
+    let zero_hash = anchor.get_zero_hash(0).call().await?;
+    // the roots of the peer leaves are checked through the quorum, if any.
+    let quorum_provider = ctx.evm_quorum_provider(chain_id).await?;
+    let quorum_anchor = VAnchorContract::new(contract, quorum_provider);
+    let mut empty_leaf = [0u8; 32];
+    zero_hash.to_big_endian(&mut empty_leaf);
+
+    let peers_config = &ctx.config.peers;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(peers_config.request_timeout))
+        .build()?;
+    for peer in &peers_config.urls {
+        let leaves_url =
+            peer.join(&format!("api/v1/leaves/evm/{chain_id}/{contract:?}"))?;
+        let response = async {
+            client
+                .get(leaves_url)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<PeerLeavesResponse>()
+                .await
+        }
+        .await;
+        let response = match response {
+            Ok(response) if !response.leaves.is_empty() => response,
+            Ok(_) => continue,
+            Err(e) => {
+                tracing::debug!(%peer, error = %e, "Peer query failed");
+                continue;
+            }
+        };
+        // hashing the whole tree is blocking, keep it off the async runtime.
+        let empty_leaf = empty_leaf.to_vec();
+        let (root, leaves) = tokio::task::spawn_blocking(move || {
+            compute_merkle_root(&response.leaves, empty_leaf)
+                .map(|root| (root, response.leaves))
+        })
+        .await
+        .map_err(|_| {
+            webb_relayer_utils::Error::Generic(
+                "computing the root of the peer leaves failed",
+            )
+        })??;
+        if !quorum_anchor.is_known_root(root).call().await? {
+            tracing::warn!(
+                %peer,
+                %chain_id,
+                %contract,
+                "The root of the peer leaves is not known on chain",
+            );
+            continue;
+        }
+        ctx.cache_peer_leaves(
+            resource_id,
+            leaves.clone(),
+            response.last_queried_block,
+        );
+        return Ok((leaves, response.last_queried_block));
+    }
+    Err(webb_relayer_utils::Error::Generic(
+        "no peer served leaves known on chain",
+    ))
+}
@@ -7,11 +7,17 @@ use ethereum_types::Address;
 use webb_proposals::TypedChainId;
 use webb_relayer_context::RelayerContext;
 use webb_relayer_handler_utils::EvmVanchorCommand;
-use webb_relayer_tx_relay::evm::masp_vanchor::handle_masp_vanchor_relay_tx;
+use webb_relayer_tx_relay::evm::masp_vanchor::submit_masp_vanchor_relay_job;
 use webb_relayer_utils::HandlerError;
 
+use super::envelope::{release_envelope_nonces, verify_envelope};
+
 /// Handles MASP tx withdrawal relaying request for evm chains.
 ///
+/// The request is validated and submitted as a relay job, the rest of the
+/// relaying happens in the background and can be tracked with the returned
+/// job id.
+///
 /// Returns a Result with the `WithdrawTxResponse`.
 ///
 /// # Arguments
@@ -25,8 +31,18 @@ pub async fn handle_masp_tx_relaying_evm(
     Json(payload): Json<EvmVanchorCommand>,
 ) -> Result<Json<WithdrawTxResponse>, HandlerError> {
     tracing::debug!(%chain_id, %contract, ?payload, "Received withdrawal request");
-    let response = handle_masp_vanchor_relay_tx(
-        ctx,
+    let nonce = match verify_envelope(&ctx, chain_id, contract, &payload) {
+        Ok(nonce) => nonce,
+        Err(e) => {
+            let response = WithdrawTxFailureResponse::new(
+                "Transaction request failed",
+                &e,
+            );
+            return Ok(Json(WithdrawTxResponse::Failure(response)));
+        }
+    };
+    let response = submit_masp_vanchor_relay_job(
+        ctx.clone(),
         TypedChainId::Evm(chain_id),
         contract,
         payload,
@@ -34,20 +50,21 @@ pub async fn handle_masp_tx_relaying_evm(
     .await;
 
     match response {
-        Ok(tx_item_key) => {
+        Ok((job_id, tx_item_key)) => {
             let response = WithdrawTxSuccessResponse {
                 status: "Sent".to_string(),
-                message: "Transaction sent successfully".to_string(),
+                message: "Transaction accepted for relaying".to_string(),
                 item_key: tx_item_key,
+                job_id: Some(job_id),
             };
             Ok(Json(WithdrawTxResponse::Success(response)))
         }
         Err(e) => {
-            let response = WithdrawTxFailureResponse {
-                status: "Failed".to_string(),
-                message: "Transaction request failed".to_string(),
-                reason: format!("{e}"),
-            };
+            release_envelope_nonces(&ctx, [nonce]);
+            let response = WithdrawTxFailureResponse::new(
+                "Transaction request failed",
+                &e,
+            );
             Ok(Json(WithdrawTxResponse::Failure(response)))
         }
     }
@@ -1,7 +1,7 @@
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::Json;
-use ethereum_types::Address;
+use ethereum_types::{Address, H256};
 use serde::Serialize;
 use std::sync::Arc;
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
@@ -21,6 +21,28 @@ pub struct ResourceMetricResponse {
     pub account_balance: String,
 }
 
+/// The metrics of a single resource
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceMetricsEntry {
+    /// The chain id of the resource.
+    pub chain_id: u32,
+    /// The resource id.
+    pub resource_id: H256,
+    /// Total gas spent on Resource.
+    pub total_gas_spent: String,
+    /// Total fees earned on Resource.
+    pub total_fee_earned: String,
+}
+
+/// Response with the metrics of all the resources
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesMetricsResponse {
+    /// The metrics of every resource the relayer kept metrics for.
+    pub resources: Vec<ResourceMetricsEntry>,
+}
+
 /// Handles relayer metric requests
 ///
 /// Returns a Result with the `MetricResponse` on success
@@ -57,3 +79,24 @@ pub async fn handle_evm_metric_info(
         account_balance,
     })
 }
+
+/// Handles the metrics requests for all the resources
+///
+/// Returns the `ResourcesMetricsResponse`, for dashboards that can't scrape
+/// the Prometheus metrics.
+pub async fn handle_resources_metric_info(
+    State(ctx): State<Arc<RelayerContext>>,
+) -> Json<ResourcesMetricsResponse> {
+    let metrics = ctx.metrics.lock().await;
+    let mut resources: Vec<_> = metrics
+        .resource_metrics()
+        .map(|(resource_id, metric)| ResourceMetricsEntry {
+            chain_id: resource_id.typed_chain_id().underlying_chain_id(),
+            resource_id: H256::from(resource_id.to_bytes()),
+            total_gas_spent: metric.total_gas_spent.get().to_string(),
+            total_fee_earned: metric.total_fee_earned.get().to_string(),
+        })
+        .collect();
+    resources.sort_unstable_by_key(|r| r.resource_id);
+    Json(ResourcesMetricsResponse { resources })
+}
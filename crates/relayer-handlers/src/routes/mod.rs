@@ -1,5 +1,14 @@
-use ethereum_types::H512;
+use axum::http::StatusCode;
+use ethereum_types::{H256, H512, U256};
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use webb::evm::ethers::signers::Signer;
+use webb::evm::ethers::utils::keccak256;
+use webb_proposals::ResourceId;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_utils::revert::humanize_error;
+use webb_relayer_utils::HandlerError;
+use webb_relayer_utils::{AmountKind, TransactionRelayingError};
 
 /// Module for handling encrypted commitment leaves API
 pub mod encrypted_outputs;
@@ -7,21 +16,51 @@ pub mod encrypted_outputs;
 /// Module for handle commitment leaves API
 pub mod leaves;
 
+/// Module for handling merkle roots history API
+pub mod roots;
+
 /// Module for handling relayer metric API
 pub mod metric;
 
 /// Module for handling relayer info API
 pub mod info;
 
+/// Module for handling relayer health API
+pub mod health;
+
+/// Module for handling relayer status API
+pub mod status;
+
+/// Module for handling relayer admin API
+pub mod admin;
+
 /// Module for handling fee info API
 pub mod fee_info;
 
+/// Module for handling transaction estimate API
+pub mod estimate;
+
+/// Module for handling transaction simulation API
+pub mod simulate;
+
 /// Module for handling transaction status API
 pub mod transaction_status;
 
+/// Module for handling relay jobs API
+pub mod jobs;
+
+/// Module for handling profit-and-loss accounting API
+pub mod accounting;
+
+/// Module for verifying the signed envelopes of the relay commands
+mod envelope;
+
 /// Module for handling private tx withdraw API
 pub mod private_tx_withdraw;
 
+/// Module for handling wrap-and-deposit relaying API
+pub mod wrap_and_deposit;
+
 /// Module for handling masp private tx withdrawal API
 #[cfg(feature = "masp-tx-relaying")]
 pub mod masp_tx_relaying;
@@ -66,6 +105,45 @@ impl From<OptionalRangeQuery> for core::ops::Range<u32> {
     }
 }
 
+/// Signs a data query response with the relayer key of the chain, so clients
+/// can hold the relayer accountable for the data it serves.
+///
+/// The signature is an EIP-191 signature of the digest
+/// `keccak256(resourceId ‖ start ‖ end ‖ lastQueriedBlock ‖ itemHashes)`,
+/// where `start` and `end` are big-endian `u32`, `lastQueriedBlock` is a
+/// big-endian `u64` and `itemHashes` are the `keccak256` hashes of the
+/// served items, in order. Returns `None` if no key is configured for the
+/// chain.
+pub(crate) async fn sign_data_query<I, T>(
+    ctx: &RelayerContext,
+    resource_id: ResourceId,
+    range: &Range<u32>,
+    last_queried_block: u64,
+    items: I,
+) -> Result<Option<String>, HandlerError>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let chain_id = resource_id.typed_chain_id().underlying_chain_id();
+    let Ok(wallet) = ctx.evm_wallet(chain_id).await else {
+        return Ok(None);
+    };
+    let mut message = Vec::new();
+    message.extend_from_slice(&resource_id.to_bytes());
+    message.extend_from_slice(&range.start.to_be_bytes());
+    message.extend_from_slice(&range.end.to_be_bytes());
+    message.extend_from_slice(&last_queried_block.to_be_bytes());
+    for item in items {
+        message.extend_from_slice(&keccak256(item.as_ref()));
+    }
+    let digest = keccak256(message);
+    let signature = wallet.sign_message(digest).await.map_err(|e| {
+        HandlerError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    Ok(Some(format!("0x{signature}")))
+}
+
 const fn default_zero() -> Option<u32> {
     Some(0)
 }
@@ -81,6 +159,9 @@ pub struct WithdrawTxSuccessResponse {
     status: String,
     message: String,
     item_key: H512,
+    /// The relay job tracking this transaction, if it was submitted as a job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    job_id: Option<H256>,
 }
 
 /// Failure response for withdrawal tx relaying API request.
@@ -90,6 +171,65 @@ pub struct WithdrawTxFailureResponse {
     status: String,
     message: String,
     reason: String,
+    /// The current minimum, if the amount or the fee was too low.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<AmountMinimum>,
+    /// The recent merkle roots known by the relayer, if the roots of the
+    /// proof are not among them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    known_roots: Vec<H256>,
+}
+
+impl WithdrawTxFailureResponse {
+    /// Creates a failure response for the given relaying error.
+    pub fn new(message: &str, error: &TransactionRelayingError) -> Self {
+        Self {
+            status: "Failed".to_string(),
+            message: message.to_string(),
+            reason: humanize_error(&error.to_string()),
+            minimum: AmountMinimum::from_error(error),
+            known_roots: known_roots_from_error(error),
+        }
+    }
+}
+
+/// The merkle roots known by the relayer, if the roots of the proof were not
+/// among them.
+fn known_roots_from_error(error: &TransactionRelayingError) -> Vec<H256> {
+    match error {
+        TransactionRelayingError::InvalidMerkleRoots { known_roots } => {
+            known_roots.clone()
+        }
+        TransactionRelayingError::InvalidBatchCommand(_, e) => {
+            known_roots_from_error(e)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The minimum accepted for an amount of a withdrawal that was too low.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmountMinimum {
+    kind: AmountKind,
+    amount: U256,
+}
+
+impl AmountMinimum {
+    fn from_error(error: &TransactionRelayingError) -> Option<Self> {
+        match error {
+            TransactionRelayingError::AmountTooLow {
+                kind, minimum, ..
+            } => Some(Self {
+                kind: *kind,
+                amount: *minimum,
+            }),
+            TransactionRelayingError::InvalidBatchCommand(_, e) => {
+                Self::from_error(e)
+            }
+            _ => None,
+        }
+    }
 }
 
 /// Withdrawal tx relaying API request response
@@ -102,3 +242,24 @@ pub enum WithdrawTxResponse {
     /// Failure response for withdrawal tx API request.
     Failure(WithdrawTxFailureResponse),
 }
+
+/// Success response for batch withdrawal tx relaying API request.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchWithdrawTxSuccessResponse {
+    status: String,
+    message: String,
+    batch_id: H256,
+    item_keys: Vec<H512>,
+}
+
+/// Batch withdrawal tx relaying API request response
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+#[serde(rename_all = "camelCase")]
+pub enum BatchWithdrawTxResponse {
+    /// Success response for batch withdrawal tx API request.
+    Success(BatchWithdrawTxSuccessResponse),
+    /// Failure response for batch withdrawal tx API request.
+    Failure(WithdrawTxFailureResponse),
+}
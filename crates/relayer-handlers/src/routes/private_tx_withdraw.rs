@@ -7,11 +7,19 @@ use ethereum_types::Address;
 use webb_proposals::TypedChainId;
 use webb_relayer_context::RelayerContext;
 use webb_relayer_handler_utils::EvmVanchorCommand;
-use webb_relayer_tx_relay::evm::vanchor::handle_vanchor_relay_tx;
-use webb_relayer_utils::HandlerError;
+use webb_relayer_tx_relay::evm::vanchor::{
+    handle_vanchor_relay_tx_batch, submit_vanchor_relay_job,
+};
+use webb_relayer_utils::{HandlerError, TransactionRelayingError};
+
+use super::envelope::{release_envelope_nonces, verify_envelope};
 
 /// Handles private tx withdraw request for evm chains.
 ///
+/// The request is validated and submitted as a relay job, the rest of the
+/// relaying happens in the background and can be tracked with the returned
+/// job id.
+///
 /// Returns a Result with the `WithdrawTxResponse`.
 ///
 /// # Arguments
@@ -25,8 +33,18 @@ pub async fn handle_private_tx_withdraw_evm(
     Json(payload): Json<EvmVanchorCommand>,
 ) -> Result<Json<WithdrawTxResponse>, HandlerError> {
     tracing::debug!(%chain_id, %contract, ?payload, "Received withdrawal request");
-    let response = handle_vanchor_relay_tx(
-        ctx,
+    let nonce = match verify_envelope(&ctx, chain_id, contract, &payload) {
+        Ok(nonce) => nonce,
+        Err(e) => {
+            let response = WithdrawTxFailureResponse::new(
+                "Transaction request failed",
+                &e,
+            );
+            return Ok(Json(WithdrawTxResponse::Failure(response)));
+        }
+    };
+    let response = submit_vanchor_relay_job(
+        ctx.clone(),
         TypedChainId::Evm(chain_id),
         contract,
         payload,
@@ -34,21 +52,103 @@ pub async fn handle_private_tx_withdraw_evm(
     .await;
 
     match response {
-        Ok(tx_item_key) => {
+        Ok((job_id, tx_item_key)) => {
             let response = WithdrawTxSuccessResponse {
                 status: "Sent".to_string(),
-                message: "Transaction sent successfully".to_string(),
+                message: "Transaction accepted for relaying".to_string(),
                 item_key: tx_item_key,
+                job_id: Some(job_id),
             };
             Ok(Json(WithdrawTxResponse::Success(response)))
         }
         Err(e) => {
-            let response = WithdrawTxFailureResponse {
-                status: "Failed".to_string(),
-                message: "Transaction request failed".to_string(),
-                reason: format!("{e}"),
-            };
+            release_envelope_nonces(&ctx, [nonce]);
+            let response = WithdrawTxFailureResponse::new(
+                "Transaction request failed",
+                &e,
+            );
             Ok(Json(WithdrawTxResponse::Failure(response)))
         }
     }
 }
+
+/// A single withdrawal in a batch withdraw request.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchWithdrawTxItem {
+    /// The address of the contract to submit the transaction to.
+    contract: Address,
+    /// The command to execute.
+    command: EvmVanchorCommand,
+}
+
+/// Handles a batch of private tx withdraw requests for the same evm chain.
+///
+/// Returns a Result with the `BatchWithdrawTxResponse`.
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the chain.
+/// * `payload` - The list of contracts and commands to execute on them.
+pub async fn handle_private_tx_withdraw_batch_evm(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path(chain_id): Path<u32>,
+    Json(payload): Json<Vec<BatchWithdrawTxItem>>,
+) -> Result<Json<BatchWithdrawTxResponse>, HandlerError> {
+    tracing::debug!(
+        %chain_id,
+        batch_size = payload.len(),
+        "Received batch withdrawal request"
+    );
+    // a rejected command releases the nonces of the rest of the batch, and
+    // two commands sharing the nonce of a signer can't both use it.
+    let mut nonces = Vec::with_capacity(payload.len());
+    for (i, item) in payload.iter().enumerate() {
+        let verified =
+            verify_envelope(&ctx, chain_id, item.contract, &item.command)
+                .map_err(|e| {
+                    TransactionRelayingError::InvalidBatchCommand(i, e.into())
+                });
+        match verified {
+            Ok(nonce) => nonces.push(nonce),
+            Err(e) => {
+                release_envelope_nonces(&ctx, nonces);
+                let response = WithdrawTxFailureResponse::new(
+                    "Batch transaction request failed",
+                    &e,
+                );
+                return Ok(Json(BatchWithdrawTxResponse::Failure(response)));
+            }
+        }
+    }
+    let cmds = payload
+        .into_iter()
+        .map(|item| (item.contract, item.command))
+        .collect();
+    let response = handle_vanchor_relay_tx_batch(
+        ctx.clone(),
+        TypedChainId::Evm(chain_id),
+        cmds,
+    )
+    .await;
+
+    match response {
+        Ok((batch_id, item_keys)) => {
+            let response = BatchWithdrawTxSuccessResponse {
+                status: "Sent".to_string(),
+                message: "Transactions sent successfully".to_string(),
+                batch_id,
+                item_keys,
+            };
+            Ok(Json(BatchWithdrawTxResponse::Success(response)))
+        }
+        Err(e) => {
+            release_envelope_nonces(&ctx, nonces);
+            let response = WithdrawTxFailureResponse::new(
+                "Batch transaction request failed",
+                &e,
+            );
+            Ok(Json(BatchWithdrawTxResponse::Failure(response)))
+        }
+    }
+}
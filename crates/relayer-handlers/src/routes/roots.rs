@@ -0,0 +1,84 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use ethereum_types::Address;
+use serde::Serialize;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_config::evm::Contract;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::{
+    MerkleRootRecord, MerkleRootStore, MERKLE_ROOT_HISTORY_SIZE,
+};
+use webb_relayer_utils::HandlerError;
+
+/// Merkle roots history response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootsHistoryResponse {
+    /// The recent roots known by the anchor, of its own tree and of its
+    /// neighbors' trees, oldest first for each tree.
+    roots: Vec<MerkleRootRecord>,
+    /// The number of recent roots kept for each tree.
+    history_size: usize,
+}
+
+/// Handles merkle roots history requests for evm
+///
+/// Proofs built against a slightly stale root are still valid on chain, so
+/// this returns the recent roots the relayer accepts for the given anchor,
+/// with the block each was observed at. An empty history means the relayer
+/// has not observed any root yet and does not check them.
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the chain to query
+/// * `contract` - An address of the anchor to query
+pub async fn handle_roots_history_evm(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path((chain_id, contract)): Path<(u32, Address)>,
+) -> Result<Json<RootsHistoryResponse>, HandlerError> {
+    let chain = ctx.config.evm.get(&chain_id.to_string()).ok_or_else(|| {
+        tracing::warn!("Unsupported Chain: {chain_id}");
+        HandlerError(
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported Chain: {chain_id}"),
+        )
+    })?;
+    let is_anchor = chain.contracts.iter().any(
+        |c| matches!(c, Contract::VAnchor(c) if c.common.address == contract),
+    );
+    if !is_anchor {
+        tracing::warn!(
+            "Unsupported Contract: {contract} for chain : {chain_id}"
+        );
+        return Err(HandlerError(
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported Contract: {contract} for chain : {chain_id}"),
+        ));
+    }
+    let target_system =
+        TargetSystem::new_contract_address(contract.to_fixed_bytes());
+    let resource_id =
+        ResourceId::new(target_system, TypedChainId::Evm(chain_id));
+    let roots = ctx.store().get_merkle_root_history(resource_id)?;
+    Ok(Json(RootsHistoryResponse {
+        roots,
+        history_size: MERKLE_ROOT_HISTORY_SIZE,
+    }))
+}
@@ -0,0 +1,91 @@
+use super::*;
+use axum::extract::{Path, State};
+use std::sync::Arc;
+
+use axum::Json;
+use ethereum_types::Address;
+use webb::evm::ethers::types::Bytes;
+use webb_proposals::TypedChainId;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_handler_utils::EvmVanchorCommand;
+use webb_relayer_tx_relay::evm::vanchor::simulate_vanchor_relay_tx;
+use webb_relayer_utils::HandlerError;
+
+/// A prospective VAnchor command to simulate.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulateTxRequest {
+    /// The address of the contract the transaction would be sent to.
+    contract: Address,
+    /// The command to simulate.
+    command: EvmVanchorCommand,
+}
+
+/// Outcome of a VAnchor command, executed against the latest state of the
+/// chain.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulateTxResponse {
+    /// Whether the transaction would succeed.
+    success: bool,
+    /// Why the transaction would revert, if it would.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    revert_reason: Option<String>,
+    /// The raw data of the revert, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    revert_data: Option<Bytes>,
+    /// Why the relayer would reject the command before sending it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rejection: Option<WithdrawTxFailureResponse>,
+}
+
+/// Handles the simulation of a VAnchor command on an evm chain.
+///
+/// The `transact` call of the command is executed with `eth_call` from the
+/// relayer account, and the revert reason is decoded if it reverts. The
+/// command is never enqueued.
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the chain.
+/// * `payload` - The contract and the command to simulate.
+pub async fn handle_simulate_evm(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path(chain_id): Path<u32>,
+    Json(payload): Json<SimulateTxRequest>,
+) -> Result<Json<SimulateTxResponse>, HandlerError> {
+    // check if the simulation is enabled for relayer
+    if !ctx.config.features.simulation {
+        tracing::warn!("Simulation is not enabled for relayer.");
+        return Err(HandlerError(
+            StatusCode::FORBIDDEN,
+            "Simulation is not enabled for relayer.".to_string(),
+        ));
+    }
+    tracing::debug!(%chain_id, contract = %payload.contract, "Received simulate request");
+    let simulation = simulate_vanchor_relay_tx(
+        &ctx,
+        TypedChainId::Evm(chain_id),
+        payload.contract,
+        payload.command,
+    )
+    .await;
+    let response = match simulation {
+        Ok(simulation) => SimulateTxResponse {
+            success: !simulation.reverted,
+            revert_reason: simulation.revert_reason,
+            revert_data: simulation.revert_data,
+            rejection: None,
+        },
+        Err(e) => SimulateTxResponse {
+            success: false,
+            revert_reason: None,
+            revert_data: None,
+            rejection: Some(WithdrawTxFailureResponse::new(
+                "Transaction request would be rejected",
+                &e,
+            )),
+        },
+    };
+    Ok(Json(response))
+}
@@ -0,0 +1,103 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use ethereum_types::{Address, U256};
+use serde::Serialize;
+use webb::evm::ethers::middleware::gas_oracle::GasOracle;
+use webb::evm::ethers::providers::Middleware;
+use webb::evm::ethers::signers::Signer;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::queue::QueueKey;
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_utils::HandlerError;
+
+/// Relayer status response on an EVM chain
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmStatusResponse {
+    /// The chain this status is for.
+    chain_id: u32,
+    /// The address of the relayer wallet on this chain.
+    address: Address,
+    /// The nonce of the relayer wallet at the latest block.
+    nonce: U256,
+    /// The number of pending, processing or waiting items in the tx queue.
+    queued: usize,
+    /// The gas price the relayer currently uses for its fees, in wei.
+    gas_price: U256,
+    /// The native token balance of the relayer wallet, in wei.
+    balance: U256,
+}
+
+/// Handles relayer status requests for evm
+///
+/// Reports the on-chain nonce and balance of the relayer wallet, the
+/// number of locally queued transactions and the gas price in use, to
+/// quickly tell whether the relayer is healthy on the given chain.
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the chain to query
+pub async fn handle_evm_status(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path(chain_id): Path<u32>,
+) -> Result<Json<EvmStatusResponse>, HandlerError> {
+    if !ctx.config.evm.contains_key(&chain_id.to_string()) {
+        tracing::warn!("Unsupported Chain: {chain_id}");
+        return Err(HandlerError(
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported Chain: {chain_id}"),
+        ));
+    }
+    let wallet = ctx.evm_wallet(chain_id).await?;
+    let provider = ctx.evm_provider(chain_id).await?;
+    let address = wallet.address();
+
+    let queue_name = SledQueueKey::from_evm_chain_id(chain_id).queue_name();
+    let queued = ctx
+        .store()
+        .queue_items(&queue_name)?
+        .iter()
+        .filter(|item| item.state().is_pending())
+        .count();
+
+    let nonce = provider
+        .get_transaction_count(address, None)
+        .await
+        .map_err(webb_relayer_utils::Error::from)?;
+    let balance = provider
+        .get_balance(address, None)
+        .await
+        .map_err(webb_relayer_utils::Error::from)?;
+    let gas_price = ctx
+        .gas_oracle(chain_id)
+        .await?
+        .fetch()
+        .await
+        .map_err(webb_relayer_utils::Error::from)?;
+
+    Ok(Json(EvmStatusResponse {
+        chain_id,
+        address,
+        nonce,
+        queued,
+        gas_price,
+        balance,
+    }))
+}
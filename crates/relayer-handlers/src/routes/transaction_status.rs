@@ -1,15 +1,18 @@
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 
 use axum::http::StatusCode;
 use axum::Json;
-use ethereum_types::H512;
-use serde::Serialize;
+use ethereum_types::{Address, H256, H512};
+use serde::{Deserialize, Serialize};
 use webb::evm::ethers::types::transaction::eip2718::TypedTransaction;
 use webb_relayer_context::RelayerContext;
 use webb_relayer_store::queue::{QueueItem, QueueStore};
 use webb_relayer_store::{queue::QueueItemState, sled::SledQueueKey};
+use webb_relayer_store::{
+    RelayedTxRecord, RelayedTxStore, TxBatchStore, TxHashStore,
+};
 use webb_relayer_utils::HandlerError;
 
 /// Transaction status response struct
@@ -18,6 +21,18 @@ use webb_relayer_utils::HandlerError;
 pub struct TransactionStatusResponse {
     status: QueueItemState,
     item_key: String,
+    /// The hash of the mined transaction once processed, otherwise of the
+    /// last one broadcast, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tx_hash: Option<H256>,
+    /// The hashes of all the transactions broadcast for the item, the
+    /// oldest first, as gas bumps and retries change the hash.
+    tx_hashes: Vec<H256>,
+    /// While the item is pending and the chain is under maintenance, the end
+    /// of the maintenance window, in seconds since the unix epoch, until
+    /// which the item is held.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delayed_until: Option<u64>,
 }
 
 /// Handles transaction progress of item in queue for evm chains.
@@ -38,9 +53,23 @@ pub async fn handle_transaction_status_evm(
         .unwrap_or(None);
 
     if let Some(item) = maybe_item {
+        let tx_hashes = store.get_tx_hashes(chain_id, item_key.0)?;
+        let status = item.state();
+        let tx_hash = match &status {
+            QueueItemState::Processed { tx_hash, .. } => Some(*tx_hash),
+            _ => tx_hashes.last().copied(),
+        };
+        let delayed_until = if status.is_pending() {
+            ctx.maintenance_until(chain_id)
+        } else {
+            None
+        };
         return Ok(Json(TransactionStatusResponse {
-            status: item.state(),
+            status,
             item_key: item_key.to_string(),
+            tx_hash,
+            tx_hashes,
+            delayed_until,
         }));
     }
     Err(HandlerError(
@@ -48,3 +77,118 @@ pub async fn handle_transaction_status_evm(
         format!("Transaction item for key : {} not found in queue", item_key),
     ))
 }
+
+/// Status of a single transaction in a batch
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemStatus {
+    /// The state of the item in the queue, `None` if it is no longer queued.
+    status: Option<QueueItemState>,
+    item_key: String,
+}
+
+/// Transaction batch status response struct
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionBatchStatusResponse {
+    batch_id: H256,
+    items: Vec<BatchItemStatus>,
+}
+
+/// Handles transaction progress of a batch of items in queue for evm chains.
+///
+/// Returns a Result with the `TransactionBatchStatusResponse` on success
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the chain.
+/// * `batch_id` - The 32 bytes id returned when the batch was submitted.
+pub async fn handle_transaction_batch_status_evm(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path((chain_id, batch_id)): Path<(u32, H256)>,
+) -> Result<Json<TransactionBatchStatusResponse>, HandlerError> {
+    let store = ctx.store();
+    let item_keys =
+        store.get_tx_batch(chain_id, batch_id)?.ok_or_else(|| {
+            HandlerError(
+                StatusCode::NOT_FOUND,
+                format!("Transaction batch {batch_id:?} not found"),
+            )
+        })?;
+    let items = item_keys
+        .into_iter()
+        .map(|item_key| {
+            let maybe_item: Option<QueueItem<TypedTransaction>> = store
+                .get_item(SledQueueKey::from_evm_with_custom_key(
+                    chain_id, item_key.0,
+                ))
+                .unwrap_or(None);
+            BatchItemStatus {
+                status: maybe_item.map(|item| item.state()),
+                item_key: format!("{item_key:?}"),
+            }
+        })
+        .collect();
+    Ok(Json(TransactionBatchStatusResponse { batch_id, items }))
+}
+
+/// Query parameters for looking up relayed transactions.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayedTxsQuery {
+    /// The recipient of the withdrawals.
+    recipient: Address,
+    /// An optional token to filter the withdrawals by.
+    token: Option<Address>,
+}
+
+/// A relayed transaction along with its current state in the queue.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayedTxResponse {
+    #[serde(flatten)]
+    record: RelayedTxRecord,
+    /// The state of the item in the queue, `None` if it is no longer queued.
+    status: Option<QueueItemState>,
+}
+
+/// Handles looking up the withdrawals relayed to a recipient.
+///
+/// Returns a Result with the list of `RelayedTxResponse` on success
+///
+/// # Arguments
+///
+/// * `recipient` - The recipient address of the withdrawals.
+/// * `token` - An optional token address to filter the withdrawals by.
+pub async fn handle_relayed_txs_by_recipient(
+    State(ctx): State<Arc<RelayerContext>>,
+    Query(query): Query<RelayedTxsQuery>,
+) -> Result<Json<Vec<RelayedTxResponse>>, HandlerError> {
+    // check if data query is enabled for relayer
+    if !ctx.config.features.data_query {
+        tracing::warn!("Data query is not enabled for relayer.");
+        return Err(HandlerError(
+            StatusCode::FORBIDDEN,
+            "Data query is not enabled for relayer.".to_string(),
+        ));
+    }
+    let store = ctx.store();
+    let records =
+        store.get_relayed_txs_by_recipient(query.recipient, query.token)?;
+    let txs = records
+        .into_iter()
+        .map(|record| {
+            let maybe_item: Option<QueueItem<TypedTransaction>> = store
+                .get_item(SledQueueKey::from_evm_with_custom_key(
+                    record.chain_id,
+                    record.item_key.0,
+                ))
+                .unwrap_or(None);
+            RelayedTxResponse {
+                status: maybe_item.map(|item| item.state()),
+                record,
+            }
+        })
+        .collect();
+    Ok(Json(txs))
+}
@@ -0,0 +1,68 @@
+use super::*;
+use axum::extract::{Path, State};
+use std::sync::Arc;
+
+use axum::Json;
+use ethereum_types::Address;
+use webb_proposals::TypedChainId;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_handler_utils::EvmVanchorCommand;
+use webb_relayer_tx_relay::evm::wrap_and_deposit::handle_wrap_and_deposit_relay_tx;
+use webb_relayer_utils::HandlerError;
+
+use super::envelope::{release_envelope_nonces, verify_envelope};
+
+/// Handles wrap-and-deposit relaying request for evm chains.
+///
+/// Returns a Result with the `WithdrawTxResponse`, whose item key is the one
+/// of the `transact` transaction depositing the tokens.
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the chain.
+/// * `contract` - An address of the contract to submit transaction.
+/// * `payload` - An EvmVanchorCommand struct containing the command to execute.
+pub async fn handle_wrap_and_deposit_evm(
+    State(ctx): State<Arc<RelayerContext>>,
+    Path((chain_id, contract)): Path<(u32, Address)>,
+    Json(payload): Json<EvmVanchorCommand>,
+) -> Result<Json<WithdrawTxResponse>, HandlerError> {
+    tracing::debug!(%chain_id, %contract, ?payload, "Received wrap and deposit request");
+    let nonce = match verify_envelope(&ctx, chain_id, contract, &payload) {
+        Ok(nonce) => nonce,
+        Err(e) => {
+            let response = WithdrawTxFailureResponse::new(
+                "Transaction request failed",
+                &e,
+            );
+            return Ok(Json(WithdrawTxResponse::Failure(response)));
+        }
+    };
+    let response = handle_wrap_and_deposit_relay_tx(
+        ctx.clone(),
+        TypedChainId::Evm(chain_id),
+        contract,
+        payload,
+    )
+    .await;
+
+    match response {
+        Ok(tx_item_key) => {
+            let response = WithdrawTxSuccessResponse {
+                status: "Sent".to_string(),
+                message: "Transactions sent successfully".to_string(),
+                item_key: tx_item_key,
+                job_id: None,
+            };
+            Ok(Json(WithdrawTxResponse::Success(response)))
+        }
+        Err(e) => {
+            release_envelope_nonces(&ctx, [nonce]);
+            let response = WithdrawTxFailureResponse::new(
+                "Transaction request failed",
+                &e,
+            );
+            Ok(Json(WithdrawTxResponse::Failure(response)))
+        }
+    }
+}
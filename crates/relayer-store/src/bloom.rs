@@ -0,0 +1,130 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// A fixed-size counting Bloom filter over 32-byte hashes.
+///
+/// It is used as a front cache for the event hash store, so that lookups for
+/// events that were never seen do not need to hit the database. A negative
+/// answer is always exact, a positive one may be a false positive and must be
+/// confirmed against the backing store.
+///
+/// Each slot counts the entries hashed to it, so removed entries can be
+/// forgotten without rebuilding the filter. A slot that saturates is never
+/// decremented again, which keeps negative answers exact.
+#[derive(Clone, Debug)]
+pub struct CountingBloomFilter {
+    counters: Vec<u8>,
+    num_slots: u64,
+    num_hashes: u32,
+}
+
+impl CountingBloomFilter {
+    /// Creates a new filter sized for `expected_items` entries with the given
+    /// target `false_positive_rate`.
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let n = expected_items.max(1) as f64;
+        let p = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+        let num_slots = (-(n * p.ln()) / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let num_hashes = ((num_slots as f64 / n) * ln2).round().max(1.0) as u32;
+        Self {
+            counters: vec![0; num_slots as usize],
+            num_slots,
+            num_hashes,
+        }
+    }
+
+    /// Adds the hash to the filter.
+    ///
+    /// A hash must be inserted once per entry stored, and removed once per
+    /// entry deleted.
+    pub fn insert(&mut self, hash: &[u8; 32]) {
+        for i in self.indices(hash) {
+            let counter = &mut self.counters[i as usize];
+            *counter = counter.saturating_add(1);
+        }
+    }
+
+    /// Removes a hash that was inserted before from the filter.
+    pub fn remove(&mut self, hash: &[u8; 32]) {
+        for i in self.indices(hash) {
+            let counter = &mut self.counters[i as usize];
+            if *counter != 0 && *counter != u8::MAX {
+                *counter -= 1;
+            }
+        }
+    }
+
+    /// Returns `false` if the hash was definitely never inserted.
+    pub fn contains(&self, hash: &[u8; 32]) -> bool {
+        self.indices(hash).all(|i| self.counters[i as usize] != 0)
+    }
+
+    /// Removes all the entries from the filter.
+    pub fn clear(&mut self) {
+        self.counters.iter_mut().for_each(|c| *c = 0);
+    }
+
+    /// Derives the slot indices using double hashing, the input is already
+    /// a uniformly distributed hash so we just split it.
+    fn indices(&self, hash: &[u8; 32]) -> impl Iterator<Item = u64> {
+        let mut h1 = [0u8; 8];
+        let mut h2 = [0u8; 8];
+        h1.copy_from_slice(&hash[0..8]);
+        h2.copy_from_slice(&hash[8..16]);
+        let h1 = u64::from_le_bytes(h1);
+        let h2 = u64::from_le_bytes(h2) | 1;
+        let num_slots = self.num_slots;
+        (0..u64::from(self.num_hashes))
+            .map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_slots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use webb::evm::ethers::utils::keccak256;
+
+    #[test]
+    fn counting_bloom_filter_should_work() {
+        let mut filter = CountingBloomFilter::new(1000, 0.01);
+        let inserted = (0u32..1000)
+            .map(|i| keccak256(i.to_le_bytes()))
+            .collect::<Vec<_>>();
+        for hash in &inserted {
+            filter.insert(hash);
+        }
+        // no false negatives.
+        assert!(inserted.iter().all(|hash| filter.contains(hash)));
+        // false positives stay around the configured rate.
+        let false_positives = (1000u32..11000)
+            .map(|i| keccak256(i.to_le_bytes()))
+            .filter(|hash| filter.contains(hash))
+            .count();
+        assert!(false_positives < 300, "{false_positives} false positives");
+
+        // removed entries are forgotten, the others are kept.
+        let (removed, kept) = inserted.split_at(500);
+        for hash in removed {
+            filter.remove(hash);
+        }
+        assert!(kept.iter().all(|hash| filter.contains(hash)));
+        let false_positives =
+            removed.iter().filter(|hash| filter.contains(hash)).count();
+        assert!(false_positives < 30, "{false_positives} false positives");
+
+        filter.clear();
+        assert!(!inserted.iter().any(|hash| filter.contains(hash)));
+    }
+}
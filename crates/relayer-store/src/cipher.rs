@@ -0,0 +1,115 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encryption at rest of the values written to the store.
+//!
+//! Values are sealed with XChaCha20-Poly1305 under a random nonce, and
+//! tagged with a magic prefix, so the values written before the encryption
+//! was enabled can still be read.
+
+use std::borrow::Cow;
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+/// Prefix of the sealed values, which no plain (JSON or raw hash) value
+/// starts with.
+const MAGIC: &[u8; 4] = b"\0enc";
+/// The size of the XChaCha20-Poly1305 nonce.
+const NONCE_SIZE: usize = 24;
+
+/// Seals and opens the values written to the store.
+#[derive(Clone)]
+pub struct StoreCipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl std::fmt::Debug for StoreCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreCipher").finish_non_exhaustive()
+    }
+}
+
+impl StoreCipher {
+    /// Creates a new cipher with the given 256-bit key.
+    pub fn new(key: [u8; 32]) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(&key.into()),
+        }
+    }
+
+    /// Encrypts the given value.
+    pub fn seal(&self, value: &[u8]) -> Vec<u8> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, value)
+            .expect("encrypting in memory never fails");
+        let mut sealed =
+            Vec::with_capacity(MAGIC.len() + NONCE_SIZE + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    /// Decrypts the given value, plain values are returned as is.
+    pub fn open<'a>(&self, value: &'a [u8]) -> crate::Result<Cow<'a, [u8]>> {
+        let Some(sealed) = value.strip_prefix(MAGIC) else {
+            return Ok(Cow::Borrowed(value));
+        };
+        if sealed.len() < NONCE_SIZE {
+            return Err(webb_relayer_utils::Error::Generic(
+                "the encrypted store value is truncated",
+            ));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_SIZE);
+        let plaintext = self
+            .cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                webb_relayer_utils::Error::Generic(
+                    "failed to decrypt a store value, is the key right?",
+                )
+            })?;
+        Ok(Cow::Owned(plaintext))
+    }
+
+    /// Returns `true` if the given value was sealed by a cipher.
+    pub fn is_sealed(value: &[u8]) -> bool {
+        value.starts_with(MAGIC)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_and_open_should_work() {
+        let cipher = StoreCipher::new([7u8; 32]);
+        let value = br#"{"inner":"0x42"}"#;
+        let sealed = cipher.seal(value);
+        assert!(StoreCipher::is_sealed(&sealed));
+        assert_ne!(&sealed[MAGIC.len()..], &value[..]);
+        assert_eq!(cipher.open(&sealed).unwrap().as_ref(), value);
+        // the same value is sealed under a different nonce every time.
+        assert_ne!(cipher.seal(value), sealed);
+        // plain values written before the encryption are read as is.
+        assert_eq!(cipher.open(value).unwrap().as_ref(), value);
+        // a wrong key can not open the value.
+        let other = StoreCipher::new([8u8; 32]);
+        assert!(other.open(&sealed).is_err());
+    }
+}
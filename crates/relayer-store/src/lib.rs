@@ -27,8 +27,14 @@ use std::fmt::{Debug, Display};
 use webb::evm::ethers::types;
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
 use webb_relayer_utils::Result;
+/// A counting Bloom filter used as a front cache for event hashes.
+pub mod bloom;
+/// Encryption at rest of the store values.
+pub mod cipher;
 /// A module for managing in-memory storage of the relayer.
 pub mod mem;
+/// A read-through LRU cache used in front of the hot read paths.
+pub mod read_cache;
 /// A module for setting up and managing a [Sled](https://sled.rs)-based database.
 #[cfg(feature = "sled")]
 pub mod sled;
@@ -40,15 +46,11 @@ pub use self::sled::SledStore;
 /// A store that uses in memory data structures as the backend.
 pub use mem::InMemoryStore;
 
-/// HistoryStoreKey contains the keys used to store the history of events.
+/// HistoryStoreKey is the key used to store the history of events of a
+/// resource (a contract or a pallet) on a chain.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
-pub enum HistoryStoreKey {
-    /// Block Queue Key
-    Block { chain_id: u32 },
-    /// ResourceId Key
-    ResourceId {
-        resource_id: webb_proposals::ResourceId,
-    },
+pub struct HistoryStoreKey {
+    resource_id: webb_proposals::ResourceId,
 }
 
 /// A Bridge Key is a unique key used for Sending and Receiving Commands to the Signature Bridge
@@ -102,57 +104,30 @@ impl BridgeKey {
 }
 
 impl HistoryStoreKey {
-    /// Returns the chain id of the chain this key is for.
-
-    pub fn chain_id(&self) -> u32 {
-        match self {
-            HistoryStoreKey::Block { chain_id } => *chain_id,
-            HistoryStoreKey::ResourceId { resource_id } => {
-                resource_id.typed_chain_id().underlying_chain_id()
-            }
-        }
+    /// Returns the resource id this key is for.
+    pub fn resource_id(&self) -> ResourceId {
+        self.resource_id
     }
-    /// Returns the address of the chain this key is for.
-    pub fn address(&self) -> types::H160 {
-        match self {
-            HistoryStoreKey::Block { .. } => types::H160::zero(),
-            HistoryStoreKey::ResourceId { resource_id } => {
-                // take 20 bytes of target system bytes
-                types::H160::from_slice(
-                    &resource_id.target_system().to_bytes()[6..],
-                )
-            }
-        }
+
+    /// Returns the typed chain id of the chain this key is for.
+    pub fn typed_chain_id(&self) -> TypedChainId {
+        self.resource_id.typed_chain_id()
     }
 
-    /// Returns the bytes of the key.
+    /// Returns the bytes of the key, which are the bytes of the resource id.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut vec = vec![];
-        match self {
-            Self::Block { chain_id } => {
-                vec.extend_from_slice(&chain_id.to_be_bytes());
-            }
-            Self::ResourceId { resource_id } => {
-                let bytes = resource_id.to_bytes();
-                vec.extend_from_slice(&bytes)
-            }
-        }
-        vec
+        self.resource_id.to_bytes().to_vec()
     }
 }
 
 impl Display for HistoryStoreKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Block { chain_id } => write!(f, "Block(ChainId {chain_id})"),
-            Self::ResourceId { resource_id } => {
-                write!(
-                    f,
-                    "ResourceId(ChainId {})",
-                    resource_id.typed_chain_id().underlying_chain_id()
-                )
-            }
-        }
+        write!(
+            f,
+            "ResourceId({:?}, 0x{})",
+            self.typed_chain_id(),
+            hex::encode(self.to_bytes())
+        )
     }
 }
 
@@ -162,15 +137,9 @@ impl Display for BridgeKey {
     }
 }
 
-impl From<u32> for HistoryStoreKey {
-    fn from(chain_id: u32) -> Self {
-        Self::Block { chain_id }
-    }
-}
-
 impl From<ResourceId> for HistoryStoreKey {
     fn from(resource_id: ResourceId) -> Self {
-        Self::ResourceId { resource_id }
+        Self { resource_id }
     }
 }
 
@@ -179,7 +148,7 @@ impl From<(TargetSystem, TypedChainId)> for HistoryStoreKey {
         (target_system, typed_chain_id): (TargetSystem, TypedChainId),
     ) -> Self {
         let resource_id = ResourceId::new(target_system, typed_chain_id);
-        Self::ResourceId { resource_id }
+        Self { resource_id }
     }
 }
 
@@ -188,7 +157,7 @@ impl From<(TypedChainId, TargetSystem)> for HistoryStoreKey {
         (typed_chain_id, target_system): (TypedChainId, TargetSystem),
     ) -> Self {
         let resource_id = ResourceId::new(target_system, typed_chain_id);
-        Self::ResourceId { resource_id }
+        Self { resource_id }
     }
 }
 
@@ -240,16 +209,73 @@ pub trait HistoryStore: Clone + Send + Sync {
     ) -> crate::Result<u64> {
         self.get_target_block_number(key, 1u64)
     }
+
+    /// Sets the block number that contract got deployed at, once it is
+    /// detected on chain.
+    fn set_deployment_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block_number: u64,
+    ) -> crate::Result<()>;
+    /// Get the block number that contract got deployed at.
+    /// if it was never detected, returns `None`.
+    fn get_deployment_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Option<u64>>;
+
+    /// Sets how many blocks the events of that contract are queried for at
+    /// once, as learned from the node.
+    fn set_blocks_per_step<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        blocks_per_step: u64,
+    ) -> crate::Result<()>;
+    /// Get how many blocks the events of that contract are queried for at
+    /// once. if it was never learned, returns `None`.
+    fn get_blocks_per_step<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Option<u64>>;
+
+    /// Sets the position, as its block number and log index, of the last
+    /// event the given handler of that contract went through.
+    fn set_handler_checkpoint<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        handler: &str,
+        position: (u64, u64),
+    ) -> crate::Result<()>;
+    /// Get the position of the last event the given handler of that
+    /// contract went through. if it never saved one, returns `None`.
+    fn get_handler_checkpoint<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        handler: &str,
+    ) -> crate::Result<Option<(u64, u64)>>;
+    /// Forget the positions of all the handlers of that contract, so they go
+    /// through its events again.
+    fn clear_handler_checkpoints<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<()>;
 }
 
 /// A Simple Event Store, that does not store the events, instead it store the hash of the event as the key
-/// and the value is just empty bytes.
+/// and the block number it was seen at as the value.
 ///
-/// This is mainly useful to mark the event as processed.
+/// This is mainly useful to mark the event as processed. Events are indexed by
+/// the [`HistoryStoreKey`] and block number, so old entries can be pruned once
+/// they fall out of the deduplication window.
 pub trait EventHashStore: Send + Sync + Clone {
     /// Store the event in the store.
     /// the key is the hash of the event.
-    fn store_event(&self, event: &[u8]) -> crate::Result<()>;
+    fn store_event<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        event: &[u8],
+        block_number: u64,
+    ) -> crate::Result<()>;
 
     /// Check if the event is stored in the store.
     /// the key is the hash of the event.
@@ -258,6 +284,18 @@ pub trait EventHashStore: Send + Sync + Clone {
     /// Delete the event from the store.
     /// the key is the hash of the event.
     fn delete_event(&self, event: &[u8]) -> crate::Result<()>;
+
+    /// Delete all the events stored for the given key before `block_number`,
+    /// returns how many events were pruned.
+    fn prune_events<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block_number: u64,
+    ) -> crate::Result<usize>;
+
+    /// Delete all the events, of any key, stored before the given time
+    /// (unix timestamp in seconds), returns how many events were purged.
+    fn purge_events_before(&self, stored_before: u64) -> crate::Result<usize>;
 }
 
 /// A Leaf Cache Store is a simple trait that would help in
@@ -301,6 +339,73 @@ pub trait LeafCacheStore: HistoryStore {
         leaves: &[(u32, Vec<u8>)],
         block_number: u64,
     ) -> crate::Result<()>;
+
+    /// Insert a batch of leaves and its last deposit block number for the
+    /// given key, all at once.
+    ///
+    /// Does nothing if the batch is empty.
+    fn insert_leaves_batch<K: Into<HistoryStoreKey> + Debug + Clone>(
+        &self,
+        key: K,
+        batch: &LeavesBatch,
+    ) -> crate::Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.insert_leaves_and_last_deposit_block_number(
+            key,
+            batch.leaves(),
+            batch.last_deposit_block_number(),
+        )
+    }
+}
+
+/// A batch of leaves buffered in memory, to be written to a
+/// [`LeafCacheStore`] at once with [`LeafCacheStore::insert_leaves_batch`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LeavesBatch {
+    leaves: Vec<(u32, Vec<u8>)>,
+    last_deposit_block_number: u64,
+}
+
+impl LeavesBatch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a leaf, deposited at the given block, to the batch.
+    pub fn push(&mut self, leaf_index: u32, leaf: Vec<u8>, block_number: u64) {
+        self.leaves.push((leaf_index, leaf));
+        self.last_deposit_block_number =
+            self.last_deposit_block_number.max(block_number);
+    }
+
+    /// The leaves of the batch, in the order they were added.
+    pub fn leaves(&self) -> &[(u32, Vec<u8>)] {
+        &self.leaves
+    }
+
+    /// The block number of the last deposit in the batch.
+    pub fn last_deposit_block_number(&self) -> u64 {
+        self.last_deposit_block_number
+    }
+
+    /// The number of leaves in the batch.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Whether the batch has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Removes all the leaves from the batch.
+    pub fn clear(&mut self) {
+        self.leaves.clear();
+        self.last_deposit_block_number = 0;
+    }
 }
 
 /// An Encrypted Output Cache Store is a simple trait that would help in
@@ -309,6 +414,12 @@ pub trait EncryptedOutputCacheStore: HistoryStore {
     /// The Output type which is the encrypted output.
     type Output: IntoIterator<Item = String>;
 
+    /// Clear encrypted output cache on relayer
+    fn clear_encrypted_output_cache<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<()>;
+
     /// Get all the encrypted output for the given key.
     fn get_encrypted_output<K: Into<HistoryStoreKey> + Debug>(
         &self,
@@ -406,6 +517,560 @@ pub enum BridgeCommand {
     },
 }
 
+/// A withdrawal relayed by this relayer, indexed by its recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayedTxRecord {
+    /// The chain id where the withdrawal was relayed.
+    pub chain_id: u32,
+    /// The VAnchor contract the withdrawal was sent to.
+    pub contract: types::Address,
+    /// The recipient of the withdrawal.
+    pub recipient: types::Address,
+    /// The token being withdrawn.
+    pub token: types::Address,
+    /// The key of the transaction item in the tx queue.
+    pub item_key: types::H512,
+    /// The fee paid to the relayer.
+    pub fee: types::U256,
+    /// The refund requested by the user.
+    pub refund: types::U256,
+    /// The time when the withdrawal was relayed (unix timestamp in seconds).
+    pub relayed_at: u64,
+}
+
+/// A store for the withdrawals relayed by this relayer, so they can be
+/// looked up by their recipient later.
+pub trait RelayedTxStore {
+    /// Index the relayed withdrawal by its recipient and token.
+    fn insert_relayed_tx(&self, record: &RelayedTxRecord) -> crate::Result<()>;
+
+    /// Get the withdrawals relayed to the given recipient, optionally
+    /// filtered by token, ordered by the time they were relayed.
+    fn get_relayed_txs_by_recipient(
+        &self,
+        recipient: types::Address,
+        token: Option<types::Address>,
+    ) -> crate::Result<Vec<RelayedTxRecord>>;
+
+    /// Get the total refund relayed to the given recipient on the given
+    /// chain since the given time (unix timestamp in seconds).
+    fn get_refunds_by_recipient_since(
+        &self,
+        chain_id: u32,
+        recipient: types::Address,
+        since: u64,
+    ) -> crate::Result<types::U256>;
+
+    /// Reserve the refund of the withdrawal with the given item key against
+    /// the refunds reserved to the recipient on the chain since the given
+    /// time (unix timestamp in seconds).
+    ///
+    /// Returns `false`, without reserving anything, if it would take them
+    /// over `max_refund`. A withdrawal reserved twice only counts once.
+    fn reserve_recipient_refund(
+        &self,
+        chain_id: u32,
+        recipient: types::Address,
+        item_key: types::H512,
+        refund: types::U256,
+        since: u64,
+        max_refund: types::U256,
+    ) -> crate::Result<bool>;
+
+    /// Release the refund reserved to the withdrawal with the given item
+    /// key, as it was not relayed after all.
+    fn release_recipient_refund(
+        &self,
+        chain_id: u32,
+        recipient: types::Address,
+        item_key: types::H512,
+    ) -> crate::Result<()>;
+
+    /// Delete the withdrawals relayed before the given time (unix timestamp
+    /// in seconds), returns how many records were purged.
+    fn purge_relayed_txs_before(&self, before: u64) -> crate::Result<usize>;
+}
+
+/// A store for the nullifiers spent on each anchor, so commands spending
+/// them again can be rejected before they revert on chain.
+pub trait NullifierStore {
+    /// Mark the nullifiers as spent on the given resource.
+    fn insert_nullifiers(
+        &self,
+        resource_id: ResourceId,
+        nullifiers: &[types::H256],
+    ) -> crate::Result<()>;
+
+    /// Get the given nullifiers that are already spent on the given
+    /// resource, in the same order.
+    fn get_spent_nullifiers(
+        &self,
+        resource_id: ResourceId,
+        nullifiers: &[types::H256],
+    ) -> crate::Result<Vec<types::H256>>;
+}
+
+/// A store for the nonces of the signed relay commands, so a captured
+/// command can't be relayed again while its envelope is still valid.
+pub trait RelayNonceStore {
+    /// Mark the nonce as used by the signer until `expires_at` (unix
+    /// timestamp in seconds), returns `false` if the signer already used it
+    /// and it is not expired yet at `now`.
+    fn use_relay_nonce(
+        &self,
+        signer: types::Address,
+        nonce: u64,
+        now: u64,
+        expires_at: u64,
+    ) -> crate::Result<bool>;
+
+    /// Whether the signer already used the nonce, and it is not expired yet
+    /// at `now`, without using it.
+    fn is_relay_nonce_used(
+        &self,
+        signer: types::Address,
+        nonce: u64,
+        now: u64,
+    ) -> crate::Result<bool>;
+
+    /// Release the nonce of the signer, as the command it was used for was
+    /// rejected after all.
+    fn release_relay_nonce(
+        &self,
+        signer: types::Address,
+        nonce: u64,
+    ) -> crate::Result<()>;
+
+    /// Delete the nonces expired before the given time (unix timestamp in
+    /// seconds), returns how many nonces were purged.
+    fn purge_relay_nonces_before(&self, before: u64) -> crate::Result<usize>;
+}
+
+/// A store for the transfers funding the wrap-and-deposit commands, so a
+/// transfer only ever funds a single deposit.
+pub trait FundingTransferStore {
+    /// Mark the transfer as used to fund a deposit on the given chain,
+    /// returns `false` if it was already used.
+    fn use_funding_transfer(
+        &self,
+        chain_id: u32,
+        tx_hash: types::H256,
+    ) -> crate::Result<bool>;
+
+    /// Release the transfer, as the deposit it was used for was not
+    /// enqueued after all.
+    fn release_funding_transfer(
+        &self,
+        chain_id: u32,
+        tx_hash: types::H256,
+    ) -> crate::Result<()>;
+}
+
+/// A store of the last request relayed through a trusted forwarder for each
+/// of its sponsors, so the requests of a sponsor are relayed one at a time:
+/// all of them would otherwise be signed with the same forwarder nonce.
+pub trait ForwardedRequestStore {
+    /// The item key of the last request relayed for the sponsor.
+    fn last_forwarded_request(
+        &self,
+        chain_id: u32,
+        forwarder: types::Address,
+        sponsor: types::Address,
+    ) -> crate::Result<Option<types::H512>>;
+
+    /// Record the request as the last one relayed for the sponsor, returns
+    /// `false` if the last one is not `previous` anymore.
+    fn replace_forwarded_request(
+        &self,
+        chain_id: u32,
+        forwarder: types::Address,
+        sponsor: types::Address,
+        previous: Option<types::H512>,
+        item_key: types::H512,
+    ) -> crate::Result<bool>;
+}
+
+/// The number of recent roots kept for each merkle tree known by an anchor,
+/// the same as the root history of the anchor contracts.
+pub const MERKLE_ROOT_HISTORY_SIZE: usize = 30;
+
+/// A merkle root known by an anchor, with the block it was observed at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleRootRecord {
+    /// The chain id of the tree the root belongs to, the one of the anchor
+    /// itself or of one of its neighbors.
+    pub source_chain_id: u64,
+    /// The merkle root.
+    pub root: types::H256,
+    /// The block of the anchor's chain where the root was observed.
+    pub block_number: u64,
+}
+
+/// A store for the recent merkle roots known by each anchor, of its own
+/// tree and of the trees of its neighbors (its edges), so commands proving
+/// against unknown roots can be rejected before they revert on chain.
+pub trait MerkleRootStore {
+    /// Remember a new root of the tree of the given chain, as known by the
+    /// given resource since the given block, keeping only the
+    /// [`MERKLE_ROOT_HISTORY_SIZE`] most recent ones.
+    fn insert_merkle_root(
+        &self,
+        resource_id: ResourceId,
+        source_chain_id: u64,
+        root: types::H256,
+        block_number: u64,
+    ) -> crate::Result<()>;
+
+    /// Get the recent roots known by the given resource, of its own tree and
+    /// of its neighbors' trees, oldest first for each tree.
+    fn get_merkle_root_history(
+        &self,
+        resource_id: ResourceId,
+    ) -> crate::Result<Vec<MerkleRootRecord>>;
+
+    /// Get the recent roots known by the given resource, of its own tree and
+    /// of its neighbors' trees.
+    fn get_known_merkle_roots(
+        &self,
+        resource_id: ResourceId,
+    ) -> crate::Result<Vec<types::H256>> {
+        let history = self.get_merkle_root_history(resource_id)?;
+        Ok(history.into_iter().map(|r| r.root).collect())
+    }
+}
+
+/// A store for groups of transactions that were enqueued together, so their
+/// progress can be tracked with a single batch id.
+pub trait TxBatchStore {
+    /// Store the item keys of the transactions in the batch.
+    fn insert_tx_batch(
+        &self,
+        chain_id: u32,
+        batch_id: types::H256,
+        item_keys: &[types::H512],
+    ) -> crate::Result<()>;
+
+    /// Enqueues the transactions of a batch, in order, and stores their item
+    /// keys, in a single transaction: either the whole batch is enqueued or
+    /// none of it.
+    ///
+    /// The items must all be keyed, and on the same queue. Returns their
+    /// item keys, or `None` if the batch would take a resource over
+    /// `max_pending` pending items in the queue, in which case none of it is
+    /// enqueued (see [`queue::QueueStore::enqueue_item_capped`]).
+    fn enqueue_tx_batch<K, T>(
+        &self,
+        chain_id: u32,
+        batch_id: types::H256,
+        items: Vec<(K, queue::QueueItem<T>)>,
+        max_pending: Option<usize>,
+    ) -> crate::Result<Option<Vec<types::H512>>>
+    where
+        K: queue::QueueKey,
+        T: Serialize + DeserializeOwned + Clone;
+
+    /// Get the item keys of the transactions in the batch, if any.
+    fn get_tx_batch(
+        &self,
+        chain_id: u32,
+        batch_id: types::H256,
+    ) -> crate::Result<Option<Vec<types::H512>>>;
+}
+
+/// A store for the hashes of the transactions broadcast for the items of the
+/// tx queues, since each gas bump or retry of an item changes its hash.
+pub trait TxHashStore {
+    /// Append the hash of a transaction broadcast for the item.
+    fn insert_tx_hash(
+        &self,
+        chain_id: u32,
+        item_key: [u8; 64],
+        tx_hash: types::H256,
+    ) -> crate::Result<()>;
+
+    /// Get the hashes of the transactions broadcast for the item, the
+    /// oldest first.
+    fn get_tx_hashes(
+        &self,
+        chain_id: u32,
+        item_key: [u8; 64],
+    ) -> crate::Result<Vec<types::H256>>;
+}
+
+/// The stage a relay job is at, until its transaction reaches the tx queue.
+///
+/// Once enqueued, the rest of the job lifecycle is tracked by the state of
+/// the item in the queue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "camelCase")]
+pub enum RelayJobStage {
+    /// The command passed validation and its transaction was built.
+    Validated,
+    /// Estimating the gas and checking the fee paid by the user.
+    CheckingFee,
+    /// The transaction was enqueued in the tx queue.
+    Enqueued,
+    /// The job failed before its transaction was enqueued.
+    Failed {
+        /// The error message.
+        reason: String,
+    },
+}
+
+/// A relay request that is processed in the background.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayJob {
+    /// The unique id of the job.
+    pub job_id: types::H256,
+    /// The chain id where the transaction is relayed.
+    pub chain_id: u32,
+    /// The key of the transaction item in the tx queue.
+    pub item_key: types::H512,
+    /// The current stage of the job.
+    pub stage: RelayJobStage,
+    /// The time when the job was created (unix timestamp in seconds).
+    pub created_at: u64,
+    /// The time when the job was last updated (unix timestamp in seconds).
+    pub updated_at: u64,
+}
+
+impl RelayJob {
+    /// Creates a new job, in the [`RelayJobStage::Validated`] stage.
+    pub fn new(
+        job_id: types::H256,
+        chain_id: u32,
+        item_key: types::H512,
+    ) -> Self {
+        let now = unix_timestamp();
+        Self {
+            job_id,
+            chain_id,
+            item_key,
+            stage: RelayJobStage::Validated,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Moves the job to the given stage.
+    pub fn set_stage(&mut self, stage: RelayJobStage) {
+        self.stage = stage;
+        self.updated_at = unix_timestamp();
+    }
+}
+
+fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+/// A store for relay jobs, so their progress can be queried later.
+pub trait RelayJobStore {
+    /// Insert the job, replacing any previous version of it.
+    fn insert_relay_job(&self, job: &RelayJob) -> crate::Result<()>;
+
+    /// Get the job with the given id, if any.
+    fn get_relay_job(
+        &self,
+        job_id: types::H256,
+    ) -> crate::Result<Option<RelayJob>>;
+
+    /// Delete the jobs last updated before the given time (unix timestamp
+    /// in seconds), returns how many jobs were purged.
+    fn purge_relay_jobs_before(&self, before: u64) -> crate::Result<usize>;
+}
+
+/// The phase of a threshold signing job on Tangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TangleJobPhase {
+    /// The key generation phase (phase one).
+    KeyGeneration,
+    /// The signing phase (phase two).
+    Signing,
+    /// Any other kind of job.
+    Other,
+}
+
+/// The stage of a job on Tangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TangleJobStage {
+    /// The job was submitted, its result is not known yet.
+    Submitted,
+    /// The result of the job was submitted.
+    ResultSubmitted,
+}
+
+/// A job of the jobs pallet on Tangle, as seen by the relayer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TangleJob {
+    /// The chain id of the Tangle node.
+    pub chain_id: u32,
+    /// The id of the job.
+    pub job_id: u64,
+    /// The role type of the job, e.g. `Tss(DfnsCGGMP21Secp256k1)`.
+    pub role_type: String,
+    /// The phase of the job, if it was seen when submitted.
+    pub phase: Option<TangleJobPhase>,
+    /// The current stage of the job.
+    pub stage: TangleJobStage,
+    /// The block the job was submitted in, if it was seen.
+    pub submitted_at: Option<u64>,
+    /// The block the result of the job was submitted in, if any.
+    pub result_submitted_at: Option<u64>,
+    /// The time when the job was last updated (unix timestamp in seconds),
+    /// `0` for the jobs stored before it was recorded.
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+impl TangleJob {
+    /// Records that the job was updated now.
+    pub fn touch(&mut self) {
+        self.updated_at = unix_timestamp();
+    }
+}
+
+/// A store for the jobs of the jobs pallet on Tangle, so their progress can
+/// be queried later.
+pub trait TangleJobStore {
+    /// Insert the job, replacing any previous version of it.
+    fn insert_tangle_job(&self, job: &TangleJob) -> crate::Result<()>;
+
+    /// Get the job with the given id on the given chain, if any.
+    fn get_tangle_job(
+        &self,
+        chain_id: u32,
+        job_id: u64,
+    ) -> crate::Result<Option<TangleJob>>;
+
+    /// Delete the jobs last updated before the given time (unix timestamp
+    /// in seconds), returns how many jobs were purged.
+    fn purge_tangle_jobs_before(&self, before: u64) -> crate::Result<usize>;
+}
+
+/// The costs of a transaction once it settled on chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TxCost {
+    /// The gas used by the transaction.
+    pub gas_used: types::U256,
+    /// The gas paid for the transaction (gas used × effective gas price).
+    pub gas_spent: types::U256,
+    /// The native value sent with the transaction (the refund).
+    pub value_sent: types::U256,
+}
+
+/// The profit-and-loss ledger of a resource (a contract on a chain).
+///
+/// Fees are in the units of the fee token of the resource, while gas and
+/// refunds are in the units of the native token of the chain.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfitAndLoss {
+    /// The fees collected by the succeeded transactions.
+    pub fees_earned: types::U256,
+    /// The total gas used by the transactions.
+    pub gas_used: types::U256,
+    /// The total gas paid for the transactions, succeeded or not.
+    pub gas_spent: types::U256,
+    /// The refunds paid to the recipients.
+    pub refunds_paid: types::U256,
+    /// The number of transactions that succeeded.
+    pub txs_succeeded: u64,
+    /// The number of transactions that reverted.
+    pub txs_failed: u64,
+    /// The number of subsidized transactions, relayed without checking
+    /// their fee.
+    #[serde(default)]
+    pub txs_subsidized: u64,
+    /// The gas paid for the subsidized transactions, succeeded or not.
+    #[serde(default)]
+    pub gas_subsidized: types::U256,
+}
+
+/// A store for the profit-and-loss accounting of the relayed transactions.
+pub trait AccountingStore {
+    /// Remember the fee that the transaction with the given item key
+    /// collects once it succeeds.
+    fn insert_expected_fee(
+        &self,
+        item_key: [u8; 64],
+        fee: types::U256,
+    ) -> crate::Result<()>;
+
+    /// Whether the transaction with the given item key still has an
+    /// expected fee to collect, i.e. it is a relayed transaction.
+    fn has_expected_fee(&self, item_key: [u8; 64]) -> crate::Result<bool>;
+
+    /// Reserve one of the subsidized transactions of the chain for the
+    /// current day to the transaction with the given item key, so its gas is
+    /// accounted for as a subsidy once it settles.
+    ///
+    /// Returns `false`, without reserving anything, once `max_daily_txs`
+    /// transactions are reserved for the day. A transaction reserved twice
+    /// only counts once.
+    fn reserve_subsidized_tx(
+        &self,
+        chain_id: TypedChainId,
+        item_key: [u8; 64],
+        max_daily_txs: Option<u64>,
+    ) -> crate::Result<bool>;
+
+    /// Release the subsidized transaction reserved to the transaction with
+    /// the given item key, as it was not relayed after all.
+    fn release_subsidized_tx(
+        &self,
+        chain_id: TypedChainId,
+        item_key: [u8; 64],
+    ) -> crate::Result<()>;
+
+    /// Get the number of transactions subsidized on the given chain during
+    /// the given day (see [`current_day`]).
+    fn get_daily_subsidized_txs(
+        &self,
+        chain_id: TypedChainId,
+        day: u64,
+    ) -> crate::Result<u64>;
+
+    /// Account for a transaction that settled on chain, collecting its
+    /// expected fee (if any) when it succeeded.
+    ///
+    /// The gas it spent also counts towards the daily spend of its chain,
+    /// and towards the subsidies if the transaction is subsidized.
+    fn settle_tx(
+        &self,
+        resource_id: ResourceId,
+        item_key: [u8; 64],
+        cost: TxCost,
+        succeeded: bool,
+    ) -> crate::Result<()>;
+
+    /// Get the profit-and-loss ledger of every resource.
+    fn get_profit_and_loss(
+        &self,
+    ) -> crate::Result<Vec<(ResourceId, ProfitAndLoss)>>;
+
+    /// Get the gas spent on the given chain during the given day
+    /// (see [`current_day`]).
+    fn get_daily_gas_spent(
+        &self,
+        chain_id: TypedChainId,
+        day: u64,
+    ) -> crate::Result<types::U256>;
+}
+
+/// The current UTC day, counted in days since the unix epoch.
+pub fn current_day() -> u64 {
+    unix_timestamp() / 86_400
+}
+
 /// A trait for Cached Token Price.
 pub trait TokenPriceCacheStore<CachedTokenPrice>
 where
@@ -36,6 +36,10 @@ pub struct InMemoryStore {
     encrypted_output_store: Arc<RwLock<MemStoreForVec>>,
     last_block_numbers: Arc<RwLock<HashMap<HistoryStoreKey, u64>>>,
     target_block_numbers: Arc<RwLock<HashMap<HistoryStoreKey, u64>>>,
+    deployment_block_numbers: Arc<RwLock<HashMap<HistoryStoreKey, u64>>>,
+    blocks_per_step: Arc<RwLock<HashMap<HistoryStoreKey, u64>>>,
+    handler_checkpoints:
+        Arc<RwLock<HashMap<(HistoryStoreKey, String), (u64, u64)>>>,
     last_deposit_block_numbers: Arc<RwLock<HashMap<HistoryStoreKey, u64>>>,
     encrypted_output_last_deposit_block_numbers:
         Arc<RwLock<HashMap<HistoryStoreKey, u64>>>,
@@ -109,6 +113,79 @@ impl HistoryStore for InMemoryStore {
             .unwrap_or(default_block_number);
         Ok(val)
     }
+
+    #[tracing::instrument(skip(self))]
+    fn set_deployment_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block_number: u64,
+    ) -> crate::Result<()> {
+        let mut guard = self.deployment_block_numbers.write();
+        guard.insert(key.into(), block_number);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_deployment_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Option<u64>> {
+        let guard = self.deployment_block_numbers.read();
+        Ok(guard.get(&key.into()).cloned())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn set_blocks_per_step<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        blocks_per_step: u64,
+    ) -> crate::Result<()> {
+        let mut guard = self.blocks_per_step.write();
+        guard.insert(key.into(), blocks_per_step);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_blocks_per_step<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Option<u64>> {
+        let guard = self.blocks_per_step.read();
+        Ok(guard.get(&key.into()).cloned())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn set_handler_checkpoint<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        handler: &str,
+        position: (u64, u64),
+    ) -> crate::Result<()> {
+        let mut guard = self.handler_checkpoints.write();
+        guard.insert((key.into(), handler.to_owned()), position);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_handler_checkpoint<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        handler: &str,
+    ) -> crate::Result<Option<(u64, u64)>> {
+        let guard = self.handler_checkpoints.read();
+        Ok(guard.get(&(key.into(), handler.to_owned())).cloned())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn clear_handler_checkpoints<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<()> {
+        let key = key.into();
+        let mut guard = self.handler_checkpoints.write();
+        guard.retain(|(k, _), _| *k != key);
+        Ok(())
+    }
 }
 
 impl LeafCacheStore for InMemoryStore {
@@ -202,6 +279,16 @@ impl LeafCacheStore for InMemoryStore {
 impl EncryptedOutputCacheStore for InMemoryStore {
     type Output = Vec<String>;
 
+    #[tracing::instrument(skip(self))]
+    fn clear_encrypted_output_cache<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<()> {
+        let mut guard = self.encrypted_output_store.write();
+        guard.remove(&key.into());
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     fn get_encrypted_output<K: Into<HistoryStoreKey> + Debug>(
         &self,
@@ -224,7 +311,7 @@ impl EncryptedOutputCacheStore for InMemoryStore {
         let iter = val
             .into_iter()
             .skip(range.start as usize)
-            .take((range.end - range.start) as usize)
+            .take(range.end.saturating_sub(range.start) as usize)
             .map(hex::encode);
         Ok(iter.collect())
     }
@@ -296,6 +383,12 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use webb_proposals::{TargetSystem, TypedChainId};
+
+    fn test_key() -> HistoryStoreKey {
+        let target_system = TargetSystem::new_contract_address([1u8; 20]);
+        HistoryStoreKey::from((TypedChainId::Evm(1), target_system))
+    }
 
     #[test]
     fn it_gets_all_leaves() {
@@ -304,7 +397,7 @@ mod tests {
         let generated_leaves = (0..20u32)
             .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
             .collect::<Vec<_>>();
-        let key = HistoryStoreKey::from(1u32);
+        let key = test_key();
         let block_number = 20u64;
         store
             .insert_leaves_and_last_deposit_block_number(
@@ -333,7 +426,7 @@ mod tests {
         let generated_leaves = (0..20u32)
             .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
             .collect::<Vec<_>>();
-        let key = HistoryStoreKey::from(1u32);
+        let key = test_key();
         let block_number = 20u64;
         store
             .insert_leaves_and_last_deposit_block_number(
@@ -364,7 +457,7 @@ mod tests {
         let generated_leaves = (0..20u32)
             .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
             .collect::<Vec<_>>();
-        let key = HistoryStoreKey::from(1u32);
+        let key = test_key();
         let block_number = 20u64;
         store
             .insert_leaves_and_last_deposit_block_number(
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use webb::evm::ethers::types::H256;
+use webb::evm::ethers::types::{H256, H512, U256};
 use webb::evm::ethers::{types::transaction::eip2718::TypedTransaction, utils};
 use webb_relayer_utils::static_tx_payload::TypeErasedStaticTxPayload;
 
@@ -28,6 +28,28 @@ pub struct QueueItem<T> {
     enqueued_at: u128,
     /// Time to live
     ttl: u128,
+    /// The priority class of the item.
+    #[serde(default)]
+    priority: QueueItemPriority,
+    /// The highest gas price the item may be sent with, if any.
+    #[serde(default)]
+    max_gas_price: Option<U256>,
+    /// The unix timestamp (in seconds) the item must be processed by, if any.
+    #[serde(default)]
+    deadline: Option<u64>,
+    /// The resource the item was enqueued for, if any.
+    ///
+    /// The queue takes turns between the resources of its items.
+    #[serde(default)]
+    resource_id: Option<H256>,
+    /// The item keys of the items that must be processed before this one is
+    /// sent, e.g. the transactions funding a deposit.
+    #[serde(default)]
+    depends_on: Vec<H512>,
+    /// Whether the item is still sent once the daily gas budget of its chain
+    /// is spent, like the governance transactions.
+    #[serde(default)]
+    budget_exempt: bool,
 }
 
 impl<T> QueueItem<T> {
@@ -42,6 +64,12 @@ impl<T> QueueItem<T> {
             state: Default::default(),
             enqueued_at: now.as_millis(),
             ttl: 3 * 60 * 60 * 1000, // 3 hours
+            priority: Default::default(),
+            max_gas_price: None,
+            deadline: None,
+            resource_id: None,
+            depends_on: Vec::new(),
+            budget_exempt: false,
         }
     }
     /// Returns the state of the QueueItem.
@@ -49,6 +77,20 @@ impl<T> QueueItem<T> {
         self.state.clone()
     }
 
+    /// Returns the time when the item was enqueued (unix timestamp in milliseconds).
+    pub fn enqueued_at(&self) -> u128 {
+        self.enqueued_at
+    }
+
+    /// Returns how long ago the item was enqueued.
+    pub fn elapsed_since_enqueued(&self) -> std::time::Duration {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards");
+        let elapsed = now.as_millis().saturating_sub(self.enqueued_at);
+        std::time::Duration::from_millis(elapsed as u64)
+    }
+
     /// Unwraps the QueueItem and returns the inner value.
     pub fn inner(self) -> T {
         self.inner
@@ -59,10 +101,111 @@ impl<T> QueueItem<T> {
         self.ttl = ttl;
     }
 
-    /// set item state.
+    /// set item state, without checking the transition.
     pub fn set_state(&mut self, state: QueueItemState) {
         self.state = state;
     }
+
+    /// Moves the item to the given state, if it may go there from its
+    /// current one, see [`QueueItemState::can_transition_to`].
+    pub fn transition_to(
+        &mut self,
+        state: QueueItemState,
+    ) -> crate::Result<()> {
+        if !self.state.can_transition_to(&state) {
+            return Err(
+                webb_relayer_utils::Error::InvalidQueueItemTransition {
+                    from: self.state.name(),
+                    to: state.name(),
+                },
+            );
+        }
+        self.state = state;
+        Ok(())
+    }
+
+    /// Returns the priority class of the item.
+    pub fn priority(&self) -> QueueItemPriority {
+        self.priority
+    }
+
+    /// set item priority class.
+    pub fn set_priority(&mut self, priority: QueueItemPriority) {
+        self.priority = priority;
+    }
+
+    /// Returns the highest gas price the item may be sent with, if any.
+    pub fn max_gas_price(&self) -> Option<U256> {
+        self.max_gas_price
+    }
+
+    /// set the highest gas price the item may be sent with.
+    pub fn set_max_gas_price(&mut self, max_gas_price: Option<U256>) {
+        self.max_gas_price = max_gas_price;
+    }
+
+    /// Returns the unix timestamp (in seconds) the item must be processed by,
+    /// if any.
+    pub fn deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+
+    /// set the unix timestamp (in seconds) the item must be processed by.
+    pub fn set_deadline(&mut self, deadline: Option<u64>) {
+        self.deadline = deadline;
+    }
+
+    /// Returns the resource the item was enqueued for, if any.
+    pub fn resource_id(&self) -> Option<H256> {
+        self.resource_id
+    }
+
+    /// set the resource the item was enqueued for.
+    pub fn set_resource_id(&mut self, resource_id: Option<H256>) {
+        self.resource_id = resource_id;
+    }
+
+    /// Returns the item keys of the items that must be processed before
+    /// this one is sent.
+    pub fn depends_on(&self) -> &[H512] {
+        &self.depends_on
+    }
+
+    /// set the items that must be processed before this one is sent.
+    pub fn set_depends_on(&mut self, depends_on: Vec<H512>) {
+        self.depends_on = depends_on;
+    }
+
+    /// Returns whether the item is still sent once the daily gas budget of
+    /// its chain is spent.
+    pub fn is_budget_exempt(&self) -> bool {
+        self.budget_exempt
+    }
+
+    /// set whether the item is still sent once the daily gas budget of its
+    /// chain is spent.
+    pub fn set_budget_exempt(&mut self, budget_exempt: bool) {
+        self.budget_exempt = budget_exempt;
+    }
+
+    /// Checks if the deadline of the item has passed.
+    pub fn is_past_deadline(&self) -> bool {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("SystemTime before UNIX EPOCH!");
+        self.deadline
+            .map_or(false, |deadline| now.as_secs() >= deadline)
+    }
+
+    /// Resets the item to `Pending` as if it was just enqueued,
+    /// so it gets processed again and does not expire right away.
+    pub fn reset(&mut self) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards");
+        self.state = QueueItemState::Pending;
+        self.enqueued_at = now.as_millis();
+    }
     /// Checks if item has been expired.
     pub fn is_expired(&self) -> bool {
         let now = std::time::SystemTime::now()
@@ -75,19 +218,75 @@ impl<T> QueueItem<T> {
     }
 }
 
+/// The priority class of an item in the queue.
+///
+/// Items of a lower class wait until no item of a higher class is pending.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+    Serialize,
+    Deserialize,
+)]
+pub enum QueueItemPriority {
+    /// Housekeeping items, such as treasury sweeps.
+    Low,
+    /// Relayed and governance transactions.
+    #[default]
+    Normal,
+}
+
 /// The status of the item in the queue.
+///
+/// An item goes through `Pending` -> `Validated` -> `Broadcast` -> `InBlock`
+/// -> `Processed`, and may end up `Failed` or `Expired` on the way, see
+/// [`QueueItemState::can_transition_to`] for the allowed transitions.
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub enum QueueItemState {
     /// The current item is pending and waiting in the queue to be dequeued and processed.
     #[default]
     Pending,
-    /// The item is being processed.
+    /// The item was being processed by an older relayer, before the steps
+    /// of the processing had their own states. Never entered anymore.
     Processing {
         /// A meaningful step for the current item state.
         step: String,
         /// A meaningful progress percentage for the current item state (0 to 1).
         progress: Option<f32>,
     },
+    /// The item is waiting for the gas price to drop below the highest one
+    /// it may be sent with.
+    WaitingForGasPrice {
+        /// The highest gas price the item may be sent with.
+        #[serde(rename = "maxGasPrice")]
+        max_gas_price: U256,
+        /// The gas price when the item was last checked.
+        #[serde(rename = "gasPrice")]
+        gas_price: U256,
+    },
+    /// The transaction of the item passed its dry run.
+    Validated,
+    /// The transaction of the item was broadcast, and is waiting to be
+    /// included in a block.
+    Broadcast {
+        /// How many times it was broadcast, starting at 1. Stuck
+        /// transactions are broadcast again with a higher gas price.
+        attempt: u32,
+    },
+    /// The transaction of the item was included in a block, and is waiting
+    /// for it to be finalized.
+    InBlock {
+        /// The hash of the block.
+        #[serde(rename = "blockHash")]
+        block_hash: H256,
+    },
+    /// The deadline of the item passed before it could be processed.
+    Expired,
     /// The item failed to be processed.
     Failed {
         /// The error message.
@@ -98,9 +297,100 @@ pub enum QueueItemState {
         /// Transaction hash.
         #[serde(rename = "txHash")]
         tx_hash: H256,
+        /// The block the transaction was included in.
+        #[serde(default, rename = "blockNumber")]
+        block_number: Option<u64>,
+        /// The gas used by the transaction.
+        #[serde(default, rename = "gasUsed")]
+        gas_used: Option<U256>,
+        /// The fee paid for the transaction, in wei.
+        #[serde(default, rename = "effectiveFee")]
+        effective_fee: Option<U256>,
     },
 }
 
+/// A snapshot of the items of a queue, used to sample its throughput.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    /// When the snapshot was taken (unix timestamp in milliseconds).
+    pub sampled_at: u128,
+    /// The number of items waiting or being processed.
+    pub pending: usize,
+    /// When the oldest of the pending items was enqueued
+    /// (unix timestamp in milliseconds).
+    pub oldest_pending_enqueued_at: Option<u128>,
+    /// The number of items enqueued since the given time.
+    pub enqueued_since: usize,
+}
+
+impl QueueItemState {
+    /// The name of the state, in kebab-case.
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueueItemState::Pending => "pending",
+            QueueItemState::Processing { .. } => "processing",
+            QueueItemState::WaitingForGasPrice { .. } => {
+                "waiting-for-gas-price"
+            }
+            QueueItemState::Validated => "validated",
+            QueueItemState::Broadcast { .. } => "broadcast",
+            QueueItemState::InBlock { .. } => "in-block",
+            QueueItemState::Expired => "expired",
+            QueueItemState::Failed { .. } => "failed",
+            QueueItemState::Processed { .. } => "processed",
+        }
+    }
+
+    /// Whether the item is still waiting or being processed.
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            QueueItemState::Pending
+                | QueueItemState::Processing { .. }
+                | QueueItemState::WaitingForGasPrice { .. }
+                | QueueItemState::Validated
+                | QueueItemState::Broadcast { .. }
+                | QueueItemState::InBlock { .. }
+        )
+    }
+
+    /// Whether an item in this state may move to the `next` one.
+    ///
+    /// Items waiting to be sent may be parked until the gas price drops,
+    /// expire, or pass their dry run. Once validated, they are broadcast,
+    /// again with each replacement of a stuck transaction, then included in
+    /// a block and finalized. A reorg may move the transaction to another
+    /// block, or retract it back to broadcast, and a transaction stuck past
+    /// its deadline expires once cancelled. Any of them may fail or go back
+    /// to pending, when retried or dropped from the mempool, but processed
+    /// items never change anymore.
+    pub fn can_transition_to(&self, next: &QueueItemState) -> bool {
+        use QueueItemState::*;
+        match (self, next) {
+            // the items processed by an older relayer may end up anywhere.
+            (Processing { .. }, _) => true,
+            (Processed { .. }, _) => false,
+            (_, Pending | Failed { .. }) => true,
+            (
+                Pending | WaitingForGasPrice { .. },
+                WaitingForGasPrice { .. } | Validated | Expired,
+            ) => true,
+            // transactions with an inconclusive dry run are still broadcast.
+            (Pending | Validated, Broadcast { attempt: 1 }) => true,
+            // a stuck transaction was cancelled after its deadline.
+            (Broadcast { .. }, Expired) => true,
+            (Broadcast { attempt }, Broadcast { attempt: next }) => {
+                next > attempt
+            }
+            (Broadcast { .. } | InBlock { .. }, InBlock { .. }) => true,
+            // the block of the transaction was retracted by a reorg.
+            (InBlock { .. }, Broadcast { .. }) => true,
+            (Broadcast { .. } | InBlock { .. }, Processed { .. }) => true,
+            _ => false,
+        }
+    }
+}
+
 /// A Queue Store is a simple trait that help storing items in a queue.
 /// The queue is a FIFO queue, that can be used to store anything that can be serialized.
 ///
@@ -117,6 +407,19 @@ where
         key: Self::Key,
         item: QueueItem<Item>,
     ) -> crate::Result<()>;
+    /// Insert an item into the queue, unless its resource already has
+    /// `max_pending` items pending in it, see
+    /// [`QueueStore::count_pending_items_of`].
+    ///
+    /// The count is checked in the same transaction as the item is inserted.
+    /// Items without a resource are always inserted. Returns whether the item
+    /// was inserted.
+    fn enqueue_item_capped(
+        &self,
+        key: Self::Key,
+        item: QueueItem<Item>,
+        max_pending: usize,
+    ) -> crate::Result<bool>;
     /// Get an item from the queue, and removes it.
     fn dequeue_item(
         &self,
@@ -154,6 +457,41 @@ where
     fn shift_item_to_end<F>(&self, key: Self::Key, f: F) -> crate::Result<bool>
     where
         F: FnMut(&mut QueueItem<Item>) -> crate::Result<()>;
+
+    /// Check if the queue has any pending item of a higher priority class
+    /// than the given one.
+    fn has_pending_items_above(
+        &self,
+        key: Self::Key,
+        priority: QueueItemPriority,
+    ) -> crate::Result<bool>;
+
+    /// Check if the queue has any pending item of another resource than the
+    /// given one, of the given priority class or a higher one.
+    ///
+    /// Items without a resource are not counted.
+    fn has_pending_items_besides(
+        &self,
+        key: Self::Key,
+        resource_id: Option<H256>,
+        priority: QueueItemPriority,
+    ) -> crate::Result<bool>;
+
+    /// Count the items of the given resource that are still waiting or
+    /// being processed (see [`QueueItemState::is_pending`]).
+    fn count_pending_items_of(
+        &self,
+        key: Self::Key,
+        resource_id: H256,
+    ) -> crate::Result<usize>;
+
+    /// Takes a snapshot of the items of the queue, counting the items
+    /// enqueued since the given time (unix timestamp in milliseconds).
+    fn queue_stats(
+        &self,
+        key: Self::Key,
+        since: u128,
+    ) -> crate::Result<QueueStats>;
 }
 
 impl<S, T> QueueStore<T> for Arc<S>
@@ -171,6 +509,15 @@ where
         S::enqueue_item(self, key, item)
     }
 
+    fn enqueue_item_capped(
+        &self,
+        key: Self::Key,
+        item: QueueItem<T>,
+        max_pending: usize,
+    ) -> crate::Result<bool> {
+        S::enqueue_item_capped(self, key, item, max_pending)
+    }
+
     fn dequeue_item(
         &self,
         key: Self::Key,
@@ -210,6 +557,39 @@ where
     {
         S::shift_item_to_end(self, key, f)
     }
+
+    fn has_pending_items_above(
+        &self,
+        key: Self::Key,
+        priority: QueueItemPriority,
+    ) -> crate::Result<bool> {
+        S::has_pending_items_above(self, key, priority)
+    }
+
+    fn has_pending_items_besides(
+        &self,
+        key: Self::Key,
+        resource_id: Option<H256>,
+        priority: QueueItemPriority,
+    ) -> crate::Result<bool> {
+        S::has_pending_items_besides(self, key, resource_id, priority)
+    }
+
+    fn count_pending_items_of(
+        &self,
+        key: Self::Key,
+        resource_id: H256,
+    ) -> crate::Result<usize> {
+        S::count_pending_items_of(self, key, resource_id)
+    }
+
+    fn queue_stats(
+        &self,
+        key: Self::Key,
+        since: u128,
+    ) -> crate::Result<QueueStats> {
+        S::queue_stats(self, key, since)
+    }
 }
 
 /// Create unique key for queue item, which can we used to update and remove item from queue.
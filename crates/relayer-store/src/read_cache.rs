@@ -0,0 +1,239 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::hash::Hash;
+use core::num::NonZeroUsize;
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// A read-through LRU cache in front of the hot read paths of a store.
+///
+/// A miss is read from the backing store while holding the lock, and the
+/// writers invalidate the entries only after writing to the backing store, so
+/// a value can never be cached after the write that made it stale.
+pub struct ReadCache<K, V> {
+    inner: Mutex<LruCache<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> ReadCache<K, V> {
+    /// Creates a new cache holding up to `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity.max(1))
+            .expect("capacity is at least one");
+        Self {
+            inner: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Returns the cached value of the key, or reads it with `f` and caches
+    /// it. Errors are not cached.
+    pub fn get_or_try_insert_with<E>(
+        &self,
+        key: K,
+        f: impl FnOnce() -> Result<V, E>,
+    ) -> Result<V, E> {
+        let mut cache = self.inner.lock();
+        if let Some(v) = cache.get(&key) {
+            return Ok(v.clone());
+        }
+        let v = f()?;
+        cache.put(key, v.clone());
+        Ok(v)
+    }
+
+    /// Drops the cached value of the key.
+    pub fn invalidate(&self, key: &K) {
+        self.inner.lock().pop(key);
+    }
+
+    /// Drops the cached values of all the keys matching the predicate.
+    pub fn invalidate_where(&self, f: impl Fn(&K) -> bool) {
+        let mut cache = self.inner.lock();
+        let stale = cache
+            .iter()
+            .map(|(k, _)| k)
+            .filter(|k| f(k))
+            .cloned()
+            .collect::<Vec<_>>();
+        for k in stale {
+            cache.pop(&k);
+        }
+    }
+}
+
+/// The slot of a key in a [`SliceCache`], locked while its values are read
+/// from the backing store.
+type Slot<V> = Arc<Mutex<Option<Arc<[V]>>>>;
+
+/// The slots of a [`SliceCache`], with the number of values cached in each.
+struct Slots<K, V> {
+    lru: LruCache<K, (Slot<V>, usize)>,
+    len: usize,
+}
+
+/// A read-through LRU cache of slices, shared by all of their readers.
+///
+/// It is bounded by the total number of values it holds rather than by the
+/// number of keys, and each key has its own lock, so a miss reading the
+/// values of a key from the backing store does not block the readers of the
+/// other keys. Like the [`ReadCache`], the writers invalidate the key only
+/// after writing to the backing store, and a miss that raced with them is
+/// not cached.
+pub struct SliceCache<K, V> {
+    slots: Mutex<Slots<K, V>>,
+    max_len: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> SliceCache<K, V> {
+    /// Creates a new cache holding up to `max_len` values.
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            slots: Mutex::new(Slots {
+                lru: LruCache::unbounded(),
+                len: 0,
+            }),
+            max_len,
+        }
+    }
+
+    /// Returns the cached values of the key, or reads them with `f` and
+    /// caches them if they fit. Errors are not cached.
+    pub fn get_or_try_insert_with<E>(
+        &self,
+        key: K,
+        f: impl FnOnce() -> Result<Vec<V>, E>,
+    ) -> Result<Arc<[V]>, E> {
+        let slot = {
+            let mut slots = self.slots.lock();
+            let (slot, _) = slots
+                .lru
+                .get_or_insert(key.clone(), || (Arc::new(Mutex::new(None)), 0));
+            slot.clone()
+        };
+        let mut values = slot.lock();
+        if let Some(values) = values.as_ref() {
+            return Ok(values.clone());
+        }
+        let read: Arc<[V]> = f()?.into();
+        if read.len() > self.max_len {
+            return Ok(read);
+        }
+        let mut slots = self.slots.lock();
+        // the key was invalidated while its values were read.
+        let current = slots.lru.peek(&key).map(|(s, _)| s.clone());
+        if !current.map_or(false, |current| Arc::ptr_eq(&current, &slot)) {
+            return Ok(read);
+        }
+        *values = Some(read.clone());
+        slots.len += read.len();
+        if let Some((_, len)) = slots.lru.peek_mut(&key) {
+            *len = read.len();
+        }
+        // evict the least recently used keys, but the one just read.
+        let lru_keys = slots
+            .lru
+            .iter()
+            .rev()
+            .filter(|(k, (_, len))| **k != key && *len > 0)
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<_>>();
+        for k in lru_keys {
+            if slots.len <= self.max_len {
+                break;
+            }
+            if let Some((_, len)) = slots.lru.pop(&k) {
+                slots.len -= len;
+            }
+        }
+        Ok(read)
+    }
+
+    /// Drops the cached values of the key.
+    pub fn invalidate(&self, key: &K) {
+        let mut slots = self.slots.lock();
+        if let Some((_, len)) = slots.lru.pop(key) {
+            slots.len -= len;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_cache_should_work() {
+        let cache = ReadCache::<(u32, u32), u64>::new(2);
+        let read = |v| Ok::<_, ()>(v);
+        assert_eq!(cache.get_or_try_insert_with((1, 1), || read(10)), Ok(10));
+        // a hit does not read again.
+        assert_eq!(cache.get_or_try_insert_with((1, 1), || read(11)), Ok(10));
+        // errors are not cached.
+        assert_eq!(cache.get_or_try_insert_with((1, 2), || Err(())), Err(()));
+        assert_eq!(cache.get_or_try_insert_with((1, 2), || read(20)), Ok(20));
+        // the least recently used entry is evicted.
+        assert_eq!(cache.get_or_try_insert_with((2, 1), || read(30)), Ok(30));
+        assert_eq!(cache.get_or_try_insert_with((1, 1), || read(12)), Ok(12));
+
+        cache.invalidate(&(1, 1));
+        assert_eq!(cache.get_or_try_insert_with((1, 1), || read(13)), Ok(13));
+        cache.invalidate_where(|(a, _)| *a == 1);
+        assert_eq!(cache.get_or_try_insert_with((1, 1), || read(14)), Ok(14));
+        assert_eq!(cache.get_or_try_insert_with((2, 1), || read(31)), Ok(30));
+    }
+
+    #[test]
+    fn slice_cache_should_work() {
+        let cache = SliceCache::<u32, u64>::new(3);
+        let read = |v: &[u64]| Ok::<_, ()>(v.to_vec());
+        let a = cache.get_or_try_insert_with(1, || read(&[1, 2])).unwrap();
+        // a hit shares the cached values.
+        let b = cache.get_or_try_insert_with(1, || read(&[3])).unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        // errors are not cached.
+        assert!(cache.get_or_try_insert_with(2, || Err(())).is_err());
+        assert_eq!(
+            *cache.get_or_try_insert_with(2, || read(&[4])).unwrap(),
+            [4]
+        );
+        // over the size, the least recently used key is evicted.
+        assert_eq!(
+            *cache.get_or_try_insert_with(3, || read(&[5, 6])).unwrap(),
+            [5, 6]
+        );
+        assert_eq!(
+            *cache.get_or_try_insert_with(1, || read(&[7])).unwrap(),
+            [7]
+        );
+        // values that can't fit are not cached.
+        assert_eq!(
+            cache
+                .get_or_try_insert_with(4, || read(&[8, 9, 10, 11]))
+                .unwrap()
+                .len(),
+            4
+        );
+        assert_eq!(
+            *cache.get_or_try_insert_with(4, || read(&[12])).unwrap(),
+            [12]
+        );
+
+        cache.invalidate(&4);
+        assert_eq!(
+            *cache.get_or_try_insert_with(4, || read(&[13])).unwrap(),
+            [13]
+        );
+    }
+}
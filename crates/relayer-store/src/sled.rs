@@ -12,25 +12,137 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::bloom::CountingBloomFilter;
+use super::cipher::StoreCipher;
+use super::read_cache::{ReadCache, SliceCache};
 use super::HistoryStoreKey;
 use super::{
-    EncryptedOutputCacheStore, EventHashStore, HistoryStore, LeafCacheStore,
-    TokenPriceCacheStore,
+    AccountingStore, EncryptedOutputCacheStore, EventHashStore,
+    ForwardedRequestStore, FundingTransferStore, HistoryStore, LeafCacheStore,
+    LeavesBatch, MerkleRootRecord, MerkleRootStore, NullifierStore,
+    ProfitAndLoss, RelayJob, RelayJobStore, RelayNonceStore, RelayedTxRecord,
+    RelayedTxStore, TangleJob, TangleJobStore, TokenPriceCacheStore,
+    TxBatchStore, TxCost, TxHashStore, MERKLE_ROOT_HISTORY_SIZE,
+};
+use crate::queue::{
+    QueueItem, QueueItemPriority, QueueItemState, QueueKey, QueueStats,
+    QueueStore,
 };
-use crate::queue::{QueueItem, QueueKey, QueueStore};
 use crate::BridgeKey;
 use core::fmt;
+use parking_lot::{Mutex, RwLock};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use sled::Transactional;
-use std::collections::BTreeMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Debug;
 use std::path::Path;
+use std::sync::Arc;
 use webb::evm::ethers::{self, types};
+use webb_proposals::{ResourceId, TypedChainId};
+
+/// The number of event hashes the in-memory Bloom filter is sized for.
+const EVENT_FILTER_CAPACITY: usize = 1_000_000;
+/// The target false positive rate of the in-memory Bloom filter.
+const EVENT_FILTER_FALSE_POSITIVE_RATE: f64 = 0.01;
+/// The number of leaves, of all the anchors, kept in the read cache.
+const LEAVES_CACHE_CAPACITY: usize = 1 << 20;
+/// The number of block numbers kept in the read cache.
+const BLOCK_NUMBERS_CACHE_CAPACITY: usize = 1024;
+
+/// The block number trees that are kept in the read cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum BlockNumberTree {
+    /// The last block number processed by the events watchers.
+    LastBlock,
+    /// The block number of the last deposit.
+    LastDeposit,
+}
+
+impl BlockNumberTree {
+    fn name(self) -> &'static str {
+        match self {
+            Self::LastBlock => "last_block_numbers",
+            Self::LastDeposit => "last_deposit_block_number",
+        }
+    }
+}
+
+/// The trees that are kept per [`HistoryStoreKey`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum KeyedTree {
+    /// The leaves of a merkle tree.
+    Leaves,
+    /// The encrypted outputs of a VAnchor.
+    EncryptedOutputs,
+}
+
+impl KeyedTree {
+    fn prefix(&self) -> &'static str {
+        match self {
+            Self::Leaves => "leaves",
+            Self::EncryptedOutputs => "encrypted_outputs",
+        }
+    }
+
+    /// The name of the tree of the given key.
+    fn name(&self, key: HistoryStoreKey) -> String {
+        format!("{}/{}", self.prefix(), hex::encode(key.to_bytes()))
+    }
+
+    /// The name the tree of the given key had before the trees were named
+    /// after the full resource id.
+    ///
+    /// It only used the underlying chain id and the (display formatted,
+    /// hence shortened) last 20 bytes of the target system, so keys of
+    /// different chain types or similar addresses shared the same tree.
+    fn legacy_name(&self, key: HistoryStoreKey) -> String {
+        let target_system = key.resource_id().target_system().to_bytes();
+        format!(
+            "{}/{}/{}",
+            self.prefix(),
+            key.typed_chain_id().underlying_chain_id(),
+            types::H160::from_slice(&target_system[6..])
+        )
+    }
+}
+
+/// The number of keys stored in each kind of tree of a [`SledStore`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeStatistics {
+    /// Keys in all the leaves trees.
+    pub leaves: usize,
+    /// Keys in all the encrypted outputs trees.
+    pub encrypted_outputs: usize,
+    /// Keys in all the queues, including their bookkeeping keys.
+    pub queues: usize,
+    /// Keys in the event hashes tree.
+    pub event_hashes: usize,
+}
+
 /// SledStore is a store that stores the history of events in  a [Sled](https://sled.rs)-based database.
 #[derive(Clone)]
 pub struct SledStore {
     db: sled::Db,
+    /// A front cache for the `event_hashes` tree.
+    event_filter: Arc<RwLock<CountingBloomFilter>>,
+    /// A read cache for the leaves of each anchor, sorted by index and
+    /// invalidated on insert.
+    leaves_cache: Arc<SliceCache<HistoryStoreKey, (u32, types::H256)>>,
+    /// A read cache for the block number trees, invalidated on insert.
+    block_numbers_cache:
+        Arc<ReadCache<(BlockNumberTree, HistoryStoreKey), Option<u64>>>,
+    /// The keyed trees that were already checked for a legacy tree to
+    /// migrate since the store was opened.
+    migrated_trees: Arc<Mutex<HashSet<(KeyedTree, HistoryStoreKey)>>>,
+    /// The queues whose counters were already checked since the store was
+    /// opened.
+    counted_queues: Arc<Mutex<HashSet<String>>>,
+    /// Encrypts the queue items and the leaves, if enabled.
+    cipher: Option<Arc<StoreCipher>>,
+    /// Whether the event hashes are indexed by the time they are stored.
+    event_retention: bool,
 }
 
 impl std::fmt::Debug for SledStore {
@@ -47,7 +159,23 @@ impl SledStore {
             .temporary(cfg!(test))
             .mode(sled::Mode::HighThroughput)
             .open()?;
-        Ok(Self { db })
+        let store = Self {
+            db,
+            event_filter: Arc::new(RwLock::new(CountingBloomFilter::new(
+                EVENT_FILTER_CAPACITY,
+                EVENT_FILTER_FALSE_POSITIVE_RATE,
+            ))),
+            leaves_cache: Arc::new(SliceCache::new(LEAVES_CACHE_CAPACITY)),
+            block_numbers_cache: Arc::new(ReadCache::new(
+                BLOCK_NUMBERS_CACHE_CAPACITY,
+            )),
+            migrated_trees: Default::default(),
+            counted_queues: Default::default(),
+            cipher: None,
+            event_retention: false,
+        };
+        store.rebuild_event_filter()?;
+        Ok(store)
     }
     /// Creates a temporary SledStore.
     pub fn temporary() -> crate::Result<Self> {
@@ -55,10 +183,531 @@ impl SledStore {
         Self::open(dir.path())
     }
 
+    /// Encrypts the queue items and the leaves written from now on with
+    /// the given 256-bit key.
+    ///
+    /// The values written before are still read as is, and encrypted the
+    /// next time they are written.
+    pub fn with_encryption_key(mut self, key: [u8; 32]) -> Self {
+        self.cipher = Some(Arc::new(StoreCipher::new(key)));
+        self
+    }
+
+    /// Indexes the event hashes stored from now on by the time they are
+    /// stored, so they can be purged once they are older than their
+    /// retention window, see [`EventHashStore::purge_events_before`].
+    pub fn with_event_retention(mut self) -> Self {
+        self.event_retention = true;
+        self
+    }
+
+    /// Encrypts a value before it is written, if the encryption is enabled.
+    fn seal(&self, value: Vec<u8>) -> Vec<u8> {
+        match &self.cipher {
+            Some(cipher) => cipher.seal(&value),
+            None => value,
+        }
+    }
+
+    /// Decrypts a value that was read, if it was encrypted.
+    fn unseal<'a>(&self, value: &'a [u8]) -> crate::Result<Cow<'a, [u8]>> {
+        match &self.cipher {
+            Some(cipher) => cipher.open(value),
+            None if StoreCipher::is_sealed(value) => {
+                Err(webb_relayer_utils::Error::Generic(
+                    "the store is encrypted, but no encryption key is set",
+                ))
+            }
+            None => Ok(Cow::Borrowed(value)),
+        }
+    }
+
+    /// Serializes a queue item, encrypting it if enabled.
+    fn encode_item<T: Serialize>(
+        &self,
+        item: &QueueItem<T>,
+    ) -> crate::Result<Vec<u8>> {
+        Ok(self.seal(serde_json::to_vec(item)?))
+    }
+
+    /// Deserializes a queue item, decrypting it if needed.
+    fn decode_item<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> crate::Result<QueueItem<T>> {
+        Ok(serde_json::from_slice(&self.unseal(bytes)?)?)
+    }
+
+    /// Reads a block number from the given tree, through the read cache.
+    fn get_block_number(
+        &self,
+        tree: BlockNumberTree,
+        key: HistoryStoreKey,
+    ) -> crate::Result<Option<u64>> {
+        self.block_numbers_cache.get_or_try_insert_with(
+            (tree, key),
+            || -> crate::Result<_> {
+                let val =
+                    self.db.open_tree(tree.name())?.get(key.to_bytes())?;
+                Ok(val.map(|v| {
+                    let mut output = [0u8; 8];
+                    output.copy_from_slice(&v);
+                    u64::from_le_bytes(output)
+                }))
+            },
+        )
+    }
+
+    /// Opens the tree of the given kind for the key, migrating the legacy
+    /// tree of the key into it the first time it is opened.
+    fn keyed_tree(
+        &self,
+        kind: KeyedTree,
+        key: HistoryStoreKey,
+    ) -> crate::Result<sled::Tree> {
+        let tree = self.db.open_tree(kind.name(key))?;
+        let mut migrated_trees = self.migrated_trees.lock();
+        if migrated_trees.contains(&(kind, key)) {
+            return Ok(tree);
+        }
+        let legacy_name = kind.legacy_name(key);
+        let has_legacy_tree = self
+            .db
+            .tree_names()
+            .iter()
+            .any(|name| &name[..] == legacy_name.as_bytes());
+        if has_legacy_tree {
+            let legacy_tree = self.db.open_tree(&legacy_name)?;
+            let mut batch = sled::Batch::default();
+            for entry in legacy_tree.iter() {
+                let (k, v) = entry?;
+                batch.insert(k, v);
+            }
+            tree.apply_batch(batch)?;
+            self.db.drop_tree(&legacy_name)?;
+            tracing::info!(
+                %key,
+                from = %legacy_name,
+                to = %kind.name(key),
+                "Migrated legacy store tree",
+            );
+        }
+        migrated_trees.insert((kind, key));
+        Ok(tree)
+    }
+
+    /// Drops the tree of the given kind for the key, and its legacy tree.
+    fn drop_keyed_tree(
+        &self,
+        kind: KeyedTree,
+        key: HistoryStoreKey,
+    ) -> crate::Result<()> {
+        self.db.drop_tree(kind.legacy_name(key))?;
+        self.db.drop_tree(kind.name(key))?;
+        Ok(())
+    }
+
+    /// Opens the tree of the queue, counting the items already in it the
+    /// first time it is opened, if they were enqueued before the queues kept
+    /// count of their pending items.
+    fn queue_tree(&self, queue_name: &str) -> crate::Result<sled::Tree> {
+        let tree = self.db.open_tree(format!("queue_{queue_name}"))?;
+        let mut counted_queues = self.counted_queues.lock();
+        if counted_queues.contains(queue_name) {
+            return Ok(tree);
+        }
+        if !tree.contains_key(QUEUE_COUNTED_KEY)? {
+            let prefix =
+                tree.get("key_prefix")?.unwrap_or_else(|| b"item".into());
+            let mut counts = BTreeMap::<Vec<u8>, u64>::new();
+            for entry in tree.scan_prefix(&prefix) {
+                let (key, value) = entry?;
+                if key.len() != prefix.len() + std::mem::size_of::<u64>() {
+                    continue;
+                }
+                for counter in self.item_counters(&value)? {
+                    *counts.entry(counter).or_default() += 1;
+                }
+            }
+            let mut batch = sled::Batch::default();
+            for (counter, count) in counts {
+                batch.insert(counter, &count.to_be_bytes());
+            }
+            batch.insert(QUEUE_COUNTED_KEY, &[1u8]);
+            tree.apply_batch(batch)?;
+            tracing::info!(queue = %queue_name, "Counted the queue items");
+        }
+        counted_queues.insert(queue_name.to_owned());
+        Ok(tree)
+    }
+
+    /// The counters of the queue the encoded item is counted in.
+    fn item_counters(&self, item_bytes: &[u8]) -> crate::Result<Vec<Vec<u8>>> {
+        let item: QueueItem<serde::de::IgnoredAny> =
+            self.decode_item(item_bytes)?;
+        Ok(queue_counters(&item))
+    }
+
+    /// Removes the encoded item at the given key of the queue tree, and
+    /// takes it out of the counters of the queue.
+    fn remove_in(
+        &self,
+        db: &sled::transaction::TransactionalTree,
+        item_key: &[u8],
+    ) -> sled::transaction::ConflictableTransactionResult<
+        Option<sled::IVec>,
+        std::io::Error,
+    > {
+        use sled::transaction::ConflictableTransactionError::Abort;
+        let Some(item_bytes) = db.remove(item_key)? else {
+            return Ok(None);
+        };
+        let counters = self.item_counters(&item_bytes).map_err(|e| {
+            Abort(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                e.to_string(),
+            ))
+        })?;
+        add_to_counters(db, &counters, -1)?;
+        Ok(Some(item_bytes))
+    }
+
+    /// The leaves of the key sorted by index, shared with the read cache.
+    fn cached_leaves(
+        &self,
+        key: HistoryStoreKey,
+    ) -> crate::Result<Arc<[(u32, types::H256)]>> {
+        self.leaves_cache.get_or_try_insert_with(key, || {
+            let tree = self.keyed_tree(KeyedTree::Leaves, key)?;
+            let mut leaves = tree
+                .iter()
+                .flatten()
+                .map(|(k, v)| {
+                    let leaf_index_bytes =
+                        k.get(0..4).expect("leaf index bytes");
+                    let leaf_index_bytes = leaf_index_bytes
+                        .try_into()
+                        .expect("leaf index bytes is u32 bytes");
+                    let leaf_index = u32::from_le_bytes(leaf_index_bytes);
+                    let leaf = types::H256::from_slice(&self.unseal(&v)?);
+                    Ok((leaf_index, leaf))
+                })
+                .collect::<crate::Result<Vec<_>>>()?;
+            // the indices are stored little endian, out of order.
+            leaves.sort_unstable_by_key(|(leaf_index, _)| *leaf_index);
+            Ok(leaves)
+        })
+    }
+
+    /// Drops the cached leaves and block numbers of the key, once they were
+    /// written to the store.
+    fn invalidate_leaves(&self, key: HistoryStoreKey) {
+        self.leaves_cache.invalidate(&key);
+        self.block_numbers_cache
+            .invalidate(&(BlockNumberTree::LastBlock, key));
+        self.block_numbers_cache
+            .invalidate(&(BlockNumberTree::LastDeposit, key));
+    }
+
     /// Gets the total amount of data stored on disk
     pub fn get_data_stored_size(&self) -> u64 {
         self.db.size_on_disk().unwrap_or_default()
     }
+
+    /// Counts the keys stored in the leaves, encrypted outputs, queues and
+    /// event hashes trees.
+    ///
+    /// This walks every key of these trees, so it is meant to be sampled
+    /// periodically rather than called on a hot path.
+    pub fn tree_statistics(&self) -> crate::Result<TreeStatistics> {
+        let mut stats = TreeStatistics::default();
+        for name in self.db.tree_names() {
+            let Ok(name) = std::str::from_utf8(&name) else {
+                continue;
+            };
+            let count = if name.starts_with("leaves/") {
+                &mut stats.leaves
+            } else if name.starts_with("encrypted_outputs/") {
+                &mut stats.encrypted_outputs
+            } else if name.starts_with("queue_") {
+                &mut stats.queues
+            } else if name == "event_hashes" {
+                &mut stats.event_hashes
+            } else {
+                continue;
+            };
+            *count += self.db.open_tree(name)?.len();
+        }
+        Ok(stats)
+    }
+
+    /// Flushes all the dirty data of the store to disk, returning how long
+    /// it took.
+    pub fn flush(&self) -> crate::Result<std::time::Duration> {
+        let started_at = std::time::Instant::now();
+        self.db.flush()?;
+        Ok(started_at.elapsed())
+    }
+
+    /// Returns the names of all the queues in the store.
+    pub fn queue_names(&self) -> Vec<String> {
+        self.db
+            .tree_names()
+            .into_iter()
+            .filter_map(|name| {
+                let name = std::str::from_utf8(&name).ok()?;
+                name.strip_prefix("queue_").map(ToOwned::to_owned)
+            })
+            .collect()
+    }
+
+    /// Returns the items of the given queue in order, without removing them.
+    ///
+    /// The inner values are returned as raw JSON, so the items of any queue
+    /// can be read without knowing their type.
+    pub fn queue_items(
+        &self,
+        queue_name: &str,
+    ) -> crate::Result<Vec<QueueItem<serde_json::Value>>> {
+        if !self.queue_names().iter().any(|name| name == queue_name) {
+            return Ok(Vec::new());
+        }
+        let tree = self.queue_tree(queue_name)?;
+        let prefix = tree.get("key_prefix")?.unwrap_or_else(|| b"item".into());
+        tree.scan_prefix(prefix)
+            .values()
+            .map(|v| self.decode_item(&v?))
+            .collect()
+    }
+
+    /// Removes the items of the given queue whose state matches the filter.
+    ///
+    /// Returns the number of removed items.
+    #[tracing::instrument(skip(self, filter))]
+    pub fn purge_queue_items<F>(
+        &self,
+        queue_name: &str,
+        filter: F,
+    ) -> crate::Result<usize>
+    where
+        F: Fn(&QueueItemState) -> bool,
+    {
+        self.remove_queue_items(queue_name, |item| filter(&item.state()))
+    }
+
+    /// Removes the items of the given queue that are no longer pending
+    /// (processed, failed or expired) and were enqueued before the given
+    /// time (unix timestamp in milliseconds).
+    ///
+    /// Returns the number of removed items.
+    #[tracing::instrument(skip(self))]
+    pub fn purge_finished_queue_items(
+        &self,
+        queue_name: &str,
+        enqueued_before: u128,
+    ) -> crate::Result<usize> {
+        self.remove_queue_items(queue_name, |item| {
+            !item.state().is_pending() && item.enqueued_at() < enqueued_before
+        })
+    }
+
+    /// Removes the items of the given queue that match the filter.
+    fn remove_queue_items<F>(
+        &self,
+        queue_name: &str,
+        filter: F,
+    ) -> crate::Result<usize>
+    where
+        F: Fn(&QueueItem<serde_json::Value>) -> bool,
+    {
+        if !self.queue_names().iter().any(|name| name == queue_name) {
+            return Ok(0);
+        }
+        let tree = self.queue_tree(queue_name)?;
+        let prefix = tree.get("key_prefix")?.unwrap_or_else(|| b"item".into());
+        let mut removed = Vec::new();
+        for entry in tree.scan_prefix(&prefix) {
+            let (key, value) = entry?;
+            if key.len() != prefix.len() + std::mem::size_of::<u64>() {
+                continue;
+            }
+            let item: QueueItem<serde_json::Value> =
+                self.decode_item(&value)?;
+            if filter(&item) {
+                let exists =
+                    tree.transaction::<_, _, std::io::Error>(|db| {
+                        self.remove_in(db, &key)
+                    })?;
+                if exists.is_some() {
+                    removed.push(key);
+                }
+            }
+        }
+        // also drop the custom keys pointing to the removed items.
+        let mut removed_item_keys = Vec::new();
+        for entry in tree.iter() {
+            let (key, value) = entry?;
+            if key.len() == 64 && removed.contains(&value) {
+                tree.remove(&key)?;
+                removed_item_keys.push(key);
+            }
+        }
+        if let Some(chain_id) = queue_name
+            .strip_prefix("evm_tx_")
+            .and_then(|chain_id| chain_id.parse::<u32>().ok())
+        {
+            self.remove_tx_records(chain_id, &tree, &removed_item_keys)?;
+        }
+        self.db.flush()?;
+        tracing::debug!(purged = removed.len(), "purged queue items");
+        Ok(removed.len())
+    }
+
+    /// Removes the tx hashes of the removed items of an evm tx queue, and
+    /// the batches of the chain that have none of their items left in it.
+    fn remove_tx_records(
+        &self,
+        chain_id: u32,
+        queue_tree: &sled::Tree,
+        removed_item_keys: &[sled::IVec],
+    ) -> crate::Result<()> {
+        let tx_hashes = self.db.open_tree("tx_hashes")?;
+        for item_key in removed_item_keys {
+            let mut key = chain_id.to_be_bytes().to_vec();
+            key.extend_from_slice(item_key);
+            tx_hashes.remove(key)?;
+        }
+        let tx_batches = self.db.open_tree("tx_batches")?;
+        for entry in tx_batches.scan_prefix(chain_id.to_be_bytes()) {
+            let (key, value) = entry?;
+            let item_keys: Vec<types::H512> = serde_json::from_slice(&value)?;
+            let mut items_left = false;
+            for item_key in &item_keys {
+                items_left |= queue_tree.contains_key(item_key.as_bytes())?;
+            }
+            if !items_left {
+                tx_batches.remove(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Resets the item with the given key back to `Pending` and moves it
+    /// to the end of its transaction queue.
+    ///
+    /// Returns the name of the queue the item was found in, if any.
+    #[tracing::instrument(skip(self))]
+    pub fn requeue_item(
+        &self,
+        item_key: [u8; 64],
+    ) -> crate::Result<Option<String>> {
+        for queue_name in self.queue_names() {
+            let chain_id = |prefix: &str| {
+                queue_name.strip_prefix(prefix)?.parse::<u32>().ok()
+            };
+            let key = if let Some(chain_id) = chain_id("evm_tx_") {
+                SledQueueKey::from_evm_with_custom_key(chain_id, item_key)
+            } else if let Some(chain_id) = chain_id("substrate_tx_") {
+                SledQueueKey::from_substrate_with_custom_key(chain_id, item_key)
+            } else {
+                continue;
+            };
+            let requeued = QueueStore::<serde_json::Value>::shift_item_to_end(
+                self,
+                key,
+                |item| {
+                    item.reset();
+                    Ok(())
+                },
+            )?;
+            if requeued {
+                return Ok(Some(queue_name));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Refills the event filter from the `event_hashes` tree, when the
+    /// store is opened.
+    fn rebuild_event_filter(&self) -> crate::Result<()> {
+        let tree = self.db.open_tree("event_hashes")?;
+        let mut filter = self.event_filter.write();
+        filter.clear();
+        for key in tree.iter().keys() {
+            let key = key?;
+            if let Ok(hash) = <[u8; 32]>::try_from(&key[..]) {
+                filter.insert(&hash);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the given event hash and its block and time index entries,
+    /// returns `false` if it was not stored.
+    fn remove_event_hash(&self, hash: &[u8; 32]) -> crate::Result<bool> {
+        let tree = self.db.open_tree("event_hashes")?;
+        let Some(value) = self.remove_event_hash_entry(&tree, hash)? else {
+            return Ok(false);
+        };
+        // entries written before the block index existed have an empty value.
+        if value.len() >= EVENT_HASH_VALUE_LEN {
+            let (key_bytes, block_number) =
+                value[..EVENT_HASH_VALUE_LEN].split_at(32);
+            let by_block_tree = self.db.open_tree(format!(
+                "event_hashes_by_block/{}",
+                hex::encode(key_bytes)
+            ))?;
+            let mut index_key = block_number.to_vec();
+            index_key.extend_from_slice(hash);
+            by_block_tree.remove(index_key)?;
+        }
+        self.remove_event_time_index(hash, &value)?;
+        Ok(true)
+    }
+
+    /// Removes the given event hash from the `event_hashes` tree and from
+    /// the event filter, returns its value if it was stored.
+    fn remove_event_hash_entry(
+        &self,
+        tree: &sled::Tree,
+        hash: &[u8; 32],
+    ) -> crate::Result<Option<sled::IVec>> {
+        // hold the filter lock while removing, so the filter counts the
+        // hashes in the tree exactly once.
+        let mut filter = self.event_filter.write();
+        let value = tree.remove(hash)?;
+        if value.is_some() {
+            filter.remove(hash);
+        }
+        Ok(value)
+    }
+
+    /// Removes the time index entry of the removed event hash, if it was
+    /// indexed by time.
+    fn remove_event_time_index(
+        &self,
+        hash: &[u8],
+        value: &[u8],
+    ) -> crate::Result<()> {
+        if let Some(time_key) = event_time_key(hash, value) {
+            self.db
+                .open_tree("event_hashes_by_time")?
+                .remove(time_key)?;
+        }
+        Ok(())
+    }
+
+    /// Opens the tree that indexes the event hashes of the given key by block number.
+    fn event_hashes_by_block_tree(
+        &self,
+        key: &HistoryStoreKey,
+    ) -> crate::Result<sled::Tree> {
+        let tree = self.db.open_tree(format!(
+            "event_hashes_by_block/{}",
+            hex::encode(key.to_bytes())
+        ))?;
+        Ok(tree)
+    }
 }
 
 impl HistoryStore for SledStore {
@@ -72,6 +721,8 @@ impl HistoryStore for SledStore {
         let bytes = block_number.to_le_bytes();
         let key: HistoryStoreKey = key.into();
         let old = tree.insert(key.to_bytes(), &bytes)?;
+        self.block_numbers_cache
+            .invalidate(&(BlockNumberTree::LastBlock, key));
         match old {
             Some(v) => {
                 let mut output = [0u8; 8];
@@ -88,17 +739,9 @@ impl HistoryStore for SledStore {
         key: K,
         default_block_number: u64,
     ) -> crate::Result<u64> {
-        let tree = self.db.open_tree("last_block_numbers")?;
-        let key: HistoryStoreKey = key.into();
-        let val = tree.get(key.to_bytes())?;
-        match val {
-            Some(v) => {
-                let mut output = [0u8; 8];
-                output.copy_from_slice(&v);
-                Ok(u64::from_le_bytes(output))
-            }
-            None => Ok(default_block_number),
-        }
+        let block_number =
+            self.get_block_number(BlockNumberTree::LastBlock, key.into())?;
+        Ok(block_number.unwrap_or(default_block_number))
     }
 
     #[tracing::instrument(skip(self))]
@@ -139,180 +782,273 @@ impl HistoryStore for SledStore {
             None => Ok(default_block_number),
         }
     }
-}
-
-impl LeafCacheStore for SledStore {
-    type Output = BTreeMap<u32, types::H256>;
 
     #[tracing::instrument(skip(self))]
-    fn clear_leaves_cache<K: Into<HistoryStoreKey> + Debug>(
+    fn set_deployment_block_number<K: Into<HistoryStoreKey> + Debug>(
         &self,
         key: K,
+        block_number: u64,
     ) -> crate::Result<()> {
+        let tree = self.db.open_tree("deployment_block_numbers")?;
         let key: HistoryStoreKey = key.into();
-        self.db.drop_tree(format!(
-            "leaves/{}/{}",
-            key.chain_id(),
-            key.address()
-        ))?;
+        tree.insert(key.to_bytes(), &block_number.to_be_bytes())?;
         Ok(())
     }
 
     #[tracing::instrument(skip(self))]
-    fn get_leaves<K: Into<HistoryStoreKey> + Debug>(
+    fn get_deployment_block_number<K: Into<HistoryStoreKey> + Debug>(
         &self,
         key: K,
-    ) -> crate::Result<Self::Output> {
+    ) -> crate::Result<Option<u64>> {
+        let tree = self.db.open_tree("deployment_block_numbers")?;
         let key: HistoryStoreKey = key.into();
-        let tree = self.db.open_tree(format!(
-            "leaves/{}/{}",
-            key.chain_id(),
-            key.address()
-        ))?;
-        let leaves_map: BTreeMap<_, _> = tree
-            .iter()
-            .flatten()
-            .map(|(k, v)| {
-                let leaf_index_bytes = k.get(0..4).expect("leaf index bytes");
-                let leaf_index_bytes = leaf_index_bytes
-                    .try_into()
-                    .expect("leaf index bytes is u32 bytes");
-                let leaf_index = u32::from_le_bytes(leaf_index_bytes);
-                let leaf = types::H256::from_slice(&v);
-                (leaf_index, leaf)
-            })
-            .collect();
-        Ok(leaves_map)
+        let val = tree.get(key.to_bytes())?;
+        Ok(val.map(|v| {
+            let mut output = [0u8; 8];
+            output.copy_from_slice(&v);
+            u64::from_be_bytes(output)
+        }))
     }
 
     #[tracing::instrument(skip(self))]
-    fn get_leaves_with_range<K: Into<HistoryStoreKey> + Debug>(
+    fn set_blocks_per_step<K: Into<HistoryStoreKey> + Debug>(
         &self,
         key: K,
-        range: core::ops::Range<u32>,
-    ) -> crate::Result<Self::Output> {
+        blocks_per_step: u64,
+    ) -> crate::Result<()> {
+        let tree = self.db.open_tree("blocks_per_step")?;
         let key: HistoryStoreKey = key.into();
-        let tree = self.db.open_tree(format!(
-            "leaves/{}/{}",
-            key.chain_id(),
-            key.address()
-        ))?;
-        let range_start = range.start.to_le_bytes();
-        let range_end = range.end.to_le_bytes();
-        let leaves = tree
-            .range(range_start..range_end)
-            .flatten()
-            .map(|(k, v)| {
-                let leaf_index_bytes = k.get(0..4).expect("leaf index bytes");
-                let leaf_index_bytes = leaf_index_bytes
-                    .try_into()
-                    .expect("leaf index bytes is u32 bytes");
-                let leaf_index = u32::from_le_bytes(leaf_index_bytes);
-                let leaf = types::H256::from_slice(&v);
-                (leaf_index, leaf)
-            })
-            .collect();
-        Ok(leaves)
+        tree.insert(key.to_bytes(), &blocks_per_step.to_be_bytes())?;
+        Ok(())
     }
 
     #[tracing::instrument(skip(self))]
-    fn get_last_deposit_block_number<K: Into<HistoryStoreKey> + Debug>(
+    fn get_blocks_per_step<K: Into<HistoryStoreKey> + Debug>(
         &self,
         key: K,
-    ) -> crate::Result<u64> {
-        let tree = self.db.open_tree("last_deposit_block_number")?;
+    ) -> crate::Result<Option<u64>> {
+        let tree = self.db.open_tree("blocks_per_step")?;
         let key: HistoryStoreKey = key.into();
         let val = tree.get(key.to_bytes())?;
-        match val {
-            Some(v) => {
-                let mut output = [0u8; 8];
-                output.copy_from_slice(&v);
-                Ok(u64::from_le_bytes(output))
-            }
-            None => Ok(0u64),
-        }
+        Ok(val.map(|v| {
+            let mut output = [0u8; 8];
+            output.copy_from_slice(&v);
+            u64::from_be_bytes(output)
+        }))
     }
 
     #[tracing::instrument(skip(self))]
-    fn insert_leaves_and_last_deposit_block_number<
-        K: Into<HistoryStoreKey> + Debug,
-    >(
+    fn set_handler_checkpoint<K: Into<HistoryStoreKey> + Debug>(
         &self,
         key: K,
-        leaves: &[(u32, Vec<u8>)],
-        block_number: u64,
+        handler: &str,
+        (block_number, log_index): (u64, u64),
     ) -> crate::Result<()> {
+        let tree = self.db.open_tree("handler_checkpoints")?;
         let key: HistoryStoreKey = key.into();
-
-        let leaf_tree = self.db.open_tree(format!(
-            "leaves/{}/{}",
-            key.chain_id(),
-            key.address()
-        ))?;
-        // This is last deposit event block number
-        let set_block_tree1 = self.db.open_tree("last_deposit_block_number")?;
-        // This will be used by event watcher to track the block number has been processed
-        let set_block_tree2 = self.db.open_tree("last_block_numbers")?;
-        let block_number_bytes = block_number.to_le_bytes();
-
-        (&leaf_tree, &set_block_tree1, &set_block_tree2).transaction(
-            |(leaf_tree, set_block_tree1, set_block_tree2)| {
-                for (k, v) in leaves {
-                    leaf_tree.insert(&k.to_le_bytes(), v.as_slice())?;
-                }
-                set_block_tree1.insert(key.to_bytes(), &block_number_bytes)?;
-                set_block_tree2.insert(key.to_bytes(), &block_number_bytes)?;
-                Ok(())
-            },
-        )?;
+        // key ++ handler, so the checkpoints of a contract share a prefix.
+        let mut checkpoint_key = key.to_bytes();
+        checkpoint_key.extend_from_slice(handler.as_bytes());
+        let mut value = [0u8; 16];
+        value[..8].copy_from_slice(&block_number.to_be_bytes());
+        value[8..].copy_from_slice(&log_index.to_be_bytes());
+        tree.insert(checkpoint_key, &value)?;
         Ok(())
     }
-}
-
-impl EncryptedOutputCacheStore for SledStore {
-    type Output = Vec<String>;
 
     #[tracing::instrument(skip(self))]
-    fn get_encrypted_output<K: Into<HistoryStoreKey> + Debug>(
+    fn get_handler_checkpoint<K: Into<HistoryStoreKey> + Debug>(
         &self,
         key: K,
-    ) -> crate::Result<Self::Output> {
+        handler: &str,
+    ) -> crate::Result<Option<(u64, u64)>> {
+        let tree = self.db.open_tree("handler_checkpoints")?;
         let key: HistoryStoreKey = key.into();
-        let tree = self.db.open_tree(format!(
-            "encrypted_outputs/{}/{}",
-            key.chain_id(),
-            key.address()
-        ))?;
-        let encrypted_outputs: Vec<_> = tree
-            .iter()
-            .values()
-            .flatten()
-            .map(|v| hex::encode(&v))
-            .collect();
-        Ok(encrypted_outputs)
+        let mut checkpoint_key = key.to_bytes();
+        checkpoint_key.extend_from_slice(handler.as_bytes());
+        let val = tree.get(checkpoint_key)?;
+        Ok(val.map(|v| {
+            let mut block_number = [0u8; 8];
+            let mut log_index = [0u8; 8];
+            block_number.copy_from_slice(&v[..8]);
+            log_index.copy_from_slice(&v[8..]);
+            (
+                u64::from_be_bytes(block_number),
+                u64::from_be_bytes(log_index),
+            )
+        }))
     }
 
     #[tracing::instrument(skip(self))]
-    fn get_encrypted_output_with_range<K: Into<HistoryStoreKey> + Debug>(
+    fn clear_handler_checkpoints<K: Into<HistoryStoreKey> + Debug>(
         &self,
         key: K,
-        range: core::ops::Range<u32>,
-    ) -> crate::Result<Self::Output> {
+    ) -> crate::Result<()> {
+        let tree = self.db.open_tree("handler_checkpoints")?;
         let key: HistoryStoreKey = key.into();
-        let tree = self.db.open_tree(format!(
-            "encrypted_outputs/{}/{}",
-            key.chain_id(),
-            key.address()
-        ))?;
-        let range_start = range.start.to_le_bytes();
-        let range_end = range.end.to_le_bytes();
-        let encrypted_outputs: Vec<_> = tree
-            .range(range_start..range_end)
-            .values()
-            .flatten()
-            .map(|v| hex::encode(&v))
+        for checkpoint_key in tree.scan_prefix(key.to_bytes()).keys() {
+            tree.remove(checkpoint_key?)?;
+        }
+        Ok(())
+    }
+}
+
+impl LeafCacheStore for SledStore {
+    type Output = BTreeMap<u32, types::H256>;
+
+    #[tracing::instrument(skip(self))]
+    fn clear_leaves_cache<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<()> {
+        let key: HistoryStoreKey = key.into();
+        self.drop_keyed_tree(KeyedTree::Leaves, key)?;
+        self.leaves_cache.invalidate(&key);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_leaves<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Self::Output> {
+        let leaves = self.cached_leaves(key.into())?;
+        Ok(leaves.iter().copied().collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_leaves_with_range<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        range: core::ops::Range<u32>,
+    ) -> crate::Result<Self::Output> {
+        let leaves = self.cached_leaves(key.into())?;
+        let start = leaves.partition_point(|(i, _)| *i < range.start);
+        let end = leaves.partition_point(|(i, _)| *i < range.end);
+        Ok(leaves[start..end.max(start)].iter().copied().collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_last_deposit_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<u64> {
+        let block_number =
+            self.get_block_number(BlockNumberTree::LastDeposit, key.into())?;
+        Ok(block_number.unwrap_or_default())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn insert_leaves_and_last_deposit_block_number<
+        K: Into<HistoryStoreKey> + Debug,
+    >(
+        &self,
+        key: K,
+        leaves: &[(u32, Vec<u8>)],
+        block_number: u64,
+    ) -> crate::Result<()> {
+        let key: HistoryStoreKey = key.into();
+
+        let leaf_tree = self.keyed_tree(KeyedTree::Leaves, key)?;
+        // This is last deposit event block number
+        let set_block_tree1 = self.db.open_tree("last_deposit_block_number")?;
+        // This will be used by event watcher to track the block number has been processed
+        let set_block_tree2 = self.db.open_tree("last_block_numbers")?;
+        let block_number_bytes = block_number.to_le_bytes();
+        let leaves: Vec<_> = leaves
+            .iter()
+            .map(|(k, v)| (k.to_le_bytes(), self.seal(v.clone())))
             .collect();
-        Ok(encrypted_outputs)
+
+        (&leaf_tree, &set_block_tree1, &set_block_tree2).transaction(
+            |(leaf_tree, set_block_tree1, set_block_tree2)| {
+                for (k, v) in &leaves {
+                    leaf_tree.insert(k, v.as_slice())?;
+                }
+                set_block_tree1.insert(key.to_bytes(), &block_number_bytes)?;
+                set_block_tree2.insert(key.to_bytes(), &block_number_bytes)?;
+                Ok(())
+            },
+        )?;
+        self.invalidate_leaves(key);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, batch), fields(len = batch.len()))]
+    fn insert_leaves_batch<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        batch: &LeavesBatch,
+    ) -> crate::Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let key: HistoryStoreKey = key.into();
+
+        let leaf_tree = self.keyed_tree(KeyedTree::Leaves, key)?;
+        let set_block_tree1 = self.db.open_tree("last_deposit_block_number")?;
+        let set_block_tree2 = self.db.open_tree("last_block_numbers")?;
+        let block_number_bytes =
+            batch.last_deposit_block_number().to_le_bytes();
+        // the leaves are written as a single sled batch, instead of a write
+        // per leaf inside the transaction.
+        let mut leaves = sled::Batch::default();
+        for (k, v) in batch.leaves() {
+            leaves.insert(k.to_le_bytes().to_vec(), self.seal(v.clone()));
+        }
+
+        (&leaf_tree, &set_block_tree1, &set_block_tree2).transaction(
+            |(leaf_tree, set_block_tree1, set_block_tree2)| {
+                leaf_tree.apply_batch(&leaves)?;
+                set_block_tree1.insert(key.to_bytes(), &block_number_bytes)?;
+                set_block_tree2.insert(key.to_bytes(), &block_number_bytes)?;
+                Ok(())
+            },
+        )?;
+        self.invalidate_leaves(key);
+        Ok(())
+    }
+}
+
+impl EncryptedOutputCacheStore for SledStore {
+    type Output = Vec<String>;
+
+    #[tracing::instrument(skip(self))]
+    fn clear_encrypted_output_cache<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<()> {
+        let key: HistoryStoreKey = key.into();
+        self.drop_keyed_tree(KeyedTree::EncryptedOutputs, key)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Self::Output> {
+        let key: HistoryStoreKey = key.into();
+        let tree = self.keyed_tree(KeyedTree::EncryptedOutputs, key)?;
+        let encrypted_outputs =
+            indexed_encrypted_outputs(&tree, |_| true).into_values();
+        Ok(encrypted_outputs.collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output_with_range<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        range: core::ops::Range<u32>,
+    ) -> crate::Result<Self::Output> {
+        let key: HistoryStoreKey = key.into();
+        let tree = self.keyed_tree(KeyedTree::EncryptedOutputs, key)?;
+        // the indices are stored little endian, so the order of the keys is
+        // not the order of the indices and the range can not be scanned.
+        let encrypted_outputs =
+            indexed_encrypted_outputs(&tree, |i| range.contains(&i))
+                .into_values();
+        Ok(encrypted_outputs.collect())
     }
 
     fn get_last_deposit_block_number_for_encrypted_output<
@@ -347,11 +1083,8 @@ impl EncryptedOutputCacheStore for SledStore {
     ) -> crate::Result<()> {
         let key: HistoryStoreKey = key.into();
 
-        let encrypted_output_tree = self.db.open_tree(format!(
-            "encrypted_outputs/{}/{}",
-            key.chain_id(),
-            key.address()
-        ))?;
+        let encrypted_output_tree =
+            self.keyed_tree(KeyedTree::EncryptedOutputs, key)?;
         let set_block_tree = self
             .db
             .open_tree("encrypted_output_last_deposit_block_number")?;
@@ -370,27 +1103,312 @@ impl EncryptedOutputCacheStore for SledStore {
     }
 }
 
+/// Reads the hex encoded encrypted outputs of the tree whose index matches
+/// the filter, ordered by their index.
+fn indexed_encrypted_outputs(
+    tree: &sled::Tree,
+    filter: impl Fn(u32) -> bool,
+) -> BTreeMap<u32, String> {
+    tree.iter()
+        .flatten()
+        .filter_map(|(k, v)| {
+            let index_bytes = k.get(0..4)?.try_into().ok()?;
+            let index = u32::from_le_bytes(index_bytes);
+            filter(index).then(|| (index, hex::encode(&v)))
+        })
+        .collect()
+}
+
 impl EventHashStore for SledStore {
-    fn store_event(&self, event: &[u8]) -> crate::Result<()> {
+    #[tracing::instrument(skip(self, event))]
+    fn store_event<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        event: &[u8],
+        block_number: u64,
+    ) -> crate::Result<()> {
+        let key: HistoryStoreKey = key.into();
         let tree = self.db.open_tree("event_hashes")?;
+        let by_block_tree = self.event_hashes_by_block_tree(&key)?;
         let hash = ethers::utils::keccak256(event);
-        tree.insert(hash, &[])?;
+        // the value is the history store key followed by the block number,
+        // so we can find the index entry again when deleting the event.
+        let mut value = key.to_bytes();
+        value.extend_from_slice(&block_number.to_be_bytes());
+        let mut index_key = block_number.to_be_bytes().to_vec();
+        index_key.extend_from_slice(&hash);
+        // also index the event by the time it was stored, for the data
+        // retention policy, if it is enabled. the time is appended to the
+        // value so the index entry is found again too.
+        let by_time_tree = self.db.open_tree("event_hashes_by_time")?;
+        let stored_at = crate::unix_timestamp().to_be_bytes();
+        let mut time_key = stored_at.to_vec();
+        time_key.extend_from_slice(&hash);
+        if self.event_retention {
+            value.extend_from_slice(&stored_at);
+        }
+        // hold the filter lock while writing, so the filter counts the
+        // hashes in the tree exactly once.
+        let mut filter = self.event_filter.write();
+        let stored_before = (&tree, &by_block_tree, &by_time_tree)
+            .transaction(|(tree, by_block_tree, by_time_tree)| {
+                let previous =
+                    tree.insert(hash.as_slice(), value.as_slice())?;
+                // an event stored again is indexed under its new time only.
+                if let Some(previous_key) = previous
+                    .as_deref()
+                    .and_then(|previous| event_time_key(&hash, previous))
+                {
+                    by_time_tree.remove(previous_key)?;
+                }
+                by_block_tree.insert(index_key.as_slice(), &[])?;
+                if self.event_retention {
+                    by_time_tree.insert(time_key.as_slice(), &[])?;
+                }
+                Ok(previous.is_some())
+            })?;
+        // an event stored again is already counted by the filter.
+        if !stored_before {
+            filter.insert(&hash);
+        }
         Ok(())
     }
 
     fn contains_event(&self, event: &[u8]) -> crate::Result<bool> {
-        let tree = self.db.open_tree("event_hashes")?;
         let hash = ethers::utils::keccak256(event);
+        if !self.event_filter.read().contains(&hash) {
+            return Ok(false);
+        }
+        let tree = self.db.open_tree("event_hashes")?;
         let exists = tree.contains_key(hash)?;
         Ok(exists)
     }
 
     fn delete_event(&self, event: &[u8]) -> crate::Result<()> {
-        let tree = self.db.open_tree("event_hashes")?;
         let hash = ethers::utils::keccak256(event);
-        tree.remove(hash)?;
+        self.remove_event_hash(&hash)?;
         Ok(())
     }
+
+    #[tracing::instrument(skip(self))]
+    fn prune_events<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block_number: u64,
+    ) -> crate::Result<usize> {
+        let key: HistoryStoreKey = key.into();
+        let tree = self.db.open_tree("event_hashes")?;
+        let by_block_tree = self.event_hashes_by_block_tree(&key)?;
+        let mut pruned = 0;
+        for index_key in by_block_tree
+            .range(..block_number.to_be_bytes().as_slice())
+            .keys()
+        {
+            let index_key = index_key?;
+            let Ok(hash) = <[u8; 32]>::try_from(&index_key[8..]) else {
+                continue;
+            };
+            if let Some(value) = self.remove_event_hash_entry(&tree, &hash)? {
+                self.remove_event_time_index(&hash, &value)?;
+            }
+            by_block_tree.remove(&index_key)?;
+            pruned += 1;
+        }
+        if pruned > 0 {
+            tracing::debug!(pruned, "pruned old event hashes");
+        }
+        Ok(pruned)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn purge_events_before(&self, stored_before: u64) -> crate::Result<usize> {
+        let by_time_tree = self.db.open_tree("event_hashes_by_time")?;
+        let mut purged = 0;
+        for time_key in by_time_tree
+            .range(..stored_before.to_be_bytes().as_slice())
+            .keys()
+        {
+            let time_key = time_key?;
+            let Ok(hash) = <[u8; 32]>::try_from(&time_key[8..]) else {
+                continue;
+            };
+            // the event may already be gone (deleted or pruned), or stored
+            // again since, in which case only this time index entry is left
+            // to drop.
+            let stored = self.db.open_tree("event_hashes")?.get(hash)?;
+            let expired = stored.map_or(false, |value| {
+                event_time_key(&hash, &value)
+                    .map_or(true, |key| key == time_key.as_ref())
+            });
+            if expired && self.remove_event_hash(&hash)? {
+                purged += 1;
+            }
+            by_time_tree.remove(&time_key)?;
+        }
+        if purged > 0 {
+            tracing::debug!(purged, "purged expired event hashes");
+        }
+        Ok(purged)
+    }
+}
+
+/// The key marking a queue tree whose items are counted, see
+/// [`SledStore::queue_tree`].
+const QUEUE_COUNTED_KEY: &[u8] = b"counted";
+/// The prefix of the counter of the pending items of a resource.
+const PENDING_OF_PREFIX: &[u8] = b"pending_of";
+/// The prefix of the counters of the items of the resources still waiting
+/// in a queue, per priority class, in total and per resource.
+const WAITING_OF_PREFIX: &[u8] = b"waiting_of";
+/// The priority classes of the items, in order.
+const QUEUE_ITEM_PRIORITIES: [QueueItemPriority; 2] =
+    [QueueItemPriority::Low, QueueItemPriority::Normal];
+
+/// The counter of the items of a resource that are still pending (see
+/// [`QueueItemState::is_pending`]).
+fn pending_of_counter(resource_id: types::H256) -> Vec<u8> {
+    [PENDING_OF_PREFIX, resource_id.as_bytes()].concat()
+}
+
+/// The counter of the items of the given priority class still waiting to
+/// be processed, of the given resource or of all of them.
+fn waiting_of_counter(
+    priority: QueueItemPriority,
+    resource_id: Option<types::H256>,
+) -> Vec<u8> {
+    let mut counter = [WAITING_OF_PREFIX, &[priority as u8]].concat();
+    if let Some(resource_id) = resource_id {
+        counter.extend_from_slice(resource_id.as_bytes());
+    }
+    counter
+}
+
+/// The counters of the queue the item is counted in.
+///
+/// Only the items of a resource are counted.
+fn queue_counters<T>(item: &QueueItem<T>) -> Vec<Vec<u8>> {
+    let Some(resource_id) = item.resource_id() else {
+        return Vec::new();
+    };
+    let state = item.state();
+    let mut counters = Vec::new();
+    if state.is_pending() {
+        counters.push(pending_of_counter(resource_id));
+    }
+    if state == QueueItemState::Pending {
+        counters.push(waiting_of_counter(item.priority(), None));
+        counters.push(waiting_of_counter(item.priority(), Some(resource_id)));
+    }
+    counters
+}
+
+/// Reads the value of a counter of a queue.
+fn counter_value(value: Option<sled::IVec>) -> u64 {
+    value.map_or(0, |v| {
+        let mut output = [0u8; 8];
+        output.copy_from_slice(&v);
+        u64::from_be_bytes(output)
+    })
+}
+
+/// Adds `delta` to each of the given counters of a queue, dropping the
+/// counters that reach zero.
+fn add_to_counters(
+    db: &sled::transaction::TransactionalTree,
+    counters: &[Vec<u8>],
+    delta: i64,
+) -> sled::transaction::ConflictableTransactionResult<(), std::io::Error> {
+    for counter in counters {
+        let count =
+            counter_value(db.get(counter)?).saturating_add_signed(delta);
+        if count == 0 {
+            db.remove(counter.as_slice())?;
+        } else {
+            db.insert(counter.as_slice(), &count.to_be_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the queue has room for items of the given resources, without
+/// any of them going over `max_pending` pending items.
+fn has_room_for(
+    db: &sled::transaction::TransactionalTree,
+    resource_ids: impl IntoIterator<Item = types::H256>,
+    max_pending: usize,
+) -> sled::transaction::ConflictableTransactionResult<bool, std::io::Error> {
+    let mut added = BTreeMap::<types::H256, u64>::new();
+    for resource_id in resource_ids {
+        *added.entry(resource_id).or_default() += 1;
+    }
+    for (resource_id, added) in added {
+        let pending = counter_value(db.get(pending_of_counter(resource_id))?);
+        if pending + added > max_pending as u64 {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// The resource the item takes a pending slot of, if any.
+fn pending_resource_of<T>(item: &QueueItem<T>) -> Option<types::H256> {
+    item.resource_id().filter(|_| item.state().is_pending())
+}
+
+/// Appends the encoded item to the end of the queue tree, indexes it under
+/// its item key, if it has one, and adds it to the given counters of the
+/// queue (see [`queue_counters`]).
+fn enqueue_in(
+    db: &sled::transaction::TransactionalTree,
+    key: Option<[u8; 64]>,
+    item_bytes: &[u8],
+    counters: &[Vec<u8>],
+) -> sled::transaction::ConflictableTransactionResult<(), std::io::Error> {
+    // get the last id of the queue.
+    let last_item_idx = match db.get("last_item_idx")? {
+        Some(v) => {
+            let mut output = [0u8; 8];
+            output.copy_from_slice(&v);
+            u64::from_be_bytes(output)
+        }
+        None => 0u64,
+    };
+    // increment it.
+    let next_idx = last_item_idx + 1u64;
+    let idx_bytes = next_idx.to_be_bytes();
+    // then save it.
+    db.insert("last_item_idx", &idx_bytes)?;
+    db.insert("key_prefix", "item")?;
+    // we create a item key like so
+    // tx_key = 4 bytes prefix ("item") + 8 bytes of the index.
+    let mut item_key = [0u8; 4 + std::mem::size_of::<u64>()];
+    let prefix = db.get("key_prefix")?.unwrap_or_else(|| b"item".into());
+    item_key[0..4].copy_from_slice(&prefix);
+    item_key[4..].copy_from_slice(&idx_bytes);
+    // then we save it.
+    db.insert(&item_key, item_bytes)?;
+    if let Some(k) = key {
+        // also save the key where we can find it by special key.
+        db.insert(&k[..], &item_key)?;
+    }
+    add_to_counters(db, counters, 1)
+}
+
+/// The length of the value of an event hash: the history store key
+/// followed by the block number. The time it was stored follows, if it is
+/// indexed by time.
+const EVENT_HASH_VALUE_LEN: usize = 32 + 8;
+
+/// The key of the time index entry of an event hash, from its value, if it
+/// is indexed by time.
+fn event_time_key(hash: &[u8], value: &[u8]) -> Option<Vec<u8>> {
+    let stored_at = value.get(EVENT_HASH_VALUE_LEN..)?;
+    if stored_at.len() != 8 {
+        return None;
+    }
+    let mut time_key = stored_at.to_vec();
+    time_key.extend_from_slice(hash);
+    Some(time_key)
 }
 
 /// SledQueueKey is a key for a queue in Sled.
@@ -521,40 +1539,14 @@ where
         key: Self::Key,
         item: QueueItem<T>,
     ) -> crate::Result<()> {
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
-        let item_bytes = serde_json::to_vec(&item)?;
+        let tree = self.queue_tree(&key.queue_name())?;
+        let item_bytes = self.encode_item(&item)?;
+        let counters = queue_counters(&item);
         // we do everything inside a single transaction
         // so everything happens atomically and if anything fails
         // we revert everything back to the old state.
         tree.transaction::<_, _, std::io::Error>(|db| {
-            // get the last id of the queue.
-            let last_item_idx = match db.get("last_item_idx")? {
-                Some(v) => {
-                    let mut output = [0u8; 8];
-                    output.copy_from_slice(&v);
-                    u64::from_be_bytes(output)
-                }
-                None => 0u64,
-            };
-            // increment it.
-            let next_idx = last_item_idx + 1u64;
-            let idx_bytes = next_idx.to_be_bytes();
-            // then save it.
-            db.insert("last_item_idx", &idx_bytes)?;
-            db.insert("key_prefix", "item")?;
-            // we create a item key like so
-            // tx_key = 4 bytes prefix ("item") + 8 bytes of the index.
-            let mut item_key = [0u8; 4 + std::mem::size_of::<u64>()];
-            let prefix =
-                db.get("key_prefix")?.unwrap_or_else(|| b"item".into());
-            item_key[0..4].copy_from_slice(&prefix);
-            item_key[4..].copy_from_slice(&idx_bytes);
-            // then we save it.
-            db.insert(&item_key, item_bytes.as_slice())?;
-            if let Some(k) = key.item_key() {
-                // also save the key where we can find it by special key.
-                db.insert(&k[..], &item_key)?;
-            }
+            enqueue_in(db, key.item_key(), &item_bytes, &counters)?;
             tracing::trace!("enqueue item under key = {}", key);
             Ok(())
         })?;
@@ -563,56 +1555,80 @@ where
         Ok(())
     }
 
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn enqueue_item_capped(
+        &self,
+        key: Self::Key,
+        item: QueueItem<T>,
+        max_pending: usize,
+    ) -> crate::Result<bool> {
+        let tree = self.queue_tree(&key.queue_name())?;
+        let item_bytes = self.encode_item(&item)?;
+        let counters = queue_counters(&item);
+        let resource_id = pending_resource_of(&item);
+        let enqueued = tree.transaction::<_, _, std::io::Error>(|db| {
+            if !has_room_for(db, resource_id, max_pending)? {
+                return Ok(false);
+            }
+            enqueue_in(db, key.item_key(), &item_bytes, &counters)?;
+            Ok(true)
+        })?;
+        self.db.flush()?;
+        Ok(enqueued)
+    }
+
     #[tracing::instrument(skip_all, fields(key = %key))]
     fn dequeue_item(
         &self,
         key: Self::Key,
     ) -> crate::Result<Option<QueueItem<T>>> {
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
+        let tree = self.queue_tree(&key.queue_name())?;
         // now we create a lazy iterator that will scan
         // over all saved items in the queue
         // with the specific key prefix.
         let prefix = tree.get("key_prefix")?.unwrap_or_else(|| b"item".into());
         let mut queue = tree.scan_prefix(prefix);
-        let (key, value) = match queue.next() {
-            Some(Ok(v)) => v,
+        let key = match queue.next() {
+            Some(Ok((key, _))) => key,
             _ => {
                 return Ok(None);
             }
         };
-        let item = serde_json::from_slice(&value)?;
         // now it is safe to remove it from the queue.
-        tree.remove(key)?;
+        let removed = tree.transaction::<_, _, std::io::Error>(|db| {
+            self.remove_in(db, &key)
+        })?;
+        let item = removed.map(|v| self.decode_item(&v)).transpose()?;
         // flush db
         self.db.flush()?;
-        Ok(Some(item))
+        Ok(item)
     }
 
     #[tracing::instrument(skip_all, fields(key = %key))]
     fn peek_item(&self, key: Self::Key) -> crate::Result<Option<QueueItem<T>>> {
         // this method, is similar to dequeue_tx, expect we don't
         // remove anything from the queue.
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
+        let tree = self.queue_tree(&key.queue_name())?;
         let prefix = tree.get("key_prefix")?.unwrap_or_else(|| b"item".into());
         let mut queue = tree.scan_prefix(prefix);
         let (_, value) = match queue.next() {
             Some(Ok(v)) => v,
             _ => return Ok(None),
         };
-        let item: QueueItem<T> = serde_json::from_slice(&value)?;
+        let item: QueueItem<T> = self.decode_item(&value)?;
         Ok(Some(item))
     }
 
     #[tracing::instrument(skip_all, fields(key = %key))]
     fn get_item(&self, key: Self::Key) -> crate::Result<Option<QueueItem<T>>> {
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
+        let tree = self.queue_tree(&key.queue_name())?;
         let inner_key = match key.item_key() {
             Some(k) => k,
             None => return Ok(None),
         };
         if let Some(item_key) = tree.get(&inner_key[..])? {
             if let Some(item_bytes) = tree.get(&item_key[..])? {
-                let item = serde_json::from_slice(&item_bytes)?;
+                let item = self.decode_item(&item_bytes)?;
                 self.db.flush()?;
                 return Ok(Some(item));
             }
@@ -622,7 +1638,7 @@ where
 
     #[tracing::instrument(skip_all, fields(key = %key))]
     fn has_item(&self, key: Self::Key) -> crate::Result<bool> {
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
+        let tree = self.queue_tree(&key.queue_name())?;
         if let Some(k) = key.item_key() {
             tree.contains_key(&k[..]).map_err(Into::into)
         } else {
@@ -635,16 +1651,20 @@ where
         &self,
         key: Self::Key,
     ) -> crate::Result<Option<QueueItem<T>>> {
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
+        let tree = self.queue_tree(&key.queue_name())?;
         let inner_key = match key.item_key() {
             Some(k) => k,
             None => return Ok(None),
         };
         match tree.get(&inner_key[..])? {
             Some(k) => {
-                let exists = tree.remove(&k)?;
-                tree.remove(inner_key)?;
-                let item = exists.and_then(|v| serde_json::from_slice(&v).ok());
+                let exists =
+                    tree.transaction::<_, _, std::io::Error>(|db| {
+                        let exists = self.remove_in(db, &k)?;
+                        db.remove(&inner_key[..])?;
+                        Ok(exists)
+                    })?;
+                let item = exists.and_then(|v| self.decode_item(&v).ok());
                 tracing::trace!("removed item from the queue..");
                 self.db.flush()?;
                 Ok(item)
@@ -662,19 +1682,31 @@ where
     where
         F: FnOnce(&mut QueueItem<T>) -> crate::Result<()>,
     {
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
+        let tree = self.queue_tree(&key.queue_name())?;
         let inner_key = match key.item_key() {
             Some(k) => k,
             None => return Ok(false),
         };
         if let Some(item_key) = tree.get(&inner_key[..])? {
             if let Some(item_bytes) = tree.get(&item_key[..])? {
-                let mut item = serde_json::from_slice(&item_bytes)?;
+                let mut item: QueueItem<T> = self.decode_item(&item_bytes)?;
                 f(&mut item)?;
-                let updated_item_bytes = serde_json::to_vec(&item)?;
-                tree.insert(item_key, updated_item_bytes)?;
+                let updated_item_bytes = self.encode_item(&item)?;
+                let counters = queue_counters(&item);
+                let updated =
+                    tree.transaction::<_, _, std::io::Error>(|db| {
+                        if self.remove_in(db, &item_key)?.is_none() {
+                            return Ok(false);
+                        }
+                        db.insert(
+                            &item_key[..],
+                            updated_item_bytes.as_slice(),
+                        )?;
+                        add_to_counters(db, &counters, 1)?;
+                        Ok(true)
+                    })?;
                 self.db.flush()?;
-                return Ok(true);
+                return Ok(updated);
             }
         }
 
@@ -686,25 +1718,131 @@ where
     where
         F: FnOnce(&mut QueueItem<T>) -> crate::Result<()>,
     {
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
+        let tree = self.queue_tree(&key.queue_name())?;
         let inner_key = match key.item_key() {
             Some(k) => k,
             None => return Ok(false),
         };
         if let Some(item_key) = tree.get(&inner_key[..])? {
-            if let Some(item_bytes) = tree.remove(&item_key)? {
-                tree.remove(&item_key[..])?;
-                tracing::trace!("removed item from the queue..");
-                let mut item: QueueItem<T> =
-                    serde_json::from_slice(&item_bytes)?;
+            if let Some(item_bytes) = tree.get(&item_key[..])? {
+                // a rejected update leaves the item where it is.
+                let mut item: QueueItem<T> = self.decode_item(&item_bytes)?;
                 f(&mut item)?;
-                self.enqueue_item(key, item)?;
+                let updated_item_bytes = self.encode_item(&item)?;
+                let counters = queue_counters(&item);
+                // the item is moved in a single transaction, so it is
+                // never missing from the queue nor counted twice.
+                let shifted =
+                    tree.transaction::<_, _, std::io::Error>(|db| {
+                        if self.remove_in(db, &item_key)?.is_none() {
+                            return Ok(false);
+                        }
+                        tracing::trace!("removed item from the queue..");
+                        enqueue_in(
+                            db,
+                            Some(inner_key),
+                            &updated_item_bytes,
+                            &counters,
+                        )?;
+                        Ok(true)
+                    })?;
                 self.db.flush()?;
+                return Ok(shifted);
+            }
+        }
+        Ok(false)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn has_pending_items_above(
+        &self,
+        key: Self::Key,
+        priority: QueueItemPriority,
+    ) -> crate::Result<bool> {
+        let tree = self.queue_tree(&key.queue_name())?;
+        let prefix = tree.get("key_prefix")?.unwrap_or_else(|| b"item".into());
+        for entry in tree.scan_prefix(prefix) {
+            let (_, value) = entry?;
+            let item: QueueItem<T> = self.decode_item(&value)?;
+            if item.priority() > priority
+                && item.state() == QueueItemState::Pending
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn has_pending_items_besides(
+        &self,
+        key: Self::Key,
+        resource_id: Option<types::H256>,
+        priority: QueueItemPriority,
+    ) -> crate::Result<bool> {
+        let tree = self.queue_tree(&key.queue_name())?;
+        // the items of the other resources are the ones of all the
+        // resources, but the given one.
+        let classes =
+            QUEUE_ITEM_PRIORITIES.into_iter().filter(|p| *p >= priority);
+        for class in classes {
+            let waiting = tree.get(waiting_of_counter(class, None))?;
+            let waiting_of = match resource_id {
+                Some(_) => tree.get(waiting_of_counter(class, resource_id))?,
+                None => None,
+            };
+            if counter_value(waiting) > counter_value(waiting_of) {
                 return Ok(true);
             }
         }
         Ok(false)
     }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn count_pending_items_of(
+        &self,
+        key: Self::Key,
+        resource_id: types::H256,
+    ) -> crate::Result<usize> {
+        let tree = self.queue_tree(&key.queue_name())?;
+        let count = counter_value(tree.get(pending_of_counter(resource_id))?);
+        Ok(count as usize)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn queue_stats(
+        &self,
+        key: Self::Key,
+        since: u128,
+    ) -> crate::Result<QueueStats> {
+        let sampled_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_millis();
+        let mut stats = QueueStats {
+            sampled_at,
+            ..Default::default()
+        };
+        let tree = self.queue_tree(&key.queue_name())?;
+        let prefix = tree.get("key_prefix")?.unwrap_or_else(|| b"item".into());
+        for entry in tree.scan_prefix(prefix) {
+            let (_, value) = entry?;
+            let item: QueueItem<T> = self.decode_item(&value)?;
+            let enqueued_at = item.enqueued_at();
+            if enqueued_at > since && enqueued_at <= sampled_at {
+                stats.enqueued_since += 1;
+            }
+            if item.state().is_pending() {
+                stats.pending += 1;
+                stats.oldest_pending_enqueued_at = Some(
+                    stats
+                        .oldest_pending_enqueued_at
+                        .map_or(enqueued_at, |oldest| oldest.min(enqueued_at)),
+                );
+            }
+        }
+        Ok(stats)
+    }
 }
 
 impl<T> TokenPriceCacheStore<T> for SledStore
@@ -726,80 +1864,1672 @@ where
         Ok(())
     }
 }
-#[cfg(test)]
-mod tests {
-    use crate::queue::{QueueItemState, TransactionQueueItemKey};
-
-    use super::*;
-    use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
-    use webb::evm::ethers::prelude::rand::Rng;
-    use webb::evm::ethers::types;
-    use webb::evm::ethers::types::transaction::request::TransactionRequest;
-    use webb::evm::{
-        contract::protocol_solidity::variable_anchor::v_anchor_contract::NewNullifierFilter,
-        ethers::types::U64,
-    };
-    use webb_proposals::{TargetSystem, TypedChainId};
 
-    impl SledQueueKey {
-        pub fn from_evm_tx(chain_id: u32, tx: &TypedTransaction) -> Self {
-            let key = {
-                let mut bytes = [0u8; 64];
-                let tx_hash =
-                    tx.clone().set_chain_id(U64::from(chain_id)).sighash();
-                bytes[..32].copy_from_slice(tx_hash.as_fixed_bytes());
-                bytes
+impl RelayedTxStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn insert_relayed_tx(&self, record: &RelayedTxRecord) -> crate::Result<()> {
+        let tree = self.db.open_tree("relayed_txs_by_recipient")?;
+        // recipient ++ token ++ relayed_at ++ item_key, so a prefix scan over
+        // the recipient (and token) returns the records in time order.
+        let mut key = Vec::with_capacity(20 + 20 + 8 + 64);
+        key.extend_from_slice(record.recipient.as_bytes());
+        key.extend_from_slice(record.token.as_bytes());
+        key.extend_from_slice(&record.relayed_at.to_be_bytes());
+        key.extend_from_slice(record.item_key.as_bytes());
+        let value = serde_json::to_vec(record)?;
+        tree.insert(key, value)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_relayed_txs_by_recipient(
+        &self,
+        recipient: types::Address,
+        token: Option<types::Address>,
+    ) -> crate::Result<Vec<RelayedTxRecord>> {
+        let tree = self.db.open_tree("relayed_txs_by_recipient")?;
+        let mut prefix = recipient.as_bytes().to_vec();
+        if let Some(token) = token {
+            prefix.extend_from_slice(token.as_bytes());
+        }
+        let mut records = tree
+            .scan_prefix(prefix)
+            .values()
+            .map(|v| Ok(serde_json::from_slice(&v?)?))
+            .collect::<crate::Result<Vec<RelayedTxRecord>>>()?;
+        // without a token filter, records of different tokens are interleaved.
+        records.sort_by_key(|r| r.relayed_at);
+        Ok(records)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_refunds_by_recipient_since(
+        &self,
+        chain_id: u32,
+        recipient: types::Address,
+        since: u64,
+    ) -> crate::Result<types::U256> {
+        let tree = self.db.open_tree("relayed_txs_by_recipient")?;
+        tree.scan_prefix(recipient.as_bytes()).values().try_fold(
+            types::U256::zero(),
+            |total, v| {
+                let record: RelayedTxRecord = serde_json::from_slice(&v?)?;
+                if record.chain_id == chain_id && record.relayed_at >= since {
+                    Ok(total.saturating_add(record.refund))
+                } else {
+                    Ok(total)
+                }
+            },
+        )
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn reserve_recipient_refund(
+        &self,
+        chain_id: u32,
+        recipient: types::Address,
+        item_key: types::H512,
+        refund: types::U256,
+        since: u64,
+        max_refund: types::U256,
+    ) -> crate::Result<bool> {
+        use sled::transaction::ConflictableTransactionError::Abort;
+        let tree = self.db.open_tree("recipient_refund_reservations")?;
+        let key = recipient_refund_key(chain_id, recipient);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let reserved = tree.transaction(|tree| {
+            let decode_err = |e| Abort(std::io::Error::from(e));
+            // (item key, refund, reserved at) of each reservation.
+            let mut reservations: Vec<(types::H512, types::U256, u64)> =
+                match tree.get(key.as_slice())? {
+                    Some(v) => {
+                        serde_json::from_slice(&v).map_err(decode_err)?
+                    }
+                    None => Vec::new(),
+                };
+            // the reservations out of the window are dropped on the way.
+            reservations.retain(|(_, _, reserved_at)| *reserved_at >= since);
+            if reservations.iter().any(|(key, _, _)| *key == item_key) {
+                return Ok(true);
+            }
+            let total = reservations
+                .iter()
+                .fold(types::U256::zero(), |total, (_, refund, _)| {
+                    total.saturating_add(*refund)
+                });
+            if total.saturating_add(refund) > max_refund {
+                return Ok(false);
+            }
+            reservations.push((item_key, refund, now));
+            let value =
+                serde_json::to_vec(&reservations).map_err(decode_err)?;
+            tree.insert(key.as_slice(), value)?;
+            Ok(true)
+        })?;
+        Ok(reserved)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn release_recipient_refund(
+        &self,
+        chain_id: u32,
+        recipient: types::Address,
+        item_key: types::H512,
+    ) -> crate::Result<()> {
+        use sled::transaction::ConflictableTransactionError::Abort;
+        let tree = self.db.open_tree("recipient_refund_reservations")?;
+        let key = recipient_refund_key(chain_id, recipient);
+        tree.transaction(|tree| {
+            let decode_err = |e| Abort(std::io::Error::from(e));
+            let Some(v) = tree.get(key.as_slice())? else {
+                return Ok(());
             };
-            Self::from_evm_with_custom_key(chain_id, key)
+            let mut reservations: Vec<(types::H512, types::U256, u64)> =
+                serde_json::from_slice(&v).map_err(decode_err)?;
+            reservations.retain(|(key, _, _)| *key != item_key);
+            let value =
+                serde_json::to_vec(&reservations).map_err(decode_err)?;
+            tree.insert(key.as_slice(), value)?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn purge_relayed_txs_before(&self, before: u64) -> crate::Result<usize> {
+        let tree = self.db.open_tree("relayed_txs_by_recipient")?;
+        let mut purged = 0;
+        for entry in tree.iter() {
+            let (key, value) = entry?;
+            let record: RelayedTxRecord = serde_json::from_slice(&value)?;
+            if record.relayed_at < before {
+                tree.remove(key)?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+}
+
+/// chain id ++ recipient.
+fn recipient_refund_key(chain_id: u32, recipient: types::Address) -> Vec<u8> {
+    let mut key = chain_id.to_be_bytes().to_vec();
+    key.extend_from_slice(recipient.as_bytes());
+    key
+}
+
+impl RelayJobStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn insert_relay_job(&self, job: &RelayJob) -> crate::Result<()> {
+        let tree = self.db.open_tree("relay_jobs")?;
+        let value = serde_json::to_vec(job)?;
+        tree.insert(job.job_id.as_bytes(), value)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_relay_job(
+        &self,
+        job_id: types::H256,
+    ) -> crate::Result<Option<RelayJob>> {
+        let tree = self.db.open_tree("relay_jobs")?;
+        match tree.get(job_id.as_bytes())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn purge_relay_jobs_before(&self, before: u64) -> crate::Result<usize> {
+        let tree = self.db.open_tree("relay_jobs")?;
+        let mut purged = 0;
+        for entry in tree.iter() {
+            let (key, value) = entry?;
+            let job: RelayJob = serde_json::from_slice(&value)?;
+            if job.updated_at < before {
+                tree.remove(key)?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+}
+
+/// The key of a Tangle job: chain id ++ job id.
+fn tangle_job_key(chain_id: u32, job_id: u64) -> [u8; 12] {
+    let mut key = [0u8; 12];
+    key[..4].copy_from_slice(&chain_id.to_be_bytes());
+    key[4..].copy_from_slice(&job_id.to_be_bytes());
+    key
+}
+
+impl TangleJobStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn insert_tangle_job(&self, job: &TangleJob) -> crate::Result<()> {
+        let tree = self.db.open_tree("tangle_jobs")?;
+        let value = serde_json::to_vec(job)?;
+        tree.insert(tangle_job_key(job.chain_id, job.job_id), value)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_tangle_job(
+        &self,
+        chain_id: u32,
+        job_id: u64,
+    ) -> crate::Result<Option<TangleJob>> {
+        let tree = self.db.open_tree("tangle_jobs")?;
+        match tree.get(tangle_job_key(chain_id, job_id))? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn purge_tangle_jobs_before(&self, before: u64) -> crate::Result<usize> {
+        let tree = self.db.open_tree("tangle_jobs")?;
+        let mut purged = 0;
+        for entry in tree.iter() {
+            let (key, value) = entry?;
+            let job: TangleJob = serde_json::from_slice(&value)?;
+            // the jobs stored before they had an update time are kept.
+            if job.updated_at != 0 && job.updated_at < before {
+                tree.remove(key)?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+}
+
+/// The key of a spent nullifier: resource id ++ nullifier.
+fn nullifier_key(resource_id: ResourceId, nullifier: types::H256) -> [u8; 64] {
+    let mut key = [0u8; 64];
+    key[..32].copy_from_slice(&resource_id.to_bytes());
+    key[32..].copy_from_slice(nullifier.as_bytes());
+    key
+}
+
+impl NullifierStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn insert_nullifiers(
+        &self,
+        resource_id: ResourceId,
+        nullifiers: &[types::H256],
+    ) -> crate::Result<()> {
+        let tree = self.db.open_tree("spent_nullifiers")?;
+        let mut batch = sled::Batch::default();
+        for nullifier in nullifiers {
+            let key = nullifier_key(resource_id, *nullifier);
+            batch.insert(key.as_slice(), &[]);
+        }
+        tree.apply_batch(batch)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_spent_nullifiers(
+        &self,
+        resource_id: ResourceId,
+        nullifiers: &[types::H256],
+    ) -> crate::Result<Vec<types::H256>> {
+        let tree = self.db.open_tree("spent_nullifiers")?;
+        let mut spent = Vec::new();
+        for nullifier in nullifiers {
+            if tree.contains_key(nullifier_key(resource_id, *nullifier))? {
+                spent.push(*nullifier);
+            }
+        }
+        Ok(spent)
+    }
+}
+
+impl RelayNonceStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn use_relay_nonce(
+        &self,
+        signer: types::Address,
+        nonce: u64,
+        now: u64,
+        expires_at: u64,
+    ) -> crate::Result<bool> {
+        let tree = self.db.open_tree("relay_nonces")?;
+        let mut key = signer.as_bytes().to_vec();
+        key.extend_from_slice(&nonce.to_be_bytes());
+        // the nonce is free again once expired, the timestamp of the
+        // expired envelope is too old to be relayed anyway.
+        loop {
+            let current = tree.get(&key)?;
+            if let Some(value) = &current {
+                let used_until = <[u8; 8]>::try_from(value.as_ref())
+                    .map(u64::from_be_bytes)
+                    .unwrap_or(u64::MAX);
+                if used_until > now {
+                    return Ok(false);
+                }
+            }
+            let swapped = tree.compare_and_swap(
+                &key,
+                current,
+                Some(expires_at.to_be_bytes().as_slice()),
+            )?;
+            if swapped.is_ok() {
+                return Ok(true);
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn is_relay_nonce_used(
+        &self,
+        signer: types::Address,
+        nonce: u64,
+        now: u64,
+    ) -> crate::Result<bool> {
+        let tree = self.db.open_tree("relay_nonces")?;
+        let mut key = signer.as_bytes().to_vec();
+        key.extend_from_slice(&nonce.to_be_bytes());
+        let used = tree.get(&key)?.map_or(false, |value| {
+            <[u8; 8]>::try_from(value.as_ref())
+                .map(u64::from_be_bytes)
+                .unwrap_or(u64::MAX)
+                > now
+        });
+        Ok(used)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn release_relay_nonce(
+        &self,
+        signer: types::Address,
+        nonce: u64,
+    ) -> crate::Result<()> {
+        let tree = self.db.open_tree("relay_nonces")?;
+        let mut key = signer.as_bytes().to_vec();
+        key.extend_from_slice(&nonce.to_be_bytes());
+        tree.remove(key)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn purge_relay_nonces_before(&self, before: u64) -> crate::Result<usize> {
+        let tree = self.db.open_tree("relay_nonces")?;
+        let mut purged = 0;
+        for entry in tree.iter() {
+            let (key, value) = entry?;
+            let expired = <[u8; 8]>::try_from(value.as_ref())
+                .map(u64::from_be_bytes)
+                .map_or(false, |used_until| used_until < before);
+            if expired {
+                tree.remove(key)?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+}
+
+impl FundingTransferStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn use_funding_transfer(
+        &self,
+        chain_id: u32,
+        tx_hash: types::H256,
+    ) -> crate::Result<bool> {
+        let tree = self.db.open_tree("funding_transfers")?;
+        let mut key = chain_id.to_be_bytes().to_vec();
+        key.extend_from_slice(tx_hash.as_bytes());
+        let swapped = tree.compare_and_swap(
+            key,
+            None as Option<&[u8]>,
+            Some(&[] as &[u8]),
+        )?;
+        Ok(swapped.is_ok())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn release_funding_transfer(
+        &self,
+        chain_id: u32,
+        tx_hash: types::H256,
+    ) -> crate::Result<()> {
+        let tree = self.db.open_tree("funding_transfers")?;
+        let mut key = chain_id.to_be_bytes().to_vec();
+        key.extend_from_slice(tx_hash.as_bytes());
+        tree.remove(key)?;
+        Ok(())
+    }
+}
+
+impl ForwardedRequestStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn last_forwarded_request(
+        &self,
+        chain_id: u32,
+        forwarder: types::Address,
+        sponsor: types::Address,
+    ) -> crate::Result<Option<types::H512>> {
+        let tree = self.db.open_tree("forwarded_requests")?;
+        let key = forwarded_request_key(chain_id, forwarder, sponsor);
+        Ok(tree.get(key)?.map(|v| types::H512::from_slice(&v)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn replace_forwarded_request(
+        &self,
+        chain_id: u32,
+        forwarder: types::Address,
+        sponsor: types::Address,
+        previous: Option<types::H512>,
+        item_key: types::H512,
+    ) -> crate::Result<bool> {
+        let tree = self.db.open_tree("forwarded_requests")?;
+        let key = forwarded_request_key(chain_id, forwarder, sponsor);
+        let swapped = tree.compare_and_swap(
+            key,
+            previous.as_ref().map(|v| v.as_bytes()),
+            Some(item_key.as_bytes()),
+        )?;
+        Ok(swapped.is_ok())
+    }
+}
+
+/// chain id ++ forwarder ++ sponsor.
+fn forwarded_request_key(
+    chain_id: u32,
+    forwarder: types::Address,
+    sponsor: types::Address,
+) -> Vec<u8> {
+    let mut key = chain_id.to_be_bytes().to_vec();
+    key.extend_from_slice(forwarder.as_bytes());
+    key.extend_from_slice(sponsor.as_bytes());
+    key
+}
+
+impl MerkleRootStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn insert_merkle_root(
+        &self,
+        resource_id: ResourceId,
+        source_chain_id: u64,
+        root: types::H256,
+        block_number: u64,
+    ) -> crate::Result<()> {
+        let tree = self.db.open_tree("merkle_roots")?;
+        // resource id ++ source chain id, so a prefix scan over the resource
+        // returns the roots of all the trees it knows.
+        let mut key = resource_id.to_bytes().to_vec();
+        key.extend_from_slice(&source_chain_id.to_be_bytes());
+        let mut roots: Vec<MerkleRootRecord> = match tree.get(&key)? {
+            Some(v) => serde_json::from_slice(&v)?,
+            None => Vec::new(),
+        };
+        if roots.iter().any(|r| r.root == root) {
+            return Ok(());
+        }
+        roots.push(MerkleRootRecord {
+            source_chain_id,
+            root,
+            block_number,
+        });
+        let stale = roots.len().saturating_sub(MERKLE_ROOT_HISTORY_SIZE);
+        roots.drain(..stale);
+        tree.insert(key, serde_json::to_vec(&roots)?)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_merkle_root_history(
+        &self,
+        resource_id: ResourceId,
+    ) -> crate::Result<Vec<MerkleRootRecord>> {
+        let tree = self.db.open_tree("merkle_roots")?;
+        let mut history = Vec::new();
+        for v in tree.scan_prefix(resource_id.to_bytes()).values() {
+            let roots: Vec<MerkleRootRecord> = serde_json::from_slice(&v?)?;
+            history.extend(roots);
+        }
+        Ok(history)
+    }
+}
+
+impl TxBatchStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn insert_tx_batch(
+        &self,
+        chain_id: u32,
+        batch_id: types::H256,
+        item_keys: &[types::H512],
+    ) -> crate::Result<()> {
+        let tree = self.db.open_tree("tx_batches")?;
+        let mut key = chain_id.to_be_bytes().to_vec();
+        key.extend_from_slice(batch_id.as_bytes());
+        let value = serde_json::to_vec(item_keys)?;
+        tree.insert(key, value)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(chain_id, ?batch_id))]
+    fn enqueue_tx_batch<K, T>(
+        &self,
+        chain_id: u32,
+        batch_id: types::H256,
+        items: Vec<(K, QueueItem<T>)>,
+        max_pending: Option<usize>,
+    ) -> crate::Result<Option<Vec<types::H512>>>
+    where
+        K: QueueKey,
+        T: Serialize + DeserializeOwned + Clone,
+    {
+        let Some((first_key, _)) = items.first() else {
+            return Ok(Some(Vec::new()));
+        };
+        let queue_name = first_key.queue_name();
+        let mut item_keys = Vec::with_capacity(items.len());
+        let mut encoded = Vec::with_capacity(items.len());
+        for (key, item) in &items {
+            let (true, Some(item_key)) =
+                (key.queue_name() == queue_name, key.item_key())
+            else {
+                return Err(webb_relayer_utils::Error::Generic(
+                    "the items of a batch must be keyed, on the same queue",
+                ));
+            };
+            item_keys.push(types::H512::from(item_key));
+            encoded.push((
+                item_key,
+                self.encode_item(item)?,
+                queue_counters(item),
+            ));
+        }
+        let resource_ids = items
+            .iter()
+            .filter_map(|(_, item)| pending_resource_of(item))
+            .collect::<Vec<_>>();
+        let queue_tree = self.queue_tree(&queue_name)?;
+        let batches_tree = self.db.open_tree("tx_batches")?;
+        let mut batch_key = chain_id.to_be_bytes().to_vec();
+        batch_key.extend_from_slice(batch_id.as_bytes());
+        let batch_value = serde_json::to_vec(&item_keys)?;
+        let enqueued = (&queue_tree, &batches_tree)
+            .transaction::<_, _, std::io::Error>(|(queue, batches)| {
+                if let Some(max_pending) = max_pending {
+                    let resource_ids = resource_ids.iter().copied();
+                    if !has_room_for(queue, resource_ids, max_pending)? {
+                        return Ok(false);
+                    }
+                }
+                for (item_key, item_bytes, counters) in &encoded {
+                    enqueue_in(queue, Some(*item_key), item_bytes, counters)?;
+                }
+                batches.insert(batch_key.as_slice(), batch_value.as_slice())?;
+                Ok(true)
+            })?;
+        self.db.flush()?;
+        Ok(enqueued.then_some(item_keys))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_tx_batch(
+        &self,
+        chain_id: u32,
+        batch_id: types::H256,
+    ) -> crate::Result<Option<Vec<types::H512>>> {
+        let tree = self.db.open_tree("tx_batches")?;
+        let mut key = chain_id.to_be_bytes().to_vec();
+        key.extend_from_slice(batch_id.as_bytes());
+        match tree.get(key)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl TxHashStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn insert_tx_hash(
+        &self,
+        chain_id: u32,
+        item_key: [u8; 64],
+        tx_hash: types::H256,
+    ) -> crate::Result<()> {
+        let tree = self.db.open_tree("tx_hashes")?;
+        let mut key = chain_id.to_be_bytes().to_vec();
+        key.extend_from_slice(&item_key);
+        let mut tx_hashes: Vec<types::H256> = match tree.get(&key)? {
+            Some(v) => serde_json::from_slice(&v)?,
+            None => Vec::new(),
+        };
+        if tx_hashes.contains(&tx_hash) {
+            return Ok(());
+        }
+        tx_hashes.push(tx_hash);
+        tree.insert(key, serde_json::to_vec(&tx_hashes)?)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_tx_hashes(
+        &self,
+        chain_id: u32,
+        item_key: [u8; 64],
+    ) -> crate::Result<Vec<types::H256>> {
+        let tree = self.db.open_tree("tx_hashes")?;
+        let mut key = chain_id.to_be_bytes().to_vec();
+        key.extend_from_slice(&item_key);
+        match tree.get(key)? {
+            Some(v) => Ok(serde_json::from_slice(&v)?),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+impl AccountingStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn insert_expected_fee(
+        &self,
+        item_key: [u8; 64],
+        fee: types::U256,
+    ) -> crate::Result<()> {
+        let tree = self.db.open_tree("accounting_expected_fees")?;
+        let value = serde_json::to_vec(&fee)?;
+        tree.insert(item_key, value)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn has_expected_fee(&self, item_key: [u8; 64]) -> crate::Result<bool> {
+        let tree = self.db.open_tree("accounting_expected_fees")?;
+        Ok(tree.contains_key(item_key)?)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn reserve_subsidized_tx(
+        &self,
+        chain_id: TypedChainId,
+        item_key: [u8; 64],
+        max_daily_txs: Option<u64>,
+    ) -> crate::Result<bool> {
+        use sled::transaction::ConflictableTransactionError::Abort;
+        let subsidized = self.db.open_tree("accounting_subsidized_txs")?;
+        let daily = self.db.open_tree("accounting_daily_subsidized_txs")?;
+        let day = crate::current_day();
+        let daily_key = daily_gas_key(chain_id, day);
+        let reserved =
+            (&subsidized, &daily).transaction(|(subsidized, daily)| {
+                let decode_err = |e| Abort(std::io::Error::from(e));
+                // a transaction enqueued twice is only counted once.
+                if subsidized.get(item_key.as_slice())?.is_some() {
+                    return Ok(true);
+                }
+                let count: u64 = match daily.get(daily_key.as_slice())? {
+                    Some(v) => {
+                        serde_json::from_slice(&v).map_err(decode_err)?
+                    }
+                    None => 0,
+                };
+                if max_daily_txs.map_or(false, |max| count >= max) {
+                    return Ok(false);
+                }
+                // the day is kept to release the right daily count.
+                let value = serde_json::to_vec(&day).map_err(decode_err)?;
+                subsidized.insert(item_key.as_slice(), value)?;
+                let value =
+                    serde_json::to_vec(&(count + 1)).map_err(decode_err)?;
+                daily.insert(daily_key.as_slice(), value)?;
+                Ok(true)
+            })?;
+        Ok(reserved)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn release_subsidized_tx(
+        &self,
+        chain_id: TypedChainId,
+        item_key: [u8; 64],
+    ) -> crate::Result<()> {
+        use sled::transaction::ConflictableTransactionError::Abort;
+        let subsidized = self.db.open_tree("accounting_subsidized_txs")?;
+        let daily = self.db.open_tree("accounting_daily_subsidized_txs")?;
+        (&subsidized, &daily).transaction(|(subsidized, daily)| {
+            let decode_err = |e| Abort(std::io::Error::from(e));
+            let Some(day) = subsidized.remove(item_key.as_slice())? else {
+                return Ok(());
+            };
+            let day: u64 = serde_json::from_slice(&day).map_err(decode_err)?;
+            let daily_key = daily_gas_key(chain_id, day);
+            let count: u64 = match daily.get(daily_key.as_slice())? {
+                Some(v) => serde_json::from_slice(&v).map_err(decode_err)?,
+                None => 0,
+            };
+            let value = serde_json::to_vec(&count.saturating_sub(1))
+                .map_err(decode_err)?;
+            daily.insert(daily_key.as_slice(), value)?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_daily_subsidized_txs(
+        &self,
+        chain_id: TypedChainId,
+        day: u64,
+    ) -> crate::Result<u64> {
+        let tree = self.db.open_tree("accounting_daily_subsidized_txs")?;
+        match tree.get(daily_gas_key(chain_id, day))? {
+            Some(v) => Ok(serde_json::from_slice(&v)?),
+            None => Ok(0),
         }
     }
 
+    #[tracing::instrument(skip(self))]
+    fn settle_tx(
+        &self,
+        resource_id: ResourceId,
+        item_key: [u8; 64],
+        cost: TxCost,
+        succeeded: bool,
+    ) -> crate::Result<()> {
+        use sled::transaction::ConflictableTransactionError::Abort;
+        let expected_fees = self.db.open_tree("accounting_expected_fees")?;
+        let ledger = self.db.open_tree("accounting_ledger")?;
+        let daily_gas = self.db.open_tree("accounting_daily_gas")?;
+        let subsidized_txs = self.db.open_tree("accounting_subsidized_txs")?;
+        let key = resource_id.to_bytes();
+        let daily_key =
+            daily_gas_key(resource_id.typed_chain_id(), crate::current_day());
+        let trees = (&expected_fees, &ledger, &daily_gas, &subsidized_txs);
+        trees.transaction(
+            |(expected_fees, ledger, daily_gas, subsidized_txs)| {
+                let decode_err = |e| Abort(std::io::Error::from(e));
+                let fee: types::U256 =
+                    match expected_fees.remove(item_key.as_slice())? {
+                        Some(v) => {
+                            serde_json::from_slice(&v).map_err(decode_err)?
+                        }
+                        None => types::U256::zero(),
+                    };
+                let mut pnl: ProfitAndLoss = match ledger.get(key.as_slice())? {
+                    Some(v) => {
+                        serde_json::from_slice(&v).map_err(decode_err)?
+                    }
+                    None => ProfitAndLoss::default(),
+                };
+                pnl.gas_used += cost.gas_used;
+                pnl.gas_spent += cost.gas_spent;
+                if subsidized_txs.remove(item_key.as_slice())?.is_some() {
+                    pnl.txs_subsidized += 1;
+                    pnl.gas_subsidized += cost.gas_spent;
+                }
+                if succeeded {
+                    pnl.fees_earned += fee;
+                    pnl.refunds_paid += cost.value_sent;
+                    pnl.txs_succeeded += 1;
+                } else {
+                    pnl.txs_failed += 1;
+                }
+                let value = serde_json::to_vec(&pnl).map_err(decode_err)?;
+                ledger.insert(key.as_slice(), value)?;
+                let mut spent: types::U256 =
+                    match daily_gas.get(daily_key.as_slice())? {
+                        Some(v) => {
+                            serde_json::from_slice(&v).map_err(decode_err)?
+                        }
+                        None => types::U256::zero(),
+                    };
+                spent += cost.gas_spent;
+                let value = serde_json::to_vec(&spent).map_err(decode_err)?;
+                daily_gas.insert(daily_key.as_slice(), value)?;
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_profit_and_loss(
+        &self,
+    ) -> crate::Result<Vec<(ResourceId, ProfitAndLoss)>> {
+        let tree = self.db.open_tree("accounting_ledger")?;
+        tree.iter()
+            .map(|entry| {
+                let (key, value) = entry?;
+                let mut bytes = [0u8; 32];
+                bytes.copy_from_slice(&key);
+                let resource_id = ResourceId::from(bytes);
+                Ok((resource_id, serde_json::from_slice(&value)?))
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_daily_gas_spent(
+        &self,
+        chain_id: TypedChainId,
+        day: u64,
+    ) -> crate::Result<types::U256> {
+        let tree = self.db.open_tree("accounting_daily_gas")?;
+        match tree.get(daily_gas_key(chain_id, day))? {
+            Some(v) => Ok(serde_json::from_slice(&v)?),
+            None => Ok(types::U256::zero()),
+        }
+    }
+}
+
+/// The key of the gas spent on a chain during a day.
+fn daily_gas_key(chain_id: TypedChainId, day: u64) -> [u8; 16] {
+    let mut key = [0u8; 16];
+    key[..8].copy_from_slice(&chain_id.chain_id().to_be_bytes());
+    key[8..].copy_from_slice(&day.to_be_bytes());
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::queue::TransactionQueueItemKey;
+
+    use super::*;
+    use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
+    use webb::evm::ethers::prelude::rand::Rng;
+    use webb::evm::ethers::types;
+    use webb::evm::ethers::types::transaction::request::TransactionRequest;
+    use webb::evm::{
+        contract::protocol_solidity::variable_anchor::v_anchor_contract::NewNullifierFilter,
+        ethers::types::U64,
+    };
+    use webb_proposals::{TargetSystem, TypedChainId};
+
+    fn test_key(typed_chain_id: TypedChainId) -> HistoryStoreKey {
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let target_system =
+            TargetSystem::new_contract_address(contract.to_fixed_bytes());
+        HistoryStoreKey::from((typed_chain_id, target_system))
+    }
+
+    impl SledQueueKey {
+        pub fn from_evm_tx(chain_id: u32, tx: &TypedTransaction) -> Self {
+            let key = {
+                let mut bytes = [0u8; 64];
+                let tx_hash =
+                    tx.clone().set_chain_id(U64::from(chain_id)).sighash();
+                bytes[..32].copy_from_slice(tx_hash.as_fixed_bytes());
+                bytes
+            };
+            Self::from_evm_with_custom_key(chain_id, key)
+        }
+    }
+
+    #[test]
+    fn get_last_block_number_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let block_number = 20u64;
+        let default_block_number = 1u64;
+        let history_store_key = (
+            TypedChainId::Evm(chain_id),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        store
+            .set_last_block_number(history_store_key, block_number)
+            .unwrap();
+
+        let block = store
+            .get_last_block_number(history_store_key, default_block_number);
+
+        match block {
+            Ok(b) => {
+                println!("retrieved block {block:?}");
+                assert_eq!(b, 20u64);
+            }
+            Err(e) => {
+                println!("Error encountered {e:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn deployment_block_number_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        assert_eq!(
+            store
+                .get_deployment_block_number(history_store_key)
+                .unwrap(),
+            None
+        );
+        store
+            .set_deployment_block_number(history_store_key, 42)
+            .unwrap();
+        assert_eq!(
+            store
+                .get_deployment_block_number(history_store_key)
+                .unwrap(),
+            Some(42)
+        );
+    }
+
+    #[test]
+    fn blocks_per_step_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        assert_eq!(store.get_blocks_per_step(history_store_key).unwrap(), None);
+        store.set_blocks_per_step(history_store_key, 50).unwrap();
+        store.set_blocks_per_step(history_store_key, 25).unwrap();
+        assert_eq!(
+            store.get_blocks_per_step(history_store_key).unwrap(),
+            Some(25)
+        );
+    }
+
+    #[test]
+    fn handler_checkpoints_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        let checkpoint =
+            |handler| store.get_handler_checkpoint(history_store_key, handler);
+        assert_eq!(checkpoint("leaves").unwrap(), None);
+        store
+            .set_handler_checkpoint(history_store_key, "leaves", (10, 2))
+            .unwrap();
+        store
+            .set_handler_checkpoint(history_store_key, "leaves", (12, 0))
+            .unwrap();
+        store
+            .set_handler_checkpoint(history_store_key, "deposit", (11, 1))
+            .unwrap();
+        assert_eq!(checkpoint("leaves").unwrap(), Some((12, 0)));
+        assert_eq!(checkpoint("deposit").unwrap(), Some((11, 1)));
+        store.clear_handler_checkpoints(history_store_key).unwrap();
+        assert_eq!(checkpoint("leaves").unwrap(), None);
+        assert_eq!(checkpoint("deposit").unwrap(), None);
+    }
+
+    #[test]
+    fn get_leaves_with_range_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let block_number = 20u64;
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(chain_id),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        let generated_leaves = (0..20u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        store
+            .insert_leaves_and_last_deposit_block_number(
+                history_store_key,
+                &generated_leaves,
+                block_number,
+            )
+            .unwrap();
+        let leaves = store
+            .get_leaves_with_range(history_store_key, 5..10)
+            .unwrap();
+        assert_eq!(leaves.len(), 5);
+        assert_eq!(
+            leaves
+                .values()
+                .map(|v| v.to_fixed_bytes().to_vec())
+                .collect::<Vec<_>>(),
+            generated_leaves
+                .into_iter()
+                .skip(5)
+                .take(5)
+                .map(|(_, v)| v)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn tx_queue_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        // it is now empty
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Option::<QueueItem<TypedTransaction>>::None
+        );
+
+        let tx1: TypedTransaction = TransactionRequest::pay(
+            types::Address::random(),
+            types::U256::one(),
+        )
+        .from(types::Address::random())
+        .into();
+
+        let queue_item1 = QueueItem::new(tx1.clone());
+        store
+            .enqueue_item(
+                SledQueueKey::from_evm_tx(chain_id, &tx1),
+                queue_item1.clone(),
+            )
+            .unwrap();
+        let tx2: TypedTransaction = TransactionRequest::pay(
+            types::Address::random(),
+            types::U256::one(),
+        )
+        .from(types::Address::random())
+        .into();
+        let queue_item2 = QueueItem::new(tx2.clone());
+        store
+            .enqueue_item(
+                SledQueueKey::from_evm_tx(chain_id, &tx2),
+                queue_item2.clone(),
+            )
+            .unwrap();
+
+        // now let's dequeue transactions.
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Some(queue_item1)
+        );
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Some(queue_item2)
+        );
+
+        let tx3: TypedTransaction = TransactionRequest::pay(
+            types::Address::random(),
+            types::U256::one(),
+        )
+        .from(types::Address::random())
+        .into();
+        let queue_item3 = QueueItem::new(tx3.clone());
+        store
+            .enqueue_item(
+                SledQueueKey::from_evm_tx(chain_id, &tx3),
+                queue_item3.clone(),
+            )
+            .unwrap();
+        assert_eq!(
+            store
+                .peek_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Some(queue_item3)
+        );
+        assert!(QueueStore::<TypedTransaction>::has_item(
+            &store,
+            SledQueueKey::from_evm_tx(chain_id, &tx3)
+        )
+        .unwrap());
+        let _: Option<QueueItem<TypedTransaction>> = store
+            .remove_item(SledQueueKey::from_evm_tx(chain_id, &tx3))
+            .unwrap();
+        assert!(!QueueStore::<TypedTransaction>::has_item(
+            &store,
+            SledQueueKey::from_evm_tx(chain_id, &tx3)
+        )
+        .unwrap());
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Option::<QueueItem<TypedTransaction>>::None
+        );
+    }
+
+    #[test]
+    fn queue_items_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        assert!(store.queue_names().is_empty());
+        assert!(store.queue_items("evm_tx_1").unwrap().is_empty());
+
+        let txs = (0..3)
+            .map(|_| {
+                TypedTransaction::from(
+                    TransactionRequest::pay(
+                        types::Address::random(),
+                        types::U256::one(),
+                    )
+                    .from(types::Address::random()),
+                )
+            })
+            .collect::<Vec<_>>();
+        for tx in &txs {
+            store
+                .enqueue_item(
+                    SledQueueKey::from_evm_tx(chain_id, tx),
+                    QueueItem::new(tx.clone()),
+                )
+                .unwrap();
+        }
+        assert_eq!(store.queue_names(), vec!["evm_tx_1".to_string()]);
+        // items are listed in queue order and left in the queue.
+        let items = store.queue_items("evm_tx_1").unwrap();
+        assert_eq!(items.len(), 3);
+        for (item, tx) in items.iter().zip(&txs) {
+            assert_eq!(item.clone().inner(), serde_json::to_value(tx).unwrap());
+        }
+        assert_eq!(store.queue_items("evm_tx_1").unwrap(), items);
+    }
+
+    #[test]
+    fn purge_and_requeue_items_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let txs = (0..3)
+            .map(|_| {
+                TypedTransaction::from(
+                    TransactionRequest::pay(
+                        types::Address::random(),
+                        types::U256::one(),
+                    )
+                    .from(types::Address::random()),
+                )
+            })
+            .collect::<Vec<_>>();
+        // the first item is pending, the others have failed.
+        for (i, tx) in txs.iter().enumerate() {
+            let mut item = QueueItem::new(tx.clone());
+            if i > 0 {
+                item.set_state(QueueItemState::Failed {
+                    reason: "nonce too low".to_string(),
+                });
+            }
+            store
+                .enqueue_item(SledQueueKey::from_evm_tx(chain_id, tx), item)
+                .unwrap();
+        }
+
+        // the requeued item is pending again, at the end of the queue.
+        let queue_name = store.requeue_item(txs[1].item_key()).unwrap();
+        assert_eq!(queue_name.as_deref(), Some("evm_tx_1"));
+        let items = store.queue_items("evm_tx_1").unwrap();
+        assert_eq!(items[2].state(), QueueItemState::Pending);
+        assert_eq!(
+            items[2].clone().inner(),
+            serde_json::to_value(&txs[1]).unwrap()
+        );
+        assert_eq!(store.requeue_item([0u8; 64]).unwrap(), None);
+
+        // only the remaining failed item is purged.
+        let purged = store
+            .purge_queue_items("evm_tx_1", |state| {
+                matches!(state, QueueItemState::Failed { .. })
+            })
+            .unwrap();
+        assert_eq!(purged, 1);
+        assert!(!QueueStore::<TypedTransaction>::has_item(
+            &store,
+            SledQueueKey::from_evm_tx(chain_id, &txs[2])
+        )
+        .unwrap());
+        assert_eq!(store.queue_items("evm_tx_1").unwrap().len(), 2);
+        assert_eq!(store.purge_queue_items("evm_tx_1", |_| true).unwrap(), 2);
+        assert!(store.queue_items("evm_tx_1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn events_hash_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let key = test_key(TypedChainId::Evm(1));
+
+        let events = (0..20)
+            .map(|_| NewNullifierFilter {
+                nullifier: types::H256::random().to_fixed_bytes().into(),
+            })
+            .collect::<Vec<_>>();
+
+        for (i, event) in events.iter().enumerate() {
+            let event_bytes = serde_json::to_vec(&event).unwrap();
+            // check if the event is already in the store
+            assert!(!store.contains_event(&event_bytes).unwrap());
+            // add the event
+            store.store_event(key, &event_bytes, i as u64).unwrap();
+        }
+
+        for event in &events {
+            let event_bytes = serde_json::to_vec(&event).unwrap();
+            // check if the event is in the store
+            assert!(store.contains_event(&event_bytes).unwrap());
+            // remove the event
+            store.delete_event(&event_bytes).unwrap();
+            assert!(!store.contains_event(&event_bytes).unwrap());
+        }
+        // nothing left to prune.
+        assert_eq!(store.prune_events(key, 20).unwrap(), 0);
+    }
+
+    #[test]
+    fn prune_events_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let key = test_key(TypedChainId::Evm(1));
+        let other_key = test_key(TypedChainId::Evm(2));
+
+        let events = (0..10u64)
+            .map(|i| {
+                let event = NewNullifierFilter {
+                    nullifier: types::H256::random().to_fixed_bytes().into(),
+                };
+                (i, serde_json::to_vec(&event).unwrap())
+            })
+            .collect::<Vec<_>>();
+        for (block_number, event_bytes) in &events {
+            store.store_event(key, event_bytes, *block_number).unwrap();
+            store
+                .store_event(other_key, &block_number.to_le_bytes(), 0)
+                .unwrap();
+        }
+
+        // prune everything before block 5.
+        assert_eq!(store.prune_events(key, 5).unwrap(), 5);
+        for (block_number, event_bytes) in &events {
+            assert_eq!(
+                store.contains_event(event_bytes).unwrap(),
+                *block_number >= 5
+            );
+            // other keys are left untouched.
+            assert!(store.contains_event(&block_number.to_le_bytes()).unwrap());
+        }
+        // the pruned events are removed from the filter too.
+        for (block_number, event_bytes) in &events {
+            let hash = ethers::utils::keccak256(event_bytes);
+            assert_eq!(
+                store.event_filter.read().contains(&hash),
+                *block_number >= 5
+            );
+        }
+        // pruning is idempotent.
+        assert_eq!(store.prune_events(key, 5).unwrap(), 0);
+
+        // the filter is rebuilt from disk when the store is reopened.
+        drop(store);
+        let store = SledStore::open(tmp.path()).unwrap();
+        for (block_number, event_bytes) in &events {
+            assert_eq!(
+                store.contains_event(event_bytes).unwrap(),
+                *block_number >= 5
+            );
+        }
+    }
+
+    #[test]
+    fn relayed_txs_by_recipient_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let recipient = types::Address::random();
+        let tokens = [types::Address::random(), types::Address::random()];
+        let records = (0..4u64)
+            .map(|i| RelayedTxRecord {
+                chain_id: 1,
+                contract: types::Address::random(),
+                recipient,
+                token: tokens[i as usize % 2],
+                item_key: types::H512::random(),
+                fee: types::U256::from(i),
+                refund: types::U256::zero(),
+                relayed_at: 100 - i,
+            })
+            .collect::<Vec<_>>();
+        for record in &records {
+            store.insert_relayed_tx(record).unwrap();
+        }
+        // unrelated recipient.
+        store
+            .insert_relayed_tx(&RelayedTxRecord {
+                recipient: types::Address::random(),
+                ..records[0].clone()
+            })
+            .unwrap();
+
+        let found =
+            store.get_relayed_txs_by_recipient(recipient, None).unwrap();
+        let mut expected = records.clone();
+        expected.reverse();
+        assert_eq!(found, expected);
+
+        let found = store
+            .get_relayed_txs_by_recipient(recipient, Some(tokens[1]))
+            .unwrap();
+        assert_eq!(found, vec![records[3].clone(), records[1].clone()]);
+
+        assert!(store
+            .get_relayed_txs_by_recipient(types::Address::random(), None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn refunds_by_recipient_since_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let recipient = types::Address::random();
+        let record = RelayedTxRecord {
+            chain_id: 1,
+            contract: types::Address::random(),
+            recipient,
+            token: types::Address::random(),
+            item_key: types::H512::random(),
+            fee: types::U256::zero(),
+            refund: types::U256::from(10),
+            relayed_at: 100,
+        };
+        for (chain_id, relayed_at) in [(1, 100), (1, 200), (1, 300), (2, 300)] {
+            store
+                .insert_relayed_tx(&RelayedTxRecord {
+                    chain_id,
+                    item_key: types::H512::random(),
+                    relayed_at,
+                    ..record.clone()
+                })
+                .unwrap();
+        }
+
+        let total = store
+            .get_refunds_by_recipient_since(1, recipient, 200)
+            .unwrap();
+        assert_eq!(total, types::U256::from(20));
+        let total = store
+            .get_refunds_by_recipient_since(1, types::Address::random(), 0)
+            .unwrap();
+        assert_eq!(total, types::U256::zero());
+    }
+
+    #[test]
+    fn recipient_refunds_should_be_reserved_up_to_the_cap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let recipient = types::Address::random();
+        let keys: Vec<_> = (0..3).map(|_| types::H512::random()).collect();
+        let reserve = |key, since| {
+            store
+                .reserve_recipient_refund(
+                    1,
+                    recipient,
+                    key,
+                    types::U256::from(10),
+                    since,
+                    types::U256::from(20),
+                )
+                .unwrap()
+        };
+        assert!(reserve(keys[0], 0));
+        // reserving the same withdrawal again does not count it twice.
+        assert!(reserve(keys[0], 0));
+        assert!(reserve(keys[1], 0));
+        // the cap is reached, until a reservation is released.
+        assert!(!reserve(keys[2], 0));
+        store
+            .release_recipient_refund(1, recipient, keys[1])
+            .unwrap();
+        assert!(reserve(keys[2], 0));
+        // the reservations out of the window do not count.
+        assert!(reserve(types::H512::random(), u64::MAX));
+        // nor do the ones of other chains.
+        assert!(store
+            .reserve_recipient_refund(
+                2,
+                recipient,
+                keys[0],
+                types::U256::from(20),
+                0,
+                types::U256::from(20),
+            )
+            .unwrap());
+    }
+
     #[test]
-    fn get_last_block_number_should_work() {
+    fn spent_nullifiers_should_work() {
         let tmp = tempfile::tempdir().unwrap();
         let store = SledStore::open(tmp.path()).unwrap();
-        let chain_id = 1u32;
-        let contract =
-            types::H160::from_slice("11111111111111111111".as_bytes());
-        let block_number = 20u64;
-        let default_block_number = 1u64;
-        let history_store_key = (
-            TypedChainId::Evm(chain_id),
-            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        let resource_id = test_key(TypedChainId::Evm(1)).resource_id();
+        let other_resource_id =
+            test_key(TypedChainId::Substrate(1)).resource_id();
+        let spent = (0..3).map(|_| types::H256::random()).collect::<Vec<_>>();
+        let unspent = types::H256::random();
+        store.insert_nullifiers(resource_id, &spent).unwrap();
+
+        let query = [spent[2], unspent, spent[0]];
+        assert_eq!(
+            store.get_spent_nullifiers(resource_id, &query).unwrap(),
+            vec![spent[2], spent[0]]
         );
+        // nullifiers are tracked per resource.
+        assert!(store
+            .get_spent_nullifiers(other_resource_id, &query)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn known_merkle_roots_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let resource_id = test_key(TypedChainId::Evm(1)).resource_id();
+        let local_chain_id = TypedChainId::Evm(1).chain_id();
+        let neighbor_chain_id = TypedChainId::Evm(2).chain_id();
+        assert!(store
+            .get_known_merkle_roots(resource_id)
+            .unwrap()
+            .is_empty());
+
+        let local_roots = (0..MERKLE_ROOT_HISTORY_SIZE + 5)
+            .map(|_| types::H256::random())
+            .collect::<Vec<_>>();
+        for root in &local_roots {
+            store
+                .insert_merkle_root(resource_id, local_chain_id, *root, 100)
+                .unwrap();
+        }
+        let neighbor_root = types::H256::random();
         store
-            .set_last_block_number(history_store_key, block_number)
+            .insert_merkle_root(
+                resource_id,
+                neighbor_chain_id,
+                neighbor_root,
+                110,
+            )
+            .unwrap();
+        // a root seen twice is only kept once, with the block it was first
+        // observed at.
+        store
+            .insert_merkle_root(
+                resource_id,
+                neighbor_chain_id,
+                neighbor_root,
+                120,
+            )
             .unwrap();
 
-        let block = store
-            .get_last_block_number(history_store_key, default_block_number);
+        let known = store.get_known_merkle_roots(resource_id).unwrap();
+        // only the most recent local roots are kept.
+        let mut expected = local_roots[5..].to_vec();
+        expected.push(neighbor_root);
+        assert_eq!(known, expected);
 
-        match block {
-            Ok(b) => {
-                println!("retrieved block {block:?}");
-                assert_eq!(b, 20u64);
-            }
-            Err(e) => {
-                println!("Error encountered {e:?}");
-            }
+        let history = store.get_merkle_root_history(resource_id).unwrap();
+        assert_eq!(history.len(), MERKLE_ROOT_HISTORY_SIZE + 1);
+        assert_eq!(
+            history.last(),
+            Some(&MerkleRootRecord {
+                source_chain_id: neighbor_chain_id,
+                root: neighbor_root,
+                block_number: 110,
+            })
+        );
+    }
+
+    #[test]
+    fn tx_batches_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let batch_id = types::H256::random();
+        let item_keys =
+            (0..3).map(|_| types::H512::random()).collect::<Vec<_>>();
+        store.insert_tx_batch(1, batch_id, &item_keys).unwrap();
+        assert_eq!(store.get_tx_batch(1, batch_id).unwrap(), Some(item_keys));
+        // batches are scoped by chain.
+        assert_eq!(store.get_tx_batch(2, batch_id).unwrap(), None);
+        assert_eq!(store.get_tx_batch(1, types::H256::random()).unwrap(), None);
+    }
+
+    #[test]
+    fn enqueue_tx_batch_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1;
+        let batch_id = types::H256::random();
+        let txs = (0..3u64)
+            .map(|i| -> TypedTransaction {
+                TransactionRequest::pay(types::Address::random(), i).into()
+            })
+            .collect::<Vec<_>>();
+        let items = txs
+            .iter()
+            .map(|tx| {
+                let key = SledQueueKey::from_evm_with_custom_key(
+                    chain_id,
+                    tx.item_key(),
+                );
+                (key, QueueItem::new(tx.clone()))
+            })
+            .collect::<Vec<_>>();
+        let item_keys = store
+            .enqueue_tx_batch(chain_id, batch_id, items.clone(), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            store.get_tx_batch(chain_id, batch_id).unwrap(),
+            Some(item_keys)
+        );
+        // the items are enqueued in order.
+        let queue_key = SledQueueKey::from_evm_chain_id(chain_id);
+        for tx in &txs {
+            let item: QueueItem<TypedTransaction> =
+                store.dequeue_item(queue_key).unwrap().unwrap();
+            assert_eq!(&item.inner(), tx);
         }
+
+        // a batch across queues is rejected as a whole.
+        let other_batch_id = types::H256::random();
+        let mut items = items;
+        items[1].0 = SledQueueKey::from_evm_with_custom_key(
+            chain_id + 1,
+            txs[1].item_key(),
+        );
+        assert!(store
+            .enqueue_tx_batch(chain_id, other_batch_id, items, None)
+            .is_err());
+        assert_eq!(store.get_tx_batch(chain_id, other_batch_id).unwrap(), None);
+        let item: Option<QueueItem<TypedTransaction>> =
+            store.peek_item(queue_key).unwrap();
+        assert!(item.is_none());
+
+        // a batch taking a resource over its cap is rejected as a whole.
+        let resource_id = types::H256::random();
+        let items = txs
+            .iter()
+            .map(|tx| {
+                let key = SledQueueKey::from_evm_with_custom_key(
+                    chain_id,
+                    tx.item_key(),
+                );
+                let mut item = QueueItem::new(tx.clone());
+                item.set_resource_id(Some(resource_id));
+                (key, item)
+            })
+            .collect::<Vec<_>>();
+        let enqueued = store
+            .enqueue_tx_batch(chain_id, other_batch_id, items.clone(), Some(2))
+            .unwrap();
+        assert_eq!(enqueued, None);
+        assert_eq!(store.get_tx_batch(chain_id, other_batch_id).unwrap(), None);
+        let enqueued = store
+            .enqueue_tx_batch(chain_id, other_batch_id, items, Some(3))
+            .unwrap();
+        assert!(enqueued.is_some());
     }
 
     #[test]
-    fn get_leaves_with_range_should_work() {
+    fn tx_hashes_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let item_key = types::H512::random().0;
+        assert!(store.get_tx_hashes(1, item_key).unwrap().is_empty());
+        let (first, replacement) =
+            (types::H256::random(), types::H256::random());
+        store.insert_tx_hash(1, item_key, first).unwrap();
+        store.insert_tx_hash(1, item_key, replacement).unwrap();
+        // the same hash is only recorded once.
+        store.insert_tx_hash(1, item_key, first).unwrap();
+        assert_eq!(
+            store.get_tx_hashes(1, item_key).unwrap(),
+            vec![first, replacement]
+        );
+        // hashes are scoped by chain.
+        assert!(store.get_tx_hashes(2, item_key).unwrap().is_empty());
+    }
+
+    #[test]
+    fn relay_jobs_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let mut job =
+            RelayJob::new(types::H256::random(), 1, types::H512::random());
+        store.insert_relay_job(&job).unwrap();
+        assert_eq!(store.get_relay_job(job.job_id).unwrap(), Some(job.clone()));
+
+        job.set_stage(crate::RelayJobStage::Failed {
+            reason: "fee too low".to_string(),
+        });
+        store.insert_relay_job(&job).unwrap();
+        assert_eq!(store.get_relay_job(job.job_id).unwrap(), Some(job.clone()));
+        assert_eq!(store.get_relay_job(types::H256::random()).unwrap(), None);
+
+        let updated_at = job.updated_at;
+        assert_eq!(store.purge_relay_jobs_before(updated_at).unwrap(), 0);
+        assert_eq!(store.purge_relay_jobs_before(updated_at + 1).unwrap(), 1);
+        assert_eq!(store.get_relay_job(job.job_id).unwrap(), None);
+    }
+
+    #[test]
+    fn tangle_jobs_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let mut job = TangleJob {
+            chain_id: 1081,
+            job_id: 7,
+            role_type: "Tss(DfnsCGGMP21Secp256k1)".to_string(),
+            phase: Some(crate::TangleJobPhase::KeyGeneration),
+            stage: crate::TangleJobStage::Submitted,
+            submitted_at: Some(100),
+            result_submitted_at: None,
+            updated_at: 100,
+        };
+        store.insert_tangle_job(&job).unwrap();
+        assert_eq!(store.get_tangle_job(1081, 7).unwrap(), Some(job.clone()));
+
+        job.stage = crate::TangleJobStage::ResultSubmitted;
+        job.result_submitted_at = Some(110);
+        store.insert_tangle_job(&job).unwrap();
+        assert_eq!(store.get_tangle_job(1081, 7).unwrap(), Some(job));
+        // jobs are kept per chain.
+        assert_eq!(store.get_tangle_job(1080, 7).unwrap(), None);
+        assert_eq!(store.get_tangle_job(1081, 8).unwrap(), None);
+
+        assert_eq!(store.purge_tangle_jobs_before(100).unwrap(), 0);
+        assert_eq!(store.purge_tangle_jobs_before(101).unwrap(), 1);
+        assert_eq!(store.get_tangle_job(1081, 7).unwrap(), None);
+    }
+
+    #[test]
+    fn insert_leaves_and_last_deposit_block_number_should_work() {
         let tmp = tempfile::tempdir().unwrap();
         let store = SledStore::open(tmp.path()).unwrap();
         let chain_id = 1u32;
-        let block_number = 20u64;
         let contract =
             types::H160::from_slice("11111111111111111111".as_bytes());
         let history_store_key = (
             TypedChainId::Evm(chain_id),
             TargetSystem::new_contract_address(contract.to_fixed_bytes()),
         );
-        let generated_leaves = (0..20u32)
+        let generated_leaves = (0..2u32)
             .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
             .collect::<Vec<_>>();
+        let block_number = 20u64;
+        let default_block_number = 1u64;
         store
             .insert_leaves_and_last_deposit_block_number(
                 history_store_key,
@@ -808,9 +3538,9 @@ mod tests {
             )
             .unwrap();
         let leaves = store
-            .get_leaves_with_range(history_store_key, 5..10)
+            .get_leaves_with_range(history_store_key, 0..2)
             .unwrap();
-        assert_eq!(leaves.len(), 5);
+        assert_eq!(leaves.len(), 2);
         assert_eq!(
             leaves
                 .values()
@@ -818,185 +3548,184 @@ mod tests {
                 .collect::<Vec<_>>(),
             generated_leaves
                 .into_iter()
-                .skip(5)
-                .take(5)
                 .map(|(_, v)| v)
                 .collect::<Vec<_>>()
         );
+        assert_eq!(
+            store
+                .get_last_deposit_block_number(history_store_key)
+                .unwrap(),
+            block_number
+        );
+        assert_eq!(
+            store
+                .get_last_block_number(history_store_key, default_block_number)
+                .unwrap(),
+            block_number
+        );
     }
 
     #[test]
-    fn tx_queue_should_work() {
+    fn insert_leaves_batch_should_work() {
         let tmp = tempfile::tempdir().unwrap();
         let store = SledStore::open(tmp.path()).unwrap();
         let chain_id = 1u32;
-        // it is now empty
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(chain_id),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        let default_block_number = 1u64;
+        // an empty batch is a no-op.
+        store
+            .insert_leaves_batch(history_store_key, &LeavesBatch::new())
+            .unwrap();
         assert_eq!(
             store
-                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .get_last_deposit_block_number(history_store_key)
                 .unwrap(),
-            Option::<QueueItem<TypedTransaction>>::None
+            0
         );
 
-        let tx1: TypedTransaction = TransactionRequest::pay(
-            types::Address::random(),
-            types::U256::one(),
-        )
-        .from(types::Address::random())
-        .into();
-
-        let queue_item1 = QueueItem::new(tx1.clone());
-        store
-            .enqueue_item(
-                SledQueueKey::from_evm_tx(chain_id, &tx1),
-                queue_item1.clone(),
-            )
-            .unwrap();
-        let tx2: TypedTransaction = TransactionRequest::pay(
-            types::Address::random(),
-            types::U256::one(),
-        )
-        .from(types::Address::random())
-        .into();
-        let queue_item2 = QueueItem::new(tx2.clone());
+        let generated_leaves = (0..10u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        let mut batch = LeavesBatch::new();
+        for (i, leaf) in &generated_leaves {
+            batch.push(*i, leaf.clone(), 20 + u64::from(*i / 2));
+        }
+        assert_eq!(batch.len(), 10);
+        assert_eq!(batch.last_deposit_block_number(), 24);
         store
-            .enqueue_item(
-                SledQueueKey::from_evm_tx(chain_id, &tx2),
-                queue_item2.clone(),
-            )
+            .insert_leaves_batch(history_store_key, &batch)
             .unwrap();
+        batch.clear();
+        assert!(batch.is_empty());
 
-        // now let's dequeue transactions.
+        let leaves = store.get_leaves(history_store_key).unwrap();
+        assert_eq!(
+            leaves
+                .into_iter()
+                .map(|(i, v)| (i, v.to_fixed_bytes().to_vec()))
+                .collect::<Vec<_>>(),
+            generated_leaves
+        );
         assert_eq!(
             store
-                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .get_last_deposit_block_number(history_store_key)
                 .unwrap(),
-            Some(queue_item1)
+            24
         );
         assert_eq!(
             store
-                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .get_last_block_number(history_store_key, default_block_number)
                 .unwrap(),
-            Some(queue_item2)
+            24
         );
+    }
 
-        let tx3: TypedTransaction = TransactionRequest::pay(
-            types::Address::random(),
-            types::U256::one(),
-        )
-        .from(types::Address::random())
-        .into();
-        let queue_item3 = QueueItem::new(tx3.clone());
-        store
-            .enqueue_item(
-                SledQueueKey::from_evm_tx(chain_id, &tx3),
-                queue_item3.clone(),
-            )
-            .unwrap();
+    #[test]
+    fn cached_reads_should_see_inserts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        // warm up the cache with empty reads.
+        assert!(store
+            .get_leaves_with_range(history_store_key, 0..10)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            store.get_last_block_number(history_store_key, 1).unwrap(),
+            1
+        );
         assert_eq!(
             store
-                .peek_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .get_last_deposit_block_number(history_store_key)
                 .unwrap(),
-            Some(queue_item3)
+            0
         );
-        assert!(QueueStore::<TypedTransaction>::has_item(
-            &store,
-            SledQueueKey::from_evm_tx(chain_id, &tx3)
-        )
-        .unwrap());
-        let _: Option<QueueItem<TypedTransaction>> = store
-            .remove_item(SledQueueKey::from_evm_tx(chain_id, &tx3))
+
+        let leaf = types::H256::random();
+        store
+            .insert_leaves_and_last_deposit_block_number(
+                history_store_key,
+                &[(0, leaf.to_fixed_bytes().to_vec())],
+                20,
+            )
             .unwrap();
-        assert!(!QueueStore::<TypedTransaction>::has_item(
-            &store,
-            SledQueueKey::from_evm_tx(chain_id, &tx3)
-        )
-        .unwrap());
+        let leaves = store
+            .get_leaves_with_range(history_store_key, 0..10)
+            .unwrap();
+        assert_eq!(leaves.into_iter().collect::<Vec<_>>(), vec![(0, leaf)]);
+        assert_eq!(
+            store.get_last_block_number(history_store_key, 1).unwrap(),
+            20
+        );
         assert_eq!(
             store
-                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .get_last_deposit_block_number(history_store_key)
                 .unwrap(),
-            Option::<QueueItem<TypedTransaction>>::None
+            20
         );
-    }
-
-    #[test]
-    fn events_hash_should_work() {
-        let tmp = tempfile::tempdir().unwrap();
-        let store = SledStore::open(tmp.path()).unwrap();
-
-        let events = (0..20)
-            .map(|_| NewNullifierFilter {
-                nullifier: types::H256::random().to_fixed_bytes().into(),
-            })
-            .collect::<Vec<_>>();
 
-        for event in &events {
-            let event_bytes = serde_json::to_vec(&event).unwrap();
-            // check if the event is already in the store
-            assert!(!store.contains_event(&event_bytes).unwrap());
-            // add the event
-            store.store_event(&event_bytes).unwrap();
-        }
+        store.set_last_block_number(history_store_key, 30).unwrap();
+        assert_eq!(
+            store.get_last_block_number(history_store_key, 1).unwrap(),
+            30
+        );
 
-        for event in &events {
-            let event_bytes = serde_json::to_vec(&event).unwrap();
-            // check if the event is in the store
-            assert!(store.contains_event(&event_bytes).unwrap());
-            // remove the event
-            store.delete_event(&event_bytes).unwrap();
-        }
+        store.clear_leaves_cache(history_store_key).unwrap();
+        assert!(store
+            .get_leaves_with_range(history_store_key, 0..10)
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn insert_leaves_and_last_deposit_block_number_should_work() {
+    fn tree_statistics_should_work() {
         let tmp = tempfile::tempdir().unwrap();
         let store = SledStore::open(tmp.path()).unwrap();
-        let chain_id = 1u32;
         let contract =
             types::H160::from_slice("11111111111111111111".as_bytes());
         let history_store_key = (
-            TypedChainId::Evm(chain_id),
+            TypedChainId::Evm(1),
             TargetSystem::new_contract_address(contract.to_fixed_bytes()),
         );
-        let generated_leaves = (0..2u32)
+        assert_eq!(store.tree_statistics().unwrap(), TreeStatistics::default());
+
+        let leaves = (0..3u32)
             .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
             .collect::<Vec<_>>();
-        let block_number = 20u64;
-        let default_block_number = 1u64;
         store
             .insert_leaves_and_last_deposit_block_number(
                 history_store_key,
-                &generated_leaves,
-                block_number,
+                &leaves,
+                20,
             )
             .unwrap();
-        let leaves = store
-            .get_leaves_with_range(history_store_key, 0..2)
+        let encrypted_outputs =
+            (0..2u32).map(|i| (i, vec![0u8; 168])).collect::<Vec<_>>();
+        store
+            .insert_encrypted_output_and_last_deposit_block_number(
+                history_store_key,
+                &encrypted_outputs,
+                20,
+            )
             .unwrap();
-        assert_eq!(leaves.len(), 2);
-        assert_eq!(
-            leaves
-                .values()
-                .map(|v| v.to_fixed_bytes().to_vec())
-                .collect::<Vec<_>>(),
-            generated_leaves
-                .into_iter()
-                .map(|(_, v)| v)
-                .collect::<Vec<_>>()
-        );
-        assert_eq!(
-            store
-                .get_last_deposit_block_number(history_store_key)
-                .unwrap(),
-            block_number
-        );
-        assert_eq!(
-            store
-                .get_last_block_number(history_store_key, default_block_number)
-                .unwrap(),
-            block_number
-        );
+        store.store_event(history_store_key, b"event", 20).unwrap();
+
+        let stats = store.tree_statistics().unwrap();
+        assert_eq!(stats.leaves, 3);
+        assert_eq!(stats.encrypted_outputs, 2);
+        assert_eq!(stats.queues, 0);
+        assert_eq!(stats.event_hashes, 1);
+        assert!(store.flush().is_ok());
     }
 
     #[test]
@@ -1042,6 +3771,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_encrypted_outputs_with_range_should_be_ordered_by_index() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        // past 256 outputs the little endian keys are no longer in order.
+        let generated_encrypted_outputs = (0..600u32)
+            .map(|i| (i, i.to_be_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        store
+            .insert_encrypted_output_and_last_deposit_block_number(
+                history_store_key,
+                &generated_encrypted_outputs,
+                20,
+            )
+            .unwrap();
+        let expected = |range: core::ops::Range<usize>| {
+            generated_encrypted_outputs[range]
+                .iter()
+                .map(|(_, v)| hex::encode(v))
+                .collect::<Vec<_>>()
+        };
+        let encrypted_outputs = store
+            .get_encrypted_output_with_range(history_store_key, 5..10)
+            .unwrap();
+        assert_eq!(encrypted_outputs, expected(5..10));
+        let encrypted_outputs = store
+            .get_encrypted_output_with_range(history_store_key, 250..u32::MAX)
+            .unwrap();
+        assert_eq!(encrypted_outputs, expected(250..600));
+        let encrypted_outputs =
+            store.get_encrypted_output(history_store_key).unwrap();
+        assert_eq!(encrypted_outputs, expected(0..600));
+    }
+
+    #[test]
+    fn legacy_keyed_trees_should_be_migrated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let key = test_key(TypedChainId::Evm(1));
+        let leaf = types::H256::random();
+        {
+            let db = sled::open(tmp.path()).unwrap();
+            let legacy_tree =
+                db.open_tree(KeyedTree::Leaves.legacy_name(key)).unwrap();
+            legacy_tree
+                .insert(&7u32.to_le_bytes(), leaf.as_bytes())
+                .unwrap();
+            db.flush().unwrap();
+        }
+        let store = SledStore::open(tmp.path()).unwrap();
+        let leaves = store.get_leaves(key).unwrap();
+        assert_eq!(leaves, BTreeMap::from([(7, leaf)]));
+        let legacy_name = KeyedTree::Leaves.legacy_name(key);
+        assert!(!store
+            .db
+            .tree_names()
+            .iter()
+            .any(|name| &name[..] == legacy_name.as_bytes()));
+        // the migrated tree is used from now on.
+        store
+            .insert_leaves_and_last_deposit_block_number(
+                key,
+                &[(8, leaf.as_bytes().to_vec())],
+                20,
+            )
+            .unwrap();
+        assert_eq!(store.get_leaves(key).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn keyed_trees_should_not_collide_across_chain_types() {
+        let store = SledStore::temporary().unwrap();
+        let evm_key = test_key(TypedChainId::Evm(1));
+        let substrate_key = test_key(TypedChainId::Substrate(1));
+        let leaf = types::H256::random().as_bytes().to_vec();
+        store
+            .insert_leaves_and_last_deposit_block_number(
+                evm_key,
+                &[(0, leaf)],
+                20,
+            )
+            .unwrap();
+        assert_eq!(store.get_leaves(evm_key).unwrap().len(), 1);
+        assert!(store.get_leaves(substrate_key).unwrap().is_empty());
+    }
+
     #[test]
     fn update_item_should_work() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1069,110 +3889,512 @@ mod tests {
         let queue_item2 = QueueItem::new(tx2.clone());
         store
             .enqueue_item(
-                SledQueueKey::from_evm_tx(chain_id, &tx2),
-                queue_item2,
+                SledQueueKey::from_evm_tx(chain_id, &tx2),
+                queue_item2,
+            )
+            .unwrap();
+
+        // peak the first item
+        let item1: QueueItem<TypedTransaction> = store
+            .peek_item(SledQueueKey::from_evm_tx(chain_id, &tx1))
+            .unwrap()
+            .unwrap();
+        // default state of item should be pending
+        assert_eq!(item1.state(), QueueItemState::Pending);
+        store
+            .update_item(
+                SledQueueKey::from_evm_tx(chain_id, &tx1),
+                |item1: &mut QueueItem<TypedTransaction>| {
+                    item1.transition_to(QueueItemState::Validated)
+                },
+            )
+            .unwrap();
+
+        // peak the first item again to check if the state is updated
+        let item1_updated: QueueItem<TypedTransaction> = store
+            .peek_item(SledQueueKey::from_evm_tx(chain_id, &tx1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(item1_updated.state(), QueueItemState::Validated);
+    }
+
+    #[test]
+    fn shift_item_to_end_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let tx1: TypedTransaction = TransactionRequest::pay(
+            types::Address::random(),
+            types::U256::one(),
+        )
+        .from(types::Address::random())
+        .into();
+        let queue_item1 = QueueItem::new(tx1.clone());
+        store
+            .enqueue_item(
+                SledQueueKey::from_evm_with_custom_key(
+                    chain_id,
+                    tx1.item_key(),
+                ),
+                queue_item1,
+            )
+            .unwrap();
+
+        // peak at tx1 item.
+        let item1: QueueItem<TypedTransaction> = store
+            .peek_item(SledQueueKey::from_evm_with_custom_key(
+                chain_id,
+                tx1.item_key(),
+            ))
+            .unwrap()
+            .unwrap();
+        // default state of item should be pending.
+        assert_eq!(item1.state(), QueueItemState::Pending);
+
+        //  Update state as failed and shift item to the end of queue.
+        store
+            .shift_item_to_end(
+                SledQueueKey::from_evm_with_custom_key(
+                    chain_id,
+                    tx1.item_key(),
+                ),
+                |item1: &mut QueueItem<TypedTransaction>| {
+                    let state = QueueItemState::Failed {
+                        reason: "Out of gas".to_string(),
+                    };
+                    item1.set_state(state);
+                    Ok(())
+                },
+            )
+            .unwrap();
+
+        // Peak the tx1 item again to check if the state is updated.
+        let item1_updated: QueueItem<TypedTransaction> = store
+            .peek_item(SledQueueKey::from_evm_with_custom_key(
+                chain_id,
+                tx1.item_key(),
+            ))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            item1_updated.state(),
+            QueueItemState::Failed {
+                reason: "Out of gas".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn max_gas_price_should_be_kept_while_waiting() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let tx: TypedTransaction = TransactionRequest::pay(
+            types::Address::random(),
+            types::U256::one(),
+        )
+        .from(types::Address::random())
+        .into();
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        let max_gas_price = types::U256::from(30_000_000_000u64);
+        let mut item = QueueItem::new(tx);
+        item.set_max_gas_price(Some(max_gas_price));
+        store.enqueue_item(key, item).unwrap();
+
+        // park the item until the gas price drops.
+        let waiting = QueueItemState::WaitingForGasPrice {
+            max_gas_price,
+            gas_price: max_gas_price * 3,
+        };
+        store
+            .shift_item_to_end(key, |item: &mut QueueItem<TypedTransaction>| {
+                item.set_state(waiting.clone());
+                Ok(())
+            })
+            .unwrap();
+
+        let item: QueueItem<TypedTransaction> =
+            store.peek_item(key).unwrap().unwrap();
+        assert_eq!(item.state(), waiting);
+        assert_eq!(item.max_gas_price(), Some(max_gas_price));
+    }
+
+    #[test]
+    fn processed_state_without_execution_details_should_deserialize() {
+        // items processed before the execution details were recorded.
+        let tx_hash = types::H256::random();
+        let legacy = serde_json::json!({
+            "Processed": { "txHash": tx_hash },
+        });
+        let state: QueueItemState = serde_json::from_value(legacy).unwrap();
+        assert_eq!(
+            state,
+            QueueItemState::Processed {
+                tx_hash,
+                block_number: None,
+                gas_used: None,
+                effective_fee: None,
+            }
+        );
+    }
+
+    #[test]
+    fn queue_item_transitions_should_be_enforced() {
+        let tx: TypedTransaction = TransactionRequest::pay(
+            types::Address::from_low_u64_be(1),
+            types::U256::one(),
+        )
+        .into();
+        let mut item = QueueItem::new(tx);
+        // it has to pass its dry run before being broadcast.
+        assert!(item
+            .transition_to(QueueItemState::InBlock {
+                block_hash: types::H256::random(),
+            })
+            .is_err());
+        assert_eq!(item.state(), QueueItemState::Pending);
+        item.transition_to(QueueItemState::Validated).unwrap();
+        item.transition_to(QueueItemState::Broadcast { attempt: 1 })
+            .unwrap();
+        // replacing a stuck transaction is a new attempt.
+        item.transition_to(QueueItemState::Broadcast { attempt: 2 })
+            .unwrap();
+        assert!(item
+            .transition_to(QueueItemState::Broadcast { attempt: 2 })
+            .is_err());
+        // a reorg moves the transaction to another block, or retracts it.
+        item.transition_to(QueueItemState::InBlock {
+            block_hash: types::H256::random(),
+        })
+        .unwrap();
+        item.transition_to(QueueItemState::InBlock {
+            block_hash: types::H256::random(),
+        })
+        .unwrap();
+        item.transition_to(QueueItemState::Broadcast { attempt: 1 })
+            .unwrap();
+        let processed = QueueItemState::Processed {
+            tx_hash: types::H256::random(),
+            block_number: Some(1),
+            gas_used: None,
+            effective_fee: None,
+        };
+        item.transition_to(processed.clone()).unwrap();
+        // processed items never change anymore.
+        assert!(item.transition_to(QueueItemState::Pending).is_err());
+        assert!(item
+            .transition_to(QueueItemState::Failed {
+                reason: "late".to_string(),
+            })
+            .is_err());
+        assert_eq!(item.state(), processed);
+    }
+
+    #[test]
+    fn rejected_shift_should_keep_the_item() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let tx: TypedTransaction = TransactionRequest::pay(
+            types::Address::from_low_u64_be(1),
+            types::U256::one(),
+        )
+        .into();
+        let key =
+            || SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        store
+            .enqueue_item(key(), QueueItem::new(tx.clone()))
+            .unwrap();
+        // a pending item can't be included in a block without a broadcast.
+        let shifted = store.shift_item_to_end(
+            key(),
+            |item: &mut QueueItem<TypedTransaction>| {
+                item.transition_to(QueueItemState::InBlock {
+                    block_hash: types::H256::random(),
+                })
+            },
+        );
+        assert!(shifted.is_err());
+        let item: QueueItem<TypedTransaction> =
+            store.get_item(key()).unwrap().unwrap();
+        assert_eq!(item.state(), QueueItemState::Pending);
+    }
+
+    #[test]
+    fn has_pending_items_above_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let queue_key = || SledQueueKey::from_evm_chain_id(chain_id);
+        let tx = |i| -> TypedTransaction {
+            TransactionRequest::pay(
+                types::Address::from_low_u64_be(i),
+                types::U256::one(),
+            )
+            .into()
+        };
+        let mut sweep = QueueItem::new(tx(1));
+        sweep.set_priority(QueueItemPriority::Low);
+        store
+            .enqueue_item(
+                SledQueueKey::from_evm_with_custom_key(
+                    chain_id,
+                    tx(1).item_key(),
+                ),
+                sweep,
+            )
+            .unwrap();
+        // only low priority items are queued.
+        assert!(!QueueStore::<TypedTransaction>::has_pending_items_above(
+            &store,
+            queue_key(),
+            QueueItemPriority::Low
+        )
+        .unwrap());
+
+        store
+            .enqueue_item(
+                SledQueueKey::from_evm_with_custom_key(
+                    chain_id,
+                    tx(2).item_key(),
+                ),
+                QueueItem::new(tx(2)),
             )
             .unwrap();
+        assert!(QueueStore::<TypedTransaction>::has_pending_items_above(
+            &store,
+            queue_key(),
+            QueueItemPriority::Low
+        )
+        .unwrap());
 
-        // peak the first item
-        let item1: QueueItem<TypedTransaction> = store
-            .peek_item(SledQueueKey::from_evm_tx(chain_id, &tx1))
-            .unwrap()
-            .unwrap();
-        // default state of item should be pending
-        assert_eq!(item1.state(), QueueItemState::Pending);
+        // processed items do not hold back lower priority ones.
         store
             .update_item(
-                SledQueueKey::from_evm_tx(chain_id, &tx1),
-                |item1: &mut QueueItem<TypedTransaction>| {
-                    let state = QueueItemState::Processing {
-                        step: "Dry run passed".to_string(),
-                        progress: Some(0.5),
-                    };
-                    item1.set_state(state);
+                SledQueueKey::from_evm_with_custom_key(
+                    chain_id,
+                    tx(2).item_key(),
+                ),
+                |item: &mut QueueItem<TypedTransaction>| {
+                    item.set_state(QueueItemState::Processed {
+                        tx_hash: types::H256::zero(),
+                        block_number: None,
+                        gas_used: None,
+                        effective_fee: None,
+                    });
                     Ok(())
                 },
             )
             .unwrap();
-
-        // peak the first item again to check if the state is updated
-        let item1_updated: QueueItem<TypedTransaction> = store
-            .peek_item(SledQueueKey::from_evm_tx(chain_id, &tx1))
-            .unwrap()
-            .unwrap();
-        assert_eq!(
-            item1_updated.state(),
-            QueueItemState::Processing {
-                step: "Dry run passed".to_string(),
-                progress: Some(0.5)
-            }
-        );
+        assert!(!QueueStore::<TypedTransaction>::has_pending_items_above(
+            &store,
+            queue_key(),
+            QueueItemPriority::Low
+        )
+        .unwrap());
     }
 
     #[test]
-    fn shift_item_to_end_should_work() {
+    fn pending_items_by_resource_should_work() {
         let tmp = tempfile::tempdir().unwrap();
         let store = SledStore::open(tmp.path()).unwrap();
         let chain_id = 1u32;
-        let tx1: TypedTransaction = TransactionRequest::pay(
-            types::Address::random(),
-            types::U256::one(),
-        )
-        .from(types::Address::random())
-        .into();
-        let queue_item1 = QueueItem::new(tx1.clone());
+        let queue_key = || SledQueueKey::from_evm_chain_id(chain_id);
+        let tx = |i| -> TypedTransaction {
+            TransactionRequest::pay(
+                types::Address::from_low_u64_be(i),
+                types::U256::one(),
+            )
+            .into()
+        };
+        let busy = types::H256::repeat_byte(1);
+        let quiet = types::H256::repeat_byte(2);
+        let enqueue = |i, resource_id, priority| {
+            let mut item = QueueItem::new(tx(i));
+            item.set_resource_id(resource_id);
+            item.set_priority(priority);
+            let key = SledQueueKey::from_evm_with_custom_key(
+                chain_id,
+                tx(i).item_key(),
+            );
+            store.enqueue_item(key, item).unwrap();
+        };
+        enqueue(1, Some(busy), QueueItemPriority::Normal);
+        enqueue(2, Some(busy), QueueItemPriority::Normal);
+        // lower priority items do not take turns with the others.
+        enqueue(3, Some(quiet), QueueItemPriority::Low);
+        let count = |resource_id| {
+            QueueStore::<TypedTransaction>::count_pending_items_of(
+                &store,
+                queue_key(),
+                resource_id,
+            )
+            .unwrap()
+        };
+        let besides = |resource_id| {
+            QueueStore::<TypedTransaction>::has_pending_items_besides(
+                &store,
+                queue_key(),
+                resource_id,
+                QueueItemPriority::Normal,
+            )
+            .unwrap()
+        };
+        assert_eq!(count(busy), 2);
+        assert_eq!(count(quiet), 1);
+        assert!(!besides(Some(busy)));
+
+        // items without a resource are not counted.
+        enqueue(4, None, QueueItemPriority::Normal);
+        assert!(!besides(Some(busy)));
+
+        enqueue(5, Some(quiet), QueueItemPriority::Normal);
+        assert!(besides(Some(busy)));
+        assert!(besides(None));
+
+        // finished items are no longer counted.
         store
-            .enqueue_item(
+            .update_item(
                 SledQueueKey::from_evm_with_custom_key(
                     chain_id,
-                    tx1.item_key(),
+                    tx(5).item_key(),
                 ),
-                queue_item1,
+                |item: &mut QueueItem<TypedTransaction>| {
+                    item.set_state(QueueItemState::Expired);
+                    Ok(())
+                },
             )
             .unwrap();
+        assert_eq!(count(quiet), 1);
+        assert!(!besides(Some(busy)));
 
-        // peak at tx1 item.
-        let item1: QueueItem<TypedTransaction> = store
-            .peek_item(SledQueueKey::from_evm_with_custom_key(
+        // removed items are no longer counted either.
+        QueueStore::<TypedTransaction>::remove_item(
+            &store,
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx(2).item_key()),
+        )
+        .unwrap();
+        assert_eq!(count(busy), 1);
+
+        // the cap is checked when the item is enqueued.
+        let capped = |i, max_pending| {
+            let mut item = QueueItem::new(tx(i));
+            item.set_resource_id(Some(busy));
+            let key = SledQueueKey::from_evm_with_custom_key(
                 chain_id,
-                tx1.item_key(),
-            ))
-            .unwrap()
-            .unwrap();
-        // default state of item should be pending.
-        assert_eq!(item1.state(), QueueItemState::Pending);
+                tx(i).item_key(),
+            );
+            store.enqueue_item_capped(key, item, max_pending).unwrap()
+        };
+        assert!(!capped(6, 1));
+        assert!(capped(6, 2));
+        assert_eq!(count(busy), 2);
 
-        //  Update state as failed and shift item to the end of queue.
+        // the items of a queue enqueued before it kept count are counted
+        // the first time it is opened.
+        let tree = store
+            .db
+            .open_tree(format!("queue_{}", queue_key().queue_name()));
+        let tree = tree.unwrap();
+        let counters = tree
+            .iter()
+            .keys()
+            .map(Result::unwrap)
+            .filter(|k| {
+                k.starts_with(PENDING_OF_PREFIX)
+                    || k.starts_with(WAITING_OF_PREFIX)
+                    || &k[..] == QUEUE_COUNTED_KEY
+            })
+            .collect::<Vec<_>>();
+        for counter in counters {
+            tree.remove(counter).unwrap();
+        }
+        let store = SledStore {
+            counted_queues: Default::default(),
+            ..store.clone()
+        };
+        assert_eq!(
+            QueueStore::<TypedTransaction>::count_pending_items_of(
+                &store,
+                queue_key(),
+                busy,
+            )
+            .unwrap(),
+            2
+        );
+        assert!(QueueStore::<TypedTransaction>::has_pending_items_besides(
+            &store,
+            queue_key(),
+            Some(quiet),
+            QueueItemPriority::Normal,
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn queue_stats_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let queue_key = || SledQueueKey::from_evm_chain_id(chain_id);
+        let tx = |i| -> TypedTransaction {
+            TransactionRequest::pay(
+                types::Address::from_low_u64_be(i),
+                types::U256::one(),
+            )
+            .into()
+        };
+        let stats =
+            QueueStore::<TypedTransaction>::queue_stats(&store, queue_key(), 0)
+                .unwrap();
+        assert_eq!(stats.pending, 0);
+        assert_eq!(stats.oldest_pending_enqueued_at, None);
+
+        let first = QueueItem::new(tx(1));
+        let first_enqueued_at = first.enqueued_at();
+        for (i, item) in [(1, first), (2, QueueItem::new(tx(2)))] {
+            store
+                .enqueue_item(
+                    SledQueueKey::from_evm_with_custom_key(
+                        chain_id,
+                        tx(i).item_key(),
+                    ),
+                    item,
+                )
+                .unwrap();
+        }
+        let stats =
+            QueueStore::<TypedTransaction>::queue_stats(&store, queue_key(), 0)
+                .unwrap();
+        assert_eq!(stats.pending, 2);
+        assert_eq!(stats.enqueued_since, 2);
+        assert_eq!(stats.oldest_pending_enqueued_at, Some(first_enqueued_at));
+
+        // processed items are no longer pending, and only the items
+        // enqueued after the last sample are counted.
         store
-            .shift_item_to_end(
+            .update_item(
                 SledQueueKey::from_evm_with_custom_key(
                     chain_id,
-                    tx1.item_key(),
+                    tx(1).item_key(),
                 ),
-                |item1: &mut QueueItem<TypedTransaction>| {
-                    let state = QueueItemState::Failed {
-                        reason: "Out of gas".to_string(),
-                    };
-                    item1.set_state(state);
+                |item: &mut QueueItem<TypedTransaction>| {
+                    item.set_state(QueueItemState::Failed {
+                        reason: "reverted".to_string(),
+                    });
                     Ok(())
                 },
             )
             .unwrap();
-
-        // Peak the tx1 item again to check if the state is updated.
-        let item1_updated: QueueItem<TypedTransaction> = store
-            .peek_item(SledQueueKey::from_evm_with_custom_key(
-                chain_id,
-                tx1.item_key(),
-            ))
-            .unwrap()
-            .unwrap();
-        assert_eq!(
-            item1_updated.state(),
-            QueueItemState::Failed {
-                reason: "Out of gas".to_string()
-            }
-        );
+        let stats = QueueStore::<TypedTransaction>::queue_stats(
+            &store,
+            queue_key(),
+            stats.sampled_at,
+        )
+        .unwrap();
+        assert_eq!(stats.pending, 1);
+        assert_eq!(stats.enqueued_since, 0);
     }
 
     #[test]
@@ -1225,6 +4447,140 @@ mod tests {
         assert!(!queue_item1.is_expired());
     }
 
+    #[test]
+    fn item_should_be_past_deadline() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let tx1: TypedTransaction = TransactionRequest::pay(
+            types::Address::random(),
+            types::U256::one(),
+        )
+        .from(types::Address::random())
+        .into();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let key = SledQueueKey::from_evm_tx(chain_id, &tx1);
+        let mut queue_item1 = QueueItem::new(tx1);
+        // no deadline, never past it.
+        assert!(!queue_item1.is_past_deadline());
+        queue_item1.set_deadline(Some(now + 60));
+        assert!(!queue_item1.is_past_deadline());
+        queue_item1.set_deadline(Some(now - 1));
+        store.enqueue_item(key, queue_item1).unwrap();
+        // the deadline is kept in the store.
+        let item: QueueItem<TypedTransaction> =
+            store.peek_item(key).unwrap().unwrap();
+        assert_eq!(item.deadline(), Some(now - 1));
+        assert!(item.is_past_deadline());
+        // a past deadline does not expire the item on its own.
+        assert!(!item.is_expired());
+    }
+
+    #[test]
+    fn profit_and_loss_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let resource_id = ResourceId::new(
+            TargetSystem::new_contract_address([1u8; 20]),
+            TypedChainId::Evm(5),
+        );
+        let cost = TxCost {
+            gas_used: 100.into(),
+            gas_spent: 1_000.into(),
+            value_sent: 10.into(),
+        };
+        store.insert_expected_fee([1u8; 64], 50.into()).unwrap();
+        store.insert_expected_fee([2u8; 64], 70.into()).unwrap();
+        assert!(store.has_expected_fee([1u8; 64]).unwrap());
+        store.settle_tx(resource_id, [1u8; 64], cost, true).unwrap();
+        assert!(!store.has_expected_fee([1u8; 64]).unwrap());
+        // a reverted transaction only costs gas.
+        store
+            .settle_tx(resource_id, [2u8; 64], cost, false)
+            .unwrap();
+        // the expected fee was collected, settling again earns nothing.
+        store.settle_tx(resource_id, [1u8; 64], cost, true).unwrap();
+
+        let ledgers = store.get_profit_and_loss().unwrap();
+        assert_eq!(ledgers.len(), 1);
+        assert_eq!(ledgers[0].0, resource_id);
+        assert_eq!(
+            ledgers[0].1,
+            ProfitAndLoss {
+                fees_earned: 50.into(),
+                gas_used: 300.into(),
+                gas_spent: 3_000.into(),
+                refunds_paid: 20.into(),
+                txs_succeeded: 2,
+                txs_failed: 1,
+                ..Default::default()
+            }
+        );
+        // failed transactions count towards the daily gas spend too.
+        let today = crate::current_day();
+        let spent = store
+            .get_daily_gas_spent(TypedChainId::Evm(5), today)
+            .unwrap();
+        assert_eq!(spent, 3_000.into());
+        let other = store
+            .get_daily_gas_spent(TypedChainId::Evm(1), today)
+            .unwrap();
+        assert_eq!(other, types::U256::zero());
+    }
+
+    #[test]
+    fn subsidized_txs_should_be_accounted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = TypedChainId::Evm(5);
+        let resource_id = ResourceId::new(
+            TargetSystem::new_contract_address([1u8; 20]),
+            chain_id,
+        );
+        let cost = TxCost {
+            gas_used: 100.into(),
+            gas_spent: 1_000.into(),
+            value_sent: 0.into(),
+        };
+        let today = crate::current_day();
+        store.insert_expected_fee([1u8; 64], 0.into()).unwrap();
+        let reserve =
+            |key, max| store.reserve_subsidized_tx(chain_id, key, max).unwrap();
+        assert!(reserve([1u8; 64], Some(2)));
+        // enqueuing the same transaction again does not count it twice.
+        assert!(reserve([1u8; 64], Some(2)));
+        assert!(reserve([2u8; 64], Some(2)));
+        assert_eq!(store.get_daily_subsidized_txs(chain_id, today).unwrap(), 2);
+        // the cap is reached, until a reservation is released.
+        assert!(!reserve([4u8; 64], Some(2)));
+        store.release_subsidized_tx(chain_id, [2u8; 64]).unwrap();
+        assert!(reserve([4u8; 64], Some(2)));
+        store.release_subsidized_tx(chain_id, [4u8; 64]).unwrap();
+        assert!(reserve([2u8; 64], None));
+        assert_eq!(
+            store
+                .get_daily_subsidized_txs(TypedChainId::Evm(1), today)
+                .unwrap(),
+            0
+        );
+
+        store.settle_tx(resource_id, [1u8; 64], cost, true).unwrap();
+        store
+            .settle_tx(resource_id, [2u8; 64], cost, false)
+            .unwrap();
+        // a paying transaction is not a subsidy.
+        store.settle_tx(resource_id, [3u8; 64], cost, true).unwrap();
+        let ledgers = store.get_profit_and_loss().unwrap();
+        assert_eq!(ledgers[0].1.txs_subsidized, 2);
+        assert_eq!(ledgers[0].1.gas_subsidized, 2_000.into());
+        assert_eq!(ledgers[0].1.gas_spent, 3_000.into());
+        // settled subsidies still count towards the daily cap.
+        assert_eq!(store.get_daily_subsidized_txs(chain_id, today).unwrap(), 2);
+    }
+
     #[test]
     fn test_get_item_works() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1268,6 +4624,9 @@ mod tests {
                     |item1: &mut QueueItem<TypedTransaction>| {
                         let state = QueueItemState::Processed {
                             tx_hash: tx_hash.into(),
+                            block_number: None,
+                            gas_used: None,
+                            effective_fee: None,
                         };
                         item1.set_state(state);
                         Ok(())
@@ -1297,8 +4656,247 @@ mod tests {
             assert_eq!(item_key_found, tx_item_key);
             let expect_item_state = QueueItemState::Processed {
                 tx_hash: tx.sighash().0.into(),
+                block_number: None,
+                gas_used: None,
+                effective_fee: None,
             };
             assert_eq!(item.state(), expect_item_state);
         }
     }
+
+    #[test]
+    fn encrypted_store_should_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path())
+            .unwrap()
+            .with_encryption_key([7u8; 32]);
+        let chain_id = 1u32;
+        let tx: TypedTransaction = TransactionRequest::pay(
+            types::Address::random(),
+            types::U256::one(),
+        )
+        .into();
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        store.enqueue_item(key, QueueItem::new(tx.clone())).unwrap();
+        // the raw values are sealed.
+        let tree = store.db.open_tree(format!("queue_{}", key.queue_name()));
+        let (_, raw) =
+            tree.unwrap().scan_prefix(b"item").next().unwrap().unwrap();
+        assert!(StoreCipher::is_sealed(&raw));
+        let item: QueueItem<TypedTransaction> =
+            store.get_item(key).unwrap().unwrap();
+        assert_eq!(item.inner(), tx);
+
+        let history_store_key = (
+            TypedChainId::Evm(chain_id),
+            TargetSystem::new_contract_address([1u8; 20]),
+        );
+        let leaf = types::H256::random();
+        store
+            .insert_leaves_and_last_deposit_block_number(
+                history_store_key,
+                &[(0, leaf.to_fixed_bytes().to_vec())],
+                20,
+            )
+            .unwrap();
+        let leaves = store.get_leaves(history_store_key).unwrap();
+        assert_eq!(leaves.get(&0), Some(&leaf));
+
+        // without the key, the sealed values can not be read.
+        let store = SledStore {
+            cipher: None,
+            ..store
+        };
+        let result: crate::Result<Option<QueueItem<TypedTransaction>>> =
+            store.get_item(key);
+        assert!(result.is_err());
+        assert!(store.get_leaves(history_store_key).is_err());
+    }
+
+    #[test]
+    fn retention_purge_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap().with_event_retention();
+        let chain_id = 1u32;
+        // the first item is pending, the second one has failed.
+        let mut item_keys = Vec::new();
+        for i in 0..2 {
+            let tx: TypedTransaction = TransactionRequest::pay(
+                types::Address::random(),
+                types::U256::one(),
+            )
+            .from(types::Address::random())
+            .into();
+            let mut item = QueueItem::new(tx.clone());
+            if i > 0 {
+                item.set_state(QueueItemState::Failed {
+                    reason: "nonce too low".to_string(),
+                });
+            }
+            let key = SledQueueKey::from_evm_tx(chain_id, &tx);
+            let item_key = key.item_key().unwrap();
+            store.enqueue_item(key, item).unwrap();
+            store
+                .insert_tx_hash(chain_id, item_key, types::H256::random())
+                .unwrap();
+            item_keys.push(types::H512::from_slice(&item_key));
+        }
+        // a batch is kept as long as one of its items is.
+        let batch_ids = [types::H256::random(), types::H256::random()];
+        store
+            .insert_tx_batch(chain_id, batch_ids[0], &item_keys)
+            .unwrap();
+        store
+            .insert_tx_batch(chain_id, batch_ids[1], &item_keys[1..])
+            .unwrap();
+        let enqueued_at =
+            store.queue_items("evm_tx_1").unwrap()[1].enqueued_at();
+        // items enqueued after the retention window are kept.
+        assert_eq!(
+            store
+                .purge_finished_queue_items("evm_tx_1", enqueued_at)
+                .unwrap(),
+            0
+        );
+        // pending items are never purged.
+        assert_eq!(
+            store
+                .purge_finished_queue_items("evm_tx_1", enqueued_at + 1)
+                .unwrap(),
+            1
+        );
+        let items = store.queue_items("evm_tx_1").unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].state(), QueueItemState::Pending);
+        // the tx hashes and the batches of the purged items go with them.
+        let tx_hashes = |item_key: types::H512| {
+            store
+                .get_tx_hashes(chain_id, item_key.to_fixed_bytes())
+                .unwrap()
+        };
+        assert_eq!(tx_hashes(item_keys[0]).len(), 1);
+        assert!(tx_hashes(item_keys[1]).is_empty());
+        assert!(store
+            .get_tx_batch(chain_id, batch_ids[0])
+            .unwrap()
+            .is_some());
+        assert!(store
+            .get_tx_batch(chain_id, batch_ids[1])
+            .unwrap()
+            .is_none());
+
+        let recipient = types::Address::random();
+        for relayed_at in [100, 200] {
+            store
+                .insert_relayed_tx(&RelayedTxRecord {
+                    chain_id,
+                    contract: types::Address::random(),
+                    recipient,
+                    token: types::Address::random(),
+                    item_key: types::H512::random(),
+                    fee: types::U256::zero(),
+                    refund: types::U256::zero(),
+                    relayed_at,
+                })
+                .unwrap();
+        }
+        assert_eq!(store.purge_relayed_txs_before(150).unwrap(), 1);
+        let records =
+            store.get_relayed_txs_by_recipient(recipient, None).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].relayed_at, 200);
+
+        let key = test_key(TypedChainId::Evm(chain_id));
+        store.store_event(key, b"event", 1).unwrap();
+        let stored_at = crate::unix_timestamp();
+        assert_eq!(store.purge_events_before(stored_at - 60).unwrap(), 0);
+        assert!(store.contains_event(b"event").unwrap());
+        assert_eq!(store.purge_events_before(stored_at + 1).unwrap(), 1);
+        assert!(!store.contains_event(b"event").unwrap());
+        // the block index entry is gone as well.
+        assert_eq!(store.prune_events(key, u64::MAX).unwrap(), 0);
+
+        // a pruned event leaves no time index entry behind.
+        store.store_event(key, b"event", 1).unwrap();
+        assert_eq!(store.prune_events(key, u64::MAX).unwrap(), 1);
+        let by_time_tree = store.db.open_tree("event_hashes_by_time").unwrap();
+        assert!(by_time_tree.is_empty());
+        // nor is an event indexed by time without the retention enabled.
+        let store = SledStore {
+            event_retention: false,
+            ..store
+        };
+        store.store_event(key, b"event", 1).unwrap();
+        assert!(by_time_tree.is_empty());
+    }
+
+    #[test]
+    fn relay_nonces_should_be_used_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let signer = types::Address::random();
+        assert!(!store.is_relay_nonce_used(signer, 1, 100).unwrap());
+        assert!(store.use_relay_nonce(signer, 1, 100, 400).unwrap());
+        // the nonce can't be used again until it expires.
+        assert!(store.is_relay_nonce_used(signer, 1, 200).unwrap());
+        assert!(!store.use_relay_nonce(signer, 1, 200, 500).unwrap());
+        // other nonces, and the same nonce of other signers, are free.
+        assert!(store.use_relay_nonce(signer, 2, 200, 500).unwrap());
+        let other = types::Address::random();
+        assert!(store.use_relay_nonce(other, 1, 200, 500).unwrap());
+        // a released nonce is free again.
+        store.release_relay_nonce(other, 1).unwrap();
+        assert!(store.use_relay_nonce(other, 1, 200, 500).unwrap());
+        // once expired, the nonce is free again.
+        assert!(!store.is_relay_nonce_used(signer, 1, 400).unwrap());
+        assert!(store.use_relay_nonce(signer, 1, 400, 700).unwrap());
+
+        assert_eq!(store.purge_relay_nonces_before(600).unwrap(), 2);
+        assert!(!store.use_relay_nonce(signer, 1, 600, 900).unwrap());
+        assert!(store.use_relay_nonce(signer, 2, 600, 900).unwrap());
+    }
+
+    #[test]
+    fn funding_transfers_should_be_used_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let tx_hash = types::H256::random();
+        assert!(store.use_funding_transfer(1, tx_hash).unwrap());
+        assert!(!store.use_funding_transfer(1, tx_hash).unwrap());
+        // the same hash on another chain is another transfer.
+        assert!(store.use_funding_transfer(2, tx_hash).unwrap());
+        // a released transfer can fund another deposit.
+        store.release_funding_transfer(1, tx_hash).unwrap();
+        assert!(store.use_funding_transfer(1, tx_hash).unwrap());
+    }
+
+    #[test]
+    fn forwarded_requests_should_be_replaced_in_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let forwarder = types::Address::random();
+        let sponsor = types::Address::random();
+        let (first, second) = (types::H512::random(), types::H512::random());
+        let last = store.last_forwarded_request(1, forwarder, sponsor);
+        assert_eq!(last.unwrap(), None);
+        assert!(store
+            .replace_forwarded_request(1, forwarder, sponsor, None, first)
+            .unwrap());
+        // a request built before the first one was recorded is rejected.
+        assert!(!store
+            .replace_forwarded_request(1, forwarder, sponsor, None, second)
+            .unwrap());
+        assert!(store
+            .replace_forwarded_request(
+                1,
+                forwarder,
+                sponsor,
+                Some(first),
+                second
+            )
+            .unwrap());
+        let last = store.last_forwarded_request(1, forwarder, sponsor);
+        assert_eq!(last.unwrap(), Some(second));
+    }
 }
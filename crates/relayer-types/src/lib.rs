@@ -1,6 +1,7 @@
 use std::sync::Arc;
 use webb::evm::ethers::{prelude::TimeLag, providers};
 use webb_relayer_utils::multi_provider::MultiProvider;
+use webb_relayer_utils::rpc_transport::RpcTransport;
 
 pub mod etherscan_api;
 pub mod mnemonic;
@@ -13,10 +14,10 @@ pub mod suri;
 pub type EthersTimeLagClient = TimeLag<
     Arc<
         providers::Provider<
-            providers::RetryClient<MultiProvider<providers::Http>>,
+            providers::RetryClient<MultiProvider<RpcTransport>>,
         >,
     >,
 >;
 
 pub type EthersClient =
-    providers::Provider<providers::RetryClient<MultiProvider<providers::Http>>>;
+    providers::Provider<providers::RetryClient<MultiProvider<RpcTransport>>>;
@@ -101,7 +101,29 @@ impl<'de> Deserialize<'de> for PrivateKey {
                         Ok(Secret::from(&private_key))
                     }
                 } else if value.starts_with('>') {
-                    todo!("Implement command execution to extract the private key")
+                    // Read secrets from the output of a command, i.e. a KMS
+                    let command = value.trim_start_matches('>').trim();
+                    tracing::trace!("Reading the secret from a command");
+                    let output = std::process::Command::new("sh")
+                        .arg("-c")
+                        .arg(command)
+                        .output()
+                        .map_err(|e| {
+                            serde::de::Error::custom(format!(
+                                "error while running the command: {e}"
+                            ))
+                        })?;
+                    if !output.status.success() {
+                        return Err(serde::de::Error::custom(format!(
+                            "the command exited with {}",
+                            output.status
+                        )));
+                    }
+                    let val = String::from_utf8_lossy(&output.stdout);
+                    let val = val.trim();
+                    Secret::from_str(val).map_err(|e| {
+                        serde::de::Error::custom(format!("{e}\n expected a 66 chars string (including the 0x prefix) but found {} char", val.len()))
+                    })
                 } else {
                     // if it doesn't contains special characters and has 12 or 24 words in it
                     let wallet = MnemonicBuilder::<English>::default()
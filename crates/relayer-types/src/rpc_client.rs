@@ -1,11 +1,27 @@
+use core::num::NonZeroU32;
 use jsonrpsee::async_client::ClientBuilder;
 use jsonrpsee::client_transport::ws::WsTransportClientBuilder;
 use jsonrpsee::core::client::Client;
 use jsonrpsee::core::JsonRawValue;
+use smoldot_light::{AddChainConfig, AddChainConfigJsonRpc};
 use webb::substrate::subxt::{self, rpc::RpcClientT};
 
-#[derive(Debug)]
-pub struct WebbRpcClient(pub Client);
+/// The RPC client of a Substrate node.
+pub enum WebbRpcClient {
+    /// Connected to a trusted node over a websocket.
+    Ws(Client),
+    /// Connected through an embedded smoldot light client.
+    LightClient(subxt::client::LightClientRpc),
+}
+
+impl core::fmt::Debug for WebbRpcClient {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Ws(client) => f.debug_tuple("Ws").field(client).finish(),
+            Self::LightClient(_) => f.write_str("LightClient"),
+        }
+    }
+}
 
 impl WebbRpcClient {
     pub async fn new(
@@ -25,7 +41,47 @@ impl WebbRpcClient {
             .max_notifs_per_subscription(4096)
             .build_with_tokio(sender, receiver);
 
-        Ok(Self(client))
+        Ok(Self::Ws(client))
+    }
+
+    /// Starts an embedded light client of the chain with the given spec.
+    ///
+    /// The `bootnodes`, if any, replace the ones of the chain spec.
+    pub fn light_client(
+        chain_spec: &str,
+        bootnodes: &[String],
+    ) -> webb_relayer_utils::Result<Self> {
+        let mut chain_spec: serde_json::Value =
+            serde_json::from_str(chain_spec)?;
+        if !bootnodes.is_empty() {
+            chain_spec["bootNodes"] = serde_json::json!(bootnodes);
+        }
+        let chain_spec = chain_spec.to_string();
+        let config = AddChainConfig {
+            specification: chain_spec.as_str(),
+            json_rpc: AddChainConfigJsonRpc::Enabled {
+                max_pending_requests: NonZeroU32::new(128)
+                    .expect("128 is not zero"),
+                max_subscriptions: 1024,
+            },
+            potential_relay_chains: core::iter::empty(),
+            database_content: "",
+            user_data: (),
+        };
+        let rpc = subxt::client::LightClientRpc::new(config).map_err(|_| {
+            webb_relayer_utils::Error::Generic("Light client failed to start")
+        })?;
+        Ok(Self::LightClient(rpc))
+    }
+
+    /// Whether the client is still connected to the chain.
+    ///
+    /// The light client runs in-process, so it is always connected.
+    pub fn is_connected(&self) -> bool {
+        match self {
+            Self::Ws(client) => client.is_connected(),
+            Self::LightClient(_) => true,
+        }
     }
 }
 
@@ -35,7 +91,10 @@ impl RpcClientT for WebbRpcClient {
         method: &'a str,
         params: Option<Box<JsonRawValue>>,
     ) -> subxt::rpc::RpcFuture<'a, Box<JsonRawValue>> {
-        self.0.request_raw(method, params)
+        match self {
+            Self::Ws(client) => client.request_raw(method, params),
+            Self::LightClient(rpc) => rpc.request_raw(method, params),
+        }
     }
 
     fn subscribe_raw<'a>(
@@ -44,6 +103,9 @@ impl RpcClientT for WebbRpcClient {
         params: Option<Box<JsonRawValue>>,
         unsub: &'a str,
     ) -> subxt::rpc::RpcFuture<'a, subxt::rpc::RpcSubscription> {
-        self.0.subscribe_raw(sub, params, unsub)
+        match self {
+            Self::Ws(client) => client.subscribe_raw(sub, params, unsub),
+            Self::LightClient(rpc) => rpc.subscribe_raw(sub, params, unsub),
+        }
     }
 }
@@ -0,0 +1,257 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A rolling error-rate circuit breaker.
+//!
+//! Every chain the relayer talks to gets its own breaker. Watchers and
+//! transaction queues report the outcome of their RPC calls, and once the
+//! error rate over the rolling window crosses the configured threshold the
+//! breaker opens, pausing all work on that chain. After the cooldown a single
+//! probe is let through (half-open); a success closes the breaker again and a
+//! failure re-opens it for another cooldown. A probe whose outcome is never
+//! reported is given up after a cooldown, letting another one through.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use prometheus::core::{AtomicF64, GenericGauge};
+
+/// The state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CircuitState {
+    /// Requests flow normally.
+    Closed,
+    /// The cooldown elapsed and a probe request is allowed through.
+    HalfOpen,
+    /// The error threshold was exceeded, all requests are paused.
+    Open,
+}
+
+impl CircuitState {
+    /// Numeric representation of the state, used for metrics.
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Self::Closed => 0.0,
+            Self::HalfOpen => 1.0,
+            Self::Open => 2.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// Outcomes inside the rolling window, `true` for failures.
+    samples: VecDeque<(Instant, bool)>,
+    state: CircuitState,
+    opened_at: Option<Instant>,
+    /// When the in-flight probe of the half-open breaker was let through.
+    probing_since: Option<Instant>,
+}
+
+/// Tracks the rolling error rate of a chain and trips once it is exceeded.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    error_threshold: f64,
+    min_samples: usize,
+    window: Duration,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+    state_gauge: Option<GenericGauge<AtomicF64>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a new closed circuit breaker.
+    ///
+    /// * `error_threshold` - error rate (between 0 and 1) at which the breaker opens.
+    /// * `min_samples` - minimum number of outcomes in the window before the rate is evaluated.
+    /// * `window` - length of the rolling window.
+    /// * `cooldown` - how long the breaker stays open before probing.
+    pub fn new(
+        error_threshold: f64,
+        min_samples: usize,
+        window: Duration,
+        cooldown: Duration,
+    ) -> Self {
+        Self {
+            error_threshold,
+            min_samples,
+            window,
+            cooldown,
+            inner: Mutex::new(Inner {
+                samples: VecDeque::new(),
+                state: CircuitState::Closed,
+                opened_at: None,
+                probing_since: None,
+            }),
+            state_gauge: None,
+        }
+    }
+
+    /// Reports every state transition to the given gauge.
+    pub fn with_state_gauge(mut self, gauge: GenericGauge<AtomicF64>) -> Self {
+        gauge.set(CircuitState::Closed.as_f64());
+        self.state_gauge = Some(gauge);
+        self
+    }
+
+    /// Returns the current state of the breaker.
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Returns `true` if work should proceed.
+    ///
+    /// An open breaker whose cooldown has elapsed moves to half-open here,
+    /// allowing the caller to probe the chain. Only one probe is in flight
+    /// at a time, until its outcome is recorded.
+    pub fn is_request_allowed(&self) -> bool {
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => {
+                let probe_given_up = inner
+                    .probing_since
+                    .map(|at| at.elapsed() >= self.cooldown)
+                    .unwrap_or(true);
+                if probe_given_up {
+                    inner.probing_since = Some(Instant::now());
+                }
+                probe_given_up
+            }
+            CircuitState::Open => {
+                let cooled_down = inner
+                    .opened_at
+                    .map(|at| at.elapsed() >= self.cooldown)
+                    .unwrap_or(true);
+                if cooled_down {
+                    self.transition(&mut inner, CircuitState::HalfOpen);
+                    inner.probing_since = Some(Instant::now());
+                }
+                cooled_down
+            }
+        }
+    }
+
+    /// Records a successful call.
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::HalfOpen => {
+                inner.samples.clear();
+                self.transition(&mut inner, CircuitState::Closed);
+            }
+            CircuitState::Closed => self.push_sample(&mut inner, false),
+            CircuitState::Open => {}
+        }
+    }
+
+    /// Records a failed call, opening the breaker if the threshold is exceeded.
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::HalfOpen => {
+                self.transition(&mut inner, CircuitState::Open);
+            }
+            CircuitState::Closed => {
+                self.push_sample(&mut inner, true);
+                let total = inner.samples.len();
+                let failures =
+                    inner.samples.iter().filter(|(_, failed)| *failed).count();
+                if total >= self.min_samples
+                    && failures as f64 / total as f64 >= self.error_threshold
+                {
+                    self.transition(&mut inner, CircuitState::Open);
+                }
+            }
+            CircuitState::Open => {}
+        }
+    }
+
+    fn push_sample(&self, inner: &mut Inner, failed: bool) {
+        let now = Instant::now();
+        inner.samples.push_back((now, failed));
+        while let Some((at, _)) = inner.samples.front() {
+            if now.duration_since(*at) > self.window {
+                inner.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn transition(&self, inner: &mut Inner, state: CircuitState) {
+        if state == CircuitState::Open {
+            inner.opened_at = Some(Instant::now());
+        }
+        inner.probing_since = None;
+        if inner.state != state {
+            tracing::warn!(from = ?inner.state, to = ?state, "Circuit breaker transition");
+        }
+        inner.state = state;
+        if let Some(gauge) = &self.state_gauge {
+            gauge.set(state.as_f64());
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_threshold_and_recovers_after_probe() {
+        let breaker = CircuitBreaker::new(
+            0.5,
+            4,
+            Duration::from_secs(60),
+            Duration::from_millis(0),
+        );
+        breaker.record_success();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        // cooldown is zero, so the next request is the probe.
+        assert!(breaker.is_request_allowed());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn failed_probe_reopens() {
+        let breaker = CircuitBreaker::new(
+            0.5,
+            1,
+            Duration::from_secs(60),
+            Duration::from_secs(3600),
+        );
+        breaker.record_failure();
+        assert!(!breaker.is_request_allowed());
+        breaker.inner.lock().unwrap().opened_at = None;
+        assert!(breaker.is_request_allowed());
+        // the probe is in flight, nothing else goes through.
+        assert!(!breaker.is_request_allowed());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.is_request_allowed());
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Heartbeats of the supervised background tasks.
+//!
+//! A supervised task runs inside the scope of its [`Heartbeat`], and the
+//! critical loops (like the transaction queues) call [`beat`] on every
+//! iteration. A task that stops beating is stuck, even if it did not crash,
+//! which is what the systemd watchdog integration is built on.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+tokio::task_local! {
+    static CURRENT: Heartbeat;
+}
+
+/// The heartbeat of a single task.
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeat {
+    /// Unix timestamp (in seconds) of the last beat, `0` if it never beat.
+    last_beat_at: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    /// Records a beat now.
+    pub fn beat(&self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.last_beat_at.store(now, Ordering::Relaxed);
+    }
+
+    /// Unix timestamp (in seconds) of the last beat, if the task ever beat.
+    pub fn last_beat_at(&self) -> Option<u64> {
+        match self.last_beat_at.load(Ordering::Relaxed) {
+            0 => None,
+            at => Some(at),
+        }
+    }
+
+    /// Runs `fut` with this heartbeat as the one [`beat`] records to.
+    pub async fn scope<F: Future>(self, fut: F) -> F::Output {
+        CURRENT.scope(self, fut).await
+    }
+}
+
+/// Records a beat of the task the caller runs in.
+///
+/// Does nothing if the caller does not run inside a [`Heartbeat::scope`].
+pub fn beat() {
+    let _ = CURRENT.try_with(Heartbeat::beat);
+}
+
+/// The longest [`sleep`] goes without a beat, well within the time after
+/// which a task that stopped beating is taken as stuck.
+pub const BEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Sleeps for `duration`, beating at least every [`BEAT_INTERVAL`].
+///
+/// For the tasks that run periodically with an interval longer than the
+/// heartbeat timeout, so they are not taken as stuck while idle.
+pub async fn sleep(duration: Duration) {
+    let mut remaining = duration;
+    while !remaining.is_zero() {
+        let chunk = remaining.min(BEAT_INTERVAL);
+        tokio::time::sleep(chunk).await;
+        remaining -= chunk;
+        beat();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn beat_should_record_to_the_current_scope() {
+        let heartbeat = Heartbeat::default();
+        assert_eq!(heartbeat.last_beat_at(), None);
+        // outside of a scope, beats go nowhere.
+        beat();
+        assert_eq!(heartbeat.last_beat_at(), None);
+        heartbeat.clone().scope(async { beat() }).await;
+        assert!(heartbeat.last_beat_at().is_some());
+    }
+}
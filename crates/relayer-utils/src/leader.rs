@@ -0,0 +1,80 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Leadership of this relayer among its replicas.
+//!
+//! When several relayers watch the same anchors, only the leader signs and
+//! submits proposals. The [`Leadership`] handle is shared between the
+//! leader election task, which updates it, and the proposal queues and the
+//! bridge watchers, which check it before doing any work.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A shared flag telling whether this relayer is currently the leader.
+#[derive(Debug, Clone)]
+pub struct Leadership {
+    is_leader: Arc<AtomicBool>,
+}
+
+impl Leadership {
+    /// A relayer that is always the leader, used when the leader election
+    /// is disabled.
+    pub fn always() -> Self {
+        Self {
+            is_leader: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// A relayer that starts as a follower, until it wins the election.
+    pub fn follower() -> Self {
+        Self {
+            is_leader: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether this relayer is currently the leader.
+    pub fn is_leader(&self) -> bool {
+        self.is_leader.load(Ordering::Relaxed)
+    }
+
+    /// Updates the leadership, returns whether it changed.
+    pub fn set_leader(&self, is_leader: bool) -> bool {
+        self.is_leader.swap(is_leader, Ordering::Relaxed) != is_leader
+    }
+}
+
+impl Default for Leadership {
+    fn default() -> Self {
+        Self::always()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leadership_should_be_shared_between_clones() {
+        let leadership = Leadership::follower();
+        let other = leadership.clone();
+        assert!(!other.is_leader());
+        assert!(leadership.set_leader(true));
+        assert!(other.is_leader());
+        // setting it again is not a change.
+        assert!(!leadership.set_leader(true));
+        assert!(other.set_leader(false));
+        assert!(!leadership.is_leader());
+    }
+}
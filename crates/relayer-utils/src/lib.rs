@@ -12,30 +12,54 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
 use std::sync::Arc;
 
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use multi_provider::MultiProvider;
+use rpc_transport::RpcTransport;
 use webb::substrate::subxt::PolkadotConfig;
 use webb::{evm::ethers, substrate::subxt};
 use webb_proposals::ResourceId;
 
+/// Per-chain circuit breaker.
+pub mod circuit_breaker;
 pub mod clickable_link;
+/// Heartbeats of the supervised background tasks.
+pub mod heartbeat;
+/// Leadership of this relayer among its replicas.
+pub mod leader;
+/// Log filter of the relayer, tunable at runtime.
+pub mod log_filter;
 
 /// Metrics functionality
 pub mod metric;
 /// Multi provider for ethers.
 pub mod multi_provider;
+/// Global pause of the relayer, during maintenance.
+pub mod pause;
 /// A module used for debugging relayer lifecycle, sync state, or other relayer state.
 pub mod probe;
+/// Quorum of the endpoints of a chain for the reads used in safety checks.
+pub mod quorum_provider;
 /// Retry functionality
 pub mod retry;
+/// Decoding of the reverts of the contracts.
+pub mod revert;
+/// HTTP JSON-RPC transport with timeouts, throttling and metrics.
+pub mod rpc_transport;
 /// type-erased StaticTxPayload for Substrate Transaction queue.
 pub mod static_tx_payload;
 
 type RetryClientProvider = ethers::providers::Provider<
-    ethers::providers::RetryClient<MultiProvider<ethers::providers::Http>>,
+    ethers::providers::RetryClient<MultiProvider<RpcTransport>>,
+>;
+type QuorumClientProvider = ethers::providers::Provider<
+    quorum_provider::QuorumClient<
+        ethers::providers::RetryClient<MultiProvider<RpcTransport>>,
+        RpcTransport,
+    >,
 >;
 /// Type alias for runtime config of Tangle client.
 pub type TangleRuntimeConfig = PolkadotConfig;
@@ -113,6 +137,11 @@ pub enum Error {
     EthersContractCallWithRetryCloneable(
         #[from] ethers::contract::ContractError<Arc<RetryClientProvider>>,
     ),
+    /// Smart contract error, for the reads that require a quorum.
+    #[error(transparent)]
+    EthersContractCallWithQuorum(
+        #[from] ethers::contract::ContractError<QuorumClientProvider>,
+    ),
     /// Ethers Timelag provider error.
     #[error(transparent)]
     EthersTimelagRetryClientError(
@@ -146,6 +175,10 @@ pub enum Error {
         >,
     ),
 
+    /// Smart contract ABI encoding/decoding error.
+    #[error(transparent)]
+    EthersAbi(#[from] ethers::contract::AbiError),
+
     /// SCALE Codec error.
     #[error(transparent)]
     ScaleCodec(#[from] webb::substrate::scale::Error),
@@ -194,6 +227,12 @@ pub enum Error {
         /// The chain id of the chain.
         chain_id: String,
     },
+    /// None of the transports to an EVM chain could be opened.
+    #[error("No transport could be opened to chain: {}", chain_id)]
+    NoTransportAvailable {
+        /// The chain id of the chain.
+        chain_id: String,
+    },
     /// Substrate node not found.
     #[error("Node Not Found: {}", chain_id)]
     NodeNotFound {
@@ -218,6 +257,9 @@ pub enum Error {
     /// Arkworks Errors.
     #[error("{}", _0)]
     ArkworksError(String),
+    /// Encrypted keystore errors.
+    #[error("Keystore error: {}", _0)]
+    KeystoreError(String),
     /// Etherscan api configuration not found.
     #[error("Etherscan api configuration not found for chain : {}", chain_id)]
     EtherscanConfigNotFound {
@@ -249,6 +291,14 @@ pub enum Error {
     /// Invalid Proposals batch.
     #[error("Invalid proposals batch")]
     InvalidProposalsBatch,
+    /// A queue item can't move from its state to the given one.
+    #[error("Invalid queue item transition from {} to {}", from, to)]
+    InvalidQueueItemTransition {
+        /// The current state of the item.
+        from: &'static str,
+        /// The state the item was moved to.
+        to: &'static str,
+    },
 }
 
 /// Vanchor withdraw tx relaying errors.
@@ -263,15 +313,36 @@ pub enum TransactionRelayingError {
     /// Unsupported contract address
     #[error("Unsupported contract address: {0}")]
     UnsupportedContract(String),
+    /// The token is not in the allowlist of the chain
+    #[error(
+        "Unsupported token: {0} is not allowed to be relayed on this chain"
+    )]
+    UnsupportedToken(String),
     /// Invalid relayer address
     #[error("Invalid relayer address: {0}")]
     InvalidRelayerAddress(String),
     /// Invalid Merkle root
     #[error("Invalid Merkle roots")]
-    InvalidMerkleRoots,
+    InvalidMerkleRoots {
+        /// The recent roots known by the relayer, if the submitted roots
+        /// are not among them.
+        known_roots: Vec<ethers::types::H256>,
+    },
     /// Invalid refund amount
     #[error("InvalidRefundAmount: {0}")]
     InvalidRefundAmount(String),
+    /// The amount withdrawn or the fee is below the minimum of the contract
+    #[error(
+        "AmountTooLow: {kind} of {amount} is below the minimum of {minimum}"
+    )]
+    AmountTooLow {
+        /// What is too low, either the withdrawal amount or the fee
+        kind: AmountKind,
+        /// The amount sent by the user
+        amount: ethers::types::U256,
+        /// The current minimum accepted by the relayer
+        minimum: ethers::types::U256,
+    },
     /// Error while wrapping fee
     #[error("WrappingFeeError: {0}")]
     WrappingFeeError(String),
@@ -284,6 +355,81 @@ pub enum TransactionRelayingError {
     /// Client Error
     #[error("ClientError: {0}")]
     ClientError(String),
+    /// Leader election error
+    #[error("LeaderElectionError: {0}")]
+    LeaderElectionError(String),
+    /// The command spends nullifiers that are already spent
+    #[error("NullifierAlreadySpent: {0}")]
+    NullifierAlreadySpent(String),
+    /// The tokens to wrap and deposit can't be transferred to the relayer
+    #[error("InvalidFunding: {0}")]
+    InvalidFunding(String),
+    /// The deadline of the command already passed
+    #[error("DeadlinePassed: the deadline {0} already passed")]
+    DeadlinePassed(u64),
+    /// Invalid batch size
+    #[error("Invalid batch size: {0}, expected between 1 and {1} commands")]
+    InvalidBatchSize(usize, usize),
+    /// A command in the batch is invalid
+    #[error("Invalid command #{0} in batch: {1}")]
+    InvalidBatchCommand(usize, Box<TransactionRelayingError>),
+    /// The relayer is shutting down, and no longer accepts transactions
+    #[error("ShuttingDown: the relayer no longer accepts transactions")]
+    ShuttingDown,
+    /// The relayer is paused for maintenance
+    #[error("Paused: the relayer is paused and does not accept transactions")]
+    Paused,
+    /// The chain is in watch-only mode, the relayer does not send any
+    /// transaction to it
+    #[error("WatchOnlyChain: the relayer only watches chain {0}")]
+    WatchOnlyChain(u32),
+    /// The contract already has the maximum number of transactions waiting
+    /// or being processed in the queue of its chain
+    #[error(
+        "ResourceBusy: the contract already has {0} transactions in flight, \
+         try again later"
+    )]
+    ResourceBusy(u32),
+    /// The signed envelope of the command is missing, expired or not signed
+    /// by the recipient of the command
+    #[error("InvalidEnvelope: {0}")]
+    InvalidEnvelope(String),
+    /// The nonce of the signed envelope was already used by its signer
+    #[error("ReplayedCommand: the nonce {0} was already used")]
+    ReplayedCommand(u64),
+    /// A request of the sponsor of the trusted forwarder is still in flight,
+    /// the requests of a sponsor are relayed one at a time
+    #[error(
+        "ForwarderBusy: a request of the sponsor {0} is still in flight, \
+         try again later"
+    )]
+    ForwarderBusy(String),
+    /// The relayer already checks the maximum number of relay jobs in the
+    /// background
+    #[error(
+        "RelayerBusy: {0} relay jobs are already being checked, \
+         try again later"
+    )]
+    RelayerBusy(usize),
+}
+
+/// The amounts of a withdrawal that are subject to a minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AmountKind {
+    /// The amount withdrawn from the contract
+    Withdrawal,
+    /// The fee paid to the relayer
+    Fee,
+}
+
+impl fmt::Display for AmountKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountKind::Withdrawal => write!(f, "Withdrawal amount"),
+            AmountKind::Fee => write!(f, "Fee"),
+        }
+    }
 }
 
 /// A type alias for the result for webb relayer, that uses the `Error` enum.
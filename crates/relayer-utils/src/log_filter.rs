@@ -0,0 +1,101 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Log filter of the relayer, tunable at runtime.
+//!
+//! The [`LogFilterHandle`] is created along with the logger and shared with
+//! the admin API, so the filtering directives can be changed while the
+//! relayer is running, e.g. to trace a single chain while debugging,
+//! without restarting it and losing the state of its queues and watchers.
+
+use std::sync::{Arc, RwLock};
+
+type ReloadFn = dyn Fn(&str) -> Result<(), String> + Send + Sync;
+
+/// A shared handle to the filtering directives of the logger.
+#[derive(Clone, Default)]
+pub struct LogFilterHandle {
+    directives: Arc<RwLock<String>>,
+    reload: Option<Arc<ReloadFn>>,
+}
+
+impl std::fmt::Debug for LogFilterHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogFilterHandle")
+            .field("directives", &self.directives())
+            .field("reloadable", &self.reload.is_some())
+            .finish()
+    }
+}
+
+impl LogFilterHandle {
+    /// A handle to a logger started with the given directives, which are
+    /// replaced by calling `reload`.
+    pub fn new<F>(directives: impl Into<String>, reload: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Self {
+            directives: Arc::new(RwLock::new(directives.into())),
+            reload: Some(Arc::new(reload)),
+        }
+    }
+
+    /// The directives the logger currently filters with.
+    pub fn directives(&self) -> String {
+        self.directives
+            .read()
+            .map(|d| d.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the directives of the logger, the current ones are kept
+    /// if the new ones are invalid.
+    pub fn set_directives(&self, directives: &str) -> Result<(), String> {
+        let reload = self
+            .reload
+            .as_ref()
+            .ok_or_else(|| "The log filter is not reloadable".to_string())?;
+        reload(directives)?;
+        if let Ok(mut current) = self.directives.write() {
+            *current = directives.to_string();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directives_should_only_change_when_reloaded() {
+        let handle = LogFilterHandle::new("webb_relayer=info", |d: &str| {
+            if d.contains('!') {
+                Err(format!("invalid directives: {d}"))
+            } else {
+                Ok(())
+            }
+        });
+        let other = handle.clone();
+        assert!(other.set_directives("webb_probe=trace").is_ok());
+        assert_eq!(handle.directives(), "webb_probe=trace");
+        assert!(handle.set_directives("webb_probe=!").is_err());
+        assert_eq!(other.directives(), "webb_probe=trace");
+        // without a logger behind it, nothing can be reloaded.
+        let noop = LogFilterHandle::default();
+        assert!(noop.set_directives("webb_probe=trace").is_err());
+        assert_eq!(noop.directives(), "");
+    }
+}
@@ -13,13 +13,25 @@
 // limitations under the License.
 
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use prometheus::core::{AtomicF64, GenericCounter, GenericGauge};
 use prometheus::labels;
 use prometheus::opts;
-use prometheus::{register_counter, register_gauge, Encoder, TextEncoder};
+use prometheus::{
+    histogram_opts, register_counter, register_counter_vec, register_gauge,
+    register_gauge_vec, register_histogram, register_histogram_vec, Encoder,
+    TextEncoder,
+};
+use prometheus::{CounterVec, GaugeVec, Histogram, HistogramVec};
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
 
+use crate::rpc_transport::RpcMetrics;
+
+/// The most proposals waiting for their signature whose enqueue time is
+/// kept, so proposals that are never signed can't grow it forever.
+const MAX_PENDING_PROPOSAL_SIGNATURES: usize = 4096;
+
 /// A struct for collecting metrics for particular resource.
 #[derive(Debug, Clone)]
 pub struct ResourceMetric {
@@ -46,6 +58,8 @@ pub struct Metrics {
     pub proposals_processed_substrate_tx_queue: GenericCounter<AtomicF64>,
     /// Proposals dequeued and executed through transaction queue
     pub proposals_processed_evm_tx_queue: GenericCounter<AtomicF64>,
+    /// Proposal executions dropped because the bridge nonce advanced
+    pub proposals_dropped_stale_nonce: GenericCounter<AtomicF64>,
     /// Transaction queue backoff metric
     pub transaction_queue_back_off: GenericCounter<AtomicF64>,
     /// Substrate Transaction queue backoff metric
@@ -58,10 +72,43 @@ pub struct Metrics {
     pub gas_spent: GenericCounter<AtomicF64>,
     /// Total amount of data stored metric
     pub total_amount_of_data_stored: GenericGauge<AtomicF64>,
+    /// Number of keys stored per kind of tree
+    pub store_tree_keys: GaugeVec,
+    /// Time taken to flush the store to disk
+    pub store_flush_latency: Histogram,
+    /// Number of supervised background tasks currently running
+    pub supervised_tasks_running: GenericGauge<AtomicF64>,
+    /// Number of times supervised background tasks were restarted
+    pub supervised_task_restarts: GenericCounter<AtomicF64>,
+    /// Time from enqueueing a transaction to its finalization
+    time_to_finality: HistogramVec,
+    /// Number of times a broadcast transaction was found stuck
+    stuck_transactions: CounterVec,
+    /// Number of items enqueued per queue
+    queue_items_enqueued: CounterVec,
+    /// Number of items that left the pending states per queue
+    queue_items_dequeued: CounterVec,
+    /// Number of pending items per queue
+    queue_pending_items: GaugeVec,
+    /// Age of the oldest pending item per queue
+    queue_oldest_pending_item_age: GaugeVec,
+    /// Number of store entries purged by the retention policy per kind
+    store_purged_entries: CounterVec,
+    /// Time from a deposit event to the enqueue of its proposals
+    proposal_enqueue_latency: HistogramVec,
+    /// Time from the enqueue of a proposal to its signature
+    proposal_signing_latency: HistogramVec,
+    /// The target resource and the enqueue time of the proposals waiting
+    /// for their signature, by proposal hash
+    pending_proposal_signatures: HashMap<[u8; 32], (ResourceId, Instant)>,
     /// Resource metric
     resource_metric_map: HashMap<ResourceId, ResourceMetric>,
     /// Metric for account balance (in gwei) on specific chain
     account_balance: HashMap<TypedChainId, GenericGauge<AtomicF64>>,
+    /// Metric for circuit breaker state on specific chain
+    circuit_breaker_state: HashMap<TypedChainId, GenericGauge<AtomicF64>>,
+    /// Metrics for the RPC requests made to specific chain
+    rpc_metrics: HashMap<TypedChainId, RpcMetrics>,
 }
 
 impl Metrics {
@@ -103,6 +150,11 @@ impl Metrics {
             "Total number of signed proposals processed by evm transaction queue",
         )?;
 
+        let proposals_dropped_stale_nonce = register_counter!(
+            "proposals_dropped_stale_nonce",
+            "Total number of proposal executions dropped because the bridge nonce already advanced",
+        )?;
+
         let transaction_queue_back_off = register_counter!(
             "transaction_queue_back_off",
             "How many times the transaction queue backed off",
@@ -131,6 +183,94 @@ impl Metrics {
             "The Total number of data stored",
         )?;
 
+        let store_tree_keys = register_gauge_vec!(
+            "store_tree_keys",
+            "The number of keys stored per kind of tree",
+            &["tree"]
+        )?;
+
+        let store_flush_latency = register_histogram!(histogram_opts!(
+            "store_flush_duration_seconds",
+            "Time taken to flush the store to disk",
+            vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0],
+        ))?;
+
+        let supervised_tasks_running = register_gauge!(
+            "supervised_tasks_running",
+            "The number of supervised background tasks currently running",
+        )?;
+
+        let supervised_task_restarts = register_counter!(
+            "supervised_task_restarts",
+            "The number of times supervised background tasks were restarted",
+        )?;
+
+        let time_to_finality = register_histogram_vec!(
+            "time_to_finality_seconds",
+            "Time from enqueueing a transaction to its finalization on chain",
+            &["chain_type", "chain_id", "command"],
+            vec![
+                1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0,
+                3600.0, 10800.0,
+            ],
+        )?;
+
+        let stuck_transactions = register_counter_vec!(
+            "stuck_transactions",
+            "Number of times a broadcast transaction was not mined in time",
+            &["chain_type", "chain_id"]
+        )?;
+
+        let queue_items_enqueued = register_counter_vec!(
+            "queue_items_enqueued",
+            "Number of items enqueued in the queue",
+            &["queue"]
+        )?;
+
+        let queue_items_dequeued = register_counter_vec!(
+            "queue_items_dequeued",
+            "Number of items of the queue that were processed, failed or expired",
+            &["queue"]
+        )?;
+
+        let queue_pending_items = register_gauge_vec!(
+            "queue_pending_items",
+            "Number of items waiting or being processed in the queue",
+            &["queue"]
+        )?;
+
+        let queue_oldest_pending_item_age = register_gauge_vec!(
+            "queue_oldest_pending_item_age_seconds",
+            "Age of the oldest item waiting or being processed in the queue",
+            &["queue"]
+        )?;
+
+        let store_purged_entries = register_counter_vec!(
+            "store_purged_entries",
+            "Number of store entries purged by the data retention policy",
+            &["kind"]
+        )?;
+
+        let proposal_enqueue_latency = register_histogram_vec!(
+            "proposal_enqueue_latency_seconds",
+            "Time from a deposit event to the enqueue of its anchor update proposals",
+            &["resource_id"],
+            vec![
+                1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0,
+                3600.0,
+            ],
+        )?;
+
+        let proposal_signing_latency = register_histogram_vec!(
+            "proposal_signing_latency_seconds",
+            "Time from the enqueue of a proposal to its signature",
+            &["resource_id"],
+            vec![
+                1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0,
+                3600.0, 10800.0,
+            ],
+        )?;
+
         Ok(Self {
             bridge_watcher_back_off,
             total_transaction_made,
@@ -139,14 +279,31 @@ impl Metrics {
             proposals_processed_tx_queue,
             proposals_processed_substrate_tx_queue,
             proposals_processed_evm_tx_queue,
+            proposals_dropped_stale_nonce,
             transaction_queue_back_off,
             substrate_transaction_queue_back_off,
             evm_transaction_queue_back_off,
             total_fee_earned,
             gas_spent,
             total_amount_of_data_stored,
+            store_tree_keys,
+            store_flush_latency,
+            supervised_tasks_running,
+            supervised_task_restarts,
+            time_to_finality,
+            stuck_transactions,
+            queue_items_enqueued,
+            queue_items_dequeued,
+            queue_pending_items,
+            queue_oldest_pending_item_age,
+            store_purged_entries,
+            proposal_enqueue_latency,
+            proposal_signing_latency,
+            pending_proposal_signatures: Default::default(),
             resource_metric_map: Default::default(),
             account_balance: Default::default(),
+            circuit_breaker_state: Default::default(),
+            rpc_metrics: Default::default(),
         })
     }
 
@@ -178,6 +335,13 @@ impl Metrics {
         }
     }
 
+    /// Returns the metrics of every resource the relayer kept metrics for.
+    pub fn resource_metrics(
+        &self,
+    ) -> impl Iterator<Item = (&ResourceId, &ResourceMetric)> {
+        self.resource_metric_map.iter()
+    }
+
     pub fn resource_metric_entry(
         &mut self,
         resource_id: ResourceId,
@@ -207,6 +371,165 @@ impl Metrics {
         })
     }
 
+    /// Gauge tracking the circuit breaker state of a specific chain.
+    ///
+    /// `0` means closed, `1` half-open and `2` open.
+    pub fn circuit_breaker_state_entry(
+        &mut self,
+        chain: TypedChainId,
+    ) -> &mut GenericGauge<AtomicF64> {
+        self.circuit_breaker_state.entry(chain).or_insert_with(|| {
+            let chain_id = chain.underlying_chain_id().to_string();
+            register_gauge!(opts!(
+                "chain_circuit_breaker_state",
+                "Circuit breaker state on chain (0 closed, 1 half-open, 2 open)",
+                labels!(
+                    "chain_type" => Self::chain_name(chain),
+                    "chain_id" => &chain_id,
+                )
+            ))
+            .expect("create gauge for circuit breaker state")
+        })
+    }
+
+    /// Records how long it took for a transaction of the given command type
+    /// to be finalized on a specific chain, since it was enqueued.
+    pub fn observe_time_to_finality(
+        &self,
+        chain: TypedChainId,
+        command: &str,
+        elapsed: Duration,
+    ) {
+        let chain_id = chain.underlying_chain_id().to_string();
+        self.time_to_finality
+            .with_label_values(&[Self::chain_name(chain), &chain_id, command])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Records that a broadcast transaction on a specific chain was not mined
+    /// within the configured number of blocks.
+    pub fn record_stuck_transaction(&self, chain: TypedChainId) {
+        let chain_id = chain.underlying_chain_id().to_string();
+        self.stuck_transactions
+            .with_label_values(&[Self::chain_name(chain), &chain_id])
+            .inc();
+    }
+
+    /// Records a sample of the given queue: the items enqueued and dequeued
+    /// since the last sample, the items still pending and the age of the
+    /// oldest of them.
+    pub fn record_queue_sample(
+        &self,
+        queue: &str,
+        enqueued: usize,
+        dequeued: usize,
+        pending: usize,
+        oldest_pending_age: Option<Duration>,
+    ) {
+        self.queue_items_enqueued
+            .with_label_values(&[queue])
+            .inc_by(enqueued as f64);
+        self.queue_items_dequeued
+            .with_label_values(&[queue])
+            .inc_by(dequeued as f64);
+        self.queue_pending_items
+            .with_label_values(&[queue])
+            .set(pending as f64);
+        self.queue_oldest_pending_item_age
+            .with_label_values(&[queue])
+            .set(oldest_pending_age.unwrap_or_default().as_secs_f64());
+    }
+
+    /// Records the store entries of the given kind (i.e. `tx_history`)
+    /// purged by the data retention policy.
+    pub fn record_purged_entries(&self, kind: &str, purged: usize) {
+        self.store_purged_entries
+            .with_label_values(&[kind])
+            .inc_by(purged as f64);
+    }
+
+    /// Records that a proposal for the given (target) resource was enqueued
+    /// `since_deposit` after the deposit event it was made for, and starts
+    /// timing its signature.
+    pub fn record_proposal_enqueued(
+        &mut self,
+        resource_id: ResourceId,
+        proposal_hash: [u8; 32],
+        since_deposit: Option<Duration>,
+    ) {
+        if let Some(since_deposit) = since_deposit {
+            self.proposal_enqueue_latency
+                .with_label_values(&[&Self::resource_label(resource_id)])
+                .observe(since_deposit.as_secs_f64());
+        }
+        let pending = &mut self.pending_proposal_signatures;
+        if pending.len() >= MAX_PENDING_PROPOSAL_SIGNATURES {
+            // the proposals waiting for longer than the last bucket will
+            // not be signed anymore.
+            let max_wait = Duration::from_secs(10800);
+            pending.retain(|_, (_, at)| at.elapsed() < max_wait);
+            if pending.len() >= MAX_PENDING_PROPOSAL_SIGNATURES {
+                return;
+            }
+        }
+        pending
+            .entry(proposal_hash)
+            .or_insert_with(|| (resource_id, Instant::now()));
+    }
+
+    /// Records that the proposal with the given hash was signed, if its
+    /// enqueue was recorded.
+    pub fn record_proposal_signed(&mut self, proposal_hash: [u8; 32]) {
+        let Some((resource_id, enqueued_at)) =
+            self.pending_proposal_signatures.remove(&proposal_hash)
+        else {
+            return;
+        };
+        self.proposal_signing_latency
+            .with_label_values(&[&Self::resource_label(resource_id)])
+            .observe(enqueued_at.elapsed().as_secs_f64());
+    }
+
+    /// The label of a resource id, its hex encoding.
+    fn resource_label(resource_id: ResourceId) -> String {
+        format!("0x{}", hex::encode(resource_id.to_bytes()))
+    }
+
+    /// Metrics tracking the RPC requests made to a specific chain.
+    pub fn rpc_metrics_entry(&mut self, chain: TypedChainId) -> &RpcMetrics {
+        self.rpc_metrics.entry(chain).or_insert_with(|| {
+            let chain_type = Self::chain_name(chain);
+            let chain_id = chain.underlying_chain_id().to_string();
+            let requests = register_counter!(opts!(
+                "rpc_requests",
+                "Total number of RPC requests made to chain",
+                labels!("chain_type" => chain_type, "chain_id" => &chain_id)
+            ))
+            .expect("create counter for rpc requests");
+            let errors = register_counter!(opts!(
+                "rpc_request_errors",
+                "Total number of failed RPC requests made to chain",
+                labels!("chain_type" => chain_type, "chain_id" => &chain_id)
+            ))
+            .expect("create counter for rpc request errors");
+            let latency = register_histogram!(histogram_opts!(
+                "rpc_request_duration_seconds",
+                "Latency of the RPC requests made to chain",
+                prometheus::DEFAULT_BUCKETS.to_vec(),
+                labels!(
+                    "chain_type".to_string() => chain_type.to_string(),
+                    "chain_id".to_string() => chain_id.clone(),
+                )
+            ))
+            .expect("create histogram for rpc request latency");
+            RpcMetrics {
+                requests,
+                errors,
+                latency,
+            }
+        })
+    }
+
     /// Registers new counters to track metric for individual resources.
     fn register_resource_id_counters(
         resource_id: ResourceId,
@@ -0,0 +1,66 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Global pause of the relayer, during maintenance.
+//!
+//! While paused, the relayer rejects new relay requests and keeps the
+//! proposals queued instead of signing them, but the transactions already
+//! queued or broadcast are still processed. The [`PauseSwitch`] is shared
+//! between the admin API, which flips it, and everything it pauses.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A shared flag telling whether the relayer is paused.
+#[derive(Debug, Clone, Default)]
+pub struct PauseSwitch {
+    paused: Arc<AtomicBool>,
+}
+
+impl PauseSwitch {
+    /// A switch that starts paused or not.
+    pub fn new(paused: bool) -> Self {
+        Self {
+            paused: Arc::new(AtomicBool::new(paused)),
+        }
+    }
+
+    /// Whether the relayer is currently paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Pauses or resumes the relayer, returns whether it changed.
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.paused.swap(paused, Ordering::Relaxed) != paused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_should_be_shared_between_clones() {
+        let pause = PauseSwitch::default();
+        let other = pause.clone();
+        assert!(!other.is_paused());
+        assert!(pause.set_paused(true));
+        assert!(other.is_paused());
+        // pausing it again is not a change.
+        assert!(!other.set_paused(true));
+        assert!(other.set_paused(false));
+        assert!(!pause.is_paused());
+    }
+}
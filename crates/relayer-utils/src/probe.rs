@@ -13,9 +13,15 @@
 // limitations under the License.
 
 use derive_more::Display;
+use serde::Serialize;
+use tokio::sync::broadcast;
 /// Target for logger
 pub const TARGET: &str = "webb_probe";
 
+/// The number of probe events buffered for each subscriber of the
+/// [`ProbeBus`] before the slowest ones start missing events.
+const PROBE_BUS_CAPACITY: usize = 1024;
+
 /// The Kind of the Probe.
 #[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Kind {
@@ -50,3 +56,64 @@ pub enum Kind {
     #[display(fmt = "retry")]
     Retry,
 }
+
+/// A probe event, as captured from the tracing events of the [`TARGET`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeEvent {
+    /// The [`Kind`] of the probe, as displayed.
+    pub kind: Option<String>,
+    /// The time of the event (unix timestamp in milliseconds).
+    pub timestamp: u64,
+    /// The other fields of the event, like the chain id or the tx hash.
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A bus the probe events are published on, so they can be exported for
+/// downstream indexing. Publishing never blocks, and the events are dropped
+/// when nobody listens.
+#[derive(Debug, Clone)]
+pub struct ProbeBus {
+    tx: broadcast::Sender<ProbeEvent>,
+}
+
+impl ProbeBus {
+    /// Publishes the event to the current subscribers.
+    pub fn publish(&self, event: ProbeEvent) {
+        // an error only means there are no subscribers.
+        let _ = self.tx.send(event);
+    }
+
+    /// Subscribes to the events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<ProbeEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for ProbeBus {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(PROBE_BUS_CAPACITY);
+        Self { tx }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn probe_bus_should_publish_to_subscribers() {
+        let bus = ProbeBus::default();
+        let event = ProbeEvent {
+            kind: Some(Kind::TxQueue.to_string()),
+            timestamp: 1,
+            fields: serde_json::Map::new(),
+        };
+        // nobody listens yet, the event is dropped.
+        bus.publish(event.clone());
+        let mut rx = bus.subscribe();
+        bus.publish(event.clone());
+        assert_eq!(rx.recv().await.unwrap(), event);
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::Debug;
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use webb::evm::ethers::providers::{
+    JsonRpcClient, JsonRpcClientWrapper, Provider, ProviderError, Quorum,
+    QuorumProvider, WeightedProvider,
+};
+
+/// QuorumClient is a JsonRpcClient for the reads used in safety checks.
+///
+/// Without a quorum, the reads are forwarded to the regular client of the
+/// chain. With one, they are sent to all the endpoints of the chain, and a
+/// value is only trusted once enough of them agree on it.
+pub enum QuorumClient<C, P> {
+    /// The regular client of the chain.
+    Single(Arc<Provider<C>>),
+    /// All the endpoints of the chain, with the number that must agree.
+    Quorum(QuorumProvider<P>),
+}
+
+impl<C, P> Debug for QuorumClient<C, P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Single(_) => f.write_str("QuorumClient::Single"),
+            Self::Quorum(_) => f.write_str("QuorumClient::Quorum"),
+        }
+    }
+}
+
+impl<C, P> QuorumClient<C, P> {
+    /// Requires `quorum` of the given endpoints to agree on every read.
+    pub fn quorum(quorum: usize, providers: impl IntoIterator<Item = P>) -> Self
+    where
+        P: JsonRpcClientWrapper,
+    {
+        let providers = providers.into_iter().map(WeightedProvider::new);
+        Self::Quorum(QuorumProvider::new(
+            Quorum::ProviderCount(quorum),
+            providers,
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl<C, P> JsonRpcClient for QuorumClient<C, P>
+where
+    C: JsonRpcClient,
+    C::Error: Into<ProviderError>,
+    P: JsonRpcClientWrapper,
+{
+    type Error = ProviderError;
+
+    async fn request<
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    >(
+        &self,
+        method: &str,
+        params: T,
+    ) -> Result<R, Self::Error> {
+        match self {
+            Self::Single(provider) => {
+                let client: &C = (**provider).as_ref();
+                client.request(method, params).await.map_err(Into::into)
+            }
+            Self::Quorum(provider) => {
+                provider.request(method, params).await.map_err(Into::into)
+            }
+        }
+    }
+}
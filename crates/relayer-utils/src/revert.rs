@@ -0,0 +1,232 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use webb::evm::contract::protocol_solidity::{
+    fungible_token_wrapper::FUNGIBLETOKENWRAPPERCONTRACT_ABI,
+    masp_vanchor::MULTIASSETVANCHORCONTRACT_ABI,
+    signature_bridge::SIGNATUREBRIDGECONTRACT_ABI,
+    variable_anchor::VANCHORCONTRACT_ABI,
+};
+use webb::evm::ethers::abi::{Abi, AbiDecode, AbiError, Token};
+use webb::evm::ethers::types::{I256, U256};
+
+/// The selector of the `Error(string)` reverts of solidity.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// The selector of the `Panic(uint256)` reverts of solidity.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// The registry of the contracts of the relayer, built once.
+static REGISTRY: Lazy<RevertRegistry> = Lazy::new(RevertRegistry::new);
+
+/// The custom errors of the contracts, by selector, to decode the data of
+/// their reverts.
+#[derive(Debug, Clone, Default)]
+pub struct RevertRegistry {
+    errors: HashMap<[u8; 4], AbiError>,
+}
+
+impl RevertRegistry {
+    /// The registry of the errors of the VAnchor, MASP, SignatureBridge and
+    /// token wrapper contracts.
+    pub fn new() -> Self {
+        let mut registry = Self::default();
+        let abis: [&Abi; 4] = [
+            &*VANCHORCONTRACT_ABI,
+            &*MULTIASSETVANCHORCONTRACT_ABI,
+            &*SIGNATUREBRIDGECONTRACT_ABI,
+            &*FUNGIBLETOKENWRAPPERCONTRACT_ABI,
+        ];
+        for abi in abis {
+            registry.register(abi);
+        }
+        registry
+    }
+
+    /// Registers the custom errors of the given ABI.
+    ///
+    /// An error shared by several contracts is registered once.
+    pub fn register(&mut self, abi: &Abi) {
+        for error in abi.errors() {
+            let mut selector = [0u8; 4];
+            selector.copy_from_slice(&error.signature()[..4]);
+            self.errors.entry(selector).or_insert_with(|| error.clone());
+        }
+    }
+
+    /// Decodes the data of a revert, as `Error(string)`, `Panic(uint256)`
+    /// or one of the registered custom errors.
+    ///
+    /// Returns `None` for an unknown selector or malformed arguments.
+    pub fn decode(&self, data: &[u8]) -> Option<String> {
+        if data.len() < 4 {
+            return None;
+        }
+        let (selector, args) = data.split_at(4);
+        if selector == ERROR_SELECTOR {
+            return String::decode(args).ok();
+        }
+        if selector == PANIC_SELECTOR {
+            let code = U256::decode(args).ok()?;
+            return Some(format!("Panic(0x{code:x})"));
+        }
+        let error = self.errors.get(selector)?;
+        let args = error.decode(args).ok()?;
+        let args: Vec<_> = args.iter().map(format_token).collect();
+        Some(format!("{}({})", error.name, args.join(", ")))
+    }
+
+    /// The reason of a reverted call, from the error message of the node.
+    ///
+    /// Nodes put the ABI encoded revert in the data of the error, and for
+    /// `Error(string)` reverts usually the decoded reason in its message too.
+    pub fn revert_reason(&self, message: &str) -> Option<String> {
+        if let Some((_, reason)) = self.decode_blobs(message).into_iter().next()
+        {
+            return Some(reason);
+        }
+        let (_, reason) = message.split_once("execution reverted: ")?;
+        let reason = reason.split(|c| c == ',' || c == '"').next()?;
+        Some(reason.trim().to_string())
+    }
+
+    /// Replaces the hex encoded reverts in the given error message with
+    /// their decoded reason, leaving the rest of the message as is.
+    pub fn humanize(&self, message: &str) -> String {
+        let mut humanized = String::with_capacity(message.len());
+        let mut end = 0;
+        for (range, reason) in self.decode_blobs(message) {
+            humanized.push_str(&message[end..range.start]);
+            humanized.push_str(&reason);
+            end = range.end;
+        }
+        humanized.push_str(&message[end..]);
+        humanized
+    }
+
+    /// The `0x` prefixed hex blobs of the message that decode as a revert,
+    /// with their decoded reason.
+    fn decode_blobs(&self, message: &str) -> Vec<(Range<usize>, String)> {
+        let mut decoded = Vec::new();
+        let mut offset = 0;
+        while let Some(found) = message[offset..].find("0x") {
+            let start = offset + found;
+            let digits = message[start + 2..]
+                .find(|c: char| !c.is_ascii_hexdigit())
+                .unwrap_or(message.len() - start - 2);
+            let end = start + 2 + digits;
+            let reason = hex::decode(&message[start + 2..end])
+                .ok()
+                .and_then(|data| self.decode(&data));
+            if let Some(reason) = reason {
+                decoded.push((start..end, reason));
+            }
+            offset = end;
+        }
+        decoded
+    }
+}
+
+/// Decodes the data of a revert with the errors of the contracts of the
+/// relayer, see [`RevertRegistry::decode`].
+pub fn decode_revert_data(data: &[u8]) -> Option<String> {
+    REGISTRY.decode(data)
+}
+
+/// The reason of a reverted call from the error message of the node, see
+/// [`RevertRegistry::revert_reason`].
+pub fn revert_reason(message: &str) -> Option<String> {
+    REGISTRY.revert_reason(message)
+}
+
+/// Decodes the reverts in an error message before it is shown to the users,
+/// see [`RevertRegistry::humanize`].
+pub fn humanize_error(message: &str) -> String {
+    REGISTRY.humanize(message)
+}
+
+/// Formats an argument of a custom error the way solidity writes it.
+fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("{address:?}"),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => {
+            format!("0x{}", hex::encode(bytes))
+        }
+        Token::Int(value) => I256::from_raw(*value).to_string(),
+        Token::Uint(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => format!("{value:?}"),
+        Token::FixedArray(tokens) | Token::Array(tokens) => {
+            let tokens: Vec<_> = tokens.iter().map(format_token).collect();
+            format!("[{}]", tokens.join(", "))
+        }
+        Token::Tuple(tokens) => {
+            let tokens: Vec<_> = tokens.iter().map(format_token).collect();
+            format!("({})", tokens.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use webb::evm::ethers::abi::{parse_abi, AbiEncode};
+
+    fn registry() -> RevertRegistry {
+        let mut registry = RevertRegistry::default();
+        let abi = parse_abi(&["error InvalidAmount(uint256 amount)"]).unwrap();
+        registry.register(&abi);
+        registry
+    }
+
+    #[test]
+    fn should_decode_registered_custom_errors() {
+        let registry = registry();
+        let selector = *registry.errors.keys().next().unwrap();
+        let mut data = selector.to_vec();
+        data.extend(U256::from(42).encode());
+        assert_eq!(
+            registry.decode(&data).as_deref(),
+            Some("InvalidAmount(42)")
+        );
+        // unknown selectors are not guessed.
+        data[0] ^= 0xff;
+        assert_eq!(registry.decode(&data), None);
+    }
+
+    #[test]
+    fn should_humanize_reverts_in_error_messages() {
+        let registry = registry();
+        let mut data = ERROR_SELECTOR.to_vec();
+        data.extend("Invalid nonce".to_string().encode());
+        let message = format!(
+            "(code: 3, message: execution reverted, data: Some(String(\"0x{}\")))",
+            hex::encode(&data),
+        );
+        assert_eq!(
+            registry.humanize(&message),
+            "(code: 3, message: execution reverted, data: Some(String(\"Invalid nonce\")))",
+        );
+        assert_eq!(
+            registry.revert_reason(&message).as_deref(),
+            Some("Invalid nonce")
+        );
+        // addresses and hashes are not reverts.
+        let message = format!("tx 0x{} reverted", "ab".repeat(32));
+        assert_eq!(registry.humanize(&message), message);
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The JSON-RPC transport used by the EVM providers.
+//!
+//! It wraps the ethers [`Http`] or [`Ipc`] transport with connect/request
+//! timeouts, an optional request throttle, and per-chain request metrics.
+//! Together with the [`MultiProvider`](crate::multi_provider::MultiProvider)
+//! and the ethers `RetryClient` on top, it forms the provider middleware stack
+//! of each chain. Subscriptions use a separate websocket provider.
+
+use core::fmt::Debug;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use prometheus::core::{AtomicF64, GenericCounter};
+use prometheus::Histogram;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use url::Url;
+use webb::evm::ethers::providers::{Http, Ipc, JsonRpcClient, ProviderError};
+
+/// Metrics recorded for every JSON-RPC request of a chain.
+#[derive(Debug, Clone)]
+pub struct RpcMetrics {
+    /// Total number of requests made.
+    pub requests: GenericCounter<AtomicF64>,
+    /// Total number of requests that failed.
+    pub errors: GenericCounter<AtomicF64>,
+    /// Request latency in seconds.
+    pub latency: Histogram,
+}
+
+/// Spaces the requests out so that at most `requests_per_second` are sent.
+#[derive(Debug)]
+pub struct Throttle {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl Throttle {
+    /// Creates a new throttle allowing `requests_per_second` requests.
+    pub fn new(requests_per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / requests_per_second.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits until the next request is allowed to be sent.
+    pub async fn wait(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = core::cmp::max(*next_slot, Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// The underlying ethers transport.
+#[derive(Debug, Clone)]
+enum Inner {
+    Http(Http),
+    /// The IPC transport has no timeouts of its own.
+    Ipc {
+        client: Ipc,
+        request_timeout: Duration,
+    },
+}
+
+/// HTTP or IPC JSON-RPC transport with timeouts, throttling and metrics.
+#[derive(Debug, Clone)]
+pub struct RpcTransport {
+    inner: Inner,
+    throttle: Option<Arc<Throttle>>,
+    metrics: Option<RpcMetrics>,
+}
+
+impl RpcTransport {
+    /// Creates a new transport for the given endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The HTTP(s) endpoint of the RPC node.
+    /// * `connect_timeout` - Timeout for establishing a connection.
+    /// * `request_timeout` - Timeout for a whole request, from connecting until the response is read.
+    pub fn new(
+        url: Url,
+        connect_timeout: Duration,
+        request_timeout: Duration,
+    ) -> crate::Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(connect_timeout)
+            .timeout(request_timeout)
+            .build()?;
+        Ok(Self {
+            inner: Inner::Http(Http::new_with_client(url, client)),
+            throttle: None,
+            metrics: None,
+        })
+    }
+
+    /// Connects a new transport to the IPC socket of a local node.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the IPC socket of the node.
+    /// * `request_timeout` - Timeout for a whole request, until the response is read.
+    pub async fn connect_ipc(
+        path: impl AsRef<Path>,
+        request_timeout: Duration,
+    ) -> crate::Result<Self> {
+        let client = Ipc::connect(path).await.map_err(ProviderError::from)?;
+        Ok(Self {
+            inner: Inner::Ipc {
+                client,
+                request_timeout,
+            },
+            throttle: None,
+            metrics: None,
+        })
+    }
+
+    /// Throttles the requests of this transport, the throttle can be
+    /// shared between the transports of the same chain.
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Records the requests of this transport to the given metrics.
+    pub fn with_metrics(mut self, metrics: RpcMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl JsonRpcClient for RpcTransport {
+    type Error = ProviderError;
+
+    async fn request<
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    >(
+        &self,
+        method: &str,
+        params: T,
+    ) -> Result<R, Self::Error> {
+        if let Some(throttle) = &self.throttle {
+            throttle.wait().await;
+        }
+        let started_at = std::time::Instant::now();
+        let result = match &self.inner {
+            Inner::Http(client) => client
+                .request(method, params)
+                .await
+                .map_err(ProviderError::from),
+            Inner::Ipc {
+                client,
+                request_timeout,
+            } => {
+                let request = client.request(method, params);
+                match tokio::time::timeout(*request_timeout, request).await {
+                    Ok(result) => result.map_err(ProviderError::from),
+                    Err(_) => Err(ProviderError::CustomError(format!(
+                        "IPC request timed out after {request_timeout:?}"
+                    ))),
+                }
+            }
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.requests.inc();
+            metrics.latency.observe(started_at.elapsed().as_secs_f64());
+            if result.is_err() {
+                metrics.errors.inc();
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn throttle_spaces_requests() {
+        let throttle = Throttle::new(100);
+        let started_at = Instant::now();
+        for _ in 0..5 {
+            throttle.wait().await;
+        }
+        // the first request is sent right away, the rest 10ms apart.
+        assert!(started_at.elapsed() >= Duration::from_millis(40));
+    }
+}
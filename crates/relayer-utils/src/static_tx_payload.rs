@@ -16,9 +16,32 @@ pub struct TypeErasedStaticTxPayload {
 }
 
 impl TypeErasedStaticTxPayload {
+    /// Creates a payload from the already encoded data of the call.
+    pub fn new(
+        pallet_name: impl Into<String>,
+        call_name: impl Into<String>,
+        tx_data: Vec<u8>,
+        validation_hash: [u8; 32],
+    ) -> Self {
+        Self {
+            pallet_name: pallet_name.into(),
+            call_name: call_name.into(),
+            tx_data,
+            validation_hash,
+        }
+    }
+
     pub fn tx_data(&self) -> &[u8] {
         self.tx_data.as_slice()
     }
+
+    pub fn pallet_name(&self) -> &str {
+        &self.pallet_name
+    }
+
+    pub fn call_name(&self) -> &str {
+        &self.call_name
+    }
 }
 
 impl std::fmt::Debug for TypeErasedStaticTxPayload {
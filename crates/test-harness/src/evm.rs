@@ -0,0 +1,128 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use serde_json::json;
+use webb::evm::ethers::abi::{Abi, Tokenize};
+use webb::evm::ethers::contract::ContractFactory;
+use webb::evm::ethers::middleware::SignerMiddleware;
+use webb::evm::ethers::providers::{Http, Provider};
+use webb::evm::ethers::signers::{LocalWallet, Signer};
+use webb::evm::ethers::types::{Address, Bytes};
+use webb::evm::ethers::utils::{Anvil, AnvilInstance};
+
+/// The client used to send transactions to a local EVM chain.
+pub type LocalEvmClient = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// A local EVM chain, backed by an anvil node.
+///
+/// The first anvil account is the relayer wallet, the others are free to
+/// be used by the tests.
+pub struct LocalEvmChain {
+    name: String,
+    chain_id: u32,
+    anvil: AnvilInstance,
+}
+
+impl LocalEvmChain {
+    /// Spawns an anvil node with the given chain name and id.
+    ///
+    /// # Panics
+    ///
+    /// If the `anvil` binary is not in the `PATH`.
+    pub fn spawn(name: &str, chain_id: u32) -> Self {
+        let anvil = Anvil::new().chain_id(u64::from(chain_id)).spawn();
+        Self {
+            name: name.to_owned(),
+            chain_id,
+            anvil,
+        }
+    }
+
+    /// The name of the chain.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The chain id of the chain.
+    pub fn chain_id(&self) -> u32 {
+        self.chain_id
+    }
+
+    /// The HTTP endpoint of the node.
+    pub fn http_endpoint(&self) -> String {
+        self.anvil.endpoint()
+    }
+
+    /// The websocket endpoint of the node.
+    pub fn ws_endpoint(&self) -> String {
+        self.anvil.ws_endpoint()
+    }
+
+    /// The wallet of the anvil account at the given index.
+    pub fn wallet(&self, index: usize) -> LocalWallet {
+        LocalWallet::from(self.anvil.keys()[index].clone())
+            .with_chain_id(self.chain_id)
+    }
+
+    /// A client sending transactions from the anvil account at the given
+    /// index.
+    pub fn client(&self, index: usize) -> anyhow::Result<Arc<LocalEvmClient>> {
+        let provider = Provider::<Http>::try_from(self.http_endpoint())?;
+        Ok(Arc::new(SignerMiddleware::new(
+            provider,
+            self.wallet(index),
+        )))
+    }
+
+    /// Deploys a contract from the anvil account at the given index.
+    ///
+    /// Returns the address of the deployed contract.
+    pub async fn deploy<T: Tokenize>(
+        &self,
+        index: usize,
+        abi: Abi,
+        bytecode: Bytes,
+        args: T,
+    ) -> anyhow::Result<Address> {
+        let factory = ContractFactory::new(abi, bytecode, self.client(index)?);
+        let contract = factory.deploy(args)?.send().await?;
+        Ok(contract.address())
+    }
+
+    /// The relayer configuration of the chain, with the given contracts.
+    ///
+    /// The relayer uses the first anvil account as its wallet.
+    pub fn relayer_config(
+        &self,
+        contracts: Vec<serde_json::Value>,
+    ) -> serde_json::Value {
+        let private_key = hex::encode(self.anvil.keys()[0].to_bytes());
+        json!({
+            "evm": {
+                &self.name: {
+                    "name": &self.name,
+                    "chain-id": self.chain_id,
+                    "http-endpoint": self.http_endpoint(),
+                    "ws-endpoint": self.ws_endpoint(),
+                    "block-confirmations": 0,
+                    "private-key": format!("0x{private_key}"),
+                    "enabled": true,
+                    "contracts": contracts,
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Relayer Test Harness 🧪
+//!
+//! An in-process integration test harness for the relayer, so its features
+//! can be tested end to end with `cargo test`:
+//!
+//! - [`LocalEvmChain`] spins up an
+//!   [anvil](https://github.com/foundry-rs/foundry) node and deploys
+//!   contracts on it.
+//! - [`LocalTangleNode`] spins up a local Tangle node, from the binary at
+//!   `TANGLE_NODE_PATH` (or `tangle-standalone` in the `PATH`).
+//! - [`TestRelayer`] boots the relayer in-process with a test configuration
+//!   and drives its HTTP API.
+//!
+//! ## Usage
+//! ```rust,ignore
+//! use webb_relayer_test_harness::{LocalEvmChain, TestRelayer};
+//!
+//! let chain = LocalEvmChain::spawn("hermes", 5001);
+//! let relayer = TestRelayer::builder()
+//!     .evm_chain(&chain, vec![])
+//!     .start()
+//!     .await?;
+//! let status: serde_json::Value =
+//!     relayer.get(&format!("status/evm/{}", chain.chain_id())).await?;
+//! ```
+//!
+//! The nodes are killed, and the relayer shut down, once they are dropped.
+
+#![deny(unsafe_code)]
+#![warn(missing_docs)]
+
+/// Local EVM chains
+mod evm;
+/// In-process relayer
+mod relayer;
+/// Local Tangle nodes
+mod tangle;
+
+pub use evm::LocalEvmChain;
+pub use relayer::{TestRelayer, TestRelayerBuilder};
+pub use tangle::LocalTangleNode;
+
+use std::net::TcpListener;
+use std::time::Duration;
+
+/// Returns a local TCP port that is free to listen on.
+pub fn free_port() -> anyhow::Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Waits until something listens on the given local port.
+async fn wait_for_port(port: u16, timeout: Duration) -> anyhow::Result<()> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("nothing is listening on port {port}");
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
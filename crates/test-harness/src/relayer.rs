@@ -0,0 +1,171 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::task::JoinHandle;
+use webb_relayer::service::build_web_services;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::SledStore;
+
+use crate::{LocalEvmChain, LocalTangleNode};
+
+/// How long the relayer may take to start serving its API.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A relayer running in-process, serving its API on a local port.
+///
+/// The relayer is shut down once dropped.
+pub struct TestRelayer {
+    ctx: RelayerContext,
+    port: u16,
+    http: reqwest::Client,
+    server: JoinHandle<webb_relayer::Result<()>>,
+    _dir: TempDir,
+}
+
+impl TestRelayer {
+    /// Starts building the configuration of a test relayer.
+    pub fn builder() -> TestRelayerBuilder {
+        TestRelayerBuilder::default()
+    }
+
+    /// The context of the relayer.
+    pub fn context(&self) -> &RelayerContext {
+        &self.ctx
+    }
+
+    /// The URL of the given API path, relative to `/api/v1/`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://127.0.0.1:{}/api/v1/{path}", self.port)
+    }
+
+    /// Sends a GET request to the given API path, and decodes its response.
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> anyhow::Result<T> {
+        let response = self
+            .http
+            .get(self.url(path))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    /// Sends a POST request with the given JSON body to the given API path,
+    /// and decodes its response.
+    pub async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> anyhow::Result<T> {
+        let response = self
+            .http
+            .post(self.url(path))
+            .json(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+}
+
+impl Drop for TestRelayer {
+    fn drop(&mut self) {
+        self.ctx.shutdown();
+        self.server.abort();
+    }
+}
+
+/// Builds the configuration of a [`TestRelayer`] out of configuration
+/// fragments, which are loaded the same way as the files of a config
+/// directory.
+///
+/// The relayer uses the static fee oracle by default, so the tests do not
+/// depend on CoinGecko or Etherscan.
+#[derive(Default)]
+pub struct TestRelayerBuilder {
+    fragments: Vec<serde_json::Value>,
+}
+
+impl TestRelayerBuilder {
+    /// Adds a local EVM chain, with the given contracts.
+    pub fn evm_chain(
+        mut self,
+        chain: &LocalEvmChain,
+        contracts: Vec<serde_json::Value>,
+    ) -> Self {
+        self.fragments.push(chain.relayer_config(contracts));
+        self
+    }
+
+    /// Adds a local Tangle node under the given name, with the given pallets.
+    pub fn tangle_node(
+        mut self,
+        node: &LocalTangleNode,
+        name: &str,
+        pallets: Vec<serde_json::Value>,
+    ) -> Self {
+        self.fragments.push(node.relayer_config(name, pallets));
+        self
+    }
+
+    /// Adds a raw configuration fragment, in the format of the config files.
+    ///
+    /// The fragments added later take precedence over the earlier ones.
+    pub fn config(mut self, fragment: serde_json::Value) -> Self {
+        self.fragments.push(fragment);
+        self
+    }
+
+    /// Boots the relayer, and waits until it serves its API.
+    pub async fn start(self) -> anyhow::Result<TestRelayer> {
+        let dir = tempfile::tempdir()?;
+        let config_dir = dir.path().join("config");
+        std::fs::create_dir(&config_dir)?;
+        let port = crate::free_port()?;
+        let base = json!({
+            "port": port,
+            "fee-oracle": { "type": "Static" },
+        });
+        let fragments = std::iter::once(base).chain(self.fragments);
+        // the files are merged in the order of their names.
+        for (i, fragment) in fragments.enumerate() {
+            let path = config_dir.join(format!("{i:03}.json"));
+            std::fs::write(path, serde_json::to_vec_pretty(&fragment)?)?;
+        }
+        let config = webb_relayer_config::utils::load(&config_dir)?;
+        let store = SledStore::open(dir.path().join("store"))?;
+        let ctx = RelayerContext::new(config, store.clone()).await?;
+        let server = tokio::spawn(build_web_services(ctx.clone()));
+        webb_relayer::service::ignite(ctx.clone(), Arc::new(store)).await?;
+        let relayer = TestRelayer {
+            ctx,
+            port,
+            http: reqwest::Client::new(),
+            server,
+            _dir: dir,
+        };
+        crate::wait_for_port(port, STARTUP_TIMEOUT).await?;
+        tracing::debug!(port, "Test relayer started");
+        Ok(relayer)
+    }
+}
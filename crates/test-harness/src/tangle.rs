@@ -0,0 +1,106 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::process::{Child, Command};
+
+/// How long a Tangle node may take to start listening.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A local Tangle node, running a development chain.
+///
+/// The node is killed once dropped.
+pub struct LocalTangleNode {
+    chain_id: u32,
+    rpc_port: u16,
+    _process: Child,
+    _base_path: TempDir,
+}
+
+impl LocalTangleNode {
+    /// Spawns a Tangle development node with the given chain id, and waits
+    /// until its RPC server is up.
+    ///
+    /// The binary is taken from `TANGLE_NODE_PATH`, or `tangle-standalone`
+    /// in the `PATH`.
+    pub async fn spawn(chain_id: u32) -> anyhow::Result<Self> {
+        let binary = std::env::var("TANGLE_NODE_PATH")
+            .unwrap_or_else(|_| "tangle-standalone".to_owned());
+        let rpc_port = crate::free_port()?;
+        let base_path = tempfile::tempdir()?;
+        let process = Command::new(binary)
+            .arg("--dev")
+            .arg("--alice")
+            .arg("--base-path")
+            .arg(base_path.path())
+            .arg("--rpc-port")
+            .arg(rpc_port.to_string())
+            .arg("--rpc-cors")
+            .arg("all")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        crate::wait_for_port(rpc_port, STARTUP_TIMEOUT).await?;
+        Ok(Self {
+            chain_id,
+            rpc_port,
+            _process: process,
+            _base_path: base_path,
+        })
+    }
+
+    /// The chain id of the node.
+    pub fn chain_id(&self) -> u32 {
+        self.chain_id
+    }
+
+    /// The HTTP endpoint of the node.
+    pub fn http_endpoint(&self) -> String {
+        format!("http://127.0.0.1:{}", self.rpc_port)
+    }
+
+    /// The websocket endpoint of the node.
+    pub fn ws_endpoint(&self) -> String {
+        format!("ws://127.0.0.1:{}", self.rpc_port)
+    }
+
+    /// The relayer configuration of the node, with the given pallets.
+    ///
+    /// The relayer uses the `//Alice` account.
+    pub fn relayer_config(
+        &self,
+        name: &str,
+        pallets: Vec<serde_json::Value>,
+    ) -> serde_json::Value {
+        json!({
+            "substrate": {
+                name: {
+                    "name": name,
+                    "chain-id": self.chain_id,
+                    "http-endpoint": self.http_endpoint(),
+                    "ws-endpoint": self.ws_endpoint(),
+                    "runtime": "DKG",
+                    "suri": "//Alice",
+                    "enabled": true,
+                    "pallets": pallets,
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! End to end tests of the relayer API against local nodes.
+//!
+//! They need the `anvil` binary in the `PATH`, run them with
+//! `cargo test -p webb-relayer-test-harness -- --ignored`.
+
+use webb::evm::ethers::signers::Signer;
+use webb_relayer_test_harness::{LocalEvmChain, TestRelayer};
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn serves_health_and_evm_status() -> anyhow::Result<()> {
+    let chain = LocalEvmChain::spawn("hermes", 5001);
+    let relayer = TestRelayer::builder()
+        .evm_chain(&chain, vec![])
+        .start()
+        .await?;
+
+    let health: serde_json::Value = relayer.get("health").await?;
+    assert_eq!(health["healthy"], true);
+
+    let status: serde_json::Value = relayer
+        .get(&format!("status/evm/{}", chain.chain_id()))
+        .await?;
+    assert_eq!(status["chainId"], chain.chain_id());
+    let relayer_address = format!("{:?}", chain.wallet(0).address());
+    assert_eq!(status["address"], relayer_address);
+    assert_eq!(status["queued"], 0);
+    Ok(())
+}
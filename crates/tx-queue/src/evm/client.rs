@@ -0,0 +1,341 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
+use webb::evm::ethers::providers::{
+    Middleware, MiddlewareError, ProviderError,
+};
+use webb::evm::ethers::types::{
+    BlockId, BlockNumber, Transaction, TransactionReceipt, H256, U256, U64,
+};
+
+/// Why the dry run of a transaction failed.
+#[derive(Debug)]
+pub enum DryRunError {
+    /// The node ran the transaction, and it reverted.
+    Reverted(String),
+    /// The node could not run the transaction, e.g. it is unreachable.
+    Provider(ProviderError),
+}
+
+impl std::fmt::Display for DryRunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reverted(message) => write!(f, "{message}"),
+            Self::Provider(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// The chain operations the [`TxQueue`](super::TxQueue) sends its
+/// transactions with.
+///
+/// It is implemented for every ethers [`Middleware`], and by
+/// [`MockEvmTxClient`] in the tests, so the state machine of the queue can
+/// be tested without a node.
+#[async_trait::async_trait]
+pub trait EvmTxClient: Send + Sync {
+    /// The chain id of the chain.
+    async fn chain_id(&self) -> Result<U256, ProviderError>;
+    /// The latest block number.
+    async fn block_number(&self) -> Result<U64, ProviderError>;
+    /// The current gas price.
+    async fn gas_price(&self) -> Result<U256, ProviderError>;
+    /// The current max fee per gas and max priority fee per gas.
+    async fn eip1559_fees(&self) -> Result<(U256, U256), ProviderError>;
+    /// Simulates the transaction on top of the latest block.
+    async fn dry_run(&self, tx: &TypedTransaction) -> Result<(), DryRunError>;
+    /// Estimates the gas used by the transaction on top of the latest block.
+    async fn estimate_gas(
+        &self,
+        tx: &TypedTransaction,
+    ) -> Result<U256, ProviderError>;
+    /// The pending nonce of the account the transactions are sent from.
+    async fn pending_nonce(&self) -> Result<U256, ProviderError>;
+    /// Fills, signs and broadcasts the transaction.
+    ///
+    /// Returns the hash of the broadcast transaction.
+    async fn send_tx(
+        &self,
+        tx: TypedTransaction,
+    ) -> Result<H256, ProviderError>;
+    /// The broadcast transaction with the given hash, if the node knows it.
+    async fn transaction(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Option<Transaction>, ProviderError>;
+    /// The receipt of the transaction with the given hash, once it is mined.
+    async fn transaction_receipt(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Option<TransactionReceipt>, ProviderError>;
+}
+
+fn provider_error<E: std::fmt::Display>(e: E) -> ProviderError {
+    ProviderError::CustomError(e.to_string())
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> EvmTxClient for M {
+    async fn chain_id(&self) -> Result<U256, ProviderError> {
+        Middleware::get_chainid(self).await.map_err(provider_error)
+    }
+
+    async fn block_number(&self) -> Result<U64, ProviderError> {
+        Middleware::get_block_number(self)
+            .await
+            .map_err(provider_error)
+    }
+
+    async fn gas_price(&self) -> Result<U256, ProviderError> {
+        Middleware::get_gas_price(self)
+            .await
+            .map_err(provider_error)
+    }
+
+    async fn eip1559_fees(&self) -> Result<(U256, U256), ProviderError> {
+        Middleware::estimate_eip1559_fees(self, None)
+            .await
+            .map_err(provider_error)
+    }
+
+    async fn dry_run(&self, tx: &TypedTransaction) -> Result<(), DryRunError> {
+        Middleware::call(self, tx, None)
+            .await
+            .map(|_| ())
+            .map_err(|e| match e.as_error_response() {
+                Some(rpc_error) if rpc_error.is_revert() => {
+                    DryRunError::Reverted(e.to_string())
+                }
+                _ => DryRunError::Provider(provider_error(e)),
+            })
+    }
+
+    async fn estimate_gas(
+        &self,
+        tx: &TypedTransaction,
+    ) -> Result<U256, ProviderError> {
+        Middleware::estimate_gas(self, tx, None)
+            .await
+            .map_err(provider_error)
+    }
+
+    async fn pending_nonce(&self) -> Result<U256, ProviderError> {
+        let sender = Middleware::default_sender(self).ok_or_else(|| {
+            provider_error("the client has no account to send from")
+        })?;
+        let block = BlockId::Number(BlockNumber::Pending);
+        Middleware::get_transaction_count(self, sender, Some(block))
+            .await
+            .map_err(provider_error)
+    }
+
+    async fn send_tx(
+        &self,
+        tx: TypedTransaction,
+    ) -> Result<H256, ProviderError> {
+        let pending = Middleware::send_transaction(self, tx, None)
+            .await
+            .map_err(provider_error)?;
+        Ok(*pending)
+    }
+
+    async fn transaction(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Option<Transaction>, ProviderError> {
+        Middleware::get_transaction(self, tx_hash)
+            .await
+            .map_err(provider_error)
+    }
+
+    async fn transaction_receipt(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Option<TransactionReceipt>, ProviderError> {
+        Middleware::get_transaction_receipt(self, tx_hash)
+            .await
+            .map_err(provider_error)
+    }
+}
+
+/// What a [`MockEvmTxClient`] does with the transactions it is sent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MockTxOutcome {
+    /// The transaction is mined in the next block, and succeeds.
+    #[default]
+    Mined,
+    /// The transaction is mined in the next block, and reverts.
+    Reverted,
+    /// The dry run of the transaction reverts with the given reason.
+    DryRunFailed(String),
+    /// The node fails to dry run the transaction, with the given reason.
+    Unreachable(String),
+    /// The node rejects the transaction with the given reason.
+    Rejected(String),
+    /// The transaction is dropped from the mempool.
+    Dropped,
+}
+
+#[derive(Debug, Default)]
+struct MockChain {
+    block_number: u64,
+    gas_price: U256,
+    outcome: MockTxOutcome,
+    sent: Vec<TypedTransaction>,
+    transactions: HashMap<H256, Transaction>,
+    receipts: HashMap<H256, TransactionReceipt>,
+}
+
+/// An in-memory [`EvmTxClient`], mining the transactions it is sent
+/// according to the configured [`MockTxOutcome`].
+///
+/// The chain advances one block per sent transaction.
+#[derive(Debug, Clone)]
+pub struct MockEvmTxClient {
+    chain_id: U256,
+    chain: Arc<Mutex<MockChain>>,
+}
+
+impl MockEvmTxClient {
+    /// Creates a mock client of the given chain, mining every transaction.
+    pub fn new(chain_id: u32) -> Self {
+        let chain = MockChain {
+            gas_price: U256::from(1_000_000_000u64),
+            ..Default::default()
+        };
+        Self {
+            chain_id: chain_id.into(),
+            chain: Arc::new(Mutex::new(chain)),
+        }
+    }
+
+    /// Sets what happens to the transactions sent from now on.
+    pub fn set_outcome(&self, outcome: MockTxOutcome) {
+        self.chain.lock().outcome = outcome;
+    }
+
+    /// Sets the gas price of the chain.
+    pub fn set_gas_price(&self, gas_price: U256) {
+        self.chain.lock().gas_price = gas_price;
+    }
+
+    /// The transactions broadcast so far, in order.
+    pub fn sent_transactions(&self) -> Vec<TypedTransaction> {
+        self.chain.lock().sent.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl EvmTxClient for MockEvmTxClient {
+    async fn chain_id(&self) -> Result<U256, ProviderError> {
+        Ok(self.chain_id)
+    }
+
+    async fn block_number(&self) -> Result<U64, ProviderError> {
+        Ok(self.chain.lock().block_number.into())
+    }
+
+    async fn gas_price(&self) -> Result<U256, ProviderError> {
+        Ok(self.chain.lock().gas_price)
+    }
+
+    async fn eip1559_fees(&self) -> Result<(U256, U256), ProviderError> {
+        let gas_price = self.chain.lock().gas_price;
+        Ok((gas_price, gas_price))
+    }
+
+    async fn dry_run(&self, _tx: &TypedTransaction) -> Result<(), DryRunError> {
+        match &self.chain.lock().outcome {
+            MockTxOutcome::DryRunFailed(reason) => {
+                Err(DryRunError::Reverted(reason.clone()))
+            }
+            MockTxOutcome::Unreachable(reason) => Err(DryRunError::Provider(
+                ProviderError::CustomError(reason.clone()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    async fn estimate_gas(
+        &self,
+        _tx: &TypedTransaction,
+    ) -> Result<U256, ProviderError> {
+        Ok(U256::from(21_000u64))
+    }
+
+    async fn pending_nonce(&self) -> Result<U256, ProviderError> {
+        Ok(U256::from(self.chain.lock().sent.len()))
+    }
+
+    async fn send_tx(
+        &self,
+        tx: TypedTransaction,
+    ) -> Result<H256, ProviderError> {
+        let mut chain = self.chain.lock();
+        if let MockTxOutcome::Rejected(reason) = &chain.outcome {
+            return Err(ProviderError::CustomError(reason.clone()));
+        }
+        let nonce = tx.nonce().copied().unwrap_or(chain.sent.len().into());
+        chain.sent.push(tx.clone());
+        let tx_hash = H256::from_low_u64_be(chain.sent.len() as u64);
+        let gas_price = tx.gas_price().unwrap_or(chain.gas_price);
+        if chain.outcome == MockTxOutcome::Dropped {
+            return Ok(tx_hash);
+        }
+        chain.block_number += 1;
+        let block_number = U64::from(chain.block_number);
+        let transaction = Transaction {
+            hash: tx_hash,
+            nonce,
+            block_number: Some(block_number),
+            gas: tx.gas().copied().unwrap_or_default(),
+            gas_price: Some(gas_price),
+            ..Default::default()
+        };
+        let status = match chain.outcome {
+            MockTxOutcome::Reverted => 0u64,
+            _ => 1u64,
+        };
+        let receipt = TransactionReceipt {
+            transaction_hash: tx_hash,
+            block_number: Some(block_number),
+            gas_used: Some(U256::from(21_000u64)),
+            effective_gas_price: Some(gas_price),
+            status: Some(status.into()),
+            ..Default::default()
+        };
+        chain.transactions.insert(tx_hash, transaction);
+        chain.receipts.insert(tx_hash, receipt);
+        Ok(tx_hash)
+    }
+
+    async fn transaction(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Option<Transaction>, ProviderError> {
+        Ok(self.chain.lock().transactions.get(&tx_hash).cloned())
+    }
+
+    async fn transaction_receipt(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Option<TransactionReceipt>, ProviderError> {
+        Ok(self.chain.lock().receipts.get(&tx_hash).cloned())
+    }
+}
@@ -18,19 +18,27 @@ use std::time::Duration;
 use ethereum_types::U64;
 use futures::TryFutureExt;
 use rand::Rng;
+use webb::evm::contract::protocol_solidity::signature_bridge::ExecuteProposalWithSignatureCall;
+use webb::evm::ethers::abi::{self, ParamType, Token};
+use webb::evm::ethers::contract::EthCall;
 use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
-use webb::evm::ethers::middleware::SignerMiddleware;
-use webb::evm::ethers::prelude::TimeLag;
-use webb::evm::ethers::providers::Middleware;
+use webb::evm::ethers::providers::ProviderError;
 
 use webb::evm::ethers::types;
+use webb::evm::ethers::utils::id;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_config::StuckTxConfig;
 use webb_relayer_store::queue::{
-    QueueItemState, QueueStore, TransactionQueueItemKey,
+    QueueItem, QueueItemPriority, QueueItemState, QueueKey, QueueStore,
+    TransactionQueueItemKey,
 };
 use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{AccountingStore, TxCost, TxHashStore};
 use webb_relayer_utils::clickable_link::ClickableLink;
+use webb_relayer_utils::revert::humanize_error;
 
-use super::EvmTxQueueConfig;
+use super::{DryRunError, EvmTxClient, EvmTxQueueConfig, NonceTracker};
+use crate::stats::QueueSampler;
 
 /// The TxQueue stores transaction requests so the relayer can process them later.
 /// This prevents issues such as creating transactions with the same nonce.
@@ -49,7 +57,9 @@ where
 
 impl<S, C> TxQueue<S, C>
 where
-    S: QueueStore<TypedTransaction, Key = SledQueueKey>,
+    S: QueueStore<TypedTransaction, Key = SledQueueKey>
+        + AccountingStore
+        + TxHashStore,
     C: EvmTxQueueConfig,
 {
     /// Creates a new TxQueue instance.
@@ -73,16 +83,10 @@ where
     /// Returns a future that resolves `Ok(())` on success, otherwise returns an error.
     #[tracing::instrument(skip_all, fields(chain = %self.chain_id))]
     pub async fn run(self) -> webb_relayer_utils::Result<()> {
-        let provider = self.ctx.get_evm_provider(&self.chain_id).await?;
-        let wallet = self.ctx.get_evm_wallet(&self.chain_id).await?;
-        let signer_client = SignerMiddleware::new(provider, wallet);
-        let block_confirmations =
-            self.ctx.block_confirmations(&self.chain_id)?;
-
-        // TimeLag client
-        let client = TimeLag::new(signer_client, block_confirmations);
+        let client = self.ctx.evm_tx_client(&self.chain_id).await?;
+        let stuck_tx = self.ctx.stuck_tx(&self.chain_id)?;
         let chain_id = client
-            .get_chainid()
+            .chain_id()
             .map_err(|_| {
                 webb_relayer_utils::Error::Generic(
                     "Failed to fetch chain id from client",
@@ -104,8 +108,50 @@ where
             chain_id = %chain_id,
             starting = true,
         );
+        let circuit_breaker = self.ctx.circuit_breaker(&self.chain_id);
+        let queue_key = SledQueueKey::from_evm_chain_id(chain_id);
+        let sampler =
+            tokio::sync::Mutex::new(QueueSampler::new(queue_key.queue_name()));
+        let nonces = tokio::sync::Mutex::new(NonceTracker::new(
+            self.ctx.nonce_strategy(&self.chain_id)?,
+        ));
         let task = || async {
+            // the resource of the last item sent, the queue takes turns
+            // between resources so a busy one does not starve the others.
+            // Items without a resource (e.g. governance) never wait.
+            let mut last_served = None;
             loop {
+                // let the supervisor know the queue is not stuck.
+                webb_relayer_utils::heartbeat::beat();
+                // sample the queue into its throughput metrics.
+                let mut sampler = sampler.lock().await;
+                if sampler.is_due() {
+                    match store.queue_stats(queue_key, sampler.since()) {
+                        Ok(stats) => sampler
+                            .record(stats, &*self.ctx.metrics().lock().await),
+                        Err(e) => {
+                            tracing::warn!(%e, "Failed to sample the tx queue")
+                        }
+                    }
+                }
+                drop(sampler);
+                // Pause the queue while the chain is disabled at runtime.
+                if self.ctx.is_chain_disabled(&self.chain_id) {
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                    continue;
+                }
+                // Hold the items while the chain is under maintenance.
+                if self.ctx.maintenance_until(&self.chain_id).is_some() {
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                    continue;
+                }
+                // Pause the queue while the chain's circuit breaker is open.
+                if let Some(cb) = &circuit_breaker {
+                    if !cb.is_request_allowed() {
+                        tokio::time::sleep(Duration::from_millis(1000)).await;
+                        continue;
+                    }
+                }
                 let maybe_item = store
                     .peek_item(SledQueueKey::from_evm_chain_id(chain_id))?;
                 let maybe_explorer = self.ctx.explorer(&self.chain_id)?;
@@ -134,8 +180,36 @@ where
                     continue;
                 }
 
-                // Process transactions only when in pending state.
-                if item.state() != QueueItemState::Pending {
+                // Give up on the tx once its deadline passed, it would not
+                // be finalized in time anyway.
+                if item.is_past_deadline()
+                    && matches!(
+                        item.state(),
+                        QueueItemState::Pending
+                            | QueueItemState::WaitingForGasPrice { .. }
+                    )
+                {
+                    tracing::debug!(
+                        ?tx_hash,
+                        deadline = ?item.deadline(),
+                        "Tx deadline passed, expiring it",
+                    );
+                    store.shift_item_to_end(
+                        SledQueueKey::from_evm_with_custom_key(
+                            chain_id,
+                            tx_item_key,
+                        ),
+                        |item| item.transition_to(QueueItemState::Expired),
+                    )?;
+                    continue;
+                }
+                // Process transactions only when in pending state, or
+                // waiting for the gas price to drop.
+                if !matches!(
+                    item.state(),
+                    QueueItemState::Pending
+                        | QueueItemState::WaitingForGasPrice { .. }
+                ) {
                     // Shift it back to the end of the queue
                     // so that we can process other items.
                     store.shift_item_to_end(
@@ -149,24 +223,145 @@ where
                     tokio::time::sleep(Duration::from_millis(100)).await;
                     continue;
                 }
+                // Items depending on others, e.g. a deposit on the
+                // transactions funding it, wait until those are processed,
+                // and fail if any of them did not go through.
+                match dependencies_of(&*store, chain_id, &item)? {
+                    Dependencies::Processed => {}
+                    Dependencies::Pending => {
+                        store.shift_item_to_end(
+                            SledQueueKey::from_evm_with_custom_key(
+                                chain_id,
+                                tx_item_key,
+                            ),
+                            // Do not update the state.
+                            |_| Ok(()),
+                        )?;
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                    Dependencies::Failed(reason) => {
+                        tracing::warn!(
+                            ?tx_hash,
+                            %reason,
+                            "Dependency of the tx failed, failing it too",
+                        );
+                        store.shift_item_to_end(
+                            SledQueueKey::from_evm_with_custom_key(
+                                chain_id,
+                                tx_item_key,
+                            ),
+                            |item| {
+                                let state = QueueItemState::Failed {
+                                    reason: reason.clone(),
+                                };
+                                item.transition_to(state)
+                            },
+                        )?;
+                        continue;
+                    }
+                }
+                // Lower priority items (e.g. treasury sweeps) wait for the
+                // other pending items.
+                if item.priority() < QueueItemPriority::Normal
+                    && store.has_pending_items_above(
+                        SledQueueKey::from_evm_chain_id(chain_id),
+                        item.priority(),
+                    )?
+                {
+                    store.shift_item_to_end(
+                        SledQueueKey::from_evm_with_custom_key(
+                            chain_id,
+                            tx_item_key,
+                        ),
+                        // Do not update the state.
+                        |_| Ok(()),
+                    )?;
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+                // Let the pending items of the other resources go first when
+                // this item's resource was the last one served.
+                if item.resource_id().is_some()
+                    && item.resource_id() == last_served
+                    && store.has_pending_items_besides(
+                        SledQueueKey::from_evm_chain_id(chain_id),
+                        item.resource_id(),
+                        item.priority(),
+                    )?
+                {
+                    store.shift_item_to_end(
+                        SledQueueKey::from_evm_with_custom_key(
+                            chain_id,
+                            tx_item_key,
+                        ),
+                        // Do not update the state.
+                        |_| Ok(()),
+                    )?;
+                    continue;
+                }
+                // Pause the transactions once the daily gas budget of the
+                // chain is spent, only the governance ones still go.
+                if !item.is_budget_exempt()
+                    && is_gas_budget_exceeded(&self.ctx, &*store, chain_id)
+                        .await
+                {
+                    store.shift_item_to_end(
+                        SledQueueKey::from_evm_with_custom_key(
+                            chain_id,
+                            tx_item_key,
+                        ),
+                        // Do not update the state.
+                        |_| Ok(()),
+                    )?;
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                    continue;
+                }
+                // Never send a transaction above the gas price ceiling its
+                // client set, park it until the gas price drops instead.
+                if let Some(max_gas_price) = item.max_gas_price() {
+                    let outcome =
+                        pin_gas_price(&client, &mut raw_tx, max_gas_price)
+                            .await;
+                    let parked_state = match outcome {
+                        Ok(None) => None,
+                        Ok(Some(gas_price)) => {
+                            tracing::debug!(
+                                ?tx_hash,
+                                %gas_price,
+                                %max_gas_price,
+                                "Gas price is above the ceiling of the tx",
+                            );
+                            Some(QueueItemState::WaitingForGasPrice {
+                                max_gas_price,
+                                gas_price,
+                            })
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                %e,
+                                ?tx_hash,
+                                "Failed to get the gas price",
+                            );
+                            Some(item.state())
+                        }
+                    };
+                    if let Some(state) = parked_state {
+                        store.shift_item_to_end(
+                            SledQueueKey::from_evm_with_custom_key(
+                                chain_id,
+                                tx_item_key,
+                            ),
+                            |item| item.transition_to(state.clone()),
+                        )?;
+                        tokio::time::sleep(Duration::from_millis(1000)).await;
+                        continue;
+                    }
+                }
                 tracing::info!(?tx_hash, tx = ?raw_tx, "Found tx in queue");
-                // update transaction status as Processing.
-                store.update_item(
-                    SledQueueKey::from_evm_with_custom_key(
-                        chain_id,
-                        tx_item_key,
-                    ),
-                    |item| {
-                        let state = QueueItemState::Processing {
-                            step: "Item picked, processing".to_string(),
-                            progress: Some(0.0),
-                        };
-                        item.set_state(state);
-                        Ok(())
-                    },
-                )?;
+                last_served = item.resource_id();
                 // dry run test
-                let dry_run_outcome = client.call(&raw_tx.clone(), None).await;
+                let dry_run_outcome = client.dry_run(&raw_tx).await;
                 match dry_run_outcome {
                     Ok(_) => {
                         tracing::event!(
@@ -178,23 +373,19 @@ where
                             dry_run = "passed",
                             %tx_hash,
                         );
-                        // update transaction status as Processing and set progress.
+                        // update transaction status as Validated.
                         store.update_item(
                             SledQueueKey::from_evm_with_custom_key(
                                 chain_id,
                                 tx_item_key,
                             ),
                             |item| {
-                                let state = QueueItemState::Processing {
-                                    step: "Dry run passed".to_string(),
-                                    progress: Some(0.5),
-                                };
-                                item.set_state(state);
-                                Ok(())
+                                item.transition_to(QueueItemState::Validated)
                             },
                         )?;
                     }
                     Err(err) => {
+                        let reason = humanize_error(&err.to_string());
                         tracing::event!(
                             target: webb_relayer_utils::probe::TARGET,
                             tracing::Level::DEBUG,
@@ -202,10 +393,60 @@ where
                             ty = "EVM",
                             chain_id = %chain_id,
                             errored = true,
-                            error = %err,
+                            error = %reason,
                             dry_run = "failed",
                             %tx_hash,
                         );
+                        let key = SledQueueKey::from_evm_with_custom_key(
+                            chain_id,
+                            tx_item_key,
+                        );
+                        let is_proposal = is_proposal_execution(&raw_tx);
+                        // the proposal nonce of the bridge already advanced,
+                        // the proposal can never be executed anymore.
+                        if is_proposal && is_stale_proposal_nonce(&err) {
+                            tracing::warn!(
+                                ?tx_hash,
+                                error = %err,
+                                "Dropping proposal execution with a stale nonce",
+                            );
+                            tracing::event!(
+                                target: webb_relayer_utils::probe::TARGET,
+                                tracing::Level::DEBUG,
+                                kind = %webb_relayer_utils::probe::Kind::TxQueue,
+                                ty = "EVM",
+                                chain_id = %chain_id,
+                                dropped = "stale-proposal-nonce",
+                                %tx_hash,
+                            );
+                            store.remove_item(key)?;
+                            self.ctx
+                                .metrics()
+                                .lock()
+                                .await
+                                .proposals_dropped_stale_nonce
+                                .inc();
+                            continue;
+                        }
+                        // other failures of a proposal execution (e.g. its
+                        // predecessor is not executed yet) are retried, they
+                        // do not count towards the circuit breaker as they
+                        // are expected until the proposals are in order.
+                        if is_proposal {
+                            store.shift_item_to_end(key, |item| {
+                                item.transition_to(QueueItemState::Pending)
+                            })?;
+                            tokio::time::sleep(Duration::from_millis(1000))
+                                .await;
+                            continue;
+                        }
+                        // a revert is up to the tx, only the failures of
+                        // the node count towards the circuit breaker.
+                        if let (Some(cb), DryRunError::Provider(_)) =
+                            (&circuit_breaker, &err)
+                        {
+                            cb.record_failure();
+                        }
                         // update transaction status as Failed and re insert into queue.
                         store.shift_item_to_end(
                             SledQueueKey::from_evm_with_custom_key(
@@ -214,20 +455,65 @@ where
                             ),
                             |item| {
                                 let state = QueueItemState::Failed {
-                                    reason: err.to_string(),
+                                    reason: reason.clone(),
                                 };
-                                item.set_state(state);
-                                Ok(())
+                                item.transition_to(state)
                             },
                         )?;
                         continue; // keep going.
                     }
                 }
 
-                let pending_tx = client.send_transaction(raw_tx.clone(), None);
-                let tx = match pending_tx.await {
-                    Ok(pending) => {
-                        let signed_tx_hash = *pending;
+                // the transactions enqueued without a gas limit are sent with
+                // the headroom of their contract over the estimate.
+                if let (None, Some(to)) = (raw_tx.gas(), raw_tx.to_addr()) {
+                    let to = *to;
+                    match client.estimate_gas(&raw_tx).await {
+                        Ok(gas) => {
+                            let gas = self.ctx.gas_limit_with_headroom(
+                                &self.chain_id,
+                                to,
+                                gas,
+                            )?;
+                            raw_tx.set_gas(gas);
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                e = %humanize_error(&e.to_string()),
+                                ?tx_hash,
+                                "Failed to estimate the gas of the tx",
+                            );
+                        }
+                    }
+                }
+
+                // the transactions enqueued without a nonce are sent with
+                // the one picked by the nonce strategy of the chain.
+                let mut nonces = nonces.lock().await;
+                if raw_tx.nonce().is_none() {
+                    match nonces.next_nonce(&client).await {
+                        Ok(Some(nonce)) => {
+                            raw_tx.set_nonce(nonce);
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            tracing::warn!(
+                                %e,
+                                ?tx_hash,
+                                "Failed to get the nonce of the tx",
+                            );
+                        }
+                    }
+                }
+                let sent = client.send_tx(raw_tx.clone()).await;
+                match &sent {
+                    Ok(_) => nonces.on_sent(),
+                    Err(_) => nonces.reset(),
+                }
+                drop(nonces);
+
+                let tx = match sent {
+                    Ok(signed_tx_hash) => {
                         tracing::event!(
                             target: webb_relayer_utils::probe::TARGET,
                             tracing::Level::DEBUG,
@@ -256,25 +542,50 @@ where
                                 tx_hash_string,
                             );
                         }
-                        // update transaction progress.
+                        // keep track of the hash, it changes when replaced.
+                        store.insert_tx_hash(
+                            chain_id,
+                            tx_item_key,
+                            signed_tx_hash,
+                        )?;
+                        // update transaction status as Broadcast.
                         store.update_item(
                             SledQueueKey::from_evm_with_custom_key(
                                 chain_id,
                                 tx_item_key,
                             ),
                             |item| {
-                                let state = QueueItemState::Processing {
-                                    step: "Transaction submitted on chain.."
-                                        .to_string(),
-                                    progress: Some(0.8),
-                                };
-                                item.set_state(state);
-                                Ok(())
+                                let state =
+                                    QueueItemState::Broadcast { attempt: 1 };
+                                item.transition_to(state)
                             },
                         )?;
-                        pending.interval(Duration::from_millis(1000)).await
+                        match stuck_tx {
+                            Some(config) => {
+                                let sent = SentItem {
+                                    chain_id,
+                                    item_key: tx_item_key,
+                                    raw_tx: &raw_tx,
+                                    max_gas_price: item.max_gas_price(),
+                                    deadline: item.deadline(),
+                                };
+                                wait_for_stuck_tx(
+                                    &self.ctx,
+                                    &*store,
+                                    &client,
+                                    signed_tx_hash,
+                                    sent,
+                                    config,
+                                )
+                                .await
+                            }
+                            None => {
+                                wait_for_receipt(&client, signed_tx_hash).await
+                            }
+                        }
                     }
                     Err(e) => {
+                        let reason = humanize_error(&e.to_string());
                         let tx_hash_string = format!("0x{tx_hash:x}");
                         if let Some(mut url) = maybe_explorer.clone() {
                             url.set_path(&format!("tx/{tx_hash_string}"));
@@ -285,13 +596,13 @@ where
                             tracing::error!(
                                 "Error while sending tx {}, {}",
                                 clickable_link,
-                                e,
+                                reason,
                             );
                         } else {
                             tracing::error!(
                                 "Error while sending tx {}, {}",
                                 tx_hash_string,
-                                e
+                                reason,
                             );
                         }
                         tracing::event!(
@@ -302,8 +613,11 @@ where
                             chain_id = %chain_id,
                             errored = true,
                             raw_tx_hash = %tx_hash,
-                            error = %e,
+                            error = %reason,
                         );
+                        if let Some(cb) = &circuit_breaker {
+                            cb.record_failure();
+                        }
 
                         // update transaction status as Failed
                         store.shift_item_to_end(
@@ -313,10 +627,9 @@ where
                             ),
                             |item| {
                                 let state = QueueItemState::Failed {
-                                    reason: e.to_string(),
+                                    reason: reason.clone(),
                                 };
-                                item.set_state(state);
-                                Ok(())
+                                item.transition_to(state)
                             },
                         )?;
 
@@ -325,15 +638,75 @@ where
                 };
 
                 match tx {
+                    Ok(Some(receipt)) if is_cancellation(&raw_tx, &receipt) => {
+                        // the tx was stuck past its deadline and cancelled.
+                        tracing::info!(
+                            ?tx_hash,
+                            cancellation_tx_hash = ?receipt.transaction_hash,
+                            "Tx was cancelled after its deadline passed",
+                        );
+                        account_settled_tx(
+                            &*store,
+                            chain_id,
+                            &raw_tx,
+                            tx_item_key,
+                            &receipt,
+                            false,
+                        );
+                        store.shift_item_to_end(
+                            SledQueueKey::from_evm_with_custom_key(
+                                chain_id,
+                                tx_item_key,
+                            ),
+                            |item| item.transition_to(QueueItemState::Expired),
+                        )?;
+                    }
                     Ok(Some(receipt)) => {
                         let tx_hash_string =
                             format!("0x{:x}", receipt.transaction_hash);
-                        match receipt.status {
-                            Some(v) if v.is_zero() => {
-                                tracing::info!("Tx {} Failed", tx_hash_string);
-                                continue;
+                        let reverted =
+                            matches!(receipt.status, Some(v) if v.is_zero());
+                        // a forwarder does not revert when the call it
+                        // executes does.
+                        let forward_failed = !reverted
+                            && is_failed_forwarded_call(&raw_tx, &receipt);
+                        let succeeded = !reverted && !forward_failed;
+                        account_settled_tx(
+                            &*store,
+                            chain_id,
+                            &raw_tx,
+                            tx_item_key,
+                            &receipt,
+                            succeeded,
+                        );
+                        if !succeeded {
+                            tracing::info!("Tx {} Failed", tx_hash_string);
+                            if let Some(cb) = &circuit_breaker {
+                                cb.record_failure();
                             }
-                            _ => {}
+                            // the item is settled, it must not count as
+                            // pending anymore.
+                            let reason = if forward_failed {
+                                String::from("The forwarded call reverted")
+                            } else {
+                                reverted_tx_reason(&client, &raw_tx).await
+                            };
+                            store.shift_item_to_end(
+                                SledQueueKey::from_evm_with_custom_key(
+                                    chain_id,
+                                    tx_item_key,
+                                ),
+                                |item| {
+                                    let state = QueueItemState::Failed {
+                                        reason: reason.clone(),
+                                    };
+                                    item.transition_to(state)
+                                },
+                            )?;
+                            continue;
+                        }
+                        if let Some(cb) = &circuit_breaker {
+                            cb.record_success();
                         }
 
                         if let Some(mut url) = maybe_explorer.clone() {
@@ -367,11 +740,26 @@ where
                             |item| {
                                 let state = QueueItemState::Processed {
                                     tx_hash: receipt.transaction_hash,
+                                    block_number: receipt
+                                        .block_number
+                                        .map(|n| n.as_u64()),
+                                    gas_used: receipt.gas_used,
+                                    effective_fee: effective_fee(
+                                        &raw_tx, &receipt,
+                                    ),
                                 };
-                                item.set_state(state);
-                                Ok(())
+                                item.transition_to(state)
                             },
                         )?;
+                        self.ctx
+                            .metrics()
+                            .lock()
+                            .await
+                            .observe_time_to_finality(
+                                TypedChainId::Evm(chain_id),
+                                &command_type(&raw_tx),
+                                item.elapsed_since_enqueued(),
+                            );
                     }
                     Ok(None) => {
                         // this should never happen
@@ -384,6 +772,8 @@ where
                             "Tx {} Dropped from Mempool!!",
                             tx_hash_string
                         );
+                        // its nonce was never used.
+                        nonces.lock().await.reset();
                         // Re insert transaction in the queue.
                         store.shift_item_to_end(
                             SledQueueKey::from_evm_with_custom_key(
@@ -392,13 +782,14 @@ where
                             ),
                             |item| {
                                 let state = QueueItemState::Pending;
-                                item.set_state(state);
-                                Ok(())
+                                item.transition_to(state)
                             },
                         )?;
                     }
                     Err(e) => {
-                        let reason = e.to_string();
+                        // whether its nonce was used is unknown.
+                        nonces.lock().await.reset();
+                        let reason = humanize_error(&e.to_string());
                         let tx_hash_string = format!("0x{tx_hash:x}");
                         if let Some(mut url) = maybe_explorer.clone() {
                             url.set_path(&format!("tx/{tx_hash_string}"));
@@ -426,8 +817,11 @@ where
                             chain_id = %chain_id,
                             errored = true,
                             %tx_hash,
-                            error = %e,
+                            error = %reason,
                         );
+                        if let Some(cb) = &circuit_breaker {
+                            cb.record_failure();
+                        }
                         // Update transaction status and re insert in the queue.
                         store.shift_item_to_end(
                             SledQueueKey::from_evm_with_custom_key(
@@ -436,10 +830,9 @@ where
                             ),
                             |item| {
                                 let state = QueueItemState::Failed {
-                                    reason: e.to_string(),
+                                    reason: reason.clone(),
                                 };
-                                item.set_state(state);
-                                Ok(())
+                                item.transition_to(state)
                             },
                         )?;
                     }
@@ -459,3 +852,590 @@ where
         Ok(())
     }
 }
+
+/// How often the block number is polled while waiting for a broadcast
+/// transaction, to tell whether it is stuck.
+const STUCK_TX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How often a broadcast transaction is polled for its receipt.
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How many polls in a row a broadcast transaction may be unknown to the
+/// node before it is considered dropped from the mempool.
+const MAX_MISSING_TX_POLLS: u32 = 3;
+/// The minimum gas price bump most nodes accept to replace a transaction.
+const MIN_GAS_PRICE_BUMP_PERCENT: u64 = 10;
+
+/// A broadcast transaction of the queue.
+struct SentItem<'a> {
+    chain_id: u32,
+    item_key: [u8; 64],
+    raw_tx: &'a TypedTransaction,
+    max_gas_price: Option<types::U256>,
+    deadline: Option<u64>,
+}
+
+impl SentItem<'_> {
+    fn is_past_deadline(&self) -> bool {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("SystemTime before UNIX EPOCH!");
+        self.deadline
+            .map_or(false, |deadline| now.as_secs() >= deadline)
+    }
+}
+
+/// Waits for the receipt of a broadcast transaction, replacing it whenever
+/// it is stuck.
+///
+/// A transaction that was not mined within `after_blocks` blocks is replaced
+/// by the same transaction, with the same nonce and a higher gas price, up to
+/// `max_replacements` times, as long as it stays within the gas price ceiling
+/// of the item. Once the deadline of the item passed, it is not replaced
+/// anymore but cancelled, by a transfer of nothing to the sender with the
+/// same nonce. The receipt of whichever of the sent transactions gets mined
+/// is returned.
+async fn wait_for_stuck_tx<C, S, M>(
+    ctx: &C,
+    store: &S,
+    client: &M,
+    tx_hash: types::H256,
+    sent: SentItem<'_>,
+    config: StuckTxConfig,
+) -> Result<Option<types::TransactionReceipt>, ProviderError>
+where
+    C: EvmTxQueueConfig,
+    S: QueueStore<TypedTransaction, Key = SledQueueKey> + TxHashStore,
+    M: EvmTxClient,
+{
+    let mut sent_hashes = vec![tx_hash];
+    let mut pending = Box::pin(wait_for_receipt(client, tx_hash));
+    let mut replacements = 0;
+    let mined = loop {
+        tokio::select! {
+            result = &mut pending => break result,
+            () = wait_for_blocks(client, config.after_blocks) => {}
+        }
+        // still not mined after `after_blocks` blocks, it is stuck.
+        let tx_hash = sent_hashes.last().copied().unwrap_or_default();
+        ctx.metrics()
+            .lock()
+            .await
+            .record_stuck_transaction(TypedChainId::Evm(sent.chain_id));
+        if sent.is_past_deadline() {
+            tracing::warn!(
+                ?tx_hash,
+                deadline = ?sent.deadline,
+                "Tx is stuck past its deadline, cancelling it",
+            );
+            let cancellation = cancel_stuck_tx(
+                client,
+                sent.raw_tx,
+                tx_hash,
+                config.gas_price_bump_percent,
+            )
+            .await;
+            let Some(cancellation_tx_hash) = cancellation else {
+                continue;
+            };
+            sent_hashes.push(cancellation_tx_hash);
+            let recorded = store.insert_tx_hash(
+                sent.chain_id,
+                sent.item_key,
+                cancellation_tx_hash,
+            );
+            if let Err(e) = recorded {
+                tracing::warn!(?e, "Failed to record the cancellation tx hash");
+            }
+            // whichever of the stuck tx or its cancellation gets mined.
+            break wait_for_receipt(client, cancellation_tx_hash).await;
+        }
+        if replacements >= config.max_replacements {
+            if sent.deadline.is_some() {
+                // it is cancelled once its deadline passed.
+                continue;
+            }
+            tracing::warn!(
+                ?tx_hash,
+                after_blocks = config.after_blocks,
+                "Tx is stuck and was replaced too many times, waiting for it",
+            );
+            break (&mut pending).await;
+        }
+        replacements += 1;
+        tracing::warn!(
+            ?tx_hash,
+            after_blocks = config.after_blocks,
+            replacement = replacements,
+            "Tx is stuck, replacing it with a higher gas price",
+        );
+        let replacement = replace_stuck_tx(
+            client,
+            sent.raw_tx,
+            tx_hash,
+            config.gas_price_bump_percent,
+            sent.max_gas_price,
+        )
+        .await;
+        let Some(replacement_tx_hash) = replacement else {
+            continue;
+        };
+        sent_hashes.push(replacement_tx_hash);
+        pending = Box::pin(wait_for_receipt(client, replacement_tx_hash));
+        let recorded = store.insert_tx_hash(
+            sent.chain_id,
+            sent.item_key,
+            replacement_tx_hash,
+        );
+        if let Err(e) = recorded {
+            tracing::warn!(?e, "Failed to record the replacement tx hash");
+        }
+        let key = SledQueueKey::from_evm_with_custom_key(
+            sent.chain_id,
+            sent.item_key,
+        );
+        let updated = store.update_item(key, |item| {
+            let state = QueueItemState::Broadcast {
+                attempt: replacements + 1,
+            };
+            item.transition_to(state)
+        });
+        if let Err(e) = updated {
+            tracing::warn!(?e, "Failed to update the tx item state");
+        }
+    };
+    // the last sent transaction is dropped once an earlier one with the
+    // same nonce is mined instead.
+    if let Ok(None) = mined {
+        for tx_hash in sent_hashes.iter().rev().skip(1) {
+            if let Ok(Some(receipt)) =
+                client.transaction_receipt(*tx_hash).await
+            {
+                return Ok(Some(receipt));
+            }
+        }
+    }
+    mined
+}
+
+/// Waits for the receipt of a broadcast transaction, once its block is
+/// confirmed.
+///
+/// Returns `None` if the transaction was dropped from the mempool.
+async fn wait_for_receipt<M: EvmTxClient>(
+    client: &M,
+    tx_hash: types::H256,
+) -> Result<Option<types::TransactionReceipt>, ProviderError> {
+    let mut missing_polls = 0;
+    loop {
+        if let Some(receipt) = client.transaction_receipt(tx_hash).await? {
+            // the block number of the client lags behind by the block
+            // confirmations of the chain.
+            let mined_at = receipt.block_number.unwrap_or_default();
+            if client.block_number().await? >= mined_at {
+                return Ok(Some(receipt));
+            }
+        } else if client.transaction(tx_hash).await?.is_none() {
+            missing_polls += 1;
+            if missing_polls >= MAX_MISSING_TX_POLLS {
+                return Ok(None);
+            }
+        } else {
+            missing_polls = 0;
+        }
+        // the queue is waiting, not stuck.
+        webb_relayer_utils::heartbeat::beat();
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+}
+
+/// Resolves once `blocks` blocks were mined since it was first polled.
+async fn wait_for_blocks<M: EvmTxClient>(client: &M, blocks: u64) {
+    let mut started_at = None;
+    loop {
+        match client.block_number().await {
+            Ok(current) => {
+                let started_at = *started_at.get_or_insert(current);
+                if current.saturating_sub(started_at) >= U64::from(blocks) {
+                    return;
+                }
+            }
+            Err(e) => {
+                tracing::debug!(%e, "Failed to get the block number");
+            }
+        }
+        webb_relayer_utils::heartbeat::beat();
+        tokio::time::sleep(STUCK_TX_POLL_INTERVAL).await;
+    }
+}
+
+/// Sends the replacement of a stuck transaction: the same transaction, with
+/// the same nonce and a gas price raised by `bump_percent`.
+///
+/// Returns `None` if it could not be sent, e.g. if the stuck transaction
+/// was mined in the meantime or the raised gas price is above
+/// `max_gas_price`.
+async fn replace_stuck_tx<M: EvmTxClient>(
+    client: &M,
+    raw_tx: &TypedTransaction,
+    stuck_tx_hash: types::H256,
+    bump_percent: u64,
+    max_gas_price: Option<types::U256>,
+) -> Option<types::H256> {
+    let replacement = |_: &types::Transaction| raw_tx.clone();
+    let replacement_tx_hash = send_over_stuck_tx(
+        client,
+        stuck_tx_hash,
+        bump_percent,
+        max_gas_price,
+        replacement,
+    )
+    .await?;
+    tracing::info!(?stuck_tx_hash, ?replacement_tx_hash, "Replaced stuck tx");
+    Some(replacement_tx_hash)
+}
+
+/// Cancels a stuck transaction, by sending a transfer of nothing from the
+/// sender to itself with the same nonce, and a gas price raised by
+/// `bump_percent`. It is paid by the relayer alone, so it is not held to the
+/// gas price ceiling of the item.
+///
+/// Returns `None` if it could not be sent, like [`replace_stuck_tx`].
+async fn cancel_stuck_tx<M: EvmTxClient>(
+    client: &M,
+    raw_tx: &TypedTransaction,
+    stuck_tx_hash: types::H256,
+    bump_percent: u64,
+) -> Option<types::H256> {
+    let cancellation = |stuck_tx: &types::Transaction| {
+        // keep the type of the stuck transaction, to replace it.
+        let mut tx = raw_tx.clone();
+        tx.set_to(stuck_tx.from);
+        tx.set_value(types::U256::zero());
+        tx.set_data(types::Bytes::default());
+        tx
+    };
+    let cancellation_tx_hash = send_over_stuck_tx(
+        client,
+        stuck_tx_hash,
+        bump_percent,
+        None,
+        cancellation,
+    )
+    .await?;
+    tracing::info!(?stuck_tx_hash, ?cancellation_tx_hash, "Cancelled stuck tx");
+    Some(cancellation_tx_hash)
+}
+
+/// Sends the transaction built by `build` from the stuck transaction, with
+/// its nonce and a gas price raised by `bump_percent`, so it is mined
+/// instead.
+async fn send_over_stuck_tx<M, F>(
+    client: &M,
+    stuck_tx_hash: types::H256,
+    bump_percent: u64,
+    max_gas_price: Option<types::U256>,
+    build: F,
+) -> Option<types::H256>
+where
+    M: EvmTxClient,
+    F: FnOnce(&types::Transaction) -> TypedTransaction,
+{
+    let stuck_tx = match client.transaction(stuck_tx_hash).await {
+        Ok(Some(tx)) => tx,
+        Ok(None) => {
+            tracing::warn!(?stuck_tx_hash, "Stuck tx not found, not replacing");
+            return None;
+        }
+        Err(e) => {
+            tracing::warn!(%e, ?stuck_tx_hash, "Failed to get the stuck tx");
+            return None;
+        }
+    };
+    let bump_percent = bump_percent.max(MIN_GAS_PRICE_BUMP_PERCENT);
+    let bump = |v: types::U256| {
+        v.saturating_add(v.saturating_mul(bump_percent.into()) / 100)
+    };
+    let mut replacement = build(&stuck_tx);
+    replacement.set_nonce(stuck_tx.nonce);
+    replacement.set_gas(stuck_tx.gas);
+    match &mut replacement {
+        TypedTransaction::Eip1559(inner) => {
+            inner.max_fee_per_gas = stuck_tx.max_fee_per_gas.map(bump);
+            inner.max_priority_fee_per_gas =
+                stuck_tx.max_priority_fee_per_gas.map(bump);
+        }
+        tx => {
+            if let Some(gas_price) = stuck_tx.gas_price {
+                tx.set_gas_price(bump(gas_price));
+            }
+        }
+    }
+    // for EIP-1559 transactions this is the max fee per gas.
+    if let (Some(max_gas_price), Some(gas_price)) =
+        (max_gas_price, replacement.gas_price())
+    {
+        if gas_price > max_gas_price {
+            tracing::warn!(
+                ?stuck_tx_hash,
+                %gas_price,
+                %max_gas_price,
+                "Not replacing stuck tx above its gas price ceiling",
+            );
+            return None;
+        }
+    }
+    match client.send_tx(replacement).await {
+        Ok(tx_hash) => Some(tx_hash),
+        Err(e) => {
+            tracing::warn!(%e, ?stuck_tx_hash, "Failed to replace stuck tx");
+            None
+        }
+    }
+}
+
+/// Whether the receipt is the one of the cancellation of the transaction,
+/// sent by [`cancel_stuck_tx`] once it was stuck past its deadline.
+fn is_cancellation(
+    tx: &TypedTransaction,
+    receipt: &types::TransactionReceipt,
+) -> bool {
+    receipt.to == Some(receipt.from) && tx.to_addr() != receipt.to.as_ref()
+}
+
+/// Pins the gas price of a transaction to the current one, so it is not
+/// sent above `max_gas_price`.
+///
+/// Returns the current gas price, leaving the transaction as is, if it is
+/// above `max_gas_price`. EIP-1559 transactions get their max fee per gas
+/// capped at `max_gas_price`.
+async fn pin_gas_price<M: EvmTxClient>(
+    client: &M,
+    tx: &mut TypedTransaction,
+    max_gas_price: types::U256,
+) -> Result<Option<types::U256>, ProviderError> {
+    let gas_price = client.gas_price().await?;
+    if gas_price > max_gas_price {
+        return Ok(Some(gas_price));
+    }
+    match tx {
+        TypedTransaction::Eip1559(inner) => {
+            let (max_fee, max_priority_fee) = client.eip1559_fees().await?;
+            let max_fee = max_fee.min(max_gas_price);
+            inner.max_fee_per_gas = Some(max_fee);
+            inner.max_priority_fee_per_gas =
+                Some(max_priority_fee.min(max_fee));
+        }
+        tx => tx.set_gas_price(gas_price),
+    }
+    Ok(None)
+}
+
+/// How far the items a queue item depends on got.
+enum Dependencies {
+    /// All of them were processed, the item may be sent.
+    Processed,
+    /// Some of them are still waiting or being processed.
+    Pending,
+    /// One of them failed, expired or is gone, the item can't be sent.
+    Failed(String),
+}
+
+/// Checks how far the items the given item depends on got.
+fn dependencies_of<S>(
+    store: &S,
+    chain_id: u32,
+    item: &QueueItem<TypedTransaction>,
+) -> webb_relayer_utils::Result<Dependencies>
+where
+    S: QueueStore<TypedTransaction, Key = SledQueueKey>,
+{
+    let mut dependencies = Dependencies::Processed;
+    for item_key in item.depends_on() {
+        let key = SledQueueKey::from_evm_with_custom_key(
+            chain_id,
+            item_key.to_fixed_bytes(),
+        );
+        match store.get_item(key)?.map(|item| item.state()) {
+            Some(QueueItemState::Processed { .. }) => {}
+            Some(state) if state.is_pending() => {
+                dependencies = Dependencies::Pending;
+            }
+            Some(state) => {
+                return Ok(Dependencies::Failed(format!(
+                    "The tx {item_key:?} it depends on is {}",
+                    state.name()
+                )));
+            }
+            None => {
+                return Ok(Dependencies::Failed(format!(
+                    "The tx {item_key:?} it depends on is gone"
+                )));
+            }
+        }
+    }
+    Ok(dependencies)
+}
+
+/// The command type of a transaction, as the 4 bytes selector of the
+/// contract function it calls, used to label its metrics.
+fn command_type(tx: &TypedTransaction) -> String {
+    match tx.data() {
+        Some(data) if data.len() >= 4 => {
+            format!("0x{}", hex::encode(&data[..4]))
+        }
+        _ => String::from("transfer"),
+    }
+}
+
+/// Whether the transaction executes a proposal on a signature bridge.
+fn is_proposal_execution(tx: &TypedTransaction) -> bool {
+    let selector = ExecuteProposalWithSignatureCall::selector();
+    matches!(tx.data(), Some(data) if data.starts_with(&selector))
+}
+
+/// Whether the call failed because the proposal nonce of the bridge already
+/// advanced past the one of the proposal.
+///
+/// A nonce too far ahead (`Nonce must not increment more than 1`) is not
+/// stale, the proposal may be executed once its predecessor is.
+fn is_stale_proposal_nonce(err: &DryRunError) -> bool {
+    let message = err.to_string();
+    webb_relayer_utils::revert::revert_reason(&message)
+        .map_or(false, |reason| reason.contains("Invalid nonce"))
+}
+
+/// Whether the transaction calls `execute` of a trusted forwarder (ERC-2771),
+/// and the call it executed reverted.
+///
+/// The forwarder only returns `false` then, so the call is taken as reverted
+/// when the receipt has no log of its recipient: the contracts relayed
+/// through a forwarder always emit events.
+fn is_failed_forwarded_call(
+    tx: &TypedTransaction,
+    receipt: &types::TransactionReceipt,
+) -> bool {
+    let selector =
+        id("execute((address,address,uint256,uint256,uint256,bytes),bytes)");
+    let Some(data) = tx.data().filter(|data| data.starts_with(&selector))
+    else {
+        return false;
+    };
+    let request = ParamType::Tuple(vec![
+        ParamType::Address,
+        ParamType::Address,
+        ParamType::Uint(256),
+        ParamType::Uint(256),
+        ParamType::Uint(256),
+        ParamType::Bytes,
+    ]);
+    let params = [request, ParamType::Bytes];
+    let to = match abi::decode(&params, &data[4..]).ok().as_deref() {
+        Some([Token::Tuple(request), _]) => request.get(1).cloned(),
+        _ => None,
+    };
+    match to {
+        Some(Token::Address(to)) => {
+            !receipt.logs.iter().any(|log| log.address == to)
+        }
+        _ => false,
+    }
+}
+
+/// The reason a mined transaction reverted, decoded from the revert of the
+/// same call replayed on top of the latest block.
+async fn reverted_tx_reason<M: EvmTxClient>(
+    client: &M,
+    tx: &TypedTransaction,
+) -> String {
+    let reason = match client.dry_run(tx).await {
+        Err(e) => {
+            let message = e.to_string();
+            webb_relayer_utils::revert::revert_reason(&message)
+                .unwrap_or_else(|| humanize_error(&message))
+        }
+        Ok(_) => String::from("unknown reason"),
+    };
+    format!("Reverted on chain: {reason}")
+}
+
+/// Whether the chain already spent its daily gas budget.
+///
+/// Fails closed: when the budget or the spend can't be determined (e.g. the
+/// price oracle is unreachable), the budget is considered exceeded.
+async fn is_gas_budget_exceeded<C: EvmTxQueueConfig, S: AccountingStore>(
+    ctx: &C,
+    store: &S,
+    chain_id: u32,
+) -> bool {
+    let budget = match ctx.daily_gas_budget(&types::U256::from(chain_id)).await
+    {
+        Ok(Some(budget)) => budget,
+        Ok(None) => return false,
+        Err(e) => {
+            tracing::warn!(?e, "Failed to get the daily gas budget");
+            return true;
+        }
+    };
+    let today = webb_relayer_store::current_day();
+    match store.get_daily_gas_spent(TypedChainId::Evm(chain_id), today) {
+        Ok(spent) => {
+            let exceeded = spent >= budget;
+            if exceeded {
+                tracing::debug!(
+                    %spent,
+                    %budget,
+                    "Daily gas budget exceeded, pausing relayed transactions",
+                );
+            }
+            exceeded
+        }
+        Err(e) => {
+            tracing::warn!(?e, "Failed to get the daily gas spend");
+            true
+        }
+    }
+}
+
+/// The fee paid for a settled transaction: the gas it used at its effective
+/// gas price.
+fn effective_fee(
+    tx: &TypedTransaction,
+    receipt: &types::TransactionReceipt,
+) -> Option<types::U256> {
+    let gas_used = receipt.gas_used?;
+    let gas_price = receipt.effective_gas_price.or_else(|| tx.gas_price())?;
+    Some(gas_used.saturating_mul(gas_price))
+}
+
+/// Accounts for the costs of a settled transaction in the profit-and-loss
+/// ledger of the contract it called.
+///
+/// The transaction is already settled at this point, so a failure here is
+/// only logged.
+fn account_settled_tx<S: AccountingStore>(
+    store: &S,
+    chain_id: u32,
+    tx: &TypedTransaction,
+    item_key: [u8; 64],
+    receipt: &types::TransactionReceipt,
+    succeeded: bool,
+) {
+    let Some(contract) = tx.to_addr() else {
+        return;
+    };
+    let resource_id = ResourceId::new(
+        TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        TypedChainId::Evm(chain_id),
+    );
+    let cost = TxCost {
+        gas_used: receipt.gas_used.unwrap_or_default(),
+        gas_spent: effective_fee(tx, receipt).unwrap_or_default(),
+        value_sent: tx.value().copied().unwrap_or_default(),
+    };
+    if let Err(e) = store.settle_tx(resource_id, item_key, cost, succeeded) {
+        tracing::warn!(
+            ?e,
+            tx_hash = ?receipt.transaction_hash,
+            "Failed to account for the settled transaction",
+        );
+    }
+}
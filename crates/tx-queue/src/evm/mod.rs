@@ -12,44 +12,509 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod client;
 mod evm_tx_queue;
+mod nonce;
 use std::sync::Arc;
 
-use ethereum_types::U256;
+pub use client::{DryRunError, EvmTxClient, MockEvmTxClient, MockTxOutcome};
+use ethereum_types::{Address, U256};
 #[doc(hidden)]
 pub use evm_tx_queue::*;
+pub use nonce::NonceTracker;
 
+use tokio::sync::Mutex;
 use url::Url;
-use webb::evm::ethers::{providers::Middleware, signers::LocalWallet};
+use webb_relayer_config::{NonceStrategy, StuckTxConfig};
+use webb_relayer_utils::circuit_breaker::CircuitBreaker;
+use webb_relayer_utils::metric::Metrics;
 use webb_relayer_utils::Result;
 
 /// Config trait for EVM tx queue.
 #[async_trait::async_trait]
 pub trait EvmTxQueueConfig {
-    type EtherClient: Middleware;
+    /// The client the queue sends its transactions with.
+    type TxClient: EvmTxClient;
     /// Maximum number of milliseconds to wait before dequeuing a transaction from
     /// the queue.
     fn max_sleep_interval(&self, chain_id: &U256) -> Result<u64>;
     /// Block confirmations
     fn block_confirmations(&self, chain_id: &U256) -> Result<u8>;
+    /// Stuck transaction detection of this chain, if enabled.
+    ///
+    /// Broadcast transactions that are not mined within the configured
+    /// number of blocks are replaced with a higher gas price.
+    fn stuck_tx(&self, chain_id: &U256) -> Result<Option<StuckTxConfig>>;
+    /// How the nonces of the transactions sent on this chain are picked.
+    fn nonce_strategy(&self, chain_id: &U256) -> Result<NonceStrategy>;
+    /// Adds the gas limit headroom configured for the contract at `to` to a
+    /// gas estimate of a transaction sent to it.
+    ///
+    /// Only used for the transactions enqueued without a gas limit.
+    fn gas_limit_with_headroom(
+        &self,
+        chain_id: &U256,
+        to: Address,
+        gas: U256,
+    ) -> Result<U256>;
     /// Block Explorer for this chain.
     ///
     /// Optional, and only used for printing a clickable links
     /// for transactions and contracts.
     fn explorer(&self, chain_id: &U256) -> Result<Option<Url>>;
-    /// Returns a new `EthereumProvider`.
+    /// Circuit breaker of this chain, if enabled.
     ///
-    /// # Arguments
+    /// While it is open, the queue stops dequeuing transactions.
+    fn circuit_breaker(&self, chain_id: &U256) -> Option<Arc<CircuitBreaker>>;
+    /// Whether this chain was disabled at runtime.
     ///
-    /// * `chain_id` - A string representing the chain id.
-    async fn get_evm_provider(
-        &self,
-        chain_id: &U256,
-    ) -> Result<Arc<Self::EtherClient>>;
-    /// Returns an EVM wallet.
+    /// While disabled, the queue stops dequeuing transactions.
+    fn is_chain_disabled(&self, chain_id: &U256) -> bool;
+    /// The end of the maintenance window this chain is in, in seconds since
+    /// the unix epoch, if it is in one.
+    ///
+    /// Until then, the queue stops dequeuing transactions.
+    fn maintenance_until(&self, chain_id: &U256) -> Option<u64>;
+    /// The metrics of the relayer.
+    fn metrics(&self) -> Arc<Mutex<Metrics>>;
+    /// Daily gas budget of this chain in wei, if it has one.
+    ///
+    /// Once it is spent, the queue pauses relayed transactions until the
+    /// next day.
+    async fn daily_gas_budget(&self, chain_id: &U256) -> Result<Option<U256>>;
+    /// Returns the client the queue sends its transactions with.
+    ///
+    /// It signs them with the relayer wallet of this chain, and only sees
+    /// blocks once they have the configured block confirmations.
     ///
     /// # Arguments
     ///
     /// * `chain_id` - A string representing the chain id.
-    async fn get_evm_wallet(&self, chain_id: &U256) -> Result<LocalWallet>;
+    async fn evm_tx_client(&self, chain_id: &U256) -> Result<Self::TxClient>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use webb::evm::contract::protocol_solidity::signature_bridge::ExecuteProposalWithSignatureCall;
+    use webb::evm::ethers::abi::{self, Token};
+    use webb::evm::ethers::contract::EthCall;
+    use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
+    use webb::evm::ethers::types::{Address, TransactionRequest};
+    use webb::evm::ethers::utils::id;
+    use webb_relayer_store::queue::{
+        QueueItem, QueueItemState, QueueStore, TransactionQueueItemKey,
+    };
+    use webb_relayer_store::sled::SledQueueKey;
+    use webb_relayer_store::SledStore;
+    use webb_relayer_utils::circuit_breaker::CircuitState;
+
+    use super::*;
+
+    pub struct TxQueueContext {
+        client: MockEvmTxClient,
+        metrics: Arc<Mutex<Metrics>>,
+        nonce_strategy: NonceStrategy,
+        daily_gas_budget: Option<U256>,
+        circuit_breaker: Option<Arc<CircuitBreaker>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EvmTxQueueConfig for TxQueueContext {
+        type TxClient = MockEvmTxClient;
+
+        fn max_sleep_interval(&self, _chain_id: &U256) -> Result<u64> {
+            Ok(100)
+        }
+
+        fn block_confirmations(&self, _chain_id: &U256) -> Result<u8> {
+            Ok(0)
+        }
+
+        fn stuck_tx(&self, _chain_id: &U256) -> Result<Option<StuckTxConfig>> {
+            Ok(None)
+        }
+
+        fn nonce_strategy(&self, _chain_id: &U256) -> Result<NonceStrategy> {
+            Ok(self.nonce_strategy)
+        }
+
+        fn gas_limit_with_headroom(
+            &self,
+            _chain_id: &U256,
+            _to: Address,
+            gas: U256,
+        ) -> Result<U256> {
+            Ok(gas * 12 / 10)
+        }
+
+        fn explorer(&self, _chain_id: &U256) -> Result<Option<Url>> {
+            Ok(None)
+        }
+
+        fn circuit_breaker(
+            &self,
+            _chain_id: &U256,
+        ) -> Option<Arc<CircuitBreaker>> {
+            self.circuit_breaker.clone()
+        }
+
+        fn is_chain_disabled(&self, _chain_id: &U256) -> bool {
+            false
+        }
+
+        fn maintenance_until(&self, _chain_id: &U256) -> Option<u64> {
+            None
+        }
+
+        fn metrics(&self) -> Arc<Mutex<Metrics>> {
+            self.metrics.clone()
+        }
+
+        async fn daily_gas_budget(
+            &self,
+            _chain_id: &U256,
+        ) -> Result<Option<U256>> {
+            Ok(self.daily_gas_budget)
+        }
+
+        async fn evm_tx_client(
+            &self,
+            _chain_id: &U256,
+        ) -> Result<Self::TxClient> {
+            Ok(self.client.clone())
+        }
+    }
+
+    fn transfer(value: u64) -> TypedTransaction {
+        TransactionRequest::new()
+            .to(Address::repeat_byte(0x42))
+            .value(value)
+            .gas(21_000u64)
+            .into()
+    }
+
+    async fn wait_for_state<F>(
+        store: &SledStore,
+        key: SledQueueKey,
+        f: F,
+    ) -> QueueItemState
+    where
+        F: Fn(&QueueItemState) -> bool,
+    {
+        loop {
+            let item: Option<QueueItem<TypedTransaction>> =
+                QueueStore::get_item(store, key).unwrap();
+            if let Some(state) = item.map(|item| item.state()) {
+                if f(&state) {
+                    return state;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn should_process_and_fail_txs() -> Result<()> {
+        let chain_id = 5001u32;
+        let client = MockEvmTxClient::new(chain_id);
+        let metrics = Arc::new(Mutex::new(Metrics::new()?));
+        let context = TxQueueContext {
+            client: client.clone(),
+            metrics: metrics.clone(),
+            nonce_strategy: NonceStrategy::Local,
+            daily_gas_budget: None,
+            circuit_breaker: None,
+        };
+        let store = Arc::new(SledStore::temporary()?);
+        let tx_queue = TxQueue::new(context, chain_id.into(), store.clone());
+        let handle = tokio::spawn(tx_queue.run());
+
+        // the tx is sent, mined and marked as processed.
+        let tx = transfer(1);
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        let state = tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Processed { .. })
+            }),
+        )
+        .await
+        .expect("tx to be processed");
+        assert!(matches!(
+            state,
+            QueueItemState::Processed {
+                block_number: Some(1),
+                ..
+            }
+        ));
+        assert_eq!(client.sent_transactions().len(), 1);
+
+        // the tx without a gas limit is sent with the headroom.
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(0x42))
+            .value(3u64)
+            .into();
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Processed { .. })
+            }),
+        )
+        .await
+        .expect("tx to be processed");
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].gas(), Some(&U256::from(25_200u64)));
+        // the nonces are tracked locally.
+        assert_eq!(sent[0].nonce(), Some(&U256::zero()));
+        assert_eq!(sent[1].nonce(), Some(&U256::one()));
+
+        // the tx fails its dry run, and is never sent.
+        client.set_outcome(MockTxOutcome::DryRunFailed("reverted".into()));
+        let tx = transfer(2);
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        let state = tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Failed { .. })
+            }),
+        )
+        .await
+        .expect("tx to fail");
+        assert!(matches!(
+            state,
+            QueueItemState::Failed { reason } if reason.contains("reverted")
+        ));
+        assert_eq!(client.sent_transactions().len(), 2);
+
+        // the tx is mined but reverts, and is marked as failed.
+        client.set_outcome(MockTxOutcome::Reverted);
+        let tx = transfer(4);
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        let state = tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Failed { .. })
+            }),
+        )
+        .await
+        .expect("tx to fail");
+        assert!(matches!(
+            state,
+            QueueItemState::Failed { reason } if reason.contains("Reverted")
+        ));
+        assert_eq!(client.sent_transactions().len(), 3);
+        client.set_outcome(MockTxOutcome::Mined);
+
+        // the forwarded call emits no log of its recipient, it reverted.
+        let request = Token::Tuple(vec![
+            Token::Address(Address::repeat_byte(0x44)),
+            Token::Address(Address::repeat_byte(0x45)),
+            Token::Uint(U256::zero()),
+            Token::Uint(U256::from(21_000u64)),
+            Token::Uint(U256::zero()),
+            Token::Bytes(vec![]),
+        ]);
+        let mut data = id(
+            "execute((address,address,uint256,uint256,uint256,bytes),bytes)",
+        )
+        .to_vec();
+        data.extend(abi::encode(&[request, Token::Bytes(vec![])]));
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(0x46))
+            .data(data)
+            .gas(21_000u64)
+            .into();
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        let state = tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Failed { .. })
+            }),
+        )
+        .await
+        .expect("forwarded tx to fail");
+        assert!(matches!(
+            state,
+            QueueItemState::Failed { reason } if reason.contains("forwarded")
+        ));
+        assert_eq!(client.sent_transactions().len(), 4);
+
+        // a proposal execution whose nonce is stale is dropped.
+        let proposal = |nonce: u8| -> TypedTransaction {
+            let mut data =
+                ExecuteProposalWithSignatureCall::selector().to_vec();
+            data.push(nonce);
+            TransactionRequest::new()
+                .to(Address::repeat_byte(0x43))
+                .data(data)
+                .gas(21_000u64)
+                .into()
+        };
+        client.set_outcome(MockTxOutcome::DryRunFailed(
+            "execution reverted: ProposalNonceTracker: Invalid nonce".into(),
+        ));
+        let tx = proposal(1);
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while QueueStore::<TypedTransaction>::has_item(&store, key).unwrap()
+            {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("proposal to be dropped");
+        let dropped = metrics.lock().await.proposals_dropped_stale_nonce.get();
+        assert_eq!(dropped, 1.0);
+
+        // other failures of a proposal execution are retried.
+        client.set_outcome(MockTxOutcome::DryRunFailed(
+            "execution reverted: ProposalNonceTracker: Nonce must not \
+             increment more than 1"
+                .into(),
+        ));
+        let tx = proposal(2);
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let item: Option<QueueItem<TypedTransaction>> =
+            QueueStore::get_item(&store, key)?;
+        assert_eq!(
+            item.map(|item| item.state()),
+            Some(QueueItemState::Pending)
+        );
+        client.set_outcome(MockTxOutcome::Mined);
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Processed { .. })
+            }),
+        )
+        .await
+        .expect("proposal to be executed");
+
+        handle.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_only_trip_on_failed_dry_runs_of_the_node() -> Result<()> {
+        let chain_id = 5003u32;
+        let client = MockEvmTxClient::new(chain_id);
+        let circuit_breaker = Arc::new(CircuitBreaker::new(
+            0.5,
+            1,
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        ));
+        let context = TxQueueContext {
+            client: client.clone(),
+            metrics: Arc::new(Mutex::new(Metrics::new()?)),
+            nonce_strategy: NonceStrategy::Local,
+            daily_gas_budget: None,
+            circuit_breaker: Some(circuit_breaker.clone()),
+        };
+        let store = Arc::new(SledStore::temporary()?);
+        let tx_queue = TxQueue::new(context, chain_id.into(), store.clone());
+        let handle = tokio::spawn(tx_queue.run());
+
+        // the tx reverts, the chain is fine.
+        client.set_outcome(MockTxOutcome::DryRunFailed("reverted".into()));
+        let tx = transfer(1);
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Failed { .. })
+            }),
+        )
+        .await
+        .expect("tx to fail");
+        assert_eq!(circuit_breaker.state(), CircuitState::Closed);
+
+        // the node fails to dry run the tx.
+        client.set_outcome(MockTxOutcome::Unreachable("timed out".into()));
+        let tx = transfer(2);
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Failed { .. })
+            }),
+        )
+        .await
+        .expect("tx to fail");
+        assert_eq!(circuit_breaker.state(), CircuitState::Open);
+
+        handle.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_only_send_exempt_txs_over_the_gas_budget() -> Result<()> {
+        let chain_id = 5002u32;
+        let client = MockEvmTxClient::new(chain_id);
+        let context = TxQueueContext {
+            client: client.clone(),
+            metrics: Arc::new(Mutex::new(Metrics::new()?)),
+            nonce_strategy: NonceStrategy::Local,
+            // nothing spent yet, but the budget is already exceeded.
+            daily_gas_budget: Some(U256::zero()),
+            circuit_breaker: None,
+        };
+        let store = Arc::new(SledStore::temporary()?);
+        let tx_queue = TxQueue::new(context, chain_id.into(), store.clone());
+        let handle = tokio::spawn(tx_queue.run());
+
+        // the relayer's own tx waits for the next day.
+        let tx = transfer(1);
+        let paused_key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        QueueStore::enqueue_item(&store, paused_key, QueueItem::new(tx))?;
+        // the governance tx still goes.
+        let tx = transfer(2);
+        let key =
+            SledQueueKey::from_evm_with_custom_key(chain_id, tx.item_key());
+        let mut item = QueueItem::new(tx);
+        item.set_budget_exempt(true);
+        QueueStore::enqueue_item(&store, key, item)?;
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Processed { .. })
+            }),
+        )
+        .await
+        .expect("exempt tx to be processed");
+        let item: Option<QueueItem<TypedTransaction>> =
+            QueueStore::get_item(&*store, paused_key)?;
+        assert_eq!(
+            item.map(|item| item.state()),
+            Some(QueueItemState::Pending)
+        );
+        assert_eq!(client.sent_transactions().len(), 1);
+
+        handle.abort();
+        Ok(())
+    }
 }
@@ -0,0 +1,125 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use webb::evm::ethers::providers::ProviderError;
+use webb::evm::ethers::types::U256;
+use webb_relayer_config::NonceStrategy;
+
+use super::EvmTxClient;
+
+/// How often a nonce tracked with the [`NonceStrategy::Hybrid`] strategy is
+/// reconciled with the pending nonce of the chain.
+const NONCE_RECONCILE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Picks the nonces of the transactions sent by a tx queue, according to
+/// the [`NonceStrategy`] of its chain.
+#[derive(Debug)]
+pub struct NonceTracker {
+    strategy: NonceStrategy,
+    /// The nonce of the next transaction, once fetched from the chain.
+    next: Option<U256>,
+    /// When the tracked nonce was last fetched from the chain.
+    fetched_at: Option<Instant>,
+}
+
+impl NonceTracker {
+    /// Creates a tracker following the given strategy.
+    pub fn new(strategy: NonceStrategy) -> Self {
+        Self {
+            strategy,
+            next: None,
+            fetched_at: None,
+        }
+    }
+
+    /// The nonce to send the next transaction with, or `None` to let the
+    /// client fill it from the chain.
+    pub async fn next_nonce<M: EvmTxClient>(
+        &mut self,
+        client: &M,
+    ) -> Result<Option<U256>, ProviderError> {
+        let refetch = match self.strategy {
+            NonceStrategy::Remote => return Ok(None),
+            NonceStrategy::Local => self.next.is_none(),
+            NonceStrategy::Hybrid => {
+                self.next.is_none()
+                    || matches!(
+                        self.fetched_at,
+                        Some(at) if at.elapsed() >= NONCE_RECONCILE_INTERVAL
+                    )
+            }
+        };
+        if refetch {
+            let pending = client.pending_nonce().await?;
+            if matches!(self.next, Some(next) if next != pending) {
+                tracing::warn!(
+                    tracked = ?self.next,
+                    %pending,
+                    "The tracked nonce drifted from the chain, reconciling",
+                );
+            }
+            self.next = Some(pending);
+            self.fetched_at = Some(Instant::now());
+        }
+        Ok(self.next)
+    }
+
+    /// Moves past the nonce of a transaction that was broadcast.
+    pub fn on_sent(&mut self) {
+        if let Some(next) = self.next.as_mut() {
+            *next += U256::one();
+        }
+    }
+
+    /// Forgets the tracked nonce, after a transaction failed to be sent or
+    /// was dropped, so it is fetched from the chain again.
+    pub fn reset(&mut self) {
+        self.next = None;
+        self.fetched_at = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
+    use webb::evm::ethers::types::TransactionRequest;
+
+    use super::*;
+    use crate::evm::MockEvmTxClient;
+
+    #[tokio::test]
+    async fn local_nonces_should_be_tracked_until_reset() {
+        let client = MockEvmTxClient::new(5001);
+        let mut remote = NonceTracker::new(NonceStrategy::Remote);
+        assert_eq!(remote.next_nonce(&client).await.unwrap(), None);
+
+        let mut local = NonceTracker::new(NonceStrategy::Local);
+        let nonce = local.next_nonce(&client).await.unwrap();
+        assert_eq!(nonce, Some(U256::from(0)));
+        local.on_sent();
+        // other transactions are sent from the same account.
+        for _ in 0..3 {
+            let tx: TypedTransaction = TransactionRequest::new().into();
+            client.send_tx(tx).await.unwrap();
+        }
+        // the local nonce is kept, until it is reset.
+        let nonce = local.next_nonce(&client).await.unwrap();
+        assert_eq!(nonce, Some(U256::from(1)));
+        local.reset();
+        let nonce = local.next_nonce(&client).await.unwrap();
+        assert_eq!(nonce, Some(U256::from(3)));
+    }
+}
@@ -15,6 +15,8 @@
 /// EVM Transaction Queue.
 #[cfg(feature = "evm")]
 pub mod evm;
+/// Throughput sampling of the queues.
+mod stats;
 /// Substrate Transaction Queue.
 #[cfg(feature = "substrate")]
 pub mod substrate;
@@ -0,0 +1,84 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use webb_relayer_store::queue::QueueStats;
+use webb_relayer_utils::metric::Metrics;
+
+/// How often the items of a queue are sampled.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Samples the items of a queue into its throughput metrics.
+///
+/// The items enqueued between two samples are counted from their enqueue
+/// time, and the items dequeued are the ones that left the pending states
+/// meanwhile.
+#[derive(Debug)]
+pub(crate) struct QueueSampler {
+    queue: String,
+    last_sampled: Option<Instant>,
+    last_stats: Option<QueueStats>,
+}
+
+impl QueueSampler {
+    /// Creates a new sampler for the queue with the given name.
+    pub fn new(queue: String) -> Self {
+        Self {
+            queue,
+            last_sampled: None,
+            last_stats: None,
+        }
+    }
+
+    /// Whether the queue should be sampled again.
+    pub fn is_due(&self) -> bool {
+        self.last_sampled
+            .map_or(true, |at| at.elapsed() >= SAMPLE_INTERVAL)
+    }
+
+    /// The time the items should be counted from in the next sample
+    /// (unix timestamp in milliseconds).
+    pub fn since(&self) -> u128 {
+        self.last_stats.map_or(0, |stats| stats.sampled_at)
+    }
+
+    /// Records the sample into the metrics of the queue.
+    ///
+    /// The first sample only sets the pending items, since the items
+    /// enqueued before the relayer started are not part of its throughput.
+    pub fn record(&mut self, stats: QueueStats, metrics: &Metrics) {
+        let (enqueued, dequeued) = match self.last_stats {
+            Some(last) => (
+                stats.enqueued_since,
+                (last.pending + stats.enqueued_since)
+                    .saturating_sub(stats.pending),
+            ),
+            None => (0, 0),
+        };
+        let oldest_pending_age = stats.oldest_pending_enqueued_at.map(|at| {
+            let age = stats.sampled_at.saturating_sub(at);
+            Duration::from_millis(age as u64)
+        });
+        metrics.record_queue_sample(
+            &self.queue,
+            enqueued,
+            dequeued,
+            stats.pending,
+            oldest_pending_age,
+        );
+        self.last_sampled = Some(Instant::now());
+        self.last_stats = Some(stats);
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use subxt_signer::sr25519::Keypair as Sr25519Pair;
+use webb::evm::ethers::types::H256;
+use webb::substrate::subxt::rpc::types::DryRunResult;
+use webb::substrate::subxt::tx::{SubmittableExtrinsic, TxStatus};
+use webb::substrate::subxt::{self, OnlineClient};
+use webb_relayer_utils::static_tx_payload::TypeErasedStaticTxPayload;
+use webb_relayer_utils::{Result, TangleRuntimeConfig};
+
+/// The outcome of the dry run of a signed extrinsic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubstrateDryRun {
+    /// The extrinsic would be dispatched successfully.
+    Success,
+    /// The extrinsic may not be included in a block.
+    TransactionValidityError,
+    /// The extrinsic would fail to dispatch with the given error.
+    DispatchError(String),
+}
+
+/// The status of a submitted extrinsic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubstrateTxStatus {
+    /// The extrinsic is part of the future queue.
+    Future,
+    /// The extrinsic is part of the ready queue.
+    Ready,
+    /// The extrinsic was broadcast to the network.
+    Broadcast,
+    /// The extrinsic was included in the block with the given hash.
+    InBlock {
+        /// The hash of the block.
+        block_hash: H256,
+    },
+    /// The block the extrinsic was included in was retracted.
+    Retracted,
+    /// The block the extrinsic was included in was not finalized in time.
+    FinalityTimeout,
+    /// The block the extrinsic was included in was finalized.
+    Finalized {
+        /// The hash of the block.
+        block_hash: H256,
+        /// The hash of the extrinsic.
+        extrinsic_hash: H256,
+    },
+    /// The extrinsic was replaced by another one with the same nonce.
+    Usurped,
+    /// The extrinsic was dropped from the pool.
+    Dropped,
+    /// The extrinsic is no longer valid.
+    Invalid,
+}
+
+/// The chain operations the [`SubstrateTxQueue`](super::SubstrateTxQueue)
+/// submits its extrinsics with.
+///
+/// It is implemented by [`SubxtTxClient`], and by
+/// [`MockSubstrateTxClient`] in the tests, so the state machine of the queue
+/// can be tested without a node.
+#[async_trait::async_trait]
+pub trait SubstrateTxClient: Send + Sync {
+    /// Signs the payload with the account of the relayer.
+    ///
+    /// Returns the encoded signed extrinsic.
+    async fn create_signed(
+        &self,
+        payload: &TypeErasedStaticTxPayload,
+    ) -> Result<Vec<u8>>;
+    /// Dry runs the signed extrinsic on top of the latest block.
+    async fn dry_run(&self, extrinsic: &[u8]) -> Result<SubstrateDryRun>;
+    /// Submits the signed extrinsic, and watches its status.
+    async fn submit_and_watch(
+        &self,
+        extrinsic: Vec<u8>,
+    ) -> Result<BoxStream<'static, Result<SubstrateTxStatus>>>;
+}
+
+/// A [`SubstrateTxClient`] submitting the extrinsics to a node, signed with
+/// the relayer wallet of the chain.
+#[derive(Clone)]
+pub struct SubxtTxClient {
+    client: OnlineClient<TangleRuntimeConfig>,
+    pair: Sr25519Pair,
+}
+
+impl SubxtTxClient {
+    /// Creates a client signing the extrinsics with the given pair.
+    pub fn new(
+        client: OnlineClient<TangleRuntimeConfig>,
+        pair: Sr25519Pair,
+    ) -> Self {
+        Self { client, pair }
+    }
+
+    fn extrinsic(
+        &self,
+        extrinsic: Vec<u8>,
+    ) -> SubmittableExtrinsic<
+        TangleRuntimeConfig,
+        OnlineClient<TangleRuntimeConfig>,
+    > {
+        SubmittableExtrinsic::from_bytes(self.client.clone(), extrinsic)
+    }
+}
+
+#[async_trait::async_trait]
+impl SubstrateTxClient for SubxtTxClient {
+    async fn create_signed(
+        &self,
+        payload: &TypeErasedStaticTxPayload,
+    ) -> Result<Vec<u8>> {
+        let signed = self
+            .client
+            .tx()
+            .create_signed(payload, &self.pair, Default::default())
+            .await?;
+        Ok(signed.encoded().to_vec())
+    }
+
+    async fn dry_run(&self, extrinsic: &[u8]) -> Result<SubstrateDryRun> {
+        let outcome = self.extrinsic(extrinsic.to_vec()).dry_run(None).await?;
+        Ok(match outcome {
+            DryRunResult::Success => SubstrateDryRun::Success,
+            DryRunResult::TransactionValidityError => {
+                SubstrateDryRun::TransactionValidityError
+            }
+            DryRunResult::DispatchError(err) => {
+                SubstrateDryRun::DispatchError(err.to_string())
+            }
+        })
+    }
+
+    async fn submit_and_watch(
+        &self,
+        extrinsic: Vec<u8>,
+    ) -> Result<BoxStream<'static, Result<SubstrateTxStatus>>> {
+        let progress = self.extrinsic(extrinsic).submit_and_watch().await?;
+        let statuses = progress.map(|status| {
+            let status = match status? {
+                TxStatus::Future => SubstrateTxStatus::Future,
+                TxStatus::Ready => SubstrateTxStatus::Ready,
+                TxStatus::Broadcast(_) => SubstrateTxStatus::Broadcast,
+                TxStatus::InBlock(data) => SubstrateTxStatus::InBlock {
+                    block_hash: H256::from_slice(data.block_hash().as_ref()),
+                },
+                TxStatus::Retracted(_) => SubstrateTxStatus::Retracted,
+                TxStatus::FinalityTimeout(_) => {
+                    SubstrateTxStatus::FinalityTimeout
+                }
+                TxStatus::Finalized(data) => SubstrateTxStatus::Finalized {
+                    block_hash: H256::from_slice(data.block_hash().as_ref()),
+                    extrinsic_hash: H256::from_slice(
+                        data.extrinsic_hash().as_ref(),
+                    ),
+                },
+                TxStatus::Usurped(_) => SubstrateTxStatus::Usurped,
+                TxStatus::Dropped => SubstrateTxStatus::Dropped,
+                TxStatus::Invalid => SubstrateTxStatus::Invalid,
+            };
+            Ok(status)
+        });
+        Ok(statuses.boxed())
+    }
+}
+
+/// What a [`MockSubstrateTxClient`] does with the extrinsics it is sent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MockExtrinsicOutcome {
+    /// The extrinsic is included in the next block, which is finalized.
+    #[default]
+    Finalized,
+    /// The dry run of the extrinsic fails to dispatch with the given error.
+    DispatchError(String),
+    /// The node rejects the extrinsic with the given reason.
+    Rejected(String),
+    /// The extrinsic is dropped from the pool.
+    Dropped,
+}
+
+#[derive(Debug, Default)]
+struct MockChain {
+    block_number: u64,
+    outcome: MockExtrinsicOutcome,
+    submitted: Vec<Vec<u8>>,
+}
+
+/// An in-memory [`SubstrateTxClient`], finalizing the extrinsics it is sent
+/// according to the configured [`MockExtrinsicOutcome`].
+///
+/// The signed extrinsics are the call data of their payloads, and the chain
+/// advances one block per submitted extrinsic.
+#[derive(Debug, Clone, Default)]
+pub struct MockSubstrateTxClient {
+    chain: Arc<Mutex<MockChain>>,
+}
+
+impl MockSubstrateTxClient {
+    /// Creates a mock client, finalizing every extrinsic.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets what happens to the extrinsics sent from now on.
+    pub fn set_outcome(&self, outcome: MockExtrinsicOutcome) {
+        self.chain.lock().outcome = outcome;
+    }
+
+    /// The extrinsics submitted so far, in order.
+    pub fn submitted_extrinsics(&self) -> Vec<Vec<u8>> {
+        self.chain.lock().submitted.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl SubstrateTxClient for MockSubstrateTxClient {
+    async fn create_signed(
+        &self,
+        payload: &TypeErasedStaticTxPayload,
+    ) -> Result<Vec<u8>> {
+        Ok(payload.tx_data().to_vec())
+    }
+
+    async fn dry_run(&self, _extrinsic: &[u8]) -> Result<SubstrateDryRun> {
+        match &self.chain.lock().outcome {
+            MockExtrinsicOutcome::DispatchError(err) => {
+                Ok(SubstrateDryRun::DispatchError(err.clone()))
+            }
+            _ => Ok(SubstrateDryRun::Success),
+        }
+    }
+
+    async fn submit_and_watch(
+        &self,
+        extrinsic: Vec<u8>,
+    ) -> Result<BoxStream<'static, Result<SubstrateTxStatus>>> {
+        let mut chain = self.chain.lock();
+        if let MockExtrinsicOutcome::Rejected(reason) = &chain.outcome {
+            return Err(subxt::Error::Other(reason.clone()).into());
+        }
+        chain.submitted.push(extrinsic);
+        let mut statuses = vec![SubstrateTxStatus::Ready];
+        if chain.outcome == MockExtrinsicOutcome::Dropped {
+            statuses.push(SubstrateTxStatus::Dropped);
+        } else {
+            chain.block_number += 1;
+            let block_hash = H256::from_low_u64_be(chain.block_number);
+            let extrinsic_hash =
+                H256::from_low_u64_be(chain.submitted.len() as u64);
+            statuses.push(SubstrateTxStatus::InBlock { block_hash });
+            statuses.push(SubstrateTxStatus::Finalized {
+                block_hash,
+                extrinsic_hash,
+            });
+        }
+        Ok(stream::iter(statuses.into_iter().map(Ok)).boxed())
+    }
+}
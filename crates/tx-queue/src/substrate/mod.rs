@@ -12,43 +12,64 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod client;
 mod substrate_tx_queue;
+pub use client::{
+    MockExtrinsicOutcome, MockSubstrateTxClient, SubstrateDryRun,
+    SubstrateTxClient, SubstrateTxStatus, SubxtTxClient,
+};
+use std::sync::Arc;
 #[doc(hidden)]
 pub use substrate_tx_queue::*;
-use subxt_signer::sr25519::Keypair as Sr25519Pair;
-use webb::substrate::subxt::{self, OnlineClient};
+
+use tokio::sync::Mutex;
+use webb_relayer_utils::metric::Metrics;
 use webb_relayer_utils::Result;
 
 /// Config trait for Substrate tx queue.
 #[async_trait::async_trait]
 pub trait SubstrateTxQueueConfig {
+    /// The client the queue submits its extrinsics with.
+    type TxClient: SubstrateTxClient;
     /// Maximum number of milliseconds to wait before dequeuing a transaction from
     /// the queue.
     fn max_sleep_interval(&self, chain_id: u32) -> Result<u64>;
-    /// Returns a Substrate client.
+    /// Whether this chain was disabled at runtime.
     ///
-    /// # Arguments
+    /// While disabled, the queue stops dequeuing transactions.
+    fn is_chain_disabled(&self, chain_id: u32) -> bool;
+    /// The end of the maintenance window this chain is in, in seconds since
+    /// the unix epoch, if it is in one.
     ///
-    /// * `chain_id` - A string representing the chain Id.
-    async fn substrate_provider<C: subxt::Config>(
-        &self,
-        chain_id: u32,
-    ) -> Result<OnlineClient<C>>;
-    /// Returns a Substrate wallet.
+    /// Until then, the queue stops dequeuing transactions.
+    fn maintenance_until(&self, chain_id: u32) -> Option<u64>;
+    /// The metrics of the relayer.
+    fn metrics(&self) -> Arc<Mutex<Metrics>>;
+    /// Returns the client the queue submits its extrinsics with.
+    ///
+    /// It signs them with the relayer wallet of this chain.
     ///
     /// # Arguments
     ///
     /// * `chain_id` - A string representing the chain Id.
-    async fn substrate_wallet(&self, chain_id: u32) -> Result<Sr25519Pair>;
+    async fn substrate_tx_client(
+        &self,
+        chain_id: u32,
+    ) -> Result<Self::TxClient>;
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
+    use std::time::Duration;
 
     use subxt_signer::sr25519::dev;
+    use webb::evm::ethers::types::H256;
+    use webb::substrate::subxt::OnlineClient;
     use webb::substrate::tangle_runtime::api as RuntimeApi;
-    use webb_relayer_store::queue::{QueueItem, QueueStore};
+    use webb_relayer_store::queue::{
+        QueueItem, QueueItemState, QueueStore, TransactionQueueItemKey,
+    };
     use webb_relayer_store::sled::SledQueueKey;
     use webb_relayer_store::SledStore;
     use webb_relayer_types::suri::Suri;
@@ -74,40 +95,170 @@ mod tests {
         tracing::subscriber::set_default(s)
     }
 
-    pub struct TxQueueContext;
+    pub struct TxQueueContext<T> {
+        client: T,
+        metrics: Arc<Mutex<Metrics>>,
+    }
 
     #[async_trait::async_trait]
-    impl SubstrateTxQueueConfig for TxQueueContext {
+    impl<T> SubstrateTxQueueConfig for TxQueueContext<T>
+    where
+        T: SubstrateTxClient + Clone,
+    {
+        type TxClient = T;
+
         fn max_sleep_interval(&self, _chain_id: u32) -> Result<u64> {
-            Ok(7000_u64)
+            Ok(1000_u64)
         }
 
-        async fn substrate_provider<C: subxt::Config>(
-            &self,
-            _chain_id: u32,
-        ) -> Result<OnlineClient<C>> {
-            Ok(subxt::OnlineClient::<C>::new().await?)
+        fn is_chain_disabled(&self, _chain_id: u32) -> bool {
+            false
+        }
+
+        fn maintenance_until(&self, _chain_id: u32) -> Option<u64> {
+            None
+        }
+
+        fn metrics(&self) -> Arc<Mutex<Metrics>> {
+            self.metrics.clone()
         }
 
-        async fn substrate_wallet(
+        async fn substrate_tx_client(
             &self,
             _chain_id: u32,
-        ) -> Result<Sr25519Pair> {
-            Ok(Suri(dev::alice()).into())
+        ) -> Result<Self::TxClient> {
+            Ok(self.client.clone())
         }
     }
 
+    fn remark(i: u8) -> TypeErasedStaticTxPayload {
+        TypeErasedStaticTxPayload::new(
+            "System",
+            "remark_with_event",
+            vec![i],
+            [0u8; 32],
+        )
+    }
+
+    async fn wait_for_state<F>(
+        store: &SledStore,
+        key: SledQueueKey,
+        f: F,
+    ) -> QueueItemState
+    where
+        F: Fn(&QueueItemState) -> bool,
+    {
+        loop {
+            let item: Option<QueueItem<TypeErasedStaticTxPayload>> =
+                QueueStore::get_item(store, key).unwrap();
+            if let Some(state) = item.map(|item| item.state()) {
+                if f(&state) {
+                    return state;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn should_finalize_and_fail_extrinsics() -> Result<()> {
+        let chain_id = 1081u32;
+        let client = MockSubstrateTxClient::new();
+        let context = TxQueueContext {
+            client: client.clone(),
+            metrics: Arc::new(Mutex::new(Metrics::new()?)),
+        };
+        let store = Arc::new(SledStore::temporary()?);
+        let tx_queue = SubstrateTxQueue::new(context, chain_id, store.clone());
+        let handle = tokio::spawn(tx_queue.run::<TangleRuntimeConfig>());
+
+        // the extrinsic is submitted, finalized and marked as processed.
+        let tx = remark(1);
+        let key = SledQueueKey::from_substrate_with_custom_key(
+            chain_id,
+            tx.item_key(),
+        );
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx.clone()))?;
+        let state = tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Processed { .. })
+            }),
+        )
+        .await
+        .expect("extrinsic to be processed");
+        assert!(matches!(
+            state,
+            QueueItemState::Processed { tx_hash, .. }
+                if tx_hash == H256::from_low_u64_be(1)
+        ));
+        assert_eq!(client.submitted_extrinsics(), vec![tx.tx_data().to_vec()]);
+
+        // the extrinsic fails its dry run, and is never submitted.
+        client.set_outcome(MockExtrinsicOutcome::DispatchError(
+            "BadOrigin".into(),
+        ));
+        let tx = remark(2);
+        let key = SledQueueKey::from_substrate_with_custom_key(
+            chain_id,
+            tx.item_key(),
+        );
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        let state = tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Failed { .. })
+            }),
+        )
+        .await
+        .expect("extrinsic to fail");
+        assert!(matches!(
+            state,
+            QueueItemState::Failed { reason } if reason.contains("BadOrigin")
+        ));
+        assert_eq!(client.submitted_extrinsics().len(), 1);
+
+        // the node rejects the extrinsic, which is marked as failed.
+        client.set_outcome(MockExtrinsicOutcome::Rejected("stale".into()));
+        let tx = remark(3);
+        let key = SledQueueKey::from_substrate_with_custom_key(
+            chain_id,
+            tx.item_key(),
+        );
+        QueueStore::enqueue_item(&store, key, QueueItem::new(tx))?;
+        let state = tokio::time::timeout(
+            Duration::from_secs(10),
+            wait_for_state(&store, key, |s| {
+                matches!(s, QueueItemState::Failed { .. })
+            }),
+        )
+        .await
+        .expect("extrinsic to fail");
+        assert!(matches!(
+            state,
+            QueueItemState::Failed { reason } if reason.contains("stale")
+        ));
+        assert_eq!(client.submitted_extrinsics().len(), 1);
+
+        handle.abort();
+        Ok(())
+    }
+
     #[tokio::test]
     #[ignore = "needs substrate node"]
     async fn should_handle_many_txs() -> webb_relayer_utils::Result<()> {
         let _guard = setup_tracing();
         let chain_id = 1081u32;
 
-        let context = TxQueueContext;
+        let client = OnlineClient::<TangleRuntimeConfig>::new().await?;
+        let context = TxQueueContext {
+            client: SubxtTxClient::new(
+                client.clone(),
+                Suri(dev::alice()).into(),
+            ),
+            metrics: Arc::new(Mutex::new(Metrics::new()?)),
+        };
         let store = SledStore::temporary()?;
-        let client = context
-            .substrate_provider::<TangleRuntimeConfig>(chain_id)
-            .await?;
         let store = Arc::new(store);
         let tx_queue = SubstrateTxQueue::new(context, chain_id, store.clone());
         let _handle = tokio::spawn(tx_queue.run::<TangleRuntimeConfig>());
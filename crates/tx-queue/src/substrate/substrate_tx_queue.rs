@@ -16,21 +16,23 @@ use futures::StreamExt;
 use futures::TryFutureExt;
 use rand::Rng;
 use webb::substrate::subxt;
-use webb::substrate::subxt::rpc::types::DryRunResult;
+use webb_proposals::TypedChainId;
 use webb_relayer_store::queue::QueueItem;
 use webb_relayer_store::queue::QueueItemState;
+use webb_relayer_store::queue::QueueKey;
 use webb_relayer_store::queue::QueueStore;
 use webb_relayer_store::queue::TransactionQueueItemKey;
 use webb_relayer_store::sled::SledQueueKey;
 use webb_relayer_utils::static_tx_payload::TypeErasedStaticTxPayload;
-use webb_relayer_utils::TangleRuntimeConfig;
 
 use std::sync::Arc;
 use std::time::Duration;
 
-use webb::substrate::subxt::tx::TxStatus as TransactionStatus;
-
-use super::SubstrateTxQueueConfig;
+use super::{
+    SubstrateDryRun, SubstrateTxClient, SubstrateTxQueueConfig,
+    SubstrateTxStatus as TransactionStatus,
+};
+use crate::stats::QueueSampler;
 
 /// The SubstrateTxQueue stores transaction call params in bytes so the relayer can process them later.
 /// This prevents issues such as creating transactions with the same nonce.
@@ -92,12 +94,12 @@ where
             starting = true,
         );
 
+        let queue_key = SledQueueKey::from_substrate_chain_id(chain_id);
+        let sampler =
+            tokio::sync::Mutex::new(QueueSampler::new(queue_key.queue_name()));
         let task = || async {
             //  Tangle node connection
-            let maybe_client = self
-                .ctx
-                .substrate_provider::<TangleRuntimeConfig>(chain_id)
-                .await;
+            let maybe_client = self.ctx.substrate_tx_client(chain_id).await;
 
             let client = match maybe_client {
                 Ok(client) => client,
@@ -109,8 +111,31 @@ where
                     return Err(backoff::Error::transient(err));
                 }
             };
-            let pair = self.ctx.substrate_wallet(chain_id).await?;
             loop {
+                // let the supervisor know the queue is not stuck.
+                webb_relayer_utils::heartbeat::beat();
+                // sample the queue into its throughput metrics.
+                let mut sampler = sampler.lock().await;
+                if sampler.is_due() {
+                    match store.queue_stats(queue_key, sampler.since()) {
+                        Ok(stats) => sampler
+                            .record(stats, &*self.ctx.metrics().lock().await),
+                        Err(e) => {
+                            tracing::warn!(%e, "Failed to sample the tx queue")
+                        }
+                    }
+                }
+                drop(sampler);
+                // Pause the queue while the chain is disabled at runtime.
+                if self.ctx.is_chain_disabled(chain_id) {
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                    continue;
+                }
+                // Hold the items while the chain is under maintenance.
+                if self.ctx.maintenance_until(chain_id).is_some() {
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                    continue;
+                }
                 let maybe_item = store.peek_item(
                     SledQueueKey::from_substrate_chain_id(chain_id),
                 )?;
@@ -151,32 +176,14 @@ where
                     continue;
                 }
 
-                // update transaction status as Processing.
-                store.update_item(
-                    SledQueueKey::from_substrate_with_custom_key(
-                        chain_id,
-                        tx_item_key,
-                    ),
-                    |item| {
-                        let state = QueueItemState::Processing {
-                            step: "Item picked, processing".to_string(),
-                            progress: Some(0.0),
-                        };
-                        item.set_state(state);
-                        Ok(())
-                    },
-                )?;
-
                 let signed_extrinsic = client
-                    .tx()
-                    .create_signed(&payload, &pair, Default::default())
-                    .map_err(Into::into)
+                    .create_signed(&payload)
                     .map_err(backoff::Error::transient)
                     .await?;
                 // dry run test
-                let dry_run_outcome = signed_extrinsic.dry_run(None).await;
+                let dry_run_outcome = client.dry_run(&signed_extrinsic).await;
                 match dry_run_outcome {
-                    Ok(DryRunResult::Success) => {
+                    Ok(SubstrateDryRun::Success) => {
                         tracing::event!(
                             target: webb_relayer_utils::probe::TARGET,
                             tracing::Level::DEBUG,
@@ -186,7 +193,7 @@ where
                             tx = %payload,
                             dry_run = "passed"
                         );
-                        // update transaction status as Processing and set progress.
+                        // update transaction status as Validated.
                         store.update_item(
                             SledQueueKey::from_substrate_with_custom_key(
                                 chain_id,
@@ -195,16 +202,11 @@ where
                             |item: &mut QueueItem<
                                 TypeErasedStaticTxPayload,
                             >| {
-                                let state = QueueItemState::Processing {
-                                    step: "Dry run passed".to_string(),
-                                    progress: Some(0.3),
-                                };
-                                item.set_state(state);
-                                Ok(())
+                                item.transition_to(QueueItemState::Validated)
                             },
                         )?;
                     }
-                    Ok(DryRunResult::TransactionValidityError) => {
+                    Ok(SubstrateDryRun::TransactionValidityError) => {
                         // This kinda bugged in Substrate, as it returns this error
                         // in multiple scenarios, like when the transaction is mostly will
                         // exhaust the resources. However, the transaction may still be valid
@@ -220,11 +222,11 @@ where
                             tx = %payload,
                             errored = true,
                             error = "The transaction could not be included in the block.",
-                            signed_extrinsic = %hex::encode(signed_extrinsic.encoded()),
+                            signed_extrinsic = %hex::encode(&signed_extrinsic),
                             dry_run = "transaction_validity_error"
                         );
                     }
-                    Ok(DryRunResult::DispatchError(err)) => {
+                    Ok(SubstrateDryRun::DispatchError(err)) => {
                         tracing::event!(
                             target: webb_relayer_utils::probe::TARGET,
                             tracing::Level::ERROR,
@@ -234,7 +236,7 @@ where
                             tx = %payload,
                             errored = true,
                             error = %err,
-                            signed_extrinsic = %hex::encode(signed_extrinsic.encoded()),
+                            signed_extrinsic = %hex::encode(&signed_extrinsic),
                             dry_run = "dispatch_error",
                         );
                         // update transaction status as Failed and re insert into queue.
@@ -249,8 +251,7 @@ where
                                 let state = QueueItemState::Failed {
                                     reason: err.to_string(),
                                 };
-                                item.set_state(state);
-                                Ok(())
+                                item.transition_to(state)
                             },
                         )?;
 
@@ -266,7 +267,7 @@ where
                             tx = %payload,
                             errored = true,
                             error = %err,
-                            signed_extrinsic = %hex::encode(signed_extrinsic.encoded()),
+                            signed_extrinsic = %hex::encode(&signed_extrinsic),
                             dry_run = "failed"
                         );
                         // update transaction status as Failed and re insert into queue.
@@ -281,8 +282,7 @@ where
                                 let state = QueueItemState::Failed {
                                     reason: err.to_string(),
                                 };
-                                item.set_state(state);
-                                Ok(())
+                                item.transition_to(state)
                             },
                         )?;
 
@@ -290,8 +290,8 @@ where
                     }
                 }
                 // watch_extrinsic submits and returns transaction subscription
-                let mut progress = signed_extrinsic
-                    .submit_and_watch()
+                let mut progress = client
+                    .submit_and_watch(signed_extrinsic)
                     .inspect_err(|e| {
                         tracing::event!(
                             target: webb_relayer_utils::probe::TARGET,
@@ -314,13 +314,11 @@ where
                                     let state = QueueItemState::Failed {
                                         reason: e.to_string(),
                                     };
-                                    item.set_state(state);
-                                    Ok(())
+                                    item.transition_to(state)
                                 },
                             )
                             .unwrap_or_default();
                     })
-                    .map_err(Into::into)
                     .map_err(backoff::Error::transient)
                     .await?;
 
@@ -330,13 +328,8 @@ where
                         tx_item_key,
                     ),
                     |item| {
-                        let state = QueueItemState::Processing {
-                            step: "Transaction submitted on chain.."
-                                .to_string(),
-                            progress: Some(0.4),
-                        };
-                        item.set_state(state);
-                        Ok(())
+                        let state = QueueItemState::Broadcast { attempt: 1 };
+                        item.transition_to(state)
                     },
                 )?;
 
@@ -364,12 +357,11 @@ where
                                     let state = QueueItemState::Failed {
                                         reason: err.to_string(),
                                     };
-                                    item.set_state(state);
-                                    Ok(())
+                                    item.transition_to(state)
                                 },
                             )?;
-
-                            continue; // keep going.
+                            // the item failed, stop watching its progress.
+                            break;
                         }
                     };
 
@@ -384,21 +376,6 @@ where
                                 chain_id = %chain_id,
                                 status = "Future",
                             );
-                            store.update_item(
-                                SledQueueKey::from_substrate_with_custom_key(
-                                    chain_id,
-                                    tx_item_key,
-                                ),
-                                |item| {
-                                    let state = QueueItemState::Processing {
-                                        step: "Transaction status: Future"
-                                            .to_string(),
-                                        progress: Some(0.5),
-                                    };
-                                    item.set_state(state);
-                                    Ok(())
-                                },
-                            )?;
                         }
                         TransactionStatus::Ready => {
                             tracing::event!(
@@ -410,23 +387,8 @@ where
                                 chain_id = %chain_id,
                                 status = "Ready",
                             );
-                            store.update_item(
-                                SledQueueKey::from_substrate_with_custom_key(
-                                    chain_id,
-                                    tx_item_key,
-                                ),
-                                |item| {
-                                    let state = QueueItemState::Processing {
-                                        step: "Transaction status: Ready"
-                                            .to_string(),
-                                        progress: Some(0.6),
-                                    };
-                                    item.set_state(state);
-                                    Ok(())
-                                },
-                            )?;
                         }
-                        TransactionStatus::Broadcast(_) => {
+                        TransactionStatus::Broadcast => {
                             tracing::event!(
                                 target: webb_relayer_utils::probe::TARGET,
                                 tracing::Level::DEBUG,
@@ -436,23 +398,8 @@ where
                                 chain_id = %chain_id,
                                 status = "Broadcast",
                             );
-                            store.update_item(
-                                SledQueueKey::from_substrate_with_custom_key(
-                                    chain_id,
-                                    tx_item_key,
-                                ),
-                                |item| {
-                                    let state = QueueItemState::Processing {
-                                        step: "Transaction status: Broadcast"
-                                            .to_string(),
-                                        progress: Some(0.7),
-                                    };
-                                    item.set_state(state);
-                                    Ok(())
-                                },
-                            )?;
                         }
-                        TransactionStatus::InBlock(data) => {
+                        TransactionStatus::InBlock { block_hash } => {
                             tracing::event!(
                                 target: webb_relayer_utils::probe::TARGET,
                                 tracing::Level::DEBUG,
@@ -460,7 +407,7 @@ where
                                 ty = "SUBSTRATE",
                                 tx = %payload,
                                 chain_id = %chain_id,
-                                block_hash = ?data.block_hash(),
+                                block_hash = ?block_hash,
                                 status = "InBlock",
                             );
                             store.update_item(
@@ -469,17 +416,13 @@ where
                                     tx_item_key,
                                 ),
                                 |item| {
-                                    let state = QueueItemState::Processing {
-                                        step: "Transaction status: InBlock"
-                                            .to_string(),
-                                        progress: Some(0.8),
-                                    };
-                                    item.set_state(state);
-                                    Ok(())
+                                    let state =
+                                        QueueItemState::InBlock { block_hash };
+                                    item.transition_to(state)
                                 },
                             )?;
                         }
-                        TransactionStatus::Retracted(_) => {
+                        TransactionStatus::Retracted => {
                             tracing::event!(
                                 target: webb_relayer_utils::probe::TARGET,
                                 tracing::Level::DEBUG,
@@ -489,8 +432,22 @@ where
                                 chain_id = %chain_id,
                                 status = "Retracted",
                             );
+                            // the block was retracted by a reorg, the
+                            // transaction waits for another one.
+                            store.update_item(
+                                SledQueueKey::from_substrate_with_custom_key(
+                                    chain_id,
+                                    tx_item_key,
+                                ),
+                                |item| {
+                                    let state = QueueItemState::Broadcast {
+                                        attempt: 1,
+                                    };
+                                    item.transition_to(state)
+                                },
+                            )?;
                         }
-                        TransactionStatus::FinalityTimeout(_) => {
+                        TransactionStatus::FinalityTimeout => {
                             tracing::event!(
                                 target: webb_relayer_utils::probe::TARGET,
                                 tracing::Level::DEBUG,
@@ -501,7 +458,9 @@ where
                                 status = "FinalityTimeout",
                             );
                         }
-                        TransactionStatus::Finalized(_) => {
+                        TransactionStatus::Finalized {
+                            extrinsic_hash, ..
+                        } => {
                             tracing::event!(
                                 target: webb_relayer_utils::probe::TARGET,
                                 tracing::Level::DEBUG,
@@ -518,18 +477,32 @@ where
                                     tx_item_key,
                                 ),
                                 |item| {
-                                    let state = QueueItemState::Processing {
-                                        step: "Transaction status: Finalized"
-                                            .to_string(),
-                                        progress: Some(1.0),
+                                    let state = QueueItemState::Processed {
+                                        tx_hash: extrinsic_hash,
+                                        block_number: None,
+                                        gas_used: None,
+                                        effective_fee: None,
                                     };
-                                    item.set_state(state);
-                                    Ok(())
+                                    item.transition_to(state)
                                 },
                             )?;
+                            let command = format!(
+                                "{}.{}",
+                                payload.pallet_name(),
+                                payload.call_name()
+                            );
+                            self.ctx
+                                .metrics()
+                                .lock()
+                                .await
+                                .observe_time_to_finality(
+                                    TypedChainId::Substrate(chain_id),
+                                    &command,
+                                    item.elapsed_since_enqueued(),
+                                );
                         }
 
-                        TransactionStatus::Usurped(_) => {
+                        TransactionStatus::Usurped => {
                             tracing::event!(
                                 target: webb_relayer_utils::probe::TARGET,
                                 tracing::Level::DEBUG,
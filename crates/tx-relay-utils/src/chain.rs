@@ -0,0 +1,95 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The chain abstraction of the relayed commands.
+//!
+//! The commands are generic over the types a chain uses for its proofs,
+//! accounts and amounts. A chain implements [`ChainTypes`] once, and gets
+//! the whole command surface from it; its types validate the values of the
+//! commands while they are deserialized (e.g. the checksum of an address or
+//! the length of an element), so the handlers only see well-formed commands.
+
+use core::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::{
+    MaspRelayTransaction, VAnchorRelayTransaction,
+    WrapAndDepositRelayTransaction,
+};
+
+/// The types a chain uses in the commands relayed to it.
+pub trait ChainTypes {
+    /// The encoded zero-knowledge proof.
+    type Proof: Debug + Clone + DeserializeOwned;
+    /// The root set of a proof.
+    type Roots: Debug + Clone + DeserializeOwned;
+    /// A fixed-length element, e.g. a nullifier or a commitment.
+    type Element: Debug + Clone + DeserializeOwned;
+    /// An account identifier, e.g. the recipient of a withdrawal.
+    type Identifier: Debug + Clone + DeserializeOwned;
+    /// An unsigned balance, e.g. the fee of a transaction.
+    type Balance: Debug + Clone + DeserializeOwned;
+    /// A signed amount, e.g. the external amount of a transaction.
+    type SignedAmount: Debug + Clone + DeserializeOwned;
+    /// A token identifier.
+    type Token: Debug + Clone + DeserializeOwned;
+}
+
+/// A [`VAnchorRelayTransaction`] with the types of the chain `C`.
+pub type VAnchorCommand<C> = VAnchorRelayTransaction<
+    <C as ChainTypes>::Proof,
+    <C as ChainTypes>::Roots,
+    <C as ChainTypes>::Element,
+    <C as ChainTypes>::Identifier,
+    <C as ChainTypes>::Balance,
+    <C as ChainTypes>::SignedAmount,
+    <C as ChainTypes>::Token,
+>;
+
+/// A [`MaspRelayTransaction`] with the types of the chain `C`.
+pub type MaspCommand<C> = MaspRelayTransaction<
+    <C as ChainTypes>::Proof,
+    <C as ChainTypes>::Roots,
+    <C as ChainTypes>::Element,
+    <C as ChainTypes>::Identifier,
+    <C as ChainTypes>::Balance,
+    <C as ChainTypes>::SignedAmount,
+    <C as ChainTypes>::Token,
+>;
+
+/// A [`WrapAndDepositRelayTransaction`] with the types of the chain `C`.
+pub type WrapAndDepositCommand<C> = WrapAndDepositRelayTransaction<
+    <C as ChainTypes>::Proof,
+    <C as ChainTypes>::Roots,
+    <C as ChainTypes>::Element,
+    <C as ChainTypes>::Identifier,
+    <C as ChainTypes>::Balance,
+    <C as ChainTypes>::SignedAmount,
+    <C as ChainTypes>::Token,
+>;
+
+/// Enumerates the supported protocols for relaying transactions to the
+/// chain `C`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", bound(deserialize = ""))]
+pub enum Command<C: ChainTypes> {
+    /// A transaction of a VAnchor
+    VAnchor(VAnchorCommand<C>),
+    /// A transaction of a MASP VAnchor
+    MaspVanchor(MaspCommand<C>),
+    /// A deposit into a VAnchor, wrapping the tokens of the user
+    WrapAndDeposit(WrapAndDepositCommand<C>),
+}
@@ -14,6 +14,25 @@
 
 use serde::{Deserialize, Serialize};
 
+pub mod chain;
+
+/// A nonce and a timestamp signed by the recipient (or depositor) of a
+/// relayed command, so a captured command can't be relayed again.
+///
+/// The signature covers the chain, the contract, the external data hash of
+/// the proof, the nonce and the timestamp, the relayer rejects a command
+/// whose timestamp is too old or whose nonce was already used by its signer.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedEnvelope<S> {
+    /// A nonce chosen by the signer, used once
+    pub nonce: u64,
+    /// The unix timestamp, in seconds, the command was signed at
+    pub timestamp: u64,
+    /// The signature of the envelope
+    pub signature: S,
+}
+
 /// Proof data object for VAnchor proofs on any chain
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -64,6 +83,22 @@ pub struct VAnchorRelayTransaction<P, R, E, I, B, A, T> {
     pub proof_data: ProofData<P, R, E>,
     /// The external data structure for arbitrary inputs
     pub ext_data: ExtData<P, I, B, A, T>,
+    /// The highest gas price the transaction may be sent with, in wei.
+    ///
+    /// The relayer waits for the gas price to drop below it instead of
+    /// sending the transaction at a higher price.
+    #[serde(default)]
+    pub max_gas_price: Option<B>,
+    /// The unix timestamp, in seconds, the transaction must be finalized by.
+    ///
+    /// The relayer gives up on the transaction once it passed.
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// The signed envelope of the command, protecting it from replays.
+    ///
+    /// Required when the relayer enforces replay protection.
+    #[serde(default)]
+    pub envelope: Option<SignedEnvelope<P>>,
 }
 
 /// Proof data object for MASP VAnchor proofs on any chain.
@@ -112,4 +147,73 @@ pub struct MaspRelayTransaction<P, R, E, I, B, A, T> {
     pub proof_data: MaspProofData<P, R, E>,
     /// The external data structure for arbitrary inputs
     pub ext_data: ExtData<P, I, B, A, T>,
+    /// The highest gas price the transaction may be sent with, in wei.
+    ///
+    /// The relayer waits for the gas price to drop below it instead of
+    /// sending the transaction at a higher price.
+    #[serde(default)]
+    pub max_gas_price: Option<B>,
+    /// The unix timestamp, in seconds, the transaction must be finalized by.
+    ///
+    /// The relayer gives up on the transaction once it passed.
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// The signed envelope of the command, protecting it from replays.
+    ///
+    /// Required when the relayer enforces replay protection.
+    #[serde(default)]
+    pub envelope: Option<SignedEnvelope<P>>,
+}
+
+/// How the relayer gets the tokens it wraps and deposits for the user.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WrapFunding<I, B, E> {
+    /// An EIP-2612 permit of the token, signed by its owner, letting the
+    /// relayer transfer the tokens to itself.
+    #[serde(rename_all = "camelCase")]
+    Permit {
+        /// The owner of the tokens, who signed the permit
+        owner: I,
+        /// The amount of tokens the relayer is allowed to transfer
+        value: B,
+        /// The unix timestamp, in seconds, the permit expires at
+        deadline: B,
+        /// Recovery id of the signature
+        v: u8,
+        /// First half of the signature
+        r: E,
+        /// Second half of the signature
+        s: E,
+    },
+    /// A transfer of the tokens to the relayer, made by the user beforehand.
+    #[serde(rename_all = "camelCase")]
+    Transfer {
+        /// Hash of the transaction transferring the tokens
+        tx_hash: E,
+    },
+}
+
+/// Contains data that is relayed to the VAnchors to wrap the tokens of the
+/// user and deposit them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WrapAndDepositRelayTransaction<P, R, E, I, B, A, T> {
+    /// The zero-knowledge proof data structure for VAnchor transactions
+    pub proof_data: ProofData<P, R, E>,
+    /// The external data structure for arbitrary inputs
+    pub ext_data: ExtData<P, I, B, A, T>,
+    /// How the relayer gets the tokens to deposit
+    pub funding: WrapFunding<I, B, E>,
+    /// The highest gas price the transactions may be sent with, in wei.
+    #[serde(default)]
+    pub max_gas_price: Option<B>,
+    /// The unix timestamp, in seconds, the transactions must be finalized by.
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// The signed envelope of the command, protecting it from replays.
+    ///
+    /// Required when the relayer enforces replay protection.
+    #[serde(default)]
+    pub envelope: Option<SignedEnvelope<P>>,
 }
@@ -9,23 +9,50 @@ use std::ops::Add;
 use std::sync::{Arc, Mutex};
 use webb::evm::contract::protocol_solidity::fungible_token_wrapper::FungibleTokenWrapperContract;
 use webb::evm::contract::protocol_solidity::variable_anchor::VAnchorContract;
+use webb::evm::ethers::abi::parse_abi;
+use webb::evm::ethers::contract::Contract;
 use webb::evm::ethers::middleware::gas_oracle::GasOracle;
 use webb::evm::ethers::prelude::U256;
 use webb::evm::ethers::providers::Middleware;
 use webb::evm::ethers::signers::Signer;
-use webb::evm::ethers::types::Address;
+use webb::evm::ethers::types::{Address, Bytes, H160};
 use webb::evm::ethers::utils::{format_units, parse_units};
-use webb_chains_info::chain_info_by_chain_id;
+use webb_chains_info::{chain_info_by_chain_id, Rollup};
 use webb_price_oracle_backends::PriceBackend;
 use webb_proposals::TypedChainId;
 use webb_relayer_config::evm::RelayerFeeConfig;
 use webb_relayer_context::RelayerContext;
+use webb_relayer_store::ProfitAndLoss;
 use webb_relayer_utils::Result;
 
 /// Amount of time for which a `FeeInfo` is valid after creation
 const FEE_CACHE_TIME: core::time::Duration =
     core::time::Duration::from_secs(60);
 
+/// Typical calldata length of a relay (`transact`) transaction, in bytes.
+const RELAY_TX_CALLDATA_LEN: usize = 1_700;
+
+/// The `GasPriceOracle` predeploy of OP Stack chains.
+const OP_GAS_PRICE_ORACLE: Address = H160([
+    0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0f,
+]);
+
+/// The subset of the `GasPriceOracle` ABI used for the L1 data fee.
+const OP_GAS_PRICE_ORACLE_ABI: &[&str] =
+    &["function getL1Fee(bytes data) external view returns (uint256)"];
+
+/// The `ArbGasInfo` precompile of Arbitrum chains.
+const ARB_GAS_INFO: Address = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x6c,
+]);
+
+/// The subset of the `ArbGasInfo` ABI used for the L1 data fee.
+const ARB_GAS_INFO_ABI: &[&str] = &[
+    "function getPricesInWei() external view returns (uint256, uint256, uint256, uint256, uint256, uint256)",
+];
+
 /// Cache for previously generated fee info. Key consists of the VAnchor address and chain id.
 /// Entries are valid as long as `timestamp` is no older than `FEE_CACHE_TIME`.
 static FEE_INFO_CACHED: Lazy<
@@ -59,6 +86,17 @@ pub struct EvmFeeInfo {
     /// Number of decimals of the wrapped token, internally cached to recalculate estimated fee
     #[serde(skip)]
     wrapped_token_decimals: u32,
+    /// L1 data fee of a relay transaction on L2 chains, in `nativeToken`, internally cached to
+    /// recalculate estimated fee
+    #[serde(skip)]
+    l1_data_fee: U256,
+}
+
+impl EvmFeeInfo {
+    /// Number of decimals of the wrapped token.
+    pub fn wrapped_token_decimals(&self) -> u32 {
+        self.wrapped_token_decimals
+    }
 }
 
 /// Get the current fee info.
@@ -100,6 +138,7 @@ pub async fn get_evm_fee_info(
             &chain_config.relayer_fee_config,
             fee_info.gas_price,
             gas_amount,
+            fee_info.l1_data_fee,
             fee_info.native_token_price,
             fee_info.wrapped_token_price,
             fee_info.wrapped_token_decimals,
@@ -178,10 +217,14 @@ async fn generate_fee_info(
         .fetch()
         .await?;
 
+    // On L2 chains, the L1 data fee of the transaction dominates its cost.
+    let l1_data_fee = get_l1_data_fee(chain_id, ctx).await?;
+
     let estimated_fee = calculate_transaction_fee(
         relayer_fee_config,
         gas_price,
         gas_amount,
+        l1_data_fee,
         native_token_price,
         wrapped_token_price,
         wrapped_token_decimals,
@@ -211,9 +254,110 @@ async fn generate_fee_info(
         native_token_decimals,
         wrapped_token_price,
         wrapped_token_decimals,
+        l1_data_fee,
     })
 }
 
+/// Values the profit-and-loss ledger of a contract at the current token
+/// prices, and returns its net profit in USD.
+///
+/// Fees are valued in the wrapped token of the contract (only looked up if
+/// it collected any), gas and refunds in the native token of the chain.
+pub async fn get_evm_net_profit_usd(
+    chain_id: TypedChainId,
+    contract: Address,
+    pnl: &ProfitAndLoss,
+    ctx: &RelayerContext,
+) -> Result<f64> {
+    let (native_token, native_token_decimals) =
+        get_native_token_name_and_decimals(chain_id)?;
+    let wrapped_token = if pnl.fees_earned.is_zero() {
+        None
+    } else {
+        Some(
+            get_wrapped_token_name_and_decimals(chain_id, contract, ctx)
+                .await?,
+        )
+    };
+    let mut tokens = vec![native_token];
+    if let Some((name, _)) = &wrapped_token {
+        tokens.push(name.as_str());
+    }
+    let prices = ctx.price_oracle().get_prices(&tokens).await?;
+    let price_of = |token: &str| {
+        prices.get(token).copied().ok_or_else(|| {
+            webb_relayer_utils::Error::FetchTokenPriceError {
+                token: token.into(),
+            }
+        })
+    };
+
+    let expenses = format_units(
+        pnl.gas_spent + pnl.refunds_paid,
+        u32::from(native_token_decimals),
+    )?
+    .parse::<f64>()?;
+    let mut net_profit = -expenses * price_of(native_token)?;
+    if let Some((name, decimals)) = &wrapped_token {
+        let income =
+            format_units(pnl.fees_earned, *decimals)?.parse::<f64>()?;
+        net_profit += income * price_of(name)?;
+    }
+    Ok(net_profit)
+}
+
+/// The maximum cumulative refund (in `nativeToken`) that can be relayed to a
+/// single recipient within the refund cap window, if the chain has a cap.
+pub fn max_refund_per_recipient(
+    fee_info: &EvmFeeInfo,
+    relayer_fee_config: &RelayerFeeConfig,
+) -> Result<Option<U256>> {
+    let Some(max_refund_amount) = relayer_fee_config.max_refund_per_recipient
+    else {
+        return Ok(None);
+    };
+    let max_refund = parse_units(
+        max_refund_amount / fee_info.native_token_price,
+        u32::from(fee_info.native_token_decimals),
+    )?;
+    Ok(Some(max_refund.into()))
+}
+
+/// Converts an amount of `wrappedToken` to its smallest unit, given the
+/// number of decimals of the wrapped token.
+pub fn wrapped_token_units(
+    wrapped_token_decimals: u32,
+    amount: f64,
+) -> Result<U256> {
+    let units = parse_units(amount, wrapped_token_decimals)?;
+    Ok(units.into())
+}
+
+/// Returns the number of decimals of the wrapped token of the given
+/// contract, from the cached fee info if any, without generating a new one.
+pub async fn get_wrapped_token_decimals(
+    chain_id: TypedChainId,
+    vanchor: Address,
+    ctx: &RelayerContext,
+) -> Result<u32> {
+    let cached = FEE_INFO_CACHED
+        .lock()
+        .expect("lock fee info cache mutex")
+        .get(&(vanchor, chain_id))
+        .map(|fee_info| fee_info.wrapped_token_decimals);
+    if let Some(decimals) = cached {
+        return Ok(decimals);
+    }
+    let provider = ctx.evm_provider(chain_id.underlying_chain_id()).await?;
+    let client = Arc::new(provider);
+    let anchor_contract = VAnchorContract::new(vanchor, client.clone());
+    let token_address = anchor_contract.token().call().await?;
+    let token_contract =
+        FungibleTokenWrapperContract::new(token_address, client);
+    let decimals = token_contract.decimals().call().await?;
+    Ok(decimals.into())
+}
+
 async fn max_refund(
     chain_id: TypedChainId,
     relayer_fee_config: &RelayerFeeConfig,
@@ -222,7 +366,10 @@ async fn max_refund(
     ctx: &RelayerContext,
 ) -> Result<U256> {
     let wallet = ctx.evm_wallet(chain_id.underlying_chain_id()).await?;
-    let provider = ctx.evm_provider(chain_id.underlying_chain_id()).await?;
+    // the refund is capped by the balance, read through the quorum if any.
+    let provider = ctx
+        .evm_quorum_provider(chain_id.underlying_chain_id())
+        .await?;
     let relayer_balance = provider.get_balance(wallet.address(), None).await?;
 
     // Get the maximum refund amount in USD from the config.
@@ -246,12 +393,13 @@ fn calculate_transaction_fee(
     relayer_fee_config: &RelayerFeeConfig,
     gas_price: U256,
     gas_amount: U256,
+    l1_data_fee: U256,
     native_token_price: f64,
     wrapped_token_price: f64,
     wrapped_token_decimals: u32,
 ) -> Result<U256> {
-    // Step 1: Calculate the tx fee in native token (in wei)
-    let tx_fee_native_token_wei = gas_price * gas_amount;
+    // Step 1: Calculate the tx fee in native token (in wei), including the L1 data fee on L2s
+    let tx_fee_native_token_wei = gas_price * gas_amount + l1_data_fee;
     let tx_fee_native_token = format_units(tx_fee_native_token_wei, "ether")?;
     // Step 2: Convert the tx fee to USD using the coingecko API.
     let tx_fee_tokens = tx_fee_native_token
@@ -274,6 +422,57 @@ fn calculate_transaction_fee(
     Ok(fee_with_profit)
 }
 
+/// Returns the L1 data fee (in `nativeToken` wei) of a relay transaction on the given chain.
+///
+/// Only L2 chains (as listed in chains-info) charge it, it is zero for any other chain.
+async fn get_l1_data_fee(
+    chain_id: TypedChainId,
+    ctx: &RelayerContext,
+) -> Result<U256> {
+    let rollup = match chain_id {
+        TypedChainId::Evm(id) => {
+            chain_info_by_chain_id(u64::from(id)).and_then(|info| info.rollup)
+        }
+        _ => None,
+    };
+    let Some(rollup) = rollup else {
+        return Ok(U256::zero());
+    };
+    let client = ctx.evm_provider(chain_id.underlying_chain_id()).await?;
+    // Non-zero bytes, so the data fee is not underestimated.
+    let calldata = Bytes::from(vec![0xff; RELAY_TX_CALLDATA_LEN]);
+    let l1_data_fee = match rollup {
+        Rollup::Optimism => {
+            let oracle = Contract::new(
+                OP_GAS_PRICE_ORACLE,
+                parse_abi(OP_GAS_PRICE_ORACLE_ABI)
+                    .expect("gas price oracle abi is valid"),
+                client,
+            );
+            oracle
+                .method::<_, U256>("getL1Fee", calldata)?
+                .call()
+                .await?
+        }
+        Rollup::Arbitrum => {
+            let gas_info = Contract::new(
+                ARB_GAS_INFO,
+                parse_abi(ARB_GAS_INFO_ABI).expect("arb gas info abi is valid"),
+                client,
+            );
+            let (_, per_l1_calldata_byte, _, _, _, _) = gas_info
+                .method::<_, (U256, U256, U256, U256, U256, U256)>(
+                    "getPricesInWei",
+                    (),
+                )?
+                .call()
+                .await?;
+            per_l1_calldata_byte * U256::from(calldata.len())
+        }
+    };
+    Ok(l1_data_fee)
+}
+
 /// Returns the name and decimals of the wrapped token for the given chain.
 /// then converts it to the underlying token token name to be used in the price oracle.
 async fn get_wrapped_token_name_and_decimals(
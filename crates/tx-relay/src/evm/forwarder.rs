@@ -0,0 +1,261 @@
+//! Relaying through trusted forwarders (ERC-2771).
+//!
+//! The transaction built for a command is wrapped into a `ForwardRequest`
+//! from the sponsor account, signed with its key following EIP-712, and sent
+//! to `execute` of the forwarder by the relayer account, which only pays for
+//! its gas. The forwarder appends the sponsor address to the calldata, so the
+//! recipient contract sees the sponsor as the sender.
+//!
+//! The requests of a sponsor are relayed one at a time: a request is signed
+//! with the current nonce of the sponsor in the forwarder, which only
+//! advances once the previous request is mined.
+
+use std::sync::Arc;
+use webb::evm::ethers::abi::{encode, parse_abi, Token};
+use webb::evm::ethers::contract::Contract;
+use webb::evm::ethers::prelude::Middleware;
+use webb::evm::ethers::signers::{LocalWallet, Signer};
+use webb::evm::ethers::types::transaction::eip2718::TypedTransaction;
+use webb::evm::ethers::types::{
+    Address, Bytes, TransactionRequest, H256, H512, U256,
+};
+use webb::evm::ethers::utils::keccak256;
+use webb_relayer_config::evm::ForwarderConfig;
+use webb_relayer_store::queue::{QueueItem, QueueStore};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{ForwardedRequestStore, SledStore};
+use webb_relayer_utils::revert::decode_revert_data;
+use webb_relayer_utils::TransactionRelayingError;
+
+/// The subset of the `MinimalForwarder` ABI used for relaying.
+const FORWARDER_ABI: &[&str] = &[
+    "function getNonce(address from) external view returns (uint256)",
+    "function execute((address,address,uint256,uint256,uint256,bytes) req, bytes signature) external payable returns (bool, bytes)",
+];
+
+const EIP712_DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+
+const FORWARD_REQUEST_TYPE: &str =
+    "ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,bytes data)";
+
+/// A request executed by a trusted forwarder on behalf of `from`.
+#[derive(Debug, Clone)]
+struct ForwardRequest {
+    from: Address,
+    to: Address,
+    value: U256,
+    gas: U256,
+    nonce: U256,
+    data: Bytes,
+}
+
+impl ForwardRequest {
+    /// The EIP-712 digest of the request, signed by `from`.
+    fn digest(&self, forwarder: &ForwarderConfig, chain_id: u32) -> [u8; 32] {
+        let domain_separator = keccak256(encode(&[
+            Token::FixedBytes(keccak256(EIP712_DOMAIN_TYPE).to_vec()),
+            Token::FixedBytes(keccak256(&forwarder.domain_name).to_vec()),
+            Token::FixedBytes(keccak256(&forwarder.domain_version).to_vec()),
+            Token::Uint(chain_id.into()),
+            Token::Address(forwarder.address),
+        ]));
+        let struct_hash = keccak256(encode(&[
+            Token::FixedBytes(keccak256(FORWARD_REQUEST_TYPE).to_vec()),
+            Token::Address(self.from),
+            Token::Address(self.to),
+            Token::Uint(self.value),
+            Token::Uint(self.gas),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.data).to_vec()),
+        ]));
+        let mut message = Vec::with_capacity(66);
+        message.extend_from_slice(&[0x19, 0x01]);
+        message.extend_from_slice(&domain_separator);
+        message.extend_from_slice(&struct_hash);
+        keccak256(message)
+    }
+}
+
+/// The turn of a forwarded request among the requests of its sponsor.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SponsorTurn {
+    chain_id: u32,
+    forwarder: Address,
+    sponsor: Address,
+    /// The last request relayed for the sponsor when the request was built.
+    previous: Option<H512>,
+}
+
+impl SponsorTurn {
+    /// Records the request as the last one relayed for its sponsor.
+    ///
+    /// Rejects the request if another request of the sponsor was relayed
+    /// since it was built, as both are signed with the same nonce.
+    pub(crate) fn take(
+        &self,
+        store: &SledStore,
+        item_key: H512,
+    ) -> Result<(), TransactionRelayingError> {
+        let taken = store
+            .replace_forwarded_request(
+                self.chain_id,
+                self.forwarder,
+                self.sponsor,
+                self.previous,
+                item_key,
+            )
+            .map_err(|e| {
+                TransactionRelayingError::TransactionQueueError(e.to_string())
+            })?;
+        if !taken {
+            return Err(TransactionRelayingError::ForwarderBusy(format!(
+                "{:?}",
+                self.sponsor
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Rejects a request of a sponsor whose last relayed request is still in
+/// the queue, and returns the item key of that last request.
+fn check_sponsor_idle(
+    store: &SledStore,
+    forwarder: Address,
+    chain_id: u32,
+    sponsor: Address,
+) -> Result<Option<H512>, TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    let previous = store
+        .last_forwarded_request(chain_id, forwarder, sponsor)
+        .map_err(|e| TransactionQueueError(e.to_string()))?;
+    let Some(item_key) = previous else {
+        return Ok(None);
+    };
+    let key = SledQueueKey::from_evm_with_custom_key(
+        chain_id,
+        item_key.to_fixed_bytes(),
+    );
+    let item: Option<QueueItem<TypedTransaction>> =
+        QueueStore::get_item(store, key)
+            .map_err(|e| TransactionQueueError(e.to_string()))?;
+    if item.map_or(false, |item| item.state().is_pending()) {
+        return Err(ForwarderBusy(format!("{sponsor:?}")));
+    }
+    Ok(previous)
+}
+
+/// Wraps a transaction into a call to `execute` of the trusted forwarder,
+/// executing it on behalf of the sponsor account of the forwarder.
+///
+/// The gas of the request is estimated for the call from the forwarder, and
+/// the call to `execute` is simulated: the forwarder does not revert when
+/// the call it executes does, it returns `false` instead. The nonce is the
+/// current nonce of the sponsor in the forwarder, so the request is rejected
+/// while the last one of the sponsor is still in the queue; the returned
+/// [`SponsorTurn`] is taken when the request is enqueued.
+///
+/// # Arguments
+///
+/// * `client` - The client of the relayer account, which pays for the gas
+/// * `store` - The store the requests of the sponsor are tracked in
+/// * `forwarder` - The trusted forwarder configuration
+/// * `chain_id` - The chain the transaction is sent to
+/// * `tx` - The transaction to forward
+pub(crate) async fn forward_tx<M>(
+    client: Arc<M>,
+    store: &SledStore,
+    forwarder: &ForwarderConfig,
+    chain_id: u32,
+    tx: TypedTransaction,
+) -> Result<(TypedTransaction, SponsorTurn), TransactionRelayingError>
+where
+    M: Middleware + 'static,
+{
+    use TransactionRelayingError::*;
+    let sponsor =
+        LocalWallet::from_bytes(forwarder.sponsor_private_key.as_bytes())
+            .map_err(|e| NetworkConfigurationError(e.to_string(), chain_id))?;
+    let previous = check_sponsor_idle(
+        store,
+        forwarder.address,
+        chain_id,
+        sponsor.address(),
+    )?;
+    let to = tx
+        .to_addr()
+        .copied()
+        .ok_or_else(|| ClientError("forwarded tx has no recipient".into()))?;
+    let value = tx.value().copied().unwrap_or_default();
+    let data = tx.data().cloned().unwrap_or_default();
+
+    let contract = Contract::new(
+        forwarder.address,
+        parse_abi(FORWARDER_ABI).expect("forwarder abi is valid"),
+        client.clone(),
+    );
+    let nonce = contract
+        .method::<_, U256>("getNonce", sponsor.address())
+        .map_err(|e| ClientError(e.to_string()))?
+        .call()
+        .await
+        .map_err(|e| ClientError(e.to_string()))?;
+    // the forwarder appends the sponsor address to the calldata.
+    let forwarded_data: Bytes = [data.as_ref(), sponsor.address().as_bytes()]
+        .concat()
+        .into();
+    let forwarded_call = TransactionRequest::new()
+        .from(forwarder.address)
+        .to(to)
+        .value(value)
+        .data(forwarded_data);
+    let gas = client
+        .estimate_gas(&forwarded_call.into(), None)
+        .await
+        .map_err(|e| ClientError(e.to_string()))?;
+
+    let request = ForwardRequest {
+        from: sponsor.address(),
+        to,
+        value,
+        gas,
+        nonce,
+        data,
+    };
+    let digest = request.digest(forwarder, chain_id);
+    let signature = sponsor
+        .sign_hash(H256(digest))
+        .map_err(|e| ClientError(e.to_string()))?;
+    let req = (
+        request.from,
+        request.to,
+        request.value,
+        request.gas,
+        request.nonce,
+        request.data,
+    );
+    let call = contract
+        .method::<_, (bool, Bytes)>(
+            "execute",
+            (req, Bytes::from(signature.to_vec())),
+        )
+        .map_err(|e| ClientError(e.to_string()))?
+        .value(value);
+    let (success, returndata) =
+        call.call().await.map_err(|e| ClientError(e.to_string()))?;
+    if !success {
+        let reason = decode_revert_data(&returndata)
+            .unwrap_or_else(|| String::from("unknown reason"));
+        return Err(ClientError(format!(
+            "the forwarded call reverts: {reason}"
+        )));
+    }
+    let turn = SponsorTurn {
+        chain_id,
+        forwarder: forwarder.address,
+        sponsor: sponsor.address(),
+        previous,
+    };
+    Ok((call.tx, turn))
+}
@@ -0,0 +1,199 @@
+//! Gas estimation of relayed VAnchor transactions through simulation.
+//!
+//! A representative `transact` call is built with a dummy proof and estimated
+//! with `eth_estimateGas` against the latest state, overriding the code of
+//! the anchor's verifier so the dummy proof is accepted. The cost of the
+//! skipped proof verification is then added back based on the number of
+//! public inputs of the anchor's circuit.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::ops::Add;
+use std::sync::{Arc, Mutex};
+use webb::evm::contract::protocol_solidity::variable_anchor::{
+    CommonExtData, Encryptions, PublicInputs, VAnchorContract,
+};
+use webb::evm::ethers::abi::{encode, parse_abi, Token};
+use webb::evm::ethers::contract::Contract;
+use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
+use webb::evm::ethers::prelude::U256;
+use webb::evm::ethers::providers::call_raw::spoof;
+use webb::evm::ethers::signers::Signer;
+use webb::evm::ethers::types::{Address, Bytes, H256, I256};
+use webb::evm::ethers::utils::keccak256;
+use webb_proposals::TypedChainId;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_utils::Result;
+
+/// Amount of time for which a simulated gas amount is valid.
+const SIMULATED_GAS_CACHE_TIME: core::time::Duration =
+    core::time::Duration::from_secs(60 * 60);
+
+/// Cache for previously simulated gas amounts. Key consists of the VAnchor
+/// address and chain id.
+static SIMULATED_GAS_CACHED: Lazy<
+    Mutex<HashMap<(Address, TypedChainId), (U256, DateTime<Utc>)>>,
+> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The subset of the VAnchor ABI used for the simulation.
+const VANCHOR_ABI: &[&str] = &[
+    "function getLastRoot() external view returns (uint256)",
+    "function getLatestNeighborRoots() external view returns (uint256[])",
+    "function verifier() external view returns (address)",
+    "function token() external view returns (address)",
+];
+
+/// Runtime code of a verifier that accepts any proof: returns `true`.
+const ALWAYS_TRUE_VERIFIER_CODE: [u8; 10] =
+    [0x60, 0x01, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+
+/// The number of inputs of the smallest VAnchor circuit.
+const INPUTS: usize = 2;
+
+/// Length of a typical encrypted output, in bytes.
+const ENCRYPTED_OUTPUT_LEN: usize = 232;
+
+/// The size of the BN254 scalar field.
+const FIELD_SIZE: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+/// Returns the gas amount of a typical relayed `transact` call on the given
+/// VAnchor, simulating it if it was not recently simulated.
+pub async fn get_simulated_transact_gas(
+    chain_id: TypedChainId,
+    vanchor: Address,
+    ctx: &RelayerContext,
+) -> Result<U256> {
+    let cached = {
+        let mut lock = SIMULATED_GAS_CACHED
+            .lock()
+            .expect("lock simulated gas cache mutex");
+        // Remove all items from cache which are older than the cache time.
+        lock.retain(|_, (_, timestamp)| {
+            let valid_until = timestamp.add(
+                chrono::Duration::from_std(SIMULATED_GAS_CACHE_TIME).expect(
+                    "SIMULATED_GAS_CACHE_TIME must be convertible to chrono::Duration",
+                ),
+            );
+            valid_until > Utc::now()
+        });
+        lock.get(&(vanchor, chain_id)).map(|(gas, _)| *gas)
+    };
+    if let Some(gas) = cached {
+        return Ok(gas);
+    }
+    let gas = simulate_transact_gas(chain_id, vanchor, ctx).await?;
+    SIMULATED_GAS_CACHED
+        .lock()
+        .expect("lock simulated gas cache mutex")
+        .insert((vanchor, chain_id), (gas, Utc::now()));
+    Ok(gas)
+}
+
+/// Simulates a relayed `transact` call with a dummy proof on the given
+/// VAnchor, paying a fee to the relayer.
+async fn simulate_transact_gas(
+    chain_id: TypedChainId,
+    vanchor: Address,
+    ctx: &RelayerContext,
+) -> Result<U256> {
+    let requested_chain = chain_id.underlying_chain_id();
+    let chain_config = ctx
+        .config
+        .evm
+        .get(&requested_chain.to_string())
+        .ok_or_else(|| webb_relayer_utils::Error::ChainNotFound {
+            chain_id: requested_chain.to_string(),
+        })?;
+    let wallet = ctx.evm_wallet(requested_chain).await?;
+    let relayer = chain_config.beneficiary.unwrap_or(wallet.address());
+    let client = ctx.evm_provider(requested_chain).await?;
+
+    let anchor = Contract::new(
+        vanchor,
+        parse_abi(VANCHOR_ABI).expect("vanchor abi is valid"),
+        client.clone(),
+    );
+    let last_root = anchor.method::<_, U256>("getLastRoot", ())?.call().await?;
+    let neighbor_roots = anchor
+        .method::<_, Vec<U256>>("getLatestNeighborRoots", ())?
+        .call()
+        .await?;
+    let verifier = anchor.method::<_, Address>("verifier", ())?.call().await?;
+    let token = anchor.method::<_, Address>("token", ())?.call().await?;
+
+    let roots: Vec<u8> = std::iter::once(last_root)
+        .chain(neighbor_roots.iter().copied())
+        .flat_map(|root| H256::from_uint(&root).to_fixed_bytes())
+        .collect();
+    let field_size =
+        U256::from_dec_str(FIELD_SIZE).expect("field size is valid");
+    let random_field_element =
+        || U256::from(H256::random().as_bytes()) % field_size;
+
+    // a withdrawal of nothing, paying a fee of 1 unit to the relayer.
+    let fee = U256::one();
+    let encrypted_output = Bytes::from(vec![0u8; ENCRYPTED_OUTPUT_LEN]);
+    let ext_data = encode(&[Token::Tuple(vec![
+        Token::Address(Address::zero()),
+        Token::Int(I256::zero().into_raw()),
+        Token::Address(relayer),
+        Token::Uint(fee),
+        Token::Uint(U256::zero()),
+        Token::Address(token),
+        Token::Bytes(encrypted_output.to_vec()),
+        Token::Bytes(encrypted_output.to_vec()),
+    ])]);
+    let ext_data_hash = U256::from(keccak256(ext_data)) % field_size;
+
+    let common_ext_data = CommonExtData {
+        recipient: Address::zero(),
+        ext_amount: I256::zero(),
+        relayer,
+        fee,
+        refund: U256::zero(),
+        token,
+    };
+    let public_inputs = PublicInputs {
+        roots: roots.into(),
+        extension_roots: Bytes::default(),
+        input_nullifiers: (0..INPUTS).map(|_| random_field_element()).collect(),
+        output_commitments: [random_field_element(), random_field_element()],
+        public_amount: field_size - fee,
+        ext_data_hash,
+    };
+    let encryptions = Encryptions {
+        encrypted_output_1: encrypted_output.clone(),
+        encrypted_output_2: encrypted_output,
+    };
+    let contract = VAnchorContract::new(vanchor, client.clone());
+    let call = contract.transact(
+        Bytes::from(vec![0u8; 256]),
+        [0u8; 32].into(),
+        common_ext_data,
+        public_inputs,
+        encryptions,
+    );
+    let mut typed_tx: TypedTransaction = call.tx;
+    typed_tx.set_from(wallet.address());
+
+    let state =
+        spoof::code(verifier, Bytes::from(ALWAYS_TRUE_VERIFIER_CODE.to_vec()));
+    let gas: U256 = client
+        .request("eth_estimateGas", (typed_tx, "latest", state))
+        .await?;
+    let public_inputs_count = INPUTS + neighbor_roots.len() + 6;
+    Ok(gas + verification_gas(public_inputs_count))
+}
+
+/// The gas used to verify a Groth16 proof with the given number of public
+/// inputs, through the BN254 precompiles (EIP-1108).
+fn verification_gas(public_inputs_count: usize) -> U256 {
+    // 4 pairings, plus a scalar multiplication and an addition per input.
+    let pairing = 45_000 + 4 * 34_000;
+    let per_input = 6_000 + 150;
+    // calldata decoding and bookkeeping of the verifier.
+    let overhead = 20_000;
+    U256::from(pairing + per_input * public_inputs_count + overhead)
+}
@@ -1,6 +1,6 @@
 use super::*;
 use crate::evm::fees::{get_evm_fee_info, EvmFeeInfo};
-use crate::TransactionItemKey;
+use crate::{RelayJobId, TransactionItemKey};
 use ethereum_types::{H512, U256};
 use futures::TryFutureExt;
 use std::{collections::HashMap, sync::Arc};
@@ -17,12 +17,27 @@ use webb::evm::{
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
 use webb_relayer_context::RelayerContext;
 use webb_relayer_handler_utils::{EvmCommandType, EvmVanchorCommand};
-use webb_relayer_store::queue::{
-    QueueItem, QueueStore, TransactionQueueItemKey,
-};
+use webb_relayer_store::queue::{QueueItem, TransactionQueueItemKey};
 use webb_relayer_store::sled::SledQueueKey;
 use webb_relayer_utils::TransactionRelayingError;
 
+/// A MASP VAnchor withdrawal that passed validation and is ready to be
+/// enqueued once its fee is checked.
+#[derive(Debug, Clone)]
+struct PreparedMaspVAnchorTx {
+    typed_tx: TypedTransaction,
+    chain_id: u32,
+    resource_id: ResourceId,
+    relayer: types::Address,
+    contract: types::Address,
+    recipient: types::Address,
+    token: types::Address,
+    fee: U256,
+    refund: U256,
+    max_gas_price: Option<U256>,
+    deadline: Option<u64>,
+}
+
 /// Handler for MASP VAnchor commands
 ///
 /// # Arguments
@@ -36,17 +51,71 @@ pub async fn handle_masp_vanchor_relay_tx<'a>(
     contract: types::Address,
     cmd: EvmVanchorCommand,
 ) -> Result<TransactionItemKey, TransactionRelayingError> {
+    check_accepting(&ctx)?;
+    let prepared =
+        build_masp_vanchor_relay_tx(&ctx, chain_id, contract, cmd).await?;
+    check_masp_vanchor_relay_tx_fee(&ctx, &prepared).await?;
+    enqueue_masp_vanchor_relay_tx(&ctx, prepared).await
+}
+
+/// Submits a MASP VAnchor command as a relay job.
+///
+/// Like [`submit_vanchor_relay_job`](super::vanchor::submit_vanchor_relay_job),
+/// the command is validated and its transaction is built right away, and
+/// the fee check and enqueuing run in the background.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `cmd` - The command to execute
+#[tracing::instrument(skip(ctx))]
+pub async fn submit_masp_vanchor_relay_job(
+    ctx: Arc<RelayerContext>,
+    chain_id: TypedChainId,
+    contract: types::Address,
+    cmd: EvmVanchorCommand,
+) -> Result<(RelayJobId, TransactionItemKey), TransactionRelayingError> {
+    check_accepting(&ctx)?;
+    let permit = acquire_relay_job(&ctx)?;
+    let prepared =
+        build_masp_vanchor_relay_tx(&ctx, chain_id, contract, cmd).await?;
+    let item_key = H512::from_slice(prepared.typed_tx.item_key().as_slice());
+    let job_id = submit_relay_job(
+        ctx,
+        prepared.chain_id,
+        item_key,
+        permit,
+        |ctx| async move {
+            check_masp_vanchor_relay_tx_fee(&ctx, &prepared).await?;
+            enqueue_masp_vanchor_relay_tx(&ctx, prepared).await
+        },
+    )?;
+    Ok((job_id, item_key))
+}
+
+/// Validates a MASP VAnchor command and builds the transaction for it.
+///
+/// The gas estimation and the fee check are done by
+/// [`check_masp_vanchor_relay_tx_fee`].
+async fn build_masp_vanchor_relay_tx(
+    ctx: &RelayerContext,
+    chain_id: TypedChainId,
+    contract: types::Address,
+    cmd: EvmVanchorCommand,
+) -> Result<PreparedMaspVAnchorTx, TransactionRelayingError> {
     use TransactionRelayingError::*;
     let requested_chain = chain_id.underlying_chain_id();
     let cmd = match cmd {
         EvmCommandType::VAnchor(cmd) => cmd,
         _ => return Err(InvalidCommand),
     };
+    check_deadline(cmd.deadline)?;
     let chain = ctx
         .config
         .evm
         .get(&requested_chain.to_string())
         .ok_or(UnsupportedChain(requested_chain))?;
+    check_relaying_enabled(chain)?;
     let supported_contracts: HashMap<_, _> = chain
         .contracts
         .iter()
@@ -73,11 +142,31 @@ pub async fn handle_masp_vanchor_relay_tx<'a>(
         return Err(InvalidRelayerAddress(cmd.ext_data.relayer.to_string()));
     }
 
-    // validate that the roots are multiple of 32s
+    check_token_allowed(chain, *cmd.ext_data.token)?;
+
+    let target_system = TargetSystem::new_contract_address(
+        contract_config.common.address.to_fixed_bytes(),
+    );
+    let resource_id =
+        ResourceId::new(target_system, TypedChainId::Evm(chain.chain_id));
+    check_resource_in_flight(ctx.store(), chain, resource_id)?;
+    check_nullifiers_unspent(
+        ctx.store(),
+        resource_id,
+        &cmd.proof_data.input_nullifiers,
+    )?;
+
+    // validate that the roots are multiple of 32s, and known.
     let roots = cmd.proof_data.roots.to_vec();
-    if roots.len() % 32 != 0 {
-        return Err(InvalidMerkleRoots);
-    }
+    check_merkle_roots(ctx.store(), resource_id, &roots)?;
+    check_onchain_with_quorum(
+        ctx,
+        chain,
+        contract,
+        &cmd.proof_data.input_nullifiers,
+        &roots,
+    )
+    .await?;
 
     let provider = ctx.evm_provider(requested_chain).await.map_err(|e| {
         NetworkConfigurationError(e.to_string(), requested_chain)
@@ -87,12 +176,12 @@ pub async fn handle_masp_vanchor_relay_tx<'a>(
     let contract = MultiAssetVAnchorContract::new(contract, client.clone());
 
     let common_ext_data = CommonExtData {
-        recipient: cmd.ext_data.recipient,
+        recipient: *cmd.ext_data.recipient,
         ext_amount: cmd.ext_data.ext_amount.0,
-        relayer: cmd.ext_data.relayer,
+        relayer: *cmd.ext_data.relayer,
         fee: cmd.ext_data.fee,
         refund: cmd.ext_data.refund,
-        token: cmd.ext_data.token,
+        token: *cmd.ext_data.token,
     };
     let public_inputs = PublicInputs {
         roots: roots.into(),
@@ -135,97 +224,194 @@ pub async fn handle_masp_vanchor_relay_tx<'a>(
     if !cmd.ext_data.refund.is_zero() {
         call = call.value(cmd.ext_data.refund);
     }
+    let wrapped_token_decimals = fees::get_wrapped_token_decimals(
+        TypedChainId::Evm(chain.chain_id),
+        contract_config.common.address,
+        ctx,
+    )
+    .await
+    .map_err(|e| ClientError(e.to_string()))?;
+    check_withdrawal_policy(
+        &contract_config.withdrawal_policy,
+        cmd.ext_data.ext_amount.0,
+        cmd.ext_data.fee,
+        wrapped_token_decimals,
+    )?;
+    Ok(PreparedMaspVAnchorTx {
+        typed_tx: call.tx,
+        chain_id: chain.chain_id,
+        resource_id,
+        relayer: client.signer().address(),
+        contract: contract_config.common.address,
+        recipient: *cmd.ext_data.recipient,
+        token: *cmd.ext_data.token,
+        fee: cmd.ext_data.fee,
+        refund: cmd.ext_data.refund,
+        max_gas_price: cmd.max_gas_price,
+        deadline: cmd.deadline,
+    })
+}
+
+/// Estimates the gas of a prepared MASP VAnchor transaction and checks that
+/// the fee and refund requested by the user cover it, then reserves its
+/// refund against the refund cap of the recipient.
+async fn check_masp_vanchor_relay_tx_fee(
+    ctx: &RelayerContext,
+    prepared: &PreparedMaspVAnchorTx,
+) -> Result<(), TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    let chain_id = prepared.chain_id;
+    let chain = ctx
+        .config
+        .evm
+        .get(&chain_id.to_string())
+        .ok_or(UnsupportedChain(chain_id))?;
+    let wallet = ctx
+        .evm_wallet(chain_id)
+        .await
+        .map_err(|e| NetworkConfigurationError(e.to_string(), chain_id))?;
+    let provider = ctx
+        .evm_provider(chain_id)
+        .await
+        .map_err(|e| NetworkConfigurationError(e.to_string(), chain_id))?;
+    let client = SignerMiddleware::new(provider, wallet);
 
-    let gas_amount = client
-        .estimate_gas(&call.tx, None)
+    let estimated_gas = client
+        .estimate_gas(&prepared.typed_tx, None)
         .await
         .map_err(|e| ClientError(e.to_string()))?;
-    let typed_chain_id = TypedChainId::Evm(chain.chain_id);
+    // the transaction is sent with the headroom, so the fee covers it too.
+    let gas_amount =
+        chain.gas_limit_with_headroom(prepared.contract, estimated_gas);
     let fee_info = get_evm_fee_info(
-        typed_chain_id,
-        contract_config.common.address,
+        TypedChainId::Evm(chain_id),
+        prepared.contract,
         gas_amount,
-        &ctx,
+        ctx,
     )
     .await
     .map_err(|e| ClientError(e.to_string()))?;
 
     // validate refund amount
-    if cmd.ext_data.refund > fee_info.max_refund {
+    if prepared.refund > fee_info.max_refund {
         let msg = format!(
             "User requested a refund which is higher than the maximum of {}",
             fee_info.max_refund
         );
         return Err(InvalidRefundAmount(msg));
     }
+    check_recipient_refund_cap(
+        ctx,
+        chain_id,
+        prepared.recipient,
+        prepared.refund,
+        &fee_info,
+    )?;
 
     // check the fee
     // TODO: This adjustment could potentially be exploited
     let adjusted_fee = fee_info.estimated_fee / 100 * 96;
     let wrapped_amount =
-        calculate_wrapped_refund_amount(cmd.ext_data.refund, &fee_info)
-            .map_err(|e| {
+        calculate_wrapped_refund_amount(prepared.refund, &fee_info).map_err(
+            |e| {
                 WrappingFeeError(format!(
                     "Failed to calculate wrapped refund amount: {e}"
                 ))
-            })?;
-    if cmd.ext_data.fee < adjusted_fee + wrapped_amount {
+            },
+        )?;
+    if prepared.fee < adjusted_fee + wrapped_amount {
         let msg = format!(
             "User sent a fee that is too low {} but expected {}",
-            cmd.ext_data.fee,
+            prepared.fee,
             adjusted_fee + wrapped_amount
         );
         return Err(InvalidRefundAmount(msg));
     }
 
-    let target_system = TargetSystem::new_contract_address(
-        contract_config.common.address.to_fixed_bytes(),
-    );
-    let resource_id = ResourceId::new(target_system, typed_chain_id);
+    reserve_recipient_refund(
+        ctx,
+        chain_id,
+        prepared.recipient,
+        prepared.typed_tx.item_key(),
+        prepared.refund,
+        &fee_info,
+    )
+}
 
-    let typed_tx: TypedTransaction = call.tx;
-    let item = QueueItem::new(typed_tx.clone());
+/// Enqueues a prepared MASP VAnchor transaction whose fee was checked,
+/// releasing its refund if it could not be enqueued.
+async fn enqueue_masp_vanchor_relay_tx(
+    ctx: &RelayerContext,
+    prepared: PreparedMaspVAnchorTx,
+) -> Result<TransactionItemKey, TransactionRelayingError> {
+    let typed_tx = prepared.typed_tx.clone();
+    let mut item = QueueItem::new(typed_tx.clone());
+    item.set_max_gas_price(prepared.max_gas_price);
+    item.set_deadline(prepared.deadline);
+    item.set_resource_id(Some(resource_key(prepared.resource_id)));
     let tx_key = SledQueueKey::from_evm_with_custom_key(
-        chain.chain_id,
+        prepared.chain_id,
         typed_tx.item_key(),
     );
     let store = ctx.store();
-    QueueStore::<TypedTransaction>::enqueue_item(store, tx_key, item.clone())
-        .map_err(|_| {
-        TransactionQueueError(format!(
-            "Transaction item with key : {} failed to enqueue",
-            tx_key
-        ))
-    })?;
+    let enqueued_at = item.enqueued_at();
+    if let Err(e) = enqueue_relayed_tx(ctx, prepared.chain_id, tx_key, item) {
+        release_recipient_refund(
+            store,
+            prepared.chain_id,
+            prepared.recipient,
+            typed_tx.item_key(),
+        );
+        return Err(e);
+    }
 
     tracing::trace!(
             tx_call = %hex::encode(typed_tx.sighash()),
             "Enqueued private withdraw transaction call for execution through evm tx queue",
     );
 
+    record_expected_fee(store, typed_tx.item_key(), prepared.fee);
     let item_key_hex = H512::from_slice(typed_tx.item_key().as_slice());
+    index_relayed_tx(
+        store,
+        RelayedTxRecord {
+            chain_id: prepared.chain_id,
+            contract: prepared.contract,
+            recipient: prepared.recipient,
+            token: prepared.token,
+            item_key: item_key_hex,
+            fee: prepared.fee,
+            refund: prepared.refund,
+            relayed_at: (enqueued_at / 1000) as u64,
+        },
+    );
 
     // update metric
     let metrics_clone = ctx.metrics.clone();
     let mut metrics = metrics_clone.lock().await;
     // update metric for total fee earned by relayer on particular resource
     metrics
-        .resource_metric_entry(resource_id)
+        .resource_metric_entry(prepared.resource_id)
         .total_fee_earned
-        .inc_by(cmd.ext_data.fee.as_u128() as f64);
+        .inc_by(prepared.fee.as_u128() as f64);
 
     // update metric for total fee earned by relayer
     metrics
         .total_fee_earned
-        .inc_by(cmd.ext_data.fee.as_u128() as f64);
+        .inc_by(prepared.fee.as_u128() as f64);
 
-    let relayer_balance = client
-        .get_balance(client.signer().address(), None)
-        .unwrap_or_else(|_| U256::zero())
-        .await;
+    let relayer_balance = match ctx.evm_provider(prepared.chain_id).await {
+        Ok(provider) => {
+            provider
+                .get_balance(prepared.relayer, None)
+                .unwrap_or_else(|_| U256::zero())
+                .await
+        }
+        Err(_) => U256::zero(),
+    };
 
     metrics
-        .account_balance_entry(typed_chain_id)
+        .account_balance_entry(TypedChainId::Evm(prepared.chain_id))
         .set(wei_to_gwei(relayer_balance));
     Ok(item_key_hex)
 }
@@ -1,13 +1,38 @@
-use ethereum_types::U256;
+use ethereum_types::{H512, U256};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::OwnedSemaphorePermit;
+use webb::evm::contract::protocol_solidity::variable_anchor::VAnchorContract;
 use webb::evm::ethers;
+use webb::evm::ethers::types::transaction::eip2718::TypedTransaction;
+use webb::evm::ethers::types::I256;
+use webb_proposals::ResourceId;
+use webb_relayer_config::evm::{EvmChainConfig, WithdrawalPolicyConfig};
+use webb_relayer_context::{RelayerContext, MAX_RELAY_JOBS_IN_FLIGHT};
+use webb_relayer_store::queue::{QueueItem, QueueStore};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{
+    AccountingStore, MerkleRootStore, NullifierStore, RelayJob, RelayJobStage,
+    RelayJobStore, RelayedTxRecord, RelayedTxStore, SledStore,
+};
+use webb_relayer_utils::revert::humanize_error;
+use webb_relayer_utils::{AmountKind, TransactionRelayingError};
+
+use crate::{RelayJobId, TransactionItemKey};
 
 /// For Fees calculation.
 pub mod fees;
+/// Relaying through trusted forwarders.
+pub mod forwarder;
+/// Gas estimation through simulation.
+pub mod gas_simulation;
 /// MASP vanchor transaction relaying.
 #[cfg(feature = "masp-tx-relaying")]
 pub mod masp_vanchor;
 /// Variable Anchor transaction relaying.
 pub mod vanchor;
+/// Variable Anchor wrap-and-deposit relaying.
+pub mod wrap_and_deposit;
 
 fn wei_to_gwei(wei: U256) -> f64 {
     ethers::utils::format_units(wei, "gwei")
@@ -18,3 +43,589 @@ fn wei_to_gwei(wei: U256) -> f64 {
         })
         .unwrap_or_default()
 }
+
+/// Indexes a relayed withdrawal by its recipient.
+///
+/// The transaction is already queued at this point, so a failure here is only
+/// logged and does not fail the relay request.
+fn index_relayed_tx(store: &SledStore, record: RelayedTxRecord) {
+    if let Err(e) = store.insert_relayed_tx(&record) {
+        tracing::warn!(
+            ?e,
+            recipient = %record.recipient,
+            item_key = %record.item_key,
+            "Failed to index relayed transaction",
+        );
+    }
+}
+
+/// Rejects the commands relayed to a chain the relayer only watches.
+fn check_relaying_enabled(
+    chain: &EvmChainConfig,
+) -> Result<(), TransactionRelayingError> {
+    if chain.mode.is_watch_only() {
+        return Err(TransactionRelayingError::WatchOnlyChain(chain.chain_id));
+    }
+    Ok(())
+}
+
+/// Rejects a token that is not in the allowlist of the chain, if it has one.
+fn check_token_allowed(
+    chain: &EvmChainConfig,
+    token: ethers::types::Address,
+) -> Result<(), TransactionRelayingError> {
+    match &chain.allowed_tokens {
+        Some(allowed_tokens) if !allowed_tokens.contains(&token) => Err(
+            TransactionRelayingError::UnsupportedToken(format!("{token:?}")),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Rejects a command spending nullifiers that were already spent on the
+/// anchor, as its transaction would revert on chain.
+///
+/// The spent nullifiers are only a cache of the anchor events, so the command
+/// is let through when they can't be read.
+fn check_nullifiers_unspent(
+    store: &SledStore,
+    resource_id: ResourceId,
+    nullifiers: &[ethers::types::H256],
+) -> Result<(), TransactionRelayingError> {
+    let spent = match store.get_spent_nullifiers(resource_id, nullifiers) {
+        Ok(spent) => spent,
+        Err(e) => {
+            tracing::warn!(?e, "Failed to get the spent nullifiers");
+            return Ok(());
+        }
+    };
+    if spent.is_empty() {
+        return Ok(());
+    }
+    let spent = spent
+        .iter()
+        .map(|n| format!("{n:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(TransactionRelayingError::NullifierAlreadySpent(spent))
+}
+
+/// Rejects a command whose roots are not a multiple of 32 bytes, or are all
+/// unknown to the relayer, as its proof is stale and its transaction would
+/// revert on chain.
+///
+/// The known roots are only a cache of the anchor events, so the command is
+/// let through when the relayer does not know any yet or they can't be read.
+fn check_merkle_roots(
+    store: &SledStore,
+    resource_id: ResourceId,
+    roots: &[u8],
+) -> Result<(), TransactionRelayingError> {
+    if roots.len() % 32 != 0 {
+        return Err(TransactionRelayingError::InvalidMerkleRoots {
+            known_roots: Vec::new(),
+        });
+    }
+    let known_roots = match store.get_known_merkle_roots(resource_id) {
+        Ok(known_roots) => known_roots,
+        Err(e) => {
+            tracing::warn!(?e, "Failed to get the known merkle roots");
+            return Ok(());
+        }
+    };
+    let is_known = known_roots.is_empty()
+        || roots
+            .chunks(32)
+            .map(ethers::types::H256::from_slice)
+            .any(|root| known_roots.contains(&root));
+    if is_known {
+        Ok(())
+    } else {
+        Err(TransactionRelayingError::InvalidMerkleRoots { known_roots })
+    }
+}
+
+/// Rejects a command spending nullifiers that are spent on chain, or whose
+/// root is not known by the anchor on chain, as read through the RPC quorum
+/// of the chain.
+///
+/// The spent nullifiers and known roots cached from the anchor events are
+/// only as trustworthy as the single endpoint they were read from, so once a
+/// quorum is configured, the command is only relayed if enough endpoints
+/// agree that it is valid. Nothing is checked on chain without a quorum.
+async fn check_onchain_with_quorum(
+    ctx: &RelayerContext,
+    chain: &EvmChainConfig,
+    contract: ethers::types::Address,
+    nullifiers: &[ethers::types::H256],
+    roots: &[u8],
+) -> Result<(), TransactionRelayingError> {
+    if chain.rpc.quorum.is_none() {
+        return Ok(());
+    }
+    let provider =
+        ctx.evm_quorum_provider(chain.chain_id).await.map_err(|e| {
+            TransactionRelayingError::NetworkConfigurationError(
+                e.to_string(),
+                chain.chain_id,
+            )
+        })?;
+    // the spent nullifiers and known roots share the VAnchor base interface.
+    let anchor = VAnchorContract::new(contract, provider);
+    let client_error = |e: ethers::contract::ContractError<_>| {
+        TransactionRelayingError::ClientError(e.to_string())
+    };
+    for nullifier in nullifiers {
+        let nullifier_hash = U256::from_big_endian(nullifier.as_bytes());
+        if anchor
+            .is_spent(nullifier_hash)
+            .call()
+            .await
+            .map_err(client_error)?
+        {
+            return Err(TransactionRelayingError::NullifierAlreadySpent(
+                format!("{nullifier:?}"),
+            ));
+        }
+    }
+    // the first root is the one of the anchor, the others of its neighbors.
+    if let Some(root) = roots.get(..32) {
+        let root = U256::from_big_endian(root);
+        if !anchor
+            .is_known_root(root)
+            .call()
+            .await
+            .map_err(client_error)?
+        {
+            return Err(TransactionRelayingError::InvalidMerkleRoots {
+                known_roots: Vec::new(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Rejects new transactions once the relayer started draining its queues,
+/// or while it is paused.
+fn check_accepting(
+    ctx: &RelayerContext,
+) -> Result<(), TransactionRelayingError> {
+    if ctx.is_draining() {
+        return Err(TransactionRelayingError::ShuttingDown);
+    }
+    if ctx.is_paused() {
+        return Err(TransactionRelayingError::Paused);
+    }
+    Ok(())
+}
+
+/// Rejects a command once the resource it is relayed to has the maximum
+/// number of transactions in flight in the queue of its chain, so a busy
+/// contract can not fill the queue.
+fn check_resource_in_flight(
+    store: &SledStore,
+    chain: &EvmChainConfig,
+    resource_id: ResourceId,
+) -> Result<(), TransactionRelayingError> {
+    let Some(max_in_flight) = chain.tx_queue.max_in_flight_per_resource else {
+        return Ok(());
+    };
+    let in_flight = QueueStore::<TypedTransaction>::count_pending_items_of(
+        store,
+        SledQueueKey::from_evm_chain_id(chain.chain_id),
+        resource_key(resource_id),
+    )
+    .map_err(|e| {
+        TransactionRelayingError::TransactionQueueError(e.to_string())
+    })?;
+    if in_flight >= max_in_flight as usize {
+        return Err(TransactionRelayingError::ResourceBusy(max_in_flight));
+    }
+    Ok(())
+}
+
+/// Enqueues a relayed transaction in the queue of its chain, unless its
+/// resource already has the maximum number of transactions in flight.
+///
+/// Unlike [`check_resource_in_flight`], the cap is checked in the same store
+/// transaction the item is enqueued in, so concurrent requests can not go
+/// over it.
+fn enqueue_relayed_tx(
+    ctx: &RelayerContext,
+    chain_id: u32,
+    tx_key: SledQueueKey,
+    item: QueueItem<TypedTransaction>,
+) -> Result<(), TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    let enqueue_err = |_| {
+        TransactionQueueError(format!(
+            "Transaction item with key : {} failed to enqueue",
+            tx_key
+        ))
+    };
+    let store = ctx.store();
+    let Some(max_in_flight) = max_in_flight_per_resource(ctx, chain_id) else {
+        return QueueStore::enqueue_item(store, tx_key, item)
+            .map_err(enqueue_err);
+    };
+    let enqueued = QueueStore::enqueue_item_capped(
+        store,
+        tx_key,
+        item,
+        max_in_flight as usize,
+    )
+    .map_err(enqueue_err)?;
+    if !enqueued {
+        return Err(ResourceBusy(max_in_flight));
+    }
+    Ok(())
+}
+
+/// The maximum number of transactions in flight per resource in the queue
+/// of the chain, if it is capped.
+fn max_in_flight_per_resource(
+    ctx: &RelayerContext,
+    chain_id: u32,
+) -> Option<u32> {
+    ctx.config
+        .evm
+        .get(&chain_id.to_string())
+        .and_then(|chain| chain.tx_queue.max_in_flight_per_resource)
+}
+
+/// The key the queue items of a resource are grouped by.
+fn resource_key(resource_id: ResourceId) -> ethers::types::H256 {
+    ethers::types::H256::from(resource_id.to_bytes())
+}
+
+/// Rejects a command whose deadline, if it has one, already passed.
+fn check_deadline(
+    deadline: Option<u64>,
+) -> Result<(), TransactionRelayingError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("SystemTime before UNIX EPOCH!");
+    match deadline {
+        Some(deadline) if now.as_secs() >= deadline => {
+            Err(TransactionRelayingError::DeadlinePassed(deadline))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The maximum refund per recipient of the chain, and the start of its
+/// refund cap window (unix timestamp in seconds), unless it has no cap.
+fn recipient_refund_cap(
+    ctx: &RelayerContext,
+    chain_id: u32,
+    fee_info: &fees::EvmFeeInfo,
+) -> Result<Option<(U256, u64)>, TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    let chain_config = ctx
+        .config
+        .evm
+        .get(&chain_id.to_string())
+        .ok_or(UnsupportedChain(chain_id))?;
+    let relayer_fee_config = &chain_config.relayer_fee_config;
+    let max_refund =
+        match fees::max_refund_per_recipient(fee_info, relayer_fee_config)
+            .map_err(|e| ClientError(e.to_string()))?
+        {
+            Some(max_refund) => max_refund,
+            None => return Ok(None),
+        };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let since = now.saturating_sub(relayer_fee_config.refund_cap_window / 1000);
+    Ok(Some((max_refund, since)))
+}
+
+/// Rejects a refund that would take the refunds relayed to the recipient
+/// within the refund cap window of the chain over its cap.
+///
+/// The cap is only checked here, a relayed withdrawal reserves its refund
+/// with [`reserve_recipient_refund`].
+fn check_recipient_refund_cap(
+    ctx: &RelayerContext,
+    chain_id: u32,
+    recipient: ethers::types::Address,
+    refund: U256,
+    fee_info: &fees::EvmFeeInfo,
+) -> Result<(), TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    if refund.is_zero() {
+        return Ok(());
+    }
+    let Some((max_refund, since)) =
+        recipient_refund_cap(ctx, chain_id, fee_info)?
+    else {
+        return Ok(());
+    };
+    let refunded = ctx
+        .store()
+        .get_refunds_by_recipient_since(chain_id, recipient, since)
+        .map_err(|e| ClientError(e.to_string()))?;
+    if refunded.saturating_add(refund) > max_refund {
+        let msg = format!(
+            "Recipient {recipient:?} already received {refunded} of the \
+             maximum refund of {max_refund} per recipient"
+        );
+        return Err(InvalidRefundAmount(msg));
+    }
+    Ok(())
+}
+
+/// Reserves the refund of a withdrawal about to be relayed against the
+/// refund cap of its recipient, in a single store transaction.
+///
+/// Rejects it if concurrent requests to the same recipient already took the
+/// rest of the cap since it was checked.
+fn reserve_recipient_refund(
+    ctx: &RelayerContext,
+    chain_id: u32,
+    recipient: ethers::types::Address,
+    item_key: [u8; 64],
+    refund: U256,
+    fee_info: &fees::EvmFeeInfo,
+) -> Result<(), TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    if refund.is_zero() {
+        return Ok(());
+    }
+    let Some((max_refund, since)) =
+        recipient_refund_cap(ctx, chain_id, fee_info)?
+    else {
+        return Ok(());
+    };
+    let reserved = ctx
+        .store()
+        .reserve_recipient_refund(
+            chain_id,
+            recipient,
+            ethers::types::H512::from_slice(item_key.as_slice()),
+            refund,
+            since,
+            max_refund,
+        )
+        .map_err(|e| ClientError(e.to_string()))?;
+    if !reserved {
+        let msg = format!(
+            "Recipient {recipient:?} already reached the maximum refund of \
+             {max_refund} per recipient"
+        );
+        return Err(InvalidRefundAmount(msg));
+    }
+    Ok(())
+}
+
+/// Releases the refund reserved to a withdrawal that was not relayed after
+/// all.
+///
+/// Like [`record_expected_fee`], a failure here is only logged.
+fn release_recipient_refund(
+    store: &SledStore,
+    chain_id: u32,
+    recipient: ethers::types::Address,
+    item_key: [u8; 64],
+) {
+    let item_key = ethers::types::H512::from_slice(item_key.as_slice());
+    if let Err(e) =
+        store.release_recipient_refund(chain_id, recipient, item_key)
+    {
+        tracing::warn!(
+            ?e,
+            %item_key,
+            "Failed to release the recipient refund",
+        );
+    }
+}
+
+/// Whether the withdrawals to the recipient are subsidized on the chain, i.e.
+/// relayed whatever their fee.
+///
+/// Once the daily cap of subsidized transactions of the chain is reached,
+/// withdrawals pay their fee again. The cap is only checked here, a relayed
+/// withdrawal reserves its slot with [`reserve_subsidized_tx`].
+fn is_subsidized(
+    ctx: &RelayerContext,
+    chain: &EvmChainConfig,
+    recipient: ethers::types::Address,
+) -> Result<bool, TransactionRelayingError> {
+    let Some(subsidy) = &chain.relayer_fee_config.subsidy else {
+        return Ok(false);
+    };
+    if !subsidy.covers(recipient) {
+        return Ok(false);
+    }
+    let Some(max_daily_txs) = subsidy.max_daily_txs else {
+        return Ok(true);
+    };
+    let subsidized = ctx
+        .store()
+        .get_daily_subsidized_txs(
+            webb_proposals::TypedChainId::Evm(chain.chain_id),
+            webb_relayer_store::current_day(),
+        )
+        .map_err(|e| TransactionRelayingError::ClientError(e.to_string()))?;
+    Ok(subsidized < max_daily_txs)
+}
+
+/// Rejects a withdrawal whose amount or fee is below the minimum of the
+/// withdrawal policy of its contract.
+fn check_withdrawal_policy(
+    policy: &WithdrawalPolicyConfig,
+    ext_amount: I256,
+    fee: U256,
+    wrapped_token_decimals: u32,
+) -> Result<(), TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    let to_units = |amount| {
+        fees::wrapped_token_units(wrapped_token_decimals, amount)
+            .map_err(|e| ClientError(e.to_string()))
+    };
+    // only withdrawals (negative ext amounts) are subject to the minimum.
+    if let Some(min_withdrawal_amount) = policy.min_withdrawal_amount {
+        let minimum = to_units(min_withdrawal_amount)?;
+        let amount = ext_amount.unsigned_abs();
+        if ext_amount.is_negative() && amount < minimum {
+            return Err(AmountTooLow {
+                kind: AmountKind::Withdrawal,
+                amount,
+                minimum,
+            });
+        }
+    }
+    if let Some(min_fee) = policy.min_fee {
+        let minimum = to_units(min_fee)?;
+        if fee < minimum {
+            return Err(AmountTooLow {
+                kind: AmountKind::Fee,
+                amount: fee,
+                minimum,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Remembers the fee of a queued transaction, so it is accounted for once
+/// the transaction succeeds.
+///
+/// Like [`index_relayed_tx`], a failure here is only logged.
+fn record_expected_fee(store: &SledStore, item_key: [u8; 64], fee: U256) {
+    if let Err(e) = store.insert_expected_fee(item_key, fee) {
+        tracing::warn!(
+            ?e,
+            item_key = %ethers::utils::hex::encode(item_key),
+            "Failed to record the expected fee",
+        );
+    }
+}
+
+/// Reserves one of the daily subsidized transactions of the chain to a
+/// transaction about to be relayed, so its gas is accounted for as a subsidy.
+///
+/// Returns whether it is still subsidized, i.e. whether concurrent requests
+/// did not reach the daily cap since it was found subsidized.
+fn reserve_subsidized_tx(
+    ctx: &RelayerContext,
+    chain_id: u32,
+    item_key: [u8; 64],
+) -> Result<bool, TransactionRelayingError> {
+    let max_daily_txs = ctx
+        .config
+        .evm
+        .get(&chain_id.to_string())
+        .and_then(|chain| chain.relayer_fee_config.subsidy.as_ref())
+        .and_then(|subsidy| subsidy.max_daily_txs);
+    ctx.store()
+        .reserve_subsidized_tx(
+            webb_proposals::TypedChainId::Evm(chain_id),
+            item_key,
+            max_daily_txs,
+        )
+        .map_err(|e| TransactionRelayingError::ClientError(e.to_string()))
+}
+
+/// Releases the subsidized transaction reserved to a transaction that was
+/// not relayed after all.
+///
+/// Like [`record_expected_fee`], a failure here is only logged.
+fn release_subsidized_tx(store: &SledStore, chain_id: u32, item_key: [u8; 64]) {
+    let chain_id = webb_proposals::TypedChainId::Evm(chain_id);
+    if let Err(e) = store.release_subsidized_tx(chain_id, item_key) {
+        tracing::warn!(
+            ?e,
+            item_key = %ethers::utils::hex::encode(item_key),
+            "Failed to release the subsidized transaction",
+        );
+    }
+}
+
+/// Takes a permit to run a relay job in the background, rejecting the
+/// request once the relayer already runs the maximum number of them.
+fn acquire_relay_job(
+    ctx: &RelayerContext,
+) -> Result<OwnedSemaphorePermit, TransactionRelayingError> {
+    ctx.try_acquire_relay_job()
+        .ok_or(TransactionRelayingError::RelayerBusy(
+            MAX_RELAY_JOBS_IN_FLIGHT,
+        ))
+}
+
+/// Records a new relay job of the transaction with the given item key, then
+/// runs `relay` in the background, independently of the request, recording
+/// its progress in the job.
+///
+/// The permit is held until `relay` is done, so the jobs run at once stay
+/// bounded.
+fn submit_relay_job<F, Fut>(
+    ctx: Arc<RelayerContext>,
+    chain_id: u32,
+    item_key: H512,
+    permit: OwnedSemaphorePermit,
+    relay: F,
+) -> Result<RelayJobId, TransactionRelayingError>
+where
+    F: FnOnce(Arc<RelayerContext>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<TransactionItemKey, TransactionRelayingError>>
+        + Send
+        + 'static,
+{
+    let mut job = RelayJob::new(RelayJobId::random(), chain_id, item_key);
+    let job_id = job.job_id;
+    ctx.store().insert_relay_job(&job).map_err(|e| {
+        TransactionRelayingError::TransactionQueueError(e.to_string())
+    })?;
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        update_relay_job(ctx.store(), &mut job, RelayJobStage::CheckingFee);
+        let stage = match relay(ctx.clone()).await {
+            Ok(_) => RelayJobStage::Enqueued,
+            Err(e) => {
+                let reason = humanize_error(&e.to_string());
+                tracing::warn!(?job.job_id, %reason, "Relay job failed");
+                RelayJobStage::Failed { reason }
+            }
+        };
+        update_relay_job(ctx.store(), &mut job, stage);
+    });
+    Ok(job_id)
+}
+
+/// Moves the relay job to the given stage and persists it.
+///
+/// Failures are only logged, the job keeps going either way.
+fn update_relay_job(
+    store: &SledStore,
+    job: &mut RelayJob,
+    stage: RelayJobStage,
+) {
+    job.set_stage(stage);
+    if let Err(e) = store.insert_relay_job(job) {
+        tracing::warn!(?e, ?job.job_id, "Failed to update relay job");
+    }
+}
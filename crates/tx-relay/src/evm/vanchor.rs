@@ -1,13 +1,13 @@
 use super::*;
 use crate::evm::fees::{get_evm_fee_info, EvmFeeInfo};
-use crate::TransactionItemKey;
+use crate::{RelayJobId, TransactionBatchId, TransactionItemKey};
 use ethereum_types::{H512, U256};
 use futures::TryFutureExt;
 use std::{collections::HashMap, sync::Arc};
 use webb::evm::ethers::prelude::Middleware;
 use webb::evm::ethers::types;
 use webb::evm::ethers::types::transaction::eip2718::TypedTransaction;
-use webb::evm::ethers::utils::{format_units, hex, parse_ether};
+use webb::evm::ethers::utils::{format_units, hex, keccak256, parse_ether};
 use webb::evm::{
     contract::protocol_solidity::variable_anchor::{
         CommonExtData, Encryptions, PublicInputs, VAnchorContract,
@@ -17,12 +17,36 @@ use webb::evm::{
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
 use webb_relayer_context::RelayerContext;
 use webb_relayer_handler_utils::{EvmCommandType, EvmVanchorCommand};
-use webb_relayer_store::queue::{
-    QueueItem, QueueStore, TransactionQueueItemKey,
-};
+use webb_relayer_store::queue::{QueueItem, TransactionQueueItemKey};
 use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::TxBatchStore;
+use webb_relayer_utils::revert::{decode_revert_data, humanize_error};
 use webb_relayer_utils::TransactionRelayingError;
 
+/// The maximum number of commands accepted in a single batch.
+pub const MAX_BATCH_SIZE: usize = 16;
+
+/// A VAnchor withdrawal that passed validation and is ready to be enqueued.
+#[derive(Debug, Clone)]
+pub struct PreparedVAnchorTx {
+    typed_tx: TypedTransaction,
+    chain_id: u32,
+    resource_id: ResourceId,
+    relayer: types::Address,
+    contract: types::Address,
+    recipient: types::Address,
+    token: types::Address,
+    ext_amount: types::I256,
+    fee: U256,
+    refund: U256,
+    withdrawal_policy: WithdrawalPolicyConfig,
+    wrapped_token_decimals: u32,
+    max_gas_price: Option<U256>,
+    deadline: Option<u64>,
+    subsidized: bool,
+    sponsor_turn: Option<forwarder::SponsorTurn>,
+}
+
 /// Handler for VAnchor commands
 ///
 /// # Arguments
@@ -36,17 +60,230 @@ pub async fn handle_vanchor_relay_tx<'a>(
     contract: types::Address,
     cmd: EvmVanchorCommand,
 ) -> Result<TransactionItemKey, TransactionRelayingError> {
+    check_accepting(&ctx)?;
+    let prepared =
+        prepare_vanchor_relay_tx(&ctx, chain_id, contract, cmd).await?;
+    enqueue_vanchor_relay_tx(&ctx, prepared).await
+}
+
+/// Handler for a batch of VAnchor commands on the same chain
+///
+/// Every command is validated before any of them is enqueued, so a single
+/// invalid command rejects the whole batch, and the batch is enqueued in a
+/// single store transaction. Returns the id of the batch and the item keys
+/// of the enqueued transactions, in the same order as the commands.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `chain_id` - The chain all the commands are sent to
+/// * `cmds` - The contracts and the commands to execute on them
+#[tracing::instrument(skip(ctx, cmds), fields(batch_size = cmds.len()))]
+pub async fn handle_vanchor_relay_tx_batch(
+    ctx: Arc<RelayerContext>,
+    chain_id: TypedChainId,
+    cmds: Vec<(types::Address, EvmVanchorCommand)>,
+) -> Result<
+    (TransactionBatchId, Vec<TransactionItemKey>),
+    TransactionRelayingError,
+> {
+    use TransactionRelayingError::*;
+    check_accepting(&ctx)?;
+    if cmds.is_empty() || cmds.len() > MAX_BATCH_SIZE {
+        return Err(InvalidBatchSize(cmds.len(), MAX_BATCH_SIZE));
+    }
+    let mut prepared = Vec::with_capacity(cmds.len());
+    for (i, (contract, cmd)) in cmds.into_iter().enumerate() {
+        let tx = match prepare_vanchor_relay_tx(&ctx, chain_id, contract, cmd)
+            .await
+        {
+            Ok(tx) => tx,
+            Err(e) => {
+                // none of the batch is relayed, nor subsidized.
+                prepared
+                    .iter()
+                    .for_each(|tx| release_vanchor_reservations(&ctx, tx));
+                return Err(InvalidBatchCommand(i, Box::new(e)));
+            }
+        };
+        prepared.push(tx);
+    }
+    // none of the batch is relayed if any of it fails to be enqueued.
+    let release_all = |e| {
+        prepared
+            .iter()
+            .for_each(|tx| release_vanchor_reservations(&ctx, tx));
+        e
+    };
+    for tx in &prepared {
+        take_vanchor_sponsor_turn(&ctx, tx).map_err(release_all)?;
+    }
+    // the batch id is derived from the item keys of its transactions.
+    let batch_id = TransactionBatchId::from(keccak256(
+        prepared
+            .iter()
+            .flat_map(|tx| tx.typed_tx.item_key())
+            .collect::<Vec<_>>(),
+    ));
+    let items = prepared
+        .iter()
+        .map(|tx| {
+            let key = SledQueueKey::from_evm_with_custom_key(
+                tx.chain_id,
+                tx.typed_tx.item_key(),
+            );
+            (key, vanchor_queue_item(tx))
+        })
+        .collect::<Vec<_>>();
+    let enqueued_at = items
+        .iter()
+        .map(|(_, item)| item.enqueued_at())
+        .collect::<Vec<_>>();
+    let max_in_flight =
+        max_in_flight_per_resource(&ctx, chain_id.underlying_chain_id());
+    let item_keys = ctx
+        .store()
+        .enqueue_tx_batch(
+            chain_id.underlying_chain_id(),
+            batch_id,
+            items,
+            max_in_flight.map(|max| max as usize),
+        )
+        .map_err(|e| release_all(TransactionQueueError(e.to_string())))?
+        .ok_or_else(|| {
+            release_all(ResourceBusy(max_in_flight.unwrap_or_default()))
+        })?;
+    for (tx, enqueued_at) in prepared.into_iter().zip(enqueued_at) {
+        record_vanchor_relay_tx(&ctx, &tx, enqueued_at).await;
+    }
+    Ok((batch_id, item_keys))
+}
+
+/// Submits a VAnchor command as a relay job.
+///
+/// The command is validated and its transaction is built right away, so the
+/// item key is known upfront. The fee check and enqueuing run in the
+/// background, independently of the request, and their progress is recorded
+/// in the job.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `cmd` - The command to execute
+#[tracing::instrument(skip(ctx))]
+pub async fn submit_vanchor_relay_job(
+    ctx: Arc<RelayerContext>,
+    chain_id: TypedChainId,
+    contract: types::Address,
+    cmd: EvmVanchorCommand,
+) -> Result<(RelayJobId, TransactionItemKey), TransactionRelayingError> {
+    check_accepting(&ctx)?;
+    let permit = acquire_relay_job(&ctx)?;
+    let mut prepared =
+        build_vanchor_relay_tx(&ctx, chain_id, contract, cmd).await?;
+    let item_key = H512::from_slice(prepared.typed_tx.item_key().as_slice());
+    let job_id = submit_relay_job(
+        ctx,
+        prepared.chain_id,
+        item_key,
+        permit,
+        |ctx| async move {
+            reserve_and_check_vanchor_relay_tx_fee(&ctx, &mut prepared).await?;
+            enqueue_vanchor_relay_tx(&ctx, prepared).await
+        },
+    )?;
+    Ok((job_id, item_key))
+}
+
+/// Validates a VAnchor command and builds the transaction for it, then
+/// checks the fee, without enqueuing it.
+async fn prepare_vanchor_relay_tx(
+    ctx: &RelayerContext,
+    chain_id: TypedChainId,
+    contract: types::Address,
+    cmd: EvmVanchorCommand,
+) -> Result<PreparedVAnchorTx, TransactionRelayingError> {
+    let mut prepared =
+        build_vanchor_relay_tx(ctx, chain_id, contract, cmd).await?;
+    reserve_and_check_vanchor_relay_tx_fee(ctx, &mut prepared).await?;
+    Ok(prepared)
+}
+
+/// Reserves the subsidy of a prepared VAnchor transaction, if it is
+/// subsidized, then checks its fee and reserves its refund.
+///
+/// A transaction finds the daily cap of subsidies reached if concurrent
+/// requests took the last ones, and pays its fee then, held to the minimum
+/// fee of the withdrawal policy. The reservations are released if the fee
+/// check fails.
+async fn reserve_and_check_vanchor_relay_tx_fee(
+    ctx: &RelayerContext,
+    prepared: &mut PreparedVAnchorTx,
+) -> Result<(), TransactionRelayingError> {
+    if prepared.subsidized {
+        prepared.subsidized = reserve_subsidized_tx(
+            ctx,
+            prepared.chain_id,
+            prepared.typed_tx.item_key(),
+        )?;
+        if !prepared.subsidized {
+            check_vanchor_withdrawal_policy(prepared)?;
+        }
+    }
+    let result = check_vanchor_relay_tx_fee(ctx, prepared).await;
+    if result.is_err() {
+        release_vanchor_reservations(ctx, prepared);
+    }
+    result
+}
+
+/// Releases the subsidy and the refund reserved to a prepared VAnchor
+/// transaction that is not relayed after all.
+fn release_vanchor_reservations(
+    ctx: &RelayerContext,
+    prepared: &PreparedVAnchorTx,
+) {
+    if !prepared.refund.is_zero() {
+        release_recipient_refund(
+            ctx.store(),
+            prepared.chain_id,
+            prepared.recipient,
+            prepared.typed_tx.item_key(),
+        );
+    }
+    if prepared.subsidized {
+        release_subsidized_tx(
+            ctx.store(),
+            prepared.chain_id,
+            prepared.typed_tx.item_key(),
+        );
+    }
+}
+
+/// Validates a VAnchor command and builds the transaction for it, wrapped
+/// into a call to the trusted forwarder of the contract if it has one.
+///
+/// The gas estimation and the fee check are done by
+/// [`check_vanchor_relay_tx_fee`].
+async fn build_vanchor_relay_tx(
+    ctx: &RelayerContext,
+    chain_id: TypedChainId,
+    contract: types::Address,
+    cmd: EvmVanchorCommand,
+) -> Result<PreparedVAnchorTx, TransactionRelayingError> {
     use TransactionRelayingError::*;
     let requested_chain = chain_id.underlying_chain_id();
     let cmd = match cmd {
         EvmCommandType::VAnchor(cmd) => cmd,
         _ => return Err(InvalidCommand),
     };
+    check_deadline(cmd.deadline)?;
     let chain = ctx
         .config
         .evm
         .get(&requested_chain.to_string())
         .ok_or(UnsupportedChain(requested_chain))?;
+    check_relaying_enabled(chain)?;
     let supported_contracts: HashMap<_, _> = chain
         .contracts
         .iter()
@@ -73,11 +310,31 @@ pub async fn handle_vanchor_relay_tx<'a>(
         return Err(InvalidRelayerAddress(cmd.ext_data.relayer.to_string()));
     }
 
-    // validate that the roots are multiple of 32s
+    check_token_allowed(chain, *cmd.ext_data.token)?;
+
+    let target_system = TargetSystem::new_contract_address(
+        contract_config.common.address.to_fixed_bytes(),
+    );
+    let resource_id =
+        ResourceId::new(target_system, TypedChainId::Evm(chain.chain_id));
+    check_resource_in_flight(ctx.store(), chain, resource_id)?;
+    check_nullifiers_unspent(
+        ctx.store(),
+        resource_id,
+        &cmd.proof_data.input_nullifiers,
+    )?;
+
+    // validate that the roots are multiple of 32s, and known.
     let roots = cmd.proof_data.roots.to_vec();
-    if roots.len() % 32 != 0 {
-        return Err(InvalidMerkleRoots);
-    }
+    check_merkle_roots(ctx.store(), resource_id, &roots)?;
+    check_onchain_with_quorum(
+        ctx,
+        chain,
+        contract,
+        &cmd.proof_data.input_nullifiers,
+        &roots,
+    )
+    .await?;
 
     let provider = ctx.evm_provider(requested_chain).await.map_err(|e| {
         NetworkConfigurationError(e.to_string(), requested_chain)
@@ -87,12 +344,12 @@ pub async fn handle_vanchor_relay_tx<'a>(
     let contract = VAnchorContract::new(contract, client.clone());
 
     let common_ext_data = CommonExtData {
-        recipient: cmd.ext_data.recipient,
+        recipient: *cmd.ext_data.recipient,
         ext_amount: cmd.ext_data.ext_amount.0,
-        relayer: cmd.ext_data.relayer,
+        relayer: *cmd.ext_data.relayer,
         fee: cmd.ext_data.fee,
         refund: cmd.ext_data.refund,
-        token: cmd.ext_data.token,
+        token: *cmd.ext_data.token,
     };
     let public_inputs = PublicInputs {
         roots: roots.into(),
@@ -135,99 +392,437 @@ pub async fn handle_vanchor_relay_tx<'a>(
     if !cmd.ext_data.refund.is_zero() {
         call = call.value(cmd.ext_data.refund);
     }
+    let mut typed_tx = call.tx;
+    let mut sponsor_turn = None;
+    if let Some(forwarder) = &contract_config.forwarder {
+        let (forwarded_tx, turn) = forwarder::forward_tx(
+            client.clone(),
+            ctx.store(),
+            forwarder,
+            chain.chain_id,
+            typed_tx,
+        )
+        .await?;
+        typed_tx = forwarded_tx;
+        sponsor_turn = Some(turn);
+    }
+    let subsidized = is_subsidized(ctx, chain, *cmd.ext_data.recipient)?;
+    let wrapped_token_decimals = fees::get_wrapped_token_decimals(
+        TypedChainId::Evm(chain.chain_id),
+        contract_config.common.address,
+        ctx,
+    )
+    .await
+    .map_err(|e| ClientError(e.to_string()))?;
 
-    let gas_amount = client
-        .estimate_gas(&call.tx, None)
+    let prepared = PreparedVAnchorTx {
+        typed_tx,
+        chain_id: chain.chain_id,
+        resource_id,
+        relayer: client.signer().address(),
+        contract: contract_config.common.address,
+        recipient: *cmd.ext_data.recipient,
+        token: *cmd.ext_data.token,
+        ext_amount: cmd.ext_data.ext_amount.0,
+        fee: cmd.ext_data.fee,
+        refund: cmd.ext_data.refund,
+        withdrawal_policy: contract_config.withdrawal_policy,
+        wrapped_token_decimals,
+        max_gas_price: cmd.max_gas_price,
+        deadline: cmd.deadline,
+        subsidized,
+        sponsor_turn,
+    };
+    check_vanchor_withdrawal_policy(&prepared)?;
+    Ok(prepared)
+}
+
+/// Rejects a prepared VAnchor transaction below the withdrawal policy of
+/// its contract.
+///
+/// A subsidized transaction is not held to the minimum fee of the
+/// withdrawal policy.
+fn check_vanchor_withdrawal_policy(
+    prepared: &PreparedVAnchorTx,
+) -> Result<(), TransactionRelayingError> {
+    let withdrawal_policy = if prepared.subsidized {
+        WithdrawalPolicyConfig {
+            min_fee: None,
+            ..prepared.withdrawal_policy
+        }
+    } else {
+        prepared.withdrawal_policy
+    };
+    check_withdrawal_policy(
+        &withdrawal_policy,
+        prepared.ext_amount,
+        prepared.fee,
+        prepared.wrapped_token_decimals,
+    )
+}
+
+/// The gas and fee a VAnchor command would be relayed with.
+#[derive(Debug, Clone)]
+pub struct VAnchorRelayQuote {
+    /// The gas of the transaction, headroom included.
+    pub gas_amount: U256,
+    /// The fee info the fee and refund of the command are checked against.
+    pub fee_info: EvmFeeInfo,
+    /// The lowest fee accepted for the command, its refund included.
+    pub min_fee: U256,
+    /// The maximum refund per recipient within the refund cap window, if the
+    /// chain has a cap.
+    pub max_refund_per_recipient: Option<U256>,
+}
+
+/// The outcome of running a VAnchor command through the relay validation
+/// and fee checks, without enqueuing it.
+#[derive(Debug)]
+pub struct VAnchorRelayEstimate {
+    /// The gas and fee of the command, unless the command was rejected
+    /// before they could be computed.
+    pub quote: Option<VAnchorRelayQuote>,
+    /// Whether the command would be relayed, or why it would be rejected.
+    pub result: Result<(), TransactionRelayingError>,
+}
+
+/// Runs a VAnchor command through all the checks done before relaying it,
+/// without enqueuing it, so clients can pre-flight their requests.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `cmd` - The command to estimate
+#[tracing::instrument(skip(ctx))]
+pub async fn estimate_vanchor_relay_tx(
+    ctx: &RelayerContext,
+    chain_id: TypedChainId,
+    contract: types::Address,
+    cmd: EvmVanchorCommand,
+) -> VAnchorRelayEstimate {
+    let prepared =
+        match build_vanchor_relay_tx(ctx, chain_id, contract, cmd).await {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                return VAnchorRelayEstimate {
+                    quote: None,
+                    result: Err(e),
+                }
+            }
+        };
+    let quote = match quote_vanchor_relay_tx(ctx, &prepared).await {
+        Ok(quote) => quote,
+        Err(e) => {
+            return VAnchorRelayEstimate {
+                quote: None,
+                result: Err(e),
+            }
+        }
+    };
+    // the command is quoted even while the relayer does not accept any.
+    let result = check_accepting(ctx)
+        .and_then(|()| check_vanchor_relay_tx_quote(ctx, &prepared, &quote));
+    VAnchorRelayEstimate {
+        quote: Some(quote),
+        result,
+    }
+}
+
+/// The outcome of executing a VAnchor command against the latest state of
+/// the chain, without sending it.
+#[derive(Debug, Clone)]
+pub struct VAnchorRelaySimulation {
+    /// Whether the `transact` call reverted.
+    pub reverted: bool,
+    /// The decoded reason of the revert, for `Error(string)` and
+    /// `Panic(uint256)` reverts and the custom errors of the contracts.
+    pub revert_reason: Option<String>,
+    /// The raw data of the revert, if any.
+    pub revert_data: Option<types::Bytes>,
+}
+
+/// Executes the `transact` call of a VAnchor command with `eth_call`, from
+/// the relayer account, so clients learn why it would revert before paying
+/// the fee. The command goes through the relay validation first, but not
+/// through the fee checks.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `cmd` - The command to simulate
+#[tracing::instrument(skip(ctx))]
+pub async fn simulate_vanchor_relay_tx(
+    ctx: &RelayerContext,
+    chain_id: TypedChainId,
+    contract: types::Address,
+    cmd: EvmVanchorCommand,
+) -> Result<VAnchorRelaySimulation, TransactionRelayingError> {
+    use webb::evm::ethers::providers::MiddlewareError;
+    use TransactionRelayingError::*;
+    let prepared = build_vanchor_relay_tx(ctx, chain_id, contract, cmd).await?;
+    let chain_id = prepared.chain_id;
+    let wallet = ctx
+        .evm_wallet(chain_id)
+        .await
+        .map_err(|e| NetworkConfigurationError(e.to_string(), chain_id))?;
+    let provider = ctx
+        .evm_provider(chain_id)
+        .await
+        .map_err(|e| NetworkConfigurationError(e.to_string(), chain_id))?;
+    let client = SignerMiddleware::new(provider, wallet);
+    match client.call(&prepared.typed_tx, None).await {
+        Ok(_) => Ok(VAnchorRelaySimulation {
+            reverted: false,
+            revert_reason: None,
+            revert_data: None,
+        }),
+        Err(e) => {
+            let Some(rpc_error) = e.as_error_response() else {
+                return Err(ClientError(e.to_string()));
+            };
+            let revert_data = rpc_error.as_revert_data();
+            let revert_reason = revert_data
+                .as_ref()
+                .and_then(|data| decode_revert_data(data))
+                .or_else(|| Some(humanize_error(&rpc_error.message)));
+            Ok(VAnchorRelaySimulation {
+                reverted: true,
+                revert_reason,
+                revert_data,
+            })
+        }
+    }
+}
+
+/// Estimates the gas of a prepared VAnchor transaction and checks that the
+/// fee and refund requested by the user cover it, then reserves its refund
+/// against the refund cap of the recipient.
+async fn check_vanchor_relay_tx_fee(
+    ctx: &RelayerContext,
+    prepared: &PreparedVAnchorTx,
+) -> Result<(), TransactionRelayingError> {
+    let quote = quote_vanchor_relay_tx(ctx, prepared).await?;
+    check_vanchor_relay_tx_quote(ctx, prepared, &quote)?;
+    reserve_recipient_refund(
+        ctx,
+        prepared.chain_id,
+        prepared.recipient,
+        prepared.typed_tx.item_key(),
+        prepared.refund,
+        &quote.fee_info,
+    )
+}
+
+/// Estimates the gas of a prepared VAnchor transaction, and the fee it
+/// would be relayed for.
+async fn quote_vanchor_relay_tx(
+    ctx: &RelayerContext,
+    prepared: &PreparedVAnchorTx,
+) -> Result<VAnchorRelayQuote, TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    let chain_id = prepared.chain_id;
+    let chain_config = ctx
+        .config
+        .evm
+        .get(&chain_id.to_string())
+        .ok_or(UnsupportedChain(chain_id))?;
+    let wallet = ctx
+        .evm_wallet(chain_id)
+        .await
+        .map_err(|e| NetworkConfigurationError(e.to_string(), chain_id))?;
+    let provider = ctx
+        .evm_provider(chain_id)
+        .await
+        .map_err(|e| NetworkConfigurationError(e.to_string(), chain_id))?;
+    let client = SignerMiddleware::new(provider, wallet);
+
+    let estimated_gas = client
+        .estimate_gas(&prepared.typed_tx, None)
         .await
         .map_err(|e| ClientError(e.to_string()))?;
-    let typed_chain_id = TypedChainId::Evm(chain.chain_id);
+    // the transaction is sent with the headroom, so the fee covers it too.
+    let gas_amount =
+        chain_config.gas_limit_with_headroom(prepared.contract, estimated_gas);
     let fee_info = get_evm_fee_info(
-        typed_chain_id,
-        contract_config.common.address,
+        TypedChainId::Evm(chain_id),
+        prepared.contract,
         gas_amount,
-        &ctx,
+        ctx,
     )
     .await
     .map_err(|e| ClientError(e.to_string()))?;
 
+    // TODO: This adjustment could potentially be exploited
+    let adjusted_fee = fee_info.estimated_fee / 100 * 96;
+    // the relayer pays for the gas of a subsidized transaction, but the
+    // refund is still paid by the fee.
+    let gas_fee = if prepared.subsidized {
+        U256::zero()
+    } else {
+        adjusted_fee
+    };
+    let wrapped_amount =
+        calculate_wrapped_refund_amount(prepared.refund, &fee_info).map_err(
+            |e| {
+                WrappingFeeError(format!(
+                    "Failed to calculate wrapped refund amount: {e}"
+                ))
+            },
+        )?;
+    let max_refund_per_recipient = fees::max_refund_per_recipient(
+        &fee_info,
+        &chain_config.relayer_fee_config,
+    )
+    .map_err(|e| ClientError(e.to_string()))?;
+    Ok(VAnchorRelayQuote {
+        gas_amount,
+        fee_info,
+        min_fee: gas_fee + wrapped_amount,
+        max_refund_per_recipient,
+    })
+}
+
+/// Checks that the fee and refund requested by the user are within the
+/// quote of the transaction.
+fn check_vanchor_relay_tx_quote(
+    ctx: &RelayerContext,
+    prepared: &PreparedVAnchorTx,
+    quote: &VAnchorRelayQuote,
+) -> Result<(), TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    let fee_info = &quote.fee_info;
+
     // validate refund amount
-    if cmd.ext_data.refund > fee_info.max_refund {
+    if prepared.refund > fee_info.max_refund {
         let msg = format!(
             "User requested a refund which is higher than the maximum of {}",
             fee_info.max_refund
         );
         return Err(InvalidRefundAmount(msg));
     }
+    check_recipient_refund_cap(
+        ctx,
+        prepared.chain_id,
+        prepared.recipient,
+        prepared.refund,
+        fee_info,
+    )?;
 
     // check the fee
-    // TODO: This adjustment could potentially be exploited
-    let adjusted_fee = fee_info.estimated_fee / 100 * 96;
-    let wrapped_amount =
-        calculate_wrapped_refund_amount(cmd.ext_data.refund, &fee_info)
-            .map_err(|e| {
-                WrappingFeeError(format!(
-                    "Failed to calculate wrapped refund amount: {e}"
-                ))
-            })?;
-    if cmd.ext_data.fee < adjusted_fee + wrapped_amount {
+    if prepared.fee < quote.min_fee {
         let msg = format!(
             "User sent a fee that is too low {} but expected {}",
-            cmd.ext_data.fee,
-            adjusted_fee + wrapped_amount
+            prepared.fee, quote.min_fee
         );
         return Err(InvalidRefundAmount(msg));
     }
+    Ok(())
+}
 
-    let target_system = TargetSystem::new_contract_address(
-        contract_config.common.address.to_fixed_bytes(),
-    );
-    let resource_id = ResourceId::new(target_system, typed_chain_id);
-
-    let typed_tx: TypedTransaction = call.tx;
-    let item = QueueItem::new(typed_tx.clone());
+/// Enqueues a prepared VAnchor transaction in the evm tx queue.
+async fn enqueue_vanchor_relay_tx(
+    ctx: &RelayerContext,
+    prepared: PreparedVAnchorTx,
+) -> Result<TransactionItemKey, TransactionRelayingError> {
+    let item = vanchor_queue_item(&prepared);
     let tx_key = SledQueueKey::from_evm_with_custom_key(
-        chain.chain_id,
-        typed_tx.item_key(),
+        prepared.chain_id,
+        prepared.typed_tx.item_key(),
     );
-    let store = ctx.store();
-    QueueStore::<TypedTransaction>::enqueue_item(store, tx_key, item.clone())
-        .map_err(|_| {
-        TransactionQueueError(format!(
-            "Transaction item with key : {} failed to enqueue",
-            tx_key
-        ))
-    })?;
+    if let Err(e) = take_vanchor_sponsor_turn(ctx, &prepared) {
+        release_vanchor_reservations(ctx, &prepared);
+        return Err(e);
+    }
+    let enqueued_at = item.enqueued_at();
+    if let Err(e) = enqueue_relayed_tx(ctx, prepared.chain_id, tx_key, item) {
+        release_vanchor_reservations(ctx, &prepared);
+        return Err(e);
+    }
+    record_vanchor_relay_tx(ctx, &prepared, enqueued_at).await;
+    Ok(H512::from_slice(prepared.typed_tx.item_key().as_slice()))
+}
+
+/// The queue item of a prepared VAnchor transaction.
+fn vanchor_queue_item(
+    prepared: &PreparedVAnchorTx,
+) -> QueueItem<TypedTransaction> {
+    let mut item = QueueItem::new(prepared.typed_tx.clone());
+    item.set_max_gas_price(prepared.max_gas_price);
+    item.set_deadline(prepared.deadline);
+    item.set_resource_id(Some(resource_key(prepared.resource_id)));
+    item
+}
+
+/// Takes the turn of a prepared VAnchor transaction among the requests of
+/// its sponsor, if it is forwarded.
+fn take_vanchor_sponsor_turn(
+    ctx: &RelayerContext,
+    prepared: &PreparedVAnchorTx,
+) -> Result<(), TransactionRelayingError> {
+    let Some(turn) = &prepared.sponsor_turn else {
+        return Ok(());
+    };
+    let item_key = H512::from_slice(prepared.typed_tx.item_key().as_slice());
+    turn.take(ctx.store(), item_key)
+}
 
+/// Records the fee and the index entry of an enqueued VAnchor transaction,
+/// and updates the metrics of the relayer.
+async fn record_vanchor_relay_tx(
+    ctx: &RelayerContext,
+    prepared: &PreparedVAnchorTx,
+    enqueued_at: u128,
+) {
+    let typed_tx = &prepared.typed_tx;
     tracing::trace!(
             tx_call = %hex::encode(typed_tx.sighash()),
             "Enqueued private withdraw transaction call for execution through evm tx queue",
     );
 
+    let store = ctx.store();
+    record_expected_fee(store, typed_tx.item_key(), prepared.fee);
     let item_key_hex = H512::from_slice(typed_tx.item_key().as_slice());
+    index_relayed_tx(
+        store,
+        RelayedTxRecord {
+            chain_id: prepared.chain_id,
+            contract: prepared.contract,
+            recipient: prepared.recipient,
+            token: prepared.token,
+            item_key: item_key_hex,
+            fee: prepared.fee,
+            refund: prepared.refund,
+            relayed_at: (enqueued_at / 1000) as u64,
+        },
+    );
 
     // update metric
     let metrics_clone = ctx.metrics.clone();
     let mut metrics = metrics_clone.lock().await;
     // update metric for total fee earned by relayer on particular resource
     metrics
-        .resource_metric_entry(resource_id)
+        .resource_metric_entry(prepared.resource_id)
         .total_fee_earned
-        .inc_by(cmd.ext_data.fee.as_u128() as f64);
+        .inc_by(prepared.fee.as_u128() as f64);
 
     // update metric for total fee earned by relayer
     metrics
         .total_fee_earned
-        .inc_by(cmd.ext_data.fee.as_u128() as f64);
+        .inc_by(prepared.fee.as_u128() as f64);
 
-    let relayer_balance = client
-        .get_balance(client.signer().address(), None)
-        .unwrap_or_else(|_| U256::zero())
-        .await;
+    let relayer_balance = match ctx.evm_provider(prepared.chain_id).await {
+        Ok(provider) => {
+            provider
+                .get_balance(prepared.relayer, None)
+                .unwrap_or_else(|_| U256::zero())
+                .await
+        }
+        Err(_) => U256::zero(),
+    };
 
     metrics
-        .account_balance_entry(typed_chain_id)
+        .account_balance_entry(TypedChainId::Evm(prepared.chain_id))
         .set(wei_to_gwei(relayer_balance));
-    Ok(item_key_hex)
 }
 
 fn calculate_wrapped_refund_amount(
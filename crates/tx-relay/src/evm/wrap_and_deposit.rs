@@ -0,0 +1,478 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::evm::fees::get_evm_fee_info;
+use crate::evm::gas_simulation::get_simulated_transact_gas;
+use crate::TransactionItemKey;
+use ethereum_types::{H256, H512, U256};
+use std::sync::Arc;
+use webb::evm::ethers::abi::parse_abi;
+use webb::evm::ethers::contract::Contract;
+use webb::evm::ethers::prelude::Middleware;
+use webb::evm::ethers::types;
+use webb::evm::ethers::types::transaction::eip2718::TypedTransaction;
+use webb::evm::ethers::utils::keccak256;
+use webb::evm::{
+    contract::protocol_solidity::variable_anchor::{
+        CommonExtData, Encryptions, PublicInputs, VAnchorContract,
+    },
+    ethers::prelude::{Signer, SignerMiddleware},
+};
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_context::RelayerContext;
+use webb_relayer_handler_utils::{
+    EvmCommandType, EvmVanchorCommand, WrapFunding,
+};
+use webb_relayer_store::queue::{QueueItem, TransactionQueueItemKey};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::FundingTransferStore;
+use webb_relayer_utils::TransactionRelayingError;
+
+/// The subset of the ERC20 ABI, with EIP-2612 permits, used to fund the
+/// relayer and let the VAnchor pull the tokens.
+const ERC20_ABI: &[&str] = &[
+    "function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external",
+    "function transferFrom(address from, address to, uint256 value) external returns (bool)",
+    "function approve(address spender, uint256 value) external returns (bool)",
+    "function allowance(address owner, address spender) external view returns (uint256)",
+    "function balanceOf(address account) external view returns (uint256)",
+];
+
+/// The subset of the token wrapper ABI used to price the wrapping.
+const TOKEN_WRAPPER_ABI: &[&str] = &[
+    "function getAmountToWrap(uint256 deposit) external view returns (uint256)",
+];
+
+/// Gas of the wrapping done by `transact` on top of a plain `transact`.
+const WRAPPING_GAS: u64 = 120_000;
+
+/// Gas of the `permit` and `transferFrom` calls funding the relayer.
+const PERMIT_FUNDING_GAS: u64 = 120_000;
+
+/// Gas of the `approve` call letting the VAnchor pull the tokens.
+const APPROVE_GAS: u64 = 50_000;
+
+/// Maximum age, in seconds, of a transfer funding the relayer.
+const MAX_FUNDING_TRANSFER_AGE: u64 = 60 * 60;
+
+/// Returns the gas amount of a typical wrap-and-deposit on the given
+/// VAnchor, funded with a permit.
+///
+/// Used to quote the fee of a wrap-and-deposit before it is sent.
+pub async fn get_wrap_and_deposit_gas(
+    chain_id: TypedChainId,
+    vanchor: types::Address,
+    ctx: &RelayerContext,
+) -> webb_relayer_utils::Result<U256> {
+    let transact_gas =
+        get_simulated_transact_gas(chain_id, vanchor, ctx).await?;
+    Ok(transact_gas + U256::from(WRAPPING_GAS + PERMIT_FUNDING_GAS))
+}
+
+/// Handler for VAnchor wrap-and-deposit commands
+///
+/// The relayer gets the tokens of the user, either through a permit or a
+/// transfer made beforehand, and deposits them in the VAnchor with a
+/// `transact` call wrapping them. The transactions funding the relayer are
+/// enqueued along with the `transact` one, whose item key is returned. The
+/// queue only sends it once they are all processed, and fails it if any of
+/// them did not go through, so the relayer never deposits its own tokens.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `cmd` - The command to execute
+#[tracing::instrument(skip(ctx))]
+pub async fn handle_wrap_and_deposit_relay_tx(
+    ctx: Arc<RelayerContext>,
+    chain_id: TypedChainId,
+    contract: types::Address,
+    cmd: EvmVanchorCommand,
+) -> Result<TransactionItemKey, TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    check_accepting(&ctx)?;
+    let requested_chain = chain_id.underlying_chain_id();
+    let cmd = match cmd {
+        EvmCommandType::WrapAndDeposit(cmd) => cmd,
+        _ => return Err(InvalidCommand),
+    };
+    check_deadline(cmd.deadline)?;
+    let chain = ctx
+        .config
+        .evm
+        .get(&requested_chain.to_string())
+        .ok_or(UnsupportedChain(requested_chain))?;
+    check_relaying_enabled(chain)?;
+    let contract_config = chain
+        .contracts
+        .iter()
+        .find_map(|c| match c {
+            webb_relayer_config::evm::Contract::VAnchor(c)
+                if c.common.address == contract =>
+            {
+                Some(c)
+            }
+            _ => None,
+        })
+        .ok_or(UnsupportedContract(contract.to_string()))?;
+
+    let wallet = ctx.evm_wallet(requested_chain).await.map_err(|e| {
+        NetworkConfigurationError(e.to_string(), requested_chain)
+    })?;
+    let reward_address = chain.beneficiary.unwrap_or(wallet.address());
+    if cmd.ext_data.relayer != reward_address {
+        return Err(InvalidRelayerAddress(cmd.ext_data.relayer.to_string()));
+    }
+    check_token_allowed(chain, *cmd.ext_data.token)?;
+    // only deposits can be wrapped, and refunds are paid on withdrawals.
+    let ext_amount = cmd.ext_data.ext_amount.0;
+    if !ext_amount.is_positive() || !cmd.ext_data.refund.is_zero() {
+        return Err(InvalidCommand);
+    }
+
+    let target_system = TargetSystem::new_contract_address(
+        contract_config.common.address.to_fixed_bytes(),
+    );
+    let resource_id =
+        ResourceId::new(target_system, TypedChainId::Evm(chain.chain_id));
+    check_resource_in_flight(ctx.store(), chain, resource_id)?;
+    check_nullifiers_unspent(
+        ctx.store(),
+        resource_id,
+        &cmd.proof_data.input_nullifiers,
+    )?;
+    let roots = cmd.proof_data.roots.to_vec();
+    check_merkle_roots(ctx.store(), resource_id, &roots)?;
+    check_onchain_with_quorum(
+        &ctx,
+        chain,
+        contract,
+        &cmd.proof_data.input_nullifiers,
+        &roots,
+    )
+    .await?;
+
+    let provider = ctx.evm_provider(requested_chain).await.map_err(|e| {
+        NetworkConfigurationError(e.to_string(), requested_chain)
+    })?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    let relayer = client.signer().address();
+    let client_error =
+        |e: ethers::contract::ContractError<_>| ClientError(e.to_string());
+    let abi_error = |e: ethers::abi::AbiError| ClientError(e.to_string());
+
+    // the VAnchor pulls the amount to wrap, wrapping fee included, from the
+    // relayer, unless the token is already the wrapped one.
+    let anchor = VAnchorContract::new(contract, client.clone());
+    let wrapped_token = anchor.token().call().await.map_err(client_error)?;
+    let token = *cmd.ext_data.token;
+    let deposit = ext_amount.into_raw();
+    let wraps = token != wrapped_token;
+    let amount = if wraps {
+        let wrapper = Contract::new(
+            wrapped_token,
+            parse_abi(TOKEN_WRAPPER_ABI).expect("token wrapper abi is valid"),
+            client.clone(),
+        );
+        wrapper
+            .method::<_, U256>("getAmountToWrap", deposit)
+            .map_err(abi_error)?
+            .call()
+            .await
+            .map_err(client_error)?
+    } else {
+        deposit
+    };
+
+    let erc20 = Contract::new(
+        token,
+        parse_abi(ERC20_ABI).expect("erc20 abi is valid"),
+        client.clone(),
+    );
+    let mut txs: Vec<TypedTransaction> = Vec::new();
+    let mut gas_amount = U256::zero();
+    let mut funding_transfer = None;
+    match cmd.funding {
+        WrapFunding::Permit {
+            owner,
+            value,
+            deadline,
+            v,
+            r,
+            s,
+        } => {
+            let owner: types::Address = owner.into();
+            if value < amount {
+                return Err(InvalidFunding(format!(
+                    "The permit allows {value} but {amount} are needed"
+                )));
+            }
+            let balance = erc20
+                .method::<_, U256>("balanceOf", owner)
+                .map_err(abi_error)?
+                .call()
+                .await
+                .map_err(client_error)?;
+            if balance < amount {
+                return Err(InvalidFunding(format!(
+                    "{owner:?} holds {balance} but {amount} are needed"
+                )));
+            }
+            let permit = erc20
+                .method::<_, ()>(
+                    "permit",
+                    (owner, relayer, value, deadline, v, r, s),
+                )
+                .map_err(abi_error)?;
+            // the permit is simulated, so an invalid signature is rejected
+            // before anything is enqueued.
+            permit
+                .call()
+                .await
+                .map_err(|e| InvalidFunding(e.to_string()))?;
+            let transfer = erc20
+                .method::<_, bool>("transferFrom", (owner, relayer, amount))
+                .map_err(abi_error)?;
+            txs.push(permit.tx);
+            txs.push(transfer.tx);
+            gas_amount += U256::from(PERMIT_FUNDING_GAS);
+        }
+        WrapFunding::Transfer { tx_hash } => {
+            check_funding_transfer(&*client, token, relayer, amount, tx_hash)
+                .await?;
+            funding_transfer = Some(tx_hash);
+        }
+    }
+    let allowance = erc20
+        .method::<_, U256>("allowance", (relayer, contract))
+        .map_err(abi_error)?
+        .call()
+        .await
+        .map_err(client_error)?;
+    if allowance < amount {
+        let approve = erc20
+            .method::<_, bool>("approve", (contract, U256::MAX))
+            .map_err(abi_error)?;
+        txs.push(approve.tx);
+        gas_amount += U256::from(APPROVE_GAS);
+    }
+
+    // the `transact` call can't be estimated before the relayer is funded,
+    // so its gas is simulated.
+    let transact_gas = get_simulated_transact_gas(
+        TypedChainId::Evm(chain.chain_id),
+        contract,
+        &ctx,
+    )
+    .await
+    .map_err(|e| ClientError(e.to_string()))?;
+    gas_amount += transact_gas;
+    if wraps {
+        gas_amount += U256::from(WRAPPING_GAS);
+    }
+    let gas_amount = chain.gas_limit_with_headroom(contract, gas_amount);
+    let fee_info = get_evm_fee_info(
+        TypedChainId::Evm(chain.chain_id),
+        contract,
+        gas_amount,
+        &ctx,
+    )
+    .await
+    .map_err(|e| ClientError(e.to_string()))?;
+    check_withdrawal_policy(
+        &contract_config.withdrawal_policy,
+        ext_amount,
+        cmd.ext_data.fee,
+        fee_info.wrapped_token_decimals(),
+    )?;
+    // TODO: This adjustment could potentially be exploited
+    let adjusted_fee = fee_info.estimated_fee / 100 * 96;
+    if cmd.ext_data.fee < adjusted_fee {
+        return Err(AmountTooLow {
+            kind: AmountKind::Fee,
+            amount: cmd.ext_data.fee,
+            minimum: adjusted_fee,
+        });
+    }
+
+    let common_ext_data = CommonExtData {
+        recipient: *cmd.ext_data.recipient,
+        ext_amount,
+        relayer: *cmd.ext_data.relayer,
+        fee: cmd.ext_data.fee,
+        refund: cmd.ext_data.refund,
+        token,
+    };
+    let public_inputs = PublicInputs {
+        roots: roots.into(),
+        extension_roots: cmd.proof_data.extension_roots,
+        input_nullifiers: cmd
+            .proof_data
+            .input_nullifiers
+            .iter()
+            .map(|v| v.to_fixed_bytes().into())
+            .collect(),
+        output_commitments: cmd
+            .proof_data
+            .output_commitments
+            .into_iter()
+            .map(|c| U256::from(c.to_fixed_bytes()))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap_or_default(),
+        public_amount: U256::from_big_endian(
+            &cmd.proof_data.public_amount.to_fixed_bytes(),
+        ),
+        ext_data_hash: cmd.proof_data.ext_data_hash.to_fixed_bytes().into(),
+    };
+    let encryptions = Encryptions {
+        encrypted_output_1: cmd.ext_data.encrypted_output1,
+        encrypted_output_2: cmd.ext_data.encrypted_output2,
+    };
+    let call = anchor.transact(
+        cmd.proof_data.proof,
+        [0u8; 32].into(),
+        common_ext_data,
+        public_inputs,
+        encryptions,
+    );
+    let transact_tx: TypedTransaction = call.tx;
+
+    // a funding transfer is only used once, marked right before enqueuing.
+    let store = ctx.store();
+    if let Some(tx_hash) = funding_transfer {
+        let unused = store
+            .use_funding_transfer(chain.chain_id, tx_hash)
+            .map_err(|e| TransactionQueueError(e.to_string()))?;
+        if !unused {
+            return Err(InvalidFunding(format!(
+                "The transfer {tx_hash:?} was already used"
+            )));
+        }
+    }
+
+    // the `transact` call waits for all the transactions funding it.
+    let depends_on = txs
+        .iter()
+        .map(|tx| H512::from_slice(tx.item_key().as_slice()))
+        .collect::<Vec<_>>();
+    txs.push(transact_tx.clone());
+    for tx in txs {
+        let mut item = QueueItem::new(tx.clone());
+        item.set_max_gas_price(cmd.max_gas_price);
+        item.set_deadline(cmd.deadline);
+        // only the `transact` call takes turns with the other resources,
+        // the funding transactions still go first.
+        if tx.item_key() == transact_tx.item_key() {
+            item.set_resource_id(Some(resource_key(resource_id)));
+            item.set_depends_on(depends_on.clone());
+        }
+        let tx_key = SledQueueKey::from_evm_with_custom_key(
+            chain.chain_id,
+            tx.item_key(),
+        );
+        let enqueued = enqueue_relayed_tx(&ctx, chain.chain_id, tx_key, item);
+        if let Err(err) = enqueued {
+            if let Some(tx_hash) = funding_transfer {
+                if let Err(e) =
+                    store.release_funding_transfer(chain.chain_id, tx_hash)
+                {
+                    tracing::warn!(
+                        ?e,
+                        "Failed to release the funding transfer"
+                    );
+                }
+            }
+            return Err(err);
+        }
+    }
+    tracing::trace!(
+        %contract,
+        %amount,
+        "Enqueued wrap and deposit transactions for execution through evm tx queue",
+    );
+
+    record_expected_fee(store, transact_tx.item_key(), cmd.ext_data.fee);
+    let metrics_clone = ctx.metrics.clone();
+    let mut metrics = metrics_clone.lock().await;
+    metrics
+        .resource_metric_entry(resource_id)
+        .total_fee_earned
+        .inc_by(cmd.ext_data.fee.as_u128() as f64);
+    metrics
+        .total_fee_earned
+        .inc_by(cmd.ext_data.fee.as_u128() as f64);
+    Ok(H512::from_slice(transact_tx.item_key().as_slice()))
+}
+
+/// Rejects a transfer that did not send at least `amount` of `token` to the
+/// relayer, or is too old to fund a wrap-and-deposit.
+async fn check_funding_transfer<M: Middleware>(
+    client: &M,
+    token: types::Address,
+    relayer: types::Address,
+    amount: U256,
+    tx_hash: H256,
+) -> Result<(), TransactionRelayingError> {
+    use TransactionRelayingError::*;
+    let client_error = |e: M::Error| ClientError(e.to_string());
+    let receipt = client
+        .get_transaction_receipt(tx_hash)
+        .await
+        .map_err(client_error)?
+        .filter(|receipt| receipt.status == Some(1.into()))
+        .ok_or_else(|| {
+            InvalidFunding(format!("The transfer {tx_hash:?} is not mined"))
+        })?;
+    let mined_at = match receipt.block_number {
+        Some(block_number) => client
+            .get_block(block_number)
+            .await
+            .map_err(client_error)?
+            .map(|block| block.timestamp.as_u64()),
+        None => None,
+    }
+    .unwrap_or_default();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if now.saturating_sub(mined_at) > MAX_FUNDING_TRANSFER_AGE {
+        return Err(InvalidFunding(format!(
+            "The transfer {tx_hash:?} is too old"
+        )));
+    }
+    let transfer_topic =
+        H256::from(keccak256("Transfer(address,address,uint256)"));
+    let received = receipt
+        .logs
+        .iter()
+        .filter(|log| {
+            log.address == token
+                && log.topics.len() == 3
+                && log.topics[0] == transfer_topic
+                && log.data.len() == 32
+                && types::Address::from(log.topics[2]) == relayer
+        })
+        .fold(U256::zero(), |received, log| {
+            received.saturating_add(U256::from_big_endian(&log.data))
+        });
+    if received < amount {
+        return Err(InvalidFunding(format!(
+            "The transfer {tx_hash:?} sent {received} but {amount} are needed"
+        )));
+    }
+    Ok(())
+}
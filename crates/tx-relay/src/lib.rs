@@ -4,3 +4,9 @@ pub mod evm;
 
 /// Type alias for transaction item key.
 pub type TransactionItemKey = ethereum_types::H512;
+
+/// Type alias for the id of a batch of transactions.
+pub type TransactionBatchId = ethereum_types::H256;
+
+/// Type alias for the id of a relay job.
+pub type RelayJobId = ethereum_types::H256;
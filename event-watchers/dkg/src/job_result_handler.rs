@@ -14,6 +14,7 @@
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use webb::evm::ethers::utils::keccak256;
 use webb::substrate::subxt::{self, OnlineClient};use webb::substrate::tangle_runtime::api::jobs::events::JobResultSubmitted;
 use webb::substrate::tangle_runtime::api as RuntimeApi;
 use webb::substrate::tangle_runtime::api::runtime_types::tangle_primitives::jobs::JobResult;
@@ -68,7 +69,7 @@ impl EventHandler<TangleRuntimeConfig> for JobResultHandler {
             subxt::events::Events<TangleRuntimeConfig>,
             u64,
         ),
-        _metrics: Arc<Mutex<metric::Metrics>>,
+        metrics: Arc<Mutex<metric::Metrics>>,
     ) -> webb_relayer_utils::Result<()> {
         // We go the job result submitted event
         let job_result_submitted_events: Vec<_> = events
@@ -109,6 +110,12 @@ impl EventHandler<TangleRuntimeConfig> for JobResultHandler {
 
             if let Some(phase_result) = maybe_result {
                 if let JobResult::DKGPhaseTwo(result) = phase_result.result {
+                    // the signed data of the phase two job is the proposal
+                    // voted through the signing rules contract.
+                    metrics
+                        .lock()
+                        .await
+                        .record_proposal_signed(keccak256(&result.data));
                     for bridge_key in &bridge_keys {
                         tracing::debug!(
                             %bridge_key,
@@ -0,0 +1,120 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use webb::substrate::subxt::{self, OnlineClient};
+use webb::substrate::tangle_runtime::api::jobs::events::{
+    JobResultSubmitted, JobSubmitted,
+};
+use webb::substrate::tangle_runtime::api::runtime_types::tangle_primitives::jobs::JobType;
+use webb_relayer_store::sled::SledStore;
+use webb_relayer_store::{
+    TangleJob, TangleJobPhase, TangleJobStage, TangleJobStore,
+};
+use webb_relayer_utils::{metric, TangleRuntimeConfig};
+
+use webb_event_watcher_traits::substrate::EventHandler;
+
+/// Job Status handler handles the `JobSubmitted` and `JobResultSubmitted`
+/// events and records the stage of each job in the store, so the jobs the
+/// relayer depends on can be tracked through the API.
+#[derive(Copy, Clone, Debug)]
+pub struct JobStatusHandler {
+    chain_id: u32,
+}
+
+impl JobStatusHandler {
+    pub fn new(chain_id: u32) -> Self {
+        Self { chain_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler<TangleRuntimeConfig> for JobStatusHandler {
+    type Client = OnlineClient<TangleRuntimeConfig>;
+
+    type Store = SledStore;
+
+    async fn can_handle_events(
+        &self,
+        events: subxt::events::Events<TangleRuntimeConfig>,
+    ) -> webb_relayer_utils::Result<bool> {
+        let has_event = events.has::<JobSubmitted>()?
+            || events.has::<JobResultSubmitted>()?;
+        Ok(has_event)
+    }
+
+    async fn handle_events(
+        &self,
+        store: Arc<Self::Store>,
+        _client: Arc<Self::Client>,
+        (events, block_number): (
+            subxt::events::Events<TangleRuntimeConfig>,
+            u64,
+        ),
+        _metrics: Arc<Mutex<metric::Metrics>>,
+    ) -> webb_relayer_utils::Result<()> {
+        for event in events.find::<JobSubmitted>().flatten() {
+            let phase = match event.details.job_type {
+                JobType::DKGTSSPhaseOne(_) => TangleJobPhase::KeyGeneration,
+                JobType::DKGTSSPhaseTwo(_) => TangleJobPhase::Signing,
+                _ => TangleJobPhase::Other,
+            };
+            let mut job = TangleJob {
+                chain_id: self.chain_id,
+                job_id: event.job_id,
+                role_type: format!("{:?}", event.role_type),
+                phase: Some(phase),
+                stage: TangleJobStage::Submitted,
+                submitted_at: Some(block_number),
+                result_submitted_at: None,
+                updated_at: 0,
+            };
+            job.touch();
+            store.insert_tangle_job(&job)?;
+            tracing::debug!(
+                job_id = job.job_id,
+                role_type = %job.role_type,
+                ?phase,
+                "Job submitted",
+            );
+        }
+        for event in events.find::<JobResultSubmitted>().flatten() {
+            // the job may have been submitted before the relayer started.
+            let mut job = store
+                .get_tangle_job(self.chain_id, event.job_id)?
+                .unwrap_or_else(|| TangleJob {
+                    chain_id: self.chain_id,
+                    job_id: event.job_id,
+                    role_type: format!("{:?}", event.role_type),
+                    phase: None,
+                    stage: TangleJobStage::Submitted,
+                    submitted_at: None,
+                    result_submitted_at: None,
+                    updated_at: 0,
+                });
+            job.stage = TangleJobStage::ResultSubmitted;
+            job.result_submitted_at = Some(block_number);
+            job.touch();
+            store.insert_tangle_job(&job)?;
+            tracing::debug!(
+                job_id = job.job_id,
+                role_type = %job.role_type,
+                "Job result submitted",
+            );
+        }
+        Ok(())
+    }
+}
@@ -16,6 +16,10 @@
 mod job_result_handler;
 #[doc(hidden)]
 pub use job_result_handler::*;
+/// A module for tracking the stage of the jobs on the Jobs pallet.
+mod job_status_handler;
+#[doc(hidden)]
+pub use job_status_handler::*;
 use webb::substrate::subxt::events::StaticEvent;
 use webb::substrate::tangle_runtime::api::jobs::events::JobResultSubmitted;
 use webb_event_watcher_traits::SubstrateEventWatcher;
@@ -88,6 +88,10 @@ where
         Duration::from_millis(self.config.events_watcher.polling_interval)
     }
 
+    fn adaptive_polling(&self) -> bool {
+        self.config.events_watcher.adaptive_polling
+    }
+
     fn max_blocks_per_step(&self) -> types::U64 {
         self.config.events_watcher.max_blocks_per_step.into()
     }
@@ -16,7 +16,7 @@ use std::ops;
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OnceCell};
 use webb::evm::contract::protocol_solidity::signature_bridge::AdminSetResourceWithSignatureCall;
 use webb::evm::contract::protocol_solidity::signature_bridge::SignatureBridgeContract;
 use webb::evm::contract::protocol_solidity::signature_bridge::SignatureBridgeContractEvents;
@@ -42,6 +42,8 @@ use webb_relayer_utils::metric;
 pub struct SignatureBridgeContractWrapper<M: Middleware> {
     config: webb_relayer_config::evm::SignatureBridgeContractConfig,
     contract: Arc<SignatureBridgeContract<M>>,
+    /// The chain id reported by the contract, fetched once.
+    chain_id: Arc<OnceCell<types::U256>>,
 }
 
 impl<M: Middleware> Clone for SignatureBridgeContractWrapper<M> {
@@ -49,6 +51,7 @@ impl<M: Middleware> Clone for SignatureBridgeContractWrapper<M> {
         Self {
             config: self.config.clone(),
             contract: Arc::clone(&self.contract),
+            chain_id: Arc::clone(&self.chain_id),
         }
     }
 }
@@ -64,10 +67,27 @@ impl<M: Middleware> SignatureBridgeContractWrapper<M> {
                 client,
             )),
             config,
+            chain_id: Default::default(),
         }
     }
 }
 
+impl SignatureBridgeContractWrapper<EthersTimeLagClient> {
+    /// Returns the chain id reported by the contract.
+    ///
+    /// It is only queried once, the handlers and commands then reuse it
+    /// instead of spending a round trip to the node on each of them.
+    pub async fn chain_id(&self) -> webb_relayer_utils::Result<types::U256> {
+        let chain_id = self
+            .chain_id
+            .get_or_try_init(|| async {
+                self.contract.get_chain_id().call().await
+            })
+            .await?;
+        Ok(*chain_id)
+    }
+}
+
 impl<M: Middleware> ops::Deref for SignatureBridgeContractWrapper<M> {
     type Target = Contract<M>;
 
@@ -85,6 +105,10 @@ impl<M: Middleware> WatchableContract for SignatureBridgeContractWrapper<M> {
         Duration::from_millis(self.config.events_watcher.polling_interval)
     }
 
+    fn adaptive_polling(&self) -> bool {
+        self.config.events_watcher.adaptive_polling
+    }
+
     fn max_blocks_per_step(&self) -> types::U64 {
         self.config.events_watcher.max_blocks_per_step.into()
     }
@@ -150,7 +174,7 @@ impl EventHandler for SignatureBridgeGovernanceOwnershipTransferredHandler {
                 // if the ownership is transferred to the new owner, we need to
                 // to check our txqueue and remove any pending tx that was trying to
                 // do this transfer.
-                let chain_id = wrapper.contract.get_chain_id().call().await?;
+                let chain_id = wrapper.chain_id().await?;
                 let tx_key = SledQueueKey::from_evm_with_custom_key(
                     chain_id.as_u32(),
                     make_transfer_ownership_key(v.new_owner.to_fixed_bytes())
@@ -185,7 +209,7 @@ impl BridgeWatcher for SignatureBridgeContractWatcher {
             ExecuteProposalWithSignature { data, signature } => {
                 self.execute_proposal_with_signature(
                     store,
-                    &wrapper.contract,
+                    wrapper,
                     (data, signature),
                 )
                 .await?;
@@ -200,7 +224,7 @@ impl BridgeWatcher for SignatureBridgeContractWatcher {
             } => {
                 self.transfer_ownership_with_signature(
                     store,
-                    &wrapper.contract,
+                    wrapper,
                     (
                         pub_key,
                         nonce,
@@ -221,7 +245,7 @@ impl BridgeWatcher for SignatureBridgeContractWatcher {
             } => {
                 self.admin_set_resource_with_signature(
                     store,
-                    &wrapper.contract,
+                    wrapper,
                     (
                         resource_id,
                         new_resource_id,
@@ -235,7 +259,7 @@ impl BridgeWatcher for SignatureBridgeContractWatcher {
             BatchExecuteProposalsWithSignature { data, signature } => {
                 self.batch_execute_proposals_with_signature(
                     store,
-                    &wrapper.contract,
+                    wrapper,
                     (data, signature),
                 )
                 .await?
@@ -249,7 +273,7 @@ impl BridgeWatcher for SignatureBridgeContractWatcher {
             } => {
                 self.batch_admin_set_resource_with_signature(
                     store,
-                    &wrapper.contract,
+                    wrapper,
                     (
                         resource_id,
                         new_resource_ids,
@@ -273,9 +297,10 @@ where
     async fn execute_proposal_with_signature(
         &self,
         store: Arc<<Self as EventWatcher>::Store>,
-        contract: &SignatureBridgeContract<EthersTimeLagClient>,
+        wrapper: &<Self as EventWatcher>::Contract,
         (proposal_data, signature): (Vec<u8>, Vec<u8>),
     ) -> webb_relayer_utils::Result<()> {
+        let contract = &wrapper.contract;
         let proposal_data_hex = hex::encode(&proposal_data);
         // 1. Verify proposal length. Proposal lenght should be greater than 40 bytes (proposal header(40B) + proposal body).
         if proposal_data.len() < 40 {
@@ -287,7 +312,7 @@ where
         }
 
         // 2. Verify if proposal already exists in transaction queue
-        let chain_id = contract.get_chain_id().call().await?;
+        let chain_id = wrapper.chain_id().await?;
         let proposal_data_hash = utils::keccak256(&proposal_data);
 
         // 3. Verify proposal signature. Proposal should be signed by active maintainer/dkg-key
@@ -334,7 +359,9 @@ where
         );
 
         let typed_tx: TypedTransaction = call.tx;
-        let item = QueueItem::new(typed_tx.clone());
+        let mut item = QueueItem::new(typed_tx.clone());
+        // governance goes on once the daily gas budget is spent.
+        item.set_budget_exempt(true);
         let tx_key = SledQueueKey::from_evm_with_custom_key(
             chain_id.as_u32(),
             typed_tx.item_key(),
@@ -363,7 +390,7 @@ where
     async fn transfer_ownership_with_signature(
         &self,
         store: Arc<<Self as EventWatcher>::Store>,
-        contract: &SignatureBridgeContract<EthersTimeLagClient>,
+        wrapper: &<Self as EventWatcher>::Contract,
         (
             public_key,
             nonce,
@@ -373,11 +400,12 @@ where
             signature,
         ): (Vec<u8>, u32, [u8; 32], u32, u64, Vec<u8>),
     ) -> webb_relayer_utils::Result<()> {
+        let contract = &wrapper.contract;
         // before doing anything, we need to do just two things:
         // 1. check if we already have this transaction in the queue.
         // 2. if not, check if the signature is valid.
 
-        let chain_id = contract.get_chain_id().call().await?;
+        let chain_id = wrapper.chain_id().await?;
         let new_governor_address =
             eth_address_from_uncompressed_public_key(&public_key);
         let tx_key = SledQueueKey::from_evm_with_custom_key(
@@ -472,7 +500,9 @@ where
                 signature.into(),
             )
             .gas(estimate_gas.saturating_mul(U256::from(2)));
-        let item = QueueItem::new(call.tx);
+        let mut item = QueueItem::new(call.tx);
+        // governance goes on once the daily gas budget is spent.
+        item.set_budget_exempt(true);
         QueueStore::<TypedTransaction>::enqueue_item(&store, tx_key, item)?;
         tracing::debug!(
             chain_id = %chain_id.as_u64(),
@@ -485,7 +515,7 @@ where
     async fn admin_set_resource_with_signature(
         &self,
         store: Arc<<Self as EventWatcher>::Store>,
-        contract: &SignatureBridgeContract<EthersTimeLagClient>,
+        wrapper: &<Self as EventWatcher>::Contract,
         (resource_id, new_resource_id, handler_address, nonce, signature): (
             [u8; 32],
             [u8; 32],
@@ -494,6 +524,7 @@ where
             Vec<u8>,
         ),
     ) -> webb_relayer_utils::Result<()> {
+        let contract = &wrapper.contract;
         let function_sig = AdminSetResourceWithSignatureCall::selector();
         let mut proposal_data = Vec::with_capacity(32 + 32 + 20);
         proposal_data.extend_from_slice(resource_id.as_slice());
@@ -503,7 +534,7 @@ where
         proposal_data.extend_from_slice(handler_address.as_slice());
         let proposal_data_hash = utils::keccak256(&proposal_data);
 
-        let chain_id = contract.get_chain_id().call().await?;
+        let chain_id = wrapper.chain_id().await?;
 
         // Verify proposal signature. Proposal should be signed by active maintainer/dkg-key
         let (proposal_data_clone, signature_clone) =
@@ -554,7 +585,9 @@ where
         );
 
         let typed_tx: TypedTransaction = call.tx;
-        let item = QueueItem::new(typed_tx.clone());
+        let mut item = QueueItem::new(typed_tx.clone());
+        // governance goes on once the daily gas budget is spent.
+        item.set_budget_exempt(true);
         let tx_key = SledQueueKey::from_evm_with_custom_key(
             chain_id.as_u32(),
             typed_tx.item_key(),
@@ -582,16 +615,17 @@ where
     async fn batch_execute_proposals_with_signature(
         &self,
         store: Arc<<Self as EventWatcher>::Store>,
-        contract: &SignatureBridgeContract<EthersTimeLagClient>,
+        wrapper: &<Self as EventWatcher>::Contract,
         (proposals_data, signature): (Vec<Vec<u8>>, Vec<u8>),
     ) -> webb_relayer_utils::Result<()> {
+        let contract = &wrapper.contract;
         let proposals_hash = proposals_data
             .iter()
             .map(utils::keccak256)
             .reduce(|a, b| utils::keccak256([a, b].concat()))
             .expect("should have at least one proposal");
         // 2. Verify if proposal already exists in transaction queue
-        let chain_id = contract.get_chain_id().call().await?;
+        let chain_id = wrapper.chain_id().await?;
 
         tracing::event!(
             target: webb_relayer_utils::probe::TARGET,
@@ -608,7 +642,9 @@ where
         );
 
         let typed_tx: TypedTransaction = call.tx;
-        let item = QueueItem::new(typed_tx.clone());
+        let mut item = QueueItem::new(typed_tx.clone());
+        // governance goes on once the daily gas budget is spent.
+        item.set_budget_exempt(true);
         let tx_key = SledQueueKey::from_evm_with_custom_key(
             chain_id.as_u32(),
             typed_tx.item_key(),
@@ -637,7 +673,7 @@ where
     async fn batch_admin_set_resource_with_signature(
         &self,
         store: Arc<<Self as EventWatcher>::Store>,
-        contract: &SignatureBridgeContract<EthersTimeLagClient>,
+        wrapper: &<Self as EventWatcher>::Contract,
         (resource_id, new_resource_ids, handler_addresses, nonces, signature): (
             [u8; 32],
             Vec<[u8; 32]>,
@@ -646,6 +682,7 @@ where
             Vec<u8>,
         ),
     ) -> webb_relayer_utils::Result<()> {
+        let contract = &wrapper.contract;
         let function_sig = AdminSetResourceWithSignatureCall::selector();
         if !(nonces.len() == new_resource_ids.len()
             && new_resource_ids.len() == handler_addresses.len())
@@ -673,7 +710,7 @@ where
             })
             .collect::<Vec<_>>();
         let hashed_data = utils::keccak256(&encoded_data);
-        let chain_id = contract.get_chain_id().call().await?;
+        let chain_id = wrapper.chain_id().await?;
 
         tracing::event!(
             target: webb_relayer_utils::probe::TARGET,
@@ -695,7 +732,9 @@ where
         );
 
         let typed_tx: TypedTransaction = call.tx;
-        let item = QueueItem::new(typed_tx.clone());
+        let mut item = QueueItem::new(typed_tx.clone());
+        // governance goes on once the daily gas budget is spent.
+        item.set_budget_exempt(true);
         let tx_key = SledQueueKey::from_evm_with_custom_key(
             chain_id.as_u32(),
             typed_tx.item_key(),
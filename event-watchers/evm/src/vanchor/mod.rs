@@ -2,6 +2,8 @@ use super::*;
 pub mod vanchor_deposit_handler;
 pub mod vanchor_encrypted_outputs_handler;
 pub mod vanchor_leaves_handler;
+pub mod vanchor_merkle_roots_handler;
+pub mod vanchor_nullifier_handler;
 
 #[doc(hidden)]
 pub use vanchor_deposit_handler::*;
@@ -9,3 +11,7 @@ pub use vanchor_deposit_handler::*;
 pub use vanchor_encrypted_outputs_handler::*;
 #[doc(hidden)]
 pub use vanchor_leaves_handler::*;
+#[doc(hidden)]
+pub use vanchor_merkle_roots_handler::*;
+#[doc(hidden)]
+pub use vanchor_nullifier_handler::*;
@@ -15,17 +15,21 @@
 use crate::VAnchorContractWrapper;
 use ethereum_types::H256;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 use webb::evm::contract::protocol_solidity::variable_anchor::VAnchorContractEvents;
 use webb::evm::ethers::prelude::LogMeta;
+use webb::evm::ethers::providers::Middleware;
 use webb::evm::ethers::types;
+use webb_bridge_registry_backends::{
+    BridgeRegistryBackend, MockedBridgeRegistryBackend,
+};
 use webb_event_watcher_traits::evm::EventHandler;
 use webb_proposal_signing_backends::proposal_handler;
 use webb_proposal_signing_backends::queue::policy::ProposalPolicy;
 use webb_proposal_signing_backends::queue::{
-    ProposalsQueue, QueuedAnchorUpdateProposal,
+    ProposalHash, ProposalsQueue, QueuedAnchorUpdateProposal,
 };
-use webb_relayer_config::anchor::LinkedAnchorConfig;
 use webb_relayer_store::SledStore;
 use webb_relayer_store::{EventHashStore, HistoryStore};
 use webb_relayer_types::EthersTimeLagClient;
@@ -40,6 +44,160 @@ pub struct VAnchorDepositHandler<Q, P> {
     store: Arc<SledStore>,
     proposals_queue: Q,
     policy: P,
+    /// Used to find the linked anchors when they are not configured.
+    #[builder(default = Arc::new(MockedBridgeRegistryBackend))]
+    bridge_registry_backend: Arc<dyn BridgeRegistryBackend>,
+    /// When the last leaf was propagated to the linked anchors (unix
+    /// timestamp in seconds), for the `min-interval` of the propagation
+    /// policy.
+    #[builder(default, setter(skip))]
+    last_propagated_at: Mutex<Option<u64>>,
+    /// The last leaf skipped for the `min-interval`, see [`SkippedLeaf`].
+    #[builder(default, setter(skip))]
+    skipped_leaf: Mutex<Option<SkippedLeaf>>,
+}
+
+/// The last leaf skipped for the `min-interval` of the propagation policy.
+///
+/// Its root is proposed once the interval passed, when the handler is
+/// flushed, unless a later leaf is proposed first: the root of the last
+/// leaves of a burst is not left unpropagated until the next deposit.
+struct SkippedLeaf {
+    wrapper: VAnchorContractWrapper<EthersTimeLagClient>,
+    leaf_index: u32,
+    block_number: types::U64,
+    block_timestamp: Option<u64>,
+    metrics: Arc<Mutex<metric::Metrics>>,
+}
+
+impl<Q, P> VAnchorDepositHandler<Q, P>
+where
+    Q: ProposalsQueue<Proposal = QueuedAnchorUpdateProposal> + Send + Sync,
+    P: ProposalPolicy + Send + Sync + Clone,
+{
+    /// Proposes the last root of the anchor to its linked anchors, with the
+    /// index of the given leaf.
+    ///
+    /// Returns `false` if the anchor has no linked anchors.
+    async fn propose_root(
+        &self,
+        wrapper: &VAnchorContractWrapper<EthersTimeLagClient>,
+        leaf_index: u32,
+        block_number: types::U64,
+        block_timestamp: Option<u64>,
+        metrics: &Arc<Mutex<metric::Metrics>>,
+    ) -> webb_relayer_utils::Result<bool> {
+        let mut last_root = wrapper.contract.get_last_root();
+        if wrapper.config.confirmed_roots {
+            // the client lags the block confirmations of the chain behind
+            // its tip, so its latest block is a confirmed one.
+            let confirmed_block =
+                wrapper.contract.client().get_block_number().await?;
+            // the root must include the leaf of the event, which is not
+            // confirmed yet otherwise.
+            if confirmed_block < block_number {
+                tracing::debug!(
+                    leaf_index,
+                    %confirmed_block,
+                    event_block = %block_number,
+                    "The new leaf is not confirmed yet, retrying later",
+                );
+                return Err(webb_relayer_utils::Error::Generic(
+                    "the new leaf is not confirmed yet",
+                ));
+            }
+            last_root = last_root.block(confirmed_block);
+        }
+        let root: [u8; 32] = last_root.call().await?.into();
+        let src_chain_id =
+            webb_proposals::TypedChainId::Evm(self.chain_id.as_u32());
+        let src_target_system =
+            webb_proposals::TargetSystem::new_contract_address(
+                wrapper.contract.address().to_fixed_bytes(),
+            );
+        let src_resource_id =
+            webb_proposals::ResourceId::new(src_target_system, src_chain_id);
+
+        let linked_anchors = match self
+            .bridge_registry_backend
+            .config_or_registry_bridges(
+                &wrapper.config.linked_anchors,
+                &src_resource_id,
+            )
+            .await
+        {
+            Ok(anchors) => anchors,
+            Err(webb_relayer_utils::Error::BridgeNotRegistered(_)) => {
+                tracing::error!(
+                    "Linked anchors not configured for : ({})",
+                    self.chain_id
+                );
+                return Ok(false);
+            }
+            Err(e) => return Err(e),
+        };
+
+        // how long after the deposit its proposals are enqueued, for the
+        // freshness of the bridge.
+        let since_deposit = block_timestamp.map(|deposited_at| {
+            Duration::from_secs(unix_now().saturating_sub(deposited_at))
+        });
+
+        let mut rejected_targets = 0usize;
+        for target_resource_id in linked_anchors {
+            // Anchor update proposal proposed metric
+            metrics.lock().await.anchor_update_proposals.inc();
+
+            let proposal = match target_resource_id.target_system() {
+                webb_proposals::TargetSystem::ContractAddress(_) => {
+                    let p = proposal_handler::evm_anchor_update_proposal(
+                        root,
+                        leaf_index,
+                        target_resource_id,
+                        src_resource_id,
+                    );
+                    QueuedAnchorUpdateProposal::new(p)
+                }
+                target_system => {
+                    tracing::warn!(
+                        ?target_system,
+                        ?target_resource_id,
+                        "Anchor update proposals are only supported for EVM targets, skipping.",
+                    );
+                    continue;
+                }
+            };
+
+            // a proposal rejected for one target must not keep the others
+            // from being updated.
+            let proposal_hash = proposal.full_hash();
+            match self.proposals_queue.enqueue(proposal, self.policy.clone()) {
+                Ok(()) => {
+                    metrics.lock().await.record_proposal_enqueued(
+                        target_resource_id,
+                        proposal_hash,
+                        since_deposit,
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        %e,
+                        ?target_resource_id,
+                        "Failed to enqueue the anchor update proposal, skipping.",
+                    );
+                    rejected_targets += 1;
+                }
+            }
+        }
+        if rejected_targets > 0 {
+            tracing::warn!(
+                rejected_targets,
+                leaf_index,
+                "Some linked anchors were not updated",
+            );
+        }
+        Ok(true)
+    }
 }
 
 #[async_trait::async_trait]
@@ -60,7 +218,8 @@ where
         wrapper: &Self::Contract,
     ) -> webb_relayer_utils::Result<bool> {
         use VAnchorContractEvents::*;
-        let has_event = matches!(events, NewCommitmentFilter(event_data) if event_data.leaf_index.as_u32() % 2 != 0);
+        let policy = wrapper.config.propagation_policy;
+        let has_event = matches!(events, NewCommitmentFilter(event_data) if policy.propagates_leaf(event_data.leaf_index.as_u32()));
         if !has_event {
             return Ok(false);
         }
@@ -111,28 +270,54 @@ where
             }
             _ => return Ok(()),
         };
-        // Only construct the `AnchorUpdateProposal` if this condition evaluates to `true`: `leaf_index % 2 != 0`
-        // The reason behind this is that `VAnchor` on every `transact` call, emits two events,
-        // similar to the `Deposit` event but we call it the `Insertion` event, a la two `UTXO`
-        // and since we only need to update the target `VAnchor` only when needed,
-        // the first `Insertion` event sounds redundant in this case.
+        // Only construct the `AnchorUpdateProposal` for the leaves selected by
+        // the propagation policy of the anchor. By default, that is the leaves
+        // with an odd `leaf_index`: `VAnchor` emits two `Insertion` events on
+        // every `transact` call, a la two `UTXO`, and since we only need to
+        // update the target `VAnchor` only when needed, the first `Insertion`
+        // event sounds redundant in this case.
         tracing::debug!(
             event = ?event_data,
             "VAnchor new leaf event",
         );
 
-        if event_data.leaf_index.as_u32() % 2 == 0 {
+        let policy = wrapper.config.propagation_policy;
+        if !policy.propagates_leaf(event_data.leaf_index.as_u32()) {
             tracing::debug!(
                 leaf_index = %event_data.leaf_index,
-                is_even_index = %event_data.leaf_index.as_u32() % 2 == 0,
-                "VAnchor new leaf index does not satisfy the condition, skipping proposal.",
+                every_nth_leaf = policy.every_nth_leaf,
+                "VAnchor new leaf index is not propagated, skipping proposal.",
             );
             return Ok(());
         }
-
-        let root: [u8; 32] =
-            wrapper.contract.get_last_root().call().await?.into();
         let leaf_index = event_data.leaf_index.as_u32();
+        // the interval is measured between the blocks of the leaves, so the
+        // leaves handled at once (e.g. when catching up) are throttled as
+        // they were deposited.
+        let block_timestamp = block_timestamp(wrapper, log.block_number).await;
+        if let Some(min_interval) = policy.min_interval {
+            let deposited_at = block_timestamp.unwrap_or_else(unix_now);
+            let within_interval =
+                self.last_propagated_at.lock().await.map_or(false, |at| {
+                    deposited_at.saturating_sub(at) < min_interval
+                });
+            if within_interval {
+                tracing::debug!(
+                    leaf_index = %event_data.leaf_index,
+                    min_interval,
+                    "VAnchor new leaf is within the minimum propagation interval, skipping proposal.",
+                );
+                *self.skipped_leaf.lock().await = Some(SkippedLeaf {
+                    wrapper: wrapper.clone(),
+                    leaf_index,
+                    block_number: log.block_number,
+                    block_timestamp,
+                    metrics,
+                });
+                return Ok(());
+            }
+        }
+
         let src_chain_id =
             webb_proposals::TypedChainId::Evm(self.chain_id.as_u32());
         let src_target_system =
@@ -141,51 +326,103 @@ where
             );
         let src_resource_id =
             webb_proposals::ResourceId::new(src_target_system, src_chain_id);
-
-        let linked_anchors = match &wrapper.config.linked_anchors {
-            Some(anchors) => anchors,
-            None => {
-                tracing::error!(
-                    "Linked anchors not configured for : ({})",
-                    self.chain_id
-                );
-                return Ok(());
-            }
-        };
-
-        for linked_anchor in linked_anchors {
-            let target_resource_id = match linked_anchor {
-                LinkedAnchorConfig::Raw(target) => {
-                    let bytes: [u8; 32] = target.resource_id.into();
-                    webb_proposals::ResourceId::from(bytes)
-                }
-                _ => unreachable!("unsupported"),
-            };
-            // Anchor update proposal proposed metric
-            metrics.lock().await.anchor_update_proposals.inc();
-
-            let proposal = match target_resource_id.target_system() {
-                webb_proposals::TargetSystem::ContractAddress(_) => {
-                    let p = proposal_handler::evm_anchor_update_proposal(
-                        root,
-                        leaf_index,
-                        target_resource_id,
-                        src_resource_id,
-                    );
-                    QueuedAnchorUpdateProposal::new(p)
-                }
-                _ => {
-                    unreachable!("Only evm chains are supported for now.")
-                }
-            };
-
-            self.proposals_queue
-                .enqueue(proposal, self.policy.clone())?;
+        let proposed = self
+            .propose_root(
+                wrapper,
+                leaf_index,
+                log.block_number,
+                block_timestamp,
+                &metrics,
+            )
+            .await?;
+        if !proposed {
+            return Ok(());
         }
+        *self.last_propagated_at.lock().await =
+            Some(block_timestamp.unwrap_or_else(unix_now));
+        // the root of the skipped leaves is proposed along with this one.
+        *self.skipped_leaf.lock().await = None;
         // mark this event as processed.
         let events_bytes = serde_json::to_vec(&event_data)?;
-        store.store_event(&events_bytes)?;
+        store.store_event(
+            src_resource_id,
+            &events_bytes,
+            log.block_number.as_u64(),
+        )?;
         metrics.lock().await.total_transaction_made.inc();
         Ok(())
     }
+
+    async fn flush(
+        &self,
+        _store: Arc<Self::Store>,
+    ) -> webb_relayer_utils::Result<()> {
+        let Some(skipped) = self.skipped_leaf.lock().await.take() else {
+            return Ok(());
+        };
+        let policy = skipped.wrapper.config.propagation_policy;
+        let min_interval = policy.min_interval.unwrap_or_default();
+        let within_interval =
+            self.last_propagated_at.lock().await.map_or(false, |at| {
+                unix_now().saturating_sub(at) < min_interval
+            });
+        if within_interval {
+            *self.skipped_leaf.lock().await = Some(skipped);
+            return Ok(());
+        }
+        let proposed = self
+            .propose_root(
+                &skipped.wrapper,
+                skipped.leaf_index,
+                skipped.block_number,
+                skipped.block_timestamp,
+                &skipped.metrics,
+            )
+            .await;
+        match proposed {
+            Ok(true) => {
+                tracing::debug!(
+                    leaf_index = skipped.leaf_index,
+                    "Proposed the root of the leaves skipped within the minimum propagation interval",
+                );
+                *self.last_propagated_at.lock().await = Some(unix_now());
+            }
+            Ok(false) => {}
+            // tried again on the next flush.
+            Err(e) => {
+                tracing::debug!(
+                    %e,
+                    leaf_index = skipped.leaf_index,
+                    "Failed to propose the root of the skipped leaves, retrying later",
+                );
+                *self.skipped_leaf.lock().await = Some(skipped);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The timestamp of the block, if it could be fetched.
+async fn block_timestamp(
+    wrapper: &VAnchorContractWrapper<EthersTimeLagClient>,
+    block_number: types::U64,
+) -> Option<u64> {
+    match wrapper.contract.client().get_block(block_number).await {
+        Ok(block) => block.map(|block| block.timestamp.as_u64()),
+        Err(e) => {
+            tracing::debug!(
+                %e,
+                "Failed to get the block of the deposit, its latency is not recorded",
+            );
+            None
+        }
+    }
+}
+
+/// The current unix timestamp, in seconds.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
 }
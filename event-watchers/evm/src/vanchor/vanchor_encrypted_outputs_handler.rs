@@ -85,7 +85,11 @@ impl EventHandler for VAnchorEncryptedOutputHandler {
                     log.block_number.as_u64(),
                 )?;
                 let events_bytes = serde_json::to_vec(&deposit)?;
-                store.store_event(&events_bytes)?;
+                store.store_event(
+                    history_store_key,
+                    &events_bytes,
+                    log.block_number.as_u64(),
+                )?;
                 tracing::trace!(
                     %log.block_number,
                     "detected block number",
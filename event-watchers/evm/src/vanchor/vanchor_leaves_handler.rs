@@ -29,8 +29,9 @@ use webb::evm::ethers::prelude::LogMeta;
 use webb::evm::ethers::types;
 use webb_event_watcher_traits::evm::EventHandler;
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_config::evm::LeavesFlushPolicyConfig;
 use webb_relayer_store::SledStore;
-use webb_relayer_store::{EventHashStore, LeafCacheStore};
+use webb_relayer_store::{EventHashStore, LeafCacheStore, LeavesBatch};
 use webb_relayer_types::EthersTimeLagClient;
 use webb_relayer_utils::metric;
 use webb_relayer_utils::Error;
@@ -44,6 +45,10 @@ pub struct VAnchorLeavesHandler {
     mt: Arc<Mutex<MerkleTree>>,
     hasher: Poseidon<Bn254Fr>,
     chain_id: types::U256,
+    history_store_key: ResourceId,
+    /// The leaves not yet written to the store.
+    leaves: Mutex<LeavesBatch>,
+    flush_policy: LeavesFlushPolicyConfig,
 }
 
 impl VAnchorLeavesHandler {
@@ -51,12 +56,14 @@ impl VAnchorLeavesHandler {
     /// on the given chain id.
     ///
     /// Using the storage, it will try to load any old leaves and
-    /// construct the merkle tree in memory.
+    /// construct the merkle tree in memory. The new leaves are buffered and
+    /// written to the storage in batches, according to the flush policy.
     pub fn new(
         chain_id: types::U256,
         contract_address: types::Address,
         storage: Arc<SledStore>,
         empty_leaf: Vec<u8>,
+        flush_policy: LeavesFlushPolicyConfig,
     ) -> webb_relayer_utils::Result<Self> {
         let params = setup_params::<Bn254Fr>(Curve::Bn254, 5, 3);
         let poseidon = Poseidon::<Bn254Fr>::new(params);
@@ -95,8 +102,59 @@ impl VAnchorLeavesHandler {
             chain_id,
             mt: Arc::new(Mutex::new(mt)),
             hasher: poseidon,
+            history_store_key,
+            leaves: Mutex::new(LeavesBatch::new()),
+            flush_policy,
         })
     }
+
+    /// Writes the buffered leaves to the store.
+    fn write_leaves(
+        &self,
+        store: &SledStore,
+        leaves: &mut LeavesBatch,
+    ) -> webb_relayer_utils::Result<()> {
+        store.insert_leaves_batch(self.history_store_key, leaves)?;
+        for (leaf_index, leaf) in leaves.leaves() {
+            tracing::event!(
+                target: webb_relayer_utils::probe::TARGET,
+                tracing::Level::DEBUG,
+                kind = %webb_relayer_utils::probe::Kind::LeavesStore,
+                leaf_index = %leaf_index,
+                leaf = %hex::encode(leaf),
+                chain_id = %self.chain_id,
+                block_number = %leaves.last_deposit_block_number()
+            );
+        }
+        leaves.clear();
+        Ok(())
+    }
+}
+
+/// Computes the merkle root of the tree holding the given leaves, at the
+/// indices from zero, used to check leaves fetched from elsewhere against
+/// the roots known on chain.
+pub fn compute_merkle_root(
+    leaves: &[H256],
+    empty_leaf: Vec<u8>,
+) -> webb_relayer_utils::Result<U256> {
+    let params = setup_params::<Bn254Fr>(Curve::Bn254, 5, 3);
+    let poseidon = Poseidon::<Bn254Fr>::new(params);
+    let empty_leaf_scalar: Vec<Bn254Fr> = bytes_vec_to_f(&vec![empty_leaf]);
+    let empty_leaf_vec = empty_leaf_scalar
+        .get(0)
+        .map(|d| d.into_repr().to_bytes_be())
+        .ok_or(webb_relayer_utils::Error::ConvertLeafScalarError)?;
+    let batch: BTreeMap<u32, Bn254Fr> = leaves
+        .iter()
+        .enumerate()
+        .map(|(i, leaf)| {
+            (i as u32, Bn254Fr::from_be_bytes_mod_order(leaf.as_bytes()))
+        })
+        .collect();
+    let mt = MerkleTree::new(&batch, &poseidon, &empty_leaf_vec)?;
+    let root_bytes = mt.root().into_repr().to_bytes_be();
+    Ok(U256::from_big_endian(root_bytes.as_slice()))
 }
 
 #[async_trait::async_trait]
@@ -121,7 +179,7 @@ impl EventHandler for VAnchorLeavesHandler {
     async fn handle_event(
         &self,
         store: Arc<Self::Store>,
-        wrapper: &Self::Contract,
+        _wrapper: &Self::Contract,
         (event, log): (Self::Events, LogMeta),
         _metrics: Arc<Mutex<metric::Metrics>>,
     ) -> webb_relayer_utils::Result<()> {
@@ -135,13 +193,6 @@ impl EventHandler for VAnchorLeavesHandler {
             InsertionFilter(event_data) => {
                 let commitment: [u8; 32] = event_data.commitment.into();
                 let leaf_index = event_data.leaf_index;
-                let value = (leaf_index, commitment.to_vec());
-                let target_system = TargetSystem::new_contract_address(
-                    wrapper.contract.address().to_fixed_bytes(),
-                );
-                let typed_chain_id = TypedChainId::Evm(self.chain_id.as_u32());
-                let history_store_key =
-                    ResourceId::new(target_system, typed_chain_id);
 
                 // 1. We will validate leaf before inserting it into store
                 let leaf: Bn254Fr =
@@ -185,23 +236,23 @@ impl EventHandler for VAnchorLeavesHandler {
                     %log.block_number,
                     "detected block number",
                 );
-                // 2. We will insert leaf and last deposit block number into store
-                store.insert_leaves_and_last_deposit_block_number(
-                    history_store_key,
-                    &[value.clone()],
+                // 2. We will buffer the leaf, and write the leaves to the store
+                // once the batch is full, the rest is written on flush.
+                let mut leaves = self.leaves.lock().await;
+                leaves.push(
+                    leaf_index,
+                    commitment.to_vec(),
                     log.block_number.as_u64(),
-                )?;
-                let events_bytes = serde_json::to_vec(&event_data)?;
-                store.store_event(&events_bytes)?;
-                tracing::event!(
-                    target: webb_relayer_utils::probe::TARGET,
-                    tracing::Level::DEBUG,
-                    kind = %webb_relayer_utils::probe::Kind::LeavesStore,
-                    leaf_index = %value.0,
-                    leaf = %hex::encode(value.1),
-                    chain_id = %self.chain_id,
-                    block_number = %log.block_number
                 );
+                if leaves.len() >= self.flush_policy.max_batch_size {
+                    self.write_leaves(&store, &mut leaves)?;
+                }
+                let events_bytes = serde_json::to_vec(&event_data)?;
+                store.store_event(
+                    self.history_store_key,
+                    &events_bytes,
+                    log.block_number.as_u64(),
+                )?;
             }
             EdgeAdditionFilter(v) => {
                 let merkle_root: [u8; 32] = v.merkle_root.into();
@@ -241,4 +292,12 @@ impl EventHandler for VAnchorLeavesHandler {
 
         Ok(())
     }
+
+    async fn flush(
+        &self,
+        store: Arc<Self::Store>,
+    ) -> webb_relayer_utils::Result<()> {
+        let mut leaves = self.leaves.lock().await;
+        self.write_leaves(&store, &mut leaves)
+    }
 }
@@ -0,0 +1,104 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::VAnchorContractWrapper;
+use ethereum_types::H256;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use webb::evm::contract::protocol_solidity::variable_anchor::VAnchorContractEvents;
+use webb::evm::ethers::prelude::LogMeta;
+use webb::evm::ethers::types;
+use webb_event_watcher_traits::evm::EventHandler;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_store::MerkleRootStore;
+use webb_relayer_store::SledStore;
+use webb_relayer_types::EthersTimeLagClient;
+use webb_relayer_utils::metric;
+
+/// A Merkle Roots Handler that handles `Insertion`, `EdgeAddition` and
+/// `EdgeUpdate` events and saves the new roots of the anchor and of its
+/// neighbors to the store.
+/// It is used to reject relay commands proving against unknown roots before
+/// they are sent on chain, and to serve the roots the relayer accepts.
+#[derive(Copy, Clone, Debug)]
+pub struct VAnchorMerkleRootsHandler {
+    chain_id: types::U256,
+}
+
+impl VAnchorMerkleRootsHandler {
+    pub fn new(chain_id: types::U256) -> Self {
+        Self { chain_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler for VAnchorMerkleRootsHandler {
+    type Contract = VAnchorContractWrapper<EthersTimeLagClient>;
+
+    type Events = VAnchorContractEvents;
+
+    type Store = SledStore;
+
+    async fn can_handle_events(
+        &self,
+        (events, _meta): (Self::Events, LogMeta),
+        _wrapper: &Self::Contract,
+    ) -> webb_relayer_utils::Result<bool> {
+        use VAnchorContractEvents::*;
+        let has_event = matches!(
+            events,
+            InsertionFilter(_) | EdgeAdditionFilter(_) | EdgeUpdateFilter(_)
+        );
+        Ok(has_event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn handle_event(
+        &self,
+        store: Arc<Self::Store>,
+        wrapper: &Self::Contract,
+        (event, log): (Self::Events, LogMeta),
+        _metrics: Arc<Mutex<metric::Metrics>>,
+    ) -> webb_relayer_utils::Result<()> {
+        use VAnchorContractEvents::*;
+        let typed_chain_id = TypedChainId::Evm(self.chain_id.as_u32());
+        let (source_chain_id, root) = match event {
+            InsertionFilter(v) => {
+                (typed_chain_id.chain_id(), v.new_merkle_root)
+            }
+            EdgeAdditionFilter(v) => (v.chain_id.as_u64(), v.merkle_root),
+            EdgeUpdateFilter(v) => (v.chain_id.as_u64(), v.merkle_root),
+            _ => return Ok(()),
+        };
+        let root: [u8; 32] = root.into();
+        let root = H256::from(root);
+        let target_system = TargetSystem::new_contract_address(
+            wrapper.contract.address().to_fixed_bytes(),
+        );
+        let resource_id = ResourceId::new(target_system, typed_chain_id);
+        store.insert_merkle_root(
+            resource_id,
+            source_chain_id,
+            root,
+            log.block_number.as_u64(),
+        )?;
+        tracing::trace!(
+            source_chain_id,
+            ?root,
+            %log.block_number,
+            "New merkle root",
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::VAnchorContractWrapper;
+use ethereum_types::H256;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use webb::evm::contract::protocol_solidity::variable_anchor::VAnchorContractEvents;
+use webb::evm::ethers::prelude::LogMeta;
+use webb::evm::ethers::types;
+use webb_event_watcher_traits::evm::EventHandler;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_store::NullifierStore;
+use webb_relayer_store::SledStore;
+use webb_relayer_types::EthersTimeLagClient;
+use webb_relayer_utils::metric;
+
+/// A Nullifier Handler that handles `NewNullifier` events and marks the
+/// nullifiers as spent in the store.
+/// It is used to reject relay commands spending them again before they are
+/// sent on chain.
+#[derive(Copy, Clone, Debug)]
+pub struct VAnchorNullifierHandler {
+    chain_id: types::U256,
+}
+
+impl VAnchorNullifierHandler {
+    pub fn new(chain_id: types::U256) -> Self {
+        Self { chain_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler for VAnchorNullifierHandler {
+    type Contract = VAnchorContractWrapper<EthersTimeLagClient>;
+
+    type Events = VAnchorContractEvents;
+
+    type Store = SledStore;
+
+    async fn can_handle_events(
+        &self,
+        (events, _meta): (Self::Events, LogMeta),
+        _wrapper: &Self::Contract,
+    ) -> webb_relayer_utils::Result<bool> {
+        use VAnchorContractEvents::*;
+        let has_event = matches!(events, NewNullifierFilter(_));
+        Ok(has_event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn handle_event(
+        &self,
+        store: Arc<Self::Store>,
+        wrapper: &Self::Contract,
+        (event, log): (Self::Events, LogMeta),
+        _metrics: Arc<Mutex<metric::Metrics>>,
+    ) -> webb_relayer_utils::Result<()> {
+        use VAnchorContractEvents::*;
+        if let NewNullifierFilter(v) = event {
+            let nullifier: [u8; 32] = v.nullifier.into();
+            let nullifier = H256::from(nullifier);
+            let target_system = TargetSystem::new_contract_address(
+                wrapper.contract.address().to_fixed_bytes(),
+            );
+            let typed_chain_id = TypedChainId::Evm(self.chain_id.as_u32());
+            let resource_id = ResourceId::new(target_system, typed_chain_id);
+            store.insert_nullifiers(resource_id, &[nullifier])?;
+            tracing::trace!(
+                ?nullifier,
+                %log.block_number,
+                "Nullifier spent",
+            );
+        }
+        Ok(())
+    }
+}
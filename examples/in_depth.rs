@@ -49,6 +49,7 @@ async fn main() -> anyhow::Result<()> {
             data_query: true,
             private_tx_relay: true,
             governance_relay: true,
+            simulation: false,
         },
         proposal_signing_backend: Some(ProposalSigningBackendConfig::Mocked(
             MockedProposalSigningBackendConfig {
@@ -60,52 +61,72 @@ async fn main() -> anyhow::Result<()> {
             EvmChainConfig {
                 name: String::from("polygon"),
                 enabled: true,
+                mode: Default::default(),
                 http_endpoint: "https://polygon-rpc.com/"
                     .parse::<url::Url>()?
                     .into(),
                 ws_endpoint: "wss://polygon-rpc.com/"
                     .parse::<url::Url>()?
                     .into(),
+                transports: Vec::new(),
                 explorer: Some("https://polygonscan.com".parse()?),
                 chain_id: 137,
                 private_key: Some(ethereum_types::Secret::random().into()),
+                hd_wallet: None,
+                keystore: None,
                 beneficiary: Some(ethereum_types::Address::random()), // Do not ever hardcode a private key in production!
                 contracts: vec![
                     Contract::VAnchor(VAnchorContractConfig {
                         common: CommonContractConfig {
                             address: ethereum_types::Address::random(),
                             deployed_at: 69420,
+                            gas_limit_multiplier: 1.2,
                         },
                         events_watcher: EventsWatcherConfig {
                             enable_data_query: true,
                             enabled: true,
                             polling_interval: 3000,
+                            adaptive_polling: false,
                             max_blocks_per_step: 1000,
                             print_progress_interval: 60_000,
                             sync_blocks_from: None,
                         },
                         linked_anchors: None,
                         smart_anchor_updates: Default::default(),
+                        confirmed_roots: false,
+                        withdrawal_policy: Default::default(),
+                        leaves_flush_policy: Default::default(),
+                        propagation_policy: Default::default(),
+                        forwarder: None,
                     }),
                     Contract::SignatureBridge(SignatureBridgeContractConfig {
                         common: CommonContractConfig {
                             address: ethereum_types::Address::random(),
                             deployed_at: 69420,
+                            gas_limit_multiplier: 1.2,
                         },
                         events_watcher: EventsWatcherConfig {
                             enable_data_query: true,
                             enabled: true,
                             polling_interval: 3000,
+                            adaptive_polling: false,
                             max_blocks_per_step: 1000,
                             print_progress_interval: 60_000,
                             sync_blocks_from: None,
                         },
+                        anchor_discovery: Default::default(),
                     }),
                 ],
                 block_poller: None,
                 block_confirmations: 0,
                 tx_queue: Default::default(),
                 relayer_fee_config: Default::default(),
+                circuit_breaker: Default::default(),
+                rpc: Default::default(),
+                gas_budget: None,
+                fee_harvesting: Default::default(),
+                treasury_sweep: Default::default(),
+                allowed_tokens: None,
             },
         )]),
         ..Default::default()
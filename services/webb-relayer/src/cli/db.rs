@@ -0,0 +1,68 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use webb_proposals::ResourceId;
+use webb_relayer_config::cli::DbCommand;
+use webb_relayer_store::{LeafCacheStore, SledStore};
+
+/// Executes the given `db` subcommand against the store.
+///
+/// Nothing is written to the store, the contents are only printed
+/// to stdout.
+pub fn execute(cmd: &DbCommand, store: &SledStore) -> anyhow::Result<()> {
+    match cmd {
+        DbCommand::Queues => print_queues(store, |_| true),
+        DbCommand::Proposals => {
+            print_queues(store, |name| name.starts_with("bridge_cmd_"))
+        }
+        DbCommand::Leaves { resource_id } => {
+            let resource_id = ResourceId::from(resource_id.to_fixed_bytes());
+            let leaves = store.get_leaves(resource_id)?;
+            let last_deposit_block_number =
+                store.get_last_deposit_block_number(resource_id)?;
+            println!(
+                "{} leaves (last deposit at block #{})",
+                leaves.len(),
+                last_deposit_block_number
+            );
+            for (index, leaf) in leaves {
+                println!("{index:>8}: {leaf:?}");
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Prints the items of every queue whose name matches the filter.
+fn print_queues<F>(store: &SledStore, filter: F) -> anyhow::Result<()>
+where
+    F: Fn(&str) -> bool,
+{
+    let queue_names: Vec<_> = store
+        .queue_names()
+        .into_iter()
+        .filter(|name| filter(name))
+        .collect();
+    if queue_names.is_empty() {
+        println!("no queues found");
+    }
+    for name in queue_names {
+        let items = store.queue_items(&name)?;
+        println!("== {name} ({} items) ==", items.len());
+        for item in items {
+            println!("{}", serde_json::to_string_pretty(&item)?);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use anyhow::Context;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use subxt_signer::bip39::Mnemonic;
+use subxt_signer::sr25519::Keypair as Sr25519Pair;
+use webb::evm::ethers::signers::coins_bip39::English;
+use webb::evm::ethers::signers::{LocalWallet, MnemonicBuilder, Signer};
+use webb::evm::ethers::types::H256;
+use webb_relayer_config::cli::KeygenOpts;
+
+/// The file name of the EVM keystore.
+const EVM_KEYSTORE_NAME: &str = "evm.json";
+/// The file name of the sr25519 keystore.
+const SR25519_KEYSTORE_NAME: &str = "sr25519.json";
+
+/// The secrets of a newly generated relayer account.
+struct Keys {
+    /// The mnemonic both keys are derived from, if any.
+    mnemonic: Option<Mnemonic>,
+    /// The EVM private key.
+    evm_private_key: H256,
+    /// The sr25519 seed (`MiniSecretKey`).
+    sr25519_seed: [u8; 32],
+}
+
+impl Keys {
+    /// Generates two independent random keys.
+    fn random() -> Self {
+        let wallet = LocalWallet::new(&mut OsRng);
+        let mut sr25519_seed = [0u8; 32];
+        OsRng.fill_bytes(&mut sr25519_seed);
+        Self {
+            mnemonic: None,
+            evm_private_key: H256::from_slice(&wallet.signer().to_bytes()),
+            sr25519_seed,
+        }
+    }
+
+    /// Generates a 12 words mnemonic and derives both keys from it, the
+    /// same way the config loader does when given the mnemonic.
+    fn from_new_mnemonic() -> anyhow::Result<Self> {
+        let mut entropy = [0u8; 16];
+        OsRng.fill_bytes(&mut entropy);
+        let mnemonic = Mnemonic::from_entropy(&entropy)
+            .map_err(|e| anyhow::anyhow!("failed to create mnemonic: {e}"))?;
+        let phrase = mnemonic.to_string();
+        let wallet = MnemonicBuilder::<English>::default()
+            .phrase(phrase.as_str())
+            .build()?;
+        let seed =
+            substrate_bip39::seed_from_entropy(&mnemonic.to_entropy(), "")
+                .map_err(|e| anyhow::anyhow!("failed to derive seed: {e:?}"))?;
+        let mut sr25519_seed = [0u8; 32];
+        sr25519_seed.copy_from_slice(&seed[..32]);
+        Ok(Self {
+            mnemonic: Some(mnemonic),
+            evm_private_key: H256::from_slice(&wallet.signer().to_bytes()),
+            sr25519_seed,
+        })
+    }
+}
+
+/// Generates the keys of a new relayer, prints the addresses and either
+/// prints the secrets or writes them to encrypted keystores.
+pub fn execute(opts: &KeygenOpts) -> anyhow::Result<()> {
+    let keys = if opts.mnemonic {
+        Keys::from_new_mnemonic()?
+    } else {
+        Keys::random()
+    };
+    let evm_wallet = LocalWallet::from_bytes(keys.evm_private_key.as_bytes())?;
+    let sr25519_pair = Sr25519Pair::from_seed(keys.sr25519_seed)
+        .map_err(|e| anyhow::anyhow!("invalid sr25519 seed: {e:?}"))?;
+
+    println!("EVM address:       {:?}", evm_wallet.address());
+    println!(
+        "Substrate address: {}",
+        sr25519_pair.public_key().to_account_id()
+    );
+    if let Some(mnemonic) = &keys.mnemonic {
+        println!("Mnemonic:          {mnemonic}");
+    }
+
+    match &opts.keystore_dir {
+        Some(dir) => {
+            write_keystores(dir, &keys, opts.passphrase_env.as_deref())?;
+            let passphrase_env = opts
+                .passphrase_env
+                .as_ref()
+                .map(|var| format!(", passphrase-env = \"{var}\""))
+                .unwrap_or_default();
+            println!();
+            println!("Use the keystores in the chains configuration:");
+            println!();
+            for (section, name) in [
+                ("evm.<chain>", EVM_KEYSTORE_NAME),
+                ("substrate.<node>", SR25519_KEYSTORE_NAME),
+            ] {
+                println!("[{section}]");
+                println!(
+                    "keystore = {{ path = \"{}\"{passphrase_env} }}",
+                    dir.join(name).display()
+                );
+            }
+        }
+        None => {
+            println!("EVM private key:   {:?}", keys.evm_private_key);
+            println!("sr25519 seed:      {:?}", H256::from(keys.sr25519_seed));
+        }
+    }
+    Ok(())
+}
+
+/// Encrypts both secrets into Ethereum V3 keystores under `dir`, which is
+/// the format the relayer decrypts the `keystore` configs with.
+fn write_keystores(
+    dir: &Path,
+    keys: &Keys,
+    passphrase_env: Option<&str>,
+) -> anyhow::Result<()> {
+    for name in [EVM_KEYSTORE_NAME, SR25519_KEYSTORE_NAME] {
+        let path = dir.join(name);
+        anyhow::ensure!(!path.exists(), "{} already exists", path.display());
+    }
+    let passphrase = match passphrase_env {
+        Some(var) => std::env::var(var)
+            .with_context(|| format!("error while loading this env {var}"))?,
+        None => {
+            let passphrase = rpassword::prompt_password(
+                "Enter the passphrase for the keystores: ",
+            )?;
+            let confirmation =
+                rpassword::prompt_password("Repeat the passphrase: ")?;
+            anyhow::ensure!(
+                passphrase == confirmation,
+                "the passphrases do not match"
+            );
+            passphrase
+        }
+    };
+    std::fs::create_dir_all(dir)?;
+    let secrets = [
+        (EVM_KEYSTORE_NAME, keys.evm_private_key.to_fixed_bytes()),
+        (SR25519_KEYSTORE_NAME, keys.sr25519_seed),
+    ];
+    for (name, secret) in secrets {
+        eth_keystore::encrypt_key(
+            dir,
+            &mut OsRng,
+            secret,
+            &passphrase,
+            Some(name),
+        )?;
+    }
+    Ok(())
+}
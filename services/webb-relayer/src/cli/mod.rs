@@ -0,0 +1,53 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Commands that can be run instead of starting the relayer.
+
+use webb_relayer_config::cli::{create_store, load_config, Command, Opts};
+
+/// A module for inspecting the relayer database.
+mod db;
+/// A module for generating the relayer keys.
+mod keygen;
+/// A module for checking the configuration against the live chains.
+pub mod preflight;
+/// A module for managing the transaction queues.
+mod queue;
+
+/// Executes the given command.
+///
+/// # Arguments
+///
+/// * `cmd` - The command to execute.
+/// * `opts` - The command line arguments.
+pub async fn execute(cmd: &Command, opts: &Opts) -> anyhow::Result<()> {
+    match cmd {
+        Command::Db(cmd) => {
+            let config = load_config(opts.config_dir.clone())?;
+            let store = create_store(opts, &config.store_encryption).await?;
+            db::execute(cmd, &store)
+        }
+        Command::Keygen(opts) => keygen::execute(opts),
+        Command::Queue(cmd) => {
+            let config = load_config(opts.config_dir.clone())?;
+            let store = create_store(opts, &config.store_encryption).await?;
+            queue::execute(cmd, &store)
+        }
+        Command::Schema => {
+            let schema = webb_relayer_config::utils::config_schema();
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+            Ok(())
+        }
+    }
+}
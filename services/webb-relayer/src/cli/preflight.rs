@@ -0,0 +1,220 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use webb::evm::ethers::providers::Middleware;
+use webb::evm::ethers::signers::Signer;
+use webb::evm::ethers::types::U256;
+use webb::evm::ethers::utils::format_ether;
+use webb::substrate::tangle_runtime::api as RuntimeApi;
+use webb_relayer::service::tangle::TangleClient;
+use webb_relayer_config::evm::{Contract, EvmChainConfig};
+use webb_relayer_config::substrate::SubstrateConfig;
+use webb_relayer_config::WebbRelayerConfig;
+use webb_relayer_context::{connect_substrate_node, keystore, RelayerContext};
+use webb_relayer_store::SledStore;
+
+/// How long to wait for a single endpoint call before reporting it.
+///
+/// The providers retry failed requests, so without a timeout an
+/// unreachable endpoint would stall the preflight forever.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Collects the outcome of the preflight checks.
+#[derive(Default)]
+struct Report {
+    /// The number of failed checks.
+    problems: usize,
+}
+
+impl Report {
+    fn ok(&mut self, message: impl Display) {
+        println!("  [ok]    {message}");
+    }
+
+    fn problem(&mut self, message: impl Display) {
+        println!("  [error] {message}");
+        self.problems += 1;
+    }
+}
+
+/// Runs `fut`, turning both its error and a timeout into a message.
+async fn checked<T, E, F>(fut: F) -> Result<T, String>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Display,
+{
+    match tokio::time::timeout(CHECK_TIMEOUT, fut).await {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("timed out after {CHECK_TIMEOUT:?}")),
+    }
+}
+
+/// Checks every enabled EVM chain and Substrate node of the config, prints
+/// a report and fails if any of the checks failed.
+///
+/// Nothing is started, and the relayer database is not opened.
+pub async fn run(mut config: WebbRelayerConfig) -> anyhow::Result<()> {
+    keystore::decrypt_keystores(&mut config)?;
+    // the context connects to the Substrate nodes eagerly, and fails if one
+    // of them is down. They are checked on their own connections below, so
+    // that an unreachable node shows up in the report instead.
+    let mut evm_only_config = config.clone();
+    evm_only_config.substrate.clear();
+    let ctx =
+        RelayerContext::new(evm_only_config, SledStore::temporary()?).await?;
+    let features = &config.features;
+    let needs_account = features.governance_relay || features.private_tx_relay;
+
+    let mut report = Report::default();
+    for chain in config.evm.values().filter(|c| c.enabled) {
+        println!("EVM chain {} ({})", chain.name, chain.chain_id);
+        check_evm_chain(&ctx, chain, needs_account, &mut report).await;
+    }
+    for node in config.substrate.values().filter(|n| n.enabled) {
+        println!("Substrate node {} ({})", node.name, node.chain_id);
+        check_substrate_node(node, &mut report).await;
+    }
+    ctx.shutdown();
+
+    anyhow::ensure!(
+        report.problems == 0,
+        "preflight failed with {} problem(s)",
+        report.problems
+    );
+    println!("preflight passed");
+    Ok(())
+}
+
+/// Checks the endpoint, the relayer account balance and the configured
+/// contracts of an EVM chain.
+async fn check_evm_chain(
+    ctx: &RelayerContext,
+    chain: &EvmChainConfig,
+    needs_account: bool,
+    report: &mut Report,
+) {
+    let provider = match ctx.evm_provider(chain.chain_id).await {
+        Ok(provider) => provider,
+        Err(e) => return report.problem(e),
+    };
+    match checked(provider.get_chainid()).await {
+        Ok(chain_id) if chain_id == U256::from(chain.chain_id) => {
+            report.ok("connected to the RPC endpoint")
+        }
+        Ok(chain_id) => {
+            return report.problem(format!(
+                "the RPC endpoint is for chain {chain_id}, expected {}",
+                chain.chain_id
+            ))
+        }
+        Err(e) => {
+            return report
+                .problem(format!("failed to connect to the RPC endpoint: {e}"))
+        }
+    }
+
+    match ctx.evm_wallet(chain.chain_id).await {
+        Ok(wallet) => {
+            let address = wallet.address();
+            match checked(provider.get_balance(address, None)).await {
+                Ok(balance) if balance.is_zero() => report
+                    .problem(format!("relayer account {address:?} is empty")),
+                Ok(balance) => report.ok(format!(
+                    "relayer account {address:?} holds {}",
+                    format_ether(balance)
+                )),
+                Err(e) => report.problem(format!(
+                    "failed to fetch the balance of {address:?}: {e}"
+                )),
+            }
+        }
+        Err(e) if needs_account => {
+            report.problem(format!("no relayer account: {e}"))
+        }
+        Err(_) => report.ok("no relayer account (not needed)"),
+    }
+
+    for contract in &chain.contracts {
+        let (kind, address) = match contract {
+            Contract::VAnchor(c) => ("VAnchor", c.common.address),
+            Contract::SignatureBridge(c) => {
+                ("SignatureBridge", c.common.address)
+            }
+            Contract::MaspVanchor(c) => ("MaspVanchor", c.common.address),
+        };
+        match checked(provider.get_code(address, None)).await {
+            Ok(code) if code.is_empty() => {
+                report.problem(format!("no {kind} contract at {address:?}"))
+            }
+            Ok(_) => report.ok(format!("{kind} contract at {address:?}")),
+            Err(e) => report.problem(format!(
+                "failed to fetch the code of {kind} at {address:?}: {e}"
+            )),
+        }
+    }
+}
+
+/// Checks the endpoint and the relayer account balance of a Substrate node.
+async fn check_substrate_node(node: &SubstrateConfig, report: &mut Report) {
+    let client = checked(async {
+        let rpc_client = connect_substrate_node(node).await?;
+        let client =
+            TangleClient::from_rpc_client(Arc::new(rpc_client)).await?;
+        anyhow::Ok(client)
+    })
+    .await;
+    let client = match client {
+        Ok(client) => {
+            report.ok("connected to the RPC endpoint");
+            client
+        }
+        Err(e) => {
+            return report
+                .problem(format!("failed to connect to the RPC endpoint: {e}"))
+        }
+    };
+
+    let Some(suri) = node.suri.as_ref() else {
+        return report.problem("no relayer account: missing suri or keystore");
+    };
+    let account_id = suri.public_key().to_account_id();
+    let account = RuntimeApi::storage().system().account(&account_id);
+    let balance = checked(async {
+        let info = client
+            .storage()
+            .at_latest()
+            .await?
+            .fetch_or_default(&account)
+            .await?;
+        anyhow::Ok(info.data.free)
+    })
+    .await;
+    match balance {
+        Ok(0) => {
+            report.problem(format!("relayer account {account_id} is empty"))
+        }
+        Ok(free) => {
+            report.ok(format!("relayer account {account_id} holds {free}"))
+        }
+        Err(e) => report.problem(format!(
+            "failed to fetch the balance of {account_id}: {e}"
+        )),
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use webb_relayer_config::cli::QueueCommand;
+use webb_relayer_store::queue::QueueItemState;
+use webb_relayer_store::SledStore;
+
+/// Executes the given `queue` subcommand against the store.
+pub fn execute(cmd: &QueueCommand, store: &SledStore) -> anyhow::Result<()> {
+    match cmd {
+        QueueCommand::Purge {
+            chain_id,
+            substrate,
+            state,
+        } => {
+            let queue_name = if *substrate {
+                format!("substrate_tx_{chain_id}")
+            } else {
+                format!("evm_tx_{chain_id}")
+            };
+            let purged =
+                store.purge_queue_items(&queue_name, |item_state| {
+                    state
+                        .as_deref()
+                        .map_or(true, |state| state_matches(item_state, state))
+                })?;
+            println!("purged {purged} items from {queue_name}");
+        }
+        QueueCommand::Requeue { item_key } => {
+            match store.requeue_item(item_key.to_fixed_bytes())? {
+                Some(queue_name) => {
+                    println!("requeued {item_key:?} in {queue_name}")
+                }
+                None => anyhow::bail!("item {item_key:?} not found"),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks if the state of an item is the one named on the command line.
+fn state_matches(item_state: &QueueItemState, state: &str) -> bool {
+    item_state.name().eq_ignore_ascii_case(state)
+}
@@ -17,14 +17,20 @@
 #![warn(missing_docs)]
 
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::signal::unix;
-use tokio::time;
 use webb_relayer::service::build_web_services;
+use webb_relayer::service::drain::{drain, DrainOutcome};
 
 use webb_relayer_config::cli::{create_store, load_config, setup_logger, Opts};
 use webb_relayer_context::RelayerContext;
 
+/// Commands that can be run instead of starting the relayer.
+mod cli;
+
+/// The exit code of the relayer when it shuts down before its transaction
+/// queues are drained, so the supervisor of the process can tell.
+const NOT_DRAINED_EXIT_CODE: i32 = 3;
+
 /// The main entry point for the relayer.
 ///
 /// # Arguments
@@ -33,7 +39,7 @@ use webb_relayer_context::RelayerContext;
 #[paw::main]
 #[tokio::main]
 async fn main(args: Opts) -> anyhow::Result<()> {
-    setup_logger(args.verbose, "webb_relayer")?;
+    let logger = setup_logger(args.verbose, "webb_relayer")?;
     match dotenv::dotenv() {
         Ok(_) => {
             tracing::trace!("Loaded .env file");
@@ -43,33 +49,36 @@ async fn main(args: Opts) -> anyhow::Result<()> {
         }
     }
 
+    if let Some(cmd) = args.cmd.as_ref() {
+        return cli::execute(cmd, &args).await;
+    }
+
     // The configuration is validated and configured from the given directory
     let config = load_config(args.config_dir.clone())?;
 
+    if args.dry_run {
+        return cli::preflight::run(config).await;
+    }
+
     // persistent storage for the relayer
-    let store = create_store(&args).await?;
-    let cloned_store = store.clone();
+    let store = create_store(&args, &config.store_encryption).await?;
+    // the event hashes are only indexed by time to be purged.
+    let store = match config.retention.event_hashes {
+        Some(_) => store.with_event_retention(),
+        None => store,
+    };
 
     // The RelayerContext takes a configuration, and populates objects that are needed
     // throughout the lifetime of the relayer. Items such as wallets and providers, as well
     // as a convenient place to access the configuration.
-    let ctx = RelayerContext::new(config, store.clone()).await?;
-    let metrics_clone = ctx.metrics.clone();
-
-    // metric for data stored which is determined every 1 hour
-    let sled_metric_task_handle = tokio::task::spawn(async move {
-        let mut sled_data_metric_interval =
-            time::interval(Duration::from_secs(3600));
-        loop {
-            sled_data_metric_interval.tick().await;
-            // set data stored
-            metrics_clone
-                .lock()
-                .await
-                .total_amount_of_data_stored
-                .set(cloned_store.get_data_stored_size() as f64);
-        }
-    });
+    let ctx = RelayerContext::new(config, store.clone())
+        .await?
+        .with_log_filter(logger.log_filter);
+    // export the probe events, if a broker is configured.
+    webb_relayer::service::probe_export::start_probe_exporter(
+        &ctx,
+        logger.probe_bus,
+    );
 
     // the build_web_relayer command sets up routing (endpoint queries / requests mapped to handled code)
     // so clients can interact with the relayer
@@ -77,6 +86,8 @@ async fn main(args: Opts) -> anyhow::Result<()> {
     // start all background services.
     // this does not block, will fire the services on background tasks.
     webb_relayer::service::ignite(ctx.clone(), Arc::new(store)).await?;
+    // let systemd know we are up, and keep its watchdog fed while we are.
+    webb_relayer::service::watchdog::start(&ctx);
 
     tracing::event!(
         target: webb_relayer_utils::probe::TARGET,
@@ -96,29 +107,74 @@ async fn main(args: Opts) -> anyhow::Result<()> {
             shutdown = true
         );
         tracing::warn!("Shutting down...");
+        webb_relayer::service::watchdog::stopping();
         // shut down storage fetching
         // send shutdown signal to all of the application.
         ctx.shutdown();
         // also abort the server task
         server_handle.abort();
-        // abort get sled storage data task
-        sled_metric_task_handle.abort();
+        // persist whatever is left in the queues before exiting.
+        if let Err(e) = ctx.store().flush() {
+            tracing::error!("Failed to flush the store: {e}");
+        }
         std::thread::sleep(std::time::Duration::from_millis(300));
         tracing::info!("Clean Exit ..");
     };
-    tokio::select! {
+    let should_drain = tokio::select! {
         _ = ctrlc_signal.recv() => {
             tracing::warn!("Interrupted (Ctrl+C) ...");
-            shutdown();
+            true
         },
         _ = termination_signal.recv() => {
             tracing::warn!("Got Terminate signal ...");
-            shutdown();
+            true
         },
         _ = quit_signal.recv() => {
             tracing::warn!("Quitting ...");
-            shutdown();
+            false
         },
+    };
+    let mut exit_code = 0;
+    if should_drain {
+        tracing::event!(
+            target: webb_relayer_utils::probe::TARGET,
+            tracing::Level::DEBUG,
+            kind = %webb_relayer_utils::probe::Kind::Lifecycle,
+            draining = true
+        );
+        tracing::warn!("Draining the transaction queues ...");
+        webb_relayer::service::watchdog::stopping();
+        // a second signal skips the rest of the drain.
+        tokio::select! {
+            outcome = drain(&ctx) => match outcome {
+                DrainOutcome::Drained => {
+                    tracing::info!("Transaction queues drained");
+                }
+                DrainOutcome::TimedOut { pending } => {
+                    tracing::warn!(
+                        pending,
+                        "Drain timed out, exiting with pending transactions",
+                    );
+                    exit_code = NOT_DRAINED_EXIT_CODE;
+                }
+            },
+            _ = ctrlc_signal.recv() => {
+                tracing::warn!("Interrupted again, skipping the drain ...");
+                exit_code = NOT_DRAINED_EXIT_CODE;
+            },
+            _ = termination_signal.recv() => {
+                tracing::warn!("Terminated again, skipping the drain ...");
+                exit_code = NOT_DRAINED_EXIT_CODE;
+            },
+            _ = quit_signal.recv() => {
+                tracing::warn!("Quitting, skipping the drain ...");
+                exit_code = NOT_DRAINED_EXIT_CODE;
+            },
+        }
+    }
+    shutdown();
+    if exit_code != 0 {
+        std::process::exit(exit_code);
     }
     Ok(())
 }
@@ -0,0 +1,112 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Deployment Block Detection 🔎
+//!
+//! Finds the block a contract got deployed at when its `deployed-at` is not
+//! configured, by binary searching the first block at which the node
+//! returns its code. The detected block is persisted, so the search only
+//! runs once per contract.
+
+use ethereum_types::Address;
+use webb::evm::ethers::providers::Middleware;
+use webb::evm::ethers::types::BlockId;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_config::evm::Contract;
+use webb_relayer_store::HistoryStore;
+use webb_relayer_types::EthersClient;
+
+/// Fills in the deployment block of the contract, if it is not configured.
+///
+/// If it can not be detected (e.g. the node does not serve historical
+/// state), the events watcher falls back to scanning from the genesis.
+///
+/// # Arguments
+///
+/// * `client` - EVM Chain api client
+/// * `store` -[Sled](https://sled.rs)-based database store
+/// * `chain_id` - An u32 representing the chain id of the chain
+/// * `contract` - The contract configuration to fill in
+pub async fn resolve_deployed_at(
+    client: &EthersClient,
+    store: &super::Store,
+    chain_id: u32,
+    contract: &mut Contract,
+) -> crate::Result<()> {
+    let common = match contract {
+        Contract::VAnchor(config) => &mut config.common,
+        Contract::SignatureBridge(config) => &mut config.common,
+        Contract::MaspVanchor(config) => &mut config.common,
+    };
+    if common.deployed_at != 0 {
+        return Ok(());
+    }
+    let history_store_key = ResourceId::new(
+        TargetSystem::new_contract_address(common.address.to_fixed_bytes()),
+        TypedChainId::Evm(chain_id),
+    );
+    if let Some(block) = store.get_deployment_block_number(history_store_key)? {
+        common.deployed_at = block;
+        return Ok(());
+    }
+    match find_deployment_block(client, common.address).await {
+        Ok(block) => {
+            store.set_deployment_block_number(history_store_key, block)?;
+            common.deployed_at = block;
+            tracing::info!(
+                %chain_id,
+                address = %common.address,
+                deployed_at = block,
+                "Detected the deployment block of the contract",
+            );
+        }
+        Err(e) => {
+            tracing::warn!(
+                %chain_id,
+                address = %common.address,
+                error = %e,
+                "Failed to detect the deployment block of the contract, \
+                 scanning from the genesis",
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Binary searches the first block at which the contract has code.
+async fn find_deployment_block(
+    client: &EthersClient,
+    address: Address,
+) -> crate::Result<u64> {
+    let latest = client.get_block_number().await?.as_u64();
+    let has_code_at = |block: u64| async move {
+        let code = client
+            .get_code(address, Some(BlockId::Number(block.into())))
+            .await?;
+        Ok::<_, crate::Error>(!code.is_empty())
+    };
+    if !has_code_at(latest).await? {
+        return Err(crate::Error::Generic("no contract code at this address"));
+    }
+    let (mut low, mut high) = (0u64, latest);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if has_code_at(mid).await? {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Ok(low)
+}
@@ -0,0 +1,314 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Anchor Discovery Service 🔭
+//!
+//! Reads the resource mappings of the Signature Bridge (and its Anchor Handler)
+//! and starts the event watchers and leaf caches for the anchors that got
+//! registered after the relayer started, without any config edits or restarts.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethereum_types::Address;
+use tokio::sync::Mutex;
+use webb::evm::ethers::abi::parse_abi;
+use webb::evm::ethers::contract::Contract;
+use webb::evm::ethers::prelude::TimeLag;
+use webb_bridge_registry_backends::BridgeRegistryBackend;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_config::defaults;
+use webb_relayer_config::evm::{
+    CommonContractConfig, Contract as ContractConfig,
+    SignatureBridgeContractConfig, VAnchorContractConfig,
+};
+use webb_relayer_context::RelayerContext;
+use webb_relayer_types::EthersClient;
+
+use super::evm::start_vanchor_events_watcher;
+use super::make_bridge_registry_backend;
+
+/// The subset of the Signature Bridge ABI used for discovery.
+const SIGNATURE_BRIDGE_ABI: &[&str] = &[
+    "function _resourceIdToHandlerAddress(bytes32 resourceId) external view returns (address)",
+];
+
+/// The subset of the Anchor Handler ABI used for discovery.
+const ANCHOR_HANDLER_ABI: &[&str] = &[
+    "function _resourceIDToContractAddress(bytes32 resourceId) external view returns (address)",
+];
+
+/// Starts the anchor discovery task for the given Signature Bridge.
+///
+/// Returns Ok(()) if successful, or an error if not.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `config` - Signature Bridge contract configuration
+/// * `chain_id` - The chain id of the chain the bridge is deployed on
+/// * `store` -[Sled](https://sled.rs)-based database store
+pub async fn start_anchor_discovery(
+    ctx: &RelayerContext,
+    config: &SignatureBridgeContractConfig,
+    chain_id: u32,
+    store: Arc<super::Store>,
+) -> crate::Result<()> {
+    if !config.anchor_discovery.enabled {
+        return Ok(());
+    }
+    let client = ctx.evm_provider(chain_id).await?;
+    let bridge_registry_backend = make_bridge_registry_backend(ctx).await?;
+    let my_ctx = ctx.clone();
+    let my_config = config.clone();
+    let bridge_address = config.common.address;
+    // anchors that are already configured do not need to be discovered.
+    let known_anchors: HashSet<Address> = ctx
+        .config
+        .evm
+        .values()
+        .filter(|chain| chain.chain_id == chain_id)
+        .flat_map(|chain| chain.contracts.iter())
+        .filter_map(|contract| match contract {
+            ContractConfig::VAnchor(config) => Some(config.common.address),
+            _ => None,
+        })
+        .collect();
+    // shared between restarts, so discovered anchors are not started twice.
+    let known_anchors = Arc::new(Mutex::new(known_anchors));
+    let task = move || {
+        run_anchor_discovery(
+            my_ctx.clone(),
+            my_config.clone(),
+            chain_id,
+            client.clone(),
+            bridge_registry_backend.clone(),
+            known_anchors.clone(),
+            store.clone(),
+        )
+    };
+    // kick off the discovery.
+    ctx.supervisor().spawn(
+        format!("evm:{chain_id}:anchor-discovery:{bridge_address:?}"),
+        task,
+    );
+    Ok(())
+}
+
+/// Periodically discovers new anchors and starts their services.
+async fn run_anchor_discovery(
+    ctx: RelayerContext,
+    config: SignatureBridgeContractConfig,
+    chain_id: u32,
+    client: Arc<EthersClient>,
+    bridge_registry_backend: Arc<dyn BridgeRegistryBackend>,
+    known_anchors: Arc<Mutex<HashSet<Address>>>,
+    store: Arc<super::Store>,
+) -> crate::Result<()> {
+    let bridge_address = config.common.address;
+    let polling_interval =
+        Duration::from_millis(config.anchor_discovery.polling_interval);
+    tracing::debug!(
+        %chain_id,
+        %bridge_address,
+        "Anchor discovery for Signature Bridge Started.",
+    );
+    loop {
+        let mut known_anchors = known_anchors.lock().await;
+        let result = discover_anchors(
+            &ctx,
+            chain_id,
+            bridge_address,
+            client.clone(),
+            bridge_registry_backend.as_ref(),
+            &known_anchors,
+        )
+        .await;
+        match result {
+            Ok(anchors) => {
+                for anchor in anchors {
+                    tracing::info!(
+                        %chain_id,
+                        %bridge_address,
+                        %anchor,
+                        "Discovered a new anchor registered on the bridge",
+                    );
+                    let vanchor_config =
+                        discovered_vanchor_config(&ctx, &config, anchor);
+                    let timelag_client = Arc::new(TimeLag::new(
+                        client.clone(),
+                        block_confirmations(&ctx, chain_id),
+                    ));
+                    let started = start_vanchor_events_watcher(
+                        &ctx,
+                        &vanchor_config,
+                        chain_id,
+                        timelag_client,
+                        store.clone(),
+                    )
+                    .await;
+                    match started {
+                        Ok(()) => {
+                            known_anchors.insert(anchor);
+                        }
+                        Err(e) => {
+                            tracing::error!(
+                                %chain_id,
+                                %anchor,
+                                error = %e,
+                                "Failed to start services for the discovered anchor",
+                            );
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    %chain_id,
+                    %bridge_address,
+                    error = %e,
+                    "Anchor discovery failed, will retry later",
+                );
+            }
+        }
+        drop(known_anchors);
+        tokio::time::sleep(polling_interval).await;
+    }
+}
+
+/// Returns the addresses of the anchors registered on the bridge that are not known yet.
+///
+/// The candidates are the linked anchors (from the config or the bridge registry)
+/// of every configured anchor that live on this chain.
+async fn discover_anchors(
+    ctx: &RelayerContext,
+    chain_id: u32,
+    bridge_address: Address,
+    client: Arc<EthersClient>,
+    bridge_registry_backend: &dyn BridgeRegistryBackend,
+    known_anchors: &HashSet<Address>,
+) -> crate::Result<Vec<Address>> {
+    let mut candidates: HashSet<ResourceId> = HashSet::new();
+    for chain in ctx.config.evm.values() {
+        for contract in &chain.contracts {
+            let ContractConfig::VAnchor(config) = contract else {
+                continue;
+            };
+            let src_resource_id = ResourceId::new(
+                TargetSystem::new_contract_address(config.common.address),
+                TypedChainId::Evm(chain.chain_id),
+            );
+            let linked_anchors = match bridge_registry_backend
+                .config_or_registry_bridges(
+                    &config.linked_anchors,
+                    &src_resource_id,
+                )
+                .await
+            {
+                Ok(anchors) => anchors,
+                Err(webb_relayer_utils::Error::BridgeNotRegistered(_)) => {
+                    continue
+                }
+                Err(e) => return Err(e),
+            };
+            candidates.extend(linked_anchors.into_iter().filter(|r| {
+                match r.target_system() {
+                    TargetSystem::ContractAddress(address) => {
+                        r.typed_chain_id() == TypedChainId::Evm(chain_id)
+                            && !known_anchors.contains(&Address::from(address))
+                    }
+                    _ => false,
+                }
+            }));
+        }
+    }
+
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let bridge = Contract::new(
+        bridge_address,
+        parse_abi(SIGNATURE_BRIDGE_ABI).expect("bridge abi is valid"),
+        client.clone(),
+    );
+    let mut discovered = Vec::new();
+    for resource_id in candidates {
+        let handler_address = bridge
+            .method::<_, Address>(
+                "_resourceIdToHandlerAddress",
+                resource_id.to_bytes(),
+            )?
+            .call()
+            .await?;
+        // not registered on the bridge (yet).
+        if handler_address.is_zero() {
+            continue;
+        }
+        let handler = Contract::new(
+            handler_address,
+            parse_abi(ANCHOR_HANDLER_ABI).expect("handler abi is valid"),
+            client.clone(),
+        );
+        let anchor_address = handler
+            .method::<_, Address>(
+                "_resourceIDToContractAddress",
+                resource_id.to_bytes(),
+            )?
+            .call()
+            .await?;
+        if !anchor_address.is_zero() && !known_anchors.contains(&anchor_address)
+        {
+            discovered.push(anchor_address);
+        }
+    }
+    Ok(discovered)
+}
+
+/// Creates the VAnchor configuration for a discovered anchor.
+///
+/// The anchor is assumed to be deployed after its bridge, and its linked anchors
+/// are resolved through the bridge registry backend.
+fn discovered_vanchor_config(
+    ctx: &RelayerContext,
+    bridge_config: &SignatureBridgeContractConfig,
+    address: Address,
+) -> VAnchorContractConfig {
+    let mut events_watcher = bridge_config.events_watcher;
+    events_watcher.enable_data_query = ctx.config.features.data_query;
+    VAnchorContractConfig {
+        common: CommonContractConfig {
+            address,
+            deployed_at: bridge_config.common.deployed_at,
+            gas_limit_multiplier: defaults::gas_limit_multiplier(),
+        },
+        events_watcher,
+        linked_anchors: None,
+        smart_anchor_updates: Default::default(),
+        confirmed_roots: false,
+        withdrawal_policy: Default::default(),
+        leaves_flush_policy: Default::default(),
+        propagation_policy: Default::default(),
+        forwarder: None,
+    }
+}
+
+fn block_confirmations(ctx: &RelayerContext, chain_id: u32) -> u8 {
+    ctx.config
+        .evm
+        .get(&chain_id.to_string())
+        .map(|chain| chain.block_confirmations)
+        .unwrap_or_default()
+}
@@ -0,0 +1,76 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Shutdown Drain 🚰
+//!
+//! On `SIGTERM` (or `SIGINT`), the relayer stops accepting new relay
+//! requests and keeps its transaction queues going until none of their
+//! items is pending anymore, for at most the configured drain timeout.
+
+use std::time::Duration;
+
+use webb_relayer_context::RelayerContext;
+
+/// How often the transaction queues are checked while draining.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The outcome of draining the transaction queues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrainOutcome {
+    /// No item of the transaction queues is pending anymore.
+    Drained,
+    /// The drain timeout passed with items still pending.
+    TimedOut {
+        /// How many items were still pending.
+        pending: usize,
+    },
+}
+
+/// Drains the transaction queues, resolving once none of their items is
+/// pending, or once the drain timeout passed.
+///
+/// The background tasks keep running meanwhile, it is up to the caller to
+/// shut them down afterwards.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration and store
+pub async fn drain(ctx: &RelayerContext) -> DrainOutcome {
+    let timeout = Duration::from_millis(ctx.config.shutdown.drain_timeout);
+    if !ctx.start_draining() {
+        tracing::debug!("Already draining the transaction queues");
+    }
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut pending = 0;
+    loop {
+        match ctx.pending_tx_queue_items() {
+            Ok(0) => return DrainOutcome::Drained,
+            Ok(n) => pending = n,
+            Err(e) => {
+                tracing::warn!(%e, "Failed to count the pending queue items");
+            }
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return DrainOutcome::TimedOut { pending };
+        }
+        tracing::debug!(
+            pending,
+            "Draining the transaction queues, {}s left",
+            deadline
+                .saturating_duration_since(tokio::time::Instant::now())
+                .as_secs(),
+        );
+        tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+    }
+}
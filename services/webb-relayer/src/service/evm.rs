@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use axum::routing::{get, post};
 use axum::Router;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use webb::evm::ethers::prelude::TimeLag;
@@ -16,22 +17,34 @@ use webb_ew_evm::signature_bridge_watcher::{
 };
 use webb_ew_evm::vanchor::{
     VAnchorDepositHandler, VAnchorEncryptedOutputHandler, VAnchorLeavesHandler,
+    VAnchorMerkleRootsHandler, VAnchorNullifierHandler,
 };
 use webb_ew_evm::{VAnchorContractWatcher, VAnchorContractWrapper};
 use webb_proposal_signing_backends::queue::{self, policy};
+use webb_relayer_config::compression::CompressionConfig;
 use webb_relayer_config::evm::{
     Contract, SignatureBridgeContractConfig, SmartAnchorUpdatesConfig,
     VAnchorContractConfig,
 };
 use webb_relayer_context::RelayerContext;
 
-use webb_relayer_handlers::routes::fee_info::handle_evm_fee_info;
+use webb_relayer_handlers::routes::fee_info::{
+    handle_evm_fee_info, handle_evm_simulated_fee_info,
+    handle_evm_wrap_and_deposit_fee_info,
+};
 use webb_relayer_handlers::routes::{
-    encrypted_outputs, leaves, metric, private_tx_withdraw, transaction_status,
+    accounting, encrypted_outputs, estimate, jobs, leaves, metric,
+    private_tx_withdraw, roots, simulate, status, transaction_status,
+    wrap_and_deposit,
 };
 use webb_relayer_tx_queue::evm::TxQueue;
 
+use super::deployment::resolve_deployed_at;
+use super::discovery::start_anchor_discovery;
+use super::fee_harvester::start_fee_harvester;
+use super::make_bridge_registry_backend;
 use super::make_proposal_signing_backend;
+use super::treasury::start_treasury_sweep;
 use super::ProposalSigningBackendSelector;
 
 /// Type alias for providers
@@ -40,34 +53,93 @@ pub type Client = EthersClient;
 pub type TimeLagClient = EthersTimeLagClient;
 
 /// Setup and build all the EVM web services and handlers.
-pub fn build_web_services() -> Router<Arc<RelayerContext>> {
+///
+/// # Arguments
+///
+/// * `compression` - Which of the data query routes should compress their responses
+pub fn build_web_services(
+    compression: CompressionConfig,
+) -> Router<Arc<RelayerContext>> {
+    let compression_layer = CompressionLayer::new()
+        .gzip(compression.gzip)
+        .br(compression.brotli);
+    let mut leaves_route = get(leaves::handle_leaves_cache_evm);
+    if compression.leaves {
+        leaves_route = leaves_route.layer(compression_layer.clone());
+    }
+    let mut encrypted_outputs_route =
+        get(encrypted_outputs::handle_encrypted_outputs_cache_evm);
+    if compression.encrypted_outputs {
+        encrypted_outputs_route =
+            encrypted_outputs_route.layer(compression_layer);
+    }
     Router::new()
+        .route("/leaves/evm/:chain_id/:contract", leaves_route)
         .route(
-            "/leaves/evm/:chain_id/:contract",
-            get(leaves::handle_leaves_cache_evm),
+            "/roots/history/:chain_id/:contract",
+            get(roots::handle_roots_history_evm),
         )
         .route(
             "/send/evm/:chain_id/:contract",
             post(private_tx_withdraw::handle_private_tx_withdraw_evm),
         )
+        .route(
+            "/send/batch/evm/:chain_id",
+            post(private_tx_withdraw::handle_private_tx_withdraw_batch_evm),
+        )
+        .route(
+            "/send/wrap/evm/:chain_id/:contract",
+            post(wrap_and_deposit::handle_wrap_and_deposit_evm),
+        )
         .route(
             "/tx/evm/:chain_id/:item_key",
             get(transaction_status::handle_transaction_status_evm),
         )
+        .route(
+            "/tx/evm/:chain_id/batch/:batch_id",
+            get(transaction_status::handle_transaction_batch_status_evm),
+        )
+        .route("/status/evm/:chain_id", get(status::handle_evm_status))
+        .route("/jobs/:job_id", get(jobs::handle_relay_job_status))
+        .route("/accounting", get(accounting::handle_accounting))
+        .route(
+            "/txs",
+            get(transaction_status::handle_relayed_txs_by_recipient),
+        )
         .route(
             "/encrypted_outputs/evm/:chain_id/:contract_address",
-            get(encrypted_outputs::handle_encrypted_outputs_cache_evm),
+            encrypted_outputs_route,
         )
         .route(
             "/metrics/evm/:chain_id/:contract",
             get(metric::handle_evm_metric_info),
         )
+        .route(
+            "/metrics/resources",
+            get(metric::handle_resources_metric_info),
+        )
         // for backward compatibility
         .route("/metrics", get(metric::handle_metric_info))
         .route(
             "/fee_info/evm/:chain_id/:vanchor/:gas_amount",
             get(handle_evm_fee_info),
         )
+        .route(
+            "/fee_info/evm/:chain_id/:vanchor",
+            get(handle_evm_simulated_fee_info),
+        )
+        .route(
+            "/fee_info/wrap/evm/:chain_id/:vanchor",
+            get(handle_evm_wrap_and_deposit_fee_info),
+        )
+        .route(
+            "/estimate/evm/:chain_id",
+            post(estimate::handle_estimate_evm),
+        )
+        .route(
+            "/simulate/evm/:chain_id",
+            post(simulate::handle_simulate_evm),
+        )
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http())
 }
@@ -101,7 +173,10 @@ pub async fn ignite(
         );
 
         for contract in &chain_config.contracts {
-            match contract {
+            let mut contract = contract.clone();
+            resolve_deployed_at(&client, &store, chain_id, &mut contract)
+                .await?;
+            match &contract {
                 Contract::VAnchor(config) => {
                     start_vanchor_events_watcher(
                         ctx,
@@ -116,14 +191,32 @@ pub async fn ignite(
                     start_signature_bridge_events_watcher(
                         ctx,
                         config,
+                        chain_id,
                         timelag_client.clone(),
                         store.clone(),
                     )
                     .await?;
+                    start_anchor_discovery(
+                        ctx,
+                        config,
+                        chain_id,
+                        store.clone(),
+                    )
+                    .await?;
                 }
                 Contract::MaspVanchor(_) => todo!(),
             }
         }
+        // no transactions are sent to a chain that is only watched.
+        if chain_config.mode.is_watch_only() {
+            tracing::info!(
+                chain_id = %chain_config.chain_id,
+                "Chain is in watch-only mode, the tx queue is not started",
+            );
+            continue;
+        }
+        start_fee_harvester(ctx, chain_config, store.clone()).await?;
+        start_treasury_sweep(ctx, chain_config, store.clone()).await?;
         // start the transaction queue after starting other tasks.
         start_tx_queue(ctx.clone(), chain_config.chain_id, store.clone())?;
     }
@@ -140,7 +233,7 @@ pub async fn ignite(
 /// * `config` - VAnchor contract configuration
 /// * `client` - EVM Chain api client
 /// * `store` -[Sled](https://sled.rs)-based database store
-async fn start_vanchor_events_watcher(
+pub async fn start_vanchor_events_watcher(
     ctx: &RelayerContext,
     config: &VAnchorContractConfig,
     chain_id: u32,
@@ -154,247 +247,288 @@ async fn start_vanchor_events_watcher(
         );
         return Ok(());
     }
-    let wrapper = VAnchorContractWrapper::new(
-        config.clone(),
-        ctx.config.clone(), // the original config to access all networks.
-        client.clone(),
-    );
-    let mut shutdown_signal = ctx.shutdown_signal();
     let contract_address = config.common.address;
     let my_ctx = ctx.clone();
     let my_config = config.clone();
-    let task = async move {
-        tracing::debug!(
-            "VAnchor events watcher for ({}) Started.",
-            contract_address,
-        );
-        let contract_watcher = VAnchorContractWatcher::default();
-        let proposal_signing_backend = make_proposal_signing_backend(
-            &my_ctx,
-            store.clone(),
-            chain_id,
-            my_config.linked_anchors,
-            my_ctx.config.proposal_signing_backend.clone(),
-        )
-        .await?;
-        tracing::debug!(
-            %chain_id,
-            %contract_address,
-            "Fetching the Zero Hash from the contract",
-        );
-        let zero_hash = wrapper.contract.get_zero_hash(0).call().await?;
-        tracing::debug!(
-            %chain_id,
-            %contract_address,
-            %zero_hash,
-            "Found the Zero Hash",
+    let task = move || {
+        let my_ctx = my_ctx.clone();
+        let my_config = my_config.clone();
+        let client = client.clone();
+        let store = store.clone();
+        let wrapper = VAnchorContractWrapper::new(
+            my_config.clone(),
+            my_ctx.config.clone(), // the original config to access all networks.
+            client.clone(),
         );
-        let mut zero_hash_bytes = [0u8; 32];
-        zero_hash.to_big_endian(&mut zero_hash_bytes);
-
-        let proposals_queue = queue::mem::InMemoryProposalsQueue::new();
-        let time_delay_policy = {
-            let defaults = SmartAnchorUpdatesConfig::default();
-            let v = &my_config.smart_anchor_updates;
-            let initial_delay = v
-                .initial_time_delay
-                .or(defaults.initial_time_delay)
-                .expect("initial time delay is set by default");
-            let min_delay = v
-                .min_time_delay
-                .or(defaults.min_time_delay)
-                .expect("min time delay is set by default");
-            let max_delay = v
-                .max_time_delay
-                .or(defaults.max_time_delay)
-                .expect("max time delay is set by default");
-            let window_size = v
-                .time_delay_window_size
-                .or(defaults.time_delay_window_size)
-                .expect("time delay window size is set by default");
-
-            policy::TimeDelayPolicy::builder()
-                .initial_delay(initial_delay)
-                .min_delay(min_delay)
-                .max_delay(max_delay)
-                .window_size(window_size)
-                .build()
-        };
-
-        if my_config.smart_anchor_updates.enabled {
-            tracing::info!(
+        async move {
+            tracing::debug!(
+                "VAnchor events watcher for ({}) Started.",
+                contract_address,
+            );
+            let contract_watcher = VAnchorContractWatcher::default();
+            let src_resource_id = webb_proposals::ResourceId::new(
+                webb_proposals::TargetSystem::new_contract_address(
+                    contract_address.to_fixed_bytes(),
+                ),
+                webb_proposals::TypedChainId::Evm(chain_id),
+            );
+            let bridge_registry_backend =
+                make_bridge_registry_backend(&my_ctx).await?;
+            let proposal_signing_backend = make_proposal_signing_backend(
+                &my_ctx,
+                store.clone(),
+                chain_id,
+                src_resource_id,
+                my_config.linked_anchors.clone(),
+                bridge_registry_backend.clone(),
+                my_ctx.config.proposal_signing_backend.clone(),
+            )
+            .await?;
+            tracing::debug!(
                 %chain_id,
                 %contract_address,
-                "Smart Anchor Updates enabled",
+                "Fetching the Zero Hash from the contract",
             );
-        } else {
-            tracing::info!(
-                chain_id,
+            let zero_hash = wrapper.contract.get_zero_hash(0).call().await?;
+            tracing::debug!(
+                %chain_id,
                 %contract_address,
-                "Smart Anchor Updates disabled",
+                %zero_hash,
+                "Found the Zero Hash",
             );
-        }
+            let mut zero_hash_bytes = [0u8; 32];
+            zero_hash.to_big_endian(&mut zero_hash_bytes);
+            if my_ctx.config.peers.sync {
+                super::peer_sync::bootstrap_from_peers(
+                    &my_ctx,
+                    &store,
+                    &wrapper,
+                    chain_id,
+                    &zero_hash_bytes,
+                )
+                .await?;
+            }
 
-        let enqueue_policy = my_config.smart_anchor_updates.enabled.then_some(
-            (policy::AlwaysHigherNoncePolicy, time_delay_policy.clone()),
-        );
-        let dequeue_policy = my_config
-            .smart_anchor_updates
-            .enabled
-            .then_some(time_delay_policy);
+            let proposals_queue = queue::mem::InMemoryProposalsQueue::new();
+            let time_delay_policy = {
+                let defaults = SmartAnchorUpdatesConfig::default();
+                let v = &my_config.smart_anchor_updates;
+                let initial_delay = v
+                    .initial_time_delay
+                    .or(defaults.initial_time_delay)
+                    .expect("initial time delay is set by default");
+                let min_delay = v
+                    .min_time_delay
+                    .or(defaults.min_time_delay)
+                    .expect("min time delay is set by default");
+                let max_delay = v
+                    .max_time_delay
+                    .or(defaults.max_time_delay)
+                    .expect("max time delay is set by default");
+                let window_size = v
+                    .time_delay_window_size
+                    .or(defaults.time_delay_window_size)
+                    .expect("time delay window size is set by default");
 
-        let metrics = my_ctx.metrics.clone();
-        match proposal_signing_backend {
-            ProposalSigningBackendSelector::Dkg(backend) => {
-                let deposit_handler = VAnchorDepositHandler::builder()
-                    .chain_id(chain_id)
-                    .store(store.clone())
-                    .proposals_queue(proposals_queue.clone())
-                    .policy(enqueue_policy)
-                    .build();
-                let leaves_handler = VAnchorLeavesHandler::new(
-                    chain_id.into(),
-                    contract_address,
-                    store.clone(),
-                    zero_hash_bytes.to_vec(),
-                )?;
-                let encrypted_output_handler =
-                    VAnchorEncryptedOutputHandler::new(chain_id.into());
-                let vanchor_watcher_task = contract_watcher.run(
-                    client,
-                    store,
-                    wrapper,
-                    vec![
-                        Box::new(deposit_handler),
-                        Box::new(leaves_handler),
-                        Box::new(encrypted_output_handler),
-                    ],
-                    &my_ctx,
-                );
+                policy::TimeDelayPolicy::builder()
+                    .initial_delay(initial_delay)
+                    .min_delay(min_delay)
+                    .max_delay(max_delay)
+                    .window_size(window_size)
+                    .build()
+            };
 
-                let proposals_queue_task = queue::run(
-                    proposals_queue,
-                    dequeue_policy,
-                    backend,
-                    metrics,
+            if my_config.smart_anchor_updates.enabled {
+                tracing::info!(
+                    %chain_id,
+                    %contract_address,
+                    "Smart Anchor Updates enabled",
+                );
+            } else {
+                tracing::info!(
+                    chain_id,
+                    %contract_address,
+                    "Smart Anchor Updates disabled",
                 );
+            }
+
+            let enqueue_policy =
+                my_config.smart_anchor_updates.enabled.then_some((
+                    policy::AlwaysHigherNoncePolicy,
+                    time_delay_policy.clone(),
+                ));
+            let dequeue_policy = my_config
+                .smart_anchor_updates
+                .enabled
+                .then_some(time_delay_policy);
+
+            let metrics = my_ctx.metrics.clone();
+            match proposal_signing_backend {
+                ProposalSigningBackendSelector::Dkg(backend) => {
+                    let deposit_handler = VAnchorDepositHandler::builder()
+                        .chain_id(chain_id)
+                        .store(store.clone())
+                        .proposals_queue(proposals_queue.clone())
+                        .policy(enqueue_policy)
+                        .bridge_registry_backend(
+                            bridge_registry_backend.clone(),
+                        )
+                        .build();
+                    let leaves_handler = VAnchorLeavesHandler::new(
+                        chain_id.into(),
+                        contract_address,
+                        store.clone(),
+                        zero_hash_bytes.to_vec(),
+                        my_config.leaves_flush_policy,
+                    )?;
+                    let encrypted_output_handler =
+                        VAnchorEncryptedOutputHandler::new(chain_id.into());
+                    let nullifier_handler =
+                        VAnchorNullifierHandler::new(chain_id.into());
+                    let merkle_roots_handler =
+                        VAnchorMerkleRootsHandler::new(chain_id.into());
+                    let vanchor_watcher_task = contract_watcher.run(
+                        client,
+                        store,
+                        wrapper,
+                        vec![
+                            Box::new(deposit_handler),
+                            Box::new(leaves_handler),
+                            Box::new(encrypted_output_handler),
+                            Box::new(nullifier_handler),
+                            Box::new(merkle_roots_handler),
+                        ],
+                        &my_ctx,
+                    );
+
+                    let proposals_queue_task = queue::run(
+                        proposals_queue,
+                        dequeue_policy,
+                        backend,
+                        metrics,
+                        my_ctx.leadership().clone(),
+                        my_ctx.pause_switch().clone(),
+                    );
 
-                tokio::select! {
-                    _ = proposals_queue_task => {
-                        tracing::warn!(
-                            "Proposals queue task stopped for ({})",
-                            contract_address,
-                        );
-                    },
-                    _ = vanchor_watcher_task => {
-                        tracing::warn!(
-                            "VAnchor watcher task stopped for ({})",
-                            contract_address,
-                        );
-                    },
-                    _ = shutdown_signal.recv() => {
-                        tracing::trace!(
-                            "Stopping VAnchor watcher for ({})",
-                            contract_address,
-                        );
-                    },
+                    tokio::select! {
+                        _ = proposals_queue_task => {
+                            tracing::warn!(
+                                "Proposals queue task stopped for ({})",
+                                contract_address,
+                            );
+                        },
+                        _ = vanchor_watcher_task => {
+                            tracing::warn!(
+                                "VAnchor watcher task stopped for ({})",
+                                contract_address,
+                            );
+                        },
+                    }
                 }
-            }
-            ProposalSigningBackendSelector::Mocked(backend) => {
-                let deposit_handler = VAnchorDepositHandler::builder()
-                    .chain_id(chain_id)
-                    .store(store.clone())
-                    .proposals_queue(proposals_queue.clone())
-                    .policy(enqueue_policy)
-                    .build();
-                let leaves_handler = VAnchorLeavesHandler::new(
-                    chain_id.into(),
-                    contract_address,
-                    store.clone(),
-                    zero_hash_bytes.to_vec(),
-                )?;
-                let encrypted_output_handler =
-                    VAnchorEncryptedOutputHandler::new(chain_id.into());
-                let vanchor_watcher_task = contract_watcher.run(
-                    client,
-                    store,
-                    wrapper,
-                    vec![
-                        Box::new(deposit_handler),
-                        Box::new(leaves_handler),
-                        Box::new(encrypted_output_handler),
-                    ],
-                    &my_ctx,
-                );
+                ProposalSigningBackendSelector::Mocked(backend) => {
+                    let deposit_handler = VAnchorDepositHandler::builder()
+                        .chain_id(chain_id)
+                        .store(store.clone())
+                        .proposals_queue(proposals_queue.clone())
+                        .policy(enqueue_policy)
+                        .bridge_registry_backend(
+                            bridge_registry_backend.clone(),
+                        )
+                        .build();
+                    let leaves_handler = VAnchorLeavesHandler::new(
+                        chain_id.into(),
+                        contract_address,
+                        store.clone(),
+                        zero_hash_bytes.to_vec(),
+                        my_config.leaves_flush_policy,
+                    )?;
+                    let encrypted_output_handler =
+                        VAnchorEncryptedOutputHandler::new(chain_id.into());
+                    let nullifier_handler =
+                        VAnchorNullifierHandler::new(chain_id.into());
+                    let merkle_roots_handler =
+                        VAnchorMerkleRootsHandler::new(chain_id.into());
+                    let vanchor_watcher_task = contract_watcher.run(
+                        client,
+                        store,
+                        wrapper,
+                        vec![
+                            Box::new(deposit_handler),
+                            Box::new(leaves_handler),
+                            Box::new(encrypted_output_handler),
+                            Box::new(nullifier_handler),
+                            Box::new(merkle_roots_handler),
+                        ],
+                        &my_ctx,
+                    );
 
-                let proposals_queue_task = queue::run(
-                    proposals_queue,
-                    dequeue_policy,
-                    backend,
-                    metrics,
-                );
+                    let proposals_queue_task = queue::run(
+                        proposals_queue,
+                        dequeue_policy,
+                        backend,
+                        metrics,
+                        my_ctx.leadership().clone(),
+                        my_ctx.pause_switch().clone(),
+                    );
 
-                tokio::select! {
-                    _ = proposals_queue_task => {
-                        tracing::warn!(
-                            "Proposals queue task stopped for ({})",
-                            contract_address,
-                        );
-                    },
-                    _ = vanchor_watcher_task => {
-                        tracing::warn!(
-                            "VAnchor watcher task stopped for ({})",
-                            contract_address,
-                        );
-                    },
-                    _ = shutdown_signal.recv() => {
-                        tracing::trace!(
-                            "Stopping VAnchor watcher for ({})",
-                            contract_address,
-                        );
-                    },
+                    tokio::select! {
+                        _ = proposals_queue_task => {
+                            tracing::warn!(
+                                "Proposals queue task stopped for ({})",
+                                contract_address,
+                            );
+                        },
+                        _ = vanchor_watcher_task => {
+                            tracing::warn!(
+                                "VAnchor watcher task stopped for ({})",
+                                contract_address,
+                            );
+                        },
+                    }
                 }
-            }
-            ProposalSigningBackendSelector::None => {
-                let leaves_handler = VAnchorLeavesHandler::new(
-                    chain_id.into(),
-                    contract_address,
-                    store.clone(),
-                    zero_hash_bytes.to_vec(),
-                )?;
-                let encrypted_output_handler =
-                    VAnchorEncryptedOutputHandler::new(chain_id.into());
-                let vanchor_watcher_task = contract_watcher.run(
-                    client,
-                    store,
-                    wrapper,
-                    vec![
-                        Box::new(leaves_handler),
-                        Box::new(encrypted_output_handler),
-                    ],
-                    &my_ctx,
-                );
-                tokio::select! {
-                    _ = vanchor_watcher_task => {
-                        tracing::warn!(
-                            "VAnchor watcher task stopped for ({})",
-                            contract_address,
-                        );
-                    },
-                    _ = shutdown_signal.recv() => {
-                        tracing::trace!(
-                            "Stopping VAnchor watcher for ({})",
-                            contract_address,
-                        );
-                    },
+                ProposalSigningBackendSelector::None => {
+                    let leaves_handler = VAnchorLeavesHandler::new(
+                        chain_id.into(),
+                        contract_address,
+                        store.clone(),
+                        zero_hash_bytes.to_vec(),
+                        my_config.leaves_flush_policy,
+                    )?;
+                    let encrypted_output_handler =
+                        VAnchorEncryptedOutputHandler::new(chain_id.into());
+                    let nullifier_handler =
+                        VAnchorNullifierHandler::new(chain_id.into());
+                    let merkle_roots_handler =
+                        VAnchorMerkleRootsHandler::new(chain_id.into());
+                    let vanchor_watcher_task = contract_watcher.run(
+                        client,
+                        store,
+                        wrapper,
+                        vec![
+                            Box::new(leaves_handler),
+                            Box::new(encrypted_output_handler),
+                            Box::new(nullifier_handler),
+                            Box::new(merkle_roots_handler),
+                        ],
+                        &my_ctx,
+                    );
+                    tokio::select! {
+                        _ = vanchor_watcher_task => {
+                            tracing::warn!(
+                                "VAnchor watcher task stopped for ({})",
+                                contract_address,
+                            );
+                        },
+                    }
                 }
-            }
-        };
+            };
 
-        crate::Result::Ok(())
+            crate::Result::Ok(())
+        }
     };
     // kick off the watcher.
-    tokio::task::spawn(task);
+    ctx.supervisor()
+        .spawn(format!("evm:{chain_id}:vanchor:{contract_address:?}"), task);
     Ok(())
 }
 
@@ -402,6 +536,7 @@ async fn start_vanchor_events_watcher(
 pub async fn start_signature_bridge_events_watcher(
     ctx: &RelayerContext,
     config: &SignatureBridgeContractConfig,
+    chain_id: u32,
     client: Arc<TimeLagClient>,
     store: Arc<super::Store>,
 ) -> crate::Result<()> {
@@ -412,59 +547,73 @@ pub async fn start_signature_bridge_events_watcher(
         );
         return Ok(());
     }
-    let mut shutdown_signal = ctx.shutdown_signal();
     let contract_address = config.common.address;
 
     let wrapper =
         SignatureBridgeContractWrapper::new(config.clone(), client.clone());
     let metrics = ctx.metrics.clone();
     let my_ctx = ctx.clone();
-    let task = async move {
-        tracing::debug!(
-            "Signature Bridge watcher for ({}) Started.",
-            contract_address
-        );
-        let bridge_contract_watcher = SignatureBridgeContractWatcher::default();
-        let governance_transfer_handler =
-            SignatureBridgeGovernanceOwnershipTransferredHandler::default();
-        let events_watcher_task = EventWatcher::run(
-            &bridge_contract_watcher,
-            client.clone(),
-            store.clone(),
-            wrapper.clone(),
-            vec![Box::new(governance_transfer_handler)],
-            &my_ctx,
-        );
-        let cmd_handler_task = BridgeWatcher::run(
-            &bridge_contract_watcher,
-            client,
-            store,
-            wrapper,
-            metrics.clone(),
-        );
-        tokio::select! {
-            _ = events_watcher_task => {
-                tracing::warn!(
-                    "signature bridge events watcher task stopped for ({})",
-                    contract_address
-                );
-            },
-            _ = cmd_handler_task => {
+    let task = move || {
+        let my_ctx = my_ctx.clone();
+        let wrapper = wrapper.clone();
+        let client = client.clone();
+        let store = store.clone();
+        let metrics = metrics.clone();
+        async move {
+            tracing::debug!(
+                "Signature Bridge watcher for ({}) Started.",
+                contract_address
+            );
+            // the handlers reuse the chain id, it is queried again later
+            // if this fails.
+            if let Err(e) = wrapper.chain_id().await {
                 tracing::warn!(
-                    "signature bridge cmd handler task stopped for ({})",
-                    contract_address
-                );
-            },
-            _ = shutdown_signal.recv() => {
-                tracing::trace!(
-                    "Stopping Signature Bridge watcher for ({})",
-                    contract_address,
+                    error = %e,
+                    "Failed to query the chain id of the signature bridge",
                 );
-            },
+            }
+            let bridge_contract_watcher =
+                SignatureBridgeContractWatcher::default();
+            let governance_transfer_handler =
+                SignatureBridgeGovernanceOwnershipTransferredHandler::default();
+            let events_watcher_task = EventWatcher::run(
+                &bridge_contract_watcher,
+                client.clone(),
+                store.clone(),
+                wrapper.clone(),
+                vec![Box::new(governance_transfer_handler)],
+                &my_ctx,
+            );
+            let cmd_handler_task = BridgeWatcher::run(
+                &bridge_contract_watcher,
+                client,
+                store,
+                wrapper,
+                metrics.clone(),
+                my_ctx.leadership().clone(),
+            );
+            tokio::select! {
+                _ = events_watcher_task => {
+                    tracing::warn!(
+                        "signature bridge events watcher task stopped for ({})",
+                        contract_address
+                    );
+                },
+                _ = cmd_handler_task => {
+                    tracing::warn!(
+                        "signature bridge cmd handler task stopped for ({})",
+                        contract_address
+                    );
+                },
+            }
+            crate::Result::Ok(())
         }
     };
     // kick off the watcher.
-    tokio::task::spawn(task);
+    ctx.supervisor().spawn(
+        format!("evm:{chain_id}:signature-bridge:{contract_address:?}"),
+        task,
+    );
     Ok(())
 }
 
@@ -490,27 +639,19 @@ pub fn start_tx_queue(
         return Ok(());
     }
 
-    let mut shutdown_signal = ctx.shutdown_signal();
+    let supervisor = ctx.supervisor().clone();
     let tx_queue = TxQueue::new(ctx, chain_id.into(), store);
 
     tracing::debug!("Transaction Queue for ({}) Started.", chain_id);
-    let task = async move {
-        tokio::select! {
-            _ = tx_queue.run() => {
-                tracing::warn!(
-                    "Transaction Queue task stopped for ({})",
-                    chain_id,
-                );
-            },
-            _ = shutdown_signal.recv() => {
-                tracing::trace!(
-                    "Stopping Transaction Queue for ({})",
-                    chain_id,
-                );
-            },
+    let task = move || {
+        let tx_queue = tx_queue.clone();
+        async move {
+            let result = tx_queue.run().await;
+            tracing::warn!("Transaction Queue task stopped for ({})", chain_id,);
+            result
         }
     };
     // kick off the tx_queue.
-    tokio::task::spawn(task);
+    supervisor.spawn(format!("evm:{chain_id}:tx-queue"), task);
     Ok(())
 }
@@ -0,0 +1,222 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Fee Harvesting Service 🌾
+//!
+//! Relayed withdrawals pay the relayer its fee in the wrapped token of the
+//! anchor. This service periodically sweeps the accumulated fees from the
+//! relayer's hot wallet to the configured recipient once their balance
+//! exceeds the threshold, sending the transactions through the EVM tx queue,
+//! which records their cost in the accounting ledger of the token wrapper.
+//! Optionally, the fees are unwrapped into the underlying asset on the way,
+//! so the operator's revenue ends up in a liquid asset.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethereum_types::{Address, U256};
+use webb::evm::ethers::abi::parse_abi;
+use webb::evm::ethers::contract::Contract;
+use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
+use webb::evm::ethers::signers::Signer;
+use webb::evm::ethers::utils::parse_units;
+use webb_relayer_config::evm::{Contract as ContractConfig, EvmChainConfig};
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::queue::{
+    QueueItem, QueueStore, TransactionQueueItemKey,
+};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_types::EthersClient;
+
+/// The subset of the VAnchor ABI used for harvesting.
+const VANCHOR_ABI: &[&str] =
+    &["function token() external view returns (address)"];
+
+/// The subset of the Token Wrapper ABI used for harvesting.
+const TOKEN_WRAPPER_ABI: &[&str] = &[
+    "function balanceOf(address account) external view returns (uint256)",
+    "function decimals() external view returns (uint8)",
+    "function transfer(address to, uint256 amount) external returns (bool)",
+    "function unwrapAndSendTo(address tokenAddress, uint256 amount, address recipient) external",
+];
+
+/// Starts the fee harvesting task for the given chain.
+///
+/// Returns Ok(()) if successful, or an error if not.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `chain_config` - The configuration of the chain
+/// * `store` -[Sled](https://sled.rs)-based database store
+pub async fn start_fee_harvester(
+    ctx: &RelayerContext,
+    chain_config: &EvmChainConfig,
+    store: Arc<super::Store>,
+) -> crate::Result<()> {
+    if !chain_config.fee_harvesting.enabled {
+        return Ok(());
+    }
+    let chain_id = chain_config.chain_id;
+    let Some(recipient) = chain_config.fee_harvesting.recipient else {
+        tracing::warn!(
+            %chain_id,
+            "Fee harvesting is disabled, no recipient is configured",
+        );
+        return Ok(());
+    };
+    let relayer = ctx.evm_wallet(chain_id).await?.address();
+    // the fees are paid to the beneficiary, which the relayer can't unwrap.
+    if matches!(chain_config.beneficiary, Some(b) if b != relayer) {
+        tracing::warn!(
+            %chain_id,
+            "Fee harvesting is disabled, fees are paid to the beneficiary",
+        );
+        return Ok(());
+    }
+    let client = ctx.evm_provider(chain_id).await?;
+    let my_ctx = ctx.clone();
+    let my_config = chain_config.clone();
+    let task = move || {
+        run_fee_harvester(
+            my_ctx.clone(),
+            my_config.clone(),
+            relayer,
+            recipient,
+            client.clone(),
+            store.clone(),
+        )
+    };
+    // kick off the harvester.
+    ctx.supervisor()
+        .spawn(format!("evm:{chain_id}:fee-harvester"), task);
+    Ok(())
+}
+
+/// Periodically harvests the fees of every wrapped token on the chain.
+async fn run_fee_harvester(
+    ctx: RelayerContext,
+    chain_config: EvmChainConfig,
+    relayer: Address,
+    recipient: Address,
+    client: Arc<EthersClient>,
+    store: Arc<super::Store>,
+) -> crate::Result<()> {
+    let chain_id = chain_config.chain_id;
+    let interval = Duration::from_millis(chain_config.fee_harvesting.interval);
+    tracing::debug!(%chain_id, "Fee harvester Started.");
+    loop {
+        webb_relayer_utils::heartbeat::beat();
+        // only the leader harvests, so replicas do not enqueue it twice.
+        if ctx.leadership().is_leader() {
+            let result = harvest_fees(
+                &chain_config,
+                relayer,
+                recipient,
+                client.clone(),
+                store.as_ref(),
+            )
+            .await;
+            if let Err(e) = result {
+                tracing::warn!(
+                    %chain_id,
+                    error = %e,
+                    "Fee harvesting failed, will retry later",
+                );
+            }
+        }
+        webb_relayer_utils::heartbeat::sleep(interval).await;
+    }
+}
+
+/// Enqueues a transfer (or an unwrap) to the recipient for every wrapped
+/// token of the chain's anchors whose fee balance exceeds the threshold.
+async fn harvest_fees(
+    chain_config: &EvmChainConfig,
+    relayer: Address,
+    recipient: Address,
+    client: Arc<EthersClient>,
+    store: &super::Store,
+) -> crate::Result<()> {
+    let chain_id = chain_config.chain_id;
+    let config = chain_config.fee_harvesting;
+    let anchors = chain_config.contracts.iter().filter_map(|c| match c {
+        ContractConfig::VAnchor(c) => Some(c.common.address),
+        _ => None,
+    });
+    let mut tokens = HashSet::new();
+    for anchor in anchors {
+        let vanchor = Contract::new(
+            anchor,
+            parse_abi(VANCHOR_ABI).expect("vanchor abi is valid"),
+            client.clone(),
+        );
+        let token = vanchor.method::<_, Address>("token", ())?.call().await?;
+        tokens.insert(token);
+    }
+
+    for token in tokens {
+        let wrapper = Contract::new(
+            token,
+            parse_abi(TOKEN_WRAPPER_ABI).expect("token wrapper abi is valid"),
+            client.clone(),
+        );
+        let balance = wrapper
+            .method::<_, U256>("balanceOf", relayer)?
+            .call()
+            .await?;
+        let decimals = wrapper.method::<_, u8>("decimals", ())?.call().await?;
+        let threshold: U256 =
+            parse_units(config.threshold, u32::from(decimals))?.into();
+        if balance.is_zero() || balance < threshold {
+            continue;
+        }
+        let typed_tx: TypedTransaction = if config.unwrap {
+            let unwrap_into = config.unwrap_into.unwrap_or_else(Address::zero);
+            wrapper
+                .method::<_, ()>(
+                    "unwrapAndSendTo",
+                    (unwrap_into, balance, recipient),
+                )?
+                .tx
+        } else {
+            wrapper
+                .method::<_, bool>("transfer", (recipient, balance))?
+                .tx
+        };
+        let tx_key = SledQueueKey::from_evm_with_custom_key(
+            chain_id,
+            typed_tx.item_key(),
+        );
+        // the previous harvest is still in the queue.
+        if QueueStore::<TypedTransaction>::has_item(store, tx_key)? {
+            continue;
+        }
+        QueueStore::<TypedTransaction>::enqueue_item(
+            store,
+            tx_key,
+            QueueItem::new(typed_tx),
+        )?;
+        tracing::info!(
+            %chain_id,
+            %token,
+            %balance,
+            %recipient,
+            unwrap = config.unwrap,
+            "Enqueued fee harvesting transaction",
+        );
+    }
+    Ok(())
+}
@@ -25,26 +25,68 @@ use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use axum::routing::get;
-use axum::Router;
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::map_response;
+use axum::response::Response;
+use axum::routing::{get, post, put};
+use axum::{Extension, Router};
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
+use webb::evm::ethers::signers::Signer;
+use webb_bridge_registry_backends::{
+    BridgeRegistryBackend, EvmBridgeRegistryBackend,
+    MockedBridgeRegistryBackend,
+};
 use webb_proposal_signing_backends::SigningRulesContractWrapper;
 use webb_proposal_signing_backends::{
     DkgProposalSigningRulesBackend, MockedProposalSigningBackend,
 };
 use webb_relayer_config::anchor::LinkedAnchorConfig;
 
+use webb_relayer_config::bridge_registry_backend::BridgeRegistryBackendConfig;
 use webb_relayer_config::signing_backend::ProposalSigningBackendConfig;
 use webb_relayer_context::RelayerContext;
-use webb_relayer_handlers::routes::info::handle_relayer_info;
+use webb_relayer_handlers::routes::admin::{
+    handle_config_schema, handle_disable_chain, handle_enable_chain,
+    handle_list_chains, handle_log_filter, handle_pause, handle_resume,
+    handle_resync_leaves, handle_set_log_filter,
+};
+use webb_relayer_handlers::routes::health::{
+    handle_health, handle_health_ready,
+};
 use webb_relayer_handlers::routes::info::handle_socket_info;
+use webb_relayer_handlers::routes::info::{
+    handle_relayer_info, RelayerAccounts,
+};
+use webb_relayer_handlers::routes::jobs::handle_tangle_job_status;
 use webb_relayer_store::SledStore;
 
+/// Contract deployment block detection
+pub mod deployment;
+/// Anchor auto-discovery Services
+pub mod discovery;
+/// Transaction queues drain on shutdown
+pub mod drain;
 /// EVM Specific Services
 pub mod evm;
+/// Fee harvesting Services
+pub mod fee_harvester;
+/// Peer relayers sync Services
+pub mod peer_sync;
+/// Probe events export Services
+pub mod probe_export;
+/// On-chain relayer registry Services
+pub mod registry;
+/// Data retention Services
+pub mod retention;
+/// Store metrics sampling Services
+pub mod store_metrics;
 /// Substrate Specific Services
 pub mod tangle;
+/// Treasury sweep Services
+pub mod treasury;
+/// systemd Watchdog integration
+pub mod watchdog;
 
 /// Type alias for [Sled](https://sled.rs)-based database store
 pub type Store = SledStore;
@@ -57,15 +99,48 @@ pub type Store = SledStore;
 /// * `ctx` - RelayContext reference that holds the configuration and database
 pub async fn build_web_services(ctx: RelayerContext) -> crate::Result<()> {
     let socket_addr = SocketAddr::new([0, 0, 0, 0].into(), ctx.config.port);
+    let accounts = Arc::new(RelayerAccounts::load(&ctx).await);
     let api = Router::new()
         .route("/ip", get(handle_socket_info))
         .route("/info", get(handle_relayer_info))
+        .route("/health", get(handle_health))
+        .route("/health/ready", get(handle_health_ready))
+        .route("/admin/chains", get(handle_list_chains))
+        .route(
+            "/admin/chains/:chain_id/disable",
+            post(handle_disable_chain),
+        )
+        .route("/admin/chains/:chain_id/enable", post(handle_enable_chain))
+        .route("/admin/pause", post(handle_pause))
+        .route("/admin/resume", post(handle_resume))
+        .route(
+            "/admin/log-filter",
+            get(handle_log_filter).put(handle_set_log_filter),
+        )
+        .route("/admin/config/schema", get(handle_config_schema))
+        .route(
+            "/admin/resync/:chain_id/:contract",
+            post(handle_resync_leaves),
+        )
+        .route(
+            "/tangle/:chain_id/jobs/:job_id",
+            get(handle_tangle_job_status),
+        )
+        .layer(Extension(accounts))
         .layer(TraceLayer::new_for_http())
         .layer(CorsLayer::permissive())
-        .merge(evm::build_web_services());
+        .merge(evm::build_web_services(ctx.config.compression));
 
+    let identity_headers = relayer_identity_headers(&ctx).await;
     let app = Router::new()
         .nest("/api/v1", api)
+        .layer(map_response(move |mut res: Response| {
+            let identity_headers = identity_headers.clone();
+            async move {
+                res.headers_mut().extend(identity_headers);
+                res
+            }
+        }))
         .with_state(Arc::new(ctx))
         .into_make_service_with_connect_info::<SocketAddr>();
 
@@ -74,6 +149,30 @@ pub async fn build_web_services(ctx: RelayerContext) -> crate::Result<()> {
     Ok(())
 }
 
+/// Returns the headers that identify the relayer in every response:
+/// `X-Relayer-Version`, and `X-Relayer-Id`, the address of the identity key
+/// of the relayer if it has one configured.
+async fn relayer_identity_headers(
+    ctx: &RelayerContext,
+) -> Vec<(HeaderName, HeaderValue)> {
+    let mut headers = vec![(
+        HeaderName::from_static("x-relayer-version"),
+        HeaderValue::from_static(env!("CARGO_PKG_VERSION")),
+    )];
+    if let Some(wallet) = ctx.identity_wallet().await {
+        let id = format!("{:?}", wallet.address());
+        if let Ok(id) = HeaderValue::from_str(&id) {
+            headers.push((HeaderName::from_static("x-relayer-id"), id));
+        }
+    } else {
+        tracing::warn!(
+            "No EVM chain has a key configured, responses will not carry \
+            the X-Relayer-Id header",
+        );
+    }
+    headers
+}
+
 /// Starts all background services for all chains configured in the config file.
 ///
 /// Returns a future that resolves when all services are started successfully.
@@ -90,6 +189,11 @@ pub async fn ignite(
         "Relayer configuration: {}",
         serde_json::to_string_pretty(&ctx.config)?
     );
+    // elect the leader before anything could sign or submit proposals.
+    webb_relayer_context::leader_election::start(&ctx);
+    store_metrics::start_store_metrics_sampler(&ctx, store.clone());
+    retention::start_retention_purger(&ctx, store.clone());
+    registry::start_registry_heartbeat(&ctx, store.clone());
     evm::ignite(&ctx, store.clone()).await?;
     tangle::ignite(ctx.clone(), store.clone()).await?;
     Ok(())
@@ -105,12 +209,31 @@ pub enum ProposalSigningBackendSelector {
     /// Dkg
     Dkg(DkgProposalSigningRulesBackend),
 }
+/// utility to configure bridge registry backend
+pub async fn make_bridge_registry_backend(
+    ctx: &RelayerContext,
+) -> crate::Result<Arc<dyn BridgeRegistryBackend>> {
+    match &ctx.config.bridge_registry_backend {
+        Some(BridgeRegistryBackendConfig::Evm(config)) => {
+            // read the linked anchors from the registry contract.
+            let client = ctx.evm_provider(config.chain_id).await?;
+            let backend = EvmBridgeRegistryBackend::new(config.address, client);
+            Ok(Arc::new(backend))
+        }
+        Some(BridgeRegistryBackendConfig::Mocked) | None => {
+            Ok(Arc::new(MockedBridgeRegistryBackend))
+        }
+    }
+}
+
 /// utility to configure proposal signing backend
 pub async fn make_proposal_signing_backend(
     ctx: &RelayerContext,
     store: Arc<Store>,
     chain_id: u32,
+    src_resource_id: webb_proposals::ResourceId,
     linked_anchors: Option<Vec<LinkedAnchorConfig>>,
+    bridge_registry_backend: Arc<dyn BridgeRegistryBackend>,
     proposal_signing_backend: Option<ProposalSigningBackendConfig>,
 ) -> crate::Result<ProposalSigningBackendSelector> {
     // Check if contract is configured with governance support for the relayer.
@@ -137,36 +260,25 @@ pub async fn make_proposal_signing_backend(
         Some(ProposalSigningBackendConfig::Mocked(mocked)) => {
             // if it is the mocked backend, we will use the MockedProposalSigningBackend to sign the proposal.
             // which is a bit simpler than the DkgProposalSigningRulesBackend.
-            // get only the linked chains to that anchor.
-            let mut signature_bridges: HashSet<webb_proposals::ResourceId> =
-                HashSet::new();
-
-            // Check if linked anchors are provided.
-            let linked_anchors = match linked_anchors {
-                Some(anchors) => {
-                    if anchors.is_empty() {
-                        tracing::warn!("Misconfigured Network: Linked anchors cannot be empty for governance relaying");
-                        return Ok(ProposalSigningBackendSelector::None);
-                    } else {
-                        anchors
-                    }
-                }
-                None => {
+            // get only the linked chains to that anchor, either from the config or the bridge registry.
+            let linked_anchors = match bridge_registry_backend
+                .config_or_registry_bridges(&linked_anchors, &src_resource_id)
+                .await
+            {
+                Ok(anchors) => anchors,
+                Err(webb_relayer_utils::Error::BridgeNotRegistered(_)) => {
                     tracing::warn!("Misconfigured Network: Linked anchors must be configured for governance relaying");
                     return Ok(ProposalSigningBackendSelector::None);
                 }
+                Err(e) => return Err(e),
             };
-            linked_anchors.iter().for_each(|anchor| {
-                // using chain_id to ensure that we have only one signature bridge
-                let resource_id = match anchor {
-                    LinkedAnchorConfig::Raw(target) => {
-                        let bytes: [u8; 32] = target.resource_id.into();
-                        webb_proposals::ResourceId::from(bytes)
-                    }
-                    _ => unreachable!("unsupported"),
-                };
-                signature_bridges.insert(resource_id);
-            });
+            if linked_anchors.is_empty() {
+                tracing::warn!("Misconfigured Network: Linked anchors cannot be empty for governance relaying");
+                return Ok(ProposalSigningBackendSelector::None);
+            }
+            // using a set to ensure that we have only one signature bridge per resource id.
+            let signature_bridges: HashSet<webb_proposals::ResourceId> =
+                linked_anchors.into_iter().collect();
             let backend = MockedProposalSigningBackend::builder()
                 .store(store.clone())
                 .private_key(mocked.private_key)
@@ -0,0 +1,239 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Peer Sync 🤝
+//!
+//! Bootstraps the empty leaves caches of the VAnchor contracts from the
+//! configured peer relayers, so a fresh relayer does not have to replay the
+//! whole history of the chain before serving data queries.
+//!
+//! The leaves served by a peer are only accepted if they are the whole tree
+//! of the contract on chain at the block the peer queried them at, as the
+//! events watcher resumes from that block. The encrypted outputs come from
+//! the same peer and can not be checked, a wrong one only fails to decrypt.
+
+use std::time::Duration;
+
+use ethereum_types::{Address, H256};
+use webb::evm::ethers::types::BlockId;
+use serde::Deserialize;
+use webb_ew_evm::vanchor::compute_merkle_root;
+use webb::evm::contract::protocol_solidity::variable_anchor::VAnchorContract;
+use webb_ew_evm::VAnchorContractWrapper;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::{
+    EncryptedOutputCacheStore, HistoryStore, LeafCacheStore, SledStore,
+};
+
+use super::evm::TimeLagClient;
+
+/// The leaves of a contract, as served by a peer.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PeerLeaves {
+    leaves: Vec<H256>,
+    last_queried_block: u64,
+}
+
+/// The encrypted outputs of a contract, as served by a peer.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PeerEncryptedOutputs {
+    encrypted_outputs: Vec<String>,
+}
+
+/// A verified snapshot of the caches of a contract.
+struct Snapshot {
+    leaves: Vec<(u32, Vec<u8>)>,
+    encrypted_outputs: Vec<(u32, Vec<u8>)>,
+    last_deposit_block: u64,
+}
+
+/// Bootstraps the leaves and encrypted outputs caches of the contract from
+/// the first peer that serves leaves matching a root known on chain.
+///
+/// Does nothing if the leaves cache of the contract is not empty. The events
+/// watcher then resumes from the last deposit block of the snapshot.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `store` -[Sled](https://sled.rs)-based database store
+/// * `wrapper` - The wrapper of the VAnchor contract
+/// * `chain_id` - An u32 representing the chain id of the chain
+/// * `empty_leaf` - The zero hash of the tree of the contract
+pub async fn bootstrap_from_peers(
+    ctx: &RelayerContext,
+    store: &SledStore,
+    wrapper: &VAnchorContractWrapper<TimeLagClient>,
+    chain_id: u32,
+    empty_leaf: &[u8],
+) -> crate::Result<()> {
+    let contract_address = wrapper.contract.address();
+    let resource_id = ResourceId::new(
+        TargetSystem::new_contract_address(contract_address.to_fixed_bytes()),
+        TypedChainId::Evm(chain_id),
+    );
+    // only empty caches are bootstrapped, the others catch up from the chain.
+    if store.get_leaves(resource_id)?.into_iter().next().is_some() {
+        return Ok(());
+    }
+    let peers_config = &ctx.config.peers;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(peers_config.request_timeout))
+        .build()?;
+    for peer in &peers_config.urls {
+        let snapshot = fetch_verified_snapshot(
+            ctx,
+            &client,
+            peer,
+            chain_id,
+            contract_address,
+            empty_leaf,
+        )
+        .await;
+        match snapshot {
+            Ok(snapshot) => {
+                store.insert_leaves_and_last_deposit_block_number(
+                    resource_id,
+                    &snapshot.leaves,
+                    snapshot.last_deposit_block,
+                )?;
+                store.insert_encrypted_output_and_last_deposit_block_number(
+                    resource_id,
+                    &snapshot.encrypted_outputs,
+                    snapshot.last_deposit_block,
+                )?;
+                store.set_last_block_number(
+                    resource_id,
+                    snapshot.last_deposit_block,
+                )?;
+                tracing::info!(
+                    %chain_id,
+                    %contract_address,
+                    %peer,
+                    leaves = snapshot.leaves.len(),
+                    block_number = snapshot.last_deposit_block,
+                    "Bootstrapped the leaves cache from a peer",
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                tracing::warn!(
+                    %chain_id,
+                    %contract_address,
+                    %peer,
+                    error = %e,
+                    "Failed to bootstrap the leaves cache from a peer",
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the caches of the contract from the peer, and checks that its
+/// leaves are the tree of the contract at the block the peer queried them
+/// at, through the RPC quorum of the chain if it has one.
+///
+/// The check reads the state of the contract at that block, so a peer that
+/// is too far ahead or behind (or an RPC without the historical state) fails
+/// it, and the contract is synced from the chain instead.
+async fn fetch_verified_snapshot(
+    ctx: &RelayerContext,
+    client: &reqwest::Client,
+    peer: &url::Url,
+    chain_id: u32,
+    contract_address: Address,
+    empty_leaf: &[u8],
+) -> crate::Result<Snapshot> {
+    let leaves_url = peer.join(&format!(
+        "api/v1/leaves/evm/{chain_id}/{contract_address:?}"
+    ))?;
+    let peer_leaves: PeerLeaves = client
+        .get(leaves_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if peer_leaves.leaves.is_empty() {
+        return Err(crate::Error::Generic("the peer has no leaves"));
+    }
+    let root = compute_merkle_root(&peer_leaves.leaves, empty_leaf.to_vec())?;
+    let provider = ctx.evm_quorum_provider(chain_id).await?;
+    let anchor = VAnchorContract::new(contract_address, provider);
+    let is_known_root = anchor.is_known_root(root).call().await?;
+    if !is_known_root {
+        return Err(crate::Error::Generic(
+            "the root of the peer leaves is not known on chain",
+        ));
+    }
+    // the events watcher resumes from the block the peer claims it queried
+    // the leaves at, it must be the block they are the whole tree at.
+    let block = BlockId::from(peer_leaves.last_queried_block);
+    let next_index = anchor.get_next_index().block(block).call().await?;
+    let last_root = anchor.get_last_root().block(block).call().await?;
+    if next_index as usize != peer_leaves.leaves.len() || last_root != root {
+        return Err(crate::Error::Generic(
+            "the peer leaves are not the tree at their last queried block",
+        ));
+    }
+
+    // only the encrypted outputs of the verified leaves are taken.
+    let leaves_count = peer_leaves.leaves.len();
+    let mut encrypted_outputs_url = peer.join(&format!(
+        "api/v1/encrypted_outputs/evm/{chain_id}/{contract_address:?}"
+    ))?;
+    encrypted_outputs_url
+        .query_pairs_mut()
+        .append_pair("start", "0")
+        .append_pair("end", &leaves_count.to_string());
+    let peer_encrypted_outputs: PeerEncryptedOutputs = client
+        .get(encrypted_outputs_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if peer_encrypted_outputs.encrypted_outputs.len() != leaves_count {
+        return Err(crate::Error::Generic(
+            "the peer encrypted outputs do not match its leaves",
+        ));
+    }
+    let encrypted_outputs = peer_encrypted_outputs
+        .encrypted_outputs
+        .iter()
+        .enumerate()
+        .map(|(i, output)| {
+            let output =
+                hex::decode(output.trim_start_matches("0x")).map_err(|_| {
+                    crate::Error::Generic("invalid peer encrypted output")
+                })?;
+            Ok((i as u32, output))
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    let leaves = peer_leaves
+        .leaves
+        .iter()
+        .enumerate()
+        .map(|(i, leaf)| (i as u32, leaf.as_bytes().to_vec()))
+        .collect();
+    Ok(Snapshot {
+        leaves,
+        encrypted_outputs,
+        last_deposit_block: peer_leaves.last_queried_block,
+    })
+}
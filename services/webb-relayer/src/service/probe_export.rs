@@ -0,0 +1,133 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Probe Events Exporter 📡
+//!
+//! Publishes the probe events of the relayer (the lifecycle of the tx
+//! queues, the signing backend, the leaves store...) to a NATS server, as
+//! JSON on `<subject>.<kind>`, for downstream indexing. It speaks the plain
+//! text protocol of NATS, without TLS.
+
+use std::io;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::error::RecvError;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_utils::probe::ProbeBus;
+
+/// The port of a NATS server, when the URL does not have one.
+const NATS_DEFAULT_PORT: u16 = 4222;
+
+/// Starts the probe events exporter, if a NATS server is configured.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `bus` - The bus the probe events are published on
+pub fn start_probe_exporter(ctx: &RelayerContext, bus: ProbeBus) {
+    let Some(url) = ctx.config.probe_export.nats_url.clone() else {
+        return;
+    };
+    let subject = ctx.config.probe_export.subject.clone();
+    let task =
+        move || run_nats_exporter(url.clone(), subject.clone(), bus.clone());
+    ctx.supervisor().spawn("probe:nats", task);
+}
+
+async fn run_nats_exporter(
+    url: url::Url,
+    subject: String,
+    bus: ProbeBus,
+) -> crate::Result<()> {
+    // the events published from now on are kept while connecting.
+    let mut events = bus.subscribe();
+    let host = url.host_str().unwrap_or("127.0.0.1");
+    let port = url.port().unwrap_or(NATS_DEFAULT_PORT);
+    let (reader, mut writer) =
+        TcpStream::connect((host, port)).await?.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    // the server greets with its INFO before anything else.
+    match lines.next_line().await? {
+        Some(info) if info.starts_with("INFO") => {}
+        _ => {
+            return Err(webb_relayer_utils::Error::Generic(
+                "unexpected greeting from the NATS server",
+            ))
+        }
+    }
+    writer.write_all(&connect_frame(&url)).await?;
+    tracing::info!(%host, port, "Exporting the probe events to NATS");
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line? {
+                // the server pings to check the connection is alive.
+                Some(line) if line.starts_with("PING") => {
+                    writer.write_all(b"PONG\r\n").await?;
+                }
+                Some(line) if line.starts_with("-ERR") => {
+                    tracing::warn!(%line, "NATS server error");
+                }
+                Some(_) => {}
+                None => {
+                    let eof = io::Error::from(io::ErrorKind::UnexpectedEof);
+                    return Err(eof.into());
+                }
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let subject = match &event.kind {
+                        Some(kind) => format!("{subject}.{kind}"),
+                        None => subject.clone(),
+                    };
+                    let payload = serde_json::to_vec(&event)?;
+                    writer.write_all(&pub_frame(&subject, &payload)).await?;
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "Missed some probe events");
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
+/// The `CONNECT` frame, with the credentials of the URL: a user and a
+/// password, or a token alone.
+fn connect_frame(url: &url::Url) -> Vec<u8> {
+    let mut options = serde_json::json!({
+        "verbose": false,
+        "pedantic": false,
+        "name": "webb-relayer",
+        "lang": "rust",
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    match (url.username(), url.password()) {
+        ("", _) => {}
+        (token, None) => options["auth_token"] = token.into(),
+        (user, Some(pass)) => {
+            options["user"] = user.into();
+            options["pass"] = pass.into();
+        }
+    }
+    format!("CONNECT {options}\r\n").into_bytes()
+}
+
+/// The `PUB` frame of a message on the given subject.
+fn pub_frame(subject: &str, payload: &[u8]) -> Vec<u8> {
+    let mut frame = format!("PUB {subject} {}\r\n", payload.len()).into_bytes();
+    frame.extend_from_slice(payload);
+    frame.extend_from_slice(b"\r\n");
+    frame
+}
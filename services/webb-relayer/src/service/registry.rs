@@ -0,0 +1,192 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Relayer Registry Heartbeat 📡
+//!
+//! Periodically publishes the public endpoint of the relayer, the resources
+//! it relays for and their fee parameters to an on-chain registry contract,
+//! so dApps can discover the live relayers without a centralized list.
+//!
+//! The registry contract is expected to expose:
+//!
+//! ```solidity
+//! function heartbeat(
+//!     string endpoint,
+//!     bytes32[] resourceIds,
+//!     uint256[] profitBasisPoints
+//! ) external;
+//! ```
+//!
+//! where the relayer profit of each resource is given in basis points.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethereum_types::{Address, H256, U256};
+use webb::evm::ethers::abi::parse_abi;
+use webb::evm::ethers::contract::Contract;
+use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_config::evm::Contract as ContractConfig;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::queue::{
+    QueueItem, QueueItemPriority, QueueItemState, QueueStore,
+    TransactionQueueItemKey,
+};
+use webb_relayer_store::sled::SledQueueKey;
+
+/// The subset of the registry ABI used for the heartbeats.
+const REGISTRY_ABI: &[&str] = &[
+    "function heartbeat(string endpoint, bytes32[] resourceIds, uint256[] profitBasisPoints) external",
+];
+
+/// Starts the registry heartbeat task, if enabled.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `store` -[Sled](https://sled.rs)-based database store
+pub fn start_registry_heartbeat(
+    ctx: &RelayerContext,
+    store: Arc<super::Store>,
+) {
+    let config = &ctx.config.registry;
+    if !config.enabled {
+        return;
+    }
+    let (Some(address), Some(endpoint)) =
+        (config.address, config.endpoint.clone())
+    else {
+        tracing::warn!(
+            "Registry heartbeat is disabled, no registry address or endpoint \
+             is configured",
+        );
+        return;
+    };
+    let my_ctx = ctx.clone();
+    let task = move || {
+        run_registry_heartbeat(
+            my_ctx.clone(),
+            address,
+            endpoint.clone(),
+            store.clone(),
+        )
+    };
+    ctx.supervisor().spawn("registry:heartbeat", task);
+}
+
+/// Periodically enqueues a heartbeat to the registry contract.
+async fn run_registry_heartbeat(
+    ctx: RelayerContext,
+    address: Address,
+    endpoint: url::Url,
+    store: Arc<super::Store>,
+) -> crate::Result<()> {
+    let chain_id = ctx.config.registry.chain_id;
+    let interval = Duration::from_millis(ctx.config.registry.interval);
+    let client = ctx.evm_provider(chain_id).await?;
+    let registry = Contract::new(
+        address,
+        parse_abi(REGISTRY_ABI).expect("registry abi is valid"),
+        client,
+    );
+    // the last heartbeat enqueued, so a stuck one is not enqueued again.
+    let mut last_heartbeat: Option<SledQueueKey> = None;
+    tracing::debug!(%chain_id, %address, "Registry heartbeat Started.");
+    loop {
+        webb_relayer_utils::heartbeat::beat();
+        // only the leader publishes, so replicas do not register twice.
+        if ctx.leadership().is_leader() {
+            let (resource_ids, profits) = advertised_resources(&ctx);
+            let typed_tx: TypedTransaction = registry
+                .method::<_, ()>(
+                    "heartbeat",
+                    (endpoint.to_string(), resource_ids, profits),
+                )?
+                .tx;
+            let result = enqueue_heartbeat(
+                chain_id,
+                typed_tx,
+                store.as_ref(),
+                &mut last_heartbeat,
+            );
+            if let Err(e) = result {
+                tracing::warn!(
+                    %chain_id,
+                    error = %e,
+                    "Registry heartbeat failed, will retry later",
+                );
+            }
+        }
+        webb_relayer_utils::heartbeat::sleep(interval).await;
+    }
+}
+
+/// Returns the resources of the VAnchor contracts the relayer relays for,
+/// with the relayer profit of their chain in basis points.
+fn advertised_resources(ctx: &RelayerContext) -> (Vec<H256>, Vec<U256>) {
+    let mut resource_ids = Vec::new();
+    let mut profits = Vec::new();
+    for chain_config in ctx.config.evm.values() {
+        if !chain_config.enabled {
+            continue;
+        }
+        let profit_percent =
+            chain_config.relayer_fee_config.relayer_profit_percent;
+        let profit_basis_points = (profit_percent * 100.0).round() as u64;
+        for contract in &chain_config.contracts {
+            let ContractConfig::VAnchor(config) = contract else {
+                continue;
+            };
+            let resource_id = ResourceId::new(
+                TargetSystem::new_contract_address(
+                    config.common.address.to_fixed_bytes(),
+                ),
+                TypedChainId::Evm(chain_config.chain_id),
+            );
+            resource_ids.push(H256::from(resource_id.to_bytes()));
+            profits.push(U256::from(profit_basis_points));
+        }
+    }
+    (resource_ids, profits)
+}
+
+/// Enqueues the heartbeat as a low priority item, unless the previous one
+/// is still in flight.
+fn enqueue_heartbeat(
+    chain_id: u32,
+    typed_tx: TypedTransaction,
+    store: &super::Store,
+    last_heartbeat: &mut Option<SledQueueKey>,
+) -> crate::Result<()> {
+    if let Some(last_key) = last_heartbeat {
+        let last_item =
+            QueueStore::<TypedTransaction>::get_item(store, *last_key)?;
+        let in_flight = matches!(
+            last_item.map(|item| item.state()),
+            Some(state) if !matches!(state, QueueItemState::Processed { .. })
+        );
+        if in_flight {
+            return Ok(());
+        }
+    }
+    let tx_key =
+        SledQueueKey::from_evm_with_custom_key(chain_id, typed_tx.item_key());
+    let mut item = QueueItem::new(typed_tx);
+    item.set_priority(QueueItemPriority::Low);
+    QueueStore::<TypedTransaction>::enqueue_item(store, tx_key, item)?;
+    *last_heartbeat = Some(tx_key);
+    tracing::info!(%chain_id, "Enqueued registry heartbeat transaction");
+    Ok(())
+}
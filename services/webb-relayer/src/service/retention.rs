@@ -0,0 +1,144 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Data Retention Purger 🧹
+//!
+//! Periodically purges the history that fell out of its retention window:
+//! the relayed transactions, the relay jobs and the finished items of the
+//! transaction queues (with their tx hashes and batches), the hashes of the
+//! processed events, and the finished items of the proposal queues and the
+//! Tangle jobs. The expired nonces of the signed relay commands are purged
+//! on every run.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use webb_relayer_config::retention::RetentionConfig;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::{
+    EventHashStore, RelayJobStore, RelayNonceStore, RelayedTxStore,
+    TangleJobStore,
+};
+
+/// Starts the data retention purger.
+///
+/// It always runs, since the expired nonces of the signed relay commands
+/// are purged even when no retention window is set.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `store` -[Sled](https://sled.rs)-based database store
+pub fn start_retention_purger(ctx: &RelayerContext, store: Arc<super::Store>) {
+    let my_ctx = ctx.clone();
+    let task = move || run_retention_purger(my_ctx.clone(), store.clone());
+    ctx.supervisor().spawn("store:retention", task);
+}
+
+/// The number of entries purged by a single run, per kind of history.
+#[derive(Debug, Default)]
+struct PurgeReport {
+    tx_history: usize,
+    event_hashes: usize,
+    proposals: usize,
+    relay_nonces: usize,
+}
+
+async fn run_retention_purger(
+    ctx: RelayerContext,
+    store: Arc<super::Store>,
+) -> crate::Result<()> {
+    let config = ctx.config.retention.clone();
+    let mut interval =
+        tokio::time::interval(Duration::from_millis(config.interval));
+    loop {
+        interval.tick().await;
+        // walking the trees is blocking, keep it off the async runtime.
+        let my_store = store.clone();
+        let my_config = config.clone();
+        let report = tokio::task::spawn_blocking(move || {
+            purge_expired(&my_store, &my_config)
+        })
+        .await;
+        let report = match report {
+            Ok(Ok(report)) => report,
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "Failed to purge the store");
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Store purging task failed");
+                continue;
+            }
+        };
+        tracing::debug!(?report, "Purged the expired history");
+        let metrics = ctx.metrics.lock().await;
+        metrics.record_purged_entries("tx_history", report.tx_history);
+        metrics.record_purged_entries("event_hashes", report.event_hashes);
+        metrics.record_purged_entries("proposals", report.proposals);
+        metrics.record_purged_entries("relay_nonces", report.relay_nonces);
+    }
+}
+
+/// Purges the history older than its retention window.
+fn purge_expired(
+    store: &super::Store,
+    config: &RetentionConfig,
+) -> webb_relayer_utils::Result<PurgeReport> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    // the cutoff (unix timestamp in milliseconds) of a retention window.
+    let cutoff = |window: u64| now.saturating_sub(u128::from(window));
+    let mut report = PurgeReport::default();
+    for queue_name in store.queue_names() {
+        let window = if queue_name.starts_with("evm_tx_")
+            || queue_name.starts_with("substrate_tx_")
+        {
+            config.tx_history
+        } else if queue_name.starts_with("bridge_cmd_") {
+            config.proposals
+        } else {
+            None
+        };
+        let Some(window) = window else {
+            continue;
+        };
+        let purged =
+            store.purge_finished_queue_items(&queue_name, cutoff(window))?;
+        if queue_name.starts_with("bridge_cmd_") {
+            report.proposals += purged;
+        } else {
+            report.tx_history += purged;
+        }
+    }
+    if let Some(window) = config.tx_history {
+        let before = (cutoff(window) / 1000) as u64;
+        report.tx_history += store.purge_relayed_txs_before(before)?;
+        report.tx_history += store.purge_relay_jobs_before(before)?;
+    }
+    if let Some(window) = config.proposals {
+        let before = (cutoff(window) / 1000) as u64;
+        report.proposals += store.purge_tangle_jobs_before(before)?;
+    }
+    if let Some(window) = config.event_hashes {
+        let before = (cutoff(window) / 1000) as u64;
+        report.event_hashes += store.purge_events_before(before)?;
+    }
+    // a nonce is only used until its envelope expires.
+    let before = (now / 1000) as u64;
+    report.relay_nonces += store.purge_relay_nonces_before(before)?;
+    Ok(report)
+}
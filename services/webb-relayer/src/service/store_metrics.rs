@@ -0,0 +1,102 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Store Metrics Sampler 📏
+//!
+//! Periodically samples the size of the store on disk, the number of keys in
+//! its trees (leaves, encrypted outputs, queues and event hashes) and how
+//! long it takes to flush it to disk, so capacity issues are visible before
+//! the disk fills up.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::sled::TreeStatistics;
+
+/// How often the store is sampled.
+const SAMPLING_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Starts the store metrics sampler.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the metrics
+/// * `store` -[Sled](https://sled.rs)-based database store
+pub fn start_store_metrics_sampler(
+    ctx: &RelayerContext,
+    store: Arc<super::Store>,
+) {
+    let my_ctx = ctx.clone();
+    let task = move || run_store_metrics_sampler(my_ctx.clone(), store.clone());
+    ctx.supervisor().spawn("store:metrics", task);
+}
+
+/// The store metrics taken by a single sample.
+struct StoreSample {
+    size_on_disk: u64,
+    tree_statistics: TreeStatistics,
+    flush_latency: Duration,
+}
+
+async fn run_store_metrics_sampler(
+    ctx: RelayerContext,
+    store: Arc<super::Store>,
+) -> crate::Result<()> {
+    let mut interval = tokio::time::interval(SAMPLING_INTERVAL);
+    loop {
+        interval.tick().await;
+        // walking the trees and flushing are blocking, keep them off the
+        // async runtime.
+        let my_store = store.clone();
+        let sample = tokio::task::spawn_blocking(move || {
+            Ok::<_, webb_relayer_utils::Error>(StoreSample {
+                size_on_disk: my_store.get_data_stored_size(),
+                tree_statistics: my_store.tree_statistics()?,
+                flush_latency: my_store.flush()?,
+            })
+        })
+        .await;
+        let sample = match sample {
+            Ok(Ok(sample)) => sample,
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "Failed to sample the store");
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Store sampling task failed");
+                continue;
+            }
+        };
+        let metrics = ctx.metrics.lock().await;
+        metrics
+            .total_amount_of_data_stored
+            .set(sample.size_on_disk as f64);
+        let stats = sample.tree_statistics;
+        for (tree, keys) in [
+            ("leaves", stats.leaves),
+            ("encrypted_outputs", stats.encrypted_outputs),
+            ("queues", stats.queues),
+            ("event_hashes", stats.event_hashes),
+        ] {
+            metrics
+                .store_tree_keys
+                .with_label_values(&[tree])
+                .set(keys as f64);
+        }
+        metrics
+            .store_flush_latency
+            .observe(sample.flush_latency.as_secs_f64());
+    }
+}
@@ -82,38 +82,43 @@ pub fn start_job_result_watcher(
         return Ok(());
     }
     tracing::debug!("Job Result events watcher for ({}) Started.", chain_id,);
-    let mut shutdown_signal = ctx.shutdown_signal();
     let metrics = ctx.metrics.clone();
     let webb_config = ctx.config.clone();
     let my_config = config.clone();
-    let task = async move {
-        let job_result_watcher = JobResultWatcher::default();
-        let job_result_event_handler = JobResultHandler::new(webb_config);
-        let job_result_watcher_task = job_result_watcher.run(
-            chain_id,
-            ctx.clone(),
-            store,
-            my_config.events_watcher,
-            vec![Box::new(job_result_event_handler)],
-            metrics,
-        );
-        tokio::select! {
-            _ = job_result_watcher_task => {
-                tracing::warn!(
-                    "Job Result events watcher stopped for ({})",
-                    chain_id,
-                );
-            },
-            _ = shutdown_signal.recv() => {
-                tracing::trace!(
-                    "Stopping Job Result events watcher for ({})",
+    let my_ctx = ctx.clone();
+    let task = move || {
+        let ctx = my_ctx.clone();
+        let store = store.clone();
+        let metrics = metrics.clone();
+        let webb_config = webb_config.clone();
+        let my_config = my_config.clone();
+        async move {
+            let job_result_watcher = JobResultWatcher::default();
+            let job_result_event_handler = JobResultHandler::new(webb_config);
+            let job_status_event_handler = JobStatusHandler::new(chain_id);
+            let result = job_result_watcher
+                .run(
                     chain_id,
-                );
-            },
+                    ctx,
+                    store,
+                    my_config.events_watcher,
+                    vec![
+                        Box::new(job_result_event_handler),
+                        Box::new(job_status_event_handler),
+                    ],
+                    metrics,
+                )
+                .await;
+            tracing::warn!(
+                "Job Result events watcher stopped for ({})",
+                chain_id,
+            );
+            result
         }
     };
     // kick off the watcher.
-    tokio::task::spawn(task);
+    ctx.supervisor()
+        .spawn(format!("substrate:{chain_id}:jobs-watcher"), task);
     Ok(())
 }
 
@@ -134,28 +139,23 @@ pub fn start_tx_queue<X>(
 where
     X: subxt::Config + Send + Sync,
 {
-    let mut shutdown_signal = ctx.shutdown_signal();
+    let supervisor = ctx.supervisor().clone();
 
     let tx_queue = SubstrateTxQueue::new(ctx, chain_id, store);
 
     tracing::debug!("Transaction Queue for node({}) Started.", chain_id);
-    let task = async move {
-        tokio::select! {
-            _ = tx_queue.run::<X>() => {
-                tracing::warn!(
-                    "Transaction Queue task stopped for node({})",
-                    chain_id
-                );
-            },
-            _ = shutdown_signal.recv() => {
-                tracing::trace!(
-                    "Stopping Transaction Queue for node({})",
-                    chain_id
-                );
-            },
+    let task = move || {
+        let tx_queue = tx_queue.clone();
+        async move {
+            let result = tx_queue.run::<X>().await;
+            tracing::warn!(
+                "Transaction Queue task stopped for node({})",
+                chain_id
+            );
+            result
         }
     };
     // kick off the substrate tx_queue.
-    tokio::task::spawn(task);
+    supervisor.spawn(format!("substrate:{chain_id}:tx-queue"), task);
     Ok(())
 }
@@ -0,0 +1,241 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Treasury Sweep Service 🧹
+//!
+//! Periodically transfers the native and ERC-20 balances of the hot relayer
+//! wallet above their high-water marks to a cold address, reducing the funds
+//! exposed on the hot wallet. The transfers go through the EVM tx queue as
+//! low priority items, so they never hold back relayed or governance
+//! transactions.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethereum_types::{Address, U256};
+use webb::evm::ethers::abi::parse_abi;
+use webb::evm::ethers::contract::Contract;
+use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
+use webb::evm::ethers::providers::Middleware;
+use webb::evm::ethers::signers::Signer;
+use webb::evm::ethers::types::TransactionRequest;
+use webb::evm::ethers::utils::parse_units;
+use webb_relayer_config::evm::EvmChainConfig;
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::queue::{
+    QueueItem, QueueItemPriority, QueueStore, TransactionQueueItemKey,
+};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_types::EthersClient;
+
+/// The subset of the ERC-20 ABI used for sweeping.
+const ERC20_ABI: &[&str] = &[
+    "function balanceOf(address account) external view returns (uint256)",
+    "function decimals() external view returns (uint8)",
+    "function transfer(address to, uint256 amount) external returns (bool)",
+];
+
+/// Starts the treasury sweep task for the given chain.
+///
+/// Returns Ok(()) if successful, or an error if not.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `chain_config` - The configuration of the chain
+/// * `store` -[Sled](https://sled.rs)-based database store
+pub async fn start_treasury_sweep(
+    ctx: &RelayerContext,
+    chain_config: &EvmChainConfig,
+    store: Arc<super::Store>,
+) -> crate::Result<()> {
+    if !chain_config.treasury_sweep.enabled {
+        return Ok(());
+    }
+    let chain_id = chain_config.chain_id;
+    let Some(cold_address) = chain_config.treasury_sweep.cold_address else {
+        tracing::warn!(
+            %chain_id,
+            "Treasury sweep is disabled, no cold address is configured",
+        );
+        return Ok(());
+    };
+    let hot_wallet = ctx.evm_wallet(chain_id).await?.address();
+    let client = ctx.evm_provider(chain_id).await?;
+    let my_ctx = ctx.clone();
+    let my_config = chain_config.clone();
+    // the last sweep of each asset, shared between restarts.
+    let last_sweeps = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+    let task = move || {
+        run_treasury_sweep(
+            my_ctx.clone(),
+            my_config.clone(),
+            hot_wallet,
+            cold_address,
+            client.clone(),
+            store.clone(),
+            last_sweeps.clone(),
+        )
+    };
+    // kick off the sweeper.
+    ctx.supervisor()
+        .spawn(format!("evm:{chain_id}:treasury-sweep"), task);
+    Ok(())
+}
+
+/// The last sweep enqueued for each asset (the zero address for the native
+/// token).
+type LastSweeps = Arc<tokio::sync::Mutex<HashMap<Address, SledQueueKey>>>;
+
+/// Periodically sweeps the balances of the hot wallet.
+async fn run_treasury_sweep(
+    ctx: RelayerContext,
+    chain_config: EvmChainConfig,
+    hot_wallet: Address,
+    cold_address: Address,
+    client: Arc<EthersClient>,
+    store: Arc<super::Store>,
+    last_sweeps: LastSweeps,
+) -> crate::Result<()> {
+    let chain_id = chain_config.chain_id;
+    let interval = Duration::from_millis(chain_config.treasury_sweep.interval);
+    tracing::debug!(%chain_id, "Treasury sweep Started.");
+    loop {
+        webb_relayer_utils::heartbeat::beat();
+        // only the leader sweeps, so replicas do not enqueue it twice.
+        if ctx.leadership().is_leader() {
+            let mut last_sweeps = last_sweeps.lock().await;
+            let result = sweep_balances(
+                &chain_config,
+                hot_wallet,
+                cold_address,
+                client.clone(),
+                store.as_ref(),
+                &mut last_sweeps,
+            )
+            .await;
+            if let Err(e) = result {
+                tracing::warn!(
+                    %chain_id,
+                    error = %e,
+                    "Treasury sweep failed, will retry later",
+                );
+            }
+        }
+        webb_relayer_utils::heartbeat::sleep(interval).await;
+    }
+}
+
+/// Enqueues a transfer to the cold address of the balance above the
+/// high-water mark of every configured asset.
+async fn sweep_balances(
+    chain_config: &EvmChainConfig,
+    hot_wallet: Address,
+    cold_address: Address,
+    client: Arc<EthersClient>,
+    store: &super::Store,
+    last_sweeps: &mut HashMap<Address, SledQueueKey>,
+) -> crate::Result<()> {
+    let chain_id = chain_config.chain_id;
+    let config = &chain_config.treasury_sweep;
+    if let Some(high_water_mark) = config.native_high_water_mark {
+        let balance = client.get_balance(hot_wallet, None).await?;
+        let decimals =
+            webb_chains_info::chain_info_by_chain_id(u64::from(chain_id))
+                .map_or(18, |info| info.native_currency.decimals);
+        let high_water_mark: U256 =
+            parse_units(high_water_mark, u32::from(decimals))?.into();
+        if balance > high_water_mark {
+            let amount = balance - high_water_mark;
+            let typed_tx: TypedTransaction =
+                TransactionRequest::pay(cold_address, amount).into();
+            enqueue_sweep(
+                chain_id,
+                Address::zero(),
+                amount,
+                typed_tx,
+                store,
+                last_sweeps,
+            )?;
+        }
+    }
+
+    for token_config in &config.tokens {
+        let token = Contract::new(
+            token_config.address,
+            parse_abi(ERC20_ABI).expect("erc20 abi is valid"),
+            client.clone(),
+        );
+        let balance = token
+            .method::<_, U256>("balanceOf", hot_wallet)?
+            .call()
+            .await?;
+        let decimals = token.method::<_, u8>("decimals", ())?.call().await?;
+        let high_water_mark: U256 =
+            parse_units(token_config.high_water_mark, u32::from(decimals))?
+                .into();
+        if balance <= high_water_mark {
+            continue;
+        }
+        let amount = balance - high_water_mark;
+        let typed_tx: TypedTransaction = token
+            .method::<_, bool>("transfer", (cold_address, amount))?
+            .tx;
+        enqueue_sweep(
+            chain_id,
+            token_config.address,
+            amount,
+            typed_tx,
+            store,
+            last_sweeps,
+        )?;
+    }
+    Ok(())
+}
+
+/// Enqueues the sweep of an asset as a low priority item, unless its
+/// previous sweep is still in flight.
+fn enqueue_sweep(
+    chain_id: u32,
+    asset: Address,
+    amount: U256,
+    typed_tx: TypedTransaction,
+    store: &super::Store,
+    last_sweeps: &mut HashMap<Address, SledQueueKey>,
+) -> crate::Result<()> {
+    if let Some(last_key) = last_sweeps.get(&asset) {
+        let last_sweep =
+            QueueStore::<TypedTransaction>::get_item(store, *last_key)?;
+        // a failed or expired sweep is not in flight, it is swept again.
+        let in_flight =
+            last_sweep.map_or(false, |item| item.state().is_pending());
+        if in_flight {
+            return Ok(());
+        }
+    }
+    let tx_key =
+        SledQueueKey::from_evm_with_custom_key(chain_id, typed_tx.item_key());
+    let mut item = QueueItem::new(typed_tx);
+    item.set_priority(QueueItemPriority::Low);
+    QueueStore::<TypedTransaction>::enqueue_item(store, tx_key, item)?;
+    last_sweeps.insert(asset, tx_key);
+    tracing::info!(
+        %chain_id,
+        %asset,
+        %amount,
+        "Enqueued treasury sweep transaction",
+    );
+    Ok(())
+}
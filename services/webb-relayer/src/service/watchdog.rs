@@ -0,0 +1,79 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # systemd Watchdog 🐕
+//!
+//! Notifies systemd once the relayer is started, and keeps pinging the
+//! systemd watchdog (`WatchdogSec=`) for as long as the relayer is making
+//! progress: the pings stop if the runtime itself stalls, or if any of the
+//! supervised tasks that report heartbeats (the transaction queues and the
+//! event watchers) stops beating, so systemd restarts the relayer.
+//!
+//! Outside of systemd (no `NOTIFY_SOCKET`), all of this does nothing.
+
+use std::time::Duration;
+
+use sd_notify::NotifyState;
+use webb_relayer_context::RelayerContext;
+
+/// A task that did not beat for this long is considered stuck.
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Notifies systemd that the relayer is ready, and starts pinging the
+/// watchdog if it is enabled for the service.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the task supervisor
+pub fn start(ctx: &RelayerContext) {
+    notify(NotifyState::Ready);
+    let mut usec = 0;
+    if !sd_notify::watchdog_enabled(false, &mut usec) {
+        return;
+    }
+    // systemd recommends pinging at half of the watchdog timeout.
+    let interval = Duration::from_micros(usec) / 2;
+    let supervisor = ctx.supervisor().clone();
+    let mut shutdown = ctx.shutdown_signal();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {},
+                _ = shutdown.recv() => break,
+            }
+            let stalled = supervisor.stalled_tasks(HEARTBEAT_TIMEOUT);
+            if stalled.is_empty() {
+                notify(NotifyState::Watchdog);
+            } else {
+                tracing::error!(
+                    ?stalled,
+                    "Tasks stopped heartbeating, skipping the watchdog ping",
+                );
+            }
+        }
+    });
+    tracing::debug!("systemd watchdog enabled, pinging every {interval:?}");
+}
+
+/// Notifies systemd that the relayer is shutting down.
+pub fn stopping() {
+    notify(NotifyState::Stopping);
+}
+
+fn notify(state: NotifyState) {
+    if let Err(e) = sd_notify::notify(false, &[state]) {
+        tracing::warn!("Failed to notify systemd: {e}");
+    }
+}